@@ -0,0 +1,329 @@
+//! Async counterparts of the fetch, build, and watch entry points, for
+//! programs already running on tokio (`async` feature).
+//!
+//! Fetching uses reqwest's async client with the same retries, headers, and
+//! errors as the blocking one. Parsing, `--reduce`, profiles, and outlines
+//! are shared with the blocking path unchanged.
+//!
+//! ```no_run
+//! use openapi_snapshot::asynchronous;
+//! use openapi_snapshot::{Config, Snapshot};
+//!
+//! # async fn example() -> Result<(), openapi_snapshot::AppError> {
+//! let snapshot = Snapshot::builder()
+//!     .url("http://localhost:8080/openapi.json")
+//!     .build()?;
+//! let outputs = asynchronous::build_outputs(snapshot.config()).await?;
+//! println!("{}", outputs.primary);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+use reqwest::header;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{Config, WatchOptions};
+use crate::errors::AppError;
+use crate::fetch::{
+    BASE_BACKOFF_MS, FetchResponse, MAX_RETRIES, body_snippet, build_headers, is_retryable_error,
+    log_headers, next_backoff, parse_max_age, read_openapi_file, should_retry_status,
+};
+use crate::output::{OutputPayloads, check_empty_paths, log_version_notes, transform_outputs};
+use crate::watch::{ChangeTracker, PollOutcome};
+
+/// Async [`crate::fetch::fetch_openapi`]: the raw body of `config.url`.
+pub async fn fetch_openapi(config: &Config) -> Result<Vec<u8>, AppError> {
+    Ok(fetch_openapi_response(config).await?.body)
+}
+
+/// Async [`crate::fetch::fetch_openapi_response`], retrying connection
+/// failures, 429, and 5xx the same way.
+pub async fn fetch_openapi_response(config: &Config) -> Result<FetchResponse, AppError> {
+    let headers = build_headers(&config.headers)?;
+    let verbosity = config.verbosity;
+    log_headers(verbosity, ">", &headers);
+    let client = Client::builder()
+        .timeout(Duration::from_millis(config.timeout_ms))
+        .default_headers(headers)
+        .build()
+        .map_err(|err| AppError::network(format!("client error: {err}")).with_source(err))?;
+
+    let mut backoff = BASE_BACKOFF_MS;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        verbosity.verbose(format!("GET {} (attempt {attempt})", config.url));
+        let started = Instant::now();
+        let retry = match client.get(&config.url).send().await {
+            Ok(response) => {
+                let status = response.status();
+                verbosity.verbose(format!(
+                    "HTTP {status} in {} ms",
+                    started.elapsed().as_millis()
+                ));
+                log_headers(verbosity, "<", response.headers());
+                if !status.is_success() {
+                    let snippet = body_snippet(response.text().await.unwrap_or_default());
+                    if should_retry_status(status) && attempt < MAX_RETRIES {
+                        verbosity.verbose(format!("retrying in {backoff} ms"));
+                        true
+                    } else {
+                        return Err(AppError::http(
+                            status.as_u16(),
+                            config.url.clone(),
+                            format!("HTTP {status}: {snippet}"),
+                        ));
+                    }
+                } else {
+                    let max_age = response
+                        .headers()
+                        .get(header::CACHE_CONTROL)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(parse_max_age);
+                    match response.bytes().await {
+                        Ok(bytes) => {
+                            verbosity.verbose(format!("received {} bytes", bytes.len()));
+                            return Ok(FetchResponse {
+                                status: status.as_u16(),
+                                body: Vec::from(bytes),
+                                max_age,
+                            });
+                        }
+                        Err(err) if is_retryable_error(&err) && attempt < MAX_RETRIES => true,
+                        Err(err) => {
+                            return Err(AppError::network(format!(
+                                "failed to read response: {err}"
+                            ))
+                            .with_source(err));
+                        }
+                    }
+                }
+            }
+            Err(err) if is_retryable_error(&err) && attempt < MAX_RETRIES => true,
+            Err(err) => {
+                return Err(AppError::from(err));
+            }
+        };
+        if retry {
+            tokio::time::sleep(Duration::from_millis(backoff)).await;
+            backoff = next_backoff(backoff);
+        }
+    }
+}
+
+/// Async [`crate::build_outputs`]. A local `--input` file (or stdin) is read
+/// on the blocking pool.
+pub async fn build_outputs(config: &Config) -> Result<OutputPayloads, AppError> {
+    let outputs = collect_outputs(config).await?;
+    log_version_notes(config, &outputs.version_notes);
+    Ok(outputs)
+}
+
+async fn collect_outputs(config: &Config) -> Result<OutputPayloads, AppError> {
+    match &config.input {
+        Some(path) => {
+            let path = path.clone();
+            let body = tokio::task::spawn_blocking(move || read_openapi_file(&path))
+                .await
+                .map_err(|err| {
+                    AppError::io(format!("failed to read input: {err}")).with_source(err)
+                })??;
+            transform_outputs(config, None, body, None)
+        }
+        None => {
+            let response = fetch_openapi_response(config).await?;
+            transform_outputs(
+                config,
+                Some(response.status),
+                response.body,
+                response.max_age,
+            )
+        }
+    }
+}
+
+/// Polls `config` every `options.interval_ms` until `cancel` fires or
+/// `options.max_iterations` polls have run, writing the snapshot when it
+/// changes. Failed polls are logged and retried; `options.max_failures`
+/// consecutive failures, or a failed last poll with `max_iterations`, end
+/// the loop with that error. The blocking loop's extras (backoff, events,
+/// metrics, hooks, signals) are left to the embedding program.
+pub async fn run_watch(
+    config: &Config,
+    options: &WatchOptions,
+    cancel: CancellationToken,
+) -> Result<(), AppError> {
+    let interval = Duration::from_millis(options.interval_ms.max(1));
+    let mut tracker = ChangeTracker::from_existing(config).with_stable_polls(options.stable_polls);
+    let mut polls: u64 = 0;
+    let mut failures: u64 = 0;
+    let mut last_notes = Vec::new();
+    loop {
+        let result = match collect_outputs(config)
+            .await
+            .map(|outputs| outputs.with_comparison(config.strict_bytes))
+        {
+            Ok(outputs) => check_empty_paths(config, &outputs).and_then(|warning| {
+                if outputs.version_notes != last_notes {
+                    log_version_notes(config, &outputs.version_notes);
+                    last_notes.clone_from(&outputs.version_notes);
+                }
+                if let Some(warning) = warning {
+                    config.verbosity.info(warning);
+                }
+                tracker.apply(config, &outputs)
+            }),
+            Err(err) => Err(err),
+        };
+        polls += 1;
+        let last_error = match result {
+            Ok(PollOutcome::Changed(summary)) => {
+                config.verbosity.info(summary.log_line());
+                None
+            }
+            Ok(PollOutcome::Held { seen, required }) => {
+                config.verbosity.info(format!(
+                    "change detected, holding for confirmation ({seen}/{required} polls)"
+                ));
+                None
+            }
+            Ok(PollOutcome::Unchanged) => None,
+            Err(err) => {
+                config.verbosity.info(err.to_string());
+                Some(err)
+            }
+        };
+        failures = if last_error.is_some() {
+            failures + 1
+        } else {
+            0
+        };
+        if let Some(err) = last_error
+            && ((options.max_failures > 0 && failures >= options.max_failures)
+                || options.max_iterations == Some(polls))
+        {
+            return Err(err);
+        }
+        if options.max_iterations == Some(polls) {
+            return Ok(());
+        }
+        tokio::select! {
+            () = cancel.cancelled() => return Ok(()),
+            () = tokio::time::sleep(interval) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ReduceKey;
+    use crate::snapshot::Snapshot;
+    use httpmock::prelude::*;
+
+    const SPEC: &str = r#"{"openapi":"3.0.3","paths":{"/users":{}},"components":{}}"#;
+
+    fn watch_options(max_iterations: Option<u64>) -> WatchOptions {
+        let cli = <crate::cli::Cli as clap::Parser>::try_parse_from([
+            "openapi-snapshot",
+            "watch",
+            "--interval-ms",
+            "10",
+        ])
+        .unwrap();
+        let (_, mode) = Config::from_cli(cli).unwrap();
+        let crate::config::Mode::Watch(options) = mode else {
+            panic!("expected watch mode");
+        };
+        WatchOptions {
+            max_iterations,
+            ..*options
+        }
+    }
+
+    #[tokio::test]
+    async fn builds_outputs_with_the_async_client() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/openapi.json")
+                    .header("x-team", "api");
+                then.status(200)
+                    .header("cache-control", "max-age=30")
+                    .body(SPEC);
+            })
+            .await;
+        let snapshot = Snapshot::builder()
+            .url(server.url("/openapi.json"))
+            .header("X-Team", "api")
+            .reduce([ReduceKey::Paths])
+            .build()
+            .unwrap();
+        let outputs = build_outputs(snapshot.config()).await.unwrap();
+        mock.assert_async().await;
+        assert_eq!(outputs.status, Some(200));
+        assert_eq!(outputs.max_age, Some(Duration::from_secs(30)));
+        assert_eq!(
+            outputs.primary,
+            "{\n  \"paths\": {\n    \"/users\": {}\n  }\n}"
+        );
+    }
+
+    #[tokio::test]
+    async fn http_errors_match_the_blocking_client() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/openapi.json");
+                then.status(404).body("missing");
+            })
+            .await;
+        let snapshot = Snapshot::builder()
+            .url(server.url("/openapi.json"))
+            .build()
+            .unwrap();
+        let err = fetch_openapi(snapshot.config()).await.unwrap_err();
+        assert_eq!(err.exit_code(), 9);
+        assert!(err.to_string().contains("HTTP 404"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn watch_writes_once_and_stops_when_cancelled() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/openapi.json");
+                then.status(200).body(SPEC);
+            })
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("openapi.json");
+        let config = Config {
+            out: Some(out.clone()),
+            stdout: false,
+            ..Snapshot::builder()
+                .url(server.url("/openapi.json"))
+                .build()
+                .unwrap()
+                .config()
+                .clone()
+        };
+
+        run_watch(&config, &watch_options(Some(2)), CancellationToken::new())
+            .await
+            .unwrap();
+        mock.assert_hits_async(2).await;
+        assert!(std::fs::read_to_string(&out).unwrap().contains("/users"));
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        run_watch(&config, &watch_options(None), cancel)
+            .await
+            .unwrap();
+        mock.assert_hits_async(3).await;
+    }
+}
@@ -0,0 +1,140 @@
+//! A fixed reference document that `watch --baseline` compares every poll
+//! against, so the log answers "how far has the API drifted from the
+//! released spec" rather than "what changed since the last poll".
+
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::diff::{ChangeKind, SpecChange, Subject, diff_documents, summarize_changes};
+use crate::errors::AppError;
+use crate::fetch::load_document;
+use crate::normalize::for_comparison;
+
+/// The baseline document, loaded and canonicalized once.
+#[derive(Debug, Clone)]
+pub struct Baseline {
+    pub source: String,
+    doc: Value,
+}
+
+impl Baseline {
+    /// Loads `source`, a file path or an http(s) URL fetched with `config`'s
+    /// headers and timeout. A failure keeps its exit code and names the
+    /// baseline.
+    pub fn load(config: &Config, source: &str) -> Result<Self, AppError> {
+        let doc = load_document(config, source).map_err(|err| {
+            err.with_message(format!("failed to load --baseline {source}: {err}"))
+        })?;
+        Ok(Self {
+            source: source.to_string(),
+            doc: for_comparison(doc, config.strict_bytes),
+        })
+    }
+
+    /// The changes from the baseline to `doc`, as `diff` reports them.
+    pub fn drift(&self, doc: Value, strict_bytes: bool) -> Drift {
+        self.drift_compared(&for_comparison(doc, strict_bytes))
+    }
+
+    /// [`Baseline::drift`] for a document already in comparison form.
+    pub(crate) fn drift_compared(&self, doc: &Value) -> Drift {
+        Drift {
+            changes: diff_documents(&self.doc, doc),
+        }
+    }
+}
+
+/// What differs between the baseline and a polled document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drift {
+    pub changes: Vec<SpecChange>,
+}
+
+impl Drift {
+    /// One line, e.g. "vs baseline: 2 paths added, 1 removed, 3 operations
+    /// changed".
+    pub fn summary(&self) -> String {
+        let paths = |kind| {
+            self.changes
+                .iter()
+                .filter(|change| change.kind == kind && matches!(change.subject, Subject::Path(_)))
+                .count()
+        };
+        let operations = self
+            .changes
+            .iter()
+            .filter(|change| matches!(change.subject, Subject::Operation { .. }))
+            .count();
+        format!(
+            "vs baseline: {} added, {} removed, {} changed",
+            counted(paths(ChangeKind::Added), "path"),
+            paths(ChangeKind::Removed),
+            counted(operations, "operation")
+        )
+    }
+
+    /// Every change, one per line as `diff` prints them, then the counts.
+    pub fn report(&self, baseline: &str) -> String {
+        let mut out = format!("changes from baseline {baseline}\n");
+        for change in &self.changes {
+            out.push_str(&format!("{change}\n"));
+        }
+        out.push_str(&summarize_changes(&self.changes));
+        out.push('\n');
+        out
+    }
+}
+
+fn counted(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {noun}"),
+        _ => format!("{count} {noun}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn baseline(doc: Value) -> Baseline {
+        Baseline {
+            source: "released.json".to_string(),
+            doc,
+        }
+    }
+
+    #[test]
+    fn drift_counts_paths_and_operations_against_the_baseline() {
+        let released = json!({"paths": {
+            "/users": {"get": {"responses": {"200": {"description": "ok"}}}},
+            "/teams": {"get": {"responses": {}}}
+        }});
+        let polled = json!({"paths": {
+            "/users": {
+                "get": {"responses": {"200": {"description": "ok"}, "404": {"description": "no"}}},
+                "post": {"responses": {}}
+            },
+            "/orgs": {"get": {"responses": {}}}
+        }});
+        let drift = baseline(released).drift(polled, false);
+        assert_eq!(
+            drift.summary(),
+            "vs baseline: 1 path added, 1 removed, 4 operations changed"
+        );
+        let report = drift.report("released.json");
+        assert!(report.starts_with("changes from baseline released.json\n"));
+        assert!(report.ends_with("6 changes: 3 added, 2 removed, 1 modified\n"));
+    }
+
+    #[test]
+    fn identical_documents_do_not_drift() {
+        let doc = json!({"paths": {"/users": {"get": {"responses": {}}}}});
+        let drift = baseline(doc.clone()).drift(doc, false);
+        assert!(drift.changes.is_empty());
+        assert_eq!(
+            drift.summary(),
+            "vs baseline: 0 paths added, 0 removed, 0 operations changed"
+        );
+    }
+}
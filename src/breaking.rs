@@ -0,0 +1,831 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::diff::{Operation, operations, path_names};
+use crate::validate::escape;
+
+/// A change that can break clients written against the old document,
+/// located by a JSON pointer into the new document, or into the old one for
+/// something that no longer exists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakingChange {
+    /// The [`Rule::id`] that found it.
+    pub rule: &'static str,
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for BreakingChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.rule, self.location, self.message)
+    }
+}
+
+/// One kind of breaking change. Each rule sees both documents and reports
+/// every match it finds.
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    check: fn(&Versions<'_>, &mut Report),
+}
+
+/// Every rule, in the order their findings are reported. Additions,
+/// documentation, and anything not listed here are not breaking.
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "removed-path",
+        description: "a path was removed",
+        check: removed_paths,
+    },
+    Rule {
+        id: "removed-operation",
+        description: "an operation was removed from a path that still exists",
+        check: removed_operations,
+    },
+    Rule {
+        id: "removed-parameter",
+        description: "an operation no longer accepts a parameter",
+        check: removed_parameters,
+    },
+    Rule {
+        id: "required-parameter",
+        description: "a parameter is new and required, or was optional and is now required",
+        check: required_parameters,
+    },
+    Rule {
+        id: "removed-request-property",
+        description: "a request body property was removed",
+        check: removed_request_properties,
+    },
+    Rule {
+        id: "required-request-property",
+        description: "a request body, or a property in one, became required",
+        check: required_request_properties,
+    },
+    Rule {
+        id: "narrowed-enum",
+        description: "an enum no longer allows a value it used to",
+        check: narrowed_enums,
+    },
+    Rule {
+        id: "changed-type",
+        description: "a schema or property changed its type",
+        check: changed_types,
+    },
+    Rule {
+        id: "removed-response",
+        description: "an operation no longer documents a response code",
+        check: removed_responses,
+    },
+    Rule {
+        id: "changed-security",
+        description: "the security requirements of the API or an operation changed",
+        check: changed_security,
+    },
+];
+
+/// Runs every rule in [`RULES`] over the change from `old` to `new`.
+pub fn breaking_changes(old: &Value, new: &Value) -> Vec<BreakingChange> {
+    let versions = Versions { old, new };
+    let mut changes = Vec::new();
+    for rule in RULES {
+        let mut report = Report {
+            rule: rule.id,
+            changes: &mut changes,
+        };
+        (rule.check)(&versions, &mut report);
+    }
+    changes
+}
+
+/// "no breaking changes", "1 breaking change", "3 breaking changes".
+pub fn summarize_breaking(changes: &[BreakingChange]) -> String {
+    match changes.len() {
+        0 => "no breaking changes".to_string(),
+        1 => "1 breaking change".to_string(),
+        count => format!("{count} breaking changes"),
+    }
+}
+
+struct Versions<'a> {
+    old: &'a Value,
+    new: &'a Value,
+}
+
+struct Report<'a> {
+    rule: &'static str,
+    changes: &'a mut Vec<BreakingChange>,
+}
+
+impl Report<'_> {
+    /// Adds a finding once: a component schema shared by several operations
+    /// is walked for each of them but reported at its own location.
+    fn push(&mut self, location: String, message: String) {
+        let change = BreakingChange {
+            rule: self.rule,
+            location,
+            message,
+        };
+        if !self.changes.contains(&change) {
+            self.changes.push(change);
+        }
+    }
+}
+
+/// Local `$ref` chains longer than this, or schemas nested deeper, are not
+/// followed; it also stops recursive schemas.
+const MAX_DEPTH: usize = 32;
+
+/// Called with an object in the old and new document and the location of
+/// the new one.
+type Visit<'a> = dyn FnMut(&Map<String, Value>, &Map<String, Value>, &str) + 'a;
+
+/// Keys whose values are data rather than schemas, or that other rules
+/// cover, so [`walk_pairs`] does not look inside them.
+const OPAQUE_KEYS: [&str; 6] = [
+    "example",
+    "examples",
+    "default",
+    "const",
+    "enum",
+    "securitySchemes",
+];
+
+fn removed_paths(versions: &Versions<'_>, report: &mut Report) {
+    let after = path_names(versions.new);
+    for path in path_names(versions.old) {
+        if !after.contains(&path) {
+            report.push(
+                format!("#/paths/{}", escape(path)),
+                format!("path {path} was removed"),
+            );
+        }
+    }
+}
+
+fn removed_operations(versions: &Versions<'_>, report: &mut Report) {
+    let paths = path_names(versions.new);
+    let after = operations(versions.new);
+    for (path, method) in operations(versions.old).keys() {
+        if paths.contains(path) && !after.contains_key(&(*path, *method)) {
+            report.push(
+                operation_location(path, method),
+                format!("{} {path} was removed", method.to_ascii_uppercase()),
+            );
+        }
+    }
+}
+
+fn removed_parameters(versions: &Versions<'_>, report: &mut Report) {
+    for (path, method, old, new) in common_operations(versions) {
+        let after = parameters(versions.new, path, method, &new);
+        for ((location, name), _) in parameters(versions.old, path, method, &old) {
+            if !after.contains_key(&(location.clone(), name.clone())) {
+                report.push(
+                    operation_location(path, method),
+                    format!("{location} parameter '{name}' was removed"),
+                );
+            }
+        }
+    }
+}
+
+fn required_parameters(versions: &Versions<'_>, report: &mut Report) {
+    for (path, method, old, new) in common_operations(versions) {
+        let before = parameters(versions.old, path, method, &old);
+        for ((location, name), parameter) in parameters(versions.new, path, method, &new) {
+            if !parameter.required() {
+                continue;
+            }
+            let message = match before.get(&(location.clone(), name.clone())) {
+                None => format!("new required {location} parameter '{name}'"),
+                Some(previous) if !previous.required() => {
+                    format!("{location} parameter '{name}' is now required")
+                }
+                Some(_) => continue,
+            };
+            report.push(parameter.pointer, message);
+        }
+    }
+}
+
+fn removed_request_properties(versions: &Versions<'_>, report: &mut Report) {
+    for (old, new, location) in request_schemas(versions) {
+        walk_properties(
+            versions,
+            old,
+            new,
+            location,
+            0,
+            &mut |old, new, location| {
+                let after = new.get("properties").and_then(Value::as_object);
+                for name in old
+                    .get("properties")
+                    .and_then(Value::as_object)
+                    .into_iter()
+                    .flat_map(Map::keys)
+                {
+                    if !after.is_some_and(|after| after.contains_key(name)) {
+                        report.push(
+                            format!("{location}/properties"),
+                            format!("request property '{name}' was removed"),
+                        );
+                    }
+                }
+            },
+        );
+    }
+}
+
+fn required_request_properties(versions: &Versions<'_>, report: &mut Report) {
+    for (path, method, old, new) in common_operations(versions) {
+        let required = |doc: &Value, op: &Operation<'_>| {
+            op.op
+                .get("requestBody")
+                .map(|body| resolve(doc, body).0)
+                .and_then(|body| body.get("required"))
+                .and_then(Value::as_bool)
+                .unwrap_or(false)
+        };
+        if required(versions.new, &new) && !required(versions.old, &old) {
+            report.push(
+                format!("{}/requestBody", operation_location(path, method)),
+                "request body is now required".to_string(),
+            );
+        }
+    }
+    for (old, new, location) in request_schemas(versions) {
+        walk_properties(
+            versions,
+            old,
+            new,
+            location,
+            0,
+            &mut |old, new, location| {
+                let before = string_list(old.get("required"));
+                for name in string_list(new.get("required")) {
+                    if !before.contains(&name) {
+                        report.push(
+                            format!("{location}/required"),
+                            format!("request property '{name}' is now required"),
+                        );
+                    }
+                }
+            },
+        );
+    }
+}
+
+fn narrowed_enums(versions: &Versions<'_>, report: &mut Report) {
+    walk_pairs(
+        versions.old,
+        versions.new,
+        "#",
+        0,
+        &mut |old, new, location| {
+            let (Some(before), Some(after)) = (
+                old.get("enum").and_then(Value::as_array),
+                new.get("enum").and_then(Value::as_array),
+            ) else {
+                return;
+            };
+            let removed: Vec<String> = before
+                .iter()
+                .filter(|value| !after.contains(value))
+                .map(Value::to_string)
+                .collect();
+            if !removed.is_empty() {
+                report.push(
+                    format!("{location}/enum"),
+                    format!("enum no longer allows {}", removed.join(", ")),
+                );
+            }
+        },
+    );
+}
+
+fn changed_types(versions: &Versions<'_>, report: &mut Report) {
+    walk_pairs(
+        versions.old,
+        versions.new,
+        "#",
+        0,
+        &mut |old, new, location| {
+            let (Some(before), Some(after)) = (
+                old.get("type").and_then(type_name),
+                new.get("type").and_then(type_name),
+            ) else {
+                return;
+            };
+            if before != after {
+                report.push(
+                    format!("{location}/type"),
+                    format!("type changed from {before} to {after}"),
+                );
+            }
+        },
+    );
+}
+
+fn removed_responses(versions: &Versions<'_>, report: &mut Report) {
+    for (path, method, old, new) in common_operations(versions) {
+        let after = new.op.get("responses").and_then(Value::as_object);
+        for code in old
+            .op
+            .get("responses")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flat_map(Map::keys)
+        {
+            if !after.is_some_and(|after| after.contains_key(code)) {
+                report.push(
+                    format!("{}/responses", operation_location(path, method)),
+                    format!("response {code} was removed"),
+                );
+            }
+        }
+    }
+}
+
+/// A change to the top-level requirements is reported once; operations are
+/// reported only when they declare their own.
+fn changed_security(versions: &Versions<'_>, report: &mut Report) {
+    let (old_root, new_root) = (versions.old.get("security"), versions.new.get("security"));
+    if old_root != new_root {
+        report.push(
+            "#/security".to_string(),
+            format!(
+                "security changed from {} to {}",
+                describe_security(old_root),
+                describe_security(new_root)
+            ),
+        );
+    }
+    for (path, method, old, new) in common_operations(versions) {
+        let (old_own, new_own) = (old.op.get("security"), new.op.get("security"));
+        if old_own.is_none() && new_own.is_none() {
+            continue;
+        }
+        let before = old_own.or(old_root);
+        let after = new_own.or(new_root);
+        if before != after {
+            report.push(
+                format!("{}/security", operation_location(path, method)),
+                format!(
+                    "security of {} {path} changed from {} to {}",
+                    method.to_ascii_uppercase(),
+                    describe_security(before),
+                    describe_security(after)
+                ),
+            );
+        }
+    }
+}
+
+/// Operations present in both documents.
+fn common_operations<'a>(
+    versions: &Versions<'a>,
+) -> Vec<(&'a str, &'a str, Operation<'a>, Operation<'a>)> {
+    let mut after = operations(versions.new);
+    operations(versions.old)
+        .into_iter()
+        .filter_map(|((path, method), old)| {
+            let new = after.remove(&(path, method))?;
+            Some((path, method, old, new))
+        })
+        .collect()
+}
+
+fn operation_location(path: &str, method: &str) -> String {
+    format!("#/paths/{}/{method}", escape(path))
+}
+
+struct Parameter<'a> {
+    pointer: String,
+    value: &'a Value,
+}
+
+impl Parameter<'_> {
+    fn required(&self) -> bool {
+        self.value
+            .get("required")
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+}
+
+/// The parameters that apply to an operation, keyed by location and name;
+/// the operation's own override the path's.
+fn parameters<'a>(
+    doc: &'a Value,
+    path: &str,
+    method: &str,
+    op: &Operation<'a>,
+) -> BTreeMap<(String, String), Parameter<'a>> {
+    let path_location = format!("#/paths/{}", escape(path));
+    let op_location = operation_location(path, method);
+    let mut parameters = BTreeMap::new();
+    for (list, location) in [
+        (op.path_parameters, path_location),
+        (op.op.get("parameters"), op_location),
+    ] {
+        for (index, parameter) in list
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let (value, target) = resolve(doc, parameter);
+            let (Some(name), Some(place)) = (
+                value.get("name").and_then(Value::as_str),
+                value.get("in").and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+            let pointer = target.unwrap_or_else(|| format!("{location}/parameters/{index}"));
+            parameters.insert(
+                (place.to_string(), name.to_string()),
+                Parameter { pointer, value },
+            );
+        }
+    }
+    parameters
+}
+
+/// Request body schemas of each operation, per media type both versions
+/// accept, with the location of the new one.
+fn request_schemas<'a>(versions: &Versions<'a>) -> Vec<(&'a Value, &'a Value, String)> {
+    let mut schemas = Vec::new();
+    for (path, method, old, new) in common_operations(versions) {
+        let content = |doc: &'a Value, op: &Operation<'a>| {
+            op.op
+                .get("requestBody")
+                .map(|body| resolve(doc, body))
+                .and_then(|(body, target)| {
+                    let location = target.unwrap_or_else(|| {
+                        format!("{}/requestBody", operation_location(path, method))
+                    });
+                    Some((body.get("content")?.as_object()?, location))
+                })
+        };
+        let (Some((before, _)), Some((after, location))) =
+            (content(versions.old, &old), content(versions.new, &new))
+        else {
+            continue;
+        };
+        for (media_type, media) in after {
+            let (Some(old), Some(new)) = (
+                before.get(media_type).and_then(|media| media.get("schema")),
+                media.get("schema"),
+            ) else {
+                continue;
+            };
+            schemas.push((
+                old,
+                new,
+                format!("{location}/content/{}/schema", escape(media_type)),
+            ));
+        }
+    }
+    schemas
+}
+
+/// Calls `visit` with a request schema in both versions, then with each
+/// property and array item schema the two have in common, following local
+/// `$ref`s.
+fn walk_properties(
+    versions: &Versions<'_>,
+    old: &Value,
+    new: &Value,
+    location: String,
+    depth: usize,
+    visit: &mut Visit<'_>,
+) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let (old, _) = resolve(versions.old, old);
+    let (new, target) = resolve(versions.new, new);
+    let location = target.unwrap_or(location);
+    let (Some(old), Some(new)) = (old.as_object(), new.as_object()) else {
+        return;
+    };
+    visit(old, new, &location);
+
+    let before = old.get("properties").and_then(Value::as_object);
+    for (name, schema) in new
+        .get("properties")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(previous) = before.and_then(|before| before.get(name)) {
+            let location = format!("{location}/properties/{}", escape(name));
+            walk_properties(versions, previous, schema, location, depth + 1, visit);
+        }
+    }
+    if let (Some(previous), Some(items)) = (old.get("items"), new.get("items")) {
+        let location = format!("{location}/items");
+        walk_properties(versions, previous, items, location, depth + 1, visit);
+    }
+}
+
+/// Calls `visit` with every object found at the same place in both
+/// documents. Parameter lists are matched by name and location rather than
+/// position; other arrays by position.
+fn walk_pairs(old: &Value, new: &Value, location: &str, depth: usize, visit: &mut Visit<'_>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            visit(old, new, location);
+            for (key, value) in new {
+                if OPAQUE_KEYS.contains(&key.as_str()) || key.starts_with("x-") {
+                    continue;
+                }
+                let Some(previous) = old.get(key) else {
+                    continue;
+                };
+                let location = format!("{location}/{}", escape(key));
+                if key == "parameters" {
+                    walk_parameters(previous, value, &location, depth + 1, visit);
+                } else if key == "properties" {
+                    walk_named(previous, value, &location, depth + 1, visit);
+                } else {
+                    walk_pairs(previous, value, &location, depth + 1, visit);
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for (index, (previous, value)) in old.iter().zip(new).enumerate() {
+                walk_pairs(
+                    previous,
+                    value,
+                    &format!("{location}/{index}"),
+                    depth + 1,
+                    visit,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Like [`walk_pairs`] for a map of property names, which may be any
+/// string, [`OPAQUE_KEYS`] included.
+fn walk_named(old: &Value, new: &Value, location: &str, depth: usize, visit: &mut Visit<'_>) {
+    let (Some(old), Some(new)) = (old.as_object(), new.as_object()) else {
+        return;
+    };
+    for (name, schema) in new {
+        if let Some(previous) = old.get(name) {
+            let location = format!("{location}/{}", escape(name));
+            walk_pairs(previous, schema, &location, depth + 1, visit);
+        }
+    }
+}
+
+fn walk_parameters(old: &Value, new: &Value, location: &str, depth: usize, visit: &mut Visit<'_>) {
+    let (Some(old), Some(new)) = (old.as_array(), new.as_array()) else {
+        return walk_pairs(old, new, location, depth, visit);
+    };
+    fn key(parameter: &Value) -> [Option<&str>; 3] {
+        ["in", "name", "$ref"].map(|key| parameter.get(key).and_then(Value::as_str))
+    }
+    for (index, parameter) in new.iter().enumerate() {
+        if let Some(previous) = old.iter().find(|previous| key(previous) == key(parameter)) {
+            walk_pairs(
+                previous,
+                parameter,
+                &format!("{location}/{index}"),
+                depth,
+                visit,
+            );
+        }
+    }
+}
+
+/// Follows a chain of local `$ref`s, returning the target and, when there
+/// was a reference, its location.
+fn resolve<'a>(doc: &'a Value, mut value: &'a Value) -> (&'a Value, Option<String>) {
+    let mut location = None;
+    for _ in 0..MAX_DEPTH {
+        let Some(target) = value.get("$ref").and_then(Value::as_str) else {
+            break;
+        };
+        let Some(resolved) = target
+            .strip_prefix('#')
+            .and_then(|pointer| doc.pointer(pointer))
+        else {
+            break;
+        };
+        value = resolved;
+        location = Some(target.to_string());
+    }
+    (value, location)
+}
+
+/// `"string"`, or `["string", "null"]` as `string | null`.
+fn type_name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(name) => Some(name.clone()),
+        Value::Array(names) => names
+            .iter()
+            .map(|name| name.as_str().map(str::to_string))
+            .collect::<Option<Vec<_>>>()
+            .map(|names| names.join(" | ")),
+        _ => None,
+    }
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect()
+}
+
+/// Requirement objects as `a + b`, alternatives joined by `or`; no
+/// requirements at all is `none`.
+fn describe_security(security: Option<&Value>) -> String {
+    let alternatives: Vec<String> = security
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object)
+        .map(|requirement| {
+            if requirement.is_empty() {
+                "anonymous".to_string()
+            } else {
+                requirement
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" + ")
+            }
+        })
+        .collect();
+    if alternatives.is_empty() {
+        "none".to_string()
+    } else {
+        alternatives.join(" or ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Checks `tests/fixtures/breaking/<name>`: the findings from the shared
+    /// `base.json` to `new.json` must match `expected.txt` line for line.
+    macro_rules! fixture_pair {
+        ($test:ident, $name:literal) => {
+            #[test]
+            fn $test() {
+                let old: Value =
+                    serde_json::from_str(include_str!("../tests/fixtures/breaking/base.json"))
+                        .unwrap();
+                let new: Value = serde_json::from_str(include_str!(concat!(
+                    "../tests/fixtures/breaking/",
+                    $name,
+                    "/new.json"
+                )))
+                .unwrap();
+                let expected = include_str!(concat!(
+                    "../tests/fixtures/breaking/",
+                    $name,
+                    "/expected.txt"
+                ));
+                let lines: Vec<String> = breaking_changes(&old, &new)
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                assert_eq!(lines, expected.lines().collect::<Vec<_>>(), $name);
+            }
+        };
+    }
+
+    fixture_pair!(removed_path, "removed-path");
+    fixture_pair!(removed_operation, "removed-operation");
+    fixture_pair!(removed_parameter, "removed-parameter");
+    fixture_pair!(required_parameter, "required-parameter");
+    fixture_pair!(removed_request_property, "removed-request-property");
+    fixture_pair!(required_request_property, "required-request-property");
+    fixture_pair!(narrowed_enum, "narrowed-enum");
+    fixture_pair!(changed_type, "changed-type");
+    fixture_pair!(removed_response, "removed-response");
+    fixture_pair!(changed_security, "changed-security");
+    fixture_pair!(additions_and_documentation_are_not_breaking, "additions");
+
+    #[test]
+    fn every_rule_has_a_fixture_pair() {
+        let fixtures =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/breaking");
+        for rule in RULES {
+            assert!(
+                fixtures.join(rule.id).join("expected.txt").is_file(),
+                "{}",
+                rule.id
+            );
+        }
+    }
+
+    #[test]
+    fn property_names_that_look_like_keywords_are_still_compared() {
+        let old = json!({"components": {"schemas": {"Setting": {"properties": {
+            "default": {"type": "string"},
+            "enum": {"type": "string", "enum": ["a", "b"]}
+        }}}}});
+        let new = json!({"components": {"schemas": {"Setting": {"properties": {
+            "default": {"type": "boolean"},
+            "enum": {"type": "string", "enum": ["a"]}
+        }}}}});
+        let lines: Vec<String> = breaking_changes(&old, &new)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "narrowed-enum #/components/schemas/Setting/properties/enum/enum enum no longer allows \"b\"",
+                "changed-type #/components/schemas/Setting/properties/default/type type changed from string to boolean",
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_request_schemas_are_reported_once() {
+        let doc = |required: &[&str], properties: Value| {
+            let body = json!({"content": {"application/json": {
+                "schema": {"$ref": "#/components/schemas/NewUser"}
+            }}});
+            json!({
+                "paths": {
+                    "/users": {"post": {"requestBody": body.clone(), "responses": {}}},
+                    "/users/{id}": {"put": {"requestBody": body, "responses": {}}}
+                },
+                "components": {"schemas": {"NewUser": {
+                    "type": "object",
+                    "required": required,
+                    "properties": properties
+                }}}
+            })
+        };
+        let old = doc(
+            &[],
+            json!({"name": {"type": "string"}, "nickname": {"type": "string"}}),
+        );
+        let new = doc(&["name"], json!({"name": {"type": "string"}}));
+        let lines: Vec<String> = breaking_changes(&old, &new)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "removed-request-property #/components/schemas/NewUser/properties request property 'nickname' was removed",
+                "required-request-property #/components/schemas/NewUser/required request property 'name' is now required",
+            ]
+        );
+    }
+
+    #[test]
+    fn examples_and_recursive_schemas_are_not_followed() {
+        let doc = |kind: &str| {
+            json!({
+                "paths": {"/nodes": {"post": {
+                    "requestBody": {"content": {"application/json": {
+                        "schema": {"$ref": "#/components/schemas/Node"},
+                        "example": {"type": kind}
+                    }}},
+                    "responses": {}
+                }}},
+                "components": {"schemas": {"Node": {
+                    "type": "object",
+                    "properties": {"child": {"$ref": "#/components/schemas/Node"}}
+                }}}
+            })
+        };
+        assert!(breaking_changes(&doc("leaf"), &doc("branch")).is_empty());
+    }
+
+    #[test]
+    fn summarize_breaking_counts_findings() {
+        let change = BreakingChange {
+            rule: "removed-path",
+            location: "#/paths/~1users".to_string(),
+            message: "path /users was removed".to_string(),
+        };
+        assert_eq!(summarize_breaking(&[]), "no breaking changes");
+        let two = [change.clone(), change];
+        assert_eq!(summarize_breaking(&two[..1]), "1 breaking change");
+        assert_eq!(summarize_breaking(&two), "2 breaking changes");
+    }
+}
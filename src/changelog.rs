@@ -0,0 +1,163 @@
+//! Release-note style markdown for the changes between two documents.
+
+use serde_json::Value;
+
+use crate::breaking::{BreakingChange, breaking_changes};
+use crate::diff::{ChangeKind, OperationChange, SpecChange, Subject, diff_specs};
+
+/// Renders the changes from `old` to `new` as markdown sections: Breaking
+/// (when [`breaking_changes`] finds anything), Added, Removed, and Changed,
+/// each a bullet list in a fixed order. Modified operations get a nested
+/// bullet per change. With `max_items`, each section lists at most that
+/// many entries followed by a count of the rest. Identical documents give
+/// "No changes."
+pub fn markdown_changelog(old: &Value, new: &Value, max_items: Option<usize>) -> String {
+    let changes = diff_specs(old, new);
+    let breaking = breaking_changes(old, new);
+    let of_kind = |kind| -> Vec<&SpecChange> {
+        changes
+            .iter()
+            .filter(|change| change.kind == kind)
+            .collect()
+    };
+
+    let mut sections = Vec::new();
+    let entries: Vec<Vec<String>> = breaking.iter().map(breaking_entry).collect();
+    sections.push(("Breaking", entries));
+    for (title, kind) in [
+        ("Added", ChangeKind::Added),
+        ("Removed", ChangeKind::Removed),
+        ("Changed", ChangeKind::Modified),
+    ] {
+        let entries = of_kind(kind).into_iter().map(change_entry).collect();
+        sections.push((title, entries));
+    }
+
+    let mut out = String::new();
+    for (title, entries) in sections.iter().filter(|(_, entries)| !entries.is_empty()) {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("### {title}\n\n"));
+        let shown = max_items.unwrap_or(entries.len()).min(entries.len());
+        for lines in &entries[..shown] {
+            for line in lines {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        if shown < entries.len() {
+            out.push_str(&format!("- …and {} more\n", entries.len() - shown));
+        }
+    }
+    if out.is_empty() {
+        out.push_str("No changes.\n");
+    }
+    out
+}
+
+fn breaking_entry(change: &BreakingChange) -> Vec<String> {
+    vec![format!(
+        "- {} (`{}`)",
+        capitalize(&change.message),
+        change.location
+    )]
+}
+
+/// The bullet for a change, then one nested bullet per change inside a
+/// modified operation.
+fn change_entry(change: &SpecChange) -> Vec<String> {
+    let subject = match &change.subject {
+        Subject::Path(path) => format!("Path `{path}`"),
+        Subject::Operation { path, method } => {
+            format!("`{} {path}`", method.to_ascii_uppercase())
+        }
+        Subject::Schema(name) => format!("Schema `{name}`"),
+    };
+    let mut lines = vec![format!("- {subject}")];
+    lines.extend(
+        change
+            .changes
+            .iter()
+            .map(|change| format!("  - {}", describe(change))),
+    );
+    lines
+}
+
+fn describe(change: &OperationChange) -> String {
+    match change {
+        OperationChange::ParameterAdded { location, name } => {
+            format!("Added {location} parameter `{name}`")
+        }
+        OperationChange::ParameterRemoved { location, name } => {
+            format!("Removed {location} parameter `{name}`")
+        }
+        OperationChange::ParameterChanged { location, name } => {
+            format!("Changed {location} parameter `{name}`")
+        }
+        OperationChange::ParametersChanged => "Reordered parameters".to_string(),
+        OperationChange::RequestBodyAdded => "Added a request body".to_string(),
+        OperationChange::RequestBodyRemoved => "Removed the request body".to_string(),
+        OperationChange::RequestBodyChanged => "Changed the request schema".to_string(),
+        OperationChange::ResponseAdded(code) => format!("Added response `{code}`"),
+        OperationChange::ResponseRemoved(code) => format!("Removed response `{code}`"),
+        OperationChange::ResponseChanged(code) => format!("Changed response `{code}`"),
+        OperationChange::Metadata => "Changed summary, description, or other metadata".to_string(),
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_pair(dir: &str) -> (Value, Value) {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+        let read = |path: std::path::PathBuf| -> Value {
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+        };
+        // The breaking-change cases share one old document.
+        let old = match dir.join("old.json") {
+            old if old.is_file() => old,
+            _ => dir.join("../base.json"),
+        };
+        (read(old), read(dir.join("new.json")))
+    }
+
+    #[test]
+    fn changelog_matches_golden_files() {
+        for (dir, golden) in [
+            (
+                "tests/fixtures/diff/changed",
+                include_str!("../tests/fixtures/diff/changed/expected.md"),
+            ),
+            (
+                "tests/fixtures/breaking/required-parameter",
+                include_str!("../tests/fixtures/breaking/required-parameter/expected.md"),
+            ),
+        ] {
+            let (old, new) = fixture_pair(dir);
+            assert_eq!(markdown_changelog(&old, &new, None), golden, "{dir}");
+        }
+    }
+
+    #[test]
+    fn max_items_truncates_each_section_with_a_count() {
+        let (old, new) = fixture_pair("tests/fixtures/diff/changed");
+        let golden = include_str!("../tests/fixtures/diff/changed/expected.max1.md");
+        assert_eq!(markdown_changelog(&old, &new, Some(1)), golden);
+    }
+
+    #[test]
+    fn identical_documents_have_no_changes() {
+        let (old, _) = fixture_pair("tests/fixtures/diff/changed");
+        assert_eq!(markdown_changelog(&old, &old, None), "No changes.\n");
+    }
+}
@@ -0,0 +1,317 @@
+//! Removal of leftover temp files and old history entries.
+//!
+//! Only names this tool produces are ever touched: `.<name>.<millis>.tmp`
+//! and `<name>.<millis>.partial` next to an output file, and
+//! `<stem>.<millis>.<ext>` in a history directory. Anything else, including the snapshot itself, is left alone.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::AppError;
+
+/// Temp files younger than this may belong to a write still in progress.
+const TEMP_GRACE: Duration = Duration::from_secs(60);
+
+/// How many history entries to keep. The newest entry is always kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retention {
+    pub keep_last: Option<usize>,
+    pub older_than: Option<Duration>,
+}
+
+/// Name of the history entry for `out` taken at `millis`:
+/// `api.json` becomes `api.<millis>.json`.
+pub fn history_file_name(out: &Path, millis: u128) -> String {
+    let (stem, extension) = stem_and_extension(out);
+    match extension {
+        Some(extension) => format!("{stem}.{millis}.{extension}"),
+        None => format!("{stem}.{millis}"),
+    }
+}
+
+/// Files `clean` would remove for the snapshot at `out` (and its outline,
+/// if any), in the order it removes them: temp files first, then history
+/// entries from oldest to newest.
+pub fn clean_candidates(
+    out: &Path,
+    outline_out: Option<&Path>,
+    history: Option<(&Path, Retention)>,
+    now: SystemTime,
+) -> Result<Vec<PathBuf>, AppError> {
+    let now_ms = millis_since_epoch(now);
+    let mut candidates = Vec::new();
+    for output in std::iter::once(out).chain(outline_out) {
+        let dir = parent_dir(output);
+        let name = file_name(output);
+        for (path, millis) in matching_files(&dir, |entry| temp_timestamp(entry, &name))? {
+            if now_ms.saturating_sub(millis) >= TEMP_GRACE.as_millis() {
+                candidates.push(path);
+            }
+        }
+    }
+    candidates.sort();
+
+    if let Some((dir, retention)) = history {
+        let (stem, extension) = stem_and_extension(out);
+        let mut entries = matching_files(dir, |entry| {
+            history_timestamp(entry, &stem, extension.as_deref())
+        })?;
+        // Newest first; the name breaks ties so the order is stable.
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| b.0.cmp(&a.0)));
+        let keep_last = retention.keep_last.unwrap_or(usize::MAX).max(1);
+        let mut expired: Vec<PathBuf> = entries
+            .into_iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(index, (_, millis))| {
+                *index >= keep_last
+                    || retention
+                        .older_than
+                        .is_some_and(|age| now_ms.saturating_sub(*millis) > age.as_millis())
+            })
+            .map(|(_, (path, _))| path)
+            .collect();
+        expired.reverse();
+        candidates.extend(expired);
+    }
+
+    // Belt and braces: the patterns above never match a live output file,
+    // but a history directory could be pointed at the output directory.
+    let protected: Vec<PathBuf> = std::iter::once(out)
+        .chain(outline_out)
+        .map(|path| path.to_path_buf())
+        .collect();
+    candidates.retain(|path| !protected.iter().any(|keep| same_file(path, keep)));
+    Ok(candidates)
+}
+
+/// Removes one file returned by [`clean_candidates`].
+pub fn remove_file(path: &Path) -> Result<(), AppError> {
+    fs::remove_file(path).map_err(|err| {
+        AppError::io(format!("failed to remove {}: {err}", path.display())).with_source(err)
+    })
+}
+
+/// Regular files in `dir` whose names `matches` accepts, with the timestamp
+/// it extracted. A missing directory has nothing to clean.
+fn matching_files(
+    dir: &Path,
+    matches: impl Fn(&str) -> Option<u128>,
+) -> Result<Vec<(PathBuf, u128)>, AppError> {
+    let read_error =
+        |err| AppError::io(format!("failed to read {}: {err}", dir.display())).with_source(err);
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(read_error(err)),
+    };
+    let mut found = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(read_error)?;
+        let Some(millis) = entry.file_name().to_str().and_then(&matches) else {
+            continue;
+        };
+        // symlink_metadata, so a link named like a temp file is not followed.
+        if entry
+            .path()
+            .symlink_metadata()
+            .is_ok_and(|meta| meta.is_file())
+        {
+            found.push((entry.path(), millis));
+        }
+    }
+    Ok(found)
+}
+
+/// The timestamp in `.<name>.<millis>.tmp` or `<name>.<millis>.partial`,
+/// if `entry` has either shape.
+fn temp_timestamp(entry: &str, name: &str) -> Option<u128> {
+    let millis = match entry.strip_prefix('.') {
+        Some(rest) => rest.strip_prefix(name)?.strip_suffix(".tmp")?,
+        None => entry.strip_prefix(name)?.strip_suffix(".partial")?,
+    };
+    digits(millis.strip_prefix('.')?)
+}
+
+/// The timestamp in `<stem>.<millis>[.<extension>]`, if `entry` has that
+/// shape.
+fn history_timestamp(entry: &str, stem: &str, extension: Option<&str>) -> Option<u128> {
+    let rest = entry.strip_prefix(stem)?.strip_prefix('.')?;
+    let millis = match extension {
+        Some(extension) => rest.strip_suffix(extension)?.strip_suffix('.')?,
+        None => rest,
+    };
+    digits(millis)
+}
+
+fn digits(text: &str) -> Option<u128> {
+    if text.is_empty() || !text.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+fn stem_and_extension(path: &Path) -> (String, Option<String>) {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "openapi_snapshot".to_string());
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().into_owned());
+    (stem, extension)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("openapi_snapshot")
+        .to_string()
+}
+
+fn parent_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn millis_since_epoch(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: u128 = 86_400_000;
+    /// 2024-01-01T00:00:00Z.
+    const NOW_MS: u128 = 1_704_067_200_000;
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(NOW_MS as u64)
+    }
+
+    fn touch(dir: &Path, name: &str) {
+        fs::write(dir.join(name), "{}").unwrap();
+    }
+
+    fn names(paths: &[PathBuf]) -> Vec<String> {
+        paths.iter().map(|path| file_name(path)).collect()
+    }
+
+    #[test]
+    fn only_our_stale_temp_files_are_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("api.json");
+        let outline = dir.path().join("api.outline.json");
+        let old = NOW_MS - DAY_MS;
+        touch(dir.path(), "api.json");
+        touch(dir.path(), &format!(".api.json.{old}.tmp"));
+        touch(dir.path(), &format!(".api.outline.json.{old}.tmp"));
+        touch(dir.path(), &format!("api.json.{old}.partial"));
+        // Decoys: a write in progress, other tools' files, near misses.
+        touch(dir.path(), &format!(".api.json.{}.tmp", NOW_MS - 1_000));
+        touch(dir.path(), &format!(".other.json.{old}.tmp"));
+        touch(dir.path(), ".api.json.tmp");
+        touch(dir.path(), ".api.json.12x.tmp");
+        touch(dir.path(), &format!("api.json.{old}.tmp"));
+        touch(dir.path(), &format!(".api.json.{old}.tmp.bak"));
+        touch(dir.path(), &format!("api.json.{}.partial", NOW_MS - 1_000));
+        touch(dir.path(), &format!(".api.json.{old}.partial"));
+        touch(dir.path(), &format!("api.json.{old}.tmp.partial"));
+        fs::create_dir(dir.path().join(format!(".api.json.{}.tmp", old + 1))).unwrap();
+
+        let candidates = clean_candidates(&out, Some(&outline), None, now()).unwrap();
+        assert_eq!(
+            names(&candidates),
+            vec![
+                format!(".api.json.{old}.tmp"),
+                format!(".api.outline.json.{old}.tmp"),
+                format!("api.json.{old}.partial"),
+            ]
+        );
+        for path in &candidates {
+            remove_file(path).unwrap();
+        }
+        assert!(out.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 11);
+    }
+
+    #[test]
+    fn history_keeps_the_newest_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("api.json");
+        let history = dir.path().join("history");
+        fs::create_dir(&history).unwrap();
+        for days in 1..=5 {
+            touch(&history, &history_file_name(&out, NOW_MS - days * DAY_MS));
+        }
+        touch(&history, "api.json");
+        touch(&history, "api.notes.json");
+        touch(&history, &format!("other.{NOW_MS}.json"));
+        touch(&history, &format!("api.{NOW_MS}.yaml"));
+
+        let keep_two = Retention {
+            keep_last: Some(2),
+            older_than: None,
+        };
+        let candidates = clean_candidates(&out, None, Some((&history, keep_two)), now()).unwrap();
+        let expected: Vec<String> = [5, 4, 3]
+            .iter()
+            .map(|days| format!("api.{}.json", NOW_MS - days * DAY_MS))
+            .collect();
+        assert_eq!(names(&candidates), expected);
+
+        let recent = Retention {
+            keep_last: None,
+            older_than: Some(Duration::from_millis((2 * DAY_MS + 1) as u64)),
+        };
+        let candidates = clean_candidates(&out, None, Some((&history, recent)), now()).unwrap();
+        assert_eq!(names(&candidates), expected);
+    }
+
+    #[test]
+    fn the_newest_history_entry_survives_any_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("api.json");
+        let newest = history_file_name(&out, NOW_MS - 10 * DAY_MS);
+        touch(dir.path(), &newest);
+        touch(dir.path(), &history_file_name(&out, NOW_MS - 20 * DAY_MS));
+
+        let strict = Retention {
+            keep_last: Some(0),
+            older_than: Some(Duration::from_secs(1)),
+        };
+        let candidates = clean_candidates(&out, None, Some((dir.path(), strict)), now()).unwrap();
+        assert_eq!(
+            names(&candidates),
+            vec![format!("api.{}.json", NOW_MS - 20 * DAY_MS)]
+        );
+    }
+
+    #[test]
+    fn missing_directories_have_nothing_to_clean() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("missing/api.json");
+        let retention = Retention {
+            keep_last: Some(1),
+            older_than: None,
+        };
+        let history = dir.path().join("missing-history");
+        let candidates = clean_candidates(&out, None, Some((&history, retention)), now()).unwrap();
+        assert!(candidates.is_empty());
+    }
+}
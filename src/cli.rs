@@ -1,24 +1,170 @@
 use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::errors::{DIFF_EXIT_CODES, EXIT_CODES};
+use crate::lint::parse_rule_id;
+use crate::validate::{Severity, parse_severity_override};
+use crate::version::SpecVersion;
+
 pub const DEFAULT_URL: &str = "http://localhost:3000/api-docs/openapi.json";
+pub const DEFAULT_PORT: u16 = 3000;
+pub const DEFAULT_PATH: &str = "/api-docs/openapi.json";
 pub const DEFAULT_OUT: &str = "openapi/backend_openapi.json";
 pub const DEFAULT_OUTLINE_OUT: &str = "openapi/backend_openapi.outline.json";
 pub const DEFAULT_REDUCE: &str = "paths,components";
 pub const DEFAULT_INTERVAL_MS: u64 = 2_000;
+pub const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+pub const DEFAULT_MAX_BACKOFF_MS: u64 = 60_000;
+pub const DEFAULT_PROMPT_GRACE: &str = "5s";
+pub const DEFAULT_MAX_INTERVAL: &str = "30s";
+pub const DEFAULT_REPROMPT_AFTER: u64 = 3;
+pub const DEFAULT_METRICS_PREFIX: &str = "openapi_snapshot";
 
-#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum OutputProfile {
     Full,
+    /// Still a valid spec, without descriptions, summaries, examples, `x-`
+    /// extensions, or unused components.
+    Slim,
     Outline,
 }
 
+/// Shape of `$ref` entries in outline output. `v1` keeps the legacy mix of
+/// `{"$ref": ...}` objects and bare strings; `v2` uses bare strings everywhere.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutlineCompat {
+    V1,
+    #[default]
+    V2,
+}
+
+/// Timestamp prefix for watch-mode log lines.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogTimestamps {
+    /// Local time as HH:MM:SS.
+    Time,
+    /// Full RFC 3339 timestamp with offset.
+    Rfc3339,
+}
+
+/// Document serialization format.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DocFormat {
+    Json,
+    Yaml,
+}
+
+/// How `diff` prints the changes it finds.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffFormat {
+    /// One line per added, removed, or modified entry, then a summary.
+    #[default]
+    Text,
+    /// An RFC 6902 JSON Patch that turns the old document into the new one.
+    JsonPatch,
+    /// Release-note markdown: Breaking, Added, Removed, and Changed sections.
+    Markdown,
+    /// One line per added, removed, or changed operation, e.g. `changed GET
+    /// /users [param:added page_size]`, then one per changed schema.
+    Operations,
+    /// A JSON report with the changes, the breaking findings, and the
+    /// `--fail-on` outcome.
+    Json,
+}
+
+/// Which differences make `diff` and `check` fail; see
+/// [`crate::errors::fail_on_kind`] for the exit codes.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailOn {
+    /// Fail only when the documents differ in ways that break clients.
+    Breaking,
+    /// Fail on any difference.
+    Any,
+    /// Always exit 0 and only report.
+    None,
+}
+
+/// When to color change output.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorChoice {
+    /// Color terminals unless `NO_COLOR` is set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// How far `show --resolve` inlines local `$ref`s.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResolveDepth {
+    /// Replace the references in the selected definition only.
+    Shallow,
+    /// Keep inlining inside the referenced definitions, stopping at cycles.
+    Deep,
+}
+
+/// What `search` looks through.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SearchScope {
+    /// Paths, operations, and tags.
+    Paths,
+    /// Component schemas and their properties.
+    Schemas,
+    #[default]
+    All,
+}
+
+/// Invocations shown under "Examples" in `--help` and the manual page.
+pub const EXAMPLES: [&str; 8] = [
+    "openapi-snapshot",
+    "openapi-snapshot watch",
+    "openapi-snapshot watch --url http://localhost:8080/openapi.json --out openapi/backend_openapi.json",
+    "openapi-snapshot --out openapi/backend_openapi.json --outline-out openapi/backend_openapi.outline.json",
+    "openapi-snapshot --profile outline --out openapi/backend_openapi.outline.json",
+    "openapi-snapshot --url http://localhost:3000/api-docs/openapi.json --out openapi/backend_openapi.json",
+    "openapi-snapshot --out openapi/backend_openapi.min.json",
+    "openapi-snapshot --pretty --out openapi/backend_openapi.min.json",
+];
+
+fn after_help() -> String {
+    let mut text = String::from("Examples:\n");
+    for example in EXAMPLES {
+        text.push_str(&format!("  {example}\n"));
+    }
+    text.push_str("\nExit codes:");
+    text.push_str(&exit_code_lines(&EXIT_CODES));
+    text
+}
+
+/// The exit code table of `diff` and `check`.
+fn diff_after_help() -> String {
+    format!("Exit codes:{}", exit_code_lines(&DIFF_EXIT_CODES))
+}
+
+fn exit_code_lines(codes: &[(i32, &str)]) -> String {
+    codes
+        .iter()
+        .map(|(code, meaning)| format!("\n  {code}  {meaning}"))
+        .collect()
+}
+
 #[derive(Parser, Debug)]
 #[command(
     name = "openapi-snapshot",
     version,
     about = "Fetch and save an OpenAPI JSON snapshot.",
-    after_help = "Examples:\n  openapi-snapshot\n  openapi-snapshot watch\n  openapi-snapshot --out openapi/backend_openapi.json --outline-out openapi/backend_openapi.outline.json\n  openapi-snapshot --profile outline --out openapi/backend_openapi.outline.json\n  openapi-snapshot --url http://localhost:3000/api-docs/openapi.json --out openapi/backend_openapi.json\n  openapi-snapshot --minify true --out openapi/backend_openapi.min.json"
+    after_help = after_help()
 )]
 pub struct Cli {
     #[command(subcommand)]
@@ -29,41 +175,804 @@ pub struct Cli {
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
-    Watch(WatchArgs),
+    Watch(Box<WatchArgs>),
+    /// Print a shell completion script to stdout.
+    Completions(CompletionsArgs),
+    /// Print the manual page (roff) to stdout, or write it to `--out`.
+    Man(ManArgs),
+    /// Store a bearer token for the `--url` host in the system keyring.
+    /// Fetches from that host then send it unless an `Authorization` header
+    /// is given. Reads the token without echo, or from stdin when piped.
+    Login,
+    /// Remove the stored token for the `--url` host.
+    Logout,
+    /// Write a starter openapi-snapshot.toml and prepare the output directory.
+    Init(InitArgs),
+    /// Compare two snapshots and list added, removed, and modified entries.
+    ///
+    /// Exits 0 when the documents match, 1 when they differ, and 2 or more
+    /// when they cannot be compared. With `--breaking`, lists only changes
+    /// that can break existing clients and exits 10 when there are any.
+    #[command(after_help = diff_after_help())]
+    Diff(DiffArgs),
+    /// Check a document for structural problems such as missing keys and
+    /// unresolved local `$ref`s.
+    ///
+    /// Exits 6 when any error is found, or more warnings than `--max-warnings`.
+    Validate(ValidateArgs),
+    /// Check a document against style rules: tags, success response
+    /// schemas, uuid property names, trailing slashes, and summaries.
+    ///
+    /// Exits 6 when any error-severity rule finds something.
+    Lint(LintArgs),
+    /// Outline a local OpenAPI document without fetching anything. Writes to
+    /// stdout unless `--out` is given.
+    Outline(LocalInputArgs),
+    /// Reduce a local OpenAPI document to the `--reduce` keys (default
+    /// `paths,components`) without fetching anything. Writes to stdout unless
+    /// `--out` is given.
+    Reduce(LocalInputArgs),
+    /// Fetch and transform as usual, then compare with the existing `--out`
+    /// file instead of writing it. Exits 1 when the snapshot is out of date,
+    /// 10 with `--breaking` when the new document would break clients, and
+    /// 2 or more when they cannot be compared.
+    #[command(after_help = diff_after_help())]
+    Check(CheckArgs),
+    /// Summarize a document: path, operation, schema, and tag counts, after
+    /// any `--reduce`.
+    Stats(StatsArgs),
+    /// List every operation as `METHOD /path`, sorted by path and method.
+    Paths(PathsArgs),
+    /// Print one operation (`show GET /users/{id}`) or component schema
+    /// (`show schema User`).
+    Show(ShowArgs),
+    /// Combine several documents into one. Writes to stdout unless `--out`
+    /// is given.
+    Merge(MergeArgs),
+    /// Convert a local document between JSON and YAML. Formats come from
+    /// the file extensions unless `--from`/`--to` say otherwise.
+    Convert(ConvertArgs),
+    /// Print a ready-to-run curl command for one operation, with placeholder
+    /// values for parameters and request bodies without examples.
+    Curl(CurlArgs),
+    /// Print a sample payload for a component schema, honoring `example`
+    /// and `default` and synthesizing the rest.
+    Example(ExampleArgs),
+    /// Print a component schema with every local `$ref` expanded, ready for
+    /// a JSON Schema validator. Cycles stay as `$ref`s, with a warning.
+    Schema(SchemaArgs),
+    /// Drop components that nothing under `paths` references from a local
+    /// document and list what was removed. Writes to stdout unless `--out`
+    /// is given; `--dry-run` only prints the list.
+    Prune(LocalInputArgs),
+    /// Rewrite a JSON spec file in canonical form: sorted keys, operations
+    /// in specification order, fixed indentation, trailing newline.
+    ///
+    /// With `--check`, exits 1 instead of writing when the file is not
+    /// already normalized, and with `diff`'s codes when it fails.
+    Normalize(NormalizeArgs),
+    /// Find a word in paths, operationIds, summaries, descriptions, tags,
+    /// and schema and property names, ignoring case. Prints each hit as
+    /// `location: "text"`.
+    Search(SearchArgs),
+    /// Delete temp files left next to `--out` by interrupted writes, and
+    /// history entries beyond `--keep-last`/`--older-than`. Never touches
+    /// the snapshot itself or the newest history entry; `--dry-run` only
+    /// lists what would go.
+    Clean(CleanArgs),
 }
 
 #[derive(Args, Debug, Clone)]
-pub struct CommonArgs {
+pub struct CleanArgs {
+    /// Directory of `<stem>.<unix millis>.<ext>` history entries to prune.
+    #[arg(long, value_name = "DIR")]
+    pub history_dir: Option<PathBuf>,
+    /// Keep this many of the newest history entries.
+    #[arg(long, value_name = "N", requires = "history_dir", value_parser = clap::value_parser!(u64).range(1..))]
+    pub keep_last: Option<u64>,
+    /// Delete history entries older than this, e.g. `30d` or `12h`.
+    #[arg(long, value_name = "DURATION", requires = "history_dir")]
+    pub older_than: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SearchArgs {
+    /// Text to look for; a regular expression with `--regex`.
+    pub pattern: String,
+    /// OpenAPI JSON file, or `-` for stdin; defaults to `--input` or `--url`.
+    #[arg(id = "input_file", value_name = "INPUT")]
+    pub input: Option<PathBuf>,
+    /// Treat the pattern as a regular expression.
+    #[arg(long)]
+    pub regex: bool,
+    /// Limit the search to paths or schemas.
+    #[arg(long = "in", value_enum, default_value_t = SearchScope::All)]
+    pub scope: SearchScope,
+    /// Print the hits as a JSON array.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct NormalizeArgs {
+    /// JSON file to rewrite in place.
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+    /// Report whether the file is normalized without writing it.
+    #[arg(long)]
+    pub check: bool,
+    /// Spaces per indentation level; 0 writes a single line.
+    #[arg(long, value_name = "N", default_value_t = 2, value_parser = clap::value_parser!(u8).range(0..=8))]
+    pub indent: u8,
+    /// Also sort lists whose order carries no meaning: tags and schema
+    /// `required` lists.
+    #[arg(long)]
+    pub sort_arrays: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct SchemaArgs {
+    /// Name under `components.schemas`.
+    pub name: String,
+    /// OpenAPI JSON file, or `-` for stdin; defaults to `--input` or `--url`.
+    #[arg(id = "input_file", value_name = "INPUT")]
+    pub input: Option<PathBuf>,
+    /// Add a JSON Schema 2020-12 `$schema` keyword and move the schemas
+    /// that cycles still reference into `$defs`.
+    #[arg(long)]
+    pub json_schema_draft: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ExampleArgs {
+    /// Name under `components.schemas`.
+    pub schema: String,
+    /// OpenAPI JSON file, or `-` for stdin; defaults to `--input` or `--url`.
+    #[arg(id = "input_file", value_name = "INPUT")]
+    pub input: Option<PathBuf>,
+    /// Print an array of this many varied examples instead of one.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub count: Option<u16>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CurlArgs {
+    /// HTTP method.
+    pub method: String,
+    /// Path template, matched literally.
+    #[arg(id = "operation_path", value_name = "PATH")]
+    pub path: String,
+    /// OpenAPI JSON file, or `-` for stdin; defaults to `--input` or `--url`.
+    #[arg(id = "input_file", value_name = "INPUT")]
+    pub input: Option<PathBuf>,
+    /// Server to call instead of the document's first `servers` entry.
+    #[arg(long, value_name = "URL")]
+    pub base_url: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ConvertArgs {
+    /// Document to convert, or `-` for stdin.
+    #[arg(id = "input_file", value_name = "INPUT")]
+    pub input: PathBuf,
+    /// Input format; defaults to the input's extension, then to JSON with a
+    /// YAML fallback.
+    #[arg(long, value_enum)]
+    pub from: Option<DocFormat>,
+    /// Output format; defaults to the `--out` extension and is required when
+    /// writing to stdout.
+    #[arg(long, value_enum)]
+    pub to: Option<DocFormat>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct MergeArgs {
+    /// Documents to merge, in order: file paths, http(s) URLs, or `-`.
+    #[arg(value_name = "INPUT", num_args = 2.., required = true)]
+    pub inputs: Vec<String>,
+    /// Keep the first definition of a path and method defined twice.
+    #[arg(long, conflicts_with = "prefer_last")]
+    pub prefer_first: bool,
+    /// Keep the last definition of a path and method defined twice.
+    #[arg(long)]
+    pub prefer_last: bool,
+    /// Keep both of two differing components with the same name, suffixing
+    /// the later one with its service name.
+    #[arg(long)]
+    pub rename_collisions: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ShowArgs {
+    /// HTTP method, or `schema`.
+    #[arg(value_name = "METHOD|schema")]
+    pub kind: String,
+    /// Path template, matched literally, or schema name.
+    #[arg(id = "selector", value_name = "PATH|NAME")]
+    pub target: String,
+    /// OpenAPI JSON file, or `-` for stdin; defaults to `--input` or `--url`.
+    #[arg(id = "input_file", value_name = "INPUT")]
+    pub input: Option<PathBuf>,
+    /// Inline local `$ref`s one level deep, or fully with `--resolve=deep`.
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "shallow"
+    )]
+    pub resolve: Option<ResolveDepth>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct PathsArgs {
+    /// OpenAPI JSON file, or `-` for stdin; defaults to `--input` or `--url`.
+    #[arg(id = "input_file", value_name = "INPUT")]
+    pub input: Option<PathBuf>,
+    /// Add operationId and summary columns.
+    #[arg(long, conflicts_with = "json")]
+    pub long: bool,
+    /// Print the operations as a JSON array.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct StatsArgs {
+    /// OpenAPI JSON file, or `-` for stdin; defaults to `--input` or `--url`.
+    #[arg(id = "input_file", value_name = "INPUT")]
+    pub input: Option<PathBuf>,
+    /// Print the numbers as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CheckArgs {
+    /// Write the new snapshot instead of failing when it differs.
+    #[arg(long)]
+    pub update: bool,
+    /// Also list changes that can break existing clients, and exit 10 when
+    /// there are any.
+    #[arg(long)]
+    pub breaking: bool,
+    /// Which differences fail the check: `breaking`, `any`, or `none`.
+    #[arg(long, value_enum, value_name = "POLICY")]
+    pub fail_on: Option<FailOn>,
+}
+
+/// A local document for the subcommands that skip fetching.
+#[derive(Args, Debug, Clone)]
+pub struct LocalInputArgs {
+    /// OpenAPI JSON file, or `-` for stdin.
+    #[arg(id = "input_file", value_name = "INPUT")]
+    pub input: PathBuf,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ValidateArgs {
+    /// File path or http(s) URL to check; defaults to `--input` or `--url`.
+    pub source: Option<String>,
+    /// Also fail when there are more than this many warnings.
+    #[arg(long, value_name = "N")]
+    pub max_warnings: Option<usize>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LintArgs {
+    /// File path or http(s) URL to lint; defaults to `--input` or `--url`.
+    pub source: Option<String>,
+    /// Run only this rule. Repeatable; defaults to every rule.
+    #[arg(long, value_name = "RULE", value_parser = parse_rule_id)]
+    pub rule: Vec<String>,
+    /// Skip this rule. Repeatable.
+    #[arg(long, value_name = "RULE", value_parser = parse_rule_id)]
+    pub disable_rule: Vec<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DiffArgs {
+    /// Old document: a file path or an http(s) URL.
+    pub old: String,
+    /// New document: a file path or an http(s) URL.
+    pub new: String,
+    /// List only changes that can break existing clients: removed
+    /// operations, newly required inputs, narrowed types, and the like.
+    #[arg(long)]
+    pub breaking: bool,
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+    pub format: DiffFormat,
+    /// With `--format markdown`, list at most this many entries per section
+    /// and count the rest.
+    #[arg(long, value_name = "N")]
+    pub max_items: Option<usize>,
+    /// Which differences fail the command: `breaking`, `any`, or `none`.
+    /// Without it, any difference exits 1.
+    #[arg(long, value_enum, value_name = "POLICY")]
+    pub fail_on: Option<FailOn>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct InitArgs {
+    /// URL to put in the generated config instead of the default.
     #[arg(long)]
     pub url: Option<String>,
+    /// Output path to put in the generated config instead of the default.
     #[arg(long)]
     pub out: Option<PathBuf>,
+    /// Overwrite an existing openapi-snapshot.toml.
     #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ManArgs {
+    /// Write the page to this file instead of stdout.
+    #[arg(long, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CompletionsArgs {
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+/// Flags shared by every subcommand. They are global, so they are accepted
+/// before or after the subcommand name; subcommand arguments use other ids
+/// so they do not hide them.
+#[derive(Args, Debug, Clone)]
+pub struct CommonArgs {
+    /// OpenAPI URL. Repeat together with `--out` to watch several targets.
+    #[cfg_attr(not(feature = "http"), arg(hide = true))]
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_URL")]
+    pub url: Vec<String>,
+    /// Shorthand for `--url http://localhost:<PORT>/api-docs/openapi.json`.
+    #[cfg_attr(not(feature = "http"), arg(hide = true))]
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_PORT",
+        value_parser = clap::value_parser!(u16).range(1..),
+        conflicts_with_all = ["url", "input"]
+    )]
+    pub port: Option<u16>,
+    /// Document path used with the default host, `--port`, and port answers
+    /// at the URL prompt, instead of `/api-docs/openapi.json`.
+    #[cfg_attr(not(feature = "http"), arg(hide = true))]
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_PATH",
+        value_name = "PATH",
+        conflicts_with_all = ["url", "input"]
+    )]
+    pub path: Option<String>,
+    /// Read the OpenAPI document from a local file instead of fetching a URL.
+    /// `watch` then reacts to filesystem changes rather than polling.
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_INPUT",
+        value_name = "PATH",
+        conflicts_with = "url"
+    )]
+    pub input: Option<PathBuf>,
+    /// Output path. Repeat together with `--url` to watch several targets.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_OUT")]
+    pub out: Vec<PathBuf>,
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_OUTLINE_OUT")]
     pub outline_out: Option<PathBuf>,
-    #[arg(long)]
+    /// Write the temp file for each output here instead of next to it, e.g.
+    /// when the output directory refuses dot-files. Across filesystems the
+    /// file is copied next to the output and renamed from there.
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        env = "OPENAPI_SNAPSHOT_TEMP_DIR"
+    )]
+    pub temp_dir: Option<PathBuf>,
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_REDUCE")]
     pub reduce: Option<String>,
-    #[arg(long, value_enum, default_value_t = OutputProfile::Full)]
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_PROFILE",
+        value_enum,
+        default_value_t = OutputProfile::Full,
+    )]
     pub profile: OutputProfile,
     #[arg(
         long,
-        default_value_t = false,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_OUTLINE_COMPAT",
+        value_enum,
+        default_value_t = OutlineCompat::V2,
+    )]
+    pub outline_compat: OutlineCompat,
+    /// Expand at most this many levels of object schemas in the outline;
+    /// deeper inline objects are shown as "object".
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_OUTLINE_DEPTH",
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub outline_depth: Option<u64>,
+    /// Show refs in the outline by name (`User`) instead of by pointer
+    /// (`#/components/schemas/User`).
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_OUTLINE_SHORT_REFS")]
+    pub outline_short_refs: bool,
+    /// Keep each operation's summary and description in the outline.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_OUTLINE_DESCRIPTIONS")]
+    pub outline_descriptions: bool,
+    /// Skip non-query parameters and show responses without a content
+    /// schema as null instead of failing the outline.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_OUTLINE_LENIENT")]
+    pub outline_lenient: bool,
+    /// Write single-line JSON. Without `--minify` or `--pretty`, output is
+    /// minified only when the `--out` file name ends in `.min.json`.
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_MINIFY",
         default_missing_value = "true",
         num_args(0..=1),
         value_parser = clap::builder::BoolishValueParser::new()
     )]
-    pub minify: bool,
-    #[arg(long, default_value_t = 10_000)]
+    pub minify: Option<bool>,
+    /// Write indented JSON, even to a `.min.json` file.
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_PRETTY",
+        default_missing_value = "true",
+        num_args(0..=1),
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub pretty: Option<bool>,
+    /// Request timeout in milliseconds; `--timeout` takes a duration instead.
+    #[cfg_attr(not(feature = "http"), arg(hide = true))]
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_TIMEOUT_MS",
+        default_value_t = DEFAULT_TIMEOUT_MS
+    )]
     pub timeout_ms: u64,
-    #[arg(long)]
+    /// Request timeout as a duration, e.g. `10s` or `1m`.
+    #[cfg_attr(not(feature = "http"), arg(hide = true))]
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_TIMEOUT",
+        value_name = "DURATION",
+        conflicts_with = "timeout_ms"
+    )]
+    pub timeout: Option<String>,
+    /// Also read from `OPENAPI_SNAPSHOT_HEADER` (comma-separated) and
+    /// `OPENAPI_SNAPSHOT_HEADER_1`, `OPENAPI_SNAPSHOT_HEADER_2`, ...
+    #[cfg_attr(not(feature = "http"), arg(hide = true))]
+    #[arg(long, global = true)]
     pub header: Vec<String>,
-    #[arg(long)]
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_STDOUT")]
     pub stdout: bool,
+    /// Fetch and transform as usual, then report what would be written
+    /// instead of writing it. `watch` runs a single poll per target.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_DRY_RUN")]
+    pub dry_run: bool,
+    /// Print the effective configuration, after config files and
+    /// environment variables are applied, as JSON and exit. Credential
+    /// header values are masked.
+    #[arg(long, global = true)]
+    pub print_config: bool,
+    /// Run the `validate` checks on each fetched document and fail the
+    /// snapshot on errors; warnings are logged.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_VALIDATE")]
+    pub validate: bool,
+    /// When fetching fails with a network error, timeout, or HTTP 5xx and
+    /// the `--out` file already exists, keep it, warn, and exit with
+    /// `--fallback-exit-code` instead of failing.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_FALLBACK_TO_LAST")]
+    pub fallback_to_last: bool,
+    /// Exit code for a run that kept the existing snapshot.
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_FALLBACK_EXIT_CODE",
+        value_name = "CODE",
+        default_value_t = 0,
+        requires = "fallback_to_last"
+    )]
+    pub fallback_exit_code: u8,
+    /// Write a document with an empty `paths` object even when it would
+    /// replace a snapshot that has paths.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_ALLOW_EMPTY")]
+    pub allow_empty: bool,
+    /// Compare documents byte for byte in `diff`, `check`, and watch change
+    /// detection, so key order, number formatting (`1.0` vs `1`), and
+    /// whitespace count as changes.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_STRICT_BYTES")]
+    pub strict_bytes: bool,
+    /// Check that every local `$ref` in each fetched document resolves and
+    /// warn about the ones that do not.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_VERIFY_REFS")]
+    pub verify_refs: bool,
+    /// Like `--verify-refs`, but fail the snapshot (exit 3) when a `$ref`
+    /// does not resolve.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_STRICT_REFS")]
+    pub strict_refs: bool,
+    /// Change the severity of a `validate` check, e.g.
+    /// `operation-id-missing=error`; the level is `error`, `warning`,
+    /// `info`, or `off`. Repeatable.
+    #[arg(long, global = true, value_name = "CHECK=LEVEL", value_parser = parse_severity_override)]
+    pub severity: Vec<(String, Option<Severity>)>,
+    /// Fail (exit 6) before any other step when the document does not
+    /// declare this OpenAPI version.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        env = "OPENAPI_SNAPSHOT_REQUIRE_VERSION"
+    )]
+    pub require_version: Option<SpecVersion>,
+    /// Warn about paths in each fetched document that differ only in
+    /// placeholder names, a trailing slash, or case.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_CHECK_PATHS")]
+    pub check_paths: bool,
+    /// Warn about keys that appear more than once in an object of each
+    /// fetched JSON document, where the last one silently wins. Skipped for
+    /// documents over 64 MiB.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_DETECT_DUPLICATE_KEYS")]
+    pub detect_duplicate_keys: bool,
+    /// Like `--detect-duplicate-keys`, at any size, but fail the snapshot
+    /// (exit 6) on a duplicate key.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_STRICT_DUPLICATE_KEYS")]
+    pub strict_duplicate_keys: bool,
+    /// Check each `example` against its schema in `validate`, and fail a
+    /// snapshot (exit 6) on examples that do not match.
+    #[arg(long, global = true, env = "OPENAPI_SNAPSHOT_VALIDATE_EXAMPLES")]
+    pub validate_examples: bool,
+    /// Color added, removed, and modified lines in `diff`, `check`, and watch
+    /// change summaries.
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto, env = "OPENAPI_SNAPSHOT_COLOR")]
+    pub color: ColorChoice,
+    /// Log request details to stderr; repeat (`-vv`) to include request and
+    /// response headers, with credentials redacted.
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Only print errors to stderr.
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_QUIET",
+        conflicts_with = "verbose"
+    )]
+    pub quiet: bool,
+    /// Read flag defaults from this TOML file instead of
+    /// `./openapi-snapshot.toml` or `./.config/openapi-snapshot.toml`.
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_CONFIG",
+        value_name = "PATH"
+    )]
+    pub config: Option<PathBuf>,
+    /// Snapshot the config file's `[target.<NAME>]` section. Repeat to run
+    /// several in turn, or pass `all` for every section.
+    #[arg(
+        long,
+        global = true,
+        env = "OPENAPI_SNAPSHOT_TARGET",
+        value_name = "NAME",
+        value_delimiter = ','
+    )]
+    pub target: Vec<String>,
 }
 
 #[derive(Args, Debug, Clone)]
 pub struct WatchArgs {
-    #[arg(long, default_value_t = DEFAULT_INTERVAL_MS)]
+    /// Poll interval in milliseconds; `--interval` takes a duration instead.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_INTERVAL_MS", default_value_t = DEFAULT_INTERVAL_MS)]
     pub interval_ms: u64,
-    #[arg(long, default_value_t = false)]
+    /// Poll interval as a duration, e.g. `2s`, `5m`, or `1h30m`.
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_INTERVAL",
+        value_name = "DURATION",
+        conflicts_with = "interval_ms"
+    )]
+    pub interval: Option<String>,
+    /// Randomize each poll sleep by up to ± this many milliseconds and offset
+    /// the first poll, so processes started together do not poll in lockstep.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_JITTER_MS", default_value_t = 0)]
+    pub jitter_ms: u64,
+    /// Wait this long before the first poll, e.g. `5s` while the backend
+    /// starts.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_INITIAL_DELAY", value_name = "DURATION")]
+    pub initial_delay: Option<String>,
+    /// When the URL is the default, keep retrying connection failures for
+    /// this long before prompting for another URL.
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_PROMPT_GRACE",
+        value_name = "DURATION",
+        default_value = DEFAULT_PROMPT_GRACE,
+    )]
+    pub prompt_grace: String,
+    /// Ask for a URL again after one entered at the prompt fails this many
+    /// polls in a row (0 never asks again).
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_REPROMPT_AFTER",
+        value_name = "N",
+        default_value_t = DEFAULT_REPROMPT_AFTER,
+    )]
+    pub reprompt_after: u64,
+    /// Double the interval after every 10 polls without a change (up to
+    /// `--max-interval`) and return to `--interval` once a change is seen.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_ADAPTIVE", default_value_t = false)]
+    pub adaptive: bool,
+    /// Longest interval `--adaptive` stretches to.
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_MAX_INTERVAL",
+        value_name = "DURATION",
+        default_value = DEFAULT_MAX_INTERVAL,
+    )]
+    pub max_interval: String,
+    /// Upper bound for the retry delay while the server is unreachable.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_MAX_BACKOFF_MS", default_value_t = DEFAULT_MAX_BACKOFF_MS)]
+    pub max_backoff_ms: u64,
+    /// Skip the outline file that watch writes next to a full snapshot by
+    /// default.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_NO_OUTLINE", default_value_t = false)]
     pub no_outline: bool,
+    /// Timestamp format for watch log lines.
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_LOG_TIMESTAMPS",
+        value_enum,
+        default_value_t = LogTimestamps::Time,
+    )]
+    pub log_timestamps: LogTimestamps,
+    /// Print watch log lines without a timestamp prefix.
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_NO_TIMESTAMPS",
+        default_value_t = false,
+        conflicts_with = "log_timestamps"
+    )]
+    pub no_timestamps: bool,
+    /// Also append watch log lines, with full timestamps, to this file.
+    /// The file is reopened if it is rotated away.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_LOG_FILE", value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+    /// Log every poll, including ones where the snapshot did not change.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_LOG_EVERY_POLL", default_value_t = false)]
+    pub log_every_poll: bool,
+    /// Exit after this many polls instead of watching forever.
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_MAX_ITERATIONS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with = "exit_on_change",
+    )]
+    pub max_iterations: Option<u64>,
+    /// Poll once and exit; shorthand for `--max-iterations 1`.
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_ONCE",
+        default_value_t = false,
+        conflicts_with_all = ["max_iterations", "exit_on_change"],
+    )]
+    pub once: bool,
+    /// Exit 0 as soon as the spec differs from the existing snapshot (or the
+    /// first one taken), after writing the new snapshot.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_EXIT_ON_CHANGE", default_value_t = false)]
+    pub exit_on_change: bool,
+    /// Give up on `--exit-on-change` after this long, e.g. `2m` (exit code 5).
+    ///
+    /// Not named `--timeout`: that global flag already bounds each request.
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_CHANGE_TIMEOUT",
+        value_name = "DURATION",
+        requires = "exit_on_change"
+    )]
+    pub change_timeout: Option<String>,
+    /// Only write a changed snapshot after the same new content was seen on
+    /// this many consecutive polls (useful during rolling deploys).
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_STABLE_POLLS",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    pub stable_polls: u64,
+    /// Exit with the last error after this many consecutive failed polls
+    /// (0 keeps watching forever).
+    #[arg(long, env = "OPENAPI_SNAPSHOT_MAX_FAILURES", default_value_t = 0)]
+    pub max_failures: u64,
+    /// Shell command to run after each snapshot change. Receives
+    /// `SNAPSHOT_PATH` and `OUTLINE_PATH` in its environment.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_ON_CHANGE")]
+    pub on_change: Option<String>,
+    /// Poll at the configured interval even when the server's
+    /// Cache-Control max-age is longer.
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_IGNORE_CACHE_CONTROL",
+        default_value_t = false
+    )]
+    pub ignore_cache_control: bool,
+    /// Server-Sent Events endpoint that announces spec changes; each event
+    /// triggers an immediate fetch, and the interval becomes a heartbeat.
+    #[cfg_attr(not(feature = "http"), arg(hide = true))]
+    #[arg(long, env = "OPENAPI_SNAPSHOT_WATCH_EVENTS_URL", value_name = "URL")]
+    pub watch_events_url: Option<String>,
+    /// Append one JSON event per poll to this file, or to stdout with `-`.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_EVENTS", value_name = "PATH|-")]
+    pub events: Option<String>,
+    /// Rewrite this file with Prometheus metrics after every poll, e.g. for
+    /// node_exporter's textfile collector.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_METRICS_FILE", value_name = "PATH")]
+    pub metrics_file: Option<PathBuf>,
+    /// Prefix for the metric names in `--metrics-file`.
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_METRICS_PREFIX",
+        default_value = DEFAULT_METRICS_PREFIX,
+        requires = "metrics_file",
+    )]
+    pub metrics_prefix: String,
+    /// Show a desktop notification when the snapshot changes.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_NOTIFY", default_value_t = false)]
+    pub notify: bool,
+    /// Compare every poll against this file or URL, loaded once at startup,
+    /// and log how far the API has drifted from it.
+    #[arg(long, env = "OPENAPI_SNAPSHOT_BASELINE", value_name = "PATH|URL")]
+    pub baseline: Option<String>,
+    /// Rewrite the full list of changes from `--baseline` to this file after
+    /// each poll that changes it. On Unix, SIGUSR2 also writes it.
+    #[arg(
+        long,
+        env = "OPENAPI_SNAPSHOT_BASELINE_REPORT",
+        value_name = "PATH",
+        requires = "baseline"
+    )]
+    pub baseline_report: Option<PathBuf>,
+}
+
+/// Headers from the environment: `OPENAPI_SNAPSHOT_HEADER` split on commas,
+/// then `OPENAPI_SNAPSHOT_HEADER_1`, `_2`, ... up to the first unset number.
+/// A comma-separated piece without a `:` continues the previous header, so
+/// `Accept: a, b` stays one header.
+pub fn env_headers(var: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let mut headers: Vec<String> = Vec::new();
+    if let Some(list) = var("OPENAPI_SNAPSHOT_HEADER") {
+        for piece in list.split(',') {
+            if piece.trim().is_empty() {
+                continue;
+            }
+            match headers.last_mut() {
+                Some(last) if !piece.contains(':') => {
+                    last.push(',');
+                    last.push_str(piece.trim_end());
+                }
+                _ => headers.push(piece.trim().to_string()),
+            }
+        }
+    }
+    for index in 1.. {
+        let Some(value) = var(&format!("OPENAPI_SNAPSHOT_HEADER_{index}")) else {
+            break;
+        };
+        headers.push(value);
+    }
+    headers
 }
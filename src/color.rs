@@ -0,0 +1,107 @@
+//! ANSI color for change output. Every printer that colors text goes through
+//! [`Palette`], so `--color` and `NO_COLOR` are honored the same way
+//! everywhere.
+
+use std::io::IsTerminal;
+
+use crate::cli::ColorChoice;
+use crate::diff::{ChangeKind, SpecChange};
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether one output stream gets color. The default palette is plain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Palette {
+    enabled: bool,
+}
+
+impl Palette {
+    /// `auto` colors a terminal unless `NO_COLOR` is set; `always` and
+    /// `never` ignore both.
+    pub fn new(choice: ColorChoice, is_terminal: bool, no_color: bool) -> Self {
+        let enabled = match choice {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => is_terminal && !no_color,
+        };
+        Self { enabled }
+    }
+
+    pub fn stdout(choice: ColorChoice) -> Self {
+        Self::new(choice, std::io::stdout().is_terminal(), no_color_set())
+    }
+
+    pub fn stderr(choice: ColorChoice) -> Self {
+        Self::new(choice, std::io::stderr().is_terminal(), no_color_set())
+    }
+
+    pub fn enabled(self) -> bool {
+        self.enabled
+    }
+
+    /// Wraps `text` in the color for `kind`: green for additions, red for
+    /// removals, yellow for modifications.
+    pub fn paint(self, kind: ChangeKind, text: &str) -> String {
+        if !self.enabled {
+            return text.to_string();
+        }
+        let color = match kind {
+            ChangeKind::Added => GREEN,
+            ChangeKind::Removed => RED,
+            ChangeKind::Modified => YELLOW,
+        };
+        format!("{color}{text}{RESET}")
+    }
+
+    pub fn change(self, change: &SpecChange) -> String {
+        self.paint(change.kind, &change.to_string())
+    }
+}
+
+/// `NO_COLOR` disables color when set to anything but an empty string
+/// (<https://no-color.org>).
+fn no_color_set() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::Subject;
+
+    fn added() -> SpecChange {
+        SpecChange {
+            kind: ChangeKind::Added,
+            subject: Subject::Path("/users".to_string()),
+            changes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn auto_colors_a_terminal_unless_no_color_is_set() {
+        assert!(Palette::new(ColorChoice::Auto, true, false).enabled());
+        assert!(!Palette::new(ColorChoice::Auto, false, false).enabled());
+        assert!(!Palette::new(ColorChoice::Auto, true, true).enabled());
+    }
+
+    #[test]
+    fn explicit_choice_overrides_terminal_and_no_color() {
+        assert!(Palette::new(ColorChoice::Always, false, true).enabled());
+        assert!(!Palette::new(ColorChoice::Never, true, false).enabled());
+    }
+
+    #[test]
+    fn paints_changes_by_kind() {
+        let palette = Palette::new(ColorChoice::Always, false, false);
+        assert_eq!(
+            palette.change(&added()),
+            "\x1b[32madded: path /users\x1b[0m"
+        );
+        assert_eq!(palette.paint(ChangeKind::Removed, "x"), "\x1b[31mx\x1b[0m");
+        assert_eq!(palette.paint(ChangeKind::Modified, "x"), "\x1b[33mx\x1b[0m");
+        assert_eq!(Palette::default().change(&added()), "added: path /users");
+    }
+}
@@ -0,0 +1,260 @@
+//! A JSON tree for snapshots that are written as served, without any
+//! transform: strings borrow from the fetched body where they can and
+//! objects are sorted vectors instead of maps, so the tree takes a fraction
+//! of a [`serde_json::Value`]'s memory. Keys serialize sorted and a
+//! duplicate key keeps its last value, as with a `Value`, so the written
+//! bytes are the same.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::Number;
+
+use crate::version::VersionFeatures;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node<'a> {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(Cow<'a, str>),
+    Array(Vec<Node<'a>>),
+    /// Entries sorted by key, each key once.
+    Object(Vec<(Cow<'a, str>, Node<'a>)>),
+}
+
+impl<'a> Node<'a> {
+    /// Parses `bytes` as JSON, borrowing every string without escapes.
+    pub(crate) fn parse(bytes: &'a [u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(bytes)
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&Node<'a>> {
+        let Node::Object(entries) = self else {
+            return None;
+        };
+        entries
+            .binary_search_by(|(name, _)| name.as_ref().cmp(key))
+            .ok()
+            .map(|index| &entries[index].1)
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Node::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Whether this node or any below it satisfies `found`.
+    fn any(&self, found: &impl Fn(&Node<'a>) -> bool) -> bool {
+        found(self)
+            || match self {
+                Node::Array(items) => items.iter().any(|item| item.any(found)),
+                Node::Object(entries) => entries.iter().any(|(_, child)| child.any(found)),
+                _ => false,
+            }
+    }
+}
+
+impl VersionFeatures for Node<'_> {
+    fn top_level_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(Node::as_str)
+    }
+
+    fn has_top_level(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn components_have_defs(&self) -> bool {
+        self.get("components")
+            .is_some_and(|components| components.any(&|node| node.get("$defs").is_some()))
+    }
+
+    fn has_type_array(&self) -> bool {
+        self.any(&|node| matches!(node.get("type"), Some(Node::Array(_))))
+    }
+}
+
+impl Serialize for Node<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Node::Null => serializer.serialize_unit(),
+            Node::Bool(value) => serializer.serialize_bool(*value),
+            Node::Number(number) => number.serialize(serializer),
+            Node::String(text) => serializer.serialize_str(text),
+            Node::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Node::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key.as_ref(), value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Node<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+/// An object key, borrowed when it has no escapes. (`Cow<str>`'s own
+/// `Deserialize` always copies.)
+struct Key<'a>(Cow<'a, str>);
+
+impl<'de> Deserialize<'de> for Key<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer
+            .deserialize_str(NodeVisitor)
+            .and_then(|node| match node {
+                Node::String(text) => Ok(Key(text)),
+                _ => Err(de::Error::custom("expected a string key")),
+            })
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Node<'de>, E> {
+        Ok(Node::Null)
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Node<'de>, E> {
+        Ok(Node::Bool(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Node<'de>, E> {
+        Ok(Node::Number(value.into()))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Node<'de>, E> {
+        Ok(Node::Number(value.into()))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Node<'de>, E> {
+        Ok(Number::from_f64(value).map_or(Node::Null, Node::Number))
+    }
+
+    fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Node<'de>, E> {
+        Ok(Node::String(Cow::Borrowed(value)))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Node<'de>, E> {
+        Ok(Node::String(Cow::Owned(value.to_string())))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Node<'de>, E> {
+        Ok(Node::String(Cow::Owned(value)))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node<'de>, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        items.shrink_to_fit();
+        Ok(Node::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node<'de>, A::Error> {
+        let mut entries = Vec::new();
+        while let Some((Key(key), value)) = map.next_entry()? {
+            entries.push((key, value));
+        }
+        // Reversed, a stable sort puts the last of equal keys first, and
+        // dedup keeps it.
+        entries.reverse();
+        entries.sort_by(|a: &(Cow<'de, str>, Node<'de>), b| a.0.cmp(&b.0));
+        entries.dedup_by(|later, kept| later.0 == kept.0);
+        entries.shrink_to_fit();
+        Ok(Node::Object(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::serialize_json;
+    use crate::version::{SpecVersion, version_notes, version_notes_in};
+    use serde_json::Value;
+
+    /// Serializes `text` through a [`Node`] and through a [`Value`].
+    fn both(text: &str, minify: bool) -> (String, String) {
+        let node = Node::parse(text.as_bytes()).unwrap();
+        let value: Value = serde_json::from_str(text).unwrap();
+        (
+            serialize_json(&node, minify).unwrap(),
+            serialize_json(&value, minify).unwrap(),
+        )
+    }
+
+    #[test]
+    fn serializes_exactly_like_a_value() {
+        let text = r#"{
+            "z": 1, "a": [1.0, -2, 1e2, 18446744073709551615, 0.1, true, null],
+            "m": {"bé": "café \"quoted\" \/ \n", "": {}, "empty": []},
+            "dup": 1, "dup": {"last": "wins"}, "A": "upper"
+        }"#;
+        for minify in [false, true] {
+            let (node, value) = both(text, minify);
+            assert_eq!(node, value);
+        }
+        let (node, _) = both(text, true);
+        assert!(node.starts_with(r#"{"A":"upper","a":[1.0,-2,100.0,"#));
+        assert!(node.contains(r#""dup":{"last":"wins"}"#));
+    }
+
+    #[test]
+    fn unescaped_strings_borrow_from_the_input() {
+        let text = r#"{"plain": "text", "escaped": "a\nb"}"#;
+        let node = Node::parse(text.as_bytes()).unwrap();
+        assert!(matches!(
+            node.get("plain"),
+            Some(Node::String(Cow::Borrowed("text")))
+        ));
+        assert!(matches!(
+            node.get("escaped"),
+            Some(Node::String(Cow::Owned(_)))
+        ));
+        assert_eq!(node.get("missing"), None);
+    }
+
+    #[test]
+    fn version_features_match_the_value_checks() {
+        for text in [
+            r#"{"openapi": "3.1.0", "webhooks": {}, "components": {"schemas": {"T": {"$defs": {}}}}}"#,
+            r#"{"openapi": "3.0.3", "paths": {"/a": {"get": {"x": {"type": ["string", "null"]}}}}}"#,
+            r#"{"openapi": 3.1, "swagger": "2.0"}"#,
+            r#"{"swagger": "2.0"}"#,
+            r#"{"paths": {}}"#,
+        ] {
+            let node = Node::parse(text.as_bytes()).unwrap();
+            let value: Value = serde_json::from_str(text).unwrap();
+            let version = SpecVersion::detect(&value);
+            assert_eq!(SpecVersion::detect_in(&node), version, "{text}");
+            assert_eq!(
+                version_notes_in(&node, version),
+                version_notes(&value, version),
+                "{text}"
+            );
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::io::Write;
+
+use clap::CommandFactory;
+use clap_complete::{Shell, generate};
+
+use crate::cli::Cli;
+use crate::errors::AppError;
+
+/// Writes the completion script for `shell`. It is generated from the clap
+/// definition, so new flags and subcommands are picked up automatically.
+pub fn write_completions(shell: Shell, out: &mut impl Write) -> Result<(), AppError> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    let mut script = Vec::new();
+    generate(shell, &mut command, name, &mut script);
+    out.write_all(&script)
+        .and_then(|()| out.flush())
+        .map_err(|err| AppError::io(format!("failed to write completions: {err}")).with_source(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_shell_mentions_flags_and_subcommands() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut out = Vec::new();
+            write_completions(shell, &mut out).unwrap();
+            let script = String::from_utf8(out).unwrap();
+            assert!(script.contains("outline-out"), "{shell}");
+            assert!(script.contains("interval-ms"), "{shell}");
+            assert!(script.contains("watch"), "{shell}");
+        }
+    }
+}
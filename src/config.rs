@@ -1,11 +1,32 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize, Serializer};
+use url::Url;
+
+use crate::clean::Retention;
 use crate::cli::{
-    Cli, Command, DEFAULT_OUT, DEFAULT_OUTLINE_OUT, DEFAULT_REDUCE, DEFAULT_URL, OutputProfile,
+    CleanArgs, Cli, ColorChoice, Command, CommonArgs, DEFAULT_OUT, DEFAULT_OUTLINE_OUT,
+    DEFAULT_PATH, DEFAULT_PORT, DEFAULT_REDUCE, DiffFormat, DocFormat, FailOn, LogTimestamps,
+    OutputProfile, ResolveDepth, SearchScope,
 };
+use crate::duration::{duration_ms, parse_duration, parse_positive_duration};
 use crate::errors::AppError;
+use crate::events::EventsTarget;
+use crate::fetch::mask_header;
+use crate::lint::LintOptions;
+use crate::merge::PathConflict;
+use crate::metrics::validate_prefix;
+use crate::normalize::NormalizeOptions;
+use crate::outline::OutlineOptions;
+use crate::show::Selector;
+use crate::transform::Pipeline;
+use crate::validate::{Severity, ValidateOptions};
+use crate::verbosity::Verbosity;
+use crate::version::SpecVersion;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum ReduceKey {
     Paths,
     Components,
@@ -20,41 +41,502 @@ impl ReduceKey {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Mode {
     Snapshot,
-    Watch { interval_ms: u64 },
+    Watch(Box<WatchOptions>),
+    /// Compare two documents, each a file path or URL, reporting only
+    /// breaking changes when `breaking` is set.
+    Diff {
+        old: String,
+        new: String,
+        breaking: bool,
+        format: DiffFormat,
+        max_items: Option<usize>,
+        fail_on: Option<FailOn>,
+    },
+    /// Check one document, or the configured source when `source` is unset.
+    Validate {
+        source: Option<String>,
+        max_warnings: Option<usize>,
+    },
+    /// Run the [`crate::lint`] rules on one document, or the configured
+    /// source when `source` is unset.
+    Lint {
+        source: Option<String>,
+        options: LintOptions,
+    },
+    /// Compare fresh outputs with the files on disk, rewriting them when
+    /// `update` is set and also looking for breaking changes when
+    /// `breaking` is set. `fail_on` decides which differences fail.
+    Check {
+        update: bool,
+        breaking: bool,
+        fail_on: Option<FailOn>,
+    },
+    /// Print document statistics, as JSON when `json` is set.
+    Stats {
+        json: bool,
+    },
+    /// List operations, with extra columns when `long` is set.
+    Paths {
+        long: bool,
+        json: bool,
+    },
+    /// Print one operation or schema.
+    Show {
+        selector: Selector,
+        resolve: Option<ResolveDepth>,
+    },
+    /// Merge several documents into one.
+    Merge {
+        inputs: Vec<String>,
+        paths_conflict: PathConflict,
+        rename_collisions: bool,
+    },
+    /// Convert `--input` between JSON and YAML.
+    Convert {
+        from: Option<DocFormat>,
+        to: Option<DocFormat>,
+    },
+    /// Print a curl command for one operation.
+    Curl {
+        method: String,
+        path: String,
+        base_url: Option<String>,
+    },
+    /// Print a sample payload for one schema.
+    Example {
+        schema: String,
+        count: Option<usize>,
+    },
+    /// Print one fully resolved schema.
+    Schema {
+        name: String,
+        json_schema_draft: bool,
+    },
+    /// Remove unused components from a local document.
+    Prune,
+    /// Rewrite a file in canonical form.
+    Normalize {
+        file: PathBuf,
+        options: NormalizeOptions,
+        check: bool,
+    },
+    /// Delete leftover temp files and expired history entries.
+    Clean {
+        history: Option<(PathBuf, Retention)>,
+    },
+    Search {
+        pattern: String,
+        regex: bool,
+        scope: SearchScope,
+        json: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchOptions {
+    pub interval_ms: u64,
+    pub jitter_ms: u64,
+    pub initial_delay_ms: u64,
+    pub prompt_grace_ms: u64,
+    pub reprompt_after: u64,
+    pub adaptive: bool,
+    pub max_interval_ms: u64,
+    pub max_backoff_ms: u64,
+    pub log_every_poll: bool,
+    pub max_iterations: Option<u64>,
+    pub max_failures: u64,
+    pub stable_polls: u64,
+    pub exit_on_change: bool,
+    pub change_timeout_ms: Option<u64>,
+    pub on_change: Option<String>,
+    pub notify: bool,
+    pub ignore_cache_control: bool,
+    pub sse_url: Option<String>,
+    pub events: Option<EventsTarget>,
+    pub metrics_file: Option<PathBuf>,
+    pub metrics_prefix: String,
+    pub timestamps: Option<LogTimestamps>,
+    pub log_file: Option<PathBuf>,
+    /// `--baseline`: a file or URL every poll is compared against.
+    pub baseline: Option<String>,
+    pub baseline_report: Option<PathBuf>,
 }
 
-#[derive(Debug)]
+/// Serializes with credential header values masked, so a dump can be kept
+/// next to the snapshot; see [`Config::to_json`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Label used in logs when several targets are watched together.
+    pub name: Option<String>,
     pub url: String,
     pub url_from_default: bool,
+    /// Path appended to localhost URLs built from a port (`--path`).
+    pub doc_path: String,
+    /// Local file read instead of fetching `url`.
+    pub input: Option<PathBuf>,
     pub out: Option<PathBuf>,
     pub outline_out: Option<PathBuf>,
+    /// Where written files are staged before being moved into place; next
+    /// to each file when unset.
+    pub temp_dir: Option<PathBuf>,
     pub reduce: Vec<ReduceKey>,
     pub profile: OutputProfile,
+    pub outline: OutlineOptions,
     pub minify: bool,
     pub timeout_ms: u64,
+    #[serde(serialize_with = "serialize_headers")]
     pub headers: Vec<String>,
     pub stdout: bool,
+    pub verbosity: Verbosity,
+    /// Run the structural checks from [`crate::validate`] on each fetch.
+    pub validate: bool,
+    pub color: ColorChoice,
+    /// With `--fallback-to-last`, the exit code for a run that kept the
+    /// existing snapshot after a transient fetch failure.
+    pub fallback_to_last: Option<i32>,
+    /// Allow replacing a snapshot that has paths with one that has none.
+    pub allow_empty: bool,
+    /// Compare documents byte for byte instead of in canonical form; see
+    /// [`crate::normalize::canonicalize`].
+    pub strict_bytes: bool,
+    /// Check that every local `$ref` resolves on each fetch, reporting
+    /// broken ones at this severity; see [`crate::refs`].
+    pub verify_refs: Option<Severity>,
+    /// Severities for the checks `validate` and `--validate` run.
+    pub validate_options: ValidateOptions,
+    /// Fail before any other step when the document declares a different
+    /// OpenAPI version; see [`crate::version`].
+    pub require_version: Option<SpecVersion>,
+    /// Warn about paths that collide; see [`crate::validate::path_collisions`].
+    pub check_paths: bool,
+    /// Scan each fetched JSON body for keys that appear twice in an object,
+    /// reporting them at this severity; see [`crate::duplicates`].
+    pub duplicate_keys: Option<Severity>,
+    /// The program's own steps, run before the profile's; see
+    /// [`crate::transform`]. The command line adds none. Not serialized.
+    #[serde(skip)]
+    pub transforms: Pipeline,
 }
 
 impl Config {
+    /// The URL or input file the document is read from, for logs and events.
+    pub fn source(&self) -> String {
+        match &self.input {
+            Some(path) => path.display().to_string(),
+            None => self.url.clone(),
+        }
+    }
+
+    /// This configuration as pretty-printed JSON, the shape [`Config`]
+    /// deserializes from. Credential header values are written as `***`.
+    pub fn to_json(&self) -> Result<String, AppError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|err| AppError::json(format!("json error: {err}")).with_source(err))
+    }
+
+    /// Builds the single-target configuration; use [`Config::targets_from_cli`]
+    /// when repeated `--url`/`--out` pairs are allowed.
     pub fn from_cli(cli: Cli) -> Result<(Self, Mode), AppError> {
+        let (targets, mode) = Self::targets_from_cli(cli)?;
+        match <[Self; 1]>::try_from(targets) {
+            Ok([config]) => Ok((config, mode)),
+            Err(_) => Err(AppError::usage(
+                "expected a single --url/--out pair.".to_string(),
+            )),
+        }
+    }
+
+    pub fn targets_from_cli(mut cli: Cli) -> Result<(Vec<Self>, Mode), AppError> {
         let (mode, no_outline) = match cli.command {
+            Some(
+                Command::Completions(_)
+                | Command::Man(_)
+                | Command::Init(_)
+                | Command::Login
+                | Command::Logout,
+            ) => {
+                return Err(AppError::usage(
+                    "this subcommand does not take snapshot options.".to_string(),
+                ));
+            }
             Some(Command::Watch(args)) => (
-                Mode::Watch {
-                    interval_ms: args.interval_ms,
-                },
+                Mode::Watch(Box::new(WatchOptions {
+                    interval_ms: match &args.interval {
+                        Some(value) => duration_ms(parse_duration("--interval", value)?),
+                        None => args.interval_ms,
+                    },
+                    jitter_ms: args.jitter_ms,
+                    initial_delay_ms: match &args.initial_delay {
+                        Some(value) => duration_ms(parse_duration("--initial-delay", value)?),
+                        None => 0,
+                    },
+                    prompt_grace_ms: duration_ms(parse_duration(
+                        "--prompt-grace",
+                        &args.prompt_grace,
+                    )?),
+                    reprompt_after: args.reprompt_after,
+                    adaptive: args.adaptive,
+                    max_interval_ms: duration_ms(parse_positive_duration(
+                        "--max-interval",
+                        &args.max_interval,
+                    )?),
+                    max_backoff_ms: args.max_backoff_ms,
+                    log_every_poll: args.log_every_poll,
+                    max_iterations: if args.once {
+                        Some(1)
+                    } else {
+                        args.max_iterations
+                    },
+                    max_failures: args.max_failures,
+                    stable_polls: args.stable_polls,
+                    exit_on_change: args.exit_on_change,
+                    change_timeout_ms: args
+                        .change_timeout
+                        .as_deref()
+                        .map(|value| parse_positive_duration("--change-timeout", value))
+                        .transpose()?
+                        .map(duration_ms),
+                    on_change: args.on_change,
+                    notify: args.notify,
+                    ignore_cache_control: args.ignore_cache_control,
+                    sse_url: args.watch_events_url,
+                    events: args.events.as_deref().map(EventsTarget::parse),
+                    metrics_file: args.metrics_file,
+                    metrics_prefix: {
+                        validate_prefix(&args.metrics_prefix)?;
+                        args.metrics_prefix
+                    },
+                    timestamps: (!args.no_timestamps).then_some(args.log_timestamps),
+                    log_file: args.log_file,
+                    baseline: args.baseline,
+                    baseline_report: args.baseline_report,
+                })),
                 args.no_outline,
             ),
+            Some(Command::Diff(args)) => {
+                if args.breaking && args.format != DiffFormat::Text {
+                    let format = args
+                        .format
+                        .to_possible_value()
+                        .map(|value| value.get_name().to_string())
+                        .unwrap_or_default();
+                    return Err(AppError::usage(format!(
+                        "--breaking lists findings and cannot be combined with --format {format}."
+                    )));
+                }
+                (
+                    Mode::Diff {
+                        old: args.old,
+                        new: args.new,
+                        breaking: args.breaking,
+                        format: args.format,
+                        max_items: args.max_items,
+                        fail_on: args.fail_on,
+                    },
+                    false,
+                )
+            }
+            Some(Command::Validate(args)) => (
+                Mode::Validate {
+                    source: args.source,
+                    max_warnings: args.max_warnings,
+                },
+                false,
+            ),
+            Some(Command::Lint(args)) => (
+                Mode::Lint {
+                    source: args.source,
+                    options: LintOptions {
+                        rules: args.rule,
+                        disabled: args.disable_rule,
+                    },
+                },
+                false,
+            ),
+            Some(Command::Outline(args)) => {
+                use_local_input(&mut cli.common, args.input);
+                cli.common.profile = OutputProfile::Outline;
+                (Mode::Snapshot, false)
+            }
+            Some(Command::Reduce(args)) => {
+                use_local_input(&mut cli.common, args.input);
+                cli.common
+                    .reduce
+                    .get_or_insert_with(|| DEFAULT_REDUCE.to_string());
+                (Mode::Snapshot, false)
+            }
+            Some(Command::Check(args)) => {
+                if cli.common.stdout {
+                    return Err(AppError::usage(
+                        "check compares against --out and cannot be combined with --stdout."
+                            .to_string(),
+                    ));
+                }
+                (
+                    Mode::Check {
+                        update: args.update,
+                        breaking: args.breaking,
+                        fail_on: args.fail_on,
+                    },
+                    false,
+                )
+            }
+            Some(Command::Stats(args)) => {
+                if let Some(input) = args.input {
+                    cli.common.input = Some(input);
+                }
+                cli.common.profile = OutputProfile::Full;
+                (Mode::Stats { json: args.json }, false)
+            }
+            Some(Command::Paths(args)) => {
+                if let Some(input) = args.input {
+                    cli.common.input = Some(input);
+                }
+                cli.common.profile = OutputProfile::Full;
+                (
+                    Mode::Paths {
+                        long: args.long,
+                        json: args.json,
+                    },
+                    false,
+                )
+            }
+            Some(Command::Show(args)) => {
+                if let Some(input) = args.input {
+                    cli.common.input = Some(input);
+                }
+                cli.common.profile = OutputProfile::Full;
+                (
+                    Mode::Show {
+                        selector: Selector::parse(&args.kind, &args.target)?,
+                        resolve: args.resolve,
+                    },
+                    false,
+                )
+            }
+            Some(Command::Merge(args)) => {
+                if cli.common.out.is_empty() {
+                    cli.common.stdout = true;
+                }
+                let paths_conflict = if args.prefer_first {
+                    PathConflict::PreferFirst
+                } else if args.prefer_last {
+                    PathConflict::PreferLast
+                } else {
+                    PathConflict::Error
+                };
+                (
+                    Mode::Merge {
+                        inputs: args.inputs,
+                        paths_conflict,
+                        rename_collisions: args.rename_collisions,
+                    },
+                    false,
+                )
+            }
+            Some(Command::Convert(args)) => {
+                use_local_input(&mut cli.common, args.input);
+                (
+                    Mode::Convert {
+                        from: args.from,
+                        to: args.to,
+                    },
+                    false,
+                )
+            }
+            Some(Command::Curl(args)) => {
+                if let Some(input) = args.input {
+                    cli.common.input = Some(input);
+                }
+                cli.common.profile = OutputProfile::Full;
+                (
+                    Mode::Curl {
+                        method: args.method,
+                        path: args.path,
+                        base_url: args.base_url,
+                    },
+                    false,
+                )
+            }
+            Some(Command::Example(args)) => {
+                if let Some(input) = args.input {
+                    cli.common.input = Some(input);
+                }
+                cli.common.profile = OutputProfile::Full;
+                (
+                    Mode::Example {
+                        schema: args.schema,
+                        count: args.count.map(usize::from),
+                    },
+                    false,
+                )
+            }
+            Some(Command::Schema(args)) => {
+                if let Some(input) = args.input {
+                    cli.common.input = Some(input);
+                }
+                cli.common.profile = OutputProfile::Full;
+                (
+                    Mode::Schema {
+                        name: args.name,
+                        json_schema_draft: args.json_schema_draft,
+                    },
+                    false,
+                )
+            }
+            Some(Command::Prune(args)) => {
+                use_local_input(&mut cli.common, args.input);
+                cli.common.profile = OutputProfile::Full;
+                (Mode::Prune, false)
+            }
+            Some(Command::Normalize(args)) => (
+                Mode::Normalize {
+                    file: args.file,
+                    options: NormalizeOptions {
+                        indent: usize::from(args.indent),
+                        sort_arrays: args.sort_arrays,
+                    },
+                    check: args.check,
+                },
+                false,
+            ),
+            Some(Command::Search(args)) => {
+                if let Some(input) = args.input {
+                    cli.common.input = Some(input);
+                }
+                cli.common.profile = OutputProfile::Full;
+                (
+                    Mode::Search {
+                        pattern: args.pattern,
+                        regex: args.regex,
+                        scope: args.scope,
+                        json: args.json,
+                    },
+                    false,
+                )
+            }
+            Some(Command::Clean(args)) => (
+                Mode::Clean {
+                    history: clean_history(args)?,
+                },
+                false,
+            ),
             None => (Mode::Snapshot, false),
         };
 
-        let reduce_value = match (&cli.common.reduce, mode, cli.common.profile) {
+        if no_outline && cli.common.outline_out.is_some() {
+            return Err(AppError::usage(
+                "--no-outline cannot be combined with --outline-out.".to_string(),
+            ));
+        }
+
+        let reduce_value = match (&cli.common.reduce, &mode, cli.common.profile) {
             (Some(value), _, _) => Some(value.as_str()),
-            (None, Mode::Watch { .. }, OutputProfile::Full) => Some(DEFAULT_REDUCE),
+            (None, Mode::Watch(_), OutputProfile::Full) => Some(DEFAULT_REDUCE),
             _ => None,
         };
         let reduce = match reduce_value {
@@ -62,67 +544,385 @@ impl Config {
             None => Vec::new(),
         };
 
-        let url_from_default = cli.common.url.is_none();
-        let url = cli.common.url.unwrap_or_else(|| DEFAULT_URL.to_string());
-        let out = if cli.common.stdout {
-            cli.common.out
-        } else {
-            Some(cli.common.out.unwrap_or_else(|| PathBuf::from(DEFAULT_OUT)))
+        let timeout_ms = match &cli.common.timeout {
+            Some(value) => duration_ms(parse_positive_duration("--timeout", value)?),
+            None => cli.common.timeout_ms,
         };
-        let outline_out = if cli.common.stdout {
-            None
-        } else {
-            match cli.common.outline_out {
-                Some(path) => Some(path),
-                None => match (mode, cli.common.profile, no_outline) {
-                    (Mode::Watch { .. }, OutputProfile::Full, false) => {
-                        Some(PathBuf::from(DEFAULT_OUTLINE_OUT))
-                    }
-                    _ => None,
-                },
+
+        if cli.common.stdout
+            && let Mode::Watch(options) = &mode
+            && options.events == Some(EventsTarget::Stdout)
+        {
+            return Err(AppError::usage(
+                "--events - cannot be combined with --stdout; write events to a file instead."
+                    .to_string(),
+            ));
+        }
+
+        let doc_path = match &cli.common.path {
+            Some(path) if !cli.common.url.is_empty() && cli.common.port.is_none() => {
+                return Err(AppError::usage(format!(
+                    "--path {path} cannot be combined with --url; put the path in the URL instead."
+                )));
             }
+            Some(path) => normalize_doc_path(path)?,
+            None => DEFAULT_PATH.to_string(),
+        };
+        let outline = outline_options(&cli.common);
+        // clap rejects `--port` next to `--url`, so a URL here came from the
+        // config file and the explicit port wins.
+        let urls = match cli.common.port {
+            Some(port) => vec![localhost_url(port, &doc_path)],
+            None => cli.common.url,
         };
+        let pairs = pair_targets(urls, cli.common.out)?;
+        let multi = pairs.len() > 1;
+        if multi && cli.common.input.is_some() {
+            return Err(AppError::usage(
+                "--input supports a single --out.".to_string(),
+            ));
+        }
+        if multi && cli.common.stdout {
+            return Err(AppError::usage(
+                "--stdout cannot be combined with multiple --url values.".to_string(),
+            ));
+        }
+        if multi && cli.common.outline_out.is_some() {
+            return Err(AppError::usage(
+                "--outline-out cannot be combined with multiple --url values; outline paths are derived from each --out.".to_string(),
+            ));
+        }
 
-        Ok((
-            Self {
+        let minify_choice = explicit_minify(cli.common.minify, cli.common.pretty)?;
+
+        let default_outline = matches!(
+            (&mode, cli.common.profile, no_outline),
+            (
+                Mode::Watch(_) | Mode::Clean { .. },
+                OutputProfile::Full,
+                false
+            )
+        );
+
+        let mut targets = Vec::with_capacity(pairs.len());
+        for (url, out) in pairs {
+            let url_from_default = url.is_none() && cli.common.input.is_none();
+            let url = url.unwrap_or_else(|| localhost_url(DEFAULT_PORT, &doc_path));
+            let out = if cli.common.stdout {
+                out
+            } else {
+                Some(out.unwrap_or_else(|| PathBuf::from(DEFAULT_OUT)))
+            };
+            // An explicit --outline-out is kept with --stdout so that
+            // validate_config can reject the combination.
+            let outline_out = if let Some(path) = cli.common.outline_out.clone() {
+                Some(path)
+            } else if cli.common.stdout || !default_outline {
+                None
+            } else if multi {
+                out.as_deref().map(derived_outline_path)
+            } else {
+                Some(PathBuf::from(DEFAULT_OUTLINE_OUT))
+            };
+            let minify = minify_output(minify_choice, out.as_deref());
+            let name = if multi {
+                out.as_deref().map(target_name)
+            } else {
+                None
+            };
+
+            targets.push(Self {
+                name,
                 url,
                 url_from_default,
+                doc_path: doc_path.clone(),
+                input: cli.common.input.clone(),
                 out,
                 outline_out,
-                reduce,
+                temp_dir: cli.common.temp_dir.clone(),
+                reduce: reduce.clone(),
                 profile: cli.common.profile,
-                minify: cli.common.minify,
-                timeout_ms: cli.common.timeout_ms,
-                headers: cli.common.header,
+                outline: outline.clone(),
+                minify,
+                timeout_ms,
+                headers: cli.common.header.clone(),
                 stdout: cli.common.stdout,
-            },
-            mode,
-        ))
+                verbosity: Verbosity::from_flags(cli.common.verbose, cli.common.quiet),
+                validate: cli.common.validate,
+                color: cli.common.color,
+                fallback_to_last: cli
+                    .common
+                    .fallback_to_last
+                    .then_some(i32::from(cli.common.fallback_exit_code)),
+                allow_empty: cli.common.allow_empty,
+                strict_bytes: cli.common.strict_bytes,
+                verify_refs: match (cli.common.verify_refs, cli.common.strict_refs) {
+                    (_, true) => Some(Severity::Error),
+                    (true, false) => Some(Severity::Warning),
+                    (false, false) => None,
+                },
+                validate_options: ValidateOptions {
+                    severities: cli.common.severity.iter().cloned().collect(),
+                    examples: cli.common.validate_examples,
+                },
+                require_version: cli.common.require_version,
+                check_paths: cli.common.check_paths,
+                duplicate_keys: match (
+                    cli.common.detect_duplicate_keys,
+                    cli.common.strict_duplicate_keys,
+                ) {
+                    (_, true) => Some(Severity::Error),
+                    (true, false) => Some(Severity::Warning),
+                    (false, false) => None,
+                },
+                transforms: Pipeline::default(),
+            });
+        }
+
+        Ok((targets, mode))
     }
 }
 
-pub fn validate_config(config: &Config) -> Result<(), AppError> {
+/// What `--minify` and `--pretty` ask for together, if anything. They may
+/// both be given as long as they agree (`--minify --pretty false`).
+fn explicit_minify(minify: Option<bool>, pretty: Option<bool>) -> Result<Option<bool>, AppError> {
+    match (minify, pretty) {
+        (Some(minify), Some(pretty)) if minify == pretty => Err(AppError::usage(format!(
+            "--minify {minify} contradicts --pretty {pretty}; pass only one of them."
+        ))),
+        (Some(minify), _) => Ok(Some(minify)),
+        (None, pretty) => Ok(pretty.map(|pretty| !pretty)),
+    }
+}
+
+/// The explicit choice, or else minified exactly when the output file name
+/// ends in `.min.json`. The outline follows the same setting.
+pub(crate) fn minify_output(explicit: Option<bool>, out: Option<&Path>) -> bool {
+    explicit.unwrap_or_else(|| {
+        out.and_then(Path::file_name)
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(".min.json"))
+    })
+}
+
+/// Points a local-file subcommand at its input, printing to stdout unless
+/// `--out` was given.
+fn use_local_input(common: &mut CommonArgs, input: PathBuf) {
+    common.input = Some(input);
+    if common.out.is_empty() {
+        common.stdout = true;
+    }
+}
+
+type TargetPair = (Option<String>, Option<PathBuf>);
+
+/// Zips repeated `--url`/`--out` occurrences in order. A single pair may omit
+/// either side and fall back to the defaults.
+fn pair_targets(urls: Vec<String>, outs: Vec<PathBuf>) -> Result<Vec<TargetPair>, AppError> {
+    if urls.len() <= 1 && outs.len() <= 1 {
+        return Ok(vec![(urls.into_iter().next(), outs.into_iter().next())]);
+    }
+    if urls.len() != outs.len() {
+        return Err(AppError::usage(format!(
+            "each --url needs a matching --out (got {} --url and {} --out).",
+            urls.len(),
+            outs.len()
+        )));
+    }
+    Ok(urls
+        .into_iter()
+        .zip(outs)
+        .map(|(url, out)| (Some(url), Some(out)))
+        .collect())
+}
+
+/// Outline settings from the `--outline-*` flags.
+fn outline_options(common: &CommonArgs) -> OutlineOptions {
+    OutlineOptions::default()
+        .compat(common.outline_compat)
+        .max_depth(common.outline_depth.map(|depth| depth as usize))
+        .short_refs(common.outline_short_refs)
+        .descriptions(common.outline_descriptions)
+        .strict(!common.outline_lenient)
+}
+
+/// `openapi/api.json` -> `openapi/api.outline.json`.
+/// The history directory and retention policy for `clean`, if any.
+fn clean_history(args: CleanArgs) -> Result<Option<(PathBuf, Retention)>, AppError> {
+    let Some(dir) = args.history_dir else {
+        return Ok(None);
+    };
+    if args.keep_last.is_none() && args.older_than.is_none() {
+        return Err(AppError::usage(
+            "--history-dir needs --keep-last or --older-than.".to_string(),
+        ));
+    }
+    let older_than = args
+        .older_than
+        .map(|value| parse_positive_duration("--older-than", &value))
+        .transpose()?;
+    let keep_last = args
+        .keep_last
+        .map(|count| usize::try_from(count).unwrap_or(usize::MAX));
+    Ok(Some((
+        dir,
+        Retention {
+            keep_last,
+            older_than,
+        },
+    )))
+}
+
+fn derived_outline_path(out: &Path) -> PathBuf {
+    let stem = out
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "openapi".to_string());
+    out.with_file_name(format!("{stem}.outline.json"))
+}
+
+/// Log label for a target: the output file stem (`openapi/api.json` -> `api`).
+fn target_name(out: &Path) -> String {
+    out.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| out.display().to_string())
+}
+
+/// Rejects contradictory settings. Returns warnings for combinations that
+/// are allowed but ignore part of the input.
+pub fn validate_config(config: &Config) -> Result<Vec<String>, AppError> {
+    if config.input.is_none() {
+        validate_url(&config.url, &config.doc_path)?;
+    }
     if !config.stdout && config.out.is_none() {
-        return Err(AppError::Usage(
+        return Err(AppError::usage(
             "--out is required unless --stdout is set.".to_string(),
         ));
     }
     if config.profile == OutputProfile::Outline && !config.reduce.is_empty() {
-        return Err(AppError::Usage(
+        return Err(AppError::usage(
             "--reduce is not supported with --profile outline.".to_string(),
         ));
     }
+    // Reducing drops `openapi` and `info`, and slim output is meant to stay
+    // a valid document.
+    if config.profile == OutputProfile::Slim && !config.reduce.is_empty() {
+        return Err(AppError::usage(
+            "--reduce is not supported with --profile slim; slim output is a complete document."
+                .to_string(),
+        ));
+    }
     if config.profile == OutputProfile::Outline && config.outline_out.is_some() {
-        return Err(AppError::Usage(
+        return Err(AppError::usage(
             "--outline-out is not supported with --profile outline.".to_string(),
         ));
     }
-    Ok(())
+    if config.stdout && config.outline_out.is_some() {
+        return Err(AppError::usage(
+            "--outline-out cannot be combined with --stdout; only the snapshot is printed."
+                .to_string(),
+        ));
+    }
+    let mut warnings = Vec::new();
+    if config.stdout && config.out.is_some() {
+        warnings.push("--out is ignored because --stdout is set.".to_string());
+    }
+    Ok(warnings)
+}
+
+/// Rejects URLs reqwest could not fetch, suggesting the likely intended URL
+/// for a bare `host:port` or port.
+fn validate_url(url: &str, doc_path: &str) -> Result<(), AppError> {
+    let example = localhost_url(DEFAULT_PORT, doc_path);
+    if url.trim().is_empty() {
+        return Err(AppError::usage(format!(
+            "--url is empty; expected a URL such as {example}."
+        )));
+    }
+    let scheme = match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.has_host() => {
+            return Ok(());
+        }
+        Ok(parsed) => Some(parsed.scheme().to_string()),
+        Err(_) => None,
+    };
+    let hint = match normalize_user_url(url, doc_path) {
+        Some(suggested) if !url.contains("://") => format!("did you mean {suggested}?"),
+        _ => match scheme {
+            Some(scheme) if url.contains("://") => {
+                format!("unsupported scheme '{scheme}'; use http:// or https://.")
+            }
+            _ => format!("expected a URL such as {example}."),
+        },
+    };
+    Err(AppError::usage(format!("invalid --url '{url}': {hint}")))
+}
+
+/// The OpenAPI document at `doc_path` on `localhost` at `port`.
+pub fn localhost_url(port: u16, doc_path: &str) -> String {
+    format!("http://localhost:{port}{doc_path}")
+}
+
+/// Validates a `--path` value, adding the leading `/` if it is missing.
+pub fn normalize_doc_path(value: &str) -> Result<String, AppError> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() || trimmed.contains("://") {
+        return Err(AppError::usage(format!(
+            "invalid --path '{value}': expected a path such as /openapi.json; use --url for a full URL."
+        )));
+    }
+    if trimmed.starts_with('/') {
+        Ok(trimmed.to_string())
+    } else {
+        Ok(format!("/{trimmed}"))
+    }
+}
+
+/// Expands a port, `host:port`, or full URL typed by the user into a URL;
+/// ports and hosts get `doc_path`.
+pub fn normalize_user_url(input: &str, doc_path: &str) -> Option<String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    if trimmed.chars().all(|c| c.is_ascii_digit()) {
+        return trimmed
+            .parse::<u16>()
+            .ok()
+            .filter(|port| *port > 0)
+            .map(|port| localhost_url(port, doc_path));
+    }
+    if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
+        return Some(trimmed.to_string());
+    }
+    if trimmed.contains(':') {
+        return Some(format!("http://{trimmed}{doc_path}"));
+    }
+    None
+}
+
+/// `--print-config` output: the command's mode and every target's
+/// configuration, as pretty-printed JSON.
+pub fn effective_config_json(configs: &[Config], mode: &Mode) -> Result<String, AppError> {
+    #[derive(Serialize)]
+    struct Effective<'a> {
+        mode: &'a Mode,
+        targets: &'a [Config],
+    }
+    serde_json::to_string_pretty(&Effective {
+        mode,
+        targets: configs,
+    })
+    .map_err(|err| AppError::json(format!("json error: {err}")).with_source(err))
+}
+
+fn serialize_headers<S: Serializer>(headers: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(headers.iter().map(|header| mask_header(header)))
 }
 
 pub fn parse_reduce_list(value: &str) -> Result<Vec<ReduceKey>, AppError> {
     if value.is_empty() {
-        return Err(AppError::Reduce("reduce list cannot be empty".to_string()));
+        return Err(AppError::reduce("reduce list cannot be empty".to_string()));
     }
     let mut out = Vec::new();
     for raw in value.split(',') {
@@ -131,7 +931,7 @@ pub fn parse_reduce_list(value: &str) -> Result<Vec<ReduceKey>, AppError> {
             continue;
         }
         if trimmed.to_lowercase() != trimmed {
-            return Err(AppError::Reduce(format!(
+            return Err(AppError::reduce(format!(
                 "reduce values must be lowercase: {trimmed}"
             )));
         }
@@ -139,14 +939,14 @@ pub fn parse_reduce_list(value: &str) -> Result<Vec<ReduceKey>, AppError> {
             "paths" => push_unique(&mut out, ReduceKey::Paths),
             "components" => push_unique(&mut out, ReduceKey::Components),
             _ => {
-                return Err(AppError::Reduce(format!(
+                return Err(AppError::reduce(format!(
                     "unsupported reduce value: {trimmed}"
                 )));
             }
         }
     }
     if out.is_empty() {
-        return Err(AppError::Reduce("reduce list cannot be empty".to_string()));
+        return Err(AppError::reduce("reduce list cannot be empty".to_string()));
     }
     Ok(out)
 }
@@ -160,7 +960,36 @@ fn push_unique(items: &mut Vec<ReduceKey>, key: ReduceKey) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::{CommonArgs, WatchArgs};
+    use crate::cli::{CommonArgs, OutlineCompat, WatchArgs};
+
+    #[test]
+    fn normalize_user_url_accepts_port() {
+        let url = normalize_user_url("3001", DEFAULT_PATH).unwrap();
+        assert_eq!(url, "http://localhost:3001/api-docs/openapi.json");
+    }
+
+    #[test]
+    fn normalize_user_url_accepts_full_url() {
+        let url = normalize_user_url("https://example.com/openapi.json", DEFAULT_PATH).unwrap();
+        assert_eq!(url, "https://example.com/openapi.json");
+    }
+
+    #[test]
+    fn normalize_user_url_accepts_host_port() {
+        let url = normalize_user_url("localhost:4000", DEFAULT_PATH).unwrap();
+        assert_eq!(url, "http://localhost:4000/api-docs/openapi.json");
+    }
+
+    #[test]
+    fn normalize_user_url_rejects_invalid() {
+        assert!(normalize_user_url("not a url", DEFAULT_PATH).is_none());
+    }
+
+    #[test]
+    fn normalize_user_url_rejects_out_of_range_ports() {
+        assert!(normalize_user_url("0", DEFAULT_PATH).is_none());
+        assert!(normalize_user_url("65536", DEFAULT_PATH).is_none());
+    }
 
     #[test]
     fn parse_reduce_list_accepts_paths_components() {
@@ -171,30 +1000,88 @@ mod tests {
     #[test]
     fn parse_reduce_list_rejects_mixed_case() {
         let err = parse_reduce_list("Paths").unwrap_err();
-        assert!(matches!(err, AppError::Reduce(_)));
+        assert!(matches!(err, AppError::Reduce { .. }));
     }
 
     #[test]
     fn defaults_apply_for_watch_mode() {
         let cli = Cli {
-            command: Some(Command::Watch(WatchArgs {
+            command: Some(Command::Watch(Box::new(WatchArgs {
                 interval_ms: 500,
+                interval: None,
+                jitter_ms: 0,
+                initial_delay: None,
+                prompt_grace: "5s".to_string(),
+                reprompt_after: 3,
+                adaptive: false,
+                max_interval: "30s".to_string(),
+                max_backoff_ms: 60_000,
                 no_outline: false,
-            })),
+                log_every_poll: false,
+                log_file: None,
+                max_iterations: None,
+                once: false,
+                max_failures: 0,
+                stable_polls: 1,
+                exit_on_change: false,
+                change_timeout: None,
+                on_change: None,
+                notify: false,
+                ignore_cache_control: false,
+                watch_events_url: None,
+                events: None,
+                metrics_file: None,
+                metrics_prefix: "openapi_snapshot".to_string(),
+                baseline: None,
+                baseline_report: None,
+                log_timestamps: LogTimestamps::Time,
+                no_timestamps: false,
+            }))),
             common: CommonArgs {
-                url: None,
-                out: None,
+                url: Vec::new(),
+                port: None,
+                path: None,
+                out: Vec::new(),
                 outline_out: None,
+                temp_dir: None,
                 reduce: None,
                 profile: OutputProfile::Full,
-                minify: true,
+                outline_compat: OutlineCompat::V2,
+                outline_depth: None,
+                outline_short_refs: false,
+                outline_descriptions: false,
+                outline_lenient: false,
+                minify: Some(true),
+                pretty: None,
                 timeout_ms: 10_000,
+                timeout: None,
+                input: None,
                 header: Vec::new(),
                 stdout: false,
+                config: None,
+                target: Vec::new(),
+                dry_run: false,
+                print_config: false,
+                validate: false,
+                fallback_to_last: false,
+                fallback_exit_code: 0,
+                allow_empty: false,
+                strict_bytes: false,
+                verify_refs: false,
+                strict_refs: false,
+                severity: Vec::new(),
+                require_version: None,
+                check_paths: false,
+                detect_duplicate_keys: false,
+                strict_duplicate_keys: false,
+                validate_examples: false,
+                color: ColorChoice::Auto,
+                verbose: 0,
+                quiet: false,
             },
         };
         let (config, mode) = Config::from_cli(cli).unwrap();
-        assert_eq!(config.url, DEFAULT_URL);
+        assert_eq!(config.url, crate::cli::DEFAULT_URL);
         assert!(config.url_from_default);
         assert_eq!(config.out.unwrap(), PathBuf::from(DEFAULT_OUT));
         assert_eq!(
@@ -202,29 +1089,433 @@ mod tests {
             PathBuf::from(DEFAULT_OUTLINE_OUT)
         );
         assert_eq!(config.reduce, vec![ReduceKey::Paths, ReduceKey::Components]);
-        assert!(matches!(mode, Mode::Watch { .. }));
+        assert!(matches!(mode, Mode::Watch(_)));
     }
 
     #[test]
     fn watch_mode_respects_no_outline() {
         let cli = Cli {
-            command: Some(Command::Watch(WatchArgs {
+            command: Some(Command::Watch(Box::new(WatchArgs {
                 interval_ms: 500,
+                interval: None,
+                jitter_ms: 0,
+                initial_delay: None,
+                prompt_grace: "5s".to_string(),
+                reprompt_after: 3,
+                adaptive: false,
+                max_interval: "30s".to_string(),
+                max_backoff_ms: 60_000,
                 no_outline: true,
-            })),
+                log_every_poll: false,
+                log_file: None,
+                max_iterations: None,
+                once: false,
+                max_failures: 0,
+                stable_polls: 1,
+                exit_on_change: false,
+                change_timeout: None,
+                on_change: None,
+                notify: false,
+                ignore_cache_control: false,
+                watch_events_url: None,
+                events: None,
+                metrics_file: None,
+                metrics_prefix: "openapi_snapshot".to_string(),
+                baseline: None,
+                baseline_report: None,
+                log_timestamps: LogTimestamps::Time,
+                no_timestamps: false,
+            }))),
             common: CommonArgs {
-                url: None,
-                out: None,
+                url: Vec::new(),
+                port: None,
+                path: None,
+                out: Vec::new(),
                 outline_out: None,
+                temp_dir: None,
                 reduce: None,
                 profile: OutputProfile::Full,
-                minify: true,
+                outline_compat: OutlineCompat::V2,
+                outline_depth: None,
+                outline_short_refs: false,
+                outline_descriptions: false,
+                outline_lenient: false,
+                minify: Some(true),
+                pretty: None,
                 timeout_ms: 10_000,
+                timeout: None,
+                input: None,
                 header: Vec::new(),
                 stdout: false,
+                config: None,
+                target: Vec::new(),
+                dry_run: false,
+                print_config: false,
+                validate: false,
+                fallback_to_last: false,
+                fallback_exit_code: 0,
+                allow_empty: false,
+                strict_bytes: false,
+                verify_refs: false,
+                strict_refs: false,
+                severity: Vec::new(),
+                require_version: None,
+                check_paths: false,
+                detect_duplicate_keys: false,
+                strict_duplicate_keys: false,
+                validate_examples: false,
+                color: ColorChoice::Auto,
+                verbose: 0,
+                quiet: false,
             },
         };
         let (config, _) = Config::from_cli(cli).unwrap();
         assert!(config.outline_out.is_none());
     }
+
+    fn parse(args: &[&str]) -> Cli {
+        use clap::Parser;
+        Cli::try_parse_from(std::iter::once("openapi-snapshot").chain(args.iter().copied()))
+            .unwrap()
+    }
+
+    #[test]
+    fn repeated_url_out_pairs_become_named_targets() {
+        let cli = parse(&[
+            "--url",
+            "http://localhost:3000/openapi.json",
+            "--out",
+            "openapi/api.json",
+            "--url",
+            "http://localhost:4000/openapi.json",
+            "--out",
+            "openapi/admin.json",
+            "watch",
+        ]);
+        let (targets, _) = Config::targets_from_cli(cli).unwrap();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].name.as_deref(), Some("api"));
+        assert_eq!(targets[1].url, "http://localhost:4000/openapi.json");
+        assert_eq!(
+            targets[1].outline_out.as_deref(),
+            Some(Path::new("openapi/admin.outline.json"))
+        );
+        assert!(!targets[0].url_from_default);
+    }
+
+    #[test]
+    fn port_expands_to_localhost_url() {
+        let cli = parse(&["--port", "8080", "watch"]);
+        let (config, _) = Config::from_cli(cli).unwrap();
+        assert_eq!(config.url, "http://localhost:8080/api-docs/openapi.json");
+        assert!(!config.url_from_default);
+    }
+
+    fn url_error(url: &str) -> String {
+        match validate_url(url, DEFAULT_PATH) {
+            Err(AppError::Usage { message, .. }) => message,
+            other => panic!("expected a usage error for {url}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn validate_url_accepts_http_and_https() {
+        assert!(validate_url("http://localhost:3000/api-docs/openapi.json", DEFAULT_PATH).is_ok());
+        assert!(validate_url("https://api.example.com/openapi.json", DEFAULT_PATH).is_ok());
+    }
+
+    #[test]
+    fn validate_url_rejects_bad_scheme() {
+        let message = url_error("htp://localhost:3000/api-docs/openapi.json");
+        assert!(message.contains("unsupported scheme 'htp'"), "{message}");
+        assert!(url_error("ftp://example.com/openapi.json").contains("unsupported scheme 'ftp'"));
+    }
+
+    #[test]
+    fn validate_url_suggests_scheme_for_host_and_port() {
+        assert_eq!(
+            url_error("localhost:3000"),
+            "invalid --url 'localhost:3000': did you mean http://localhost:3000/api-docs/openapi.json?"
+        );
+        assert!(url_error("8080").contains("did you mean http://localhost:8080/"));
+        assert!(url_error("not a url").contains("expected a URL such as http://localhost:3000/"));
+    }
+
+    #[test]
+    fn validate_url_rejects_empty() {
+        assert!(url_error("").starts_with("--url is empty"));
+        assert!(url_error("  ").starts_with("--url is empty"));
+    }
+
+    #[test]
+    fn validate_config_rejects_outline_out_with_stdout() {
+        let (config, _) = Config::from_cli(parse(&[
+            "--stdout",
+            "--outline-out",
+            "openapi/outline.json",
+        ]))
+        .unwrap();
+        let err = validate_config(&config).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("--outline-out cannot be combined with --stdout")
+        );
+    }
+
+    #[test]
+    fn validate_config_warns_when_stdout_ignores_out() {
+        let (config, _) = Config::from_cli(parse(&["--stdout", "--out", "api.json"])).unwrap();
+        assert_eq!(
+            validate_config(&config).unwrap(),
+            vec!["--out is ignored because --stdout is set."]
+        );
+        let (config, _) = Config::from_cli(parse(&["--out", "api.json"])).unwrap();
+        assert!(validate_config(&config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn normalize_user_url_uses_the_configured_path() {
+        assert_eq!(
+            normalize_user_url("8080", "/openapi.json").as_deref(),
+            Some("http://localhost:8080/openapi.json")
+        );
+        assert_eq!(
+            normalize_user_url("api:9000", "/openapi.json").as_deref(),
+            Some("http://api:9000/openapi.json")
+        );
+        assert_eq!(
+            normalize_user_url("https://example.com/spec.json", "/openapi.json").as_deref(),
+            Some("https://example.com/spec.json")
+        );
+    }
+
+    #[test]
+    fn path_replaces_the_default_document_path() {
+        let (config, _) = Config::from_cli(parse(&["--path", "openapi.json"])).unwrap();
+        assert_eq!(config.url, "http://localhost:3000/openapi.json");
+        assert_eq!(config.doc_path, "/openapi.json");
+        assert!(config.url_from_default);
+
+        let (config, _) =
+            Config::from_cli(parse(&["--port", "8080", "--path", "/v1/spec"])).unwrap();
+        assert_eq!(config.url, "http://localhost:8080/v1/spec");
+        assert!(!config.url_from_default);
+    }
+
+    #[test]
+    fn path_must_be_a_path_and_not_follow_a_url() {
+        use clap::Parser;
+        let argv = [
+            "openapi-snapshot",
+            "--url",
+            "http://localhost:3000/openapi.json",
+            "--path",
+            "/spec.json",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+
+        for value in ["", "http://localhost:3000/openapi.json"] {
+            let err = Config::from_cli(parse(&["--path", value])).unwrap_err();
+            assert!(matches!(err, AppError::Usage { .. }), "{value}");
+        }
+    }
+
+    #[test]
+    fn port_conflicts_with_url_and_must_be_in_range() {
+        use clap::Parser;
+        for args in [
+            &[
+                "--port",
+                "8080",
+                "--url",
+                "http://localhost:3000/openapi.json",
+            ][..],
+            &["--port", "0"][..],
+            &["--port", "65536"][..],
+        ] {
+            let argv = std::iter::once("openapi-snapshot").chain(args.iter().copied());
+            assert!(Cli::try_parse_from(argv).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn mismatched_url_out_counts_are_rejected() {
+        let cli = parse(&[
+            "--url",
+            "http://localhost:3000/openapi.json",
+            "--url",
+            "http://localhost:4000/openapi.json",
+            "--out",
+            "openapi/api.json",
+            "watch",
+        ]);
+        let err = Config::targets_from_cli(cli).unwrap_err();
+        assert!(matches!(err, AppError::Usage { .. }));
+    }
+
+    #[test]
+    fn single_target_keeps_default_outline_and_no_name() {
+        let (targets, _) = Config::targets_from_cli(parse(&["watch"])).unwrap();
+        assert_eq!(targets.len(), 1);
+        assert!(targets[0].name.is_none());
+        assert_eq!(
+            targets[0].outline_out.as_deref(),
+            Some(Path::new(DEFAULT_OUTLINE_OUT))
+        );
+    }
+
+    #[test]
+    fn no_outline_rejects_explicit_outline_out() {
+        let cli = parse(&["--outline-out", "outline.json", "watch", "--no-outline"]);
+        let err = Config::targets_from_cli(cli).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--no-outline cannot be combined with --outline-out."
+        );
+    }
+
+    #[test]
+    fn watch_parses_initial_delay_and_prompt_grace() {
+        let (_, mode) = Config::targets_from_cli(parse(&["watch"])).unwrap();
+        let Mode::Watch(options) = mode else {
+            panic!("expected watch mode");
+        };
+        assert_eq!(options.initial_delay_ms, 0);
+        assert_eq!(options.prompt_grace_ms, 5_000);
+
+        let cli = parse(&["watch", "--initial-delay", "2s", "--prompt-grace", "0s"]);
+        let (_, mode) = Config::targets_from_cli(cli).unwrap();
+        let Mode::Watch(options) = mode else {
+            panic!("expected watch mode");
+        };
+        assert_eq!(options.initial_delay_ms, 2_000);
+        assert_eq!(options.prompt_grace_ms, 0);
+    }
+
+    #[test]
+    fn outline_subcommand_reads_its_input_and_defaults_to_stdout() {
+        let (config, mode) = Config::from_cli(parse(&["outline", "spec.json"])).unwrap();
+        assert!(matches!(mode, Mode::Snapshot));
+        assert_eq!(config.input, Some(PathBuf::from("spec.json")));
+        assert_eq!(config.profile, OutputProfile::Outline);
+        assert!(config.stdout);
+        assert_eq!(config.outline_out, None);
+
+        let (config, _) =
+            Config::from_cli(parse(&["--out", "o.json", "outline", "spec.json"])).unwrap();
+        assert!(!config.stdout);
+        assert_eq!(config.out, Some(PathBuf::from("o.json")));
+    }
+
+    #[test]
+    fn reduce_subcommand_defaults_to_paths_and_components() {
+        let (config, _) = Config::from_cli(parse(&["reduce", "-"])).unwrap();
+        assert_eq!(config.input, Some(PathBuf::from("-")));
+        assert_eq!(config.reduce, vec![ReduceKey::Paths, ReduceKey::Components]);
+        assert!(config.stdout);
+
+        let (config, _) = Config::from_cli(parse(&["--reduce", "paths", "reduce", "-"])).unwrap();
+        assert_eq!(config.reduce, vec![ReduceKey::Paths]);
+    }
+
+    #[test]
+    fn minify_follows_flags_then_the_output_name() {
+        let minify = |args: &[&str]| Config::from_cli(parse(args)).unwrap().0.minify;
+        assert!(!minify(&[]));
+        assert!(!minify(&["--out", "api.json"]));
+        assert!(minify(&["--out", "api.min.json"]));
+        assert!(!minify(&["--stdout"]));
+        assert!(minify(&["--minify", "--out", "api.json"]));
+        assert!(minify(&["--pretty", "false", "--out", "api.json"]));
+        assert!(!minify(&["--pretty", "--out", "api.min.json"]));
+        assert!(!minify(&["--minify", "false", "--out", "api.min.json"]));
+        assert!(minify(&["--minify", "--pretty", "false"]));
+        assert!(!minify(&["--minify", "false", "--pretty"]));
+
+        let targets = Config::targets_from_cli(parse(&[
+            "--url",
+            "http://localhost:3000/a.json",
+            "--out",
+            "a.min.json",
+            "--url",
+            "http://localhost:3000/b.json",
+            "--out",
+            "b.json",
+        ]))
+        .unwrap()
+        .0;
+        assert_eq!(
+            targets.iter().map(|t| t.minify).collect::<Vec<_>>(),
+            vec![true, false]
+        );
+    }
+
+    #[test]
+    fn minify_and_pretty_must_not_contradict() {
+        for args in [
+            &["--minify", "--pretty"],
+            &["--minify=false", "--pretty=false"],
+        ] {
+            let err = Config::from_cli(parse(args)).unwrap_err();
+            assert!(matches!(err, AppError::Usage { .. }), "{args:?}");
+            assert!(err.to_string().contains("contradicts"), "{err}");
+        }
+    }
+
+    #[test]
+    fn config_round_trips_through_json_with_credentials_masked() {
+        let cli = parse(&[
+            "--url",
+            "http://localhost:8080/openapi.json",
+            "--header",
+            "Authorization: Bearer secret",
+            "--header",
+            "X-Team: api",
+            "--reduce",
+            "paths",
+            "--profile",
+            "slim",
+            "--outline-depth",
+            "2",
+            "watch",
+            "--interval",
+            "5s",
+            "--events",
+            "-",
+        ]);
+        let (config, mode) = Config::from_cli(cli).unwrap();
+        let json = config.to_json().unwrap();
+        assert!(!json.contains("secret"), "{json}");
+
+        let restored: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.to_json().unwrap(), json);
+        assert_eq!(restored.headers, ["Authorization: ***", "X-Team: api"]);
+        assert_eq!(restored.url, config.url);
+        assert_eq!(restored.out, config.out);
+        assert_eq!(restored.reduce, config.reduce);
+        assert_eq!(restored.profile, OutputProfile::Slim);
+        assert_eq!(restored.outline, config.outline);
+        assert_eq!(restored.verbosity, config.verbosity);
+        assert!(restored.transforms.is_empty());
+
+        let mode_json = serde_json::to_value(&mode).unwrap();
+        let restored: Mode = serde_json::from_value(mode_json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&restored).unwrap(), mode_json);
+        let Mode::Watch(options) = restored else {
+            panic!("expected watch mode");
+        };
+        assert_eq!(options.interval_ms, 5_000);
+        assert_eq!(options.events, Some(EventsTarget::Stdout));
+    }
+
+    #[test]
+    fn effective_config_lists_mode_and_targets() {
+        let cli = parse(&["--input", "openapi.json", "stats", "--json"]);
+        let (configs, mode) = Config::targets_from_cli(cli).unwrap();
+        let value: serde_json::Value =
+            serde_json::from_str(&effective_config_json(&configs, &mode).unwrap()).unwrap();
+        assert_eq!(value["mode"], serde_json::json!({"stats": {"json": true}}));
+        assert_eq!(value["targets"][0]["input"], "openapi.json");
+        assert_eq!(value["targets"][0]["profile"], "full");
+    }
 }
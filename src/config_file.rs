@@ -0,0 +1,905 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, CommandFactory, FromArgMatches};
+use toml::{Table, Value};
+
+use crate::cli::{Cli, env_headers};
+use crate::errors::AppError;
+
+/// Looked up in the working directory when `--config` is not given.
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["openapi-snapshot.toml", ".config/openapi-snapshot.toml"];
+
+/// Subcommands whose flags may be set from a table of the same name.
+const SUBCOMMAND_TABLES: [&str; 2] = ["watch", "lint"];
+
+/// Flags that choose the config file and its sections, so the file cannot
+/// set them itself.
+const COMMAND_LINE_ONLY: [&str; 2] = ["config", "target"];
+
+/// Subcommands that run without reading the config file.
+const CONFIG_FREE_SUBCOMMANDS: [&str; 9] = [
+    "completions",
+    "man",
+    "init",
+    "outline",
+    "reduce",
+    "merge",
+    "convert",
+    "prune",
+    "normalize",
+];
+
+/// `--target` value selecting every `[target.<name>]` section.
+pub const ALL_TARGETS: &str = "all";
+
+/// One run of the tool: the parsed flags and, with `--target`, the config
+/// file section they came from.
+#[derive(Debug)]
+pub struct Invocation {
+    pub target: Option<String>,
+    pub cli: Cli,
+}
+
+/// Why the command line could not be turned into invocations.
+#[derive(Debug)]
+pub enum CliError {
+    /// clap rejected the arguments, or they asked for `--help` or
+    /// `--version`; [`clap::Error::exit`] prints it the way clap would.
+    Clap(clap::Error),
+    /// The config file, or a `--target` it does not have.
+    App(AppError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Clap(err) => write!(f, "{err}"),
+            CliError::App(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for CliError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CliError::Clap(err) => Some(err),
+            CliError::App(err) => Some(err),
+        }
+    }
+}
+
+impl From<clap::Error> for CliError {
+    fn from(err: clap::Error) -> Self {
+        CliError::Clap(err)
+    }
+}
+
+impl From<AppError> for CliError {
+    fn from(err: AppError) -> Self {
+        CliError::App(err)
+    }
+}
+
+/// Parses the command line with defaults taken from the config file.
+///
+/// Keys are the long flag names: top-level keys set common flags and the
+/// `[watch]` table sets watch flags. File values become the flags' defaults,
+/// so anything given on the command line still wins and built-in defaults
+/// only apply to keys the file leaves out. Repeatable flags take arrays.
+///
+/// `[target.<name>]` sections describe separate backends. Each `--target`
+/// yields one invocation whose values layer the section over `[defaults]`
+/// over the top-level keys; without `--target` there is a single invocation
+/// built from `[defaults]` and the top-level keys.
+pub fn parse_cli() -> Result<Vec<Invocation>, CliError> {
+    let cwd = std::env::current_dir().map_err(|err| {
+        AppError::io(format!("failed to read working directory: {err}")).with_source(err)
+    })?;
+    let (invocations, warnings) = parse_cli_from(std::env::args_os(), &cwd)?;
+    let quiet = invocations
+        .first()
+        .is_some_and(|invocation| invocation.cli.common.quiet);
+    if !quiet {
+        for warning in warnings {
+            eprintln!("{warning}");
+        }
+    }
+    Ok(invocations)
+}
+
+/// [`parse_cli`] for explicit arguments and working directory. Returns the
+/// invocations plus warnings for unknown config keys. Nothing is printed
+/// and the process never exits; the caller decides what an error means.
+pub fn parse_cli_from<I, T>(args: I, cwd: &Path) -> Result<(Vec<Invocation>, Vec<String>), CliError>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    let command = Cli::command();
+    let matches = command.clone().try_get_matches_from(&args)?;
+
+    // These never fetch anything, and `init` writes the file, so a missing
+    // or broken config must not get in the way. The commands that write a local
+    // transformation also must not pick up the config's `out` and overwrite
+    // a snapshot.
+    if matches
+        .subcommand_name()
+        .is_some_and(|name| CONFIG_FREE_SUBCOMMANDS.contains(&name))
+    {
+        let cli = from_matches(&matches)?;
+        return Ok((vec![Invocation { target: None, cli }], Vec::new()));
+    }
+
+    let explicit = matches
+        .get_one::<PathBuf>("config")
+        .map(|path| cwd.join(path));
+    let path = match explicit {
+        Some(path) => Some(path),
+        None => CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| cwd.join(name))
+            .find(|path| path.is_file()),
+    };
+    let requested: Vec<String> = matches
+        .get_many::<String>("target")
+        .map(|names| names.cloned().collect())
+        .unwrap_or_default();
+    let Some(path) = path else {
+        if !requested.is_empty() {
+            return Err(AppError::usage(
+                "--target needs a config file with [target.<name>] sections.".to_string(),
+            )
+            .into());
+        }
+        let cli = from_matches(&matches)?;
+        return Ok((vec![Invocation { target: None, cli }], Vec::new()));
+    };
+
+    let (base, sections) = split_targets(read_table(&path)?, &path)?;
+    let names = select_targets(&requested, &sections, &path)?;
+    if names.len() > 1 && matches.subcommand_name() == Some("watch") {
+        return Err(AppError::usage(
+            "watch runs a single --target; start one watch per target.".to_string(),
+        )
+        .into());
+    }
+
+    let mut warnings = Vec::new();
+    if names.is_empty() {
+        let cli = parse_with_table(&command, &args, &matches, &base, &path, &mut warnings)?;
+        return Ok((vec![Invocation { target: None, cli }], warnings));
+    }
+    let mut invocations = Vec::new();
+    for name in names {
+        let mut table = base.clone();
+        if let Some(Value::Table(section)) = sections.get(&name) {
+            merge_table(&mut table, section);
+        }
+        let cli = parse_with_table(&command, &args, &matches, &table, &path, &mut warnings)?;
+        invocations.push(Invocation {
+            target: Some(name),
+            cli,
+        });
+    }
+    Ok((invocations, warnings))
+}
+
+/// The subcommand `args` name, found even when the rest of the command line
+/// is invalid, so a rejected `diff` or `check` can still exit with its own
+/// codes.
+pub fn subcommand_name<I, T>(args: I) -> Option<String>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    Cli::command()
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .ok()?
+        .subcommand_name()
+        .map(str::to_string)
+}
+
+/// Parses `args` with `table` installed as flag defaults.
+fn parse_with_table(
+    command: &clap::Command,
+    args: &[OsString],
+    matches: &ArgMatches,
+    table: &Table,
+    path: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<Cli, CliError> {
+    let mut applied = Applied::default();
+    let command = apply_table(command.clone(), table, matches, path, None, &mut applied)?;
+    for warning in applied.warnings.drain(..) {
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+    // Defaults are only parsed when used, so also try a bare `watch` run to
+    // report bad values in the file even when they would not be used now.
+    let probe = [args.first().cloned().unwrap_or_default(), "watch".into()];
+    if let Err(err) = command.clone().try_get_matches_from(probe)
+        && let Some(key) = applied.rejected_key(&err)
+    {
+        return Err(type_error(path, key, &clap_reason(&err.to_string())).into());
+    }
+    let matches = match command.try_get_matches_from(args) {
+        Ok(matches) => matches,
+        Err(err) => match applied.rejected_key(&err) {
+            Some(key) => return Err(type_error(path, key, &clap_reason(&err.to_string())).into()),
+            None => return Err(err.into()),
+        },
+    };
+    Ok(from_matches(&matches)?)
+}
+
+/// Splits off the `[target.<name>]` sections and layers `[defaults]` over
+/// the remaining top-level keys.
+fn split_targets(mut table: Table, path: &Path) -> Result<(Table, Table), AppError> {
+    let sections = match table.remove("target") {
+        None => Table::new(),
+        Some(Value::Table(sections)) => sections,
+        Some(_) => {
+            return Err(type_error(
+                path,
+                "target",
+                "expected [target.<name>] tables",
+            ));
+        }
+    };
+    if let Some((name, _)) = sections.iter().find(|(_, value)| !value.is_table()) {
+        return Err(type_error(
+            path,
+            &format!("target.{name}"),
+            "expected a table",
+        ));
+    }
+    match table.remove("defaults") {
+        None => {}
+        Some(Value::Table(defaults)) => merge_table(&mut table, &defaults),
+        Some(_) => return Err(type_error(path, "defaults", "expected a table")),
+    }
+    Ok((table, sections))
+}
+
+/// Resolves `--target` values to section names, in the order given.
+fn select_targets(
+    requested: &[String],
+    sections: &Table,
+    path: &Path,
+) -> Result<Vec<String>, AppError> {
+    let available = || {
+        if sections.is_empty() {
+            format!("{} defines no targets", path.display())
+        } else {
+            let names: Vec<&str> = sections.keys().map(String::as_str).collect();
+            format!("available targets: {}", names.join(", "))
+        }
+    };
+    let mut names: Vec<String> = Vec::new();
+    for name in requested {
+        if name == ALL_TARGETS {
+            if sections.is_empty() {
+                return Err(AppError::usage(format!("--target all: {}.", available())));
+            }
+            names.extend(sections.keys().cloned());
+        } else if sections.contains_key(name) {
+            names.push(name.clone());
+        } else {
+            return Err(AppError::usage(format!(
+                "unknown target '{name}'; {}.",
+                available()
+            )));
+        }
+    }
+    let mut seen = Vec::new();
+    names.retain(|name| {
+        let first = !seen.contains(name);
+        seen.push(name.clone());
+        first
+    });
+    Ok(names)
+}
+
+/// Overlays `overlay` onto `base`; nested tables such as `[watch]` merge key
+/// by key instead of replacing each other.
+fn merge_table(base: &mut Table, overlay: &Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(key), value) {
+            (Some(Value::Table(existing)), Value::Table(nested)) => merge_table(existing, nested),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// What applying the file did: the keys installed as flag defaults, keyed by
+/// their flag, and warnings for keys that match no flag.
+#[derive(Default)]
+struct Applied {
+    keys: Vec<(String, String)>,
+    warnings: Vec<String>,
+}
+
+impl Applied {
+    /// The config key whose value clap rejected, if the error came from one.
+    fn rejected_key(&self, err: &clap::Error) -> Option<&str> {
+        if !matches!(
+            err.kind(),
+            ErrorKind::InvalidValue | ErrorKind::ValueValidation
+        ) {
+            return None;
+        }
+        let ContextValue::String(arg) = err.get(ContextKind::InvalidArg)? else {
+            return None;
+        };
+        let flag = arg.split(' ').next()?;
+        self.keys
+            .iter()
+            .find(|(long, _)| flag == format!("--{long}"))
+            .map(|(_, key)| key.as_str())
+    }
+}
+
+/// Builds the CLI, taking `--header` from the environment unless it was
+/// given on the command line. Headers are repeatable, so they cannot use
+/// clap's single-variable `env` support.
+fn from_matches(matches: &ArgMatches) -> Result<Cli, clap::Error> {
+    let mut cli = Cli::from_arg_matches(matches)?;
+    if matches.value_source("header") != Some(ValueSource::CommandLine) {
+        let headers = env_headers(|name| std::env::var(name).ok());
+        if !headers.is_empty() {
+            cli.common.header = headers;
+        }
+    }
+    Ok(cli)
+}
+
+fn read_table(path: &Path) -> Result<Table, AppError> {
+    let contents = fs::read_to_string(path).map_err(|err| {
+        AppError::io(format!(
+            "failed to read config file {}: {err}",
+            path.display()
+        ))
+    })?;
+    contents.parse::<Table>().map_err(|err| {
+        AppError::usage(format!(
+            "invalid config file {}: {}",
+            path.display(),
+            err.message()
+        ))
+    })
+}
+
+/// Installs the table's values as defaults on `command`'s flags. `section`
+/// names the subcommand table being applied, for messages.
+fn apply_table(
+    mut command: clap::Command,
+    table: &Table,
+    matches: &ArgMatches,
+    path: &Path,
+    section: Option<&str>,
+    applied: &mut Applied,
+) -> Result<clap::Command, AppError> {
+    let key_name = |key: &str| match section {
+        Some(section) => format!("{section}.{key}"),
+        None => key.to_string(),
+    };
+
+    for (key, value) in table {
+        if section.is_none() && SUBCOMMAND_TABLES.contains(&key.as_str()) {
+            let Value::Table(sub_table) = value else {
+                return Err(type_error(path, key, "expected a table"));
+            };
+            let sub_matches = matches.subcommand_matches(key).cloned().unwrap_or_default();
+            let mut result = Ok(());
+            command = command.mut_subcommand(key, |sub| {
+                match apply_table(
+                    sub.clone(),
+                    sub_table,
+                    &sub_matches,
+                    path,
+                    Some(key),
+                    applied,
+                ) {
+                    Ok(sub) => sub,
+                    Err(err) => {
+                        result = Err(err);
+                        sub
+                    }
+                }
+            });
+            result?;
+            continue;
+        }
+
+        let Some(arg) = command
+            .get_arguments()
+            .find(|arg| {
+                arg.get_long() == Some(key.as_str()) && !COMMAND_LINE_ONLY.contains(&key.as_str())
+            })
+            .cloned()
+        else {
+            applied.warnings.push(format!(
+                "warning: {}: unknown key '{}' ignored",
+                path.display(),
+                key_name(key)
+            ));
+            continue;
+        };
+
+        let id = arg.get_id().as_str().to_string();
+        let on_command_line = is_explicit(matches, &id)
+            || command
+                .get_arg_conflicts_with(&arg)
+                .iter()
+                .any(|other| is_explicit(matches, other.get_id().as_str()));
+        if on_command_line {
+            continue;
+        }
+
+        let repeatable = matches!(arg.get_action(), ArgAction::Append);
+        let values = match value {
+            Value::Array(items) if repeatable => items
+                .iter()
+                .map(scalar)
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| type_error(path, &key_name(key), "expected an array of values"))?,
+            Value::Array(_) => {
+                return Err(type_error(path, &key_name(key), "expected a single value"));
+            }
+            other => vec![
+                scalar(other)
+                    .ok_or_else(|| type_error(path, &key_name(key), "expected a value"))?,
+            ],
+        };
+        applied.keys.push((key.clone(), key_name(key)));
+        command = command.mut_arg(id, |arg| arg.default_values(values));
+    }
+    Ok(command)
+}
+
+fn is_explicit(matches: &ArgMatches, id: &str) -> bool {
+    matches!(
+        matches
+            .try_get_raw(id)
+            .ok()
+            .flatten()
+            .and_then(|_| matches.value_source(id)),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+    )
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Integer(number) => Some(number.to_string()),
+        Value::Float(number) => Some(number.to_string()),
+        Value::Boolean(flag) => Some(flag.to_string()),
+        Value::Datetime(_) | Value::Array(_) | Value::Table(_) => None,
+    }
+}
+
+fn type_error(path: &Path, key: &str, reason: &str) -> AppError {
+    AppError::usage(format!(
+        "invalid value for '{key}' in {}: {reason}",
+        path.display()
+    ))
+}
+
+/// Drops clap's `error: ` prefix and usage footer from a value error.
+fn clap_reason(message: &str) -> String {
+    let first = message.lines().next().unwrap_or(message);
+    first.trim_start_matches("error: ").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Command, LogTimestamps, OutputProfile};
+
+    fn parse_with(file: &str, args: &[&str]) -> Result<(Cli, Vec<String>), CliError> {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("openapi-snapshot.toml"), file).unwrap();
+        let argv = std::iter::once("openapi-snapshot").chain(args.iter().copied());
+        single(parse_cli_from(argv, temp.path()))
+    }
+
+    fn parse_targets(file: &str, args: &[&str]) -> Result<Vec<Invocation>, CliError> {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(temp.path().join("openapi-snapshot.toml"), file).unwrap();
+        let argv = std::iter::once("openapi-snapshot").chain(args.iter().copied());
+        parse_cli_from(argv, temp.path()).map(|(invocations, _)| invocations)
+    }
+
+    fn single(
+        result: Result<(Vec<Invocation>, Vec<String>), CliError>,
+    ) -> Result<(Cli, Vec<String>), CliError> {
+        let (mut invocations, warnings) = result?;
+        assert_eq!(invocations.len(), 1);
+        let invocation = invocations.remove(0);
+        assert_eq!(invocation.target, None);
+        Ok((invocation.cli, warnings))
+    }
+
+    fn watch_args(cli: &Cli) -> &crate::cli::WatchArgs {
+        match &cli.command {
+            Some(Command::Watch(args)) => args,
+            _ => panic!("expected watch"),
+        }
+    }
+
+    #[test]
+    fn file_values_replace_builtin_defaults() {
+        let (cli, warnings) = parse_with(
+            r#"
+            url = "http://localhost:8080/openapi.json"
+            out = "docs/api.json"
+            header = ["Authorization: Bearer x", "X-Team: api"]
+            timeout-ms = 2500
+            minify = true
+            stdout = true
+            profile = "outline"
+            "#,
+            &[],
+        )
+        .unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(cli.common.url, vec!["http://localhost:8080/openapi.json"]);
+        assert_eq!(cli.common.out, vec![PathBuf::from("docs/api.json")]);
+        assert_eq!(
+            cli.common.header,
+            vec!["Authorization: Bearer x", "X-Team: api"]
+        );
+        assert_eq!(cli.common.timeout_ms, 2_500);
+        assert_eq!(cli.common.minify, Some(true));
+        assert!(cli.common.stdout);
+        assert_eq!(cli.common.profile, OutputProfile::Outline);
+    }
+
+    #[test]
+    fn command_line_flags_win_over_file_values() {
+        let (cli, _) = parse_with(
+            r#"
+            url = "http://localhost:8080/openapi.json"
+            header = ["X-From: file"]
+            timeout-ms = 2500
+            profile = "outline"
+            "#,
+            &[
+                "--url",
+                "http://localhost:9090/openapi.json",
+                "--header",
+                "X-From: cli",
+                "--timeout-ms",
+                "10000",
+                "--profile",
+                "full",
+            ],
+        )
+        .unwrap();
+        assert_eq!(cli.common.url, vec!["http://localhost:9090/openapi.json"]);
+        assert_eq!(cli.common.header, vec!["X-From: cli"]);
+        assert_eq!(cli.common.timeout_ms, 10_000);
+        assert_eq!(cli.common.profile, OutputProfile::Full);
+    }
+
+    #[test]
+    fn command_line_flag_overrides_conflicting_file_value() {
+        let (cli, _) = parse_with(
+            r#"
+            timeout = "5s"
+            [watch]
+            interval = "10s"
+            "#,
+            &["--timeout-ms", "1000", "watch", "--interval-ms", "500"],
+        )
+        .unwrap();
+        assert_eq!(cli.common.timeout, None);
+        assert_eq!(cli.common.timeout_ms, 1_000);
+        let watch = watch_args(&cli);
+        assert_eq!(watch.interval, None);
+        assert_eq!(watch.interval_ms, 500);
+    }
+
+    #[test]
+    fn watch_table_sets_watch_defaults() {
+        let (cli, _) = parse_with(
+            r#"
+            [watch]
+            interval = "5s"
+            adaptive = true
+            max-failures = 4
+            log-timestamps = "rfc3339"
+            on-change = "make codegen"
+            "#,
+            &["watch", "--max-failures", "2"],
+        )
+        .unwrap();
+        let watch = watch_args(&cli);
+        assert_eq!(watch.interval.as_deref(), Some("5s"));
+        assert!(watch.adaptive);
+        assert_eq!(watch.max_failures, 2);
+        assert_eq!(watch.log_timestamps, LogTimestamps::Rfc3339);
+        assert_eq!(watch.on_change.as_deref(), Some("make codegen"));
+    }
+
+    #[test]
+    fn watch_table_is_ignored_for_snapshots() {
+        let (cli, _) = parse_with("[watch]\ninterval = \"5s\"\n", &[]).unwrap();
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    fn builtin_defaults_apply_without_a_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let (cli, warnings) = single(parse_cli_from(["openapi-snapshot"], temp.path())).unwrap();
+        assert!(warnings.is_empty());
+        assert!(cli.common.url.is_empty());
+        assert_eq!(cli.common.timeout_ms, 10_000);
+        assert_eq!(cli.common.profile, OutputProfile::Full);
+    }
+
+    #[test]
+    fn lint_table_sets_lint_flags() {
+        let (cli, warnings) = parse_with(
+            "[lint]\nrule = [\"operation-tags\", \"operation-summary\"]\ndisable-rule = [\"operation-summary\"]\n",
+            &["lint"],
+        )
+        .unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        let Some(Command::Lint(args)) = cli.command else {
+            panic!("expected lint, got {:?}", cli.command);
+        };
+        assert_eq!(args.rule, vec!["operation-tags", "operation-summary"]);
+        assert_eq!(args.disable_rule, vec!["operation-summary"]);
+
+        let err = parse_with("[lint]\nrule = [\"tags\"]\n", &["lint"]).unwrap_err();
+        assert!(err.to_string().contains("'lint.rule'"), "{err}");
+    }
+
+    #[test]
+    fn unknown_keys_warn() {
+        let (_, warnings) =
+            parse_with("colour = true\n[watch]\nintervall = \"5s\"\n", &["watch"]).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("unknown key 'colour'"), "{warnings:?}");
+        assert!(
+            warnings[1].contains("unknown key 'watch.intervall'"),
+            "{warnings:?}"
+        );
+    }
+
+    #[test]
+    fn type_errors_name_the_key() {
+        let cases = [
+            ("timeout-ms = \"soon\"", "'timeout-ms'"),
+            (
+                "url = [\"a\", \"b\"]\nheader = 5\nprofile = \"tiny\"",
+                "'profile'",
+            ),
+            ("out = [\"a.json\", 3.5, {x = 1}]", "'out'"),
+            ("stdout = [true]", "'stdout'"),
+            ("watch = 3", "'watch'"),
+            ("[watch]\nmax-failures = -1", "'watch.max-failures'"),
+        ];
+        for (file, key) in cases {
+            let err = parse_with(file, &[]).err().unwrap();
+            assert!(
+                matches!(err, CliError::App(AppError::Usage { .. })),
+                "{file}"
+            );
+            assert!(err.to_string().contains(key), "{file}: {err}");
+        }
+    }
+
+    #[test]
+    fn falls_back_to_dot_config_and_honors_explicit_path() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::create_dir(temp.path().join(".config")).unwrap();
+        fs::write(
+            temp.path().join(".config/openapi-snapshot.toml"),
+            "out = \"from-dot-config.json\"\n",
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("custom.toml"),
+            "out = \"from-custom.json\"\n",
+        )
+        .unwrap();
+
+        let (cli, _) = single(parse_cli_from(["openapi-snapshot"], temp.path())).unwrap();
+        assert_eq!(cli.common.out, vec![PathBuf::from("from-dot-config.json")]);
+
+        let (cli, _) = single(parse_cli_from(
+            ["openapi-snapshot", "--config", "custom.toml"],
+            temp.path(),
+        ))
+        .unwrap();
+        assert_eq!(cli.common.out, vec![PathBuf::from("from-custom.json")]);
+
+        let err = parse_cli_from(
+            ["openapi-snapshot", "--config", "missing.toml"],
+            temp.path(),
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(err, CliError::App(AppError::Io { .. })));
+    }
+
+    #[test]
+    fn bad_arguments_are_returned_instead_of_exiting() {
+        let err = parse_with("", &["--bogus"]).err().unwrap();
+        assert!(
+            matches!(&err, CliError::Clap(err) if err.kind() == clap::error::ErrorKind::UnknownArgument)
+        );
+        let err = parse_with("", &["--help"]).err().unwrap();
+        assert!(
+            matches!(&err, CliError::Clap(err) if err.kind() == clap::error::ErrorKind::DisplayHelp)
+        );
+    }
+
+    #[test]
+    fn subcommand_names_survive_bad_arguments() {
+        let name = |args: &[&str]| {
+            subcommand_name(std::iter::once("openapi-snapshot").chain(args.iter().copied()))
+        };
+        assert_eq!(
+            name(&["diff", "--bogus", "a", "b"]).as_deref(),
+            Some("diff")
+        );
+        assert_eq!(name(&["check"]).as_deref(), Some("check"));
+        assert_eq!(name(&["--url", "x"]), None);
+    }
+
+    #[test]
+    fn invalid_toml_is_a_usage_error() {
+        let err = parse_with("url = ", &[]).err().unwrap();
+        assert!(matches!(err, CliError::App(AppError::Usage { .. })));
+        assert!(err.to_string().contains("invalid config file"));
+    }
+
+    #[test]
+    fn env_headers_split_lists_and_read_numbered_variables() {
+        let vars = [
+            ("OPENAPI_SNAPSHOT_HEADER", "Accept: a, b,X-Team: api, "),
+            ("OPENAPI_SNAPSHOT_HEADER_1", "Authorization: Bearer t,1"),
+            ("OPENAPI_SNAPSHOT_HEADER_2", "X-Extra: 2"),
+            ("OPENAPI_SNAPSHOT_HEADER_4", "X-Skipped: 4"),
+        ];
+        let lookup = |name: &str| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        };
+        assert_eq!(
+            env_headers(lookup),
+            vec![
+                "Accept: a, b",
+                "X-Team: api",
+                "Authorization: Bearer t,1",
+                "X-Extra: 2"
+            ]
+        );
+        assert!(env_headers(|_| None).is_empty());
+    }
+
+    const TARGETS: &str = r#"
+        timeout-ms = 2500
+
+        [defaults]
+        header = ["X-Team: platform"]
+        reduce = "paths"
+
+        [defaults.watch]
+        interval = "5s"
+
+        [target.api]
+        url = "http://localhost:3000/openapi.json"
+        out = "openapi/api.json"
+
+        [target.auth]
+        url = "http://localhost:4000/openapi.json"
+        out = "openapi/auth.json"
+        reduce = "paths,components"
+
+        [target.auth.watch]
+        max-failures = 4
+    "#;
+
+    #[test]
+    fn targets_layer_over_defaults_and_top_level_keys() {
+        let invocations = parse_targets(TARGETS, &["--target", "auth", "watch"]).unwrap();
+        assert_eq!(invocations.len(), 1);
+        let Invocation { target, cli } = &invocations[0];
+        assert_eq!(target.as_deref(), Some("auth"));
+        assert_eq!(cli.common.url, vec!["http://localhost:4000/openapi.json"]);
+        assert_eq!(cli.common.out, vec![PathBuf::from("openapi/auth.json")]);
+        assert_eq!(cli.common.reduce.as_deref(), Some("paths,components"));
+        assert_eq!(cli.common.header, vec!["X-Team: platform"]);
+        assert_eq!(cli.common.timeout_ms, 2500);
+        let watch = watch_args(cli);
+        assert_eq!(watch.interval.as_deref(), Some("5s"));
+        assert_eq!(watch.max_failures, 4);
+    }
+
+    #[test]
+    fn target_all_selects_every_section_once() {
+        let invocations = parse_targets(TARGETS, &["--target", "auth", "--target", "all"]).unwrap();
+        let names: Vec<_> = invocations
+            .iter()
+            .map(|invocation| invocation.target.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, vec!["auth", "api"]);
+        assert_eq!(
+            invocations[1].cli.common.out,
+            vec![PathBuf::from("openapi/api.json")]
+        );
+        assert_eq!(invocations[1].cli.common.reduce.as_deref(), Some("paths"));
+    }
+
+    #[test]
+    fn without_target_defaults_still_apply() {
+        let (cli, _) = parse_with(TARGETS, &[]).unwrap();
+        assert!(cli.common.url.is_empty());
+        assert_eq!(cli.common.reduce.as_deref(), Some("paths"));
+    }
+
+    #[test]
+    fn target_selection_errors() {
+        let err = parse_targets(TARGETS, &["--target", "billing"])
+            .err()
+            .unwrap();
+        assert!(matches!(err, CliError::App(AppError::Usage { .. })));
+        assert!(
+            err.to_string()
+                .contains("unknown target 'billing'; available targets: api, auth"),
+            "{err}"
+        );
+
+        let err = parse_targets(TARGETS, &["--target", "all", "watch"])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("single --target"), "{err}");
+
+        let err = parse_targets("url = \"x\"\n", &["--target", "all"])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("defines no targets"), "{err}");
+
+        let temp = tempfile::tempdir().unwrap();
+        let err = parse_cli_from(["openapi-snapshot", "--target", "api"], temp.path())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("needs a config file"), "{err}");
+    }
+
+    #[test]
+    fn bad_arguments_with_targets_are_returned_instead_of_exiting() {
+        let err = parse_targets(TARGETS, &["--target", "all", "--bogus"])
+            .err()
+            .unwrap();
+        assert!(
+            matches!(&err, CliError::Clap(err) if err.kind() == ErrorKind::UnknownArgument),
+            "{err}"
+        );
+        let err = parse_targets(
+            TARGETS,
+            &["--target", "auth", "watch", "--max-failures", "many"],
+        )
+        .err()
+        .unwrap();
+        assert!(
+            matches!(&err, CliError::Clap(err) if err.kind() == ErrorKind::ValueValidation),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Bearer tokens kept in the OS keyring by `login`, keyed by host, so they
+//! never have to appear in shell history or process listings.
+
+use std::io::{self, BufRead};
+
+use url::{Host, Url};
+
+use crate::config::Config;
+use crate::errors::AppError;
+
+/// Keyring service name the tokens are stored under.
+pub const SERVICE: &str = "openapi-snapshot";
+
+const ENV_ALTERNATIVE: &str = "pass the token with --header 'Authorization: Bearer <token>' or OPENAPI_SNAPSHOT_HEADER instead";
+
+/// Where tokens are kept: the OS keyring, or memory in tests.
+pub trait TokenStore {
+    fn get(&self, host: &str) -> Result<Option<String>, AppError>;
+    fn set(&self, host: &str, token: &str) -> Result<(), AppError>;
+    /// Removes the token, returning whether there was one.
+    fn delete(&self, host: &str) -> Result<bool, AppError>;
+}
+
+/// The platform keyring: Keychain, Credential Manager, or Secret Service.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Keyring;
+
+#[cfg(feature = "keyring")]
+impl TokenStore for Keyring {
+    fn get(&self, host: &str) -> Result<Option<String>, AppError> {
+        read_token(&entry(host)?)
+    }
+
+    fn set(&self, host: &str, token: &str) -> Result<(), AppError> {
+        entry(host)?.set_password(token).map_err(keyring_error)
+    }
+
+    fn delete(&self, host: &str) -> Result<bool, AppError> {
+        delete_token(&entry(host)?)
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+impl TokenStore for Keyring {
+    fn get(&self, _host: &str) -> Result<Option<String>, AppError> {
+        Ok(None)
+    }
+
+    fn set(&self, _host: &str, _token: &str) -> Result<(), AppError> {
+        Err(unsupported())
+    }
+
+    fn delete(&self, _host: &str) -> Result<bool, AppError> {
+        Err(unsupported())
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+fn unsupported() -> AppError {
+    AppError::usage(format!(
+        "built without the keyring feature; {ENV_ALTERNATIVE}."
+    ))
+}
+
+#[cfg(feature = "keyring")]
+fn entry(host: &str) -> Result<keyring::Entry, AppError> {
+    keyring::Entry::new(SERVICE, host).map_err(keyring_error)
+}
+
+#[cfg(feature = "keyring")]
+fn read_token(entry: &keyring::Entry) -> Result<Option<String>, AppError> {
+    match entry.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(keyring_error(err)),
+    }
+}
+
+#[cfg(feature = "keyring")]
+fn delete_token(entry: &keyring::Entry) -> Result<bool, AppError> {
+    match entry.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(err) => Err(keyring_error(err)),
+    }
+}
+
+/// A locked keychain or a Linux session without a secret service is not
+/// something a retry fixes, so point at the alternative.
+#[cfg(feature = "keyring")]
+fn keyring_error(err: keyring::Error) -> AppError {
+    AppError::usage(format!(
+        "could not use the system keyring ({err}); {ENV_ALTERNATIVE}."
+    ))
+}
+
+/// The keyring key for `url`: its host, plus the port when one is given.
+pub fn credential_host(url: &str) -> Result<String, AppError> {
+    let parsed =
+        Url::parse(url).map_err(|err| AppError::usage(format!("invalid URL '{url}': {err}")))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| AppError::usage(format!("URL '{url}' has no host.")))?;
+    Ok(match parsed.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    })
+}
+
+/// Stores `token` for the host of `url` and returns the message to print,
+/// which never includes the token.
+pub fn login(store: &impl TokenStore, url: &str, token: &str) -> Result<String, AppError> {
+    let host = credential_host(url)?;
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(AppError::usage("the token is empty.".to_string()));
+    }
+    store.set(&host, token)?;
+    Ok(format!("stored a token for {host}"))
+}
+
+/// Removes the token for the host of `url` and returns the message to print.
+pub fn logout(store: &impl TokenStore, url: &str) -> Result<String, AppError> {
+    let host = credential_host(url)?;
+    Ok(if store.delete(&host)? {
+        format!("removed the stored token for {host}")
+    } else {
+        format!("no stored token for {host}")
+    })
+}
+
+/// Reads the token without echoing it on a terminal, or as the first line
+/// of stdin when it is piped.
+pub fn read_token_input(host: &str) -> Result<String, AppError> {
+    let stdin = io::stdin();
+    #[cfg(not(target_arch = "wasm32"))]
+    if std::io::IsTerminal::is_terminal(&stdin) {
+        return rpassword::prompt_password(format!("Token for {host}: ")).map_err(|err| {
+            AppError::io(format!("failed to read the token: {err}")).with_source(err)
+        });
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = host;
+    let mut line = String::new();
+    stdin
+        .lock()
+        .read_line(&mut line)
+        .map_err(|err| AppError::io(format!("failed to read the token: {err}")).with_source(err))?;
+    Ok(line)
+}
+
+/// Adds `Authorization: Bearer <token>` for a host with a stored token,
+/// unless the fetch already sends an `Authorization` header or reads a local
+/// file. Tokens are only sent over https, or over http to this machine. A
+/// keyring that cannot be read is skipped: stored tokens are a convenience,
+/// not a requirement.
+pub fn attach_stored_token(config: &mut Config, store: &impl TokenStore) {
+    if config.input.is_some() || has_authorization(&config.headers) {
+        return;
+    }
+    if !sends_tokens(&config.url) {
+        config
+            .verbosity
+            .verbose("not sending stored tokens over plain http".to_string());
+        return;
+    }
+    let token = credential_host(&config.url).and_then(|host| {
+        let token = store.get(&host)?;
+        if token.is_some() {
+            config
+                .verbosity
+                .verbose(format!("using the stored token for {host}"));
+        }
+        Ok(token)
+    });
+    match token {
+        Ok(Some(token)) => config
+            .headers
+            .push(format!("Authorization: Bearer {token}")),
+        Ok(None) => {}
+        Err(err) => config
+            .verbosity
+            .verbose(format!("skipping stored tokens: {err}")),
+    }
+}
+
+/// Whether a stored token may go to `url`: over https, or to a loopback
+/// host, where plain http never leaves the machine.
+fn sends_tokens(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| {
+        url.scheme() == "https"
+            || match url.host() {
+                Some(Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+                Some(Host::Ipv4(ip)) => ip.is_loopback(),
+                Some(Host::Ipv6(ip)) => ip.is_loopback(),
+                None => false,
+            }
+    })
+}
+
+fn has_authorization(headers: &[String]) -> bool {
+    headers.iter().any(|header| {
+        header
+            .split_once(':')
+            .is_some_and(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    #[derive(Default)]
+    struct MemoryStore(RefCell<BTreeMap<String, String>>);
+
+    impl TokenStore for MemoryStore {
+        fn get(&self, host: &str) -> Result<Option<String>, AppError> {
+            Ok(self.0.borrow().get(host).cloned())
+        }
+
+        fn set(&self, host: &str, token: &str) -> Result<(), AppError> {
+            self.0
+                .borrow_mut()
+                .insert(host.to_string(), token.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, host: &str) -> Result<bool, AppError> {
+            Ok(self.0.borrow_mut().remove(host).is_some())
+        }
+    }
+
+    fn config(url: &str, headers: &[&str]) -> Config {
+        let cli =
+            <crate::cli::Cli as clap::Parser>::try_parse_from(["openapi-snapshot", "--url", url])
+                .unwrap();
+        let (mut config, _) = Config::from_cli(cli).unwrap();
+        config.headers = headers.iter().map(ToString::to_string).collect();
+        config
+    }
+
+    #[test]
+    fn hosts_include_explicit_ports() {
+        assert_eq!(
+            credential_host("https://api.example.com/v1/openapi.json").unwrap(),
+            "api.example.com"
+        );
+        assert_eq!(
+            credential_host("http://localhost:3000").unwrap(),
+            "localhost:3000"
+        );
+        assert!(credential_host("not a url").is_err());
+    }
+
+    #[test]
+    fn login_and_logout_never_print_the_token() {
+        let store = MemoryStore::default();
+        let message = login(&store, "https://api.example.com", "secret-token\n").unwrap();
+        assert_eq!(message, "stored a token for api.example.com");
+        assert_eq!(
+            store.get("api.example.com").unwrap().as_deref(),
+            Some("secret-token")
+        );
+        assert!(login(&store, "https://api.example.com", "  \n").is_err());
+
+        assert_eq!(
+            logout(&store, "https://api.example.com/other").unwrap(),
+            "removed the stored token for api.example.com"
+        );
+        assert_eq!(
+            logout(&store, "https://api.example.com").unwrap(),
+            "no stored token for api.example.com"
+        );
+    }
+
+    #[test]
+    fn stored_tokens_are_attached_unless_a_header_is_given() {
+        let store = MemoryStore::default();
+        store.set("api.example.com", "secret-token").unwrap();
+
+        let mut matching = config("https://api.example.com/openapi.json", &["X-Team: api"]);
+        attach_stored_token(&mut matching, &store);
+        assert_eq!(
+            matching.headers,
+            vec!["X-Team: api", "Authorization: Bearer secret-token"]
+        );
+
+        let mut explicit = config(
+            "https://api.example.com/openapi.json",
+            &["authorization: Basic abc"],
+        );
+        attach_stored_token(&mut explicit, &store);
+        assert_eq!(explicit.headers, vec!["authorization: Basic abc"]);
+
+        let mut other = config("https://other.example.com/openapi.json", &[]);
+        attach_stored_token(&mut other, &store);
+        assert!(other.headers.is_empty());
+    }
+
+    #[test]
+    fn stored_tokens_are_not_sent_over_plain_http_to_other_hosts() {
+        let store = MemoryStore::default();
+        store.set("api.example.com", "secret-token").unwrap();
+        store.set("localhost:3000", "local-token").unwrap();
+        store.set("127.0.0.1:3000", "local-token").unwrap();
+
+        let mut cleartext = config("http://api.example.com/openapi.json", &[]);
+        attach_stored_token(&mut cleartext, &store);
+        assert!(cleartext.headers.is_empty());
+
+        for url in [
+            "http://localhost:3000/openapi.json",
+            "http://127.0.0.1:3000/openapi.json",
+        ] {
+            let mut local = config(url, &[]);
+            attach_stored_token(&mut local, &store);
+            assert_eq!(local.headers, vec!["Authorization: Bearer local-token"]);
+        }
+    }
+
+    #[cfg(feature = "keyring")]
+    #[test]
+    fn keyring_failures_suggest_the_header_alternative() {
+        use keyring::mock::MockCredential;
+
+        let entry = keyring::Entry::new_with_credential(Box::new(MockCredential::default()));
+        assert_eq!(read_token(&entry).unwrap(), None);
+        assert!(!delete_token(&entry).unwrap());
+
+        let mock: &MockCredential = entry.get_credential().downcast_ref().unwrap();
+        mock.set_error(keyring::Error::NoStorageAccess("keychain is locked".into()));
+        let err = read_token(&entry).unwrap_err();
+        assert!(matches!(err, AppError::Usage { .. }));
+        let message = err.to_string();
+        assert!(message.contains("keychain is locked"), "{message}");
+        assert!(message.contains("OPENAPI_SNAPSHOT_HEADER"), "{message}");
+    }
+}
@@ -0,0 +1,403 @@
+//! Builds a ready-to-run `curl` command for one operation, with placeholder
+//! values wherever the document does not supply an example.
+
+use serde_json::{Map, Value};
+use url::Url;
+
+use crate::errors::AppError;
+use crate::outline::is_http_method;
+use crate::show::find_operation;
+
+/// Renders a single-line `curl` command for `method path`. The base URL is
+/// `base_url` when given, otherwise the first entry of `servers`; a relative
+/// server URL is resolved against `source` when that is an http(s) URL.
+pub fn curl_command(
+    doc: &Value,
+    method: &str,
+    path: &str,
+    base_url: Option<&str>,
+    source: &str,
+) -> Result<String, AppError> {
+    let lower = method.to_ascii_lowercase();
+    if !is_http_method(&lower) {
+        return Err(AppError::usage(format!(
+            "expected an HTTP method, got '{method}'."
+        )));
+    }
+    let method = lower;
+    let op = find_operation(doc, &method, path)?;
+    let parameters = parameters(doc, path, op);
+
+    let mut url = server_url(doc, base_url, source)?;
+    url.push_str(&fill_path(path, &parameters));
+    let query: Vec<String> = parameters
+        .iter()
+        .filter(|param| param.location == "query" && param.required)
+        .map(|param| format!("{}={}", encode(&param.name), param.url_value()))
+        .collect();
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query.join("&"));
+    }
+
+    let mut words = vec!["curl".to_string()];
+    if method != "get" {
+        words.push(format!("-X {}", method.to_ascii_uppercase()));
+    }
+    words.push(quote(&url));
+    for param in parameters
+        .iter()
+        .filter(|param| param.location == "header" && param.required)
+    {
+        words.push(format!(
+            "-H {}",
+            quote(&format!("{}: {}", param.name, param.value))
+        ));
+    }
+    if let Some((media_type, body)) = request_body(doc, op) {
+        words.push(format!(
+            "-H {}",
+            quote(&format!("Content-Type: {media_type}"))
+        ));
+        words.push(match body {
+            Some(body) => format!("-d {}", quote(&body.to_string())),
+            None => "--data-binary @body".to_string(),
+        });
+    }
+    Ok(words.join(" "))
+}
+
+/// A parameter with the value the command uses for it.
+struct Parameter {
+    name: String,
+    location: String,
+    required: bool,
+    value: String,
+    /// False for placeholders, which are left unencoded in URLs so they
+    /// stay readable.
+    given: bool,
+}
+
+impl Parameter {
+    fn url_value(&self) -> String {
+        if self.given {
+            encode(&self.value)
+        } else {
+            self.value.clone()
+        }
+    }
+}
+
+/// Path-level parameters overridden by operation-level ones with the same
+/// name and location, as OpenAPI specifies.
+fn parameters(doc: &Value, path: &str, op: &Value) -> Vec<Parameter> {
+    let path_level = doc
+        .get("paths")
+        .and_then(|paths| paths.get(path))
+        .and_then(|item| item.get("parameters"));
+    let mut merged: Vec<Parameter> = Vec::new();
+    for list in [path_level, op.get("parameters")].into_iter().flatten() {
+        for param in list.as_array().into_iter().flatten() {
+            let param = resolve(doc, param);
+            let (Some(name), Some(location)) = (
+                param.get("name").and_then(Value::as_str),
+                param.get("in").and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+            merged.retain(|seen| seen.name != name || seen.location != location);
+            let (value, given) = parameter_value(doc, name, param);
+            merged.push(Parameter {
+                name: name.to_string(),
+                location: location.to_string(),
+                required: location == "path"
+                    || param.get("required").and_then(Value::as_bool) == Some(true),
+                value,
+                given,
+            });
+        }
+    }
+    merged
+}
+
+/// The parameter's `example`, then its schema's `example`, `default`, or
+/// first `enum` value; otherwise a placeholder by type. The flag is false
+/// for placeholders.
+fn parameter_value(doc: &Value, name: &str, param: &Value) -> (String, bool) {
+    let schema = param.get("schema").map(|schema| resolve(doc, schema));
+    let given = param.get("example").or_else(|| {
+        schema.and_then(|schema| {
+            schema
+                .get("example")
+                .or_else(|| schema.get("default"))
+                .or_else(|| schema.pointer("/enum/0"))
+        })
+    });
+    match given {
+        Some(Value::String(text)) => (text.clone(), true),
+        Some(value) => (value.to_string(), true),
+        None => match schema
+            .and_then(|schema| schema.get("type"))
+            .and_then(Value::as_str)
+        {
+            Some("integer" | "number") => ("1".to_string(), false),
+            Some("boolean") => ("true".to_string(), false),
+            _ => (format!("<{name}>"), false),
+        },
+    }
+}
+
+fn fill_path(path: &str, parameters: &[Parameter]) -> String {
+    parameters
+        .iter()
+        .filter(|param| param.location == "path")
+        .fold(path.to_string(), |path, param| {
+            path.replace(&format!("{{{}}}", param.name), &param.url_value())
+        })
+}
+
+fn server_url(doc: &Value, base_url: Option<&str>, source: &str) -> Result<String, AppError> {
+    let url = match base_url {
+        Some(base_url) => base_url.to_string(),
+        None => {
+            let Some(server) = doc.pointer("/servers/0") else {
+                return Err(AppError::usage(
+                    "the document lists no servers; pass --base-url.".to_string(),
+                ));
+            };
+            server_with_defaults(server)
+        }
+    };
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return Ok(url.trim_end_matches('/').to_string());
+    }
+    Url::parse(source)
+        .and_then(|source| source.join(&url))
+        .map(|joined| joined.as_str().trim_end_matches('/').to_string())
+        .map_err(|_| {
+            AppError::usage(format!(
+                "server URL '{url}' is relative to an unknown host; pass --base-url."
+            ))
+        })
+}
+
+/// A server's URL with each `{variable}` replaced by its default.
+fn server_with_defaults(server: &Value) -> String {
+    let url = server.get("url").and_then(Value::as_str).unwrap_or("/");
+    let variables = server.get("variables").and_then(Value::as_object);
+    variables
+        .into_iter()
+        .flatten()
+        .fold(url.to_string(), |url, (name, variable)| {
+            match variable.get("default").and_then(Value::as_str) {
+                Some(default) => url.replace(&format!("{{{name}}}"), default),
+                None => url,
+            }
+        })
+}
+
+/// The media type to send and, for JSON, a body skeleton. JSON media types
+/// are preferred when the operation accepts several.
+fn request_body(doc: &Value, op: &Value) -> Option<(String, Option<Value>)> {
+    let content = resolve(doc, op.get("requestBody")?)
+        .get("content")?
+        .as_object()?;
+    let (media_type, media) = content
+        .iter()
+        .find(|(media_type, _)| is_json(media_type))
+        .or_else(|| content.iter().next())?;
+    if !is_json(media_type) {
+        return Some((media_type.clone(), None));
+    }
+    let body = match media.get("schema") {
+        Some(schema) => skeleton(doc, schema, &mut Vec::new()),
+        None => Value::Object(Map::new()),
+    };
+    Some((media_type.clone(), Some(body)))
+}
+
+fn is_json(media_type: &str) -> bool {
+    media_type == "application/json" || media_type.ends_with("+json")
+}
+
+/// A value shaped like `schema` with a placeholder per type: every object
+/// property, one array item, the first enum value. A `$ref` already being
+/// expanded further up becomes `null` so cycles terminate.
+fn skeleton(doc: &Value, schema: &Value, expanding: &mut Vec<String>) -> Value {
+    if let Some(target) = schema.get("$ref").and_then(Value::as_str) {
+        if expanding.iter().any(|seen| seen == target) {
+            return Value::Null;
+        }
+        let Some(resolved) = target
+            .strip_prefix('#')
+            .and_then(|pointer| doc.pointer(pointer))
+        else {
+            return Value::Null;
+        };
+        expanding.push(target.to_string());
+        let value = skeleton(doc, resolved, expanding);
+        expanding.pop();
+        return value;
+    }
+    if let Some(first) = schema.pointer("/enum/0") {
+        return first.clone();
+    }
+    if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+        let mut merged = Map::new();
+        for part in parts {
+            match skeleton(doc, part, expanding) {
+                Value::Object(object) => merged.extend(object),
+                other if merged.is_empty() => return other,
+                _ => {}
+            }
+        }
+        return Value::Object(merged);
+    }
+    if let Some(first) = schema
+        .pointer("/oneOf/0")
+        .or_else(|| schema.pointer("/anyOf/0"))
+    {
+        return skeleton(doc, first, expanding);
+    }
+    let kind = match schema.get("type") {
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null"),
+        Some(kind) => kind.as_str(),
+        None if schema.get("properties").is_some() => Some("object"),
+        None => None,
+    };
+    match kind {
+        Some("string") => Value::String("string".to_string()),
+        Some("integer" | "number") => Value::from(0),
+        Some("boolean") => Value::Bool(false),
+        Some("array") => Value::Array(
+            schema
+                .get("items")
+                .map(|items| skeleton(doc, items, expanding))
+                .into_iter()
+                .collect(),
+        ),
+        Some("object") => Value::Object(
+            schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .map(|(name, property)| (name.clone(), skeleton(doc, property, expanding)))
+                .collect(),
+        ),
+        _ => Value::Null,
+    }
+}
+
+/// Follows a local `$ref`, returning `value` itself when it is not one or
+/// the target is missing.
+fn resolve<'a>(doc: &'a Value, value: &'a Value) -> &'a Value {
+    value
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|target| target.strip_prefix('#'))
+        .and_then(|pointer| doc.pointer(pointer))
+        .unwrap_or(value)
+}
+
+/// Percent-encodes everything outside the unreserved URL characters.
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Wraps `text` in single quotes for POSIX shells.
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Value {
+        serde_json::from_str(include_str!("../tests/fixtures/curl/openapi.json")).unwrap()
+    }
+
+    fn command(method: &str, path: &str) -> String {
+        curl_command(&fixture(), method, path, None, "openapi.json").unwrap()
+    }
+
+    #[test]
+    fn renders_fixture_operations() {
+        let expected = include_str!("../tests/fixtures/curl/expected.txt");
+        let actual: Vec<String> = [
+            ("GET", "/invoices"),
+            ("POST", "/invoices"),
+            ("DELETE", "/invoices/{id}"),
+            ("put", "/invoices/{id}/attachments"),
+        ]
+        .iter()
+        .map(|(method, path)| command(method, path))
+        .collect();
+        assert_eq!(actual.join("\n") + "\n", expected);
+    }
+
+    #[test]
+    fn base_url_overrides_servers_and_relative_servers_use_the_source() {
+        let mut doc = fixture();
+        assert_eq!(
+            curl_command(&doc, "get", "/invoices/{id}/attachments", None, "x")
+                .unwrap_err()
+                .to_string(),
+            "no operation GET /invoices/{id}/attachments; /invoices/{id}/attachments has PUT."
+        );
+        let command = curl_command(
+            &doc,
+            "delete",
+            "/invoices/{id}",
+            Some("http://localhost:8080/"),
+            "x",
+        )
+        .unwrap();
+        assert!(command.starts_with("curl -X DELETE 'http://localhost:8080/invoices/<id>'"));
+
+        doc["servers"] = serde_json::json!([{"url": "/api"}]);
+        let command = curl_command(
+            &doc,
+            "delete",
+            "/invoices/{id}",
+            None,
+            "http://localhost:3000/openapi.json",
+        )
+        .unwrap();
+        assert!(command.starts_with("curl -X DELETE 'http://localhost:3000/api/invoices/<id>'"));
+        let err = curl_command(&doc, "delete", "/invoices/{id}", None, "openapi.json").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "server URL '/api' is relative to an unknown host; pass --base-url."
+        );
+
+        doc.as_object_mut().unwrap().remove("servers");
+        let err = curl_command(&doc, "delete", "/invoices/{id}", None, "x").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the document lists no servers; pass --base-url."
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_methods() {
+        let err = curl_command(&fixture(), "FETCH", "/invoices", None, "x").unwrap_err();
+        assert_eq!(err.to_string(), "expected an HTTP method, got 'FETCH'.");
+    }
+
+    #[test]
+    fn quote_escapes_single_quotes() {
+        assert_eq!(quote("it's"), r"'it'\''s'");
+    }
+}
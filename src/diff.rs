@@ -0,0 +1,742 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::outline::is_http_method;
+
+/// What a change applies to: a whole path, a single operation, or a
+/// component schema.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Subject {
+    Path(String),
+    Operation { path: String, method: String },
+    Schema(String),
+}
+
+impl fmt::Display for Subject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Subject::Path(path) => write!(f, "path {path}"),
+            Subject::Operation { path, method } => {
+                write!(f, "{} {path}", method.to_ascii_uppercase())
+            }
+            Subject::Schema(name) => write!(f, "schema {name}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Modified => "modified",
+        }
+    }
+}
+
+/// One difference inside a modified operation. Covers OpenAPI operations
+/// and outline entries alike.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OperationChange {
+    /// A parameter, by location (`query`, `path`, `header`, `cookie`) and
+    /// name.
+    ParameterAdded {
+        location: String,
+        name: String,
+    },
+    ParameterRemoved {
+        location: String,
+        name: String,
+    },
+    ParameterChanged {
+        location: String,
+        name: String,
+    },
+    /// The parameter list changed although no single parameter was added,
+    /// removed, or changed, e.g. its order.
+    ParametersChanged,
+    RequestBodyAdded,
+    RequestBodyRemoved,
+    RequestBodyChanged,
+    /// A response, by status code.
+    ResponseAdded(String),
+    ResponseRemoved(String),
+    ResponseChanged(String),
+    /// Anything else: summary, description, tags, `operationId`, ...
+    Metadata,
+}
+
+impl OperationChange {
+    /// The part of the operation it belongs to: "parameters", "request
+    /// body", "response schema", or "metadata".
+    pub fn part(&self) -> &'static str {
+        match self {
+            OperationChange::ParameterAdded { .. }
+            | OperationChange::ParameterRemoved { .. }
+            | OperationChange::ParameterChanged { .. }
+            | OperationChange::ParametersChanged => "parameters",
+            OperationChange::RequestBodyAdded
+            | OperationChange::RequestBodyRemoved
+            | OperationChange::RequestBodyChanged => "request body",
+            OperationChange::ResponseAdded(_)
+            | OperationChange::ResponseRemoved(_)
+            | OperationChange::ResponseChanged(_) => "response schema",
+            OperationChange::Metadata => "metadata",
+        }
+    }
+
+    /// A compact label for `diff --format operations`, e.g.
+    /// `param:added page_size` or `response:200 schema`.
+    pub fn label(&self) -> String {
+        match self {
+            OperationChange::ParameterAdded { name, .. } => format!("param:added {name}"),
+            OperationChange::ParameterRemoved { name, .. } => format!("param:removed {name}"),
+            OperationChange::ParameterChanged { name, .. } => format!("param:changed {name}"),
+            OperationChange::ParametersChanged => "param:reordered".to_string(),
+            OperationChange::RequestBodyAdded => "request:added".to_string(),
+            OperationChange::RequestBodyRemoved => "request:removed".to_string(),
+            OperationChange::RequestBodyChanged => "request:schema".to_string(),
+            OperationChange::ResponseAdded(code) => format!("response:{code} added"),
+            OperationChange::ResponseRemoved(code) => format!("response:{code} removed"),
+            OperationChange::ResponseChanged(code) => format!("response:{code} schema"),
+            OperationChange::Metadata => "metadata".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for OperationChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperationChange::ParameterAdded { location, name } => {
+                write!(f, "added {location} parameter {name}")
+            }
+            OperationChange::ParameterRemoved { location, name } => {
+                write!(f, "removed {location} parameter {name}")
+            }
+            OperationChange::ParameterChanged { location, name } => {
+                write!(f, "changed {location} parameter {name}")
+            }
+            OperationChange::ParametersChanged => f.write_str("reordered parameters"),
+            OperationChange::RequestBodyAdded => f.write_str("added request body"),
+            OperationChange::RequestBodyRemoved => f.write_str("removed request body"),
+            OperationChange::RequestBodyChanged => f.write_str("changed request body"),
+            OperationChange::ResponseAdded(code) => write!(f, "added response {code}"),
+            OperationChange::ResponseRemoved(code) => write!(f, "removed response {code}"),
+            OperationChange::ResponseChanged(code) => write!(f, "changed response {code}"),
+            OperationChange::Metadata => f.write_str("changed metadata"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecChange {
+    pub kind: ChangeKind,
+    pub subject: Subject,
+    /// For modified operations, what changed inside them, sorted.
+    pub changes: Vec<OperationChange>,
+}
+
+impl SpecChange {
+    /// For modified operations, the parts that changed (e.g. "response
+    /// schema"), each once.
+    pub fn details(&self) -> Vec<&'static str> {
+        let mut details: Vec<&'static str> = Vec::new();
+        for part in self.changes.iter().map(OperationChange::part) {
+            if !details.contains(&part) {
+                details.push(part);
+            }
+        }
+        details
+    }
+}
+
+impl fmt::Display for SpecChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind.as_str(), self.subject)?;
+        let details = self.details();
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two documents at the level of operations (`paths`) and component
+/// schemas. Works on full OpenAPI documents as well as outlines. Changes are
+/// ordered added, removed, modified, then by path and method.
+pub fn diff_specs(old: &Value, new: &Value) -> Vec<SpecChange> {
+    let mut changes = Vec::new();
+
+    let before = operations(old);
+    let after = operations(new);
+    for (key, new_op) in &after {
+        match before.get(key) {
+            None => changes.push(change(ChangeKind::Added, key, Vec::new())),
+            Some(old_op) => {
+                let details = operation_changes(old, new, old_op, new_op);
+                if !details.is_empty() {
+                    changes.push(change(ChangeKind::Modified, key, details));
+                }
+            }
+        }
+    }
+    for key in before.keys().filter(|key| !after.contains_key(*key)) {
+        changes.push(change(ChangeKind::Removed, key, Vec::new()));
+    }
+
+    let before = schemas(old);
+    let after = schemas(new);
+    for (name, schema) in after.iter().flat_map(|schemas| schemas.iter()) {
+        let kind = match before.and_then(|schemas| schemas.get(name)) {
+            None => ChangeKind::Added,
+            Some(previous) if previous != schema => ChangeKind::Modified,
+            Some(_) => continue,
+        };
+        changes.push(SpecChange {
+            kind,
+            subject: Subject::Schema(name.to_string()),
+            changes: Vec::new(),
+        });
+    }
+    for name in before
+        .iter()
+        .flat_map(|schemas| schemas.keys())
+        .filter(|name| !after.is_some_and(|schemas| schemas.contains_key(*name)))
+    {
+        changes.push(SpecChange {
+            kind: ChangeKind::Removed,
+            subject: Subject::Schema(name.to_string()),
+            changes: Vec::new(),
+        });
+    }
+
+    changes.sort_by(|a, b| (a.kind, &a.subject).cmp(&(b.kind, &b.subject)));
+    changes
+}
+
+/// Like [`diff_specs`], plus an entry for every path added or removed as a
+/// whole, so a report can name the path before its operations.
+pub fn diff_documents(old: &Value, new: &Value) -> Vec<SpecChange> {
+    let mut changes = diff_specs(old, new);
+    let before = path_names(old);
+    let after = path_names(new);
+    for (kind, paths, other) in [
+        (ChangeKind::Added, &after, &before),
+        (ChangeKind::Removed, &before, &after),
+    ] {
+        for path in paths.iter().filter(|path| !other.contains(*path)) {
+            changes.push(SpecChange {
+                kind,
+                subject: Subject::Path(path.to_string()),
+                changes: Vec::new(),
+            });
+        }
+    }
+    changes.sort_by(|a, b| (a.kind, &a.subject).cmp(&(b.kind, &b.subject)));
+    changes
+}
+
+/// Counts changes by kind, e.g. "3 changes: 2 added, 1 removed, 0 modified".
+pub fn summarize_changes(changes: &[SpecChange]) -> String {
+    let count = |kind| changes.iter().filter(|change| change.kind == kind).count();
+    format!(
+        "{} change{}: {} added, {} removed, {} modified",
+        changes.len(),
+        if changes.len() == 1 { "" } else { "s" },
+        count(ChangeKind::Added),
+        count(ChangeKind::Removed),
+        count(ChangeKind::Modified)
+    )
+}
+
+/// Renders at most `limit` changes, one per line, with a "+N more" tail.
+pub fn format_changes(changes: &[SpecChange], limit: usize) -> Vec<String> {
+    let mut lines: Vec<String> = changes
+        .iter()
+        .take(limit)
+        .map(ToString::to_string)
+        .collect();
+    if changes.len() > limit {
+        lines.push(format!("+{} more", changes.len() - limit));
+    }
+    lines
+}
+
+/// Renders [`diff_specs`] output one operation per line, by path and then
+/// method: `added GET /teams`, `removed DELETE /users/{id}`, or `changed GET
+/// /users [param:added page_size]`. Component schemas follow by name as
+/// `schema-added`, `schema-removed`, or `schema-changed` lines.
+pub fn format_operations(changes: &[SpecChange]) -> Vec<String> {
+    let verb = |kind| match kind {
+        ChangeKind::Modified => "changed",
+        kind => kind.as_str(),
+    };
+    let mut operations: Vec<&SpecChange> = Vec::new();
+    let mut schemas: Vec<&SpecChange> = Vec::new();
+    for change in changes {
+        match change.subject {
+            Subject::Operation { .. } => operations.push(change),
+            Subject::Schema(_) => schemas.push(change),
+            Subject::Path(_) => {}
+        }
+    }
+    operations.sort_by(|a, b| a.subject.cmp(&b.subject));
+    schemas.sort_by(|a, b| a.subject.cmp(&b.subject));
+
+    let mut lines = Vec::new();
+    for change in operations {
+        let mut line = format!("{} {}", verb(change.kind), change.subject);
+        if !change.changes.is_empty() {
+            let labels: Vec<String> = change.changes.iter().map(OperationChange::label).collect();
+            line.push_str(&format!(" [{}]", labels.join(", ")));
+        }
+        lines.push(line);
+    }
+    for change in schemas {
+        if let Subject::Schema(name) = &change.subject {
+            lines.push(format!("schema-{} {name}", verb(change.kind)));
+        }
+    }
+    lines
+}
+
+/// An operation together with the path-level parameters that apply to it.
+pub(crate) struct Operation<'a> {
+    pub(crate) op: &'a Value,
+    pub(crate) path_parameters: Option<&'a Value>,
+}
+
+/// Every operation in `paths`, keyed by path and method.
+pub(crate) fn operations(doc: &Value) -> BTreeMap<(&str, &str), Operation<'_>> {
+    let mut operations = BTreeMap::new();
+    let Some(paths) = doc.get("paths").and_then(Value::as_object) else {
+        return operations;
+    };
+    for (path, item) in paths {
+        let Some(item) = item.as_object() else {
+            continue;
+        };
+        for (method, op) in item {
+            if is_http_method(method) {
+                operations.insert(
+                    (path.as_str(), method.as_str()),
+                    Operation {
+                        op,
+                        path_parameters: item.get("parameters"),
+                    },
+                );
+            }
+        }
+    }
+    operations
+}
+
+pub(crate) fn path_names(doc: &Value) -> Vec<&str> {
+    doc.get("paths")
+        .and_then(Value::as_object)
+        .map(|paths| paths.keys().map(String::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// `components.schemas` of a full document, or `schemas` of an outline.
+fn schemas(doc: &Value) -> Option<&Map<String, Value>> {
+    doc.pointer("/components/schemas")
+        .or_else(|| doc.get("schemas"))
+        .and_then(Value::as_object)
+}
+
+fn change(
+    kind: ChangeKind,
+    (path, method): &(&str, &str),
+    changes: Vec<OperationChange>,
+) -> SpecChange {
+    SpecChange {
+        kind,
+        subject: Subject::Operation {
+            path: path.to_string(),
+            method: method.to_string(),
+        },
+        changes,
+    }
+}
+
+/// Lists what differs between two versions of an operation, sorted. Keys
+/// cover both OpenAPI operations and outline entries; parameter `$ref`s are
+/// followed within their own document.
+fn operation_changes<'a>(
+    old_doc: &'a Value,
+    new_doc: &'a Value,
+    old: &Operation<'a>,
+    new: &Operation<'a>,
+) -> Vec<OperationChange> {
+    let mut changes = Vec::new();
+    let field = |op: &Operation<'a>, keys: &[&str]| -> Vec<Option<&'a Value>> {
+        keys.iter().map(|key| op.op.get(*key)).collect()
+    };
+
+    let parameters = ["parameters", "query"];
+    if field(old, &parameters) != field(new, &parameters)
+        || old.path_parameters != new.path_parameters
+    {
+        let count = changes.len();
+        if let (Some(before), Some(after)) =
+            (parameter_map(old_doc, old), parameter_map(new_doc, new))
+        {
+            for ((location, name), parameter) in &after {
+                let (location, name) = (location.clone(), name.clone());
+                changes.push(match before.get(&(location.clone(), name.clone())) {
+                    None => OperationChange::ParameterAdded { location, name },
+                    Some(previous) if previous != parameter => {
+                        OperationChange::ParameterChanged { location, name }
+                    }
+                    Some(_) => continue,
+                });
+            }
+            for (location, name) in before.keys().filter(|key| !after.contains_key(*key)) {
+                changes.push(OperationChange::ParameterRemoved {
+                    location: location.clone(),
+                    name: name.clone(),
+                });
+            }
+        }
+        if changes.len() == count {
+            changes.push(OperationChange::ParametersChanged);
+        }
+    }
+
+    let request = ["requestBody", "request"];
+    if field(old, &request) != field(new, &request) {
+        let body = |op: &Operation<'a>| {
+            request
+                .iter()
+                .filter_map(|key| op.op.get(*key))
+                .find(|body| !body.is_null())
+        };
+        changes.push(match (body(old), body(new)) {
+            (None, Some(_)) => OperationChange::RequestBodyAdded,
+            (Some(_), None) => OperationChange::RequestBodyRemoved,
+            _ => OperationChange::RequestBodyChanged,
+        });
+    }
+
+    let responses = ["responses"];
+    if field(old, &responses) != field(new, &responses) {
+        let count = changes.len();
+        let codes = |op: &Operation<'a>| {
+            op.op
+                .get("responses")
+                .and_then(Value::as_object)
+                .cloned()
+                .unwrap_or_default()
+        };
+        let (before, after) = (codes(old), codes(new));
+        for (code, response) in &after {
+            changes.push(match before.get(code) {
+                None => OperationChange::ResponseAdded(code.clone()),
+                Some(previous) if previous != response => {
+                    OperationChange::ResponseChanged(code.clone())
+                }
+                Some(_) => continue,
+            });
+        }
+        for code in before.keys().filter(|code| !after.contains_key(*code)) {
+            changes.push(OperationChange::ResponseRemoved(code.clone()));
+        }
+        // `responses` of a different shape, e.g. null against an empty map.
+        if changes.len() == count {
+            changes.push(OperationChange::Metadata);
+        }
+    }
+
+    let known = ["parameters", "query", "requestBody", "request", "responses"];
+    let rest = |op: &Operation<'a>| -> Option<BTreeMap<&'a str, &'a Value>> {
+        op.op.as_object().map(|object| {
+            object
+                .iter()
+                .filter(|(key, _)| !known.contains(&key.as_str()))
+                .map(|(key, value)| (key.as_str(), value))
+                .collect()
+        })
+    };
+    if rest(old) != rest(new) {
+        changes.push(OperationChange::Metadata);
+    }
+    changes.sort();
+    changes.dedup();
+    changes
+}
+
+/// The parameters that apply to an operation keyed by location and name,
+/// the operation's own overriding the path's. Outline `query` entries are
+/// query parameters. `None` when a parameter has no name to key it by.
+fn parameter_map<'a>(
+    doc: &'a Value,
+    op: &Operation<'a>,
+) -> Option<BTreeMap<(String, String), &'a Value>> {
+    let mut parameters = BTreeMap::new();
+    for (list, default_location) in [
+        (op.path_parameters, None),
+        (op.op.get("parameters"), None),
+        (op.op.get("query"), Some("query")),
+    ] {
+        for parameter in list.and_then(Value::as_array).into_iter().flatten() {
+            let parameter = resolve_ref(doc, parameter);
+            let name = parameter.get("name").and_then(Value::as_str)?;
+            let location = parameter
+                .get("in")
+                .and_then(Value::as_str)
+                .or(default_location)?;
+            parameters.insert((location.to_string(), name.to_string()), parameter);
+        }
+    }
+    Some(parameters)
+}
+
+/// Longest chain of local `$ref`s [`resolve_ref`] follows.
+const MAX_REF_CHAIN: usize = 32;
+
+/// Follows a chain of local `$ref`s (`#/components/...`) to its target;
+/// anything else, including a reference that does not resolve, is returned
+/// as it is.
+pub(crate) fn resolve_ref<'a>(doc: &'a Value, mut value: &'a Value) -> &'a Value {
+    for _ in 0..MAX_REF_CHAIN {
+        let Some(target) = value
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|target| target.strip_prefix('#'))
+            .and_then(|pointer| doc.pointer(pointer))
+        else {
+            break;
+        };
+        value = target;
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture_before() -> Value {
+        json!({
+            "paths": {
+                "/users": {
+                    "get": {"responses": {"200": {"description": "ok"}}}
+                },
+                "/legacy/export": {
+                    "get": {"responses": {"200": {"description": "ok"}}}
+                },
+                "/invoices/{id}": {
+                    "parameters": [{"name": "id", "in": "path"}],
+                    "get": {"summary": "Get invoice", "responses": {}}
+                }
+            },
+            "components": {"schemas": {"User": {"type": "object"}, "Legacy": {}}}
+        })
+    }
+
+    fn fixture_after() -> Value {
+        json!({
+            "paths": {
+                "/users": {
+                    "get": {"responses": {"200": {"description": "users"}}}
+                },
+                "/invoices/{id}": {
+                    "parameters": [{"name": "id", "in": "path"}],
+                    "get": {"summary": "Fetch invoice", "responses": {}}
+                },
+                "/invoices/{id}/void": {
+                    "post": {"responses": {"204": {}}}
+                }
+            },
+            "components": {
+                "schemas": {"User": {"type": "object", "required": ["id"]}, "Invoice": {}}
+            }
+        })
+    }
+
+    #[test]
+    fn diff_lists_added_removed_and_modified_operations() {
+        let lines: Vec<String> = diff_specs(&fixture_before(), &fixture_after())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "added: POST /invoices/{id}/void",
+                "added: schema Invoice",
+                "removed: GET /legacy/export",
+                "removed: schema Legacy",
+                "modified: GET /invoices/{id} (metadata)",
+                "modified: GET /users (response schema)",
+                "modified: schema User",
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_reads_outline_documents() {
+        let before = json!({
+            "paths": {"/users": {"get": {"query": [], "request": null, "responses": {}}}},
+            "schemas": {}
+        });
+        let after = json!({
+            "paths": {"/users": {"get": {"query": ["limit"], "request": null, "responses": {}}}},
+            "schemas": {"User": {}}
+        });
+        let lines: Vec<String> = diff_specs(&before, &after)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            vec!["added: schema User", "modified: GET /users (parameters)"]
+        );
+    }
+
+    #[test]
+    fn identical_documents_have_no_changes() {
+        assert!(diff_specs(&fixture_before(), &fixture_before()).is_empty());
+    }
+
+    #[test]
+    fn format_changes_caps_output_with_more_tail() {
+        let changes = diff_specs(&fixture_before(), &fixture_after());
+        let lines = format_changes(&changes, 3);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[3], "+4 more");
+        assert_eq!(format_changes(&changes, 20).len(), changes.len());
+    }
+
+    #[test]
+    fn diff_documents_names_whole_paths_before_their_operations() {
+        let lines: Vec<String> = diff_documents(&fixture_before(), &fixture_after())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "added: path /invoices/{id}/void",
+                "added: POST /invoices/{id}/void",
+                "added: schema Invoice",
+                "removed: path /legacy/export",
+                "removed: GET /legacy/export",
+                "removed: schema Legacy",
+                "modified: GET /invoices/{id} (metadata)",
+                "modified: GET /users (response schema)",
+                "modified: schema User",
+            ]
+        );
+        assert!(diff_documents(&fixture_after(), &fixture_after()).is_empty());
+    }
+
+    #[test]
+    fn summarize_changes_counts_each_kind() {
+        let changes = diff_specs(&fixture_before(), &fixture_after());
+        assert_eq!(
+            summarize_changes(&changes),
+            "7 changes: 2 added, 2 removed, 3 modified"
+        );
+        assert_eq!(
+            summarize_changes(&changes[..1]),
+            "1 change: 1 added, 0 removed, 0 modified"
+        );
+    }
+
+    #[test]
+    fn modified_operations_list_each_change() {
+        let before = json!({
+            "paths": {"/users": {
+                "parameters": [{"$ref": "#/components/parameters/Tenant"}],
+                "get": {
+                    "parameters": [
+                        {"name": "limit", "in": "query"},
+                        {"name": "sort", "in": "query"}
+                    ],
+                    "responses": {"200": {"description": "ok"}, "404": {}}
+                },
+                "post": {"responses": {}}
+            }},
+            "components": {"parameters": {"Tenant": {"name": "X-Tenant", "in": "header"}}}
+        });
+        let after = json!({
+            "paths": {"/users": {
+                "parameters": [{"$ref": "#/components/parameters/Tenant"}],
+                "get": {
+                    "parameters": [
+                        {"name": "limit", "in": "query", "required": true},
+                        {"name": "page", "in": "query"}
+                    ],
+                    "responses": {"200": {"description": "users"}, "429": {}},
+                    "summary": "List users"
+                },
+                "post": {"requestBody": {"content": {}}, "responses": {}}
+            }},
+            "components": {"parameters": {"Tenant": {"name": "X-Tenant", "in": "header"}}}
+        });
+        let changes = diff_specs(&before, &after);
+        let described: Vec<(String, Vec<String>)> = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.to_string(),
+                    change.changes.iter().map(ToString::to_string).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            described,
+            vec![
+                (
+                    "modified: GET /users (parameters, response schema, metadata)".to_string(),
+                    vec![
+                        "added query parameter page".to_string(),
+                        "removed query parameter sort".to_string(),
+                        "changed query parameter limit".to_string(),
+                        "added response 429".to_string(),
+                        "removed response 404".to_string(),
+                        "changed response 200".to_string(),
+                        "changed metadata".to_string(),
+                    ]
+                ),
+                (
+                    "modified: POST /users (request body)".to_string(),
+                    vec!["added request body".to_string()]
+                ),
+            ]
+        );
+        assert_eq!(
+            format_operations(&changes),
+            vec![
+                "changed GET /users [param:added page, param:removed sort, param:changed limit, \
+                 response:429 added, response:404 removed, response:200 schema, metadata]",
+                "changed POST /users [request:added]",
+            ]
+        );
+    }
+
+    #[test]
+    fn reordered_parameters_are_still_a_change() {
+        let doc = |names: [&str; 2]| {
+            json!({"paths": {"/users": {"get": {"query": [
+                {"name": names[0]}, {"name": names[1]}
+            ]}}}})
+        };
+        let changes = diff_specs(&doc(["a", "b"]), &doc(["b", "a"]));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].changes, vec![OperationChange::ParametersChanged]);
+        assert_eq!(changes[0].details(), vec!["parameters"]);
+    }
+}
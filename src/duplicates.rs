@@ -0,0 +1,183 @@
+//! Finds object keys that appear more than once in a JSON document. A
+//! [`serde_json::Value`] keeps the last of them and drops the rest without a
+//! word, so a server that writes a path twice loses an endpoint from the
+//! snapshot. [`duplicate_keys`] re-reads the raw bytes with a streaming
+//! visitor that keeps only the keys of the objects it is inside.
+
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::validate::escape;
+
+/// Documents larger than this are not scanned by `--detect-duplicate-keys`,
+/// which would roughly double the parse time; `--strict-duplicate-keys`
+/// scans any size.
+pub const SCAN_LIMIT_BYTES: usize = 64 * 1024 * 1024;
+
+/// A key that appears `count` times in one object, located by a JSON
+/// pointer to the key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKey {
+    pub location: String,
+    pub count: usize,
+}
+
+impl fmt::Display for DuplicateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} appears {} times; the last one is kept",
+            self.location, self.count
+        )
+    }
+}
+
+/// Every duplicated key in `bytes`, ordered by where the object holding it
+/// ends (so nested objects come before their parents) and then by key.
+/// Fails when `bytes` is not JSON.
+pub fn duplicate_keys(bytes: &[u8]) -> Result<Vec<DuplicateKey>, serde_json::Error> {
+    let mut found = Vec::new();
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    Scan {
+        location: "#".to_string(),
+        found: &mut found,
+    }
+    .deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(found)
+}
+
+/// Scans one value at `location`, adding what it finds to `found`.
+struct Scan<'s> {
+    location: String,
+    found: &'s mut Vec<DuplicateKey>,
+}
+
+impl<'de> DeserializeSeed<'de> for Scan<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Scan<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut index = 0usize;
+        loop {
+            let item = Scan {
+                location: format!("{}/{index}", self.location),
+                found: &mut *self.found,
+            };
+            if seq.next_element_seed(item)?.is_none() {
+                return Ok(());
+            }
+            index += 1;
+        }
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut keys: Vec<String> = Vec::new();
+        while let Some(key) = map.next_key::<String>()? {
+            map.next_value_seed(Scan {
+                location: format!("{}/{}", self.location, escape(&key)),
+                found: &mut *self.found,
+            })?;
+            keys.push(key);
+        }
+        keys.sort();
+        for run in keys.chunk_by(|a, b| a == b).filter(|run| run.len() > 1) {
+            self.found.push(DuplicateKey {
+                location: format!("{}/{}", self.location, escape(&run[0])),
+                count: run.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locations(body: &str) -> Vec<String> {
+        duplicate_keys(body.as_bytes())
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn reports_each_duplicated_key_with_its_pointer() {
+        let body = r#"{
+            "openapi": "3.0.3",
+            "paths": {
+                "/users": {"get": {"responses": {}}},
+                "/teams": {"get": {"responses": {}}},
+                "/users": {"post": {"responses": {}}}
+            },
+            "tags": [{"name": "a", "name": "b", "name": "c"}],
+            "components": {"schemas": {"a/b": {"x~": 1, "x~": 2}}}
+        }"#;
+        assert_eq!(
+            locations(body),
+            vec![
+                "#/paths/~1users appears 2 times; the last one is kept",
+                "#/tags/0/name appears 3 times; the last one is kept",
+                "#/components/schemas/a~1b/x~0 appears 2 times; the last one is kept",
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_objects_under_a_duplicate_are_scanned_separately() {
+        let body = r#"{"a": {"b": 1, "b": 2}, "a": {"b": 3}}"#;
+        assert_eq!(
+            locations(body),
+            vec![
+                "#/a/b appears 2 times; the last one is kept",
+                "#/a appears 2 times; the last one is kept",
+            ]
+        );
+    }
+
+    #[test]
+    fn unique_keys_and_invalid_json() {
+        assert!(locations(r#"{"a": {"a": [{"a": 1}, {"a": 2}]}, "b": "a"}"#).is_empty());
+        assert!(locations("[1, null, true]").is_empty());
+        assert!(duplicate_keys(br#"{"a": 1,}"#).is_err());
+        assert!(duplicate_keys(br#"{"a": 1} {}"#).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use crate::errors::AppError;
+
+/// Parses humantime-style durations such as `500ms`, `2s`, `5m`, or `1h30m`.
+/// Every number needs a unit; `flag` is only used in error messages.
+pub fn parse_duration(flag: &str, input: &str) -> Result<Duration, AppError> {
+    let invalid = |reason: &str| {
+        AppError::usage(format!(
+            "invalid duration for {flag}: '{input}' ({reason}; e.g. 500ms, 2s, 5m, 1h30m)."
+        ))
+    };
+
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(invalid("empty value"));
+    }
+    if trimmed.starts_with('-') {
+        return Err(invalid("must be positive"));
+    }
+
+    let mut total_ms: u64 = 0;
+    let mut rest = trimmed;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(invalid("expected a number"));
+        }
+        let value: u64 = rest[..digits]
+            .parse()
+            .map_err(|_| invalid("number too large"))?;
+        rest = &rest[digits..];
+
+        let unit_len = rest
+            .find(|ch: char| ch.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let unit_ms = match &rest[..unit_len] {
+            "ms" => 1,
+            "s" => 1_000,
+            "m" => 60_000,
+            "h" => 3_600_000,
+            "d" => 86_400_000,
+            "" => return Err(invalid("missing unit")),
+            _ => return Err(invalid("unknown unit")),
+        };
+        rest = &rest[unit_len..];
+
+        total_ms = value
+            .checked_mul(unit_ms)
+            .and_then(|ms| total_ms.checked_add(ms))
+            .ok_or_else(|| invalid("number too large"))?;
+    }
+    Ok(Duration::from_millis(total_ms))
+}
+
+/// Like [`parse_duration`], but rejects zero for flags without a floor.
+pub fn parse_positive_duration(flag: &str, input: &str) -> Result<Duration, AppError> {
+    let duration = parse_duration(flag, input)?;
+    if duration.is_zero() {
+        return Err(AppError::usage(format!(
+            "{flag} must be greater than zero."
+        )));
+    }
+    Ok(duration)
+}
+
+/// Milliseconds for a duration flag, saturating instead of truncating.
+pub fn duration_ms(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_and_compound_units() {
+        assert_eq!(
+            parse_duration("--interval", "500ms").unwrap(),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            parse_duration("--interval", "2s").unwrap(),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            parse_duration("--interval", "5m").unwrap(),
+            Duration::from_secs(300)
+        );
+        assert_eq!(
+            parse_duration("--interval", "1h30m").unwrap(),
+            Duration::from_secs(5_400)
+        );
+        assert_eq!(
+            parse_duration("--older-than", "30d").unwrap(),
+            Duration::from_secs(30 * 86_400)
+        );
+    }
+
+    #[test]
+    fn rejects_unparseable_values() {
+        for input in ["", "5", "five", "2x", "s", "-2s", "1.5s"] {
+            let err = parse_duration("--timeout", input).unwrap_err();
+            assert!(matches!(err, AppError::Usage { .. }), "{input}");
+            assert!(err.to_string().contains("--timeout"), "{input}");
+        }
+    }
+
+    #[test]
+    fn positive_duration_rejects_zero() {
+        assert!(parse_duration("--interval", "0s").unwrap().is_zero());
+        let err = parse_positive_duration("--timeout", "0ms").unwrap_err();
+        assert_eq!(err.to_string(), "--timeout must be greater than zero.");
+    }
+}
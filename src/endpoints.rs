@@ -0,0 +1,123 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::outline::operations;
+
+/// One operation, as listed by `paths`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Endpoint {
+    pub method: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+/// Lists every operation in `doc`, sorted by path, then method. Methods are
+/// upper-cased.
+pub fn list_endpoints(doc: &Value) -> Vec<Endpoint> {
+    let Some(paths) = doc.get("paths").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let text = |op: &Value, key: &str| op.get(key).and_then(Value::as_str).map(str::to_string);
+    let mut endpoints: Vec<Endpoint> = operations(paths)
+        .map(|(path, method, op)| Endpoint {
+            method: method.to_ascii_uppercase(),
+            path: path.to_string(),
+            operation_id: text(op, "operationId"),
+            summary: text(op, "summary"),
+        })
+        .collect();
+    endpoints.sort_by(|a, b| (&a.path, &a.method).cmp(&(&b.path, &b.method)));
+    endpoints
+}
+
+/// Renders one `METHOD /path` line per endpoint. `long` adds aligned
+/// operationId and summary columns, with `-` where either is missing.
+pub fn format_endpoints(endpoints: &[Endpoint], long: bool) -> Vec<String> {
+    if !long {
+        return endpoints
+            .iter()
+            .map(|endpoint| format!("{} {}", endpoint.method, endpoint.path))
+            .collect();
+    }
+    let width = |column: fn(&Endpoint) -> usize| endpoints.iter().map(column).max().unwrap_or(0);
+    let method_width = width(|endpoint| endpoint.method.len());
+    let path_width = width(|endpoint| endpoint.path.len());
+    let id_width = width(|endpoint| endpoint.operation_id.as_deref().unwrap_or("-").len());
+    endpoints
+        .iter()
+        .map(|endpoint| {
+            let line = format!(
+                "{:<method_width$} {:<path_width$}  {:<id_width$}  {}",
+                endpoint.method,
+                endpoint.path,
+                endpoint.operation_id.as_deref().unwrap_or("-"),
+                endpoint.summary.as_deref().unwrap_or("-"),
+            );
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture() -> Value {
+        json!({
+            "paths": {
+                "/users/{id}": {
+                    "parameters": [],
+                    "get": {"operationId": "getUser", "summary": "Get a user"},
+                    "delete": {}
+                },
+                "/users": {
+                    "post": {"operationId": "createUser"},
+                    "get": {"operationId": "listUsers", "summary": "List users"}
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn list_endpoints_sorts_by_path_then_method() {
+        let lines = format_endpoints(&list_endpoints(&fixture()), false);
+        assert_eq!(
+            lines,
+            vec![
+                "GET /users",
+                "POST /users",
+                "DELETE /users/{id}",
+                "GET /users/{id}",
+            ]
+        );
+    }
+
+    #[test]
+    fn long_format_aligns_operation_ids_and_summaries() {
+        let lines = format_endpoints(&list_endpoints(&fixture()), true);
+        assert_eq!(
+            lines,
+            vec![
+                "GET    /users       listUsers   List users",
+                "POST   /users       createUser  -",
+                "DELETE /users/{id}  -           -",
+                "GET    /users/{id}  getUser     Get a user",
+            ]
+        );
+    }
+
+    #[test]
+    fn json_omits_missing_fields() {
+        let endpoints = list_endpoints(&fixture());
+        assert_eq!(
+            serde_json::to_value(&endpoints[1]).unwrap(),
+            json!({"method": "POST", "path": "/users", "operationId": "createUser"})
+        );
+        assert!(list_endpoints(&json!({})).is_empty());
+    }
+}
@@ -1,41 +1,579 @@
+use crate::cli::FailOn;
+
+/// Exit codes and what they mean, as listed in `--help` and the manual page.
+pub const EXIT_CODES: [(i32, &str); 11] = [
+    (0, "success"),
+    (
+        1,
+        "usage, network, or server error (HTTP 5xx and other failures)",
+    ),
+    (2, "invalid JSON"),
+    (
+        3,
+        "reduce or outline failed, or a $ref did not resolve with --strict-refs",
+    ),
+    (4, "file I/O error"),
+    (5, "timeout"),
+    (6, "validation failed"),
+    (8, "authentication failed (HTTP 401/403)"),
+    (9, "not found (HTTP 404)"),
+    (10, "breaking API changes found"),
+    (
+        11,
+        "diff, check, or normalize --check could not compare: usage, network, or server error",
+    ),
+];
+
+/// What `diff`, `check`, and `normalize --check` exit with, as listed in
+/// their `--help`. They follow `git diff`: 1 only ever means the documents
+/// differ, so a failure that exits 1 elsewhere exits [`COMPARE_FAILED`]
+/// instead.
+pub const DIFF_EXIT_CODES: [(i32, &str); 11] = [
+    (0, "no differences, or only ones --fail-on allows"),
+    (DIFFERS, "the documents differ"),
+    (2, "invalid JSON"),
+    (3, "reduce or outline failed"),
+    (4, "file I/O error"),
+    (5, "timeout"),
+    (6, "validation failed"),
+    (8, "authentication failed (HTTP 401/403)"),
+    (9, "not found (HTTP 404)"),
+    (10, "breaking API changes found"),
+    (COMPARE_FAILED, "usage, network, or server error"),
+];
+
+/// The code `diff`, `check`, and `normalize --check` exit with when the
+/// documents differ, with or without `--fail-on any`.
+pub const DIFFERS: i32 = 1;
+
+/// The code `diff`, `check`, and `normalize --check` exit with for a usage,
+/// network, or server error, which exits 1 elsewhere.
+pub const COMPARE_FAILED: i32 = 11;
+
+/// The underlying error an [`AppError`] was raised from, when there is one.
+pub type Source = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors with the process exit code each maps to:
+///
+/// | code | meaning |
+/// |------|---------|
+/// | 1 | usage, network, unreachable server, or any other HTTP error (5xx included) |
+/// | 2 | invalid JSON |
+/// | 3 | reduce or outline failed, or a `$ref` did not resolve with `--strict-refs` |
+/// | 4 | file I/O |
+/// | 5 | timeout |
+/// | 6 | validation failed |
+/// | 8 | HTTP 401 or 403: credentials missing or rejected |
+/// | 9 | HTTP 404: wrong URL or path |
+/// | 10 | breaking API changes found by `diff --breaking` or `check --breaking` |
+/// | 11 | what exits 1 elsewhere, from `diff`, `check`, or `normalize --check`; see [`DIFF_EXIT_CODES`] |
+///
+/// A [`AppError::Drift`], the documents differing, exits [`DIFFERS`]. With
+/// `--fail-on`, `diff` and `check` exit 0, 1, or 10 according to
+/// [`fail_on_kind`], and any other code is an operational error.
+///
+/// `message` is the full text shown to the user. `source`, when set, is the
+/// reqwest, serde_json, or io error behind it, available through
+/// [`std::error::Error::source`].
 #[derive(Debug)]
 pub enum AppError {
-    Usage(String),
-    Network(String),
-    Json(String),
-    Reduce(String),
-    Outline(String),
-    Io(String),
+    Usage {
+        message: String,
+        source: Option<Source>,
+    },
+    Network {
+        message: String,
+        source: Option<Source>,
+    },
+    /// A non-success HTTP response. `message` includes the status and a
+    /// snippet of the body.
+    Http {
+        status: u16,
+        url: String,
+        message: String,
+        source: Option<Source>,
+    },
+    Unreachable {
+        message: String,
+        source: Option<Source>,
+    },
+    Json {
+        message: String,
+        source: Option<Source>,
+    },
+    Reduce {
+        message: String,
+        source: Option<Source>,
+    },
+    Outline {
+        message: String,
+        source: Option<Source>,
+    },
+    Io {
+        message: String,
+        source: Option<Source>,
+    },
+    Timeout {
+        message: String,
+        source: Option<Source>,
+    },
+    Validation {
+        message: String,
+        source: Option<Source>,
+    },
+    Drift {
+        message: String,
+        source: Option<Source>,
+    },
+    /// Changes that can break existing clients; see [`crate::breaking`].
+    Breaking {
+        message: String,
+        source: Option<Source>,
+    },
+}
+
+/// What went wrong, without the message or source, for matching on in
+/// library code. New kinds may be added in minor releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    Usage,
+    Network,
+    /// A non-success HTTP response with this status.
+    Http(u16),
+    Unreachable,
+    Json,
+    Reduce,
+    Outline,
+    Io,
+    Timeout,
+    Validation,
+    Drift,
+    Breaking,
+}
+
+impl ErrorKind {
+    /// The process exit code for this kind; see [`EXIT_CODES`].
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Usage | ErrorKind::Network | ErrorKind::Unreachable => 1,
+            ErrorKind::Http(status) => match status {
+                401 | 403 => 8,
+                404 => 9,
+                _ => 1,
+            },
+            ErrorKind::Json => 2,
+            ErrorKind::Reduce | ErrorKind::Outline => 3,
+            ErrorKind::Io => 4,
+            ErrorKind::Timeout => 5,
+            ErrorKind::Validation => 6,
+            ErrorKind::Drift => DIFFERS,
+            ErrorKind::Breaking => 10,
+        }
+    }
+
+    /// The exit code for this kind when `diff`, `check`, or `normalize
+    /// --check` fails with it; see [`DIFF_EXIT_CODES`]. Every difference
+    /// they find exits through here.
+    pub fn diff_exit_code(self) -> i32 {
+        match (self, self.exit_code()) {
+            (ErrorKind::Drift, code) => code,
+            (_, 1) => COMPARE_FAILED,
+            (_, code) => code,
+        }
+    }
+}
+
+/// What `diff` or `check` found, from the point of view of `--fail-on`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Clean,
+    /// Differences, none of them breaking.
+    Changed,
+    Breaking,
+}
+
+impl Outcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Clean => "clean",
+            Outcome::Changed => "changed",
+            Outcome::Breaking => "breaking",
+        }
+    }
+}
+
+/// The error kind `policy` turns `outcome` into, or `None` when the command
+/// should succeed:
+///
+/// | outcome | `none` | `breaking` | `any` |
+/// |---------|--------|------------|-------|
+/// | clean | 0 | 0 | 0 |
+/// | non-breaking changes | 0 | 0 | 1 |
+/// | breaking changes | 0 | 10 | 10 |
+pub fn fail_on_kind(policy: FailOn, outcome: Outcome) -> Option<ErrorKind> {
+    match (policy, outcome) {
+        (FailOn::None, _) | (_, Outcome::Clean) | (FailOn::Breaking, Outcome::Changed) => None,
+        (_, Outcome::Breaking) => Some(ErrorKind::Breaking),
+        (FailOn::Any, Outcome::Changed) => Some(ErrorKind::Drift),
+    }
+}
+
+/// Fails with `message` when [`fail_on_kind`] says `outcome` should fail.
+pub fn enforce_fail_on(policy: FailOn, outcome: Outcome, message: String) -> Result<(), AppError> {
+    match fail_on_kind(policy, outcome) {
+        Some(ErrorKind::Breaking) => Err(AppError::breaking(message)),
+        Some(_) => Err(AppError::drift(message)),
+        None => Ok(()),
+    }
+}
+
+macro_rules! constructors {
+    ($($name:ident => $variant:ident),* $(,)?) => {
+        $(
+            #[doc = concat!("An [`AppError::", stringify!($variant), "`] with no source.")]
+            pub fn $name(message: impl Into<String>) -> AppError {
+                AppError::$variant {
+                    message: message.into(),
+                    source: None,
+                }
+            }
+        )*
+    };
 }
 
 impl AppError {
+    constructors! {
+        usage => Usage,
+        network => Network,
+        unreachable => Unreachable,
+        json => Json,
+        reduce => Reduce,
+        outline => Outline,
+        io => Io,
+        timeout => Timeout,
+        validation => Validation,
+        drift => Drift,
+        breaking => Breaking,
+    }
+
+    /// An [`AppError::Http`] with no source.
+    pub fn http(status: u16, url: impl Into<String>, message: impl Into<String>) -> AppError {
+        AppError::Http {
+            status,
+            url: url.into(),
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// The same error with `source` recorded as its cause.
+    pub fn with_source(mut self, err: impl Into<Source>) -> AppError {
+        *self.source_mut() = Some(err.into());
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            AppError::Usage { .. } => ErrorKind::Usage,
+            AppError::Network { .. } => ErrorKind::Network,
+            AppError::Http { status, .. } => ErrorKind::Http(*status),
+            AppError::Unreachable { .. } => ErrorKind::Unreachable,
+            AppError::Json { .. } => ErrorKind::Json,
+            AppError::Reduce { .. } => ErrorKind::Reduce,
+            AppError::Outline { .. } => ErrorKind::Outline,
+            AppError::Io { .. } => ErrorKind::Io,
+            AppError::Timeout { .. } => ErrorKind::Timeout,
+            AppError::Validation { .. } => ErrorKind::Validation,
+            AppError::Drift { .. } => ErrorKind::Drift,
+            AppError::Breaking { .. } => ErrorKind::Breaking,
+        }
+    }
+
     pub fn exit_code(&self) -> i32 {
+        self.kind().exit_code()
+    }
+
+    /// The exit code when `diff` or `check` fails with this error.
+    pub fn diff_exit_code(&self) -> i32 {
+        self.kind().diff_exit_code()
+    }
+
+    /// The text shown to the user; the same as `to_string()`.
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Usage { message, .. }
+            | AppError::Network { message, .. }
+            | AppError::Http { message, .. }
+            | AppError::Unreachable { message, .. }
+            | AppError::Json { message, .. }
+            | AppError::Reduce { message, .. }
+            | AppError::Outline { message, .. }
+            | AppError::Io { message, .. }
+            | AppError::Timeout { message, .. }
+            | AppError::Validation { message, .. }
+            | AppError::Drift { message, .. }
+            | AppError::Breaking { message, .. } => message,
+        }
+    }
+
+    fn source_mut(&mut self) -> &mut Option<Source> {
+        match self {
+            AppError::Usage { source, .. }
+            | AppError::Network { source, .. }
+            | AppError::Http { source, .. }
+            | AppError::Unreachable { source, .. }
+            | AppError::Json { source, .. }
+            | AppError::Reduce { source, .. }
+            | AppError::Outline { source, .. }
+            | AppError::Io { source, .. }
+            | AppError::Timeout { source, .. }
+            | AppError::Validation { source, .. }
+            | AppError::Drift { source, .. }
+            | AppError::Breaking { source, .. } => source,
+        }
+    }
+
+    /// The same kind of error, and so the same exit code, with a new message.
+    /// The source is not carried over.
+    pub fn with_message(&self, message: String) -> AppError {
         match self {
-            AppError::Usage(_) => 1,
-            AppError::Network(_) => 1,
-            AppError::Json(_) => 2,
-            AppError::Reduce(_) => 3,
-            AppError::Outline(_) => 3,
-            AppError::Io(_) => 4,
+            AppError::Usage { .. } => AppError::usage(message),
+            AppError::Network { .. } => AppError::network(message),
+            AppError::Http { status, url, .. } => AppError::http(*status, url.clone(), message),
+            AppError::Unreachable { .. } => AppError::unreachable(message),
+            AppError::Json { .. } => AppError::json(message),
+            AppError::Reduce { .. } => AppError::reduce(message),
+            AppError::Outline { .. } => AppError::outline(message),
+            AppError::Io { .. } => AppError::io(message),
+            AppError::Timeout { .. } => AppError::timeout(message),
+            AppError::Validation { .. } => AppError::validation(message),
+            AppError::Drift { .. } => AppError::drift(message),
+            AppError::Breaking { .. } => AppError::breaking(message),
         }
     }
 
+    /// True when a different URL might fix the error, so prompting for one
+    /// is worthwhile. Auth failures (401/403) are not: the URL is right.
     pub fn is_url_related(&self) -> bool {
-        matches!(self, AppError::Network(_) | AppError::Json(_))
+        match self.kind() {
+            ErrorKind::Http(status) => !matches!(status, 401 | 403),
+            ErrorKind::Network | ErrorKind::Unreachable | ErrorKind::Json => true,
+            _ => false,
+        }
+    }
+
+    /// True for failures that may go away on their own: the server was
+    /// unreachable, timed out, or answered 5xx. A broken document is not.
+    pub fn is_transient(&self) -> bool {
+        match self.kind() {
+            ErrorKind::Http(status) => status >= 500,
+            ErrorKind::Network | ErrorKind::Unreachable | ErrorKind::Timeout => true,
+            _ => false,
+        }
+    }
+
+    /// True when the server could not be reached at all (connection refused,
+    /// timeout), as opposed to answering with an error.
+    pub fn is_unreachable(&self) -> bool {
+        self.kind() == ErrorKind::Unreachable
     }
 }
 
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            AppError::Usage(msg)
-            | AppError::Network(msg)
-            | AppError::Json(msg)
-            | AppError::Reduce(msg)
-            | AppError::Outline(msg)
-            | AppError::Io(msg) => write!(f, "{msg}"),
+            AppError::Usage { source, .. }
+            | AppError::Network { source, .. }
+            | AppError::Http { source, .. }
+            | AppError::Unreachable { source, .. }
+            | AppError::Json { source, .. }
+            | AppError::Reduce { source, .. }
+            | AppError::Outline { source, .. }
+            | AppError::Io { source, .. }
+            | AppError::Timeout { source, .. }
+            | AppError::Validation { source, .. }
+            | AppError::Drift { source, .. }
+            | AppError::Breaking { source, .. } => source
+                .as_deref()
+                .map(|err| err as &(dyn std::error::Error + 'static)),
         }
     }
 }
 
-impl std::error::Error for AppError {}
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::io(err.to_string()).with_source(err)
+    }
+}
+
+/// Parse errors only; the command line reports them as invalid JSON.
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::json(format!("invalid JSON: {err}")).with_source(err)
+    }
+}
+
+/// Classified the way fetching does: a connection failure or timeout is
+/// [`ErrorKind::Unreachable`], an error status is [`ErrorKind::Http`], and
+/// anything else is [`ErrorKind::Network`].
+#[cfg(feature = "http")]
+impl From<reqwest::Error> for AppError {
+    fn from(err: reqwest::Error) -> Self {
+        let message = format!("request failed: {err}");
+        let error = if let Some(status) = err.status() {
+            let url = err.url().map(|url| url.to_string()).unwrap_or_default();
+            AppError::http(status.as_u16(), url, message)
+        } else if err.is_connect() || err.is_timeout() {
+            AppError::unreachable(message)
+        } else {
+            AppError::network(message)
+        };
+        error.with_source(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn http(status: u16) -> AppError {
+        AppError::http(
+            status,
+            "http://localhost:3000/openapi.json",
+            format!("HTTP {status}"),
+        )
+    }
+
+    #[test]
+    fn http_errors_map_status_to_exit_code() {
+        assert_eq!(http(401).exit_code(), 8);
+        assert_eq!(http(403).exit_code(), 8);
+        assert_eq!(http(404).exit_code(), 9);
+        assert_eq!(http(500).exit_code(), 1);
+        assert_eq!(http(400).exit_code(), 1);
+        assert_eq!(http(404).with_message("gone".to_string()).exit_code(), 9);
+    }
+
+    #[test]
+    fn auth_failures_are_not_url_related() {
+        assert!(http(404).is_url_related());
+        assert!(http(503).is_url_related());
+        assert!(AppError::unreachable("refused".to_string()).is_url_related());
+        assert!(!http(401).is_url_related());
+        assert!(!http(403).is_url_related());
+    }
+
+    #[test]
+    fn only_fetch_failures_are_transient() {
+        assert!(http(502).is_transient());
+        assert!(AppError::timeout("slow".to_string()).is_transient());
+        assert!(AppError::unreachable("refused".to_string()).is_transient());
+        assert!(AppError::network("reset".to_string()).is_transient());
+        assert!(!http(404).is_transient());
+        assert!(!http(401).is_transient());
+        assert!(!AppError::json("bad".to_string()).is_transient());
+        assert!(!AppError::reduce("missing".to_string()).is_transient());
+    }
+
+    #[test]
+    fn every_exit_code_is_documented() {
+        let errors = [
+            AppError::usage(String::new()),
+            AppError::network(String::new()),
+            http(401),
+            http(404),
+            http(500),
+            AppError::unreachable(String::new()),
+            AppError::json(String::new()),
+            AppError::reduce(String::new()),
+            AppError::outline(String::new()),
+            AppError::io(String::new()),
+            AppError::timeout(String::new()),
+            AppError::validation(String::new()),
+            AppError::drift(String::new()),
+            AppError::breaking(String::new()),
+        ];
+        for err in errors {
+            let code = err.exit_code();
+            assert!(
+                EXIT_CODES.iter().any(|(listed, _)| *listed == code),
+                "{code}"
+            );
+            let code = err.diff_exit_code();
+            assert!(
+                DIFF_EXIT_CODES.iter().any(|(listed, _)| *listed == code),
+                "{code}"
+            );
+        }
+    }
+
+    #[test]
+    fn diff_exit_codes_keep_1_for_differences() {
+        assert_eq!(AppError::usage("bad flag").diff_exit_code(), COMPARE_FAILED);
+        assert_eq!(
+            AppError::network("refused").diff_exit_code(),
+            COMPARE_FAILED
+        );
+        assert_eq!(http(500).diff_exit_code(), COMPARE_FAILED);
+        assert_eq!(http(404).diff_exit_code(), 9);
+        assert_eq!(AppError::json("bad").diff_exit_code(), 2);
+        assert_eq!(AppError::io("gone").diff_exit_code(), 4);
+        assert_eq!(AppError::drift("stale").diff_exit_code(), DIFFERS);
+        assert_eq!(AppError::breaking("removed").diff_exit_code(), 10);
+    }
+
+    #[test]
+    fn io_errors_keep_their_source() {
+        let err = AppError::from(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no such file",
+        ));
+        assert_eq!(err.kind(), ErrorKind::Io);
+        assert_eq!(err.exit_code(), 4);
+        assert_eq!(err.to_string(), "no such file");
+        let source = std::error::Error::source(&err).unwrap();
+        let io = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn json_errors_keep_line_and_column() {
+        let parse = serde_json::from_str::<serde_json::Value>("{\n  \"a\": }").unwrap_err();
+        let err = AppError::from(parse);
+        assert_eq!(err.kind(), ErrorKind::Json);
+        assert_eq!(err.exit_code(), 2);
+        assert!(err.to_string().starts_with("invalid JSON: "), "{err}");
+        let source = std::error::Error::source(&err).unwrap();
+        let json = source.downcast_ref::<serde_json::Error>().unwrap();
+        assert_eq!((json.line(), json.column()), (2, 8));
+    }
+
+    #[test]
+    fn with_source_keeps_message_and_kind() {
+        let err = AppError::usage("bad flag").with_source(std::fmt::Error);
+        assert_eq!(err.kind(), ErrorKind::Usage);
+        assert_eq!(err.to_string(), "bad flag");
+        assert!(
+            std::error::Error::source(&err)
+                .unwrap()
+                .is::<std::fmt::Error>()
+        );
+        assert!(std::error::Error::source(&AppError::usage("bad flag")).is_none());
+    }
+
+    #[test]
+    fn kind_determines_exit_code() {
+        assert_eq!(http(404).kind(), ErrorKind::Http(404));
+        assert_eq!(ErrorKind::Http(401).exit_code(), 8);
+        assert_eq!(ErrorKind::Validation.exit_code(), 6);
+        assert_eq!(AppError::drift("stale").kind().exit_code(), DIFFERS);
+        assert_eq!(AppError::breaking("removed").exit_code(), 10);
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::AppError;
+
+/// Where `--events` writes its NDJSON stream.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventsTarget {
+    Stdout,
+    File(PathBuf),
+}
+
+impl EventsTarget {
+    /// `-` means stdout; anything else is a file path.
+    pub fn parse(value: &str) -> Self {
+        if value == "-" {
+            EventsTarget::Stdout
+        } else {
+            EventsTarget::File(PathBuf::from(value))
+        }
+    }
+}
+
+/// Result of a single watch poll, as reported in the event stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventOutcome {
+    /// Fetched successfully without a change to report: the first snapshot,
+    /// or a change still held by `--stable-polls`.
+    Ok,
+    Unchanged,
+    Changed,
+    Error,
+}
+
+/// One line of the `--events` stream. Optional fields are omitted when they
+/// do not apply to the outcome.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PollEvent {
+    pub timestamp: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub outcome: EventOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paths_added: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paths_removed: Option<usize>,
+}
+
+impl PollEvent {
+    pub fn new(timestamp: String, url: &str, target: Option<&str>, outcome: EventOutcome) -> Self {
+        Self {
+            timestamp,
+            url: url.to_string(),
+            target: target.map(str::to_string),
+            outcome,
+            status: None,
+            bytes: None,
+            error: None,
+            paths_added: None,
+            paths_removed: None,
+        }
+    }
+}
+
+/// Line-buffered NDJSON writer for poll events.
+pub struct EventSink {
+    out: Box<dyn Write + Send>,
+}
+
+impl EventSink {
+    pub fn open(target: &EventsTarget) -> Result<Self, AppError> {
+        let out: Box<dyn Write + Send> = match target {
+            EventsTarget::Stdout => Box::new(LineWriter::new(io::stdout())),
+            EventsTarget::File(path) => Box::new(LineWriter::new(open_append(path)?)),
+        };
+        Ok(Self { out })
+    }
+
+    pub fn emit(&mut self, event: &PollEvent) -> Result<(), AppError> {
+        let line = serde_json::to_string(event).map_err(|err| {
+            AppError::io(format!("failed to encode event: {err}")).with_source(err)
+        })?;
+        writeln!(self.out, "{line}")
+            .map_err(|err| AppError::io(format!("failed to write event: {err}")).with_source(err))
+    }
+}
+
+fn open_append(path: &Path) -> Result<File, AppError> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| {
+            AppError::io(format!(
+                "failed to open events file {}: {err}",
+                path.display()
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_target_parses_dash_as_stdout() {
+        assert_eq!(EventsTarget::parse("-"), EventsTarget::Stdout);
+        assert_eq!(
+            EventsTarget::parse("events.ndjson"),
+            EventsTarget::File(PathBuf::from("events.ndjson"))
+        );
+    }
+
+    #[test]
+    fn poll_event_schema_is_stable() {
+        let mut event = PollEvent::new(
+            "2024-05-01T12:31:04.000Z".to_string(),
+            "http://localhost:3000/openapi.json",
+            None,
+            EventOutcome::Changed,
+        );
+        event.status = Some(200);
+        event.bytes = Some(512);
+        event.paths_added = Some(1);
+        event.paths_removed = Some(0);
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"timestamp":"2024-05-01T12:31:04.000Z","url":"http://localhost:3000/openapi.json","outcome":"changed","status":200,"bytes":512,"paths_added":1,"paths_removed":0}"#
+        );
+
+        let mut error = PollEvent::new(
+            "2024-05-01T12:31:06.000Z".to_string(),
+            "http://localhost:3000/openapi.json",
+            Some("api"),
+            EventOutcome::Error,
+        );
+        error.status = Some(502);
+        error.error = Some("HTTP 502 Bad Gateway: down".to_string());
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            r#"{"timestamp":"2024-05-01T12:31:06.000Z","url":"http://localhost:3000/openapi.json","target":"api","outcome":"error","status":502,"error":"HTTP 502 Bad Gateway: down"}"#
+        );
+    }
+
+    #[test]
+    fn event_sink_appends_one_line_per_event() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("events.ndjson");
+        std::fs::write(&path, "{\"previous\":true}\n").unwrap();
+        let event = PollEvent::new(
+            "t".to_string(),
+            "http://localhost:3000/openapi.json",
+            None,
+            EventOutcome::Unchanged,
+        );
+        let mut sink = EventSink::open(&EventsTarget::File(path.clone())).unwrap();
+        sink.emit(&event).unwrap();
+        sink.emit(&event).unwrap();
+        drop(sink);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 3);
+        assert!(contents.ends_with("\"outcome\":\"unchanged\"}\n"));
+    }
+}
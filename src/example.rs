@@ -0,0 +1,456 @@
+//! Sample payloads synthesized from JSON schemas, for `example`.
+//!
+//! The generator walks a schema the way a validator would, but produces a
+//! value instead of checking one. Explicit `example`, `examples`, and
+//! `default` values win; otherwise each type gets a deterministic value that
+//! satisfies the common constraints (`format`, `minimum`, `minLength`,
+//! `minItems`). Variation `n` shifts every synthesized value so a batch of
+//! examples does not repeat itself.
+
+use serde_json::{Map, Value};
+
+use crate::errors::AppError;
+use crate::show::find_schema;
+
+/// One example of `components.schemas.<name>`, or an array of `count`
+/// variations when `count` is given.
+pub fn schema_example(doc: &Value, name: &str, count: Option<usize>) -> Result<Value, AppError> {
+    let schema = find_schema(doc, name)?;
+    let target = format!("#/components/schemas/{name}");
+    Ok(match count {
+        None => generate(doc, schema, &target, 0),
+        Some(count) => Value::Array(
+            (0..count)
+                .map(|variation| generate(doc, schema, &target, variation))
+                .collect(),
+        ),
+    })
+}
+
+/// Synthesizes a value for `schema`. `origin` is the `$ref` the schema was
+/// reached through, so a self-reference is caught as a cycle.
+pub fn generate(doc: &Value, schema: &Value, origin: &str, variation: usize) -> Value {
+    Generator {
+        doc,
+        variation,
+        expanding: vec![origin.to_string()],
+    }
+    .value(schema)
+}
+
+struct Generator<'a> {
+    doc: &'a Value,
+    variation: usize,
+    /// `$ref`s being expanded further up; meeting one again is a cycle.
+    expanding: Vec<String>,
+}
+
+impl Generator<'_> {
+    fn value(&mut self, schema: &Value) -> Value {
+        let Some(object) = schema.as_object() else {
+            // Boolean schemas (`true`/`false`) carry no shape to follow.
+            return Value::Null;
+        };
+        if let Some(target) = object.get("$ref").and_then(Value::as_str) {
+            return self.reference(target);
+        }
+        if let Some(given) = self.given(object) {
+            return given;
+        }
+        if let Some(choices) = object.get("enum").and_then(Value::as_array)
+            && let Some(choice) = self.pick(choices)
+        {
+            return choice;
+        }
+        if let Some(constant) = object.get("const") {
+            return constant.clone();
+        }
+        if let Some(parts) = object.get("allOf").and_then(Value::as_array) {
+            return self.all_of(parts);
+        }
+        if let Some(first) = ["oneOf", "anyOf"]
+            .iter()
+            .find_map(|key| object.get(*key).and_then(|choices| choices.get(0)))
+        {
+            return self.value(first);
+        }
+        match schema_type(object) {
+            Some("string") => Value::String(self.string(object)),
+            Some("integer") => Value::from(self.number(object).round() as i64),
+            Some("number") => Value::from(self.number(object)),
+            Some("boolean") => Value::Bool(self.variation.is_multiple_of(2)),
+            Some("array") => self.array(object),
+            Some("object") => self.object(object),
+            _ => Value::Null,
+        }
+    }
+
+    /// Resolves a local `$ref`; an unresolvable or cyclic one becomes `null`.
+    fn reference(&mut self, target: &str) -> Value {
+        if self.expanding.iter().any(|seen| seen == target) {
+            return Value::Null;
+        }
+        let Some(resolved) = target
+            .strip_prefix('#')
+            .and_then(|pointer| self.doc.pointer(pointer))
+        else {
+            return Value::Null;
+        };
+        self.expanding.push(target.to_string());
+        let value = self.value(resolved);
+        self.expanding.pop();
+        value
+    }
+
+    /// An `example`, one of `examples` (chosen by variation), or `default`.
+    fn given(&self, object: &Map<String, Value>) -> Option<Value> {
+        if let Some(example) = object.get("example") {
+            return Some(example.clone());
+        }
+        if let Some(examples) = object.get("examples").and_then(Value::as_array)
+            && let Some(example) = self.pick(examples)
+        {
+            return Some(example);
+        }
+        object.get("default").cloned()
+    }
+
+    /// Merges the object parts of an `allOf`; a non-object part wins only
+    /// when no part is an object.
+    fn all_of(&mut self, parts: &[Value]) -> Value {
+        let mut merged = Map::new();
+        let mut scalar = None;
+        for part in parts {
+            match self.value(part) {
+                Value::Object(object) => merged.extend(object),
+                Value::Null => {}
+                other => scalar = scalar.or(Some(other)),
+            }
+        }
+        match scalar {
+            Some(value) if merged.is_empty() => value,
+            _ => Value::Object(merged),
+        }
+    }
+
+    fn string(&self, object: &Map<String, Value>) -> String {
+        let n = self.variation;
+        let text = match object.get("format").and_then(Value::as_str) {
+            Some("uuid") => format!("00000000-0000-4000-8000-{:012x}", n + 1),
+            Some("date-time") => format!("2024-01-{:02}T12:00:00Z", n % 28 + 1),
+            Some("date") => format!("2024-01-{:02}", n % 28 + 1),
+            Some("time") => format!("12:{:02}:00", n % 60),
+            Some("email") => format!("user{}@example.com", self.suffix()),
+            Some("uri" | "url") => format!("https://example.com/{}", n + 1),
+            Some("hostname") => format!("host{}.example.com", self.suffix()),
+            Some("ipv4") => format!("192.0.2.{}", n % 254 + 1),
+            Some("ipv6") => format!("2001:db8::{:x}", n + 1),
+            Some("byte") => "ZXhhbXBsZQ==".to_string(),
+            _ => format!("string{}", self.suffix()),
+        };
+        let min = length(object, "minLength").unwrap_or(0);
+        let max = length(object, "maxLength").unwrap_or(usize::MAX).max(min);
+        let mut text: String = text.chars().take(max).collect();
+        while text.chars().count() < min {
+            text.push('x');
+        }
+        text
+    }
+
+    /// Zero shifted by the variation, moved into `minimum`/`maximum` (and
+    /// one past an exclusive bound).
+    fn number(&self, object: &Map<String, Value>) -> f64 {
+        let bound = |key: &str, exclusive: &str, step: f64| match (
+            object.get(key).and_then(Value::as_f64),
+            object.get(exclusive),
+        ) {
+            (_, Some(Value::Number(limit))) => limit.as_f64().map(|limit| limit + step),
+            (Some(limit), Some(Value::Bool(true))) => Some(limit + step),
+            (limit, _) => limit,
+        };
+        let low = bound("minimum", "exclusiveMinimum", 1.0);
+        let high = bound("maximum", "exclusiveMaximum", -1.0);
+        let value = low.unwrap_or(0.0) + self.variation as f64;
+        match high {
+            Some(high) if value > high => high.max(low.unwrap_or(high)),
+            _ => value,
+        }
+    }
+
+    fn array(&mut self, object: &Map<String, Value>) -> Value {
+        let Some(items) = object.get("items") else {
+            return Value::Array(Vec::new());
+        };
+        let count = length(object, "minItems").unwrap_or(1).max(1);
+        let item = self.value(items);
+        Value::Array(vec![item; count])
+    }
+
+    fn object(&mut self, object: &Map<String, Value>) -> Value {
+        let properties = object.get("properties").and_then(Value::as_object);
+        Value::Object(
+            properties
+                .into_iter()
+                .flatten()
+                .map(|(name, property)| (name.clone(), self.value(property)))
+                .collect(),
+        )
+    }
+
+    /// The first choice, cycling through the rest as the variation grows.
+    fn pick(&self, choices: &[Value]) -> Option<Value> {
+        choices.get(self.variation % choices.len().max(1)).cloned()
+    }
+
+    /// Empty for the first variation, then `2`, `3`, ...
+    fn suffix(&self) -> String {
+        match self.variation {
+            0 => String::new(),
+            n => (n + 1).to_string(),
+        }
+    }
+}
+
+/// The schema's type, skipping `null` in a 3.1 type list and inferring
+/// `object` or `array` from `properties` or `items`.
+fn schema_type(object: &Map<String, Value>) -> Option<&str> {
+    match object.get("type") {
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null"),
+        Some(kind) => kind.as_str(),
+        None if object.contains_key("properties") => Some("object"),
+        None if object.contains_key("items") => Some("array"),
+        None => None,
+    }
+}
+
+fn length(object: &Map<String, Value>, key: &str) -> Option<usize> {
+    object
+        .get(key)
+        .and_then(Value::as_u64)
+        .and_then(|value| usize::try_from(value).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn first(schema: Value) -> Value {
+        generate(&Value::Null, &schema, "#/root", 0)
+    }
+
+    fn nth(schema: Value, variation: usize) -> Value {
+        generate(&Value::Null, &schema, "#/root", variation)
+    }
+
+    #[test]
+    fn explicit_example_then_examples_then_default_win() {
+        let schema = json!({"type": "string", "example": "ada", "default": "x"});
+        assert_eq!(first(schema), json!("ada"));
+        let schema = json!({"type": "string", "examples": ["a", "b"], "default": "x"});
+        assert_eq!(nth(schema.clone(), 0), json!("a"));
+        assert_eq!(nth(schema, 1), json!("b"));
+        assert_eq!(first(json!({"type": "integer", "default": 7})), json!(7));
+    }
+
+    #[test]
+    fn enums_pick_the_first_value_then_cycle() {
+        let schema = json!({"type": "string", "enum": ["open", "paid"]});
+        assert_eq!(nth(schema.clone(), 0), json!("open"));
+        assert_eq!(nth(schema.clone(), 1), json!("paid"));
+        assert_eq!(nth(schema, 2), json!("open"));
+        assert_eq!(first(json!({"const": 3})), json!(3));
+    }
+
+    #[test]
+    fn strings_respect_format() {
+        let cases = [
+            ("uuid", "00000000-0000-4000-8000-000000000001"),
+            ("date-time", "2024-01-01T12:00:00Z"),
+            ("date", "2024-01-01"),
+            ("time", "12:00:00"),
+            ("email", "user@example.com"),
+            ("uri", "https://example.com/1"),
+            ("hostname", "host.example.com"),
+            ("ipv4", "192.0.2.1"),
+            ("ipv6", "2001:db8::1"),
+            ("byte", "ZXhhbXBsZQ=="),
+            ("password", "string"),
+        ];
+        for (format, expected) in cases {
+            assert_eq!(
+                first(json!({"type": "string", "format": format})),
+                json!(expected),
+                "{format}"
+            );
+        }
+        assert_eq!(
+            nth(json!({"type": "string", "format": "email"}), 1),
+            json!("user2@example.com")
+        );
+        assert_eq!(
+            nth(json!({"type": "string", "format": "uuid"}), 1),
+            json!("00000000-0000-4000-8000-000000000002")
+        );
+    }
+
+    #[test]
+    fn strings_respect_length_limits() {
+        assert_eq!(
+            first(json!({"type": "string", "minLength": 8})),
+            json!("stringxx")
+        );
+        assert_eq!(
+            first(json!({"type": "string", "maxLength": 3})),
+            json!("str")
+        );
+    }
+
+    #[test]
+    fn numbers_respect_bounds() {
+        assert_eq!(first(json!({"type": "integer"})), json!(0));
+        assert_eq!(nth(json!({"type": "integer"}), 2), json!(2));
+        assert_eq!(first(json!({"type": "integer", "minimum": 10})), json!(10));
+        assert_eq!(
+            first(json!({"type": "integer", "minimum": 10, "exclusiveMinimum": true})),
+            json!(11)
+        );
+        assert_eq!(
+            first(json!({"type": "integer", "exclusiveMinimum": 10})),
+            json!(11)
+        );
+        assert_eq!(first(json!({"type": "number", "minimum": 0.5})), json!(0.5));
+        assert_eq!(
+            nth(json!({"type": "integer", "minimum": 1, "maximum": 2}), 5),
+            json!(2)
+        );
+        assert_eq!(first(json!({"type": "integer", "maximum": -5})), json!(-5));
+    }
+
+    #[test]
+    fn booleans_alternate_by_variation() {
+        assert_eq!(first(json!({"type": "boolean"})), json!(true));
+        assert_eq!(nth(json!({"type": "boolean"}), 1), json!(false));
+    }
+
+    #[test]
+    fn arrays_have_one_item_or_min_items() {
+        assert_eq!(
+            first(json!({"type": "array", "items": {"type": "integer"}})),
+            json!([0])
+        );
+        assert_eq!(
+            first(json!({"type": "array", "minItems": 2, "items": {"type": "boolean"}})),
+            json!([true, true])
+        );
+        assert_eq!(first(json!({"type": "array"})), json!([]));
+        assert_eq!(
+            first(json!({"items": {"type": "string"}})),
+            json!(["string"])
+        );
+    }
+
+    #[test]
+    fn objects_fill_every_property() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": {
+                "id": {"type": "integer"},
+                "name": {"type": "string"},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        });
+        assert_eq!(
+            first(schema),
+            json!({"id": 0, "name": "string", "tags": ["string"]})
+        );
+        assert_eq!(first(json!({"properties": {"a": {}}})), json!({"a": null}));
+        assert_eq!(first(json!({"type": "object"})), json!({}));
+    }
+
+    #[test]
+    fn nullable_type_lists_use_the_non_null_type() {
+        assert_eq!(first(json!({"type": ["null", "string"]})), json!("string"));
+        assert_eq!(first(json!({"type": "null"})), Value::Null);
+    }
+
+    #[test]
+    fn compositions_merge_all_of_and_take_the_first_alternative() {
+        let schema = json!({"allOf": [
+            {"type": "object", "properties": {"id": {"type": "integer"}}},
+            {"type": "object", "properties": {"name": {"type": "string"}}}
+        ]});
+        assert_eq!(first(schema), json!({"id": 0, "name": "string"}));
+        assert_eq!(
+            first(json!({"allOf": [{"type": "string", "format": "date"}]})),
+            json!("2024-01-01")
+        );
+        assert_eq!(
+            first(json!({"oneOf": [{"type": "integer"}, {"type": "string"}]})),
+            json!(0)
+        );
+        assert_eq!(
+            first(json!({"anyOf": [{"type": "string"}, {"type": "integer"}]})),
+            json!("string")
+        );
+    }
+
+    #[test]
+    fn refs_resolve_recursively_and_cycles_become_null() {
+        let doc = json!({"components": {"schemas": {
+            "Node": {
+                "type": "object",
+                "properties": {
+                    "id": {"type": "string", "format": "uuid"},
+                    "owner": {"$ref": "#/components/schemas/User"},
+                    "children": {"type": "array", "items": {"$ref": "#/components/schemas/Node"}}
+                }
+            },
+            "User": {
+                "type": "object",
+                "properties": {
+                    "email": {"type": "string", "format": "email"},
+                    "home": {"$ref": "#/components/schemas/Node"},
+                    "missing": {"$ref": "#/components/schemas/Gone"}
+                }
+            }
+        }}});
+        assert_eq!(
+            schema_example(&doc, "Node", None).unwrap(),
+            json!({
+                "children": [null],
+                "id": "00000000-0000-4000-8000-000000000001",
+                "owner": {"email": "user@example.com", "home": null, "missing": null}
+            })
+        );
+    }
+
+    #[test]
+    fn count_returns_that_many_variations() {
+        let doc = json!({"components": {"schemas": {
+            "User": {"type": "object", "properties": {
+                "id": {"type": "integer", "minimum": 1},
+                "email": {"type": "string", "format": "email"},
+                "role": {"enum": ["admin", "member"]}
+            }}
+        }}});
+        assert_eq!(
+            schema_example(&doc, "User", Some(2)).unwrap(),
+            json!([
+                {"email": "user@example.com", "id": 1, "role": "admin"},
+                {"email": "user2@example.com", "id": 2, "role": "member"}
+            ])
+        );
+    }
+
+    #[test]
+    fn unknown_schema_suggests_names() {
+        let doc = json!({"components": {"schemas": {"User": {}}}});
+        let err = schema_example(&doc, "Usr", None).unwrap_err();
+        assert_eq!(err.to_string(), "no schema 'Usr'; did you mean User?");
+    }
+}
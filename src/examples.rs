@@ -0,0 +1,333 @@
+//! Checks `example` values against the schemas they illustrate, with the
+//! [`jsonschema`] crate.
+//!
+//! The whole document is registered once as a schema resource, so each
+//! schema is compiled as a `$ref` to its own location and its local `$ref`s
+//! resolve against the document. Schemas are compiled once per location
+//! (or per `$ref` target) and reused for every example that uses them.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use jsonschema::{Draft, Registry, Validator};
+use serde_json::{Value, json};
+
+use crate::diff::resolve_ref;
+use crate::validate::escape;
+use crate::version::SpecVersion;
+
+/// The URI the document is registered under.
+const DOCUMENT_URI: &str = "urn:openapi-snapshot:document";
+
+/// Schema keywords whose values are schemas or maps or lists of schemas.
+const SCHEMA_KEYWORDS: [&str; 6] = ["items", "additionalProperties", "not", "if", "then", "else"];
+const SCHEMA_MAPS: [&str; 4] = ["properties", "patternProperties", "$defs", "definitions"];
+const SCHEMA_LISTS: [&str; 4] = ["allOf", "anyOf", "oneOf", "prefixItems"];
+
+/// An example that does not match its schema, or whose schema could not be
+/// compiled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExampleMismatch {
+    /// JSON pointer to the example.
+    pub location: String,
+    /// The first validation error, or why the schema did not compile.
+    pub error: String,
+}
+
+impl fmt::Display for ExampleMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} example does not match its schema: {}",
+            self.location, self.error
+        )
+    }
+}
+
+/// Checks every schema-level `example` and every media type or parameter
+/// `example` and `examples` entry against its schema. OpenAPI 3.0 schemas
+/// are checked as JSON Schema draft 4, with `nullable: true` allowing
+/// `null`; 3.1 schemas as draft 2020-12. Refs to other documents match
+/// anything.
+pub fn check_examples(doc: &Value) -> Vec<ExampleMismatch> {
+    let mut uses = Vec::new();
+    collect_examples(doc, doc, "#", &mut uses);
+    if uses.is_empty() {
+        return Vec::new();
+    }
+    let draft = match SpecVersion::detect(doc) {
+        SpecVersion::OpenApi31 => Draft::Draft202012,
+        _ => Draft::Draft4,
+    };
+    let registry = Registry::options().draft(draft).build([(
+        DOCUMENT_URI,
+        draft.create_resource(as_json_schema(doc, draft)),
+    )]);
+    let registry = match registry {
+        Ok(registry) => registry,
+        Err(err) => {
+            return vec![ExampleMismatch {
+                location: "#".to_string(),
+                error: format!("the document's schemas could not be loaded: {err}"),
+            }];
+        }
+    };
+
+    let mut validators: HashMap<String, Result<Validator, String>> = HashMap::new();
+    let mut mismatches = Vec::new();
+    for ExampleUse {
+        location,
+        example,
+        schema,
+    } in uses
+    {
+        let validator = validators.entry(schema).or_insert_with_key(|schema| {
+            jsonschema::options()
+                .with_draft(draft)
+                .with_registry(registry.clone())
+                .build(&json!({"$ref": format!("{DOCUMENT_URI}{}", encode_fragment(schema))}))
+                .map_err(|err| format!("the schema could not be compiled: {err}"))
+        });
+        let error = match validator {
+            Ok(validator) => match validator.validate(example) {
+                Ok(()) => continue,
+                Err(err) => match err.instance_path().as_str() {
+                    "" => err.to_string(),
+                    path => format!("{err} (at {path})"),
+                },
+            },
+            Err(error) => error.clone(),
+        };
+        mismatches.push(ExampleMismatch { location, error });
+    }
+    mismatches
+}
+
+/// An example, and a JSON pointer to the schema it should match.
+struct ExampleUse<'a> {
+    location: String,
+    example: &'a Value,
+    schema: String,
+}
+
+/// Finds the examples of media types and parameters (objects with a
+/// `schema`) and of the schemas under `components/schemas` and `schema`
+/// keys, in document order.
+fn collect_examples<'a>(
+    doc: &'a Value,
+    value: &'a Value,
+    location: &str,
+    uses: &mut Vec<ExampleUse<'a>>,
+) {
+    match value {
+        Value::Object(object) => {
+            if let Some(schema) = object.get("schema") {
+                let schema_location = format!("{location}/schema");
+                let target = ref_target(schema).unwrap_or(&schema_location).to_string();
+                if let Some(example) = object.get("example") {
+                    uses.push(ExampleUse {
+                        location: format!("{location}/example"),
+                        example,
+                        schema: target.clone(),
+                    });
+                }
+                if let Some(examples) = object.get("examples").and_then(Value::as_object) {
+                    for (name, entry) in examples {
+                        let entry = resolve_ref(doc, entry);
+                        if let Some(example) = entry.get("value") {
+                            uses.push(ExampleUse {
+                                location: format!("{location}/examples/{}/value", escape(name)),
+                                example,
+                                schema: target.clone(),
+                            });
+                        }
+                    }
+                }
+                collect_schema_examples(schema, &schema_location, uses);
+            }
+            for (key, child) in object {
+                let child_location = format!("{location}/{}", escape(key));
+                if location == "#/components" && key == "schemas" {
+                    if let Some(schemas) = child.as_object() {
+                        for (name, schema) in schemas {
+                            let schema_location = format!("{child_location}/{}", escape(name));
+                            collect_schema_examples(schema, &schema_location, uses);
+                        }
+                    }
+                } else if key != "schema" && key != "examples" && key != "example" {
+                    collect_examples(doc, child, &child_location, uses);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                collect_examples(doc, child, &format!("{location}/{index}"), uses);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_schema_examples<'a>(schema: &'a Value, location: &str, uses: &mut Vec<ExampleUse<'a>>) {
+    let Some(object) = schema.as_object() else {
+        return;
+    };
+    if let Some(example) = object.get("example") {
+        uses.push(ExampleUse {
+            location: format!("{location}/example"),
+            example,
+            schema: ref_target(schema).unwrap_or(location).to_string(),
+        });
+    }
+    for keyword in SCHEMA_KEYWORDS {
+        if let Some(child) = object.get(keyword) {
+            let child_location = format!("{location}/{}", escape(keyword));
+            match child {
+                Value::Array(items) => {
+                    for (index, item) in items.iter().enumerate() {
+                        collect_schema_examples(item, &format!("{child_location}/{index}"), uses);
+                    }
+                }
+                _ => collect_schema_examples(child, &child_location, uses),
+            }
+        }
+    }
+    for keyword in SCHEMA_MAPS {
+        if let Some(children) = object.get(keyword).and_then(Value::as_object) {
+            for (name, child) in children {
+                let child_location = format!("{location}/{}/{}", escape(keyword), escape(name));
+                collect_schema_examples(child, &child_location, uses);
+            }
+        }
+    }
+    for keyword in SCHEMA_LISTS {
+        if let Some(children) = object.get(keyword).and_then(Value::as_array) {
+            for (index, child) in children.iter().enumerate() {
+                collect_schema_examples(child, &format!("{location}/{keyword}/{index}"), uses);
+            }
+        }
+    }
+}
+
+/// The local target of a schema that is only a `$ref`, so schemas reached
+/// through the same ref share one compiled validator.
+fn ref_target(schema: &Value) -> Option<&str> {
+    let object = schema.as_object()?;
+    if object.len() != 1 {
+        return None;
+    }
+    object
+        .get("$ref")?
+        .as_str()
+        .filter(|target| target.starts_with("#/"))
+}
+
+/// Percent-encodes the characters a URI fragment cannot hold, such as the
+/// braces of path templates.
+fn encode_fragment(pointer: &str) -> String {
+    let mut out = String::with_capacity(pointer.len());
+    for byte in pointer.bytes() {
+        let c = char::from(byte);
+        if c.is_ascii_alphanumeric() || "#-._~/!$&'()*+,;=:@".contains(c) {
+            out.push(c);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+/// `doc` without refs to other documents, which are not fetched, and with
+/// OpenAPI 3.0's `nullable: true` written as a `null` entry in `type`,
+/// which draft 4 understands.
+fn as_json_schema(doc: &Value, draft: Draft) -> Value {
+    fn rewrite(value: &mut Value, nullable: bool) {
+        match value {
+            Value::Object(object) => {
+                let external = object
+                    .get("$ref")
+                    .and_then(Value::as_str)
+                    .is_some_and(|target| !target.starts_with('#'));
+                if external {
+                    object.remove("$ref");
+                }
+                if nullable
+                    && object.get("nullable") == Some(&Value::Bool(true))
+                    && let Some(Value::String(kind)) = object.get("type")
+                {
+                    let types = json!([kind, "null"]);
+                    object.insert("type".to_string(), types);
+                }
+                object
+                    .values_mut()
+                    .for_each(|child| rewrite(child, nullable));
+            }
+            Value::Array(items) => items.iter_mut().for_each(|child| rewrite(child, nullable)),
+            _ => {}
+        }
+    }
+    let mut doc = doc.clone();
+    rewrite(&mut doc, draft == Draft::Draft4);
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Value {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/validate/examples.json");
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn conforming_examples_pass_and_violating_ones_are_located() {
+        let mismatches: Vec<String> = check_examples(&fixture())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            mismatches,
+            vec![
+                "#/components/schemas/User/example example does not match its schema: \
+                 \"forty-two\" is not of type \"integer\" (at /age)",
+                "#/paths/~1users~1{id}/get/responses/200/content/application~1json/examples/stale/value \
+                 example does not match its schema: \"name\" is a required property",
+            ]
+        );
+    }
+
+    #[test]
+    fn nullable_and_3_1_type_arrays_accept_null() {
+        let doc = json!({
+            "openapi": "3.0.3",
+            "components": {"schemas": {
+                "Name": {"type": "string", "nullable": true, "example": null}
+            }}
+        });
+        assert!(check_examples(&doc).is_empty());
+
+        let doc = json!({
+            "openapi": "3.1.0",
+            "components": {"schemas": {
+                "Name": {"type": ["string", "null"], "example": null},
+                "Count": {"type": "integer", "example": null}
+            }}
+        });
+        let mismatches = check_examples(&doc);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].location, "#/components/schemas/Count/example");
+    }
+
+    #[test]
+    fn external_refs_match_anything() {
+        let doc = json!({
+            "openapi": "3.0.3",
+            "components": {"schemas": {
+                "Remote": {"$ref": "https://example.com/schemas.json#/User", "example": 1}
+            }}
+        });
+        assert_eq!(check_examples(&doc), vec![]);
+    }
+}
@@ -1,54 +1,163 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+#[cfg(feature = "http")]
 use std::thread;
 use std::time::Duration;
+#[cfg(feature = "http")]
+use std::time::Instant;
 
+#[cfg(feature = "http")]
 use reqwest::blocking::Client;
+#[cfg(feature = "http")]
 use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
 use serde_json::Value;
 
 use crate::config::Config;
 use crate::errors::AppError;
+use crate::format::{format_from_path, parse_document};
+#[cfg(feature = "http")]
+use crate::progress::ProgressLine;
+#[cfg(feature = "http")]
+use crate::verbosity::Verbosity;
 
+#[cfg(feature = "http")]
 const USER_AGENT: &str = concat!("openapi-snapshot/", env!("CARGO_PKG_VERSION"));
-const MAX_RETRIES: usize = 3;
-const BASE_BACKOFF_MS: u64 = 100;
+#[cfg(feature = "http")]
+pub(crate) const MAX_RETRIES: usize = 3;
+#[cfg(feature = "http")]
+pub(crate) const BASE_BACKOFF_MS: u64 = 100;
+#[cfg(feature = "http")]
 const MAX_BACKOFF_MS: u64 = 2_000;
+#[cfg(feature = "http")]
 const ERROR_SNIPPET_LIMIT: usize = 256;
+/// How much of a body is read between progress reports.
+#[cfg(feature = "http")]
+const CHUNK_BYTES: usize = 64 * 1024;
+/// The most a `Content-Length` reserves up front, so a wrong header cannot
+/// make the fetch allocate far more than it receives.
+#[cfg(feature = "http")]
+const MAX_PREALLOCATE_BYTES: u64 = 256 * 1024 * 1024;
+/// Input path that stands for standard input.
+pub const STDIN_PATH: &str = "-";
+
+/// A successful response: its HTTP status, raw body, and the freshness
+/// lifetime from `Cache-Control: max-age`, if the server allows caching.
+#[derive(Debug)]
+pub struct FetchResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+    pub max_age: Option<Duration>,
+}
+
+/// Why a URL cannot be fetched when the crate is built without the `http`
+/// feature.
+pub const NO_HTTP_SUPPORT: &str =
+    "built without HTTP support; read a local file with --input instead.";
+
+#[cfg(not(feature = "http"))]
+pub fn fetch_openapi_response(config: &Config) -> Result<FetchResponse, AppError> {
+    fetch_openapi_response_with_progress(config, &mut |_, _| {})
+}
+
+#[cfg(not(feature = "http"))]
+pub fn fetch_openapi_response_with_progress(
+    config: &Config,
+    _progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<FetchResponse, AppError> {
+    Err(AppError::usage(format!(
+        "{}: {NO_HTTP_SUPPORT}",
+        config.url
+    )))
+}
 
 pub fn fetch_openapi(config: &Config) -> Result<Vec<u8>, AppError> {
+    Ok(fetch_openapi_response(config)?.body)
+}
+
+/// Fetches the document, drawing a progress line on stderr when it is a
+/// terminal and the download takes a while.
+#[cfg(feature = "http")]
+pub fn fetch_openapi_response(config: &Config) -> Result<FetchResponse, AppError> {
+    let mut line = ProgressLine::for_stderr(config.verbosity);
+    fetch_openapi_response_with_progress(config, &mut |downloaded, total| {
+        if let Some(line) = &mut line {
+            line.update(downloaded, total);
+        }
+    })
+}
+
+/// Fetches the document, calling `progress` with the bytes received so far
+/// and the `Content-Length`, if the server sent one, as the body streams
+/// in. A retried attempt starts again from zero.
+#[cfg(feature = "http")]
+pub fn fetch_openapi_response_with_progress(
+    config: &Config,
+    progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<FetchResponse, AppError> {
     let headers = build_headers(&config.headers)?;
+    let verbosity = config.verbosity;
+    log_headers(verbosity, ">", &headers);
     let client = Client::builder()
         .timeout(Duration::from_millis(config.timeout_ms))
         .default_headers(headers)
         .build()
-        .map_err(|err| AppError::Network(format!("client error: {err}")))?;
+        .map_err(|err| AppError::network(format!("client error: {err}")).with_source(err))?;
 
     let mut backoff = BASE_BACKOFF_MS;
     let mut attempt = 0;
     loop {
         attempt += 1;
+        verbosity.verbose(format!("GET {} (attempt {attempt})", config.url));
+        let started = Instant::now();
         match client.get(&config.url).send() {
-            Ok(response) => {
+            Ok(mut response) => {
                 let status = response.status();
+                verbosity.verbose(format!(
+                    "HTTP {status} in {} ms",
+                    started.elapsed().as_millis()
+                ));
+                log_headers(verbosity, "<", response.headers());
                 if !status.is_success() {
                     let snippet = body_snippet(response.text().unwrap_or_default());
                     let message = format!("HTTP {status}: {snippet}");
                     if should_retry_status(status) && attempt < MAX_RETRIES {
+                        verbosity.verbose(format!("retrying in {backoff} ms"));
                         sleep(backoff);
                         backoff = next_backoff(backoff);
                         continue;
                     }
-                    return Err(AppError::Network(message));
+                    return Err(AppError::http(status.as_u16(), config.url.clone(), message));
                 }
 
-                match response.bytes() {
-                    Ok(bytes) => return Ok(bytes.to_vec()),
+                let max_age = response
+                    .headers()
+                    .get(header::CACHE_CONTROL)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(parse_max_age);
+                let total = response.content_length();
+                match read_body(&mut response, total, progress) {
+                    Ok(body) => {
+                        verbosity.verbose(format!("received {} bytes", body.len()));
+                        return Ok(FetchResponse {
+                            status: status.as_u16(),
+                            body,
+                            max_age,
+                        });
+                    }
                     Err(err) => {
-                        if is_retryable_error(&err) && attempt < MAX_RETRIES {
+                        // reqwest wraps its own errors, timeouts among them,
+                        // in the io errors its reader returns.
+                        let (retryable, err) = match err.downcast::<reqwest::Error>() {
+                            Ok(err) => (is_retryable_error(&err), read_failure(err)),
+                            Err(err) => (err.kind() == io::ErrorKind::TimedOut, read_failure(err)),
+                        };
+                        if retryable && attempt < MAX_RETRIES {
                             sleep(backoff);
                             backoff = next_backoff(backoff);
                             continue;
                         }
-                        return Err(AppError::Network(format!("failed to read response: {err}")));
+                        return Err(err);
                     }
                 }
             }
@@ -58,17 +167,93 @@ pub fn fetch_openapi(config: &Config) -> Result<Vec<u8>, AppError> {
                     backoff = next_backoff(backoff);
                     continue;
                 }
-                return Err(AppError::Network(format!("request failed: {err}")));
+                return Err(AppError::from(err));
             }
         }
     }
 }
 
+/// Reads the whole body in chunks, reporting the bytes read after each.
+#[cfg(feature = "http")]
+fn read_body(
+    reader: &mut impl Read,
+    total: Option<u64>,
+    progress: &mut dyn FnMut(u64, Option<u64>),
+) -> io::Result<Vec<u8>> {
+    let reserve = total.map_or(0, |total| total.min(MAX_PREALLOCATE_BYTES));
+    let mut body = Vec::with_capacity(usize::try_from(reserve).unwrap_or(0));
+    let mut chunk = vec![0; CHUNK_BYTES];
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => return Ok(body),
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        body.extend_from_slice(&chunk[..read]);
+        progress(body.len() as u64, total);
+    }
+}
+
+#[cfg(feature = "http")]
+fn read_failure(err: impl std::error::Error + Send + Sync + 'static) -> AppError {
+    AppError::network(format!("failed to read response: {err}")).with_source(err)
+}
+
+/// The HTTP status of an error produced by a non-success response, if any.
+pub fn http_status(err: &AppError) -> Option<u16> {
+    match err {
+        AppError::Http { status, .. } => Some(*status),
+        _ => None,
+    }
+}
+
+/// Reads a local document; `-` reads standard input.
+pub fn read_openapi_file(path: &Path) -> Result<Vec<u8>, AppError> {
+    if path == Path::new(STDIN_PATH) {
+        let mut bytes = Vec::new();
+        return io::stdin()
+            .read_to_end(&mut bytes)
+            .map(|_| bytes)
+            .map_err(|err| {
+                AppError::io(format!("failed to read standard input: {err}")).with_source(err)
+            });
+    }
+    fs::read(path).map_err(|err| {
+        AppError::io(format!("failed to read {}: {err}", path.display())).with_source(err)
+    })
+}
+
+/// Loads a document from an http(s) URL, using `config`'s headers and
+/// timeout, or from a file path, which may be YAML.
+pub fn load_document(config: &Config, source: &str) -> Result<Value, AppError> {
+    load_source(config, source).map(|(_, doc)| doc)
+}
+
+/// Like [`load_document`], also returning the bytes as read, for comparisons
+/// that care about formatting.
+pub fn load_source(config: &Config, source: &str) -> Result<(Vec<u8>, Value), AppError> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let bytes = fetch_openapi(&Config {
+            url: source.to_string(),
+            ..config.clone()
+        })?;
+        let doc = parse_json(&bytes)?;
+        return Ok((bytes, doc));
+    }
+    let path = Path::new(source);
+    let bytes = read_openapi_file(path)?;
+    let doc = parse_document(&bytes, format_from_path(path))?;
+    Ok((bytes, doc))
+}
+
 pub fn parse_json(bytes: &[u8]) -> Result<Value, AppError> {
-    serde_json::from_slice(bytes).map_err(|err| AppError::Json(format!("invalid JSON: {err}")))
+    serde_json::from_slice(bytes)
+        .map_err(|err| AppError::json(format!("invalid JSON: {err}")).with_source(err))
 }
 
-fn build_headers(raw_headers: &[String]) -> Result<HeaderMap, AppError> {
+#[cfg(feature = "http")]
+pub(crate) fn build_headers(raw_headers: &[String]) -> Result<HeaderMap, AppError> {
     let mut headers = HeaderMap::new();
     headers.insert(header::ACCEPT, HeaderValue::from_static("application/json"));
     headers.insert(header::USER_AGENT, HeaderValue::from_static(USER_AGENT));
@@ -80,41 +265,100 @@ fn build_headers(raw_headers: &[String]) -> Result<HeaderMap, AppError> {
     Ok(headers)
 }
 
+#[cfg(feature = "http")]
 fn parse_header(raw: &str) -> Result<(HeaderName, HeaderValue), AppError> {
     let mut split = raw.splitn(2, ':');
     let name = split
         .next()
         .map(str::trim)
         .filter(|value| !value.is_empty())
-        .ok_or_else(|| AppError::Usage(format!("invalid header format: {raw}")))?;
+        .ok_or_else(|| AppError::usage(format!("invalid header format: {raw}")))?;
     let value = split
         .next()
         .map(str::trim)
-        .ok_or_else(|| AppError::Usage(format!("invalid header format: {raw}")))?;
+        .ok_or_else(|| AppError::usage(format!("invalid header format: {raw}")))?;
     let header_name = HeaderName::from_bytes(name.as_bytes())
-        .map_err(|_| AppError::Usage(format!("invalid header name: {name}")))?;
+        .map_err(|_| AppError::usage(format!("invalid header name: {name}")))?;
     let header_value = HeaderValue::from_str(value)
-        .map_err(|_| AppError::Usage(format!("invalid header value for: {name}")))?;
+        .map_err(|_| AppError::usage(format!("invalid header value for: {name}")))?;
     Ok((header_name, header_value))
 }
 
-fn is_retryable_error(err: &reqwest::Error) -> bool {
+/// Header names whose values are replaced by `***` in logs and dry-run
+/// output.
+const SECRET_HEADER_HINTS: [&str; 6] = ["auth", "cookie", "token", "secret", "key", "password"];
+
+/// Redacts the value of a `Name: value` header if the name suggests a
+/// credential.
+pub(crate) fn mask_header(header: &str) -> String {
+    let Some((name, value)) = header.split_once(':') else {
+        return header.to_string();
+    };
+    let lower = name.to_ascii_lowercase();
+    if SECRET_HEADER_HINTS.iter().any(|hint| lower.contains(hint)) {
+        format!("{}: ***", name.trim())
+    } else {
+        format!("{}: {}", name.trim(), value.trim())
+    }
+}
+
+#[cfg(feature = "http")]
+pub(crate) fn log_headers(verbosity: Verbosity, prefix: &str, headers: &HeaderMap) {
+    if !verbosity.shows(Verbosity::Debug) {
+        return;
+    }
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        verbosity.debug(format!(
+            "{prefix} {}",
+            mask_header(&format!("{name}: {value}"))
+        ));
+    }
+}
+
+/// `max-age` from a Cache-Control value, ignored when `no-cache` or
+/// `no-store` is present.
+#[cfg(feature = "http")]
+pub(crate) fn parse_max_age(value: &str) -> Option<Duration> {
+    let mut max_age = None;
+    for directive in value.split(',') {
+        let directive = directive.trim().to_ascii_lowercase();
+        if directive == "no-cache" || directive == "no-store" {
+            return None;
+        }
+        if let Some(seconds) = directive.strip_prefix("max-age=") {
+            max_age = seconds
+                .trim_matches('"')
+                .parse()
+                .ok()
+                .map(Duration::from_secs);
+        }
+    }
+    max_age
+}
+
+#[cfg(feature = "http")]
+pub(crate) fn is_retryable_error(err: &reqwest::Error) -> bool {
     err.is_timeout() || err.is_connect() || err.is_body()
 }
 
-fn should_retry_status(status: reqwest::StatusCode) -> bool {
+#[cfg(feature = "http")]
+pub(crate) fn should_retry_status(status: reqwest::StatusCode) -> bool {
     status.as_u16() == 429 || status.is_server_error()
 }
 
-fn next_backoff(current: u64) -> u64 {
+#[cfg(feature = "http")]
+pub(crate) fn next_backoff(current: u64) -> u64 {
     (current.saturating_mul(2)).min(MAX_BACKOFF_MS)
 }
 
+#[cfg(feature = "http")]
 fn sleep(duration_ms: u64) {
     thread::sleep(Duration::from_millis(duration_ms));
 }
 
-fn body_snippet(body: String) -> String {
+#[cfg(feature = "http")]
+pub(crate) fn body_snippet(body: String) -> String {
     let trimmed = body.trim();
     if trimmed.is_empty() {
         return String::from("<empty body>");
@@ -132,23 +376,55 @@ mod tests {
     use super::*;
     use crate::cli::OutputProfile;
     use crate::config::Config;
+    use crate::outline::OutlineOptions;
+    use crate::validate::ValidateOptions;
+    #[cfg(feature = "http")]
     use httpmock::prelude::*;
 
+    #[test]
+    fn mask_header_hides_credential_values() {
+        assert_eq!(
+            mask_header("Authorization: Bearer abc"),
+            "Authorization: ***"
+        );
+        assert_eq!(mask_header("X-Api-Key:abc"), "X-Api-Key: ***");
+        assert_eq!(mask_header("Cookie: session=1"), "Cookie: ***");
+        assert_eq!(mask_header("X-Team: api"), "X-Team: api");
+    }
+
     fn base_config(url: String) -> Config {
         Config {
+            name: None,
             url,
             url_from_default: false,
+            doc_path: crate::cli::DEFAULT_PATH.to_string(),
+            input: None,
             out: None,
             outline_out: None,
+            temp_dir: None,
             reduce: Vec::new(),
             profile: OutputProfile::Full,
+            outline: OutlineOptions::default(),
             minify: false,
             timeout_ms: 5_000,
             headers: Vec::new(),
             stdout: true,
+            verbosity: crate::verbosity::Verbosity::Normal,
+            validate: false,
+            color: crate::cli::ColorChoice::Auto,
+            fallback_to_last: None,
+            allow_empty: false,
+            strict_bytes: false,
+            verify_refs: None,
+            validate_options: ValidateOptions::default(),
+            require_version: None,
+            check_paths: false,
+            duplicate_keys: None,
+            transforms: crate::transform::Pipeline::default(),
         }
     }
 
+    #[cfg(feature = "http")]
     #[test]
     fn fetch_includes_default_and_custom_headers() {
         let server = MockServer::start();
@@ -174,6 +450,43 @@ mod tests {
         mock.assert_hits(1);
     }
 
+    #[cfg(feature = "http")]
+    #[test]
+    fn progress_counts_up_to_the_content_length() {
+        let body = format!(r#"{{"openapi":"3.0.3","x":"{}"}}"#, "a".repeat(200_000));
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(200).body(&body);
+        });
+
+        let mut reports = Vec::new();
+        let response = fetch_openapi_response_with_progress(
+            &base_config(server.url("/openapi.json")),
+            &mut |downloaded, total| reports.push((downloaded, total)),
+        )
+        .unwrap();
+        assert_eq!(response.body, body.as_bytes());
+        let length = body.len() as u64;
+        assert!(reports.len() > 1, "{reports:?}");
+        assert!(reports.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(reports.iter().all(|&(_, total)| total == Some(length)));
+        assert_eq!(reports.last(), Some(&(length, Some(length))));
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn read_body_reports_unknown_lengths() {
+        let mut reports = Vec::new();
+        let body = read_body(&mut &[7u8; 100_000][..], None, &mut |downloaded, total| {
+            reports.push((downloaded, total))
+        })
+        .unwrap();
+        assert_eq!(body.len(), 100_000);
+        assert_eq!(reports, vec![(65_536, None), (100_000, None)]);
+    }
+
+    #[cfg(feature = "http")]
     #[test]
     fn retries_on_server_error_then_succeeds() {
         use std::sync::atomic::{AtomicUsize, Ordering};
@@ -205,6 +518,7 @@ mod tests {
         assert!(success.hits() >= 1);
     }
 
+    #[cfg(feature = "http")]
     #[test]
     fn fetch_surfaces_status_and_body_snippet() {
         let server = MockServer::start();
@@ -216,14 +530,20 @@ mod tests {
         let config = base_config(server.url("/openapi.json"));
         let err = fetch_openapi(&config).unwrap_err();
         match err {
-            AppError::Network(msg) => {
+            AppError::Http {
+                status,
+                message: msg,
+                ..
+            } => {
+                assert_eq!(status, 502);
                 assert!(msg.contains("502"));
                 assert!(msg.contains("gateway down"));
             }
-            other => panic!("expected network error, got {other:?}"),
+            other => panic!("expected HTTP error, got {other:?}"),
         }
     }
 
+    #[cfg(feature = "http")]
     #[test]
     fn returns_error_with_status_and_snippet_when_retries_exhausted() {
         let server = MockServer::start();
@@ -240,6 +560,7 @@ mod tests {
         fail.assert_hits(MAX_RETRIES);
     }
 
+    #[cfg(feature = "http")]
     #[test]
     fn stops_after_max_retries_and_returns_error() {
         let server = MockServer::start();
@@ -254,6 +575,7 @@ mod tests {
         mock.assert_hits(MAX_RETRIES);
     }
 
+    #[cfg(feature = "http")]
     #[test]
     fn error_includes_body_snippet() {
         let server = MockServer::start();
@@ -265,12 +587,109 @@ mod tests {
         let config = base_config(server.url("/openapi.json"));
         let err = fetch_openapi(&config).unwrap_err();
         match err {
-            AppError::Network(msg) => {
+            AppError::Http {
+                status,
+                message: msg,
+                ..
+            } => {
+                assert_eq!(status, 400);
                 assert!(msg.contains("400"));
                 assert!(msg.contains("something went wrong in backend"));
             }
-            other => panic!("expected network error, got {other:?}"),
+            other => panic!("expected HTTP error, got {other:?}"),
         }
         mock.assert_hits(1);
     }
+
+    #[cfg(not(feature = "http"))]
+    #[test]
+    fn urls_need_http_support() {
+        let config = base_config("http://localhost:3000/openapi.json".to_string());
+        let err = fetch_openapi(&config).unwrap_err();
+        assert!(matches!(err, AppError::Usage { .. }));
+        assert_eq!(
+            err.to_string(),
+            format!("http://localhost:3000/openapi.json: {NO_HTTP_SUPPORT}")
+        );
+        let err = load_document(&config, "https://example.com/openapi.json").unwrap_err();
+        assert!(matches!(err, AppError::Usage { .. }));
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn connection_refused_is_unreachable() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = base_config(format!("http://127.0.0.1:{port}/openapi.json"));
+        let err = fetch_openapi(&config).unwrap_err();
+        assert!(err.is_unreachable(), "got {err:?}");
+        assert!(err.is_url_related());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn http_error_status_is_not_unreachable() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(401).body("unauthorized");
+        });
+        let config = base_config(server.url("/openapi.json"));
+        let err = fetch_openapi(&config).unwrap_err();
+        assert!(!err.is_unreachable());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn http_status_is_read_from_status_errors_only() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(404).body("missing");
+        });
+        let config = base_config(server.url("/openapi.json"));
+        let err = fetch_openapi(&config).unwrap_err();
+        assert_eq!(http_status(&err), Some(404));
+        assert_eq!(
+            http_status(&AppError::network("request failed: reset".to_string())),
+            None
+        );
+        assert_eq!(
+            http_status(&AppError::json("invalid JSON".to_string())),
+            None
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn parse_max_age_respects_no_cache_and_no_store() {
+        assert_eq!(parse_max_age("max-age=60"), Some(Duration::from_secs(60)));
+        assert_eq!(
+            parse_max_age("public, Max-Age=120"),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse_max_age("no-cache, max-age=60"), None);
+        assert_eq!(parse_max_age("max-age=60, no-store"), None);
+        assert_eq!(parse_max_age("public"), None);
+        assert_eq!(parse_max_age("max-age=soon"), None);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn fetch_reports_cache_control_max_age() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(200)
+                .header("cache-control", "public, max-age=60")
+                .body(r#"{"openapi":"3.0.3","paths":{},"components":{}}"#);
+        });
+        let config = base_config(server.url("/openapi.json"));
+        let response = fetch_openapi_response(&config).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.max_age, Some(Duration::from_secs(60)));
+    }
 }
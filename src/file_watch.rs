@@ -0,0 +1,234 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+use std::time::{Duration, SystemTime};
+
+#[cfg(not(target_arch = "wasm32"))]
+use notify::event::{EventKind, ModifyKind};
+#[cfg(not(target_arch = "wasm32"))]
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::errors::AppError;
+
+/// Filesystem notifications for a single `--input` file.
+///
+/// The watch is placed on the parent directory rather than the file, so
+/// tools that replace the file (write a temp file, then rename it over) or
+/// delete and recreate it keep triggering. If the directory itself goes
+/// away the watch breaks and must be registered again.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    dir: PathBuf,
+    name: OsString,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileWatcher {
+    pub fn new(path: &Path) -> Result<Self, AppError> {
+        let (dir, name) = split_path(path)?;
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(|err| {
+            AppError::io(format!("failed to start file watcher: {err}")).with_source(err)
+        })?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|err| {
+                AppError::io(format!("failed to watch {}: {err}", dir.display())).with_source(err)
+            })?;
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            dir,
+            name,
+        })
+    }
+
+    /// Waits up to `timeout` for the file to be written, replaced, or
+    /// created. Returns `Ok(false)` on timeout and an error once the watch is
+    /// no longer valid.
+    pub fn wait_for_change(&mut self, timeout: Duration) -> Result<bool, AppError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let event = match self.events.recv_timeout(remaining) {
+                Ok(Ok(event)) => event,
+                Ok(Err(err)) => return Err(AppError::io(format!("file watcher error: {err}"))),
+                Err(RecvTimeoutError::Timeout) => return Ok(false),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(AppError::io("file watcher stopped".to_string()));
+                }
+            };
+            if matches!(event.kind, EventKind::Remove(_))
+                && event.paths.iter().any(|path| path == &self.dir)
+            {
+                return Err(AppError::io(format!("{} was removed", self.dir.display())));
+            }
+            if is_write(&event.kind)
+                && event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == Some(self.name.as_os_str()))
+            {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+/// wasm32 has no filesystem notifications, so watch mode always falls back
+/// to [`MtimePoller`].
+#[cfg(target_arch = "wasm32")]
+pub struct FileWatcher(std::convert::Infallible);
+
+#[cfg(target_arch = "wasm32")]
+impl FileWatcher {
+    pub fn new(_path: &Path) -> Result<Self, AppError> {
+        Err(AppError::io(
+            "file notifications are not available on wasm32".to_string(),
+        ))
+    }
+
+    pub fn wait_for_change(&mut self, _timeout: Duration) -> Result<bool, AppError> {
+        match self.0 {}
+    }
+}
+
+/// Fallback for filesystems without working notifications: compares the
+/// file's modification time and size between checks.
+pub struct MtimePoller {
+    path: PathBuf,
+    last: Option<(SystemTime, u64)>,
+}
+
+impl MtimePoller {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            last: stamp(path),
+        }
+    }
+
+    /// True when the file appeared, disappeared, or changed since the last
+    /// check. A removal counts so the watch loop polls and reports the
+    /// missing `--input`.
+    pub fn changed(&mut self) -> bool {
+        let current = stamp(&self.path);
+        let changed = current != self.last;
+        self.last = current;
+        changed
+    }
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Creations and content or name changes count; reads (our own included)
+/// and removals do not, so a delete-then-recreate triggers once, on create.
+#[cfg(not(target_arch = "wasm32"))]
+fn is_write(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Create(_) | EventKind::Any | EventKind::Other => true,
+        EventKind::Modify(ModifyKind::Metadata(_)) => false,
+        EventKind::Modify(_) => true,
+        EventKind::Access(_) | EventKind::Remove(_) => false,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn split_path(path: &Path) -> Result<(PathBuf, OsString), AppError> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| AppError::usage(format!("--input {} is not a file", path.display())))?
+        .to_os_string();
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    Ok((dir, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn watcher_reports_writes_and_replacements() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("openapi.json");
+        fs::write(&path, "{}").unwrap();
+        let mut watcher = FileWatcher::new(&path).unwrap();
+
+        fs::write(temp.path().join("other.json"), "{}").unwrap();
+        assert!(!watcher.wait_for_change(Duration::from_millis(300)).unwrap());
+
+        fs::write(&path, "{\"a\":1}").unwrap();
+        assert!(watcher.wait_for_change(WAIT).unwrap());
+        while watcher.wait_for_change(Duration::from_millis(200)).unwrap() {}
+
+        let staged = temp.path().join(".openapi.json.tmp");
+        fs::write(&staged, "{\"a\":2}").unwrap();
+        fs::rename(&staged, &path).unwrap();
+        assert!(watcher.wait_for_change(WAIT).unwrap());
+    }
+
+    #[test]
+    fn watcher_survives_delete_and_recreate() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("openapi.json");
+        fs::write(&path, "{}").unwrap();
+        let mut watcher = FileWatcher::new(&path).unwrap();
+
+        fs::remove_file(&path).unwrap();
+        assert!(!watcher.wait_for_change(Duration::from_millis(300)).unwrap());
+        fs::write(&path, "{\"a\":1}").unwrap();
+        assert!(watcher.wait_for_change(WAIT).unwrap());
+    }
+
+    #[test]
+    fn mtime_poller_detects_content_changes() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("openapi.json");
+        let mut poller = MtimePoller::new(&path);
+        assert!(!poller.changed());
+
+        fs::write(&path, "{}").unwrap();
+        assert!(poller.changed());
+        assert!(!poller.changed());
+
+        fs::write(&path, "{\"longer\":true}").unwrap();
+        assert!(poller.changed());
+    }
+
+    #[test]
+    fn mtime_poller_reports_removal_once() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("openapi.json");
+        fs::write(&path, "{}").unwrap();
+        let mut poller = MtimePoller::new(&path);
+
+        fs::remove_file(&path).unwrap();
+        assert!(poller.changed());
+        assert!(!poller.changed());
+
+        fs::write(&path, "{}").unwrap();
+        assert!(poller.changed());
+    }
+
+    #[test]
+    fn split_path_defaults_to_current_directory() {
+        let (dir, name) = split_path(Path::new("openapi.json")).unwrap();
+        assert_eq!(dir, PathBuf::from("."));
+        assert_eq!(name, OsString::from("openapi.json"));
+    }
+}
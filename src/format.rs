@@ -0,0 +1,134 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::cli::DocFormat;
+use crate::config::Config;
+use crate::errors::AppError;
+use crate::fetch::{parse_json, read_openapi_file};
+use crate::output::serialize_json;
+
+/// The format a file extension names: `.json`, or `.yaml`/`.yml`.
+pub fn format_from_path(path: &Path) -> Option<DocFormat> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "json" => Some(DocFormat::Json),
+        "yaml" | "yml" => Some(DocFormat::Yaml),
+        _ => None,
+    }
+}
+
+/// Parses a document in `format`. Without one, JSON is tried first and a
+/// YAML mapping is accepted in its place; anything else reports the JSON
+/// error.
+pub fn parse_document(bytes: &[u8], format: Option<DocFormat>) -> Result<Value, AppError> {
+    match format {
+        Some(DocFormat::Json) => parse_json(bytes),
+        Some(DocFormat::Yaml) => parse_yaml(bytes),
+        None => parse_json(bytes).or_else(|json_err| match parse_yaml(bytes) {
+            Ok(value @ Value::Object(_)) => Ok(value),
+            _ => Err(json_err),
+        }),
+    }
+}
+
+/// Serializes `value` as JSON (pretty unless `minify`) or YAML, without a
+/// trailing newline.
+pub fn serialize_document(
+    value: &Value,
+    format: DocFormat,
+    minify: bool,
+) -> Result<String, AppError> {
+    match format {
+        DocFormat::Json => serialize_json(value, minify),
+        DocFormat::Yaml => serde_yaml_ng::to_string(value)
+            .map(|text| text.trim_end().to_string())
+            .map_err(|err| AppError::json(format!("yaml error: {err}")).with_source(err)),
+    }
+}
+
+/// Reads `--input` and renders it in the output format for `convert`. The
+/// output format must come from `to` or the `--out` extension.
+pub fn convert_document(
+    config: &Config,
+    from: Option<DocFormat>,
+    to: Option<DocFormat>,
+) -> Result<String, AppError> {
+    let out = config.out.as_deref().filter(|_| !config.stdout);
+    let Some(to) = to.or_else(|| out.and_then(format_from_path)) else {
+        return Err(AppError::usage(match out {
+            Some(path) => format!(
+                "cannot tell the output format from {}; pass --to json or --to yaml.",
+                path.display()
+            ),
+            None => "pass --to json or --to yaml when converting to stdout.".to_string(),
+        }));
+    };
+    let input = config
+        .input
+        .as_deref()
+        .ok_or_else(|| AppError::usage("convert needs an input file.".to_string()))?;
+    let doc = parse_document(
+        &read_openapi_file(input)?,
+        from.or_else(|| format_from_path(input)),
+    )?;
+    serialize_document(&doc, to, config.minify)
+}
+
+fn parse_yaml(bytes: &[u8]) -> Result<Value, AppError> {
+    serde_yaml_ng::from_slice(bytes)
+        .map_err(|err| AppError::json(format!("invalid YAML: {err}")).with_source(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture() -> Value {
+        json!({
+            "openapi": "3.0.3",
+            "info": {"title": "Demo: \"quoted\"", "version": "1.0"},
+            "paths": {
+                "/users/{id}": {
+                    "get": {
+                        "parameters": [{"name": "id", "in": "path", "required": true}],
+                        "responses": {"200": {"description": "ok"}}
+                    }
+                }
+            },
+            "x-numbers": {"int": 9007199254740993_u64, "float": 0.1, "negative": -3, "null": null},
+            "x-strings": ["yes", "no", "on", "1.0", "", "multi\nline"]
+        })
+    }
+
+    #[test]
+    fn format_from_path_reads_known_extensions() {
+        assert_eq!(format_from_path(Path::new("a.json")), Some(DocFormat::Json));
+        assert_eq!(format_from_path(Path::new("a.YAML")), Some(DocFormat::Yaml));
+        assert_eq!(format_from_path(Path::new("a.yml")), Some(DocFormat::Yaml));
+        assert_eq!(format_from_path(Path::new("a.txt")), None);
+        assert_eq!(format_from_path(Path::new("-")), None);
+    }
+
+    #[test]
+    fn json_to_yaml_to_json_round_trips() {
+        let original = fixture();
+        let yaml = serialize_document(&original, DocFormat::Yaml, false).unwrap();
+        let parsed = parse_document(yaml.as_bytes(), Some(DocFormat::Yaml)).unwrap();
+        assert_eq!(parsed, original);
+        let json = serialize_document(&parsed, DocFormat::Json, true).unwrap();
+        assert_eq!(parse_document(json.as_bytes(), None).unwrap(), original);
+    }
+
+    #[test]
+    fn unknown_format_accepts_yaml_mappings_only() {
+        let value = parse_document(b"openapi: 3.0.3\npaths: {}\n", None).unwrap();
+        assert_eq!(value, json!({"openapi": "3.0.3", "paths": {}}));
+        let err = parse_document(b"not json", None).unwrap_err();
+        assert!(err.to_string().starts_with("invalid JSON"));
+        assert_eq!(err.exit_code(), 2);
+        let err = parse_document(b"a: [", Some(DocFormat::Yaml)).unwrap_err();
+        assert!(err.to_string().starts_with("invalid YAML"));
+    }
+}
@@ -0,0 +1,144 @@
+use std::process::{Child, Command, Stdio};
+
+use crate::config::Config;
+
+/// Runs the `--on-change` command after a snapshot write. Executions never
+/// overlap: a change detected while the previous run is still going is skipped.
+pub struct ChangeHook {
+    command: String,
+    running: Option<Child>,
+}
+
+impl ChangeHook {
+    pub fn new(command: String) -> Self {
+        Self {
+            command,
+            running: None,
+        }
+    }
+
+    /// Starts the command unless a previous run is still going. Returns a
+    /// message for the caller to log when the run was skipped or failed.
+    pub fn trigger(&mut self, config: &Config) -> Option<String> {
+        let finished = self.reap();
+        if self.is_running() {
+            return Some("on-change command still running; skipping this change.".to_string());
+        }
+        match shell_command(&self.command, config).spawn() {
+            Ok(child) => {
+                self.running = Some(child);
+                finished
+            }
+            Err(err) => Some(format!("failed to run on-change command: {err}")),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Collects a finished run, returning a message when it exited non-zero.
+    pub fn reap(&mut self) -> Option<String> {
+        let child = self.running.as_mut()?;
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                self.running = None;
+                exit_message(status)
+            }
+            Ok(None) => None,
+            Err(err) => {
+                self.running = None;
+                Some(format!("failed to check on-change command: {err}"))
+            }
+        }
+    }
+
+    /// Blocks until an in-flight run finishes so the process does not exit
+    /// while the command is still working.
+    pub fn wait(&mut self) -> Option<String> {
+        let mut child = self.running.take()?;
+        match child.wait() {
+            Ok(status) => exit_message(status),
+            Err(err) => Some(format!("failed to wait for on-change command: {err}")),
+        }
+    }
+}
+
+fn exit_message(status: std::process::ExitStatus) -> Option<String> {
+    if status.success() {
+        None
+    } else {
+        Some(format!("on-change command exited with {status}"))
+    }
+}
+
+fn shell_command(command: &str, config: &Config) -> Command {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    if let Some(out) = config.out.as_ref() {
+        cmd.env("SNAPSHOT_PATH", out);
+    }
+    if let Some(outline) = config.outline_out.as_ref() {
+        cmd.env("OUTLINE_PATH", outline);
+    }
+    cmd.stdin(Stdio::null());
+    cmd
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::cli::OutputProfile;
+    use crate::outline::OutlineOptions;
+    use crate::validate::ValidateOptions;
+
+    fn config() -> Config {
+        Config {
+            name: None,
+            url: "http://localhost:3000/api-docs/openapi.json".to_string(),
+            url_from_default: false,
+            doc_path: crate::cli::DEFAULT_PATH.to_string(),
+            input: None,
+            out: Some("openapi/backend_openapi.json".into()),
+            outline_out: None,
+            temp_dir: None,
+            reduce: Vec::new(),
+            profile: OutputProfile::Full,
+            outline: OutlineOptions::default(),
+            minify: false,
+            timeout_ms: 1_000,
+            headers: Vec::new(),
+            stdout: false,
+            verbosity: crate::verbosity::Verbosity::Normal,
+            validate: false,
+            color: crate::cli::ColorChoice::Auto,
+            fallback_to_last: None,
+            allow_empty: false,
+            strict_bytes: false,
+            verify_refs: None,
+            validate_options: ValidateOptions::default(),
+            require_version: None,
+            check_paths: false,
+            duplicate_keys: None,
+            transforms: crate::transform::Pipeline::default(),
+        }
+    }
+
+    #[test]
+    fn reap_reports_running_command_until_it_finishes() {
+        let mut hook = ChangeHook::new("sleep 0.3".to_string());
+        assert_eq!(hook.trigger(&config()), None);
+        assert_eq!(hook.reap(), None);
+        assert!(hook.is_running());
+        assert!(hook.trigger(&config()).unwrap().contains("skipping"));
+        hook.wait();
+        assert!(!hook.is_running());
+    }
+}
@@ -0,0 +1,192 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use toml::Value;
+
+use crate::cli::{
+    DEFAULT_INTERVAL_MS, DEFAULT_MAX_BACKOFF_MS, DEFAULT_OUT, DEFAULT_OUTLINE_OUT,
+    DEFAULT_PROMPT_GRACE, DEFAULT_REDUCE, DEFAULT_URL, InitArgs,
+};
+use crate::config_file::CONFIG_FILE_NAMES;
+use crate::errors::AppError;
+
+/// Pattern matching the temp files written next to outputs before they are
+/// renamed into place.
+pub const TEMP_FILE_PATTERN: &str = ".*.tmp";
+
+/// Scaffolds a project in `dir`: writes `openapi-snapshot.toml`, creates the
+/// output directory, and adds the temp-file pattern to an existing
+/// `.gitignore`. Returns one line per action for the user.
+pub fn run_init(dir: &Path, args: &InitArgs) -> Result<Vec<String>, AppError> {
+    let name = CONFIG_FILE_NAMES[0];
+    let config_path = dir.join(name);
+    let existed = config_path.exists();
+    if existed && !args.force {
+        return Err(AppError::usage(format!(
+            "{name} already exists; pass --force to overwrite it."
+        )));
+    }
+
+    let url = args.url.as_deref().unwrap_or(DEFAULT_URL);
+    let out = args
+        .out
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_OUT));
+    let mut report = Vec::new();
+
+    fs::write(&config_path, render_config(url, &out))
+        .map_err(|err| AppError::io(format!("failed to write {name}: {err}")).with_source(err))?;
+    report.push(format!(
+        "{} {name}",
+        if existed { "overwrote" } else { "created" }
+    ));
+
+    if let Some(out_dir) = out.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        let full = dir.join(out_dir);
+        if !full.is_dir() {
+            fs::create_dir_all(&full).map_err(|err| {
+                AppError::io(format!("failed to create {}: {err}", out_dir.display()))
+            })?;
+            report.push(format!("created {}/", out_dir.display()));
+        }
+    }
+
+    let gitignore = dir.join(".gitignore");
+    if gitignore.is_file() {
+        if ignore_temp_files(&gitignore)? {
+            report.push(format!("added {TEMP_FILE_PATTERN} to .gitignore"));
+        }
+    } else {
+        report.push("no .gitignore found; left it alone".to_string());
+    }
+    Ok(report)
+}
+
+fn render_config(url: &str, out: &Path) -> String {
+    let quote = |text: &str| Value::String(text.to_string()).to_string();
+    format!(
+        "# openapi-snapshot configuration. Keys are the long flag names; flags on the\n\
+         # command line and OPENAPI_SNAPSHOT_* environment variables override them.\n\
+         \n\
+         url = {url}\n\
+         out = {out}\n\
+         # outline-out = {outline_out}\n\
+         # reduce = {reduce}\n\
+         # profile = \"full\"\n\
+         # minify = false   # default: true only for *.min.json outputs\n\
+         # timeout-ms = 10000\n\
+         # header = [\"Authorization: Bearer <token>\"]\n\
+         \n\
+         [watch]\n\
+         # interval-ms = {DEFAULT_INTERVAL_MS}\n\
+         # max-backoff-ms = {DEFAULT_MAX_BACKOFF_MS}\n\
+         # prompt-grace = {prompt_grace}\n\
+         # on-change = \"npm run codegen\"\n",
+        url = quote(url),
+        out = quote(&out.display().to_string()),
+        outline_out = quote(DEFAULT_OUTLINE_OUT),
+        reduce = quote(DEFAULT_REDUCE),
+        prompt_grace = quote(DEFAULT_PROMPT_GRACE),
+    )
+}
+
+/// Appends the temp-file pattern unless a line already has it. Returns
+/// whether the file changed.
+fn ignore_temp_files(path: &Path) -> Result<bool, AppError> {
+    let io_err = |err: std::io::Error| AppError::io(format!("failed to update .gitignore: {err}"));
+    let contents = fs::read_to_string(path).map_err(io_err)?;
+    if contents
+        .lines()
+        .any(|line| line.trim() == TEMP_FILE_PATTERN)
+    {
+        return Ok(false);
+    }
+    let separator = if contents.is_empty() || contents.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    let mut file = OpenOptions::new().append(true).open(path).map_err(io_err)?;
+    write!(
+        file,
+        "{separator}# openapi-snapshot temp files\n{TEMP_FILE_PATTERN}\n"
+    )
+    .map_err(io_err)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_file::parse_cli_from;
+
+    fn init_args(url: Option<&str>, out: Option<&str>, force: bool) -> InitArgs {
+        InitArgs {
+            url: url.map(str::to_string),
+            out: out.map(PathBuf::from),
+            force,
+        }
+    }
+
+    #[test]
+    fn generated_config_parses_and_seeds_flags() {
+        let temp = tempfile::tempdir().unwrap();
+        let args = init_args(
+            Some("http://localhost:8080/docs \"v2\".json"),
+            Some("docs/api.json"),
+            false,
+        );
+        let report = run_init(temp.path(), &args).unwrap();
+        assert_eq!(
+            report,
+            vec![
+                "created openapi-snapshot.toml",
+                "created docs/",
+                "no .gitignore found; left it alone"
+            ]
+        );
+        assert!(temp.path().join("docs").is_dir());
+        assert!(!temp.path().join(".gitignore").exists());
+
+        let (invocations, warnings) = parse_cli_from(["openapi-snapshot"], temp.path()).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        let cli = &invocations[0].cli;
+        assert_eq!(
+            cli.common.url,
+            vec!["http://localhost:8080/docs \"v2\".json"]
+        );
+        assert_eq!(cli.common.out, vec![PathBuf::from("docs/api.json")]);
+    }
+
+    #[test]
+    fn existing_config_needs_force() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("openapi-snapshot.toml");
+        fs::write(&path, "url = \"keep\"\n").unwrap();
+
+        let err = run_init(temp.path(), &init_args(None, None, false))
+            .err()
+            .unwrap();
+        assert!(matches!(err, AppError::Usage { .. }));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "url = \"keep\"\n");
+
+        let report = run_init(temp.path(), &init_args(None, None, true)).unwrap();
+        assert_eq!(report[0], "overwrote openapi-snapshot.toml");
+        assert!(fs::read_to_string(&path).unwrap().contains(DEFAULT_URL));
+    }
+
+    #[test]
+    fn gitignore_gains_the_pattern_once() {
+        let temp = tempfile::tempdir().unwrap();
+        let gitignore = temp.path().join(".gitignore");
+        fs::write(&gitignore, "target").unwrap();
+
+        assert!(ignore_temp_files(&gitignore).unwrap());
+        assert!(!ignore_temp_files(&gitignore).unwrap());
+        assert_eq!(
+            fs::read_to_string(&gitignore).unwrap(),
+            "target\n# openapi-snapshot temp files\n.*.tmp\n"
+        );
+    }
+}
@@ -1,13 +1,137 @@
+//! Fetch an OpenAPI document, optionally reduce or outline it, and write it
+//! to disk, once or continuously.
+//!
+//! [`Snapshot::builder`] is the simplest way to take a snapshot from code;
+//! it applies the command line's defaults and validation:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use openapi_snapshot::{OutputProfile, Snapshot};
+//!
+//! Snapshot::builder()
+//!     .url("http://localhost:8080/openapi.json")
+//!     .header("Authorization", "Bearer TOKEN")
+//!     .profile(OutputProfile::Slim)
+//!     .timeout(Duration::from_secs(30))
+//!     .build()?
+//!     .write_to("openapi/backend_openapi.json".as_ref())?;
+//! # Ok::<(), openapi_snapshot::AppError>(())
+//! ```
+//!
+//! The crate root also re-exports the lower-level pieces the binary uses,
+//! for programs that start from command-line style arguments:
+//!
+//! ```
+//! use clap::Parser;
+//! use openapi_snapshot::{Cli, Config, Mode, build_outputs, validate_config, write_outputs};
+//!
+//! let dir = std::env::temp_dir().join(format!("openapi-snapshot-doc-{}", std::process::id()));
+//! std::fs::create_dir_all(&dir)?;
+//! let input = dir.join("openapi.json");
+//! std::fs::write(&input, r#"{"openapi":"3.0.3","paths":{"/users":{}},"components":{}}"#)?;
+//! let out = dir.join("snapshot.json");
+//!
+//! let cli = Cli::try_parse_from([
+//!     "openapi-snapshot".as_ref(),
+//!     "--input".as_ref(),
+//!     input.as_os_str(),
+//!     "--out".as_ref(),
+//!     out.as_os_str(),
+//!     "--reduce".as_ref(),
+//!     "paths".as_ref(),
+//! ])?;
+//! let (config, mode) = Config::from_cli(cli)?;
+//! assert!(matches!(mode, Mode::Snapshot));
+//! validate_config(&config)?;
+//!
+//! let outputs = build_outputs(&config)?;
+//! write_outputs(&config, &outputs)?;
+//! assert_eq!(std::fs::read_to_string(&out)?, "{\n  \"paths\": {\n    \"/users\": {}\n  }\n}");
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`outline_openapi`] works on an already parsed document:
+//!
+//! ```
+//! use openapi_snapshot::{OutlineOptions, outline_openapi};
+//!
+//! let spec = serde_json::json!({
+//!     "openapi": "3.0.3",
+//!     "paths": {"/health": {"get": {"responses": {"200": {
+//!         "content": {"application/json": {"schema": {"type": "string"}}}
+//!     }}}}}
+//! });
+//! let outline = outline_openapi(&spec, &OutlineOptions::default().short_refs(true))?;
+//! assert!(outline["paths"].get("/health").is_some());
+//! # Ok::<(), openapi_snapshot::AppError>(())
+//! ```
+
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod baseline;
+pub mod breaking;
+pub mod changelog;
+pub mod clean;
 pub mod cli;
+pub mod color;
+mod compact;
+pub mod completions;
 pub mod config;
+pub mod config_file;
+pub mod credentials;
+pub mod curl;
+pub mod diff;
+pub mod duplicates;
+mod duration;
+pub mod endpoints;
 pub mod errors;
+pub mod events;
+pub mod example;
+pub mod examples;
 pub mod fetch;
+mod file_watch;
+pub mod format;
+mod hook;
+pub mod init;
+pub mod lint;
+mod logfile;
+pub mod man;
+pub mod merge;
+mod metrics;
+mod model;
+pub mod normalize;
+mod notify;
 pub mod outline;
 pub mod output;
+pub mod patch;
+#[cfg(feature = "http")]
+mod progress;
+pub mod prune;
+pub mod refs;
+pub mod resolve;
+pub mod search;
+pub mod show;
+pub mod slim;
+mod snapshot;
+#[cfg(feature = "http")]
+mod sse;
+pub mod stats;
+pub mod transform;
+pub mod validate;
+pub mod verbosity;
+pub mod version;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 pub mod watch;
 
-pub use cli::{Cli, Command, CommonArgs, OutputProfile, WatchArgs};
-pub use config::{Config, Mode, ReduceKey, parse_reduce_list, validate_config};
-pub use errors::AppError;
-pub use output::{OutputPayloads, build_output, build_outputs, write_output, write_outputs};
-pub use watch::{maybe_prompt_for_url, run_watch};
+pub use cli::{Cli, Command, OutlineCompat, OutputProfile};
+pub use config::{Config, Mode, ReduceKey, WatchOptions, parse_reduce_list, validate_config};
+pub use errors::{AppError, ErrorKind};
+pub use outline::{OutlineOptions, outline_openapi, outline_openapi_default};
+pub use output::{
+    ComparedPayloads, OutputPayloads, build_outputs, dry_run_summary, snapshot_breaking,
+    snapshot_drift, write_outputs,
+};
+pub use snapshot::{Snapshot, SnapshotBuilder};
+pub use watch::{WatchEnv, maybe_prompt_for_url, run_watch, run_watch_targets};
@@ -0,0 +1,396 @@
+//! Style rules for documents that are already valid. Each [`Rule`] is a
+//! function from the document to findings; [`RULES`] is the whole set.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::diff::{operations, resolve_ref};
+use crate::errors::AppError;
+use crate::validate::{Severity, escape, path_collisions, plural};
+
+/// Longest summary [`RULES`]' `operation-summary` accepts, in characters.
+pub const MAX_SUMMARY_CHARS: usize = 120;
+
+/// One lint finding, located by a JSON pointer into the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    /// The [`Rule::id`] that found it.
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} ({})",
+            self.severity.as_str(),
+            self.location,
+            self.message,
+            self.rule
+        )
+    }
+}
+
+/// Where a rule reports its findings, as a location and a message.
+type Report = Vec<(String, String)>;
+
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub severity: Severity,
+    pub check: fn(&Value, &mut Report),
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "operation-tags",
+        description: "every operation has at least one tag",
+        severity: Severity::Warning,
+        check: operation_tags,
+    },
+    Rule {
+        id: "success-response-schema",
+        description: "every 2xx response other than 204 declares a schema",
+        severity: Severity::Warning,
+        check: success_response_schema,
+    },
+    Rule {
+        id: "uuid-property-names",
+        description: "string properties with format uuid are named `id` or `*_id`",
+        severity: Severity::Warning,
+        check: uuid_property_names,
+    },
+    Rule {
+        id: "no-trailing-slash",
+        description: "no path ends with a trailing slash",
+        severity: Severity::Error,
+        check: no_trailing_slash,
+    },
+    Rule {
+        id: "operation-summary",
+        description: "every operation has a summary of at most 120 characters",
+        severity: Severity::Warning,
+        check: operation_summary,
+    },
+    Rule {
+        id: "path-collision",
+        description: "no paths differ only in placeholder names, a trailing slash, or case",
+        severity: Severity::Error,
+        check: path_collision,
+    },
+];
+
+/// Which [`RULES`] to run: only `rules` when it is not empty, minus
+/// `disabled`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintOptions {
+    pub rules: Vec<String>,
+    pub disabled: Vec<String>,
+}
+
+impl LintOptions {
+    fn runs(&self, id: &str) -> bool {
+        (self.rules.is_empty() || self.rules.iter().any(|rule| rule == id))
+            && !self.disabled.iter().any(|rule| rule == id)
+    }
+}
+
+/// Runs the selected rules over `doc`, in [`RULES`] order.
+pub fn lint(doc: &Value, options: &LintOptions) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    for rule in RULES.iter().filter(|rule| options.runs(rule.id)) {
+        let mut report = Report::new();
+        (rule.check)(doc, &mut report);
+        findings.extend(report.into_iter().map(|(location, message)| LintFinding {
+            rule: rule.id,
+            severity: rule.severity,
+            location,
+            message,
+        }));
+    }
+    findings
+}
+
+/// Fails when any finding has error severity.
+pub fn check_lint(findings: &[LintFinding]) -> Result<(), AppError> {
+    let count = |severity| {
+        findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    };
+    let errors = count(Severity::Error);
+    if errors == 0 {
+        return Ok(());
+    }
+    Err(AppError::validation(format!(
+        "lint failed: {}, {}.",
+        plural(errors, "error"),
+        plural(count(Severity::Warning), "warning")
+    )))
+}
+
+/// Parses a `--rule` or `--disable-rule` value, which must name a rule.
+pub fn parse_rule_id(text: &str) -> Result<String, String> {
+    if RULES.iter().any(|rule| rule.id == text) {
+        return Ok(text.to_string());
+    }
+    let ids: Vec<&str> = RULES.iter().map(|rule| rule.id).collect();
+    Err(format!(
+        "unknown lint rule '{text}'; expected one of {}",
+        ids.join(", ")
+    ))
+}
+
+fn operation_location(path: &str, method: &str) -> String {
+    format!("#/paths/{}/{method}", escape(path))
+}
+
+fn operation_tags(doc: &Value, report: &mut Report) {
+    for ((path, method), operation) in operations(doc) {
+        let tagged = operation
+            .op
+            .get("tags")
+            .and_then(Value::as_array)
+            .is_some_and(|tags| !tags.is_empty());
+        if !tagged {
+            report.push((
+                operation_location(path, method),
+                "operation has no tags".to_string(),
+            ));
+        }
+    }
+}
+
+fn success_response_schema(doc: &Value, report: &mut Report) {
+    for ((path, method), operation) in operations(doc) {
+        let Some(responses) = operation.op.get("responses").and_then(Value::as_object) else {
+            continue;
+        };
+        for (status, response) in responses {
+            if !status.starts_with('2') || status == "204" {
+                continue;
+            }
+            let has_schema = resolve_ref(doc, response)
+                .get("content")
+                .and_then(Value::as_object)
+                .is_some_and(|content| content.values().any(|media| media.get("schema").is_some()));
+            if !has_schema {
+                report.push((
+                    format!(
+                        "{}/responses/{}",
+                        operation_location(path, method),
+                        escape(status)
+                    ),
+                    format!("{status} response declares no schema"),
+                ));
+            }
+        }
+    }
+}
+
+fn uuid_property_names(doc: &Value, report: &mut Report) {
+    fn walk(value: &Value, location: &str, report: &mut Report) {
+        match value {
+            Value::Object(object) => {
+                if let Some(properties) = object.get("properties").and_then(Value::as_object) {
+                    for (name, property) in properties {
+                        let is_uuid = property.get("type").and_then(Value::as_str)
+                            == Some("string")
+                            && property.get("format").and_then(Value::as_str) == Some("uuid");
+                        if is_uuid && name != "id" && !name.ends_with("_id") {
+                            report.push((
+                                format!("{location}/properties/{}", escape(name)),
+                                format!(
+                                    "uuid property '{name}' should be named 'id' or end in '_id'"
+                                ),
+                            ));
+                        }
+                    }
+                }
+                for (key, child) in object {
+                    walk(child, &format!("{location}/{}", escape(key)), report);
+                }
+            }
+            Value::Array(items) => {
+                for (index, child) in items.iter().enumerate() {
+                    walk(child, &format!("{location}/{index}"), report);
+                }
+            }
+            _ => {}
+        }
+    }
+    walk(doc, "#", report);
+}
+
+fn no_trailing_slash(doc: &Value, report: &mut Report) {
+    let Some(paths) = doc.get("paths").and_then(Value::as_object) else {
+        return;
+    };
+    for path in paths.keys() {
+        if path.len() > 1 && path.ends_with('/') {
+            report.push((
+                format!("#/paths/{}", escape(path)),
+                "path ends with a trailing slash".to_string(),
+            ));
+        }
+    }
+}
+
+fn operation_summary(doc: &Value, report: &mut Report) {
+    for ((path, method), operation) in operations(doc) {
+        let message = match operation.op.get("summary").and_then(Value::as_str) {
+            None | Some("") => "operation has no summary".to_string(),
+            Some(summary) if summary.chars().count() > MAX_SUMMARY_CHARS => format!(
+                "summary is {} characters; keep it to {MAX_SUMMARY_CHARS}",
+                summary.chars().count()
+            ),
+            Some(_) => continue,
+        };
+        report.push((operation_location(path, method), message));
+    }
+}
+
+fn path_collision(doc: &Value, report: &mut Report) {
+    let Some(paths) = doc.get("paths").and_then(Value::as_object) else {
+        return;
+    };
+    for collision in path_collisions(paths) {
+        report.push((collision.location(), collision.message()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Value {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/lint")
+            .join(format!("{name}.json"));
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    /// The findings `rule` alone reports for its fixture.
+    fn rule_findings(rule: &str) -> Vec<String> {
+        let options = LintOptions {
+            rules: vec![rule.to_string()],
+            disabled: Vec::new(),
+        };
+        lint(&fixture(rule), &options)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn clean_document_passes_every_rule() {
+        let findings = lint(&fixture("clean"), &LintOptions::default());
+        assert!(findings.is_empty(), "{findings:?}");
+    }
+
+    #[test]
+    fn operation_tags_flags_untagged_operations() {
+        assert_eq!(
+            rule_findings("operation-tags"),
+            vec![
+                "warning #/paths/~1users/get operation has no tags (operation-tags)",
+                "warning #/paths/~1users/post operation has no tags (operation-tags)",
+            ]
+        );
+    }
+
+    #[test]
+    fn success_response_schema_follows_refs_and_skips_204() {
+        assert_eq!(
+            rule_findings("success-response-schema"),
+            vec![
+                "warning #/paths/~1users/get/responses/200 200 response declares no schema \
+                 (success-response-schema)",
+                "warning #/paths/~1users/post/responses/201 201 response declares no schema \
+                 (success-response-schema)",
+            ]
+        );
+    }
+
+    #[test]
+    fn uuid_property_names_checks_nested_schemas() {
+        assert_eq!(
+            rule_findings("uuid-property-names"),
+            vec![
+                "warning #/components/schemas/User/properties/uuid uuid property 'uuid' \
+                 should be named 'id' or end in '_id' (uuid-property-names)",
+                "warning #/components/schemas/User/properties/owner/properties/teamUuid \
+                 uuid property 'teamUuid' should be named 'id' or end in '_id' \
+                 (uuid-property-names)",
+            ]
+        );
+    }
+
+    #[test]
+    fn no_trailing_slash_flags_paths_but_not_the_root() {
+        assert_eq!(
+            rule_findings("no-trailing-slash"),
+            vec!["error #/paths/~1users~1 path ends with a trailing slash (no-trailing-slash)"]
+        );
+    }
+
+    #[test]
+    fn operation_summary_must_exist_and_be_short() {
+        assert_eq!(
+            rule_findings("operation-summary"),
+            vec![
+                "warning #/paths/~1users/delete summary is 121 characters; keep it to 120 \
+                 (operation-summary)",
+                "warning #/paths/~1users/get operation has no summary (operation-summary)",
+                "warning #/paths/~1users/post operation has no summary (operation-summary)",
+            ]
+        );
+    }
+
+    #[test]
+    fn path_collision_groups_colliding_paths() {
+        assert_eq!(
+            rule_findings("path-collision"),
+            vec![
+                "error #/paths/~1users~1{id} paths collide, differing only in placeholder \
+                 names and trailing slash: /users/{id}, /users/{userId}/ (path-collision)",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_rule_has_a_fixture_and_options_select_rules() {
+        for rule in RULES {
+            assert!(!rule_findings(rule.id).is_empty(), "{}", rule.id);
+        }
+        let doc = fixture("no-trailing-slash");
+        let options = LintOptions {
+            rules: Vec::new(),
+            disabled: vec!["no-trailing-slash".to_string()],
+        };
+        assert!(
+            lint(&doc, &options)
+                .iter()
+                .all(|finding| finding.rule != "no-trailing-slash")
+        );
+        assert!(parse_rule_id("operation-tags").is_ok());
+        assert!(parse_rule_id("tags").is_err());
+    }
+
+    #[test]
+    fn only_errors_fail_the_lint() {
+        let findings = lint(&fixture("operation-tags"), &LintOptions::default());
+        assert!(check_lint(&findings).is_ok());
+        let findings = lint(&fixture("no-trailing-slash"), &LintOptions::default());
+        assert_eq!(
+            check_lint(&findings).unwrap_err().to_string(),
+            "lint failed: 1 error, 0 warnings."
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::errors::AppError;
+
+/// Append-only copy of the watch log for `--log-file`.
+///
+/// The file is reopened whenever it disappears, so logrotate can move it
+/// away without a `copytruncate` setup. Failed writes are reported once and
+/// otherwise ignored: losing the log file must not stop the watcher.
+#[derive(Debug)]
+pub struct LogFile {
+    path: PathBuf,
+    file: Option<File>,
+    warned: bool,
+}
+
+impl LogFile {
+    pub fn open(path: &Path) -> Result<Self, AppError> {
+        let file = open_append(path).map_err(|err| {
+            AppError::io(format!("failed to open log file {}: {err}", path.display()))
+        })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Some(file),
+            warned: false,
+        })
+    }
+
+    /// Appends one line. Returns a warning to show on stderr the first time
+    /// writing fails.
+    pub fn append(&mut self, line: &str) -> Option<String> {
+        match self.try_append(line) {
+            Ok(()) => None,
+            Err(err) => {
+                self.file = None;
+                if std::mem::replace(&mut self.warned, true) {
+                    None
+                } else {
+                    Some(format!(
+                        "warning: failed to write log file {}: {err}; further log file errors are not reported",
+                        self.path.display()
+                    ))
+                }
+            }
+        }
+    }
+
+    fn try_append(&mut self, line: &str) -> io::Result<()> {
+        let file = match self.file.take() {
+            Some(file) if self.path.is_file() => file,
+            _ => open_append(&self.path)?,
+        };
+        let file = self.file.insert(file);
+        writeln!(file, "{line}")
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_creates_parent_directories_and_appends() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("logs").join("watch.log");
+        let mut log = LogFile::open(&path).unwrap();
+        assert_eq!(log.append("first"), None);
+        drop(log);
+
+        let mut log = LogFile::open(&path).unwrap();
+        assert_eq!(log.append("second"), None);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+    }
+
+    #[test]
+    fn reopens_after_the_file_is_rotated_away() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("watch.log");
+        let rotated = temp.path().join("watch.log.1");
+        let mut log = LogFile::open(&path).unwrap();
+        log.append("before rotation");
+
+        fs::rename(&path, &rotated).unwrap();
+        assert_eq!(log.append("after rotation"), None);
+
+        assert_eq!(fs::read_to_string(&rotated).unwrap(), "before rotation\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "after rotation\n");
+    }
+
+    #[test]
+    fn write_failures_warn_once() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("watch.log");
+        let mut log = LogFile::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+
+        let warning = log.append("lost").unwrap();
+        assert!(warning.contains("failed to write log file"), "{warning}");
+        assert_eq!(log.append("lost again"), None);
+
+        fs::remove_dir(&path).unwrap();
+        assert_eq!(log.append("recovered"), None);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "recovered\n");
+    }
+}
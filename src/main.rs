@@ -1,51 +1,831 @@
-use clap::Parser;
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use clap::ValueEnum;
+use serde_json::{Value, json};
+
+use openapi_snapshot::breaking::{BreakingChange, breaking_changes, summarize_breaking};
+use openapi_snapshot::changelog::markdown_changelog;
+use openapi_snapshot::clean::{Retention, clean_candidates, remove_file};
+use openapi_snapshot::cli::{DiffFormat, FailOn, SearchScope};
+use openapi_snapshot::color::Palette;
+use openapi_snapshot::completions::write_completions;
+use openapi_snapshot::config::effective_config_json;
+use openapi_snapshot::config_file::{CliError, Invocation, parse_cli, subcommand_name};
+use openapi_snapshot::credentials::{
+    Keyring, attach_stored_token, credential_host, login, logout, read_token_input,
+};
+use openapi_snapshot::curl::curl_command;
+use openapi_snapshot::diff::{
+    SpecChange, diff_documents, diff_specs, format_operations, summarize_changes,
+};
+use openapi_snapshot::endpoints::{format_endpoints, list_endpoints};
+use openapi_snapshot::errors::{COMPARE_FAILED, Outcome, enforce_fail_on, fail_on_kind};
+use openapi_snapshot::example::schema_example;
+use openapi_snapshot::fetch::{load_document, load_source, parse_json};
+use openapi_snapshot::format::convert_document;
+use openapi_snapshot::init::run_init;
+use openapi_snapshot::lint::{check_lint, lint};
+use openapi_snapshot::man::write_man;
+use openapi_snapshot::merge::{MergeInput, merge_specs, service_name};
+use openapi_snapshot::normalize::{for_comparison, normalize_file};
+use openapi_snapshot::output::{serialize_json, write_output};
+use openapi_snapshot::patch::json_patch;
+use openapi_snapshot::prune::{format_prune_report, prune_components};
+use openapi_snapshot::refs::check_refs;
+use openapi_snapshot::resolve::{format_cycle, resolve_schema};
+use openapi_snapshot::search::{Matcher, search};
+use openapi_snapshot::show::show;
+use openapi_snapshot::stats::{format_stats, spec_stats};
+use openapi_snapshot::validate::{check_findings, validate_spec_with};
+use openapi_snapshot::watch::WatchEnv;
 use openapi_snapshot::{
-    AppError, Cli, Config, Mode, build_outputs, maybe_prompt_for_url, run_watch, validate_config,
+    AppError, Cli, Command, Config, ErrorKind, Mode, build_outputs, dry_run_summary,
+    maybe_prompt_for_url, run_watch_targets, snapshot_breaking, snapshot_drift, validate_config,
     write_outputs,
 };
 
 fn main() {
-    let cli = Cli::parse();
-    let (config, mode) = match Config::from_cli(cli) {
-        Ok(result) => result,
-        Err(err) => exit_with_error(err),
+    let invocations = match parse_cli() {
+        Ok(invocations) => invocations,
+        Err(err) => exit_with_cli_error(err),
     };
 
-    if config.stdout && config.out.is_some() {
-        eprintln!("--out is ignored because --stdout is set.");
+    let mut failures = Vec::new();
+    let mut exit_code = 0;
+    let total = invocations.len();
+    for Invocation { target, cli } in invocations {
+        let compares = compares(cli.command.as_ref());
+        let err = match run(cli) {
+            Ok(code) => {
+                exit_code = exit_code.max(code);
+                continue;
+            }
+            Err(err) => err,
+        };
+        let code = if compares {
+            err.diff_exit_code()
+        } else {
+            err.exit_code()
+        };
+        match target {
+            Some(name) => {
+                eprintln!("target '{name}' failed: {err}");
+                exit_code = exit_code.max(code);
+                failures.push((name, err));
+            }
+            None => {
+                eprintln!("{err}");
+                std::process::exit(code);
+            }
+        }
+    }
+
+    if let Some(summary) = summarize_failures(&failures, total, AppError::exit_code)
+        && total > 1
+    {
+        eprintln!("{summary}");
+    }
+    std::process::exit(exit_code);
+}
+
+/// Whether `command` compares documents, and so exits as
+/// [`AppError::diff_exit_code`] says.
+fn compares(command: Option<&Command>) -> bool {
+    match command {
+        Some(Command::Diff(_) | Command::Check(_)) => true,
+        Some(Command::Normalize(args)) => args.check,
+        _ => false,
+    }
+}
+
+/// Folds per-target failures into one error naming them, with the worst
+/// exit code among them as `exit_code` maps them.
+fn summarize_failures(
+    failures: &[(String, AppError)],
+    total: usize,
+    exit_code: fn(&AppError) -> i32,
+) -> Option<AppError> {
+    let worst = failures
+        .iter()
+        .map(|(_, err)| err)
+        .max_by_key(|err| exit_code(err))?;
+    let names: Vec<&str> = failures.iter().map(|(name, _)| name.as_str()).collect();
+    Some(worst.with_message(format!(
+        "{} of {total} targets failed: {}",
+        failures.len(),
+        names.join(", ")
+    )))
+}
+
+/// Runs one invocation and returns its exit code; only `diff`, `check`, and
+/// `--fallback-to-last` report a non-zero code without an error.
+fn run(cli: Cli) -> Result<i32, AppError> {
+    if let Some(Command::Completions(args)) = &cli.command {
+        write_completions(args.shell, &mut std::io::stdout())?;
+        return Ok(0);
+    }
+    if let Some(Command::Man(args)) = &cli.command {
+        write_man(args.out.as_deref())?;
+        return Ok(0);
+    }
+    if let Some(Command::Init(args)) = &cli.command {
+        let cwd = std::env::current_dir().map_err(|err| {
+            AppError::io(format!("failed to read working directory: {err}")).with_source(err)
+        })?;
+        for line in run_init(&cwd, args)? {
+            println!("{line}");
+        }
+        return Ok(0);
+    }
+
+    if let Some(Command::Login | Command::Logout) = &cli.command {
+        let url = cli
+            .common
+            .url
+            .first()
+            .ok_or_else(|| AppError::usage("pass the API with --url <URL>.".to_string()))?;
+        let message = if matches!(cli.command, Some(Command::Login)) {
+            let token = read_token_input(&credential_host(url)?)?;
+            login(&Keyring, url, &token)?
+        } else {
+            logout(&Keyring, url)?
+        };
+        println!("{message}");
+        return Ok(0);
     }
 
-    if let Err(err) = validate_config(&config) {
-        exit_with_error(err);
+    let dry_run = cli.common.dry_run;
+    let print_config = cli.common.print_config;
+    let (mut configs, mode) = Config::targets_from_cli(cli)?;
+
+    for config in &mut configs {
+        for warning in validate_config(config)? {
+            config.verbosity.info(warning);
+        }
+        attach_stored_token(config, &Keyring);
+    }
+    if print_config {
+        println!("{}", effective_config_json(&configs, &mode)?);
+        return Ok(0);
     }
 
     match mode {
-        Mode::Snapshot => {
-            let mut config = config;
-            let outputs = match build_outputs(&config) {
-                Ok(outputs) => outputs,
-                Err(err) => {
-                    if let Ok(true) = maybe_prompt_for_url(&mut config, &err) {
-                        match build_outputs(&config) {
-                            Ok(outputs) => outputs,
-                            Err(err) => exit_with_error(err),
-                        }
-                    } else {
-                        exit_with_error(err);
+        Mode::Snapshot if dry_run => print_dry_run(&configs),
+        Mode::Snapshot if configs.len() > 1 => {
+            let stale = Cell::new(0);
+            run_each(&configs, |config| {
+                match build_outputs(config).and_then(|outputs| write_outputs(config, &outputs)) {
+                    Ok(()) => Ok(()),
+                    Err(err) => {
+                        stale.set(stale.get().max(fall_back_to_last(config, err)?));
+                        Ok(())
                     }
                 }
+            })?;
+            return Ok(stale.get());
+        }
+        Mode::Snapshot => {
+            let Ok([mut config]) = <[Config; 1]>::try_from(configs) else {
+                return Err(AppError::usage(
+                    "no snapshot target configured.".to_string(),
+                ));
             };
-
-            if let Err(err) = write_outputs(&config, &outputs) {
-                exit_with_error(err);
+            return snapshot_with_prompt(&mut config, maybe_prompt_for_url);
+        }
+        Mode::Watch(_) if dry_run => print_dry_run(&configs),
+        Mode::Watch(options) => run_watch_targets(&mut configs, &options, WatchEnv::default()),
+        Mode::Stats { json } => run_each(&configs, |config| print_stats(config, json)),
+        Mode::Paths { long, json } => run_each(&configs, |config| print_paths(config, long, json)),
+        Mode::Show { selector, resolve } => run_each(&configs, |config| {
+            let outputs = build_outputs(config)?;
+            let found = show(&parse_json(outputs.primary.as_bytes())?, &selector, resolve)?;
+            print_json(&found, config.minify)
+        }),
+        Mode::Merge {
+            inputs,
+            paths_conflict,
+            rename_collisions,
+        } => {
+            let config = configs
+                .first()
+                .ok_or_else(|| AppError::usage("no snapshot target configured.".to_string()))?;
+            let inputs = inputs
+                .iter()
+                .map(|source| {
+                    Ok(MergeInput {
+                        service: service_name(source),
+                        doc: load_document(config, source)?,
+                    })
+                })
+                .collect::<Result<Vec<_>, AppError>>()?;
+            let merged = merge_specs(inputs, paths_conflict, rename_collisions)?;
+            write_output(config, &serialize_json(&merged, config.minify)?)
+        }
+        Mode::Convert { from, to } => run_each(&configs, |config| {
+            write_output(config, &convert_document(config, from, to)?)
+        }),
+        Mode::Curl {
+            method,
+            path,
+            base_url,
+        } => run_each(&configs, |config| {
+            let outputs = build_outputs(config)?;
+            let doc = parse_json(outputs.primary.as_bytes())?;
+            let command =
+                curl_command(&doc, &method, &path, base_url.as_deref(), &config.source())?;
+            println!("{command}");
+            Ok(())
+        }),
+        Mode::Example { schema, count } => run_each(&configs, |config| {
+            let outputs = build_outputs(config)?;
+            let doc = parse_json(outputs.primary.as_bytes())?;
+            print_json(&schema_example(&doc, &schema, count)?, config.minify)
+        }),
+        Mode::Schema {
+            name,
+            json_schema_draft,
+        } => run_each(&configs, |config| {
+            let outputs = build_outputs(config)?;
+            let doc = parse_json(outputs.primary.as_bytes())?;
+            let resolved = resolve_schema(&doc, &name, json_schema_draft)?;
+            for cycle in &resolved.cycles {
+                config.verbosity.info(format!(
+                    "warning: cyclic reference left as $ref: {}",
+                    format_cycle(cycle)
+                ));
             }
+            print_json(&resolved.value, config.minify)
+        }),
+        Mode::Prune => run_each(&configs, |config| prune_document(config, dry_run)),
+        Mode::Search {
+            pattern,
+            regex,
+            scope,
+            json,
+        } => {
+            let matcher = Matcher::new(&pattern, regex)?;
+            run_each(&configs, |config| {
+                print_search_hits(config, &matcher, scope, json)
+            })
         }
-        Mode::Watch { interval_ms } => {
-            let mut config = config;
-            if let Err(err) = run_watch(&mut config, interval_ms) {
-                exit_with_error(err);
+        Mode::Clean { history } => run_each(&configs, |config| {
+            clean_target(config, history.as_ref(), dry_run)
+        }),
+        Mode::Normalize {
+            file,
+            options,
+            check,
+        } => {
+            let changed = normalize_file(&file, &options, check)?;
+            let file = file.display();
+            match (changed, check) {
+                (false, _) => println!("{file} is already normalized"),
+                (true, false) => println!("normalized {file}"),
+                (true, true) => {
+                    return Err(AppError::drift(format!(
+                        "{file} is not normalized; run `openapi-snapshot normalize {file}` to fix it."
+                    )));
+                }
             }
+            Ok(())
         }
+        Mode::Check {
+            update,
+            breaking,
+            fail_on,
+        } => run_targets(&configs, AppError::diff_exit_code, |config| {
+            check_snapshot(config, update, breaking, fail_on)
+        }),
+        Mode::Diff {
+            old,
+            new,
+            breaking,
+            format,
+            max_items,
+            fail_on,
+        } => {
+            let config = configs
+                .first()
+                .ok_or_else(|| AppError::usage("no snapshot target configured.".to_string()))?;
+            return run_diff(config, &old, &new, breaking, format, max_items, fail_on);
+        }
+        Mode::Validate {
+            source,
+            max_warnings,
+        } => {
+            let config = configs
+                .first()
+                .ok_or_else(|| AppError::usage("no snapshot target configured.".to_string()))?;
+            let source = source.unwrap_or_else(|| config.source());
+            print_findings(config, &load_document(config, &source)?, max_warnings)
+        }
+        Mode::Lint { source, options } => {
+            let config = configs
+                .first()
+                .ok_or_else(|| AppError::usage("no snapshot target configured.".to_string()))?;
+            let source = source.unwrap_or_else(|| config.source());
+            let findings = lint(&load_document(config, &source)?, &options);
+            if findings.is_empty() {
+                println!("no lint findings");
+            }
+            for finding in &findings {
+                println!("{finding}");
+            }
+            check_lint(&findings)
+        }
+    }
+    .map(|()| 0)
+}
+
+/// With `--fallback-to-last`, a transient fetch failure leaves an existing
+/// snapshot in place: warns and returns the configured exit code. Anything
+/// else, or a missing snapshot, is still an error.
+fn fall_back_to_last(config: &Config, err: AppError) -> Result<i32, AppError> {
+    let existing = config.out.as_deref().filter(|path| path.is_file());
+    match (config.fallback_to_last, existing) {
+        (Some(code), Some(path)) if err.is_transient() => {
+            eprintln!("warning: {err}");
+            eprintln!(
+                "warning: keeping the existing snapshot {}; it may be out of date.",
+                path.display()
+            );
+            Ok(code)
+        }
+        _ => Err(err),
+    }
+}
+
+/// Writes one target's snapshot. When the default URL fails and `prompt`
+/// supplies another, that one is tried instead; a failure of either fetch
+/// may fall back to the last snapshot.
+fn snapshot_with_prompt(
+    config: &mut Config,
+    prompt: impl FnOnce(&mut Config, &AppError) -> Result<bool, AppError>,
+) -> Result<i32, AppError> {
+    let outputs = match build_outputs(config) {
+        Ok(outputs) => outputs,
+        Err(err) => match prompt(config, &err) {
+            Ok(true) => match build_outputs(config) {
+                Ok(outputs) => outputs,
+                Err(err) => return fall_back_to_last(config, err),
+            },
+            _ => return fall_back_to_last(config, err),
+        },
+    };
+    write_outputs(config, &outputs).map(|()| 0)
+}
+
+/// Runs `run` for every target, reporting each failure by name, and fails
+/// with the worst of them.
+fn run_each(
+    configs: &[Config],
+    run: impl Fn(&Config) -> Result<(), AppError>,
+) -> Result<(), AppError> {
+    run_targets(configs, AppError::exit_code, run)
+}
+
+/// [`run_each`] for a command whose exit codes `exit_code` maps, so the
+/// worst failure is picked the way the process exits with it.
+fn run_targets(
+    configs: &[Config],
+    exit_code: fn(&AppError) -> i32,
+    run: impl Fn(&Config) -> Result<(), AppError>,
+) -> Result<(), AppError> {
+    if let [config] = configs {
+        return run(config);
+    }
+    let mut failures = Vec::new();
+    for config in configs {
+        if let Err(err) = run(config) {
+            let name = config.name.clone().unwrap_or_else(|| config.source());
+            eprintln!("target '{name}' failed: {err}");
+            failures.push((name, err));
+        }
+    }
+    summarize_failures(&failures, configs.len(), exit_code).map_or(Ok(()), Err)
+}
+
+/// Prints statistics for the document as it would be written.
+fn print_stats(config: &Config, json: bool) -> Result<(), AppError> {
+    let outputs = build_outputs(config)?;
+    let stats = spec_stats(
+        &parse_json(outputs.primary.as_bytes())?,
+        outputs.primary.len(),
+    );
+    if json {
+        print_json(&stats, false)?;
+    } else {
+        println!("{}", format_stats(&stats));
+    }
+    Ok(())
+}
+
+/// Prints the document's operations, one per line or as JSON.
+fn print_paths(config: &Config, long: bool, json: bool) -> Result<(), AppError> {
+    let outputs = build_outputs(config)?;
+    let endpoints = list_endpoints(&parse_json(outputs.primary.as_bytes())?);
+    if endpoints.is_empty() {
+        config.verbosity.info("no operations found");
+    }
+    if json {
+        return print_json(&endpoints, false);
+    }
+    for line in format_endpoints(&endpoints, long) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn print_search_hits(
+    config: &Config,
+    matcher: &Matcher,
+    scope: SearchScope,
+    json: bool,
+) -> Result<(), AppError> {
+    let outputs = build_outputs(config)?;
+    let hits = search(&parse_json(outputs.primary.as_bytes())?, matcher, scope);
+    if hits.is_empty() {
+        config.verbosity.info("no matches");
+    }
+    if json {
+        return print_json(&hits, config.minify);
+    }
+    for hit in &hits {
+        println!("{hit}");
+    }
+    Ok(())
+}
+
+fn print_json(value: &impl serde::Serialize, minify: bool) -> Result<(), AppError> {
+    let text = if minify {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    }
+    .map_err(|err| AppError::json(format!("json error: {err}")).with_source(err))?;
+    println!("{text}");
+    Ok(())
+}
+
+/// Removes unused components and reports them; a dry run only reports.
+fn prune_document(config: &Config, dry_run: bool) -> Result<(), AppError> {
+    let outputs = build_outputs(config)?;
+    let mut doc = parse_json(outputs.primary.as_bytes())?;
+    let report = format_prune_report(&prune_components(&mut doc));
+    if dry_run {
+        for line in &report {
+            println!("{line}");
+        }
+        return Ok(());
+    }
+    write_output(config, &serialize_json(&doc, config.minify)?)?;
+    for line in report {
+        config.verbosity.info(line);
+    }
+    Ok(())
+}
+
+/// Removes leftover temp files and expired history entries for one target,
+/// printing each path.
+fn clean_target(
+    config: &Config,
+    history: Option<&(PathBuf, Retention)>,
+    dry_run: bool,
+) -> Result<(), AppError> {
+    let out = config
+        .out
+        .as_deref()
+        .ok_or_else(|| AppError::usage("clean needs an --out path, not --stdout.".to_string()))?;
+    let history = history.map(|(dir, retention)| (dir.as_path(), *retention));
+    let doomed = clean_candidates(
+        out,
+        config.outline_out.as_deref(),
+        history,
+        SystemTime::now(),
+    )?;
+    if doomed.is_empty() {
+        config.verbosity.info("nothing to clean");
+    }
+    for path in doomed {
+        if dry_run {
+            println!("would remove {}", path.display());
+        } else {
+            remove_file(&path)?;
+            println!("removed {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Compares a fresh snapshot with the committed one, printing the drift.
+/// Without `fail_on`, an out-of-date snapshot with no breaking changes
+/// fails with a drift error, which exits 1 like `diff`.
+fn check_snapshot(
+    config: &Config,
+    update: bool,
+    breaking: bool,
+    fail_on: Option<FailOn>,
+) -> Result<(), AppError> {
+    let outputs = build_outputs(config)?;
+    let drift = snapshot_drift(config, &outputs, Palette::stdout(config.color))?;
+    // snapshot_drift has already rejected a missing --out.
+    let out = config
+        .out
+        .as_ref()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+    if drift.is_empty() {
+        println!("{out} is up to date");
+        if let Some(policy) = fail_on {
+            println!("{}", policy_line(policy, Outcome::Clean));
+        }
+        return Ok(());
+    }
+    for line in &drift {
+        println!("{line}");
+    }
+    let breaking = if breaking || fail_on.is_some() {
+        let changes = snapshot_breaking(config, &outputs)?;
+        print_breaking_changes(&changes);
+        changes
+    } else {
+        Vec::new()
+    };
+    if update {
+        write_outputs(config, &outputs)?;
+        println!("updated {out}");
+        return Ok(());
+    }
+    let (outcome, message) = if breaking.is_empty() {
+        (
+            Outcome::Changed,
+            format!("{out} is out of date; run `openapi-snapshot check --update` to refresh it."),
+        )
+    } else {
+        (
+            Outcome::Breaking,
+            format!(
+                "{out} is out of date and the new document has {}.",
+                summarize_breaking(&breaking)
+            ),
+        )
+    };
+    match (fail_on, outcome) {
+        (Some(policy), _) => {
+            println!("{}", policy_line(policy, outcome));
+            enforce_fail_on(policy, outcome, message)
+        }
+        (None, Outcome::Changed) => Err(AppError::drift(message)),
+        (None, _) => Err(AppError::breaking(message)),
+    }
+}
+
+/// Compares `old` with `new` and prints the result in `format`, or only the
+/// breaking changes when `breaking` is set. Without `fail_on`, exits 1 when
+/// anything changed (10 with `breaking` when something breaks); with it, states the policy and outcome and exits as
+/// [`fail_on_kind`] says.
+fn run_diff(
+    config: &Config,
+    old: &str,
+    new: &str,
+    breaking: bool,
+    format: DiffFormat,
+    max_items: Option<usize>,
+    fail_on: Option<FailOn>,
+) -> Result<i32, AppError> {
+    let (old_bytes, old_doc) = load_source(config, old)?;
+    let (new_bytes, new_doc) = load_source(config, new)?;
+    let old_doc = for_comparison(old_doc, config.strict_bytes);
+    let new_doc = for_comparison(new_doc, config.strict_bytes);
+    let formatting_only = config.strict_bytes && old_doc == new_doc && old_bytes != new_bytes;
+    let findings = if breaking || fail_on.is_some() || format == DiffFormat::Json {
+        breaking_changes(&old_doc, &new_doc)
+    } else {
+        Vec::new()
+    };
+    let outcome = |changed: bool| {
+        if !findings.is_empty() {
+            Outcome::Breaking
+        } else if changed {
+            Outcome::Changed
+        } else {
+            Outcome::Clean
+        }
+    };
+
+    let changed = if breaking {
+        print_breaking_changes(&findings);
+        formatting_only || !diff_documents(&old_doc, &new_doc).is_empty()
+    } else {
+        match format {
+            DiffFormat::Text => print_diff(config, &old_doc, &new_doc, formatting_only),
+            DiffFormat::JsonPatch => print_json_patch(config, &old_doc, &new_doc)?,
+            DiffFormat::Markdown => print_markdown(&old_doc, &new_doc, max_items),
+            DiffFormat::Operations => print_operations(&old_doc, &new_doc),
+            DiffFormat::Json => {
+                let changes = diff_documents(&old_doc, &new_doc);
+                let changed = formatting_only || !changes.is_empty();
+                let outcome = outcome(changed);
+                let exit_code = match fail_on {
+                    Some(policy) => {
+                        fail_on_kind(policy, outcome).map_or(0, ErrorKind::diff_exit_code)
+                    }
+                    None => differs_code(changed),
+                };
+                print_diff_report(config, &changes, &findings, fail_on, outcome, exit_code)?;
+                changed
+            }
+        }
+    };
+
+    let Some(policy) = fail_on else {
+        if !breaking {
+            return Ok(differs_code(changed));
+        }
+        if findings.is_empty() {
+            return Ok(0);
+        }
+        return Err(breaking_error(old, new, &findings));
+    };
+    let outcome = outcome(changed);
+    let line = policy_line(policy, outcome);
+    // The JSON report carries the policy itself; patches and changelogs
+    // stay clean on stdout.
+    if breaking || format == DiffFormat::Text {
+        println!("{line}");
+    } else if format != DiffFormat::Json {
+        eprintln!("{line}");
+    }
+    let message = match outcome {
+        Outcome::Breaking => breaking_error(old, new, &findings).to_string(),
+        _ => format!("{new} differs from {old}."),
+    };
+    enforce_fail_on(policy, outcome, message).map(|()| 0)
+}
+
+fn breaking_error(old: &str, new: &str, findings: &[BreakingChange]) -> AppError {
+    AppError::breaking(format!(
+        "{new} has {} compared with {old}.",
+        summarize_breaking(findings)
+    ))
+}
+
+/// The line that says which `--fail-on` policy was applied and how it came
+/// out, e.g. `--fail-on any: breaking, exit 10`.
+fn policy_line(policy: FailOn, outcome: Outcome) -> String {
+    let name = policy
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default();
+    let code = fail_on_kind(policy, outcome).map_or(0, ErrorKind::diff_exit_code);
+    format!("--fail-on {name}: {}, exit {code}", outcome.as_str())
+}
+
+/// The exit code of a comparison that found differences or none: a plain
+/// `diff` reports them without failing, with the same code as a drift error.
+fn differs_code(changed: bool) -> i32 {
+    if changed {
+        ErrorKind::Drift.diff_exit_code()
+    } else {
+        0
+    }
+}
+
+/// Prints the changes from `old` to `new`, or "no differences"; returns
+/// whether there were any.
+fn print_diff(config: &Config, old: &Value, new: &Value, formatting_only: bool) -> bool {
+    let changes = diff_documents(old, new);
+    if changes.is_empty() {
+        if formatting_only {
+            println!("documents differ only in formatting");
+            return true;
+        }
+        println!("no differences");
+        return false;
+    }
+    let palette = Palette::stdout(config.color);
+    for change in &changes {
+        println!("{}", palette.change(change));
+    }
+    println!("{}", summarize_changes(&changes));
+    true
+}
+
+/// Prints an RFC 6902 patch from `old` to `new`, `[]` when they match;
+/// returns whether it is not empty.
+fn print_json_patch(config: &Config, old: &Value, new: &Value) -> Result<bool, AppError> {
+    let operations = json_patch(old, new);
+    let patch = serde_json::to_value(&operations)
+        .map_err(|err| AppError::json(format!("json error: {err}")).with_source(err))?;
+    println!("{}", serialize_json(&patch, config.minify)?);
+    Ok(!operations.is_empty())
+}
+
+/// Prints a markdown changelog from `old` to `new`; returns whether any
+/// operation or schema changed.
+fn print_markdown(old: &Value, new: &Value, max_items: Option<usize>) -> bool {
+    print!("{}", markdown_changelog(old, new, max_items));
+    !diff_specs(old, new).is_empty()
+}
+
+/// Prints one line per changed operation and schema, and nothing when
+/// there are none; returns whether there were any.
+fn print_operations(old: &Value, new: &Value) -> bool {
+    let lines = format_operations(&diff_specs(old, new));
+    for line in &lines {
+        println!("{line}");
+    }
+    !lines.is_empty()
+}
+
+/// Prints the changes, the breaking findings, and the `--fail-on` policy,
+/// outcome, and exit code as one JSON object.
+fn print_diff_report(
+    config: &Config,
+    changes: &[SpecChange],
+    findings: &[BreakingChange],
+    fail_on: Option<FailOn>,
+    outcome: Outcome,
+    exit_code: i32,
+) -> Result<(), AppError> {
+    let report = json!({
+        "fail_on": fail_on,
+        "outcome": outcome.as_str(),
+        "exit_code": exit_code,
+        "changes": changes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "breaking": findings
+            .iter()
+            .map(|change| json!({
+                "rule": change.rule,
+                "location": change.location,
+                "message": change.message,
+            }))
+            .collect::<Vec<_>>(),
+    });
+    println!("{}", serialize_json(&report, config.minify)?);
+    Ok(())
+}
+
+/// Prints each change as `rule location message`, then the count.
+fn print_breaking_changes(changes: &[BreakingChange]) {
+    for change in changes {
+        println!("{change}");
+    }
+    println!("{}", summarize_breaking(changes));
+}
+
+/// Fetches each target once and prints what would be written.
+fn print_dry_run(configs: &[Config]) -> Result<(), AppError> {
+    for (index, config) in configs.iter().enumerate() {
+        let outputs = build_outputs(config)?;
+        if index > 0 {
+            println!();
+        }
+        println!("{}", dry_run_summary(config, &outputs));
+    }
+    Ok(())
+}
+
+/// Prints each finding as `severity path message`.
+fn print_findings(
+    config: &Config,
+    doc: &serde_json::Value,
+    max_warnings: Option<usize>,
+) -> Result<(), AppError> {
+    let findings = validate_spec_with(doc, &config.validate_options);
+    if findings.is_empty() {
+        println!("no problems found");
+    }
+    for finding in &findings {
+        println!("{finding}");
+    }
+    for site in check_refs(doc).unchecked {
+        println!("not checked: {site}");
+    }
+    check_findings(&findings, max_warnings)
+}
+
+/// Exits for a command line that could not be parsed. `diff` and `check`
+/// keep 1 for "the documents differ", so their usage errors exit
+/// [`COMPARE_FAILED`]; `--help` and `--version` still exit 0.
+fn exit_with_cli_error(err: CliError) -> ! {
+    let compares = matches!(
+        subcommand_name(std::env::args_os()).as_deref(),
+        Some("diff" | "check")
+    );
+    match err {
+        CliError::Clap(err) if compares && err.use_stderr() => {
+            let _ = err.print();
+            std::process::exit(COMPARE_FAILED);
+        }
+        CliError::Clap(err) => err.exit(),
+        CliError::App(err) if compares => {
+            eprintln!("{err}");
+            std::process::exit(err.diff_exit_code());
+        }
+        CliError::App(err) => exit_with_error(err),
     }
 }
 
@@ -53,3 +833,38 @@ fn exit_with_error(err: AppError) -> ! {
     eprintln!("{err}");
     std::process::exit(err.exit_code());
 }
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn a_failed_retry_after_the_prompt_falls_back_to_the_last_snapshot() {
+        let temp = tempfile::tempdir().unwrap();
+        let out = temp.path().join("openapi.json");
+        std::fs::write(&out, "{\"committed\": true}\n").unwrap();
+        let cli = Cli::try_parse_from([
+            "openapi-snapshot",
+            "--url",
+            "http://127.0.0.1:9/openapi.json",
+            "--out",
+            out.to_str().unwrap(),
+            "--fallback-to-last",
+            "--fallback-exit-code",
+            "75",
+        ])
+        .unwrap();
+        let (mut config, _) = Config::from_cli(cli).unwrap();
+
+        let code = snapshot_with_prompt(&mut config, |config, _| {
+            config.url = "http://127.0.0.1:9/api-docs/openapi.json".to_string();
+            Ok(true)
+        });
+        assert_eq!(code.unwrap(), 75);
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "{\"committed\": true}\n"
+        );
+    }
+}
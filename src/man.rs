@@ -0,0 +1,157 @@
+use std::io::Write;
+use std::path::Path;
+
+use clap::CommandFactory;
+use clap_mangen::Man;
+use clap_mangen::roff::{Roff, bold, roman};
+
+use crate::cli::{Cli, EXAMPLES};
+use crate::errors::{AppError, EXIT_CODES};
+use crate::output::write_atomic;
+
+/// Writes the manual page to `out`, or to stdout without one. Like the
+/// completion scripts, it is generated from the clap definition.
+pub fn write_man(out: Option<&Path>) -> Result<(), AppError> {
+    let page = render_man()?;
+    match out {
+        Some(path) => write_atomic(path, &page),
+        None => {
+            let mut stdout = std::io::stdout();
+            stdout
+                .write_all(page.as_bytes())
+                .and_then(|()| stdout.flush())
+                .map_err(|err| {
+                    AppError::io(format!("failed to write manual page: {err}")).with_source(err)
+                })
+        }
+    }
+}
+
+/// The manual page as roff: the usual clap sections, then the options of
+/// each subcommand, the examples, and the exit codes.
+pub fn render_man() -> Result<String, AppError> {
+    let command = Cli::command();
+    let man = Man::new(command.clone());
+    let mut page = Page::default();
+    page.section(|out| man.render_title(out))?;
+    page.section(|out| man.render_name_section(out))?;
+    page.section(|out| man.render_synopsis_section(out))?;
+    page.section(|out| man.render_description_section(out))?;
+    page.section(|out| man.render_options_section(out))?;
+    page.section(|out| man.render_subcommands_section(out))?;
+
+    // Rendering each subcommand on its own leaves out the shared flags,
+    // which clap only copies into subcommands when building the parent.
+    let mut heading = Roff::new();
+    heading.control("SH", ["SUBCOMMAND OPTIONS"]);
+    page.section(|out| heading.to_writer(out))?;
+    for subcommand in command.get_subcommands() {
+        if subcommand.get_arguments().next().is_none() {
+            continue;
+        }
+        let mut heading = Roff::new();
+        heading.control("SS", [subcommand.get_name()]);
+        page.section(|out| heading.to_writer(out))?;
+        let man = Man::new(subcommand.clone());
+        page.section(|out| man.render_options_section(out))?;
+        page.strip_heading(".SH OPTIONS\n");
+    }
+
+    let mut extra = Roff::new();
+    extra.control("SH", ["EXAMPLES"]).control("nf", []);
+    for example in EXAMPLES {
+        extra.text([roman(example)]);
+    }
+    extra.control("fi", []).control("SH", ["EXIT CODES"]);
+    for (code, meaning) in EXIT_CODES {
+        extra
+            .control("TP", [])
+            .text([bold(code.to_string())])
+            .text([roman(meaning)]);
+    }
+    page.section(|out| extra.to_writer(out))?;
+    page.section(|out| man.render_version_section(out))?;
+    Ok(page.text)
+}
+
+/// Concatenated roff sections. Every rendered section starts with the same
+/// preamble, which only the first one keeps.
+#[derive(Default)]
+struct Page {
+    text: String,
+    /// Where the most recent section's content starts.
+    last: usize,
+}
+
+impl Page {
+    fn section(
+        &mut self,
+        render: impl FnOnce(&mut Vec<u8>) -> std::io::Result<()>,
+    ) -> Result<(), AppError> {
+        let mut out = Vec::new();
+        render(&mut out).map_err(|err| {
+            AppError::io(format!("failed to render manual page: {err}")).with_source(err)
+        })?;
+        let section = String::from_utf8_lossy(&out);
+        let preamble = Roff::new().render();
+        if self.text.is_empty() {
+            self.text.push_str(&preamble);
+        }
+        self.last = self.text.len();
+        self.text
+            .push_str(section.strip_prefix(preamble.as_str()).unwrap_or(&section));
+        Ok(())
+    }
+
+    /// Drops `heading` from the start of the most recent section.
+    fn strip_heading(&mut self, heading: &str) {
+        if self.text[self.last..].starts_with(heading) {
+            self.text
+                .replace_range(self.last..self.last + heading.len(), "");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_covers_flags_subcommands_examples_and_exit_codes() {
+        let page = render_man().unwrap();
+        assert!(page.contains("\n.TH openapi-snapshot 1 "), "{page}");
+        for section in [
+            ".SH SYNOPSIS",
+            ".SH OPTIONS",
+            ".SH SUBCOMMANDS",
+            ".SH \"SUBCOMMAND OPTIONS\"",
+            ".SS watch",
+            ".SH EXAMPLES",
+            ".SH \"EXIT CODES\"",
+        ] {
+            assert!(page.contains(section), "{section}");
+        }
+        assert!(page.contains("outline\\-out"));
+        assert!(page.contains("interval\\-ms"));
+        if cfg!(feature = "http") {
+            assert!(page.contains("[default: 10000]"));
+        }
+        assert!(page.contains("not found (HTTP 404)"));
+        assert_eq!(page.matches(".ds Aq").count(), 2, "one preamble");
+        // Shared flags are documented once, not under every subcommand.
+        let (_, subcommands) = page.split_once(".SS watch").unwrap();
+        assert!(!subcommands.contains("\\-\\-timeout\\-ms"));
+        assert!(!subcommands.contains(".SH OPTIONS"));
+    }
+
+    #[test]
+    fn help_lists_the_same_examples_and_exit_codes() {
+        let help = Cli::command().render_help().to_string();
+        for example in EXAMPLES {
+            assert!(help.contains(example), "{example}");
+        }
+        for (code, meaning) in EXIT_CODES {
+            assert!(help.contains(&format!("{code}  {meaning}")), "{code}");
+        }
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::errors::AppError;
+use crate::outline::is_http_method;
+
+/// What to do when two documents define the same path and method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PathConflict {
+    #[default]
+    Error,
+    PreferFirst,
+    PreferLast,
+}
+
+/// A document to merge and the name of the service it came from, used in
+/// messages and as the suffix for renamed components.
+#[derive(Debug, Clone)]
+pub struct MergeInput {
+    pub service: String,
+    pub doc: Value,
+}
+
+/// Combines documents into one: the union of their paths and components and
+/// a synthesized `info` block. Identical components are shared. Differing
+/// components with the same name are an error, unless `rename_collisions`
+/// is set: then the later one gets the service name as a suffix and the
+/// `$ref`s in its document are rewritten to match.
+pub fn merge_specs(
+    inputs: Vec<MergeInput>,
+    paths_conflict: PathConflict,
+    rename_collisions: bool,
+) -> Result<Value, AppError> {
+    let mut paths = Map::new();
+    let mut owners: BTreeMap<(String, String), String> = BTreeMap::new();
+    let mut components: Map<String, Value> = Map::new();
+    let mut tags: Vec<Value> = Vec::new();
+    let mut titles = Vec::new();
+    let mut versions = Vec::new();
+    let mut openapi = None;
+
+    for MergeInput { service, mut doc } in inputs {
+        let renames = component_renames(&components, &doc, &service, rename_collisions)?;
+        if !renames.is_empty() {
+            rewrite_refs(&mut doc, &renames);
+            rename_components(&mut doc, &renames);
+        }
+
+        openapi = openapi.or_else(|| doc.get("openapi").cloned());
+        if let Some(title) = doc.pointer("/info/title").and_then(Value::as_str) {
+            titles.push(title.to_string());
+        }
+        if let Some(version) = doc.pointer("/info/version").and_then(Value::as_str) {
+            versions.push(version.to_string());
+        }
+
+        let incoming = doc
+            .get("paths")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten();
+        for (path, item) in incoming {
+            let Some(item) = item.as_object() else {
+                continue;
+            };
+            let merged = paths
+                .entry(path.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            let Some(merged) = merged.as_object_mut() else {
+                continue;
+            };
+            for (key, value) in item {
+                if !merged.contains_key(key) {
+                    merged.insert(key.clone(), value.clone());
+                    if is_http_method(key) {
+                        owners.insert((path.clone(), key.clone()), service.clone());
+                    }
+                    continue;
+                }
+                if !is_http_method(key) {
+                    if paths_conflict == PathConflict::PreferLast {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                    continue;
+                }
+                match paths_conflict {
+                    PathConflict::PreferFirst => {}
+                    PathConflict::PreferLast => {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                    PathConflict::Error => {
+                        let owner = owners
+                            .get(&(path.clone(), key.clone()))
+                            .map_or("an earlier document", String::as_str);
+                        return Err(AppError::usage(format!(
+                            "{} {path} is defined by both {owner} and {service}; pass \
+                             --prefer-first or --prefer-last to choose one.",
+                            key.to_ascii_uppercase()
+                        )));
+                    }
+                }
+            }
+        }
+
+        let sections = doc
+            .get("components")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten();
+        for (section, entries) in sections {
+            let Some(entries) = entries.as_object() else {
+                continue;
+            };
+            let merged = components
+                .entry(section.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Some(merged) = merged.as_object_mut() {
+                for (name, value) in entries {
+                    merged.entry(name.clone()).or_insert_with(|| value.clone());
+                }
+            }
+        }
+
+        for tag in doc
+            .get("tags")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let name = tag.get("name");
+            if !tags.iter().any(|known| known.get("name") == name) {
+                tags.push(tag.clone());
+            }
+        }
+    }
+
+    let mut merged = Map::new();
+    merged.insert(
+        "openapi".to_string(),
+        openapi.unwrap_or_else(|| Value::String("3.0.3".to_string())),
+    );
+    let title = if titles.is_empty() {
+        "Merged API".to_string()
+    } else {
+        titles.join(" + ")
+    };
+    let version = if versions.is_empty() {
+        "0.0.0".to_string()
+    } else {
+        versions.join("+")
+    };
+    merged.insert(
+        "info".to_string(),
+        json!({"title": title, "version": version}),
+    );
+    if !tags.is_empty() {
+        merged.insert("tags".to_string(), Value::Array(tags));
+    }
+    merged.insert("paths".to_string(), Value::Object(paths));
+    if !components.is_empty() {
+        merged.insert("components".to_string(), Value::Object(components));
+    }
+    Ok(Value::Object(merged))
+}
+
+/// Maps `#/components/<section>/<name>` to its new pointer for every
+/// component in `doc` that collides with a different one already merged.
+fn component_renames(
+    merged: &Map<String, Value>,
+    doc: &Value,
+    service: &str,
+    rename_collisions: bool,
+) -> Result<BTreeMap<String, String>, AppError> {
+    let mut renames = BTreeMap::new();
+    let sections = doc
+        .get("components")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten();
+    for (section, entries) in sections {
+        let existing = merged.get(section).and_then(Value::as_object);
+        let entries = entries.as_object().into_iter().flatten();
+        for (name, value) in entries {
+            let Some(previous) = existing.and_then(|existing| existing.get(name)) else {
+                continue;
+            };
+            if previous == value {
+                continue;
+            }
+            if !rename_collisions {
+                return Err(AppError::usage(format!(
+                    "components.{section}.{name} in {service} differs from an earlier \
+                     definition; pass --rename-collisions to keep both."
+                )));
+            }
+            let renamed = format!("{name}{}", pascal_case(service));
+            if existing.is_some_and(|existing| existing.contains_key(&renamed)) {
+                return Err(AppError::usage(format!(
+                    "cannot rename components.{section}.{name} to {renamed}; that name is taken."
+                )));
+            }
+            renames.insert(
+                format!("#/components/{section}/{name}"),
+                format!("#/components/{section}/{renamed}"),
+            );
+        }
+    }
+    Ok(renames)
+}
+
+fn rewrite_refs(value: &mut Value, renames: &BTreeMap<String, String>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::String(target)) = object.get_mut("$ref")
+                && let Some(renamed) = renames.get(target.as_str())
+            {
+                *target = renamed.clone();
+            }
+            for child in object.values_mut() {
+                rewrite_refs(child, renames);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                rewrite_refs(item, renames);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn rename_components(doc: &mut Value, renames: &BTreeMap<String, String>) {
+    for (from, to) in renames {
+        let (Some(from), Some(to)) = (from.strip_prefix('#'), to.strip_prefix('#')) else {
+            continue;
+        };
+        let Some((section, old_name)) = from
+            .strip_prefix("/components/")
+            .and_then(|rest| rest.split_once('/'))
+        else {
+            continue;
+        };
+        let Some(new_name) = to.rsplit('/').next() else {
+            continue;
+        };
+        let entries = doc
+            .pointer_mut(&format!("/components/{section}"))
+            .and_then(Value::as_object_mut);
+        if let Some(entries) = entries
+            && let Some(value) = entries.remove(old_name)
+        {
+            entries.insert(new_name.to_string(), value);
+        }
+    }
+}
+
+/// `billing-api` → `BillingApi`.
+fn pascal_case(name: &str) -> String {
+    name.split(|ch: char| !ch.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Names the service a merge source came from: a URL's host, a file's stem,
+/// or `stdin`.
+pub fn service_name(source: &str) -> String {
+    if source == crate::fetch::STDIN_PATH {
+        return "stdin".to_string();
+    }
+    if let Ok(url) = url::Url::parse(source)
+        && let Some(host) = url.host_str()
+    {
+        return host.to_string();
+    }
+    std::path::Path::new(source).file_stem().map_or_else(
+        || source.to_string(),
+        |stem| stem.to_string_lossy().into_owned(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::{Severity, validate_spec};
+
+    fn input(service: &str) -> MergeInput {
+        let text = match service {
+            "users" => include_str!("../tests/fixtures/merge/users.json"),
+            "billing" => include_str!("../tests/fixtures/merge/billing.json"),
+            _ => include_str!("../tests/fixtures/merge/users_v2.json"),
+        };
+        MergeInput {
+            service: service.to_string(),
+            doc: serde_json::from_str(text).unwrap(),
+        }
+    }
+
+    #[test]
+    fn duplicate_operations_need_a_preference() {
+        let err = merge_specs(
+            vec![input("users"), input("users_v2")],
+            PathConflict::Error,
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "GET /users is defined by both users and users_v2; pass --prefer-first or \
+             --prefer-last to choose one."
+        );
+
+        let first = merge_specs(
+            vec![input("users"), input("users_v2")],
+            PathConflict::PreferFirst,
+            false,
+        )
+        .unwrap();
+        assert!(first["paths"]["/users"]["get"].get("summary").is_none());
+        assert!(first["paths"]["/users"].get("post").is_some());
+
+        let last = merge_specs(
+            vec![input("users"), input("users_v2")],
+            PathConflict::PreferLast,
+            false,
+        )
+        .unwrap();
+        assert_eq!(last["paths"]["/users"]["get"]["summary"], "List users (v2)");
+    }
+
+    #[test]
+    fn differing_schemas_are_an_error_or_renamed_with_refs_rewritten() {
+        let err = merge_specs(
+            vec![input("users"), input("billing")],
+            PathConflict::Error,
+            false,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("components.schemas.User in billing")
+        );
+
+        let merged = merge_specs(
+            vec![input("users"), input("billing")],
+            PathConflict::Error,
+            true,
+        )
+        .unwrap();
+        let schemas = merged["components"]["schemas"].as_object().unwrap();
+        let names: Vec<&String> = schemas.keys().collect();
+        assert_eq!(names, ["Error", "User", "UserBilling"]);
+        let invoices = &merged["paths"]["/invoices"]["get"]["responses"];
+        assert_eq!(
+            invoices["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/UserBilling"
+        );
+        assert_eq!(
+            invoices["default"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/Error"
+        );
+        let users = &merged["paths"]["/users"]["get"]["responses"]["200"];
+        assert_eq!(
+            users["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/User"
+        );
+    }
+
+    #[test]
+    fn merged_document_has_synthesized_info_and_passes_validation() {
+        let merged = merge_specs(
+            vec![input("users"), input("billing")],
+            PathConflict::Error,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            merged["info"],
+            json!({"title": "Users + Billing", "version": "1.2.0+0.9.0"})
+        );
+        assert_eq!(
+            merged["tags"],
+            json!([{"name": "users"}, {"name": "billing"}])
+        );
+        let errors: Vec<_> = validate_spec(&merged)
+            .into_iter()
+            .filter(|finding| finding.severity == Severity::Error)
+            .collect();
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn service_name_uses_host_stem_or_stdin() {
+        assert_eq!(service_name("http://billing:8080/openapi.json"), "billing");
+        assert_eq!(service_name("specs/users-api.json"), "users-api");
+        assert_eq!(service_name("-"), "stdin");
+        assert_eq!(pascal_case("users-api"), "UsersApi");
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::errors::AppError;
+use crate::output::write_atomic;
+
+/// Per-target counters exported by `--metrics-file`.
+///
+/// Each target becomes one series labelled `url="<url or input path>"`:
+///
+/// - `<prefix>_polls_total` (counter): polls attempted since watch started.
+/// - `<prefix>_consecutive_failures` (gauge): failed polls since the last
+///   success.
+/// - `<prefix>_last_success_timestamp_seconds` (gauge): Unix time of the last
+///   successful fetch, 0 before the first one.
+/// - `<prefix>_last_change_timestamp_seconds` (gauge): Unix time the snapshot
+///   was last written because the spec changed, 0 if it never was.
+/// - `<prefix>_last_payload_bytes` (gauge): size of the last fetched document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TargetMetrics {
+    pub url: String,
+    pub polls_total: u64,
+    pub consecutive_failures: u64,
+    pub last_success: i64,
+    pub last_change: i64,
+    pub last_payload_bytes: usize,
+}
+
+/// Rejects prefixes that would produce invalid Prometheus metric names.
+pub fn validate_prefix(prefix: &str) -> Result<(), AppError> {
+    let mut chars = prefix.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_' || first == ':')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == ':');
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::usage(format!(
+            "invalid --metrics-prefix '{prefix}': use letters, digits, '_' or ':' and do not start with a digit."
+        )))
+    }
+}
+
+/// Renders the Prometheus text exposition format.
+pub fn render_metrics(prefix: &str, targets: &[TargetMetrics]) -> String {
+    type Field = fn(&TargetMetrics) -> String;
+    let families: [(&str, &str, &str, Field); 5] = [
+        (
+            "polls_total",
+            "counter",
+            "Polls attempted since watch started.",
+            |t| t.polls_total.to_string(),
+        ),
+        (
+            "consecutive_failures",
+            "gauge",
+            "Failed polls since the last successful fetch.",
+            |t| t.consecutive_failures.to_string(),
+        ),
+        (
+            "last_success_timestamp_seconds",
+            "gauge",
+            "Unix time of the last successful fetch.",
+            |t| t.last_success.to_string(),
+        ),
+        (
+            "last_change_timestamp_seconds",
+            "gauge",
+            "Unix time of the last snapshot change.",
+            |t| t.last_change.to_string(),
+        ),
+        (
+            "last_payload_bytes",
+            "gauge",
+            "Size in bytes of the last fetched document.",
+            |t| t.last_payload_bytes.to_string(),
+        ),
+    ];
+
+    let mut out = String::new();
+    for (name, kind, help, value) in families {
+        let _ = writeln!(out, "# HELP {prefix}_{name} {help}");
+        let _ = writeln!(out, "# TYPE {prefix}_{name} {kind}");
+        for target in targets {
+            let _ = writeln!(
+                out,
+                "{prefix}_{name}{{url=\"{}\"}} {}",
+                escape_label(&target.url),
+                value(target)
+            );
+        }
+    }
+    out
+}
+
+pub fn write_metrics(path: &Path, prefix: &str, targets: &[TargetMetrics]) -> Result<(), AppError> {
+    write_atomic(path, &render_metrics(prefix, targets))
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_every_family_with_help_and_type() {
+        let target = TargetMetrics {
+            url: "http://localhost:3000/openapi.json".to_string(),
+            polls_total: 3,
+            consecutive_failures: 1,
+            last_success: 1_714_566_664,
+            last_change: 0,
+            last_payload_bytes: 512,
+        };
+        let text = render_metrics("openapi_snapshot", &[target]);
+        assert_eq!(
+            text,
+            "# HELP openapi_snapshot_polls_total Polls attempted since watch started.\n\
+             # TYPE openapi_snapshot_polls_total counter\n\
+             openapi_snapshot_polls_total{url=\"http://localhost:3000/openapi.json\"} 3\n\
+             # HELP openapi_snapshot_consecutive_failures Failed polls since the last successful fetch.\n\
+             # TYPE openapi_snapshot_consecutive_failures gauge\n\
+             openapi_snapshot_consecutive_failures{url=\"http://localhost:3000/openapi.json\"} 1\n\
+             # HELP openapi_snapshot_last_success_timestamp_seconds Unix time of the last successful fetch.\n\
+             # TYPE openapi_snapshot_last_success_timestamp_seconds gauge\n\
+             openapi_snapshot_last_success_timestamp_seconds{url=\"http://localhost:3000/openapi.json\"} 1714566664\n\
+             # HELP openapi_snapshot_last_change_timestamp_seconds Unix time of the last snapshot change.\n\
+             # TYPE openapi_snapshot_last_change_timestamp_seconds gauge\n\
+             openapi_snapshot_last_change_timestamp_seconds{url=\"http://localhost:3000/openapi.json\"} 0\n\
+             # HELP openapi_snapshot_last_payload_bytes Size in bytes of the last fetched document.\n\
+             # TYPE openapi_snapshot_last_payload_bytes gauge\n\
+             openapi_snapshot_last_payload_bytes{url=\"http://localhost:3000/openapi.json\"} 512\n"
+        );
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[test]
+    fn prefix_must_be_a_valid_metric_name() {
+        assert!(validate_prefix("openapi_snapshot").is_ok());
+        assert!(validate_prefix("team:api_snapshot").is_ok());
+        for prefix in ["", "9lives", "bad-prefix", "with space"] {
+            assert!(
+                matches!(validate_prefix(prefix), Err(AppError::Usage { .. })),
+                "{prefix}"
+            );
+        }
+    }
+}
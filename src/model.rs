@@ -0,0 +1,264 @@
+//! Typed views of the parts of an OpenAPI document that traversal code
+//! reads: path items, operations, parameters, and media types. Everything
+//! else, including schemas, stays as [`Value`]. Keys the model does not name
+//! are kept in each struct's `extra` map, so a value round-trips unchanged
+//! and a mistyped field nothing reads, like `operationId: 7`, is not an
+//! error.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub(crate) type JsonMap = serde_json::Map<String, Value>;
+
+/// A `{"$ref": ...}` object. Siblings of `$ref` are kept in `extra`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Reference {
+    #[serde(rename = "$ref")]
+    pub reference: String,
+    #[serde(flatten)]
+    pub extra: JsonMap,
+}
+
+/// A component reference or the component itself. An object whose `$ref`
+/// is a string is always read as a reference.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub(crate) enum RefOr<T> {
+    Ref(Reference),
+    Inline(T),
+}
+
+/// A schema is either a reference or an inline schema, left untyped.
+pub(crate) type SchemaRefOrInline = RefOr<Value>;
+
+/// Entries of `paths`. Operations are typed fields; anything else
+/// (`summary`, `parameters`, `servers`, `x-*`) lands in `extra`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) struct PathItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub get: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub put: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delete: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub head: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Operation>,
+    #[serde(flatten)]
+    pub extra: JsonMap,
+}
+
+impl PathItem {
+    /// The item's operations with their lower-case method names, in the
+    /// order the specification lists the fields.
+    pub fn operations(&self) -> impl Iterator<Item = (&'static str, &Operation)> {
+        [
+            ("get", &self.get),
+            ("put", &self.put),
+            ("post", &self.post),
+            ("delete", &self.delete),
+            ("options", &self.options),
+            ("head", &self.head),
+            ("patch", &self.patch),
+            ("trace", &self.trace),
+        ]
+        .into_iter()
+        .filter_map(|(method, op)| op.as_ref().map(|op| (method, op)))
+    }
+}
+
+/// An operation. `operationId`, `summary`, and the other keys outlines
+/// do not walk stay in `extra`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Operation {
+    /// `None` when the operation has no `parameters` key, as opposed to an
+    /// empty list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Vec<RefOr<Parameter>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<RefOr<RequestBody>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub responses: Option<BTreeMap<String, RefOr<Response>>>,
+    #[serde(flatten)]
+    pub extra: JsonMap,
+}
+
+/// A parameter. Every field is optional so that callers decide which
+/// omissions are errors. `required` is kept as written and read through
+/// [`Parameter::required`], so a non-bool like `"true"` is not an error.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Parameter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "in", skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<SchemaRefOrInline>,
+    #[serde(flatten)]
+    pub extra: JsonMap,
+}
+
+impl Parameter {
+    /// Whether `required` is `true`; missing or not a bool counts as false.
+    pub fn required(&self) -> bool {
+        self.required
+            .as_ref()
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) struct RequestBody {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<BTreeMap<String, MediaType>>,
+    #[serde(flatten)]
+    pub extra: JsonMap,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Response {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<BTreeMap<String, MediaType>>,
+    #[serde(flatten)]
+    pub extra: JsonMap,
+}
+
+/// One entry of a `content` map.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) struct MediaType {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<SchemaRefOrInline>,
+    #[serde(flatten)]
+    pub extra: JsonMap,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn path_item(value: Value) -> PathItem {
+        PathItem::deserialize(&value).unwrap()
+    }
+
+    #[test]
+    fn references_win_over_inline_values() {
+        let param: RefOr<Parameter> =
+            serde_json::from_value(json!({"$ref": "#/components/parameters/Limit"})).unwrap();
+        assert!(matches!(
+            param,
+            RefOr::Ref(Reference { ref reference, .. }) if reference == "#/components/parameters/Limit"
+        ));
+
+        let param: RefOr<Parameter> =
+            serde_json::from_value(json!({"name": "limit", "in": "query"})).unwrap();
+        let RefOr::Inline(param) = param else {
+            panic!("expected an inline parameter");
+        };
+        assert_eq!(param.name.as_deref(), Some("limit"));
+        assert_eq!(param.location.as_deref(), Some("query"));
+        assert_eq!(param.required, None);
+        assert!(!param.required());
+        assert_eq!(param.schema, None);
+    }
+
+    #[test]
+    fn non_bool_required_counts_as_optional() {
+        let value = json!({"name": "limit", "in": "query", "required": "true"});
+        let param: Parameter = serde_json::from_value(value.clone()).unwrap();
+        assert!(!param.required());
+        assert_eq!(serde_json::to_value(&param).unwrap(), value);
+
+        let param: Parameter = serde_json::from_value(json!({"required": true})).unwrap();
+        assert!(param.required());
+    }
+
+    #[test]
+    fn non_string_refs_are_inline() {
+        let schema: SchemaRefOrInline = serde_json::from_value(json!({"$ref": 5})).unwrap();
+        assert_eq!(schema, RefOr::Inline(json!({"$ref": 5})));
+    }
+
+    #[test]
+    fn missing_fields_are_none_or_empty() {
+        let item = path_item(json!({"get": {}}));
+        assert!(item.extra.is_empty());
+        let (method, op) = item.operations().next().unwrap();
+        assert_eq!(method, "get");
+        assert_eq!(op.parameters, None);
+        assert_eq!(op.request_body, None);
+        assert_eq!(op.responses, None);
+        assert!(op.extra.is_empty());
+    }
+
+    #[test]
+    fn operations_skip_non_method_keys() {
+        let item = path_item(json!({
+            "summary": "users",
+            "parameters": [{"$ref": "#/components/parameters/Id"}],
+            "post": {"operationId": "createUser"},
+            "get": {"operationId": "listUsers"},
+            "x-internal": true
+        }));
+        let methods: Vec<_> = item.operations().map(|(method, _)| method).collect();
+        assert_eq!(methods, ["get", "post"]);
+        assert_eq!(
+            item.extra.keys().collect::<Vec<_>>(),
+            ["parameters", "summary", "x-internal"]
+        );
+    }
+
+    #[test]
+    fn unknown_fields_round_trip() {
+        let value = json!({
+            "parameters": [{"name": "id", "in": "path", "required": true, "schema": {"type": "string"}, "example": "42"}],
+            "get": {
+                "operationId": "getUser",
+                "tags": ["users"],
+                "deprecated": true,
+                "requestBody": {"$ref": "#/components/requestBodies/User", "description": "sibling"},
+                "responses": {
+                    "200": {"description": "ok", "content": {"application/json": {"schema": {"$ref": "#/components/schemas/User"}, "example": {}}}},
+                    "404": {"$ref": "#/components/responses/NotFound"}
+                },
+                "x-rate-limit": 10
+            },
+            "servers": []
+        });
+        let item = path_item(value.clone());
+        assert_eq!(serde_json::to_value(&item).unwrap(), value);
+    }
+
+    #[test]
+    fn wrong_types_are_errors() {
+        assert!(PathItem::deserialize(&json!([])).is_err());
+        assert!(PathItem::deserialize(&json!({"get": []})).is_err());
+        assert!(PathItem::deserialize(&json!({"get": {"parameters": {}}})).is_err());
+        assert!(PathItem::deserialize(&json!({"get": {"responses": []}})).is_err());
+    }
+
+    #[test]
+    fn unread_fields_may_have_any_type() {
+        let value = json!({
+            "parameters": {"id": "not a list"},
+            "summary": ["users"],
+            "get": {"operationId": 7, "summary": false, "description": null, "tags": "users"}
+        });
+        let item = path_item(value.clone());
+        assert!(item.get.is_some());
+        assert_eq!(serde_json::to_value(&item).unwrap(), value);
+    }
+}
@@ -0,0 +1,392 @@
+//! Canonical formatting for spec files, so that tooling differences do not
+//! show up as diffs.
+//!
+//! Object keys are sorted, except that the operations of a path item come in
+//! the order the OpenAPI specification lists them (`get`, `put`, `post`,
+//! ...) after the path item's other keys. Output is indented by a fixed
+//! number of spaces and ends with a newline.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+
+use crate::errors::AppError;
+use crate::fetch::parse_json;
+use crate::output::write_atomic;
+
+/// Floats at or above this magnitude are kept as they are: not every integer
+/// beyond it has an exact `f64` representation.
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// The order of operations in a path item.
+const METHOD_ORDER: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NormalizeOptions {
+    /// Spaces per level; 0 writes everything on one line.
+    pub indent: usize,
+    /// Sort arrays whose order carries no meaning: operation `tags`, the
+    /// top-level `tags` list (by name), and schema `required` lists.
+    pub sort_arrays: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            sort_arrays: false,
+        }
+    }
+}
+
+/// Rewrites `path` in canonical form unless it already is, returning whether
+/// it differed. With `check` the file is never written.
+pub fn normalize_file(
+    path: &Path,
+    options: &NormalizeOptions,
+    check: bool,
+) -> Result<bool, AppError> {
+    let existing = fs::read(path).map_err(|err| {
+        AppError::io(format!("failed to read {}: {err}", path.display())).with_source(err)
+    })?;
+    let normalized = normalize_document(&parse_json(&existing)?, options);
+    if existing == normalized.as_bytes() {
+        return Ok(false);
+    }
+    if !check {
+        write_atomic(path, &normalized)?;
+    }
+    Ok(true)
+}
+
+/// The canonical text of `doc`, with a trailing newline.
+pub fn normalize_document(doc: &Value, options: &NormalizeOptions) -> String {
+    let mut doc = doc.clone();
+    if options.sort_arrays {
+        sort_unordered_arrays(&mut doc, &mut Vec::new());
+    }
+    let mut out = String::new();
+    Writer {
+        indent: options.indent,
+        out: &mut out,
+    }
+    .value(&doc, &mut Vec::new(), 0);
+    out.push('\n');
+    out
+}
+
+/// The form `diff`, `check`, and watch change detection compare documents
+/// in, so two servers that serialize the same document differently agree:
+/// object keys are sorted and integral floats become integers (`1.0` and
+/// `1`, `-0.0` and `0`). Whitespace is already gone once a document is
+/// parsed.
+pub fn canonicalize(doc: &Value) -> Value {
+    match doc {
+        Value::Number(number) => canonical_number(number),
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), canonicalize(value)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Compact JSON text of the [`canonicalize`]d document, identical for
+/// documents that differ only in formatting.
+pub fn canonical_text(doc: &Value) -> String {
+    canonicalize(doc).to_string()
+}
+
+/// `doc` as `diff`, `check`, and watch compare it: [`canonicalize`]d
+/// unless `strict_bytes` (`--strict-bytes`) asks for the document as served.
+pub fn for_comparison(doc: Value, strict_bytes: bool) -> Value {
+    if strict_bytes {
+        doc
+    } else {
+        canonicalize(&doc)
+    }
+}
+
+fn canonical_number(number: &Number) -> Value {
+    match number.as_f64() {
+        Some(float)
+            if number.is_f64() && float.fract() == 0.0 && float.abs() < MAX_EXACT_INTEGER =>
+        {
+            Value::from(float as i64)
+        }
+        _ => Value::Number(number.clone()),
+    }
+}
+
+struct Writer<'a> {
+    indent: usize,
+    out: &'a mut String,
+}
+
+impl Writer<'_> {
+    /// Writes `value`; `location` is the chain of object keys leading to it,
+    /// which tells path items apart from other objects.
+    fn value<'v>(&mut self, value: &'v Value, location: &mut Vec<&'v str>, depth: usize) {
+        match value {
+            Value::Object(object) if !object.is_empty() => {
+                let mut keys: Vec<&String> = object.keys().collect();
+                if matches!(location.as_slice(), ["paths", _] | ["webhooks", _]) {
+                    keys.sort_by_key(|key| (method_rank(key), *key));
+                }
+                self.out.push('{');
+                for (index, key) in keys.into_iter().enumerate() {
+                    self.separator(index, depth + 1);
+                    self.out.push_str(&Value::String(key.clone()).to_string());
+                    self.out.push_str(if self.indent == 0 { ":" } else { ": " });
+                    location.push(key);
+                    if let Some(child) = object.get(key) {
+                        self.value(child, location, depth + 1);
+                    }
+                    location.pop();
+                }
+                self.newline(depth);
+                self.out.push('}');
+            }
+            Value::Array(items) if !items.is_empty() => {
+                self.out.push('[');
+                for (index, item) in items.iter().enumerate() {
+                    self.separator(index, depth + 1);
+                    // Array items are not reachable by key, so they start a
+                    // fresh location.
+                    self.value(item, &mut Vec::new(), depth + 1);
+                }
+                self.newline(depth);
+                self.out.push(']');
+            }
+            other => self.out.push_str(&other.to_string()),
+        }
+    }
+
+    fn separator(&mut self, index: usize, depth: usize) {
+        if index > 0 {
+            self.out.push(',');
+        }
+        self.newline(depth);
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if self.indent > 0 {
+            self.out.push('\n');
+            self.out.push_str(&" ".repeat(self.indent * depth));
+        }
+    }
+}
+
+/// Non-operation keys of a path item sort first (0), then operations in
+/// specification order.
+fn method_rank(key: &str) -> usize {
+    METHOD_ORDER
+        .iter()
+        .position(|method| *method == key)
+        .map_or(0, |index| index + 1)
+}
+
+fn sort_unordered_arrays(value: &mut Value, location: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, child) in object.iter_mut() {
+                location.push(key.clone());
+                sort_unordered_arrays(child, location);
+                location.pop();
+            }
+        }
+        Value::Array(items) => {
+            for item in items.iter_mut() {
+                sort_unordered_arrays(item, &mut Vec::new());
+            }
+            match location.as_slice() {
+                [tags] if tags == "tags" => {
+                    items.sort_by(|a, b| tag_name(a).cmp(tag_name(b)));
+                }
+                [.., last]
+                    if (last == "required" || last == "tags")
+                        && items.iter().all(Value::is_string) =>
+                {
+                    items.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+fn tag_name(tag: &Value) -> &str {
+    tag.get("name").and_then(Value::as_str).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture() -> Value {
+        json!({
+            "paths": {
+                "/users": {
+                    "post": {"tags": ["users", "admin"], "responses": {}},
+                    "summary": "Users",
+                    "get": {"responses": {"200": {"description": "ok"}}},
+                    "delete": {"responses": {}},
+                    "parameters": []
+                }
+            },
+            "openapi": "3.0.3",
+            "tags": [{"name": "users"}, {"name": "admin", "description": "Admin"}],
+            "components": {"schemas": {"User": {
+                "required": ["name", "id"],
+                "properties": {"name": {"type": "string"}, "id": {"type": "integer"}},
+                "enum": ["b", "a"]
+            }}}
+        })
+    }
+
+    #[test]
+    fn sorts_keys_and_orders_operations_canonically() {
+        let text = normalize_document(&fixture(), &NormalizeOptions::default());
+        let order: Vec<usize> = ["\"components\"", "\"openapi\"", "\"paths\"", "\"tags\""]
+            .iter()
+            .map(|key| text.find(key).unwrap())
+            .collect();
+        assert!(order.windows(2).all(|pair| pair[0] < pair[1]));
+        let item: Vec<usize> = [
+            "\"parameters\"",
+            "\"summary\"",
+            "\"get\"",
+            "\"post\"",
+            "\"delete\"",
+        ]
+        .iter()
+        .map(|key| text.find(key).unwrap())
+        .collect();
+        assert!(item.windows(2).all(|pair| pair[0] < pair[1]), "{text}");
+        assert!(text.ends_with("}\n"));
+        assert!(text.contains("\n  \"openapi\": \"3.0.3\",\n"));
+        assert!(text.contains("\"parameters\": [],"));
+        assert_eq!(serde_json::from_str::<Value>(&text).unwrap(), fixture());
+    }
+
+    #[test]
+    fn indentation_is_configurable() {
+        let doc = json!({"b": [1, {"c": {}}], "a": "x"});
+        let four = NormalizeOptions {
+            indent: 4,
+            ..NormalizeOptions::default()
+        };
+        assert_eq!(
+            normalize_document(&doc, &four),
+            "{\n    \"a\": \"x\",\n    \"b\": [\n        1,\n        {\n            \"c\": {}\n        }\n    ]\n}\n"
+        );
+        let compact = NormalizeOptions {
+            indent: 0,
+            ..NormalizeOptions::default()
+        };
+        assert_eq!(
+            normalize_document(&doc, &compact),
+            "{\"a\":\"x\",\"b\":[1,{\"c\":{}}]}\n"
+        );
+    }
+
+    #[test]
+    fn sort_arrays_only_touches_unordered_lists() {
+        let options = NormalizeOptions {
+            sort_arrays: true,
+            ..NormalizeOptions::default()
+        };
+        let text = normalize_document(&fixture(), &options);
+        let doc: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(doc["tags"][0]["name"], "admin");
+        assert_eq!(
+            doc["paths"]["/users"]["post"]["tags"],
+            json!(["admin", "users"])
+        );
+        assert_eq!(
+            doc["components"]["schemas"]["User"]["required"],
+            json!(["id", "name"])
+        );
+        assert_eq!(
+            doc["components"]["schemas"]["User"]["enum"],
+            json!(["b", "a"])
+        );
+
+        let unsorted = normalize_document(&fixture(), &NormalizeOptions::default());
+        assert!(unsorted.contains("\"users\",\n          \"admin\""));
+    }
+
+    #[test]
+    fn normalizing_twice_equals_normalizing_once() {
+        for options in [
+            NormalizeOptions::default(),
+            NormalizeOptions {
+                indent: 4,
+                sort_arrays: true,
+            },
+            NormalizeOptions {
+                indent: 0,
+                sort_arrays: false,
+            },
+        ] {
+            let once = normalize_document(&fixture(), &options);
+            let twice = normalize_document(&serde_json::from_str(&once).unwrap(), &options);
+            assert_eq!(once, twice);
+        }
+    }
+
+    #[test]
+    fn normalize_file_rewrites_once_and_check_never_writes() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("openapi.json");
+        let messy = fixture().to_string();
+        fs::write(&path, &messy).unwrap();
+        let options = NormalizeOptions::default();
+
+        assert!(normalize_file(&path, &options, true).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), messy);
+
+        assert!(normalize_file(&path, &options, false).unwrap());
+        let normalized = fs::read_to_string(&path).unwrap();
+        assert_eq!(normalized, normalize_document(&fixture(), &options));
+        assert!(!normalize_file(&path, &options, false).unwrap());
+        assert!(!normalize_file(&path, &options, true).unwrap());
+    }
+
+    #[test]
+    fn canonical_form_ignores_key_order_number_formatting_and_whitespace() {
+        let a: Value = serde_json::from_str(
+            r#"{"paths":{"/users":{"get":{"x-rate":1.0,"x-limit":100}}},"openapi":"3.0.3","x-zero":-0.0}"#,
+        )
+        .unwrap();
+        let b: Value = serde_json::from_str(
+            "{\n  \"openapi\": \"3.0.3\",\n  \"x-zero\": 0,\n  \"paths\": {\"/users\": {\"get\": {\"x-limit\": 1e2, \"x-rate\": 1}}}\n}",
+        )
+        .unwrap();
+        assert_ne!(a, b);
+        assert_eq!(canonicalize(&a), canonicalize(&b));
+        assert_eq!(canonical_text(&a), canonical_text(&b));
+        assert_eq!(
+            canonical_text(&a),
+            r#"{"openapi":"3.0.3","paths":{"/users":{"get":{"x-limit":100,"x-rate":1}}},"x-zero":0}"#
+        );
+    }
+
+    #[test]
+    fn canonical_form_keeps_real_differences() {
+        let doc = |value: Value| json!({"components": {"schemas": {"Price": {"maximum": value}}}});
+        assert_ne!(canonicalize(&doc(json!(1.5))), canonicalize(&doc(json!(1))));
+        assert_ne!(canonicalize(&doc(json!("1"))), canonicalize(&doc(json!(1))));
+        assert_ne!(canonicalize(&json!([1, 2])), canonicalize(&json!([2, 1])));
+        let huge = json!(1e300);
+        assert_eq!(canonicalize(&huge), huge);
+    }
+}
@@ -0,0 +1,24 @@
+const SUMMARY: &str = "openapi-snapshot";
+
+/// Shows a desktop notification for a snapshot change. When no notification
+/// service is available the error carries a line for the caller to log
+/// instead; delivery problems never affect snapshot writing.
+pub fn notify_change(body: &str) -> Result<(), String> {
+    deliver(body)
+        .map_err(|reason| format!("{SUMMARY}: {body} (notification unavailable: {reason})"))
+}
+
+#[cfg(feature = "notify")]
+fn deliver(body: &str) -> Result<(), String> {
+    notify_rust::Notification::new()
+        .summary(SUMMARY)
+        .body(body)
+        .show()
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(not(feature = "notify"))]
+fn deliver(_body: &str) -> Result<(), String> {
+    Err("built without the notify feature".to_string())
+}
@@ -1,27 +1,116 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 
+use crate::cli::OutlineCompat;
 use crate::errors::AppError;
+use crate::model::{JsonMap, MediaType, Operation, Parameter, PathItem, RefOr, SchemaRefOrInline};
 
-type JsonMap = serde_json::Map<String, Value>;
 type ResultValue = Result<Value, AppError>;
 
-pub fn outline_openapi(value: &Value) -> ResultValue {
+/// Settings for [`outline_openapi`]. The default is the command line's
+/// outline: v2 refs, full depth, no descriptions, and strict parsing.
+///
+/// ```
+/// use openapi_snapshot::outline::OutlineOptions;
+///
+/// let options = OutlineOptions::default().max_depth(Some(2)).short_refs(true);
+/// assert_eq!(options.max_depth, Some(2));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutlineOptions {
+    /// Shape of `$ref` entries (`--outline-compat`).
+    pub compat: OutlineCompat,
+    /// How many levels of object schemas to expand; deeper inline objects
+    /// are shown as `"object"` (`--outline-depth`). `None` expands all.
+    pub max_depth: Option<usize>,
+    /// Shows `#/components/schemas/User` as `User` (`--outline-short-refs`).
+    pub short_refs: bool,
+    /// Keeps each operation's `summary` and `description`
+    /// (`--outline-descriptions`).
+    pub descriptions: bool,
+    /// Fails on parts the outline cannot represent: non-query parameters
+    /// and responses without a content schema. When false they are skipped
+    /// or shown as `null` (`--outline-lenient`).
+    pub strict: bool,
+}
+
+impl Default for OutlineOptions {
+    fn default() -> Self {
+        Self {
+            compat: OutlineCompat::V2,
+            max_depth: None,
+            short_refs: false,
+            descriptions: false,
+            strict: true,
+        }
+    }
+}
+
+impl OutlineOptions {
+    pub fn compat(mut self, compat: OutlineCompat) -> Self {
+        self.compat = compat;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn short_refs(mut self, short_refs: bool) -> Self {
+        self.short_refs = short_refs;
+        self
+    }
+
+    pub fn descriptions(mut self, descriptions: bool) -> Self {
+        self.descriptions = descriptions;
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    fn reference(&self, reference: &str) -> String {
+        if self.short_refs
+            && let Some((_, name)) = reference.rsplit_once('/')
+        {
+            return name.to_string();
+        }
+        reference.to_string()
+    }
+
+    fn expands(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max| depth <= max)
+    }
+}
+
+/// [`outline_openapi`] with [`OutlineOptions::default`].
+pub fn outline_openapi_default(value: &Value) -> ResultValue {
+    outline_openapi(value, &OutlineOptions::default())
+}
+
+pub fn outline_openapi(value: &Value, options: &OutlineOptions) -> ResultValue {
     let object = value
         .as_object()
-        .ok_or_else(|| AppError::Outline("OpenAPI document must be a JSON object".to_string()))?;
+        .ok_or_else(|| AppError::outline("OpenAPI document must be a JSON object".to_string()))?;
 
     let paths = object
         .get("paths")
         .and_then(|v| v.as_object())
-        .ok_or_else(|| AppError::Outline("OpenAPI document missing paths".to_string()))?;
+        .ok_or_else(|| AppError::outline("OpenAPI document missing paths".to_string()))?;
     let schemas = object
         .get("components")
         .and_then(|v| v.as_object())
         .and_then(|components| components.get("schemas"))
         .and_then(|v| v.as_object());
 
-    let outlined_paths = outline_paths(paths)?;
-    let outlined_schemas = outline_schemas(schemas)?;
+    let outlined_paths = outline_paths(paths, options)?;
+    let outlined_schemas = outline_schemas(schemas, options)?;
 
     Ok(json!({
         "paths": outlined_paths,
@@ -29,203 +118,252 @@ pub fn outline_openapi(value: &Value) -> ResultValue {
     }))
 }
 
-fn outline_paths(paths: &JsonMap) -> ResultValue {
+fn outline_paths(paths: &JsonMap, options: &OutlineOptions) -> ResultValue {
     let mut outlined = JsonMap::new();
     for (path, item) in paths {
-        let item_obj = item
-            .as_object()
-            .ok_or_else(|| AppError::Outline(format!("path item must be an object: {path}")))?;
+        if !item.is_object() {
+            return Err(AppError::outline(format!(
+                "path item must be an object: {path}"
+            )));
+        }
+        let item = PathItem::deserialize(item)
+            .map_err(|err| AppError::outline(format!("{path}: {err}")).with_source(err))?;
 
         let mut methods = JsonMap::new();
-        for (method, op) in item_obj {
-            if !is_http_method(method) {
-                continue;
+        for (method, op) in item.operations() {
+            let query = outline_query_params(op, options)?;
+            let request = outline_request_body(op, options)?;
+            let responses = outline_responses(op, options)?;
+            let mut outlined_op = JsonMap::new();
+            if options.descriptions {
+                for key in ["summary", "description"] {
+                    if let Some(text) = op.extra.get(key).filter(|text| text.is_string()) {
+                        outlined_op.insert(key.to_string(), text.clone());
+                    }
+                }
             }
-            let op_obj = op.as_object().ok_or_else(|| {
-                AppError::Outline(format!("operation must be an object: {path} {method}"))
-            })?;
-            let query = outline_query_params(op_obj)?;
-            let request = outline_request_body(op_obj)?;
-            let responses = outline_responses(op_obj)?;
-            methods.insert(
-                method.to_string(),
-                json!({
-                    "query": query,
-                    "request": request,
-                    "responses": responses,
-                }),
-            );
+            outlined_op.insert("query".to_string(), query);
+            outlined_op.insert("request".to_string(), request);
+            outlined_op.insert("responses".to_string(), responses);
+            methods.insert(method.to_string(), Value::Object(outlined_op));
         }
         outlined.insert(path.to_string(), Value::Object(methods));
     }
     Ok(Value::Object(outlined))
 }
 
-fn is_http_method(method: &str) -> bool {
+/// Every `(path, method, operation)` in a `paths` object, in document order.
+/// Path items that are not objects are skipped.
+pub(crate) fn operations(paths: &JsonMap) -> impl Iterator<Item = (&str, &str, &Value)> {
+    paths.iter().flat_map(|(path, item)| {
+        item.as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(method, _)| is_http_method(method))
+            .map(move |(method, op)| (path.as_str(), method.as_str(), op))
+    })
+}
+
+pub(crate) fn is_http_method(method: &str) -> bool {
     matches!(
         method,
         "get" | "post" | "put" | "patch" | "delete" | "options" | "head" | "trace"
     )
 }
 
-fn outline_query_params(op: &JsonMap) -> ResultValue {
-    let Some(raw_params) = op.get("parameters") else {
-        return Ok(Value::Array(Vec::new()));
-    };
-    let params_array = raw_params
-        .as_array()
-        .ok_or_else(|| AppError::Outline("parameters must be an array".to_string()))?;
-
+fn outline_query_params(op: &Operation, options: &OutlineOptions) -> ResultValue {
     let mut params = Vec::new();
-    for param in params_array {
-        params.push(outline_query_param(param)?);
+    for param in op.parameters.iter().flatten() {
+        if let Some(param) = outline_query_param(param, options)? {
+            params.push(param);
+        }
     }
     Ok(Value::Array(params))
 }
 
-fn outline_query_param(param: &Value) -> ResultValue {
-    if let Some(reference) = param.get("$ref").and_then(|v| v.as_str()) {
-        return Ok(json!({"$ref": reference}));
-    }
-
-    let obj = param
-        .as_object()
-        .ok_or_else(|| AppError::Outline("parameter must be an object".to_string()))?;
-    let location = obj
-        .get("in")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| AppError::Outline("parameter missing location".to_string()))?;
+/// A query parameter's outline; other locations are an error, or `None`
+/// when not strict.
+fn outline_query_param(
+    param: &RefOr<Parameter>,
+    options: &OutlineOptions,
+) -> Result<Option<Value>, AppError> {
+    let param = match param {
+        RefOr::Ref(reference) => return Ok(Some(outline_ref(&reference.reference, options))),
+        RefOr::Inline(param) => param,
+    };
+    let location = param
+        .location
+        .as_deref()
+        .ok_or_else(|| AppError::outline("parameter missing location".to_string()))?;
     if location != "query" {
-        return Err(AppError::Outline("non-query parameter".to_string()));
-    }
-
-    let name = obj
-        .get("name")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| AppError::Outline("query parameter missing name".to_string()))?;
-    if name.is_empty() {
-        return Err(AppError::Outline(
-            "query parameter missing name".to_string(),
-        ));
+        if !options.strict {
+            return Ok(None);
+        }
+        return Err(AppError::outline("non-query parameter".to_string()));
     }
 
-    let required = obj
-        .get("required")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
-
-    let schema_value = obj
-        .get("schema")
-        .ok_or_else(|| AppError::Outline("query parameter missing schema".to_string()))?;
-    let schema = schema_ref_or_type(schema_value)?;
+    let name = match param.name.as_deref() {
+        Some(name) if !name.is_empty() => name,
+        _ => {
+            return Err(AppError::outline(
+                "query parameter missing name".to_string(),
+            ));
+        }
+    };
+    let schema = param
+        .schema
+        .as_ref()
+        .ok_or_else(|| AppError::outline("query parameter missing schema".to_string()))?;
 
-    Ok(json!({
+    Ok(Some(json!({
         "name": name,
-        "required": required,
-        "schema": schema,
-    }))
+        "required": param.required(),
+        "schema": outline_schema(schema, options)?,
+    })))
 }
 
-fn outline_request_body(op: &JsonMap) -> ResultValue {
-    let Some(request_body) = op.get("requestBody") else {
-        return Ok(Value::Null);
+fn outline_request_body(op: &Operation, options: &OutlineOptions) -> ResultValue {
+    let request_body = match &op.request_body {
+        None => return Ok(Value::Null),
+        Some(RefOr::Ref(reference)) => {
+            return Ok(Value::String(options.reference(&reference.reference)));
+        }
+        Some(RefOr::Inline(request_body)) => request_body,
     };
 
-    if let Some(reference) = request_body.get("$ref").and_then(|v| v.as_str()) {
-        return Ok(Value::String(reference.to_string()));
-    }
-
     let content = request_body
-        .get("content")
-        .and_then(|v| v.as_object())
-        .ok_or_else(|| AppError::Outline("requestBody content must be an object".to_string()))?;
+        .content
+        .as_ref()
+        .ok_or_else(|| AppError::outline("requestBody content must be an object".to_string()))?;
 
-    select_content_schema(content)
+    select_content_schema(content, options)
 }
 
-fn outline_responses(op: &JsonMap) -> ResultValue {
+fn outline_responses(op: &Operation, options: &OutlineOptions) -> ResultValue {
     let responses = op
-        .get("responses")
-        .and_then(|v| v.as_object())
-        .ok_or_else(|| AppError::Outline("responses must be an object".to_string()))?;
+        .responses
+        .as_ref()
+        .ok_or_else(|| AppError::outline("responses must be an object".to_string()))?;
 
     let mut mapped = JsonMap::new();
     for (code, response) in responses {
-        if let Some(reference) = response.get("$ref").and_then(|v| v.as_str()) {
-            mapped.insert(code.to_string(), Value::String(reference.to_string()));
-            continue;
-        }
+        let response = match response {
+            RefOr::Ref(reference) => {
+                mapped.insert(
+                    code.to_string(),
+                    Value::String(options.reference(&reference.reference)),
+                );
+                continue;
+            }
+            RefOr::Inline(response) => response,
+        };
 
-        let content = response
-            .get("content")
-            .and_then(|v| v.as_object())
-            .ok_or_else(|| AppError::Outline(format!("response {code} missing content schema")))?;
+        let content = match &response.content {
+            Some(content) => content,
+            None if !options.strict => {
+                mapped.insert(code.to_string(), Value::Null);
+                continue;
+            }
+            None => {
+                return Err(AppError::outline(format!(
+                    "response {code} missing content schema"
+                )));
+            }
+        };
 
-        let schema = select_content_schema(content)?;
+        let schema = select_content_schema(content, options)?;
         mapped.insert(code.to_string(), schema);
     }
 
     Ok(Value::Object(mapped))
 }
 
-fn select_content_schema(content: &JsonMap) -> ResultValue {
+fn select_content_schema(
+    content: &BTreeMap<String, MediaType>,
+    options: &OutlineOptions,
+) -> ResultValue {
     if let Some(schema) = content
         .get("application/json")
-        .and_then(|v| v.get("schema"))
+        .and_then(|media| media.schema.as_ref())
     {
-        return schema_ref_or_type(schema);
+        return outline_schema(schema, options);
     }
 
-    for (_content_type, entry) in content {
-        if let Some(schema) = entry.get("schema") {
-            return schema_ref_or_type(schema);
+    for media in content.values() {
+        if let Some(schema) = &media.schema {
+            return outline_schema(schema, options);
         }
     }
 
-    Err(AppError::Outline(
+    if !options.strict {
+        return Ok(Value::Null);
+    }
+    Err(AppError::outline(
         "content missing schema for any content type".to_string(),
     ))
 }
 
-fn outline_schemas(schemas: Option<&JsonMap>) -> ResultValue {
+/// The outline of a parameter or content schema, one object level deep.
+fn outline_schema(schema: &SchemaRefOrInline, options: &OutlineOptions) -> ResultValue {
+    match schema {
+        RefOr::Ref(reference) => Ok(Value::String(options.reference(&reference.reference))),
+        RefOr::Inline(schema) => schema_ref_or_type(schema, options, 1),
+    }
+}
+
+fn outline_schemas(schemas: Option<&JsonMap>, options: &OutlineOptions) -> ResultValue {
     let mut outlined = JsonMap::new();
     if let Some(schemas) = schemas {
         for (name, schema) in schemas {
-            outlined.insert(name.to_string(), simplify_schema_definition(schema)?);
+            let simplified = match schema.get("$ref").and_then(|v| v.as_str()) {
+                Some(reference) => outline_ref(reference, options),
+                None => simplify_schema_definition(schema, options, 1)?,
+            };
+            outlined.insert(name.to_string(), simplified);
         }
     }
     Ok(Value::Object(outlined))
 }
 
-fn simplify_schema_definition(schema: &Value) -> ResultValue {
-    if let Some(reference) = schema.get("$ref").and_then(|v| v.as_str()) {
-        return Ok(json!({"$ref": reference}));
+/// Canonical outline form of a `$ref` is the bare reference string; `v1`
+/// compatibility keeps the legacy `{"$ref": ...}` object for parameters and
+/// top-level schemas.
+fn outline_ref(reference: &str, options: &OutlineOptions) -> Value {
+    let reference = options.reference(reference);
+    match options.compat {
+        OutlineCompat::V1 => json!({"$ref": reference}),
+        OutlineCompat::V2 => Value::String(reference),
     }
+}
 
-    if let Some(of) = schema.get("oneOf").and_then(|v| v.as_array()) {
-        return Ok(json!({"oneOf": collect_schema_vec(of)?}));
-    }
-    if let Some(of) = schema.get("anyOf").and_then(|v| v.as_array()) {
-        return Ok(json!({"anyOf": collect_schema_vec(of)?}));
-    }
-    if let Some(of) = schema.get("allOf").and_then(|v| v.as_array()) {
-        return Ok(json!({"allOf": collect_schema_vec(of)?}));
+/// Simplifies a schema definition `depth` object levels deep; objects
+/// beyond `options.max_depth` keep their properties unexpanded.
+fn simplify_schema_definition(
+    schema: &Value,
+    options: &OutlineOptions,
+    depth: usize,
+) -> ResultValue {
+    let (schema_type, nullable) = schema_type(schema)?;
+    if let Some(composed) = simplify_composition(schema, options, depth)? {
+        return Ok(with_nullable(composed, nullable));
     }
 
-    let schema_type = schema.get("type").and_then(|v| v.as_str());
-    match schema_type {
+    let simplified = match schema_type.as_deref() {
         Some("object") | None => {
             let properties = match schema.get("properties") {
                 None => None,
                 Some(Value::Object(props)) => {
                     let mut mapped = JsonMap::new();
                     for (name, value) in props {
-                        mapped.insert(name.to_string(), schema_ref_or_type(value)?);
+                        mapped.insert(
+                            name.to_string(),
+                            schema_ref_or_type(value, options, depth + 1)?,
+                        );
                     }
                     Some(mapped)
                 }
                 Some(_) => {
-                    return Err(AppError::Outline(
+                    return Err(AppError::outline(
                         "schema properties must be an object".to_string(),
                     ));
                 }
@@ -237,7 +375,7 @@ fn simplify_schema_definition(schema: &Value) -> ResultValue {
                     let mut names = Vec::new();
                     for item in items {
                         let Some(name) = item.as_str() else {
-                            return Err(AppError::Outline(
+                            return Err(AppError::outline(
                                 "required entries must be strings".to_string(),
                             ));
                         };
@@ -245,7 +383,7 @@ fn simplify_schema_definition(schema: &Value) -> ResultValue {
                     }
                     Some(names)
                 }
-                Some(_) => return Err(AppError::Outline("required must be an array".to_string())),
+                Some(_) => return Err(AppError::outline("required must be an array".to_string())),
             };
 
             let mut obj = JsonMap::new();
@@ -259,56 +397,128 @@ fn simplify_schema_definition(schema: &Value) -> ResultValue {
             if let Some(properties) = properties {
                 obj.insert("properties".to_string(), Value::Object(properties));
             }
-            Ok(Value::Object(obj))
+            Value::Object(obj)
         }
-        Some("array") => {
-            let items = schema
-                .get("items")
-                .ok_or_else(|| AppError::Outline("array schema missing items".to_string()))?;
-            Ok(json!({"type": "array", "items": schema_ref_or_type(items)?}))
+        Some("array") => simplify_array(schema, options, depth)?,
+        Some(other) => Value::String(other.to_string()),
+    };
+    Ok(with_nullable(simplified, nullable))
+}
+
+fn simplify_composition(
+    schema: &Value,
+    options: &OutlineOptions,
+    depth: usize,
+) -> Result<Option<Value>, AppError> {
+    for keyword in ["oneOf", "anyOf", "allOf"] {
+        if let Some(of) = schema.get(keyword).and_then(|v| v.as_array()) {
+            let mut obj = JsonMap::new();
+            obj.insert(
+                keyword.to_string(),
+                Value::Array(collect_schema_vec(of, options, depth)?),
+            );
+            return Ok(Some(Value::Object(obj)));
         }
-        Some(other) => Ok(Value::String(other.to_string())),
     }
+    Ok(None)
 }
 
-fn collect_schema_vec(items: &[Value]) -> Result<Vec<Value>, AppError> {
+fn simplify_array(schema: &Value, options: &OutlineOptions, depth: usize) -> ResultValue {
+    let items = schema
+        .get("items")
+        .ok_or_else(|| AppError::outline("array schema missing items".to_string()))?;
+    Ok(json!({"type": "array", "items": schema_ref_or_type(items, options, depth)?}))
+}
+
+/// Reads a schema's `type` along with whether it admits null, accepting both the
+/// 3.0 `nullable: true` keyword and 3.1 type arrays such as `["string", "null"]`.
+/// A 3.1 array of several types reads as their union, `"string | integer"`.
+/// Any other `type`, like `5` or `{}`, reads as no type at all.
+fn schema_type(schema: &Value) -> Result<(Option<Cow<'_, str>>, bool), AppError> {
+    let mut nullable = schema
+        .get("nullable")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let schema_type = match schema.get("type") {
+        None => None,
+        Some(Value::String(name)) => Some(Cow::Borrowed(name.as_str())),
+        Some(Value::Array(names)) => {
+            let mut found = Vec::new();
+            for name in names {
+                let name = name
+                    .as_str()
+                    .ok_or_else(|| AppError::outline("type entries must be strings".to_string()))?;
+                if name == "null" {
+                    nullable = true;
+                } else {
+                    found.push(name);
+                }
+            }
+            match found.as_slice() {
+                [] => nullable.then_some(Cow::Borrowed("null")),
+                [name] => Some(Cow::Borrowed(*name)),
+                _ => Some(Cow::Owned(found.join(" | "))),
+            }
+        }
+        Some(_) => None,
+    };
+    if schema_type.as_deref() == Some("null") {
+        nullable = false;
+    }
+    Ok((schema_type, nullable))
+}
+
+/// Marks a simplified schema as nullable: compact string forms become
+/// `"<type> | null"` and object forms gain `"nullable": true`.
+fn with_nullable(value: Value, nullable: bool) -> Value {
+    if !nullable {
+        return value;
+    }
+    match value {
+        Value::String(name) => Value::String(format!("{name} | null")),
+        Value::Object(mut obj) => {
+            obj.insert("nullable".to_string(), Value::Bool(true));
+            Value::Object(obj)
+        }
+        other => other,
+    }
+}
+
+fn collect_schema_vec(
+    items: &[Value],
+    options: &OutlineOptions,
+    depth: usize,
+) -> Result<Vec<Value>, AppError> {
     let mut out = Vec::with_capacity(items.len());
     for item in items {
-        out.push(schema_ref_or_type(item)?);
+        out.push(schema_ref_or_type(item, options, depth)?);
     }
     Ok(out)
 }
 
-fn schema_ref_or_type(schema: &Value) -> ResultValue {
+fn schema_ref_or_type(schema: &Value, options: &OutlineOptions, depth: usize) -> ResultValue {
     if let Some(reference) = schema.get("$ref").and_then(|v| v.as_str()) {
-        return Ok(Value::String(reference.to_string()));
-    }
-
-    if let Some(of) = schema.get("oneOf").and_then(|v| v.as_array()) {
-        return Ok(json!({"oneOf": collect_schema_vec(of)?}));
+        return Ok(Value::String(options.reference(reference)));
     }
-    if let Some(of) = schema.get("anyOf").and_then(|v| v.as_array()) {
-        return Ok(json!({"anyOf": collect_schema_vec(of)?}));
+    if !schema.is_object() {
+        return Err(AppError::outline("schema missing type".to_string()));
     }
-    if let Some(of) = schema.get("allOf").and_then(|v| v.as_array()) {
-        return Ok(json!({"allOf": collect_schema_vec(of)?}));
+
+    let (schema_type, nullable) = schema_type(schema)?;
+    if let Some(composed) = simplify_composition(schema, options, depth)? {
+        return Ok(with_nullable(composed, nullable));
     }
 
-    if let Some(schema_type) = schema.get("type").and_then(|v| v.as_str()) {
-        match schema_type {
-            "object" => simplify_schema_definition(schema),
-            "array" => {
-                let items = schema
-                    .get("items")
-                    .ok_or_else(|| AppError::Outline("array schema missing items".to_string()))?;
-                Ok(json!({"type": "array", "items": schema_ref_or_type(items)?}))
-            }
-            other => Ok(Value::String(other.to_string())),
+    match schema_type.as_deref() {
+        Some("object") | None if !options.expands(depth) => {
+            Ok(with_nullable(Value::String("object".to_string()), nullable))
         }
-    } else if schema.is_object() {
-        simplify_schema_definition(schema)
-    } else {
-        Err(AppError::Outline("schema missing type".to_string()))
+        Some("object") | None => simplify_schema_definition(schema, options, depth),
+        Some("array") => Ok(with_nullable(
+            simplify_array(schema, options, depth)?,
+            nullable,
+        )),
+        Some(other) => Ok(with_nullable(Value::String(other.to_string()), nullable)),
     }
 }
 
@@ -349,7 +559,7 @@ mod tests {
             }
         });
 
-        let output = outline_openapi(&input).unwrap();
+        let output = outline_openapi_default(&input).unwrap();
         let responses = output["paths"]["/health"]["get"]["responses"]["200"]
             .as_str()
             .unwrap();
@@ -367,8 +577,8 @@ mod tests {
             "paths": {"/health": []},
             "components": {"schemas": {}}
         });
-        let err = outline_openapi(&input).unwrap_err();
-        assert!(matches!(err, AppError::Outline(_)));
+        let err = outline_openapi_default(&input).unwrap_err();
+        assert!(matches!(err, AppError::Outline { .. }));
     }
 
     #[test]
@@ -386,8 +596,8 @@ mod tests {
             },
             "components": {"schemas": {}}
         });
-        let err = outline_openapi(&input).unwrap_err();
-        assert!(matches!(err, AppError::Outline(_)));
+        let err = outline_openapi_default(&input).unwrap_err();
+        assert!(matches!(err, AppError::Outline { .. }));
     }
 
     #[test]
@@ -405,8 +615,8 @@ mod tests {
             },
             "components": {"schemas": {}}
         });
-        let err = outline_openapi(&input).unwrap_err();
-        assert!(matches!(err, AppError::Outline(_)));
+        let err = outline_openapi_default(&input).unwrap_err();
+        assert!(matches!(err, AppError::Outline { .. }));
     }
 
     #[test]
@@ -424,8 +634,8 @@ mod tests {
             },
             "components": {"schemas": {}}
         });
-        let err = outline_openapi(&input).unwrap_err();
-        assert!(matches!(err, AppError::Outline(_)));
+        let err = outline_openapi_default(&input).unwrap_err();
+        assert!(matches!(err, AppError::Outline { .. }));
     }
 
     #[test]
@@ -444,8 +654,8 @@ mod tests {
                 }
             }
         });
-        let err = outline_openapi(&input).unwrap_err();
-        assert!(matches!(err, AppError::Outline(_)));
+        let err = outline_openapi_default(&input).unwrap_err();
+        assert!(matches!(err, AppError::Outline { .. }));
     }
 
     #[test]
@@ -471,8 +681,8 @@ mod tests {
             },
             "components": {"schemas": {}}
         });
-        let err = outline_openapi(&input).unwrap_err();
-        assert!(matches!(err, AppError::Outline(_)));
+        let err = outline_openapi_default(&input).unwrap_err();
+        assert!(matches!(err, AppError::Outline { .. }));
     }
 
     #[test]
@@ -498,8 +708,8 @@ mod tests {
             },
             "components": {"schemas": {}}
         });
-        let err = outline_openapi(&input).unwrap_err();
-        assert!(matches!(err, AppError::Outline(_)));
+        let err = outline_openapi_default(&input).unwrap_err();
+        assert!(matches!(err, AppError::Outline { .. }));
     }
 
     #[test]
@@ -522,8 +732,8 @@ mod tests {
                 }
             }
         });
-        let err = outline_openapi(&input).unwrap_err();
-        assert!(matches!(err, AppError::Outline(_)));
+        let err = outline_openapi_default(&input).unwrap_err();
+        assert!(matches!(err, AppError::Outline { .. }));
     }
 
     #[test]
@@ -540,8 +750,8 @@ mod tests {
             },
             "paths": {"/health": {}},
         });
-        let err = outline_openapi(&input).unwrap_err();
-        assert!(matches!(err, AppError::Outline(_)));
+        let err = outline_openapi_default(&input).unwrap_err();
+        assert!(matches!(err, AppError::Outline { .. }));
     }
 
     #[test]
@@ -557,7 +767,340 @@ mod tests {
             },
             "paths": {"/health": {}},
         });
-        let err = outline_openapi(&input).unwrap_err();
-        assert!(matches!(err, AppError::Outline(_)));
+        let err = outline_openapi_default(&input).unwrap_err();
+        assert!(matches!(err, AppError::Outline { .. }));
+    }
+
+    #[test]
+    fn outline_marks_nullable_properties_and_items() {
+        let input = json!({
+            "paths": {},
+            "components": {
+                "schemas": {
+                    "User": {
+                        "type": "object",
+                        "properties": {
+                            "nickname": {"type": "string", "nullable": true},
+                            "tags": {
+                                "type": "array",
+                                "items": {"type": "integer", "nullable": true}
+                            },
+                            "aliases": {
+                                "type": "array",
+                                "nullable": true,
+                                "items": {"type": "string"}
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        let output = outline_openapi_default(&input).unwrap();
+        let props = &output["schemas"]["User"]["properties"];
+        assert_eq!(props["nickname"], json!("string | null"));
+        assert_eq!(props["tags"]["items"], json!("integer | null"));
+        assert_eq!(props["aliases"]["nullable"], json!(true));
+        assert_eq!(props["aliases"]["items"], json!("string"));
+    }
+
+    #[test]
+    fn outline_marks_nullable_top_level_schemas() {
+        let input = json!({
+            "paths": {},
+            "components": {
+                "schemas": {
+                    "Name": {"type": "string", "nullable": true},
+                    "Maybe": {"type": "object", "nullable": true, "properties": {}},
+                    "Either": {
+                        "nullable": true,
+                        "allOf": [{"$ref": "#/components/schemas/Name"}]
+                    }
+                }
+            }
+        });
+        let output = outline_openapi_default(&input).unwrap();
+        assert_eq!(output["schemas"]["Name"], json!("string | null"));
+        assert_eq!(output["schemas"]["Maybe"]["nullable"], json!(true));
+        assert_eq!(output["schemas"]["Either"]["nullable"], json!(true));
+    }
+
+    #[test]
+    fn outline_treats_type_arrays_like_nullable() {
+        let v30 = json!({
+            "paths": {},
+            "components": {"schemas": {"Foo": {
+                "type": "object",
+                "properties": {"a": {"type": "string", "nullable": true}}
+            }}}
+        });
+        let v31 = json!({
+            "paths": {},
+            "components": {"schemas": {"Foo": {
+                "type": "object",
+                "properties": {"a": {"type": ["string", "null"]}}
+            }}}
+        });
+        assert_eq!(
+            outline_openapi_default(&v30).unwrap(),
+            outline_openapi_default(&v31).unwrap()
+        );
+    }
+
+    #[test]
+    fn outline_renders_multi_type_arrays_as_a_union() {
+        let input = json!({
+            "paths": {},
+            "components": {"schemas": {"Foo": {
+                "type": "object",
+                "properties": {
+                    "id": {"type": ["string", "integer"]},
+                    "maybe": {"type": ["integer", "null", "string"]}
+                }
+            }}}
+        });
+        let output = outline_openapi_default(&input).unwrap();
+        let properties = &output["schemas"]["Foo"]["properties"];
+        assert_eq!(properties["id"], json!("string | integer"));
+        assert_eq!(properties["maybe"], json!("integer | string | null"));
+    }
+
+    #[test]
+    fn outline_renders_mistyped_types_as_untyped_schemas() {
+        let input = json!({
+            "paths": {},
+            "components": {"schemas": {
+                "Number": {"type": 5, "properties": {"id": {"type": "string"}}},
+                "Object": {"type": {}},
+                "Untyped": {}
+            }}
+        });
+        let output = outline_openapi_default(&input).unwrap();
+        let schemas = &output["schemas"];
+        assert_eq!(
+            schemas["Number"],
+            json!({"type": "object", "properties": {"id": "string"}})
+        );
+        assert_eq!(schemas["Object"], schemas["Untyped"]);
+    }
+
+    #[test]
+    fn outline_leaves_non_nullable_schemas_unchanged() {
+        let input = json!({
+            "paths": {},
+            "components": {"schemas": {"Foo": {
+                "type": "object",
+                "properties": {"a": {"type": "string", "nullable": false}}
+            }}}
+        });
+        let output = outline_openapi_default(&input).unwrap();
+        assert_eq!(output["schemas"]["Foo"]["properties"]["a"], json!("string"));
+    }
+
+    fn ref_fixture() -> Value {
+        json!({
+            "paths": {
+                "/users": {
+                    "post": {
+                        "parameters": [{"$ref": "#/components/parameters/Limit"}],
+                        "requestBody": {"$ref": "#/components/requestBodies/NewUser"},
+                        "responses": {
+                            "200": {"$ref": "#/components/responses/User"},
+                            "201": {
+                                "content": {
+                                    "application/json": {
+                                        "schema": {"$ref": "#/components/schemas/User"}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Alias": {"$ref": "#/components/schemas/User"},
+                    "User": {"type": "object"}
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn outline_renders_refs_as_bare_strings_everywhere() {
+        let output = outline_openapi_default(&ref_fixture()).unwrap();
+        let op = &output["paths"]["/users"]["post"];
+        assert_eq!(op["query"][0], json!("#/components/parameters/Limit"));
+        assert_eq!(op["request"], json!("#/components/requestBodies/NewUser"));
+        assert_eq!(op["responses"]["200"], json!("#/components/responses/User"));
+        assert_eq!(op["responses"]["201"], json!("#/components/schemas/User"));
+        assert_eq!(
+            output["schemas"]["Alias"],
+            json!("#/components/schemas/User")
+        );
+    }
+
+    #[test]
+    fn outline_compat_v1_keeps_legacy_ref_objects() {
+        let output = outline_openapi(
+            &ref_fixture(),
+            &OutlineOptions::default().compat(OutlineCompat::V1),
+        )
+        .unwrap();
+        let op = &output["paths"]["/users"]["post"];
+        assert_eq!(
+            op["query"][0],
+            json!({"$ref": "#/components/parameters/Limit"})
+        );
+        assert_eq!(op["request"], json!("#/components/requestBodies/NewUser"));
+        assert_eq!(op["responses"]["200"], json!("#/components/responses/User"));
+        assert_eq!(
+            output["schemas"]["Alias"],
+            json!({"$ref": "#/components/schemas/User"})
+        );
+    }
+
+    fn options_fixture() -> Value {
+        json!({
+            "paths": {
+                "/users/{id}": {
+                    "get": {
+                        "summary": "Fetch a user",
+                        "description": "Looks a user up by id.",
+                        "parameters": [
+                            {"in": "path", "name": "id", "required": true, "schema": {"type": "string"}},
+                            {"in": "query", "name": "expand", "schema": {"type": "boolean"}}
+                        ],
+                        "responses": {
+                            "200": {"content": {"application/json": {"schema": {"$ref": "#/components/schemas/User"}}}},
+                            "204": {"description": "No content"}
+                        }
+                    }
+                }
+            },
+            "components": {"schemas": {"User": {
+                "type": "object",
+                "properties": {
+                    "address": {
+                        "type": "object",
+                        "properties": {"city": {"type": "string"}}
+                    },
+                    "manager": {"$ref": "#/components/schemas/User"}
+                }
+            }}}
+        })
+    }
+
+    fn lenient() -> OutlineOptions {
+        OutlineOptions::default().strict(false)
+    }
+
+    #[test]
+    fn default_options_match_the_command_line() {
+        let options = OutlineOptions::default();
+        assert_eq!(options.compat, OutlineCompat::V2);
+        assert_eq!(options.max_depth, None);
+        assert!(!options.short_refs);
+        assert!(!options.descriptions);
+        assert!(options.strict);
+        assert_eq!(
+            outline_openapi(&ref_fixture(), &options).unwrap(),
+            outline_openapi_default(&ref_fixture()).unwrap()
+        );
+    }
+
+    #[test]
+    fn default_is_strict_about_unsupported_parts() {
+        let err = outline_openapi_default(&options_fixture()).unwrap_err();
+        assert_eq!(err.to_string(), "non-query parameter");
+    }
+
+    #[test]
+    fn lenient_skips_non_query_parameters_and_missing_content() {
+        let output = outline_openapi(&options_fixture(), &lenient()).unwrap();
+        let op = &output["paths"]["/users/{id}"]["get"];
+        assert_eq!(
+            op["query"],
+            json!([{"name": "expand", "required": false, "schema": "boolean"}])
+        );
+        assert_eq!(op["responses"]["204"], Value::Null);
+    }
+
+    #[test]
+    fn default_expands_every_level_and_omits_descriptions() {
+        let output = outline_openapi(&options_fixture(), &lenient()).unwrap();
+        let user = &output["schemas"]["User"]["properties"];
+        assert_eq!(user["address"]["properties"]["city"], json!("string"));
+        assert_eq!(user["manager"], json!("#/components/schemas/User"));
+        let op = output["paths"]["/users/{id}"]["get"].as_object().unwrap();
+        assert_eq!(
+            op.keys().collect::<Vec<_>>(),
+            ["query", "request", "responses"]
+        );
+    }
+
+    #[test]
+    fn max_depth_stops_expanding_nested_objects() {
+        let output = outline_openapi(&options_fixture(), &lenient().max_depth(Some(1))).unwrap();
+        let user = &output["schemas"]["User"];
+        assert_eq!(user["properties"]["address"], json!("object"));
+        assert_eq!(
+            user["properties"]["manager"],
+            json!("#/components/schemas/User")
+        );
+
+        let output = outline_openapi(&options_fixture(), &lenient().max_depth(Some(2))).unwrap();
+        assert_eq!(
+            output["schemas"]["User"]["properties"]["address"]["properties"]["city"],
+            json!("string")
+        );
+    }
+
+    #[test]
+    fn short_refs_use_the_component_name() {
+        let output =
+            outline_openapi(&ref_fixture(), &OutlineOptions::default().short_refs(true)).unwrap();
+        let op = &output["paths"]["/users"]["post"];
+        assert_eq!(op["query"][0], json!("Limit"));
+        assert_eq!(op["request"], json!("NewUser"));
+        assert_eq!(op["responses"]["201"], json!("User"));
+        assert_eq!(output["schemas"]["Alias"], json!("User"));
+
+        let v1 = OutlineOptions::default()
+            .short_refs(true)
+            .compat(OutlineCompat::V1);
+        let output = outline_openapi(&ref_fixture(), &v1).unwrap();
+        assert_eq!(output["schemas"]["Alias"], json!({"$ref": "User"}));
+    }
+
+    #[test]
+    fn descriptions_keep_operation_summary_and_description() {
+        let output = outline_openapi(&options_fixture(), &lenient().descriptions(true)).unwrap();
+        let op = &output["paths"]["/users/{id}"]["get"];
+        assert_eq!(op["summary"], json!("Fetch a user"));
+        assert_eq!(op["description"], json!("Looks a user up by id."));
+    }
+
+    #[test]
+    fn mistyped_fields_the_outline_does_not_read_are_ignored() {
+        let input = json!({
+            "paths": {"/users": {
+                "parameters": "not a list",
+                "get": {
+                    "operationId": 7,
+                    "summary": ["list"],
+                    "responses": {"200": {
+                        "description": "ok",
+                        "content": {"application/json": {"schema": {"type": "string"}}}
+                    }}
+                }
+            }}
+        });
+        let output = outline_openapi_default(&input).unwrap();
+        assert_eq!(
+            output["paths"]["/users"]["get"]["responses"],
+            json!({"200": "string"})
+        );
+        let output = outline_openapi(&input, &lenient().descriptions(true)).unwrap();
+        assert!(output["paths"]["/users"]["get"].get("summary").is_none());
     }
 }
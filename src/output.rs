@@ -1,53 +1,372 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fs::{self, OpenOptions};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::Write;
 use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use serde::Serialize;
+use serde::de::IgnoredAny;
 use serde_json::Value;
 
-use crate::cli::OutputProfile;
-use crate::config::{Config, ReduceKey};
+use crate::breaking::{BreakingChange, breaking_changes};
+use crate::cli::{DocFormat, OutputProfile};
+use crate::color::Palette;
+use crate::compact::Node;
+use crate::config::Config;
+use crate::diff::{diff_documents, summarize_changes};
+use crate::duplicates::{SCAN_LIMIT_BYTES, duplicate_keys};
 use crate::errors::AppError;
-use crate::fetch::{fetch_openapi, parse_json};
+use crate::fetch::{fetch_openapi_response, mask_header, parse_json, read_openapi_file};
+use crate::format::{format_from_path, parse_document};
+use crate::normalize::{canonical_text, for_comparison};
 use crate::outline::outline_openapi;
+use crate::refs::check_refs;
+use crate::transform::{Pipeline, TransformCtx};
+use crate::validate::{
+    Severity, check_findings, example_findings, path_collisions, validate_spec_with,
+};
+use crate::version::{
+    SpecVersion, declared_version, declared_version_in, version_notes, version_notes_in,
+};
 
 #[derive(Debug)]
 pub struct OutputPayloads {
     pub primary: String,
     pub outline: Option<String>,
+    /// `info.version` of the fetched document, read before any reduction.
+    pub version: Option<String>,
+    /// HTTP status and size of the fetched document; `--input` has no
+    /// status.
+    pub status: Option<u16>,
+    pub bytes: usize,
+    /// Freshness lifetime the server advertised via Cache-Control.
+    pub max_age: Option<Duration>,
+    /// The OpenAPI version the document declares, and what this tool
+    /// handles differently because of it.
+    pub spec_version: SpecVersion,
+    pub version_notes: Vec<String>,
+    /// The payloads as watch compares them, set once per poll; `None` from
+    /// [`build_outputs`].
+    pub compared: Option<ComparedPayloads>,
+}
+
+impl OutputPayloads {
+    /// Parses and canonicalizes the payloads once, so change tracking, the
+    /// diff lines, and `--baseline` drift all read the same document.
+    pub(crate) fn with_comparison(mut self, strict_bytes: bool) -> Self {
+        self.compared = Some(ComparedPayloads::new(
+            &self.primary,
+            self.outline.as_deref(),
+            strict_bytes,
+        ));
+        self
+    }
+
+    /// [`OutputPayloads::compared`], or the same computed now for payloads
+    /// collected without it.
+    pub(crate) fn comparison(&self, strict_bytes: bool) -> Cow<'_, ComparedPayloads> {
+        match &self.compared {
+            Some(compared) => Cow::Borrowed(compared),
+            None => Cow::Owned(ComparedPayloads::new(
+                &self.primary,
+                self.outline.as_deref(),
+                strict_bytes,
+            )),
+        }
+    }
+}
+
+/// A snapshot's payloads in the form watch compares them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparedPayloads {
+    /// The primary payload through [`for_comparison`]; `None` when it is not
+    /// JSON.
+    pub doc: Option<Value>,
+    /// Hashes of each payload's canonical text, so reformatting alone is not
+    /// a change, or of its bytes with `--strict-bytes` or when it is not JSON.
+    pub primary_hash: u64,
+    pub outline_hash: Option<u64>,
+}
+
+impl ComparedPayloads {
+    pub(crate) fn new(primary: &str, outline: Option<&str>, strict_bytes: bool) -> Self {
+        let doc = serde_json::from_str::<Value>(primary)
+            .ok()
+            .map(|doc| for_comparison(doc, strict_bytes));
+        let primary_hash = match &doc {
+            Some(doc) if !strict_bytes => hash_text(&doc.to_string()),
+            _ => hash_text(primary),
+        };
+        Self {
+            doc,
+            primary_hash,
+            outline_hash: outline.map(|outline| hash_payload(outline, strict_bytes)),
+        }
+    }
+}
+
+/// Hashes a payload's [`canonical_text`], or its bytes with `--strict-bytes`
+/// or when it is not JSON.
+fn hash_payload(payload: &str, strict_bytes: bool) -> u64 {
+    let canonical = (!strict_bytes)
+        .then(|| serde_json::from_str::<Value>(payload).ok())
+        .flatten()
+        .map(|doc| canonical_text(&doc));
+    hash_text(canonical.as_deref().unwrap_or(payload))
+}
+
+fn hash_text(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
 }
 
 pub fn build_output(config: &Config) -> Result<String, AppError> {
     Ok(build_outputs(config)?.primary)
 }
 
+/// Fetches or reads the document and builds its payloads, logging its
+/// [`OutputPayloads::version_notes`].
 pub fn build_outputs(config: &Config) -> Result<OutputPayloads, AppError> {
-    let body = fetch_openapi(config)?;
-    let json = parse_json(&body)?;
-    match config.profile {
-        OutputProfile::Full => {
-            let mut full_value = json.clone();
-            if !config.reduce.is_empty() {
-                full_value = reduce_openapi(full_value, &config.reduce)?;
-            }
-            let primary = serialize_json(&full_value, config.minify)?;
-            let outline = if config.outline_out.is_some() {
-                let outline_value = outline_openapi(&json)?;
-                Some(serialize_json(&outline_value, config.minify)?)
-            } else {
-                None
-            };
-            Ok(OutputPayloads { primary, outline })
+    let outputs = collect_outputs(config)?;
+    log_version_notes(config, &outputs.version_notes);
+    Ok(outputs)
+}
+
+/// [`build_outputs`] without logging the version notes, for loops that log
+/// them only when they change.
+pub(crate) fn collect_outputs(config: &Config) -> Result<OutputPayloads, AppError> {
+    match &config.input {
+        Some(path) => transform_outputs(config, None, read_openapi_file(path)?, None),
+        None => {
+            let response = fetch_openapi_response(config)?;
+            transform_outputs(
+                config,
+                Some(response.status),
+                response.body,
+                response.max_age,
+            )
+        }
+    }
+}
+
+/// The CPU-only half of [`build_outputs`]: parses a fetched or read body,
+/// validates it, and runs the [`crate::transform`] steps. The body is freed
+/// as soon as it is parsed, so it never coexists with the serialized
+/// payloads.
+pub(crate) fn transform_outputs(
+    config: &Config,
+    status: Option<u16>,
+    body: Vec<u8>,
+    max_age: Option<Duration>,
+) -> Result<OutputPayloads, AppError> {
+    let bytes = body.len();
+    if let Some(severity) = config.duplicate_keys {
+        check_duplicate_keys(config, &body, severity)?;
+    }
+    if passes_through(config)
+        && let Ok(doc) = Node::parse(&body)
+    {
+        let detected = SpecVersion::detect_in(&doc);
+        require_version(config, detected, declared_version_in(&doc))?;
+        return Ok(OutputPayloads {
+            primary: serialize_json(&doc, config.minify)?,
+            outline: None,
+            version: doc
+                .get("info")
+                .and_then(|info| info.get("version"))
+                .and_then(Node::as_str)
+                .map(str::to_string),
+            status,
+            bytes,
+            max_age,
+            spec_version: detected,
+            version_notes: version_notes_in(&doc, detected),
+            compared: None,
+        });
+    }
+    let json = match &config.input {
+        Some(path) => parse_document(&body, format_from_path(path))?,
+        None => parse_json(&body)?,
+    };
+    drop(body);
+    let detected = SpecVersion::detect(&json);
+    require_version(config, detected, declared_version(&json))?;
+    let notes = version_notes(&json, detected);
+    if config.validate {
+        let findings = validate_spec_with(&json, &config.validate_options);
+        for finding in &findings {
+            config.verbosity.info(finding.to_string());
         }
-        OutputProfile::Outline => {
-            let outline_value = outline_openapi(&json)?;
-            let primary = serialize_json(&outline_value, config.minify)?;
-            Ok(OutputPayloads {
-                primary,
-                outline: None,
-            })
+        check_findings(&findings, None)?;
+    } else if config.validate_options.examples {
+        let findings = example_findings(&json, &config.validate_options);
+        for finding in &findings {
+            config.verbosity.info(finding.to_string());
         }
+        check_findings(&findings, None)?;
+    }
+    if let Some(severity) = config.verify_refs {
+        verify_refs(config, &json, severity)?;
+    }
+    if config.check_paths
+        && let Some(paths) = json.get("paths").and_then(Value::as_object)
+    {
+        for collision in path_collisions(paths) {
+            config.verbosity.info(format!("warning: {collision}"));
+        }
+    }
+    let version = spec_version(&json);
+    let ctx = TransformCtx::new(config, status).with_version(detected);
+    let doc = config.transforms.apply(json, &ctx)?;
+    // The outline reads the document before the profile's steps take it by
+    // value, so the document is never copied.
+    let outline = match (config.profile, &config.outline_out) {
+        (OutputProfile::Full | OutputProfile::Slim, Some(_)) => {
+            let outline_value = outline_openapi(&doc, &config.outline)?;
+            Some(serialize_json(&outline_value, config.minify)?)
+        }
+        _ => None,
+    };
+    let primary_value = Pipeline::for_profile(config).apply(doc, &ctx)?;
+    Ok(OutputPayloads {
+        primary: serialize_json(&primary_value, config.minify)?,
+        outline,
+        version,
+        status,
+        bytes,
+        max_age,
+        spec_version: detected,
+        version_notes: notes,
+        compared: None,
+    })
+}
+
+/// Whether the snapshot is the served document, only reformatted: nothing
+/// to transform, check, or outline. Such a document is parsed into a
+/// [`Node`] rather than a [`Value`], which takes far less memory.
+fn passes_through(config: &Config) -> bool {
+    config.profile == OutputProfile::Full
+        && config.reduce.is_empty()
+        && config.outline_out.is_none()
+        && config.transforms.is_empty()
+        && !config.validate
+        && !config.validate_options.examples
+        && config.verify_refs.is_none()
+        && !config.check_paths
+        && config
+            .input
+            .as_deref()
+            .is_none_or(|path| format_from_path(path) != Some(DocFormat::Yaml))
+}
+
+/// Fails with `--require-version` when the document declares another
+/// version.
+fn require_version(
+    config: &Config,
+    detected: SpecVersion,
+    declared: Option<&str>,
+) -> Result<(), AppError> {
+    match config.require_version {
+        Some(required) if detected != required => Err(AppError::validation(format!(
+            "expected an OpenAPI {} document, but {} declares {}.",
+            required.as_str(),
+            config.source(),
+            match declared {
+                Some(declared) => format!("version '{declared}'"),
+                None => "no version".to_string(),
+            }
+        ))),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn log_version_notes(config: &Config, notes: &[String]) {
+    for note in notes {
+        config.verbosity.info(format!("warning: {note}"));
+    }
+}
+
+/// Reports keys that appear twice in an object of a JSON `body`, failing on
+/// them when `severity` is an error. A YAML input is not scanned, and a body
+/// that is not JSON is left for the parse to report.
+fn check_duplicate_keys(config: &Config, body: &[u8], severity: Severity) -> Result<(), AppError> {
+    if config
+        .input
+        .as_deref()
+        .is_some_and(|path| format_from_path(path) == Some(DocFormat::Yaml))
+    {
+        return Ok(());
+    }
+    if severity != Severity::Error && body.len() > SCAN_LIMIT_BYTES {
+        config.verbosity.info(format!(
+            "not checked for duplicate keys: the document is {} MiB, over the {} MiB limit",
+            body.len() / (1024 * 1024),
+            SCAN_LIMIT_BYTES / (1024 * 1024)
+        ));
+        return Ok(());
+    }
+    let Ok(duplicates) = duplicate_keys(body) else {
+        return Ok(());
+    };
+    if duplicates.is_empty() {
+        return Ok(());
     }
+    if severity == Severity::Error {
+        let keys: Vec<String> = duplicates.iter().map(ToString::to_string).collect();
+        return Err(AppError::validation(format!(
+            "{} found:\n  {}",
+            match keys.len() {
+                1 => "1 duplicate key".to_string(),
+                count => format!("{count} duplicate keys"),
+            },
+            keys.join("\n  ")
+        )));
+    }
+    for duplicate in &duplicates {
+        config
+            .verbosity
+            .info(format!("warning: duplicate key {duplicate}"));
+    }
+    Ok(())
+}
+
+/// Reports the `$ref`s in `json` that do not resolve, failing on them when
+/// `severity` is an error, and lists the ones that were not checked.
+fn verify_refs(config: &Config, json: &Value, severity: Severity) -> Result<(), AppError> {
+    let report = check_refs(json);
+    for site in &report.unchecked {
+        config.verbosity.info(format!("not checked: {site}"));
+    }
+    if report.broken.is_empty() {
+        return Ok(());
+    }
+    if severity == Severity::Error {
+        let sites: Vec<String> = report.broken.iter().map(ToString::to_string).collect();
+        return Err(AppError::outline(format!(
+            "{} not resolve:\n  {}",
+            match sites.len() {
+                1 => "1 $ref does".to_string(),
+                count => format!("{count} $refs do"),
+            },
+            sites.join("\n  ")
+        )));
+    }
+    for site in &report.broken {
+        config
+            .verbosity
+            .info(format!("warning: {site} does not resolve"));
+    }
+    Ok(())
+}
+
+fn spec_version(json: &Value) -> Option<String> {
+    json.get("info")?
+        .get("version")?
+        .as_str()
+        .map(str::to_string)
 }
 
 pub fn write_output(config: &Config, payload: &str) -> Result<(), AppError> {
@@ -59,8 +378,18 @@ pub fn write_output(config: &Config, payload: &str) -> Result<(), AppError> {
     let out_path = config
         .out
         .as_ref()
-        .ok_or_else(|| AppError::Usage("--out is required unless --stdout is set.".to_string()))?;
-    write_atomic(out_path, payload)
+        .ok_or_else(|| AppError::usage("--out is required unless --stdout is set.".to_string()))?;
+    write_logged(config, out_path, payload)
+}
+
+/// Writes the outline payload to `--outline-out`, if both are present.
+pub fn write_outline(config: &Config, outputs: &OutputPayloads) -> Result<(), AppError> {
+    if let (Some(outline_payload), Some(outline_path)) =
+        (outputs.outline.as_ref(), config.outline_out.as_ref())
+    {
+        write_logged(config, outline_path, outline_payload)?;
+    }
+    Ok(())
 }
 
 pub fn write_outputs(config: &Config, outputs: &OutputPayloads) -> Result<(), AppError> {
@@ -72,48 +401,299 @@ pub fn write_outputs(config: &Config, outputs: &OutputPayloads) -> Result<(), Ap
     let out_path = config
         .out
         .as_ref()
-        .ok_or_else(|| AppError::Usage("--out is required unless --stdout is set.".to_string()))?;
-    write_atomic(out_path, &outputs.primary)?;
+        .ok_or_else(|| AppError::usage("--out is required unless --stdout is set.".to_string()))?;
+    if let Some(warning) = check_empty_paths(config, outputs)? {
+        config.verbosity.info(warning);
+    }
+    write_logged(config, out_path, &outputs.primary)?;
+    write_outline(config, outputs)
+}
 
-    if let (Some(outline_payload), Some(outline_path)) =
-        (outputs.outline.as_ref(), config.outline_out.as_ref())
-    {
-        write_atomic(outline_path, outline_payload)?;
+/// Refuses a snapshot whose `paths` object is empty when the `--out` file it
+/// would replace lists paths: a misconfigured server answering with a valid
+/// but empty spec should not wipe out the snapshot. `--allow-empty` writes it
+/// anyway. The primary payload is the outline itself under `--profile
+/// outline`, and a refused snapshot leaves the outline file alone too.
+/// Returns a warning when an empty snapshot has no earlier file to replace.
+pub fn check_empty_paths(
+    config: &Config,
+    outputs: &OutputPayloads,
+) -> Result<Option<String>, AppError> {
+    let Some(path) = config.out.as_ref().filter(|_| !config.stdout) else {
+        return Ok(None);
+    };
+    if path_count(outputs.primary.as_bytes()) != Some(0) {
+        return Ok(None);
+    }
+    match fs::read(path) {
+        Ok(existing) => match path_count(&existing) {
+            Some(count) if count > 0 && !config.allow_empty => Err(AppError::validation(format!(
+                "refusing to replace {} ({}) with a document whose `paths` object is empty; the server may be misconfigured. Pass --allow-empty to write it anyway.",
+                path.display(),
+                plural_paths(count)
+            ))),
+            _ => Ok(None),
+        },
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Some(format!(
+            "writing {} with an empty `paths` object",
+            path.display()
+        ))),
+        Err(_) => Ok(None),
+    }
+}
+
+/// The number of entries in the top-level `paths` object, read without
+/// building the rest of the document.
+fn path_count(bytes: &[u8]) -> Option<usize> {
+    #[derive(serde::Deserialize)]
+    struct Paths {
+        paths: Option<BTreeMap<String, IgnoredAny>>,
+    }
+    let doc: Paths = serde_json::from_slice(bytes).ok()?;
+    doc.paths.map(|paths| paths.len())
+}
+
+fn plural_paths(count: usize) -> String {
+    if count == 1 {
+        "1 path".to_string()
+    } else {
+        format!("{count} paths")
+    }
+}
+
+/// Compares freshly built outputs with the files on disk, for `check`.
+/// Returns nothing when they match in canonical form (byte for byte with
+/// `--strict-bytes`); otherwise a line per file that is missing or differs,
+/// followed by the structural changes from the file on disk to the new
+/// output, colored by `palette`.
+pub fn snapshot_drift(
+    config: &Config,
+    outputs: &OutputPayloads,
+    palette: Palette,
+) -> Result<Vec<String>, AppError> {
+    let out = config
+        .out
+        .as_ref()
+        .ok_or_else(|| AppError::usage("check needs an --out file to compare.".to_string()))?;
+    let mut files = vec![(out, &outputs.primary)];
+    if let (Some(outline), Some(path)) = (&outputs.outline, &config.outline_out) {
+        files.push((path, outline));
     }
 
+    let mut lines = Vec::new();
+    for (path, contents) in files {
+        let existing = match fs::read(path) {
+            Ok(existing) if existing == contents.as_bytes() => continue,
+            Ok(existing) => existing,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                lines.push(format!("{}: missing", path.display()));
+                continue;
+            }
+            Err(err) => {
+                return Err(AppError::io(format!(
+                    "failed to read {}: {err}",
+                    path.display()
+                )));
+            }
+        };
+        let Ok(old) = serde_json::from_slice::<Value>(&existing) else {
+            lines.push(format!("{}: not valid JSON", path.display()));
+            continue;
+        };
+        let old = for_comparison(old, config.strict_bytes);
+        let new = for_comparison(parse_json(contents.as_bytes())?, config.strict_bytes);
+        if old == new {
+            if config.strict_bytes {
+                lines.push(format!("{}: formatting differs", path.display()));
+            }
+            continue;
+        }
+        let changes = diff_documents(&old, &new);
+        if changes.is_empty() {
+            lines.push(format!(
+                "{}: changed outside paths and schemas",
+                path.display()
+            ));
+            continue;
+        }
+        lines.push(format!(
+            "{}: {}",
+            path.display(),
+            summarize_changes(&changes)
+        ));
+        lines.extend(
+            changes
+                .iter()
+                .map(|change| format!("  {}", palette.change(change))),
+        );
+    }
+    Ok(lines)
+}
+
+/// The changes from the `--out` file on disk to the freshly built primary
+/// output that can break existing clients, for `check --breaking`. A missing
+/// or unparsable file has nothing to break.
+pub fn snapshot_breaking(
+    config: &Config,
+    outputs: &OutputPayloads,
+) -> Result<Vec<BreakingChange>, AppError> {
+    let Some(out) = config.out.as_ref() else {
+        return Ok(Vec::new());
+    };
+    let existing = match fs::read(out) {
+        Ok(existing) => existing,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(
+                AppError::io(format!("failed to read {}: {err}", out.display())).with_source(err),
+            );
+        }
+    };
+    let Ok(old) = serde_json::from_slice::<Value>(&existing) else {
+        return Ok(Vec::new());
+    };
+    let new = parse_json(outputs.primary.as_bytes())?;
+    Ok(breaking_changes(
+        &for_comparison(old, config.strict_bytes),
+        &for_comparison(new, config.strict_bytes),
+    ))
+}
+
+fn write_logged(config: &Config, path: &Path, contents: &str) -> Result<(), AppError> {
+    if let Some(warning) = write_atomic_in(path, contents, config.temp_dir.as_deref())? {
+        config.verbosity.info(format!("warning: {warning}"));
+    }
+    config.verbosity.verbose(format!(
+        "wrote {} ({} bytes)",
+        path.display(),
+        contents.len()
+    ));
     Ok(())
 }
 
-fn reduce_openapi(value: Value, keys: &[ReduceKey]) -> Result<Value, AppError> {
-    let object = value
-        .as_object()
-        .ok_or_else(|| AppError::Reduce("OpenAPI document must be a JSON object".to_string()))?;
-    let mut reduced = serde_json::Map::new();
-    for key in keys {
-        let name = key.as_str();
-        let entry = object
-            .get(name)
-            .ok_or_else(|| AppError::Reduce(format!("missing top-level key: {name}")))?;
-        reduced.insert(name.to_string(), entry.clone());
+/// Describes what [`write_outputs`] would do, for `--dry-run`. Header values
+/// that look like credentials are masked.
+pub fn dry_run_summary(config: &Config, outputs: &OutputPayloads) -> String {
+    let mut lines = Vec::new();
+    if let Some(name) = &config.name {
+        lines.push(format!("target: {name}"));
+    }
+    match &config.input {
+        Some(path) => lines.push(format!("input: {}", path.display())),
+        None => lines.push(format!("url: {}", config.url)),
+    }
+    let headers: Vec<String> = config
+        .headers
+        .iter()
+        .map(|header| mask_header(header))
+        .collect();
+    lines.push(format!("headers: {}", list_or_none(&headers)));
+    let reduce: Vec<String> = config
+        .reduce
+        .iter()
+        .map(|key| key.as_str().to_string())
+        .collect();
+    lines.push(format!("reduce: {}", list_or_none(&reduce)));
+    lines.push(format!(
+        "profile: {}",
+        match config.profile {
+            OutputProfile::Full => "full",
+            OutputProfile::Slim => "slim",
+            OutputProfile::Outline => "outline",
+        }
+    ));
+    lines.push(match outputs.status {
+        Some(status) => format!("fetched: {} bytes (HTTP {status})", outputs.bytes),
+        None => format!("read: {} bytes", outputs.bytes),
+    });
+
+    if config.stdout {
+        lines.push(format!("snapshot: stdout, {} bytes", outputs.primary.len()));
+    } else if let Some(out) = &config.out {
+        lines.push(planned_write("snapshot", out, &outputs.primary));
+    }
+    if !config.stdout
+        && let (Some(outline), Some(path)) = (&outputs.outline, &config.outline_out)
+    {
+        lines.push(planned_write("outline", path, outline));
+    }
+    lines.push("dry run: nothing was written".to_string());
+    lines.join("\n")
+}
+
+fn planned_write(label: &str, path: &Path, contents: &str) -> String {
+    let effect = match fs::read(path) {
+        Ok(existing) if existing == contents.as_bytes() => "unchanged",
+        Ok(_) => "would change",
+        Err(_) => "would be created",
+    };
+    format!(
+        "{label}: {}, {} bytes, {effect}",
+        path.display(),
+        contents.len()
+    )
+}
+
+fn list_or_none(items: &[String]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
     }
-    Ok(Value::Object(reduced))
 }
 
-fn serialize_json(value: &Value, minify: bool) -> Result<String, AppError> {
+pub fn serialize_json(value: &impl Serialize, minify: bool) -> Result<String, AppError> {
     if minify {
-        serde_json::to_string(value).map_err(|err| AppError::Json(format!("json error: {err}")))
+        serde_json::to_string(value)
+            .map_err(|err| AppError::json(format!("json error: {err}")).with_source(err))
     } else {
         serde_json::to_string_pretty(value)
-            .map_err(|err| AppError::Json(format!("json error: {err}")))
+            .map_err(|err| AppError::json(format!("json error: {err}")).with_source(err))
     }
 }
 
-fn write_atomic(path: &Path, contents: &str) -> Result<(), AppError> {
+/// Name of the temp file `write_atomic` renames over `path`:
+/// `.<file name>.<unix millis>.tmp`. `clean` removes leftovers by this name.
+pub(crate) fn temp_file_name(path: &Path, millis: u128) -> String {
+    format!(
+        ".{}.{}.tmp",
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("openapi_snapshot"),
+        millis
+    )
+}
+
+/// Name of the copy a cross-device move stages next to `path`:
+/// `<file name>.<unix millis>.partial`. `clean` removes leftovers by this
+/// name too.
+pub(crate) fn partial_file_name(path: &Path, millis: u128) -> String {
+    format!(
+        "{}.{}.partial",
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("openapi_snapshot"),
+        millis
+    )
+}
+
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> Result<(), AppError> {
+    write_atomic_in(path, contents, None).map(|_| ())
+}
+
+/// Writes `contents` to a temp file in `temp_dir`, or next to `path` when
+/// there is none, and moves it over `path`; see [`move_into_place`]. Returns
+/// a warning when the file could only be copied over `path` in place.
+pub(crate) fn write_atomic_in(
+    path: &Path,
+    contents: &str,
+    temp_dir: Option<&Path>,
+) -> Result<Option<String>, AppError> {
     let parent = path
         .parent()
-        .ok_or_else(|| AppError::Io("output path has no parent directory".to_string()))?;
+        .ok_or_else(|| AppError::io("output path has no parent directory".to_string()))?;
     if let Err(err) = fs::create_dir_all(parent) {
-        return Err(AppError::Io(format!(
+        return Err(AppError::io(format!(
             "failed to create output directory: {err}"
         )));
     }
@@ -122,68 +702,535 @@ fn write_atomic(path: &Path, contents: &str) -> Result<(), AppError> {
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_millis();
-    let temp_name = format!(
-        ".{}.{}.tmp",
-        path.file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or("openapi_snapshot"),
-        timestamp
-    );
-    let temp_path = parent.join(temp_name);
+    let temp_path = temp_dir
+        .unwrap_or(parent)
+        .join(temp_file_name(path, timestamp));
 
-    let mut file = OpenOptions::new()
-        .create_new(true)
-        .write(true)
-        .open(&temp_path)
-        .map_err(|err| AppError::Io(format!("failed to create temp file: {err}")))?;
+    // The handle is closed at the end of this block, before the rename.
+    {
+        let mut file = OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(&temp_path)
+            .map_err(|err| {
+                AppError::io(format!(
+                    "failed to create temp file {} for {}: {err}",
+                    temp_path.display(),
+                    path.display()
+                ))
+                .with_source(err)
+            })?;
 
-    if let Err(err) = file.write_all(contents.as_bytes()) {
-        let _ = fs::remove_file(&temp_path);
-        return Err(AppError::Io(format!("failed to write temp file: {err}")));
-    }
+        if let Err(err) = file.write_all(contents.as_bytes()) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(AppError::io(format!(
+                "failed to write temp file {}: {err}",
+                temp_path.display()
+            )));
+        }
 
-    if let Err(err) = file.sync_all() {
-        let _ = fs::remove_file(&temp_path);
-        return Err(AppError::Io(format!("failed to flush temp file: {err}")));
+        if let Err(err) = file.sync_all() {
+            let _ = fs::remove_file(&temp_path);
+            return Err(AppError::io(format!(
+                "failed to flush temp file {}: {err}",
+                temp_path.display()
+            )));
+        }
     }
 
-    if let Err(err) = fs::rename(&temp_path, path) {
-        let _ = fs::remove_file(&temp_path);
-        return Err(AppError::Io(format!("failed to move temp file: {err}")));
+    move_into_place(&temp_path, path, &|from, to| fs::rename(from, to))
+}
+
+/// Renames `temp` over `path` with `rename`, removing `temp` either way.
+///
+/// When they are on different filesystems (EXDEV), `temp` is copied next to
+/// `path`, synced, and renamed from there, which is still atomic. When that
+/// fails too, as on a mount that refuses the temp file, `temp` is copied
+/// over `path` in place, which a reader can see half-written; the returned
+/// warning says so.
+fn move_into_place(
+    temp: &Path,
+    path: &Path,
+    rename: &dyn Fn(&Path, &Path) -> std::io::Result<()>,
+) -> Result<Option<String>, AppError> {
+    let result = match rename(temp, path) {
+        Ok(()) => Ok(None),
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+            move_across_devices(temp, path, rename)
+        }
+        Err(err) => Err(AppError::io(format!(
+            "failed to move temp file {} to {}: {err}",
+            temp.display(),
+            path.display()
+        ))
+        .with_source(err)),
+    };
+    let _ = fs::remove_file(temp);
+    result
+}
+
+fn move_across_devices(
+    temp: &Path,
+    path: &Path,
+    rename: &dyn Fn(&Path, &Path) -> std::io::Result<()>,
+) -> Result<Option<String>, AppError> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let staged = parent.join(partial_file_name(path, millis));
+    let staged_err = match copy_synced(temp, &staged).and_then(|()| rename(&staged, path)) {
+        Ok(()) => return Ok(None),
+        Err(err) => err,
+    };
+    let _ = fs::remove_file(&staged);
+    match copy_synced(temp, path) {
+        Ok(()) => Ok(Some(format!(
+            "{} is on another filesystem than {} and {} could not be staged next to it \
+             ({staged_err}); copied it in place, which is not atomic",
+            temp.display(),
+            path.display(),
+            staged.display()
+        ))),
+        Err(err) => Err(AppError::io(format!(
+            "failed to copy temp file {} to {}: {err}",
+            temp.display(),
+            path.display()
+        ))
+        .with_source(err)),
     }
+}
 
-    Ok(())
+/// Copies `from` to `to` and syncs `to` to disk.
+fn copy_synced(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::copy(from, to)?;
+    OpenOptions::new().write(true).open(to)?.sync_all()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_json::json;
+    use crate::validate::ValidateOptions;
 
     #[test]
-    fn reduce_openapi_keeps_only_requested_keys() {
-        let input = json!({
-            "paths": {"x": 1},
-            "components": {"y": 2},
-            "extra": {"z": 3}
-        });
-        let output = reduce_openapi(input, &[ReduceKey::Components]).unwrap();
-        assert!(output.get("paths").is_none());
-        assert!(output.get("components").is_some());
-        assert!(output.get("extra").is_none());
+    fn spec_version_reads_info_version_quietly() {
+        let json = serde_json::json!({"info": {"title": "api", "version": "1.4.2"}});
+        assert_eq!(spec_version(&json).as_deref(), Some("1.4.2"));
+        assert_eq!(spec_version(&serde_json::json!({"info": {}})), None);
+        assert_eq!(
+            spec_version(&serde_json::json!({"info": {"version": 2}})),
+            None
+        );
+        assert_eq!(spec_version(&serde_json::json!([])), None);
+    }
+
+    #[test]
+    fn require_version_fails_before_transforming_and_notes_are_kept() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut config = file_config(&temp.path().join("a.json"), &temp.path().join("b.json"));
+        let body = br#"{"openapi": "3.1.0", "info": {}, "paths": {}, "webhooks": {}}"#;
+
+        // Once transformed, and once written as served.
+        for pass_through in [false, true] {
+            if pass_through {
+                config.outline_out = None;
+                config.reduce.clear();
+            }
+            assert_eq!(passes_through(&config), pass_through);
+
+            config.require_version = Some(SpecVersion::OpenApi30);
+            let err = transform_outputs(&config, None, body.to_vec(), None).unwrap_err();
+            assert_eq!(err.kind(), crate::errors::ErrorKind::Validation);
+            assert_eq!(
+                err.to_string(),
+                "expected an OpenAPI 3.0 document, but http://localhost:3000/openapi.json \
+                 declares version '3.1.0'."
+            );
+
+            config.require_version = Some(SpecVersion::OpenApi31);
+            let outputs = transform_outputs(&config, None, body.to_vec(), None).unwrap();
+            assert_eq!(outputs.spec_version, SpecVersion::OpenApi31);
+            assert_eq!(
+                outputs.version_notes,
+                vec!["OpenAPI 3.1 webhooks are not included in outlines"]
+            );
+        }
+    }
+
+    fn cross_device() -> std::io::Error {
+        std::io::Error::from(std::io::ErrorKind::CrossesDevices)
+    }
+
+    /// A temp dir and an output dir, with a staged file in the temp dir.
+    fn staged_write() -> (tempfile::TempDir, std::path::PathBuf, std::path::PathBuf) {
+        let temp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp.path().join("stage")).unwrap();
+        fs::create_dir_all(temp.path().join("out")).unwrap();
+        let staged = temp.path().join("stage/.api.json.1.tmp");
+        fs::write(&staged, "new").unwrap();
+        let path = temp.path().join("out/api.json");
+        fs::write(&path, "old").unwrap();
+        (temp, staged, path)
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn cross_device_moves_copy_next_to_the_output_and_rename() {
+        let (temp, staged, path) = staged_write();
+        let moved = std::cell::RefCell::new(Vec::new());
+        let rename = |from: &Path, to: &Path| {
+            if from == staged.as_path() {
+                Err(cross_device())
+            } else {
+                moved
+                    .borrow_mut()
+                    .push(from.file_name().unwrap().to_string_lossy().into_owned());
+                fs::rename(from, to)
+            }
+        };
+        assert_eq!(move_into_place(&staged, &path, &rename).unwrap(), None);
+        let moved = moved.into_inner();
+        assert_eq!(moved.len(), 1);
+        assert!(
+            moved[0].starts_with("api.json.") && moved[0].ends_with(".partial"),
+            "{moved:?}"
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(entries(&temp.path().join("stage")).is_empty());
+        assert_eq!(entries(&temp.path().join("out")), vec!["api.json"]);
+    }
+
+    #[test]
+    fn unstageable_cross_device_moves_copy_in_place_with_a_warning() {
+        let (temp, staged, path) = staged_write();
+        let warning = move_into_place(&staged, &path, &|_, _| Err(cross_device()))
+            .unwrap()
+            .unwrap();
+        assert!(warning.starts_with(&format!(
+            "{} is on another filesystem than {}",
+            staged.display(),
+            path.display()
+        )));
+        assert!(warning.ends_with("copied it in place, which is not atomic"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert!(entries(&temp.path().join("stage")).is_empty());
+        assert_eq!(entries(&temp.path().join("out")), vec!["api.json"]);
+    }
+
+    #[test]
+    fn other_rename_errors_name_both_paths() {
+        let (temp, staged, path) = staged_write();
+        let err = move_into_place(&staged, &path, &|_, _| {
+            Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), crate::errors::ErrorKind::Io);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "failed to move temp file {} to {}: permission denied",
+                staged.display(),
+                path.display()
+            )
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert!(entries(&temp.path().join("stage")).is_empty());
+    }
+
+    #[test]
+    fn temp_dir_stages_the_file_outside_the_output_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let stage = temp.path().join("stage");
+        fs::create_dir_all(&stage).unwrap();
+        let path = temp.path().join("out/api.json");
+        assert_eq!(write_atomic_in(&path, "{}", Some(&stage)).unwrap(), None);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        assert!(entries(&stage).is_empty());
+
+        let err = write_atomic_in(&path, "{}", Some(&temp.path().join("missing"))).unwrap_err();
+        assert!(
+            err.to_string().starts_with(&format!(
+                "failed to create temp file {}",
+                temp.path().join("missing").display()
+            )),
+            "{err}"
+        );
+        assert!(
+            err.to_string()
+                .contains(&format!("for {}:", path.display()))
+        );
+    }
+
+    #[test]
+    fn duplicate_key_check_leaves_parse_errors_to_the_parse() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut config = file_config(&temp.path().join("a.json"), &temp.path().join("b.json"));
+        config.duplicate_keys = Some(Severity::Error);
+
+        let err =
+            transform_outputs(&config, None, br#"{"a": 1, "a": }"#.to_vec(), None).unwrap_err();
+        assert_eq!(err.kind(), crate::errors::ErrorKind::Json);
+
+        let err = transform_outputs(
+            &config,
+            None,
+            br#"{"paths": {}, "paths": {}}"#.to_vec(),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), crate::errors::ErrorKind::Validation);
+    }
+
+    fn file_config(out: &Path, outline: &Path) -> Config {
+        Config {
+            name: None,
+            url: "http://localhost:3000/openapi.json".to_string(),
+            url_from_default: false,
+            doc_path: crate::cli::DEFAULT_PATH.to_string(),
+            input: None,
+            out: Some(out.to_path_buf()),
+            outline_out: Some(outline.to_path_buf()),
+            temp_dir: None,
+            reduce: vec![crate::config::ReduceKey::Paths],
+            profile: OutputProfile::Full,
+            outline: crate::outline::OutlineOptions::default(),
+            minify: false,
+            timeout_ms: 1_000,
+            headers: vec!["Authorization: Bearer abc".to_string()],
+            stdout: false,
+            verbosity: crate::verbosity::Verbosity::Normal,
+            validate: false,
+            color: crate::cli::ColorChoice::Auto,
+            fallback_to_last: None,
+            allow_empty: false,
+            strict_bytes: false,
+            verify_refs: None,
+            validate_options: ValidateOptions::default(),
+            require_version: None,
+            check_paths: false,
+            duplicate_keys: None,
+            transforms: crate::transform::Pipeline::default(),
+        }
+    }
+
+    /// Every profile, with and without `--outline-out` and `--reduce`,
+    /// gives the payloads of running each step on its own copy of the
+    /// document, which `transform_outputs` no longer makes.
+    #[test]
+    fn payloads_match_per_step_copies_in_every_combination() {
+        let body = br##"{
+            "openapi": "3.0.3",
+            "info": {"title": "api", "version": "1.0.0", "description": "docs"},
+            "paths": {"/users": {"get": {
+                "summary": "List users",
+                "responses": {"200": {"description": "ok", "content": {"application/json": {
+                    "schema": {"$ref": "#/components/schemas/User"}
+                }}}}
+            }}},
+            "components": {"schemas": {
+                "User": {"type": "object", "properties": {"id": {"type": "string"}}},
+                "Unused": {"type": "string"}
+            }}
+        }"##;
+        let doc = parse_json(body).unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        let outline_path = temp.path().join("api.outline.json");
+        for profile in [
+            OutputProfile::Full,
+            OutputProfile::Slim,
+            OutputProfile::Outline,
+        ] {
+            for outline_out in [None, Some(outline_path.clone())] {
+                for reduce in [vec![], vec![crate::config::ReduceKey::Paths]] {
+                    let mut config = file_config(&temp.path().join("api.json"), &outline_path);
+                    config.profile = profile;
+                    config.outline_out = outline_out.clone();
+                    config.reduce = reduce;
+                    let ctx = TransformCtx::new(&config, None);
+
+                    let outputs = transform_outputs(&config, None, body.to_vec(), None).unwrap();
+                    let primary = Pipeline::for_profile(&config)
+                        .apply(doc.clone(), &ctx)
+                        .unwrap();
+                    assert_eq!(outputs.primary, serialize_json(&primary, false).unwrap());
+                    let outline = match (profile, &outline_out) {
+                        (OutputProfile::Outline, _) | (_, None) => None,
+                        _ => Some(
+                            serialize_json(&outline_openapi(&doc, &config.outline).unwrap(), false)
+                                .unwrap(),
+                        ),
+                    };
+                    assert_eq!(outputs.outline, outline, "{profile:?} {outline_out:?}");
+                }
+            }
+        }
+    }
+
+    fn payloads(primary: &str, outline: &str) -> OutputPayloads {
+        OutputPayloads {
+            primary: primary.to_string(),
+            outline: Some(outline.to_string()),
+            version: None,
+            status: Some(200),
+            bytes: 42,
+            max_age: None,
+            spec_version: crate::version::SpecVersion::OpenApi30,
+            version_notes: Vec::new(),
+            compared: None,
+        }
     }
 
     #[test]
-    fn reduce_openapi_missing_key_is_error() {
-        let input = json!({"paths": {"x": 1}});
-        let err = reduce_openapi(input, &[ReduceKey::Components]).unwrap_err();
-        assert!(matches!(err, AppError::Reduce(_)));
+    fn with_comparison_canonicalizes_the_payloads_once() {
+        let outputs =
+            payloads("{\n  \"paths\": {\"/a\": {\"x\": 1.0}}\n}", "{ }").with_comparison(false);
+        let compared = outputs.compared.as_ref().unwrap();
+        assert_eq!(
+            compared.doc,
+            Some(serde_json::json!({"paths": {"/a": {"x": 1}}}))
+        );
+        assert_eq!(
+            *compared,
+            ComparedPayloads::new(r#"{"paths":{"/a":{"x":1}}}"#, Some("{}"), false)
+        );
+        assert!(matches!(outputs.comparison(false), Cow::Borrowed(_)));
+
+        let strict = payloads(r#"{"paths":{}}"#, "{ }").with_comparison(true);
+        assert_ne!(
+            strict.compared.unwrap().outline_hash,
+            ComparedPayloads::new(r#"{"paths":{}}"#, Some("{}"), true).outline_hash
+        );
     }
 
     #[test]
-    fn reduce_openapi_requires_object() {
-        let input = json!(["not an object"]);
-        let err = reduce_openapi(input, &[ReduceKey::Components]).unwrap_err();
-        assert!(matches!(err, AppError::Reduce(_)));
+    fn dry_run_summary_reports_planned_writes() {
+        let temp = tempfile::tempdir().unwrap();
+        let out = temp.path().join("api.json");
+        let outline = temp.path().join("api.outline.json");
+        fs::write(&outline, "{}").unwrap();
+        let config = file_config(&out, &outline);
+        let outputs = payloads("{\"paths\":{}}", "{}");
+        let summary = dry_run_summary(&config, &outputs);
+        assert_eq!(
+            summary,
+            format!(
+                "url: http://localhost:3000/openapi.json\n\
+                 headers: Authorization: ***\n\
+                 reduce: paths\n\
+                 profile: full\n\
+                 fetched: 42 bytes (HTTP 200)\n\
+                 snapshot: {}, 12 bytes, would be created\n\
+                 outline: {}, 2 bytes, unchanged\n\
+                 dry run: nothing was written",
+                out.display(),
+                outline.display()
+            )
+        );
+        assert!(!out.exists());
+    }
+
+    #[test]
+    fn snapshot_drift_reports_missing_and_changed_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let out = temp.path().join("api.json");
+        let outline = temp.path().join("api.outline.json");
+        let config = file_config(&out, &outline);
+        let outputs = payloads(r#"{"paths":{"/users":{}}}"#, r#"{"paths":{}}"#);
+
+        let drift = snapshot_drift(&config, &outputs, Palette::default()).unwrap();
+        assert_eq!(
+            drift,
+            vec![
+                format!("{}: missing", out.display()),
+                format!("{}: missing", outline.display()),
+            ]
+        );
+
+        fs::write(&out, r#"{"paths":{"/users":{}}}"#).unwrap();
+        fs::write(&outline, r#"{"paths":{}}"#).unwrap();
+        assert!(
+            snapshot_drift(&config, &outputs, Palette::default())
+                .unwrap()
+                .is_empty()
+        );
+
+        fs::write(&out, r#"{"paths":{}}"#).unwrap();
+        assert_eq!(
+            snapshot_drift(&config, &outputs, Palette::default()).unwrap(),
+            vec![
+                format!(
+                    "{}: 1 change: 1 added, 0 removed, 0 modified",
+                    out.display()
+                ),
+                "  added: path /users".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn snapshot_drift_ignores_formatting_unless_strict_bytes() {
+        let temp = tempfile::tempdir().unwrap();
+        let out = temp.path().join("api.json");
+        let outline = temp.path().join("api.outline.json");
+        let mut config = file_config(&out, &outline);
+        let outputs = payloads(
+            r#"{"paths":{"/users":{"get":{"x-weight":1}}}}"#,
+            r#"{"paths":{}}"#,
+        );
+        fs::write(
+            &out,
+            "{\n  \"paths\": {\"/users\": {\"get\": {\"x-weight\": 1.0}}}\n}",
+        )
+        .unwrap();
+        fs::write(&outline, r#"{"paths":{}}"#).unwrap();
+        assert!(
+            snapshot_drift(&config, &outputs, Palette::default())
+                .unwrap()
+                .is_empty()
+        );
+
+        config.strict_bytes = true;
+        assert_eq!(
+            snapshot_drift(&config, &outputs, Palette::default()).unwrap(),
+            vec![
+                format!(
+                    "{}: 1 change: 0 added, 0 removed, 1 modified",
+                    out.display()
+                ),
+                "  modified: GET /users (metadata)".to_string(),
+            ]
+        );
+        fs::write(
+            &out,
+            "{\"paths\": {\"/users\": {\"get\": {\"x-weight\": 1}}}}",
+        )
+        .unwrap();
+        assert_eq!(
+            snapshot_drift(&config, &outputs, Palette::default()).unwrap(),
+            vec![format!("{}: formatting differs", out.display())]
+        );
+
+        config.strict_bytes = false;
+        fs::write(
+            &out,
+            r#"{"info":{"version":"1"},"paths":{"/users":{"get":{"x-weight":1}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            snapshot_drift(&config, &outputs, Palette::default()).unwrap(),
+            vec![format!(
+                "{}: changed outside paths and schemas",
+                out.display()
+            )]
+        );
     }
 }
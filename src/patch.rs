@@ -0,0 +1,140 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::validate::escape;
+
+/// One RFC 6902 operation. `path` is a JSON Pointer into the document as
+/// the operations before it have left it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+}
+
+/// An RFC 6902 JSON Patch that turns `old` into `new`. Objects are compared
+/// key by key, so only the members that differ are touched; arrays and
+/// values of different types are replaced whole. Identical documents give
+/// an empty patch.
+pub fn json_patch(old: &Value, new: &Value) -> Vec<PatchOperation> {
+    let mut operations = Vec::new();
+    diff_value(old, new, String::new(), &mut operations);
+    operations
+}
+
+fn diff_value(old: &Value, new: &Value, path: String, operations: &mut Vec<PatchOperation>) {
+    if old == new {
+        return;
+    }
+    let (Value::Object(before), Value::Object(after)) = (old, new) else {
+        operations.push(PatchOperation::Replace {
+            path,
+            value: new.clone(),
+        });
+        return;
+    };
+    for key in before.keys().filter(|key| !after.contains_key(*key)) {
+        operations.push(PatchOperation::Remove {
+            path: format!("{path}/{}", escape(key)),
+        });
+    }
+    for (key, value) in after {
+        let location = format!("{path}/{}", escape(key));
+        match before.get(key) {
+            Some(previous) => diff_value(previous, value, location, operations),
+            None => operations.push(PatchOperation::Add {
+                path: location,
+                value: value.clone(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Applies `operations` with an independent implementation of RFC 6902.
+    fn apply(old: &Value, operations: &[PatchOperation]) -> Value {
+        let patch: json_patch::Patch =
+            serde_json::from_value(serde_json::to_value(operations).unwrap()).unwrap();
+        let mut doc = old.clone();
+        json_patch::patch(&mut doc, &patch).unwrap();
+        doc
+    }
+
+    fn fixture_pair(name: &str) -> (Value, Value) {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(name);
+        let read = |path: std::path::PathBuf| -> Value {
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+        };
+        // The breaking-change cases share one old document.
+        let old = match dir.join("old.json") {
+            old if old.is_file() => old,
+            _ => dir.join("../base.json"),
+        };
+        (read(old), read(dir.join("new.json")))
+    }
+
+    #[test]
+    fn patch_round_trips_the_fixture_pairs() {
+        for name in [
+            "tests/fixtures/diff/changed",
+            "tests/fixtures/diff/reordered",
+            "tests/fixtures/breaking/required-request-property",
+            "tests/fixtures/breaking/additions",
+        ] {
+            let (old, new) = fixture_pair(name);
+            let operations = json_patch(&old, &new);
+            assert_eq!(apply(&old, &operations), new, "{name}");
+            assert!(
+                operations.iter().all(|operation| !matches!(
+                    operation,
+                    PatchOperation::Replace { path, .. } if path.is_empty()
+                )),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn patch_touches_only_changed_members_with_escaped_pointers() {
+        let old = json!({
+            "paths": {"/users/{id}": {"get": {"summary": "Get", "tags": ["a"]}}},
+            "x-tilde~key": 1,
+            "info": {"version": "1"}
+        });
+        let new = json!({
+            "paths": {"/users/{id}": {"get": {"summary": "Fetch", "tags": ["a", "b"]}}},
+            "info": {"version": "1", "title": "Users"}
+        });
+        let operations = json_patch(&old, &new);
+        assert_eq!(
+            serde_json::to_value(&operations).unwrap(),
+            json!([
+                {"op": "remove", "path": "/x-tilde~0key"},
+                {"op": "add", "path": "/info/title", "value": "Users"},
+                {"op": "replace", "path": "/paths/~1users~1{id}/get/summary", "value": "Fetch"},
+                {"op": "replace", "path": "/paths/~1users~1{id}/get/tags", "value": ["a", "b"]}
+            ])
+        );
+        assert_eq!(apply(&old, &operations), new);
+    }
+
+    #[test]
+    fn identical_documents_give_an_empty_patch_and_other_roots_are_replaced() {
+        let doc = json!({"paths": {}});
+        assert!(json_patch(&doc, &doc).is_empty());
+        let operations = json_patch(&json!([1]), &json!({"a": 1}));
+        assert_eq!(
+            operations,
+            vec![PatchOperation::Replace {
+                path: String::new(),
+                value: json!({"a": 1}),
+            }]
+        );
+        assert_eq!(apply(&json!([1]), &operations), json!({"a": 1}));
+    }
+}
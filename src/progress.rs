@@ -0,0 +1,141 @@
+//! The progress line a fetch draws on stderr while a slow or large body
+//! downloads: percent, bytes, and rate when the server sends a
+//! `Content-Length`, a spinner and a byte count when it does not.
+
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use crate::verbosity::Verbosity;
+
+/// How long a download runs before the line appears, so fast fetches print
+/// nothing.
+const DELAY: Duration = Duration::from_millis(500);
+/// The shortest time between redraws.
+const INTERVAL: Duration = Duration::from_millis(100);
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// A line redrawn in place on stderr, and cleared when dropped.
+pub(crate) struct ProgressLine {
+    started: Instant,
+    last_drawn: Option<Instant>,
+    frame: usize,
+}
+
+impl ProgressLine {
+    /// A line for stderr, unless it is not a terminal or `verbosity` is
+    /// quiet.
+    pub(crate) fn for_stderr(verbosity: Verbosity) -> Option<Self> {
+        (verbosity.shows(Verbosity::Normal) && io::stderr().is_terminal()).then(|| Self {
+            started: Instant::now(),
+            last_drawn: None,
+            frame: 0,
+        })
+    }
+
+    pub(crate) fn update(&mut self, downloaded: u64, total: Option<u64>) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.started);
+        let due = match self.last_drawn {
+            Some(last) => now.duration_since(last) >= INTERVAL,
+            None => elapsed >= DELAY,
+        };
+        if !due {
+            return;
+        }
+        self.last_drawn = Some(now);
+        self.frame += 1;
+        let line = render(downloaded, total, elapsed, self.frame);
+        let mut stderr = io::stderr().lock();
+        // A line that cannot be drawn is not worth failing the fetch over.
+        let _ = write!(stderr, "\r\x1b[2K{line}");
+        let _ = stderr.flush();
+    }
+}
+
+impl Drop for ProgressLine {
+    fn drop(&mut self) {
+        if self.last_drawn.is_some() {
+            let _ = write!(io::stderr(), "\r\x1b[2K");
+        }
+    }
+}
+
+/// The line for `downloaded` of `total` bytes after `elapsed`; `frame`
+/// turns the spinner shown when the total is unknown.
+fn render(downloaded: u64, total: Option<u64>, elapsed: Duration, frame: usize) -> String {
+    let rate = match elapsed.as_secs_f64() {
+        secs if secs > 0.0 => format!("{}/s", format_bytes((downloaded as f64 / secs) as u64)),
+        _ => "-".to_string(),
+    };
+    match total {
+        Some(total) if total > 0 => format!(
+            "downloading {} of {} ({}%), {rate}",
+            format_bytes(downloaded),
+            format_bytes(total),
+            (downloaded.min(total) * 100 / total)
+        ),
+        _ => format!(
+            "{} downloading {}, {rate}",
+            SPINNER[frame % SPINNER.len()],
+            format_bytes(downloaded)
+        ),
+    }
+}
+
+/// `bytes` in B, KiB, MiB, or GiB, with one decimal above bytes.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_totals_show_percent_and_rate() {
+        let line = render(
+            20 * 1024 * 1024,
+            Some(80 * 1024 * 1024),
+            Duration::from_secs(4),
+            1,
+        );
+        assert_eq!(line, "downloading 20.0 MiB of 80.0 MiB (25%), 5.0 MiB/s");
+        // A body longer than it advertised stops at 100%.
+        let line = render(3000, Some(2048), Duration::from_secs(1), 1);
+        assert_eq!(line, "downloading 2.9 KiB of 2.0 KiB (100%), 2.9 KiB/s");
+    }
+
+    #[test]
+    fn unknown_totals_spin_with_a_byte_count() {
+        assert_eq!(
+            render(512, None, Duration::from_secs(1), 1),
+            "/ downloading 512 B, 512 B/s"
+        );
+        assert_eq!(
+            render(512, Some(0), Duration::ZERO, 2),
+            "- downloading 512 B, -"
+        );
+    }
+
+    #[test]
+    fn bytes_use_binary_units() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024 * 1024), "5120.0 GiB");
+    }
+
+    #[test]
+    fn quiet_runs_draw_nothing() {
+        assert!(ProgressLine::for_stderr(Verbosity::Quiet).is_none());
+    }
+}
@@ -0,0 +1,251 @@
+//! Finds and removes components nothing in the document uses.
+//!
+//! A component is used when it is reachable from `paths` or `webhooks`
+//! through local `$ref`s (following references inside referenced components
+//! too), through a discriminator `mapping`, or, for security schemes,
+//! through a top-level or operation `security` requirement.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value;
+
+/// Component names by kind (`schemas`, `parameters`, ...), sorted.
+pub type ComponentNames = BTreeMap<String, Vec<String>>;
+
+/// The components reachable from the document's paths and webhooks.
+pub fn reachable_components(doc: &Value) -> BTreeSet<(String, String)> {
+    let mut reached = BTreeSet::new();
+    let mut pending = Vec::new();
+    for root in ["paths", "webhooks"] {
+        if let Some(value) = doc.get(root) {
+            collect_references(value, &mut pending);
+        }
+    }
+    if let Some(security) = doc.get("security") {
+        collect_security(security, &mut pending);
+    }
+    while let Some(key) = pending.pop() {
+        if reached.contains(&key) {
+            continue;
+        }
+        let (kind, name) = &key;
+        if let Some(component) = doc
+            .get("components")
+            .and_then(|components| components.get(kind))
+            .and_then(|group| group.get(name))
+        {
+            collect_references(component, &mut pending);
+        }
+        reached.insert(key);
+    }
+    reached
+}
+
+/// Components that [`reachable_components`] does not reach.
+pub fn unused_components(doc: &Value) -> ComponentNames {
+    let reached = reachable_components(doc);
+    let mut unused = ComponentNames::new();
+    let groups = doc.get("components").and_then(Value::as_object);
+    for (kind, group) in groups.into_iter().flatten() {
+        let Some(group) = group.as_object() else {
+            continue;
+        };
+        let names: Vec<String> = group
+            .keys()
+            .filter(|name| !reached.contains(&(kind.clone(), (*name).clone())))
+            .cloned()
+            .collect();
+        if !names.is_empty() {
+            unused.insert(kind.clone(), names);
+        }
+    }
+    unused
+}
+
+/// Removes unused components, and component groups left empty, returning
+/// what was removed.
+pub fn prune_components(doc: &mut Value) -> ComponentNames {
+    let unused = unused_components(doc);
+    if let Some(groups) = doc.get_mut("components").and_then(Value::as_object_mut) {
+        for (kind, names) in &unused {
+            if let Some(group) = groups.get_mut(kind).and_then(Value::as_object_mut) {
+                for name in names {
+                    group.remove(name);
+                }
+                if group.is_empty() {
+                    groups.remove(kind);
+                }
+            }
+        }
+    }
+    unused
+}
+
+/// `removed 3 unused components:` followed by one line per kind, or
+/// `no unused components`.
+pub fn format_prune_report(removed: &ComponentNames) -> Vec<String> {
+    let total: usize = removed.values().map(Vec::len).sum();
+    if total == 0 {
+        return vec!["no unused components".to_string()];
+    }
+    let noun = if total == 1 {
+        "component"
+    } else {
+        "components"
+    };
+    let mut lines = vec![format!("removed {total} unused {noun}:")];
+    lines.extend(
+        removed
+            .iter()
+            .map(|(kind, names)| format!("  {kind}: {}", names.join(", "))),
+    );
+    lines
+}
+
+/// Queues every component `value` references: `$ref`s, discriminator
+/// mappings, and operation-level security requirements.
+fn collect_references(value: &Value, pending: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(target) = object.get("$ref").and_then(Value::as_str)
+                && let Some(key) = component_key(target)
+            {
+                pending.push(key);
+            }
+            if let Some(mapping) = object
+                .get("discriminator")
+                .and_then(|discriminator| discriminator.get("mapping"))
+                .and_then(Value::as_object)
+            {
+                pending.extend(
+                    mapping
+                        .values()
+                        .filter_map(Value::as_str)
+                        .filter_map(component_key),
+                );
+            }
+            if let Some(security) = object.get("security") {
+                collect_security(security, pending);
+            }
+            for child in object.values() {
+                collect_references(child, pending);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_references(item, pending);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Security requirements name schemes directly rather than by `$ref`.
+fn collect_security(security: &Value, pending: &mut Vec<(String, String)>) {
+    let requirements = security.as_array().into_iter().flatten();
+    for requirement in requirements.filter_map(Value::as_object) {
+        pending.extend(
+            requirement
+                .keys()
+                .map(|name| ("securitySchemes".to_string(), name.clone())),
+        );
+    }
+}
+
+/// `("schemas", "User")` for `#/components/schemas/User` or anything inside
+/// it, such as `#/components/schemas/User/properties/id`.
+fn component_key(target: &str) -> Option<(String, String)> {
+    let mut segments = target.strip_prefix("#/components/")?.split('/');
+    let kind = segments.next()?;
+    let name = segments.next()?;
+    Some((kind.to_string(), name.replace("~1", "/").replace("~0", "~")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture() -> Value {
+        serde_json::from_str(include_str!("../tests/fixtures/prune/openapi.json")).unwrap()
+    }
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn follows_refs_inside_refs_and_survives_cycles() {
+        let reached = reachable_components(&fixture());
+        for (kind, name) in [
+            ("schemas", "User"),
+            ("schemas", "Team"),
+            ("schemas", "Address"),
+            ("schemas", "Cat"),
+            ("parameters", "UserId"),
+            ("responses", "NotFound"),
+            ("schemas", "Error"),
+            ("requestBodies", "UserBody"),
+            ("headers", "RateLimit"),
+            ("securitySchemes", "bearer"),
+            ("securitySchemes", "apiKey"),
+        ] {
+            assert!(
+                reached.contains(&(kind.to_string(), name.to_string())),
+                "{kind}.{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn prune_removes_unreachable_components_and_empty_groups() {
+        let mut doc = fixture();
+        let removed = prune_components(&mut doc);
+        assert_eq!(
+            removed,
+            ComponentNames::from([
+                ("examples".to_string(), names(&["Old"])),
+                ("parameters".to_string(), names(&["Legacy"])),
+                ("schemas".to_string(), names(&["Orphan", "OrphanChild"])),
+                ("securitySchemes".to_string(), names(&["basic"])),
+            ])
+        );
+        assert!(doc["components"].get("examples").is_none());
+        assert!(doc["components"]["schemas"].get("User").is_some());
+        assert!(unused_components(&doc).is_empty());
+    }
+
+    #[test]
+    fn report_groups_removed_entries_by_kind() {
+        let removed = prune_components(&mut fixture());
+        assert_eq!(
+            format_prune_report(&removed),
+            vec![
+                "removed 5 unused components:",
+                "  examples: Old",
+                "  parameters: Legacy",
+                "  schemas: Orphan, OrphanChild",
+                "  securitySchemes: basic",
+            ]
+        );
+        assert_eq!(
+            format_prune_report(&ComponentNames::new()),
+            vec!["no unused components"]
+        );
+    }
+
+    #[test]
+    fn component_key_handles_nested_and_escaped_pointers() {
+        assert_eq!(
+            component_key("#/components/schemas/User/properties/id"),
+            Some(("schemas".to_string(), "User".to_string()))
+        );
+        assert_eq!(
+            component_key("#/components/schemas/a~1b"),
+            Some(("schemas".to_string(), "a/b".to_string()))
+        );
+        assert_eq!(component_key("other.json#/components/schemas/User"), None);
+        assert_eq!(component_key("#/paths/~1users"), None);
+        assert!(reachable_components(&json!({})).is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::validate::escape;
+
+/// A `$ref`, located by a JSON pointer to the object that holds it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefSite {
+    pub location: String,
+    pub target: String,
+}
+
+impl fmt::Display for RefSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} $ref '{}'", self.location, self.target)
+    }
+}
+
+/// What [`check_refs`] found, each list in document order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RefReport {
+    /// Local refs whose target is missing from the document.
+    pub broken: Vec<RefSite>,
+    /// Refs to other documents or to plain-name anchors, which are not
+    /// checked.
+    pub unchecked: Vec<RefSite>,
+}
+
+/// Collects every `$ref` in `doc` and resolves each local `#/...` target as
+/// a JSON pointer, after percent-decoding it, against `doc` itself.
+pub fn check_refs(doc: &Value) -> RefReport {
+    let mut sites = Vec::new();
+    collect_refs(doc, "#".to_string(), &mut sites);
+    let mut report = RefReport::default();
+    for site in sites {
+        match local_pointer(&site.target) {
+            Some(pointer) if !resolves(doc, &pointer) => report.broken.push(site),
+            Some(_) => {}
+            None => report.unchecked.push(site),
+        }
+    }
+    report
+}
+
+/// The JSON pointer a local ref (`#` or `#/...`) names, or `None` for any
+/// other ref.
+fn local_pointer(target: &str) -> Option<String> {
+    let fragment = target.strip_prefix('#')?;
+    if !fragment.is_empty() && !fragment.starts_with('/') {
+        return None;
+    }
+    Some(percent_decode(fragment))
+}
+
+/// Whether `pointer` names a value in `doc`. A `~` must start `~0` or
+/// `~1`; serde_json would otherwise take it literally.
+fn resolves(doc: &Value, pointer: &str) -> bool {
+    let valid_escapes = pointer
+        .split('~')
+        .skip(1)
+        .all(|rest| rest.starts_with(['0', '1']));
+    valid_escapes && doc.pointer(pointer).is_some()
+}
+
+/// Decodes `%XX` escapes, as URI fragments allow; malformed escapes are
+/// kept as they are.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| text.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn collect_refs(value: &Value, location: String, sites: &mut Vec<RefSite>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::String(target)) = object.get("$ref") {
+                sites.push(RefSite {
+                    location: location.clone(),
+                    target: target.clone(),
+                });
+            }
+            for (key, child) in object {
+                collect_refs(child, format!("{location}/{}", escape(key)), sites);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                collect_refs(child, format!("{location}/{index}"), sites);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sites(sites: &[RefSite]) -> Vec<String> {
+        sites.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn nested_refs_and_refs_inside_arrays_are_resolved() {
+        let doc = json!({
+            "paths": {"/users": {"get": {
+                "parameters": [{"$ref": "#/components/parameters/Limit"}, {"$ref": "#/components/parameters/Page"}],
+                "responses": {"200": {"content": {"application/json": {"schema": {
+                    "type": "array",
+                    "items": {"oneOf": [{"$ref": "#/components/schemas/User"}, {"$ref": "#/components/schemas/Admin"}]}
+                }}}}}
+            }}},
+            "components": {
+                "parameters": {"Limit": {"name": "limit", "in": "query"}},
+                "schemas": {
+                    "User": {"properties": {"team": {"$ref": "#/components/schemas/Team"}}},
+                    "Admin": {"$ref": "#"}
+                }
+            }
+        });
+        let report = check_refs(&doc);
+        assert_eq!(
+            sites(&report.broken),
+            vec![
+                "#/components/schemas/User/properties/team $ref '#/components/schemas/Team'",
+                "#/paths/~1users/get/parameters/1 $ref '#/components/parameters/Page'",
+            ]
+        );
+        assert!(report.unchecked.is_empty());
+    }
+
+    #[test]
+    fn escaped_and_percent_encoded_segments_are_decoded() {
+        let doc = json!({
+            "paths": {"/users/{id}": {"get": {"responses": {}}}},
+            "components": {"schemas": {"a~b": {}}},
+            "x-refs": [
+                {"$ref": "#/paths/~1users~1{id}/get"},
+                {"$ref": "#/paths/~1users~1%7Bid%7D/get"},
+                {"$ref": "#/components/schemas/a~0b"},
+                {"$ref": "#/components/schemas/a~b"},
+                {"$ref": "#/paths/~1teams/get"}
+            ]
+        });
+        assert_eq!(
+            sites(&check_refs(&doc).broken),
+            vec![
+                "#/x-refs/3 $ref '#/components/schemas/a~b'",
+                "#/x-refs/4 $ref '#/paths/~1teams/get'",
+            ]
+        );
+    }
+
+    #[test]
+    fn external_refs_and_anchors_are_listed_as_unchecked() {
+        let doc = json!({"x-refs": [
+            {"$ref": "https://example.com/errors.json#/NotFound"},
+            {"$ref": "common.json"},
+            {"$ref": "#user"}
+        ]});
+        let report = check_refs(&doc);
+        assert!(report.broken.is_empty());
+        assert_eq!(
+            sites(&report.unchecked),
+            vec![
+                "#/x-refs/0 $ref 'https://example.com/errors.json#/NotFound'",
+                "#/x-refs/1 $ref 'common.json'",
+                "#/x-refs/2 $ref '#user'",
+            ]
+        );
+    }
+}
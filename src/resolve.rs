@@ -0,0 +1,346 @@
+//! Transitive expansion of local `$ref`s, shared by `show --resolve=deep`
+//! and `schema`.
+
+use serde_json::{Map, Value};
+
+use crate::errors::AppError;
+use crate::show::find_schema;
+
+const JSON_SCHEMA_DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// A value with its local `$ref`s expanded, and the reference cycles that
+/// were left in place as `$ref`s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolved {
+    pub value: Value,
+    /// Each cycle as the chain of references that closes it, e.g.
+    /// `[".../Node", ".../Child", ".../Node"]`.
+    pub cycles: Vec<Vec<String>>,
+}
+
+/// `components.schemas.<name>` with every local `$ref` expanded, as a
+/// standalone JSON Schema document when `json_schema_draft` is set.
+pub fn resolve_schema(
+    doc: &Value,
+    name: &str,
+    json_schema_draft: bool,
+) -> Result<Resolved, AppError> {
+    let schema = find_schema(doc, name)?;
+    let origin = format!(
+        "#/components/schemas/{}",
+        name.replace('~', "~0").replace('/', "~1")
+    );
+    let resolved = resolve_refs(doc, schema, Some(&origin));
+    Ok(if json_schema_draft {
+        json_schema_document(doc, resolved, &origin)
+    } else {
+        resolved
+    })
+}
+
+/// Expands every local `$ref` in `value`, following references inside the
+/// targets too. A reference to something already being expanded, including
+/// `origin` (the reference `value` itself was reached through), stays a
+/// `$ref` and is reported as a cycle. External and dangling references are
+/// left alone.
+pub fn resolve_refs(doc: &Value, value: &Value, origin: Option<&str>) -> Resolved {
+    let mut resolver = Resolver {
+        doc,
+        stack: origin.map(str::to_string).into_iter().collect(),
+        cycles: Vec::new(),
+    };
+    let value = resolver.expand(value);
+    Resolved {
+        value,
+        cycles: resolver.cycles,
+    }
+}
+
+/// Turns a resolved component schema into a standalone JSON Schema
+/// document: adds `$schema`, copies the targets of the references left by
+/// cycles into `$defs`, and points those references at `$defs` (or at the
+/// root for the schema itself).
+pub fn json_schema_document(doc: &Value, resolved: Resolved, origin: &str) -> Resolved {
+    let Resolved {
+        mut value,
+        mut cycles,
+    } = resolved;
+    let mut defs = Map::new();
+    let mut moved = vec![origin.to_string()];
+    let mut pending = remaining_refs(&value);
+    while let Some(target) = pending.pop() {
+        let name = def_name(&target);
+        if target == origin || defs.contains_key(&name) {
+            continue;
+        }
+        let Some(schema) = target
+            .strip_prefix('#')
+            .and_then(|pointer| doc.pointer(pointer))
+        else {
+            continue;
+        };
+        let def = resolve_refs(doc, schema, Some(&target));
+        pending.extend(remaining_refs(&def.value));
+        for cycle in def.cycles {
+            if !cycles.contains(&cycle) {
+                cycles.push(cycle);
+            }
+        }
+        defs.insert(name, def.value);
+        moved.push(target);
+    }
+    for def in defs.values_mut() {
+        point_refs_at_defs(def, &moved);
+    }
+    point_refs_at_defs(&mut value, &moved);
+    if let Value::Object(object) = &mut value {
+        let mut document = Map::new();
+        document.insert(
+            "$schema".to_string(),
+            Value::String(JSON_SCHEMA_DRAFT.to_string()),
+        );
+        document.append(object);
+        if !defs.is_empty() {
+            document.insert("$defs".to_string(), Value::Object(defs));
+        }
+        *object = document;
+    }
+    Resolved { value, cycles }
+}
+
+/// `Node -> Child -> Node`, naming each reference by its last segment.
+pub fn format_cycle(cycle: &[String]) -> String {
+    cycle
+        .iter()
+        .map(|target| def_name(target))
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+struct Resolver<'a> {
+    doc: &'a Value,
+    /// References being expanded, outermost first.
+    stack: Vec<String>,
+    cycles: Vec<Vec<String>>,
+}
+
+impl Resolver<'_> {
+    fn expand(&mut self, value: &Value) -> Value {
+        match value {
+            Value::Object(object) => {
+                if let Some(target) = object.get("$ref").and_then(Value::as_str)
+                    && let Some(resolved) = target
+                        .strip_prefix('#')
+                        .and_then(|pointer| self.doc.pointer(pointer))
+                {
+                    return self.follow(target, resolved, value);
+                }
+                Value::Object(
+                    object
+                        .iter()
+                        .map(|(key, child)| (key.clone(), self.expand(child)))
+                        .collect(),
+                )
+            }
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|item| self.expand(item)).collect())
+            }
+            other => other.clone(),
+        }
+    }
+
+    fn follow(&mut self, target: &str, resolved: &Value, reference: &Value) -> Value {
+        if let Some(start) = self.stack.iter().position(|seen| seen == target) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(target.to_string());
+            if !self.cycles.contains(&cycle) {
+                self.cycles.push(cycle);
+            }
+            return reference.clone();
+        }
+        self.stack.push(target.to_string());
+        let expanded = self.expand(resolved);
+        self.stack.pop();
+        expanded
+    }
+}
+
+/// Local references still present in `value`, in document order.
+fn remaining_refs(value: &Value) -> Vec<String> {
+    let mut refs = Vec::new();
+    collect_refs(value, &mut refs);
+    refs.reverse();
+    refs
+}
+
+fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(target) = object.get("$ref").and_then(Value::as_str)
+                && target.starts_with('#')
+            {
+                refs.push(target.to_string());
+            }
+            object.values().for_each(|child| collect_refs(child, refs));
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_refs(item, refs)),
+        _ => {}
+    }
+}
+
+/// Rewrites references to `moved` targets: the first (the document's own
+/// schema) becomes `#`, the rest point into `$defs`.
+fn point_refs_at_defs(value: &mut Value, moved: &[String]) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::String(target)) = object.get_mut("$ref")
+                && let Some(index) = moved.iter().position(|seen| seen == target)
+            {
+                *target = if index == 0 {
+                    "#".to_string()
+                } else {
+                    format!(
+                        "#/$defs/{}",
+                        def_name(target).replace('~', "~0").replace('/', "~1")
+                    )
+                };
+            }
+            object
+                .values_mut()
+                .for_each(|child| point_refs_at_defs(child, moved));
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| point_refs_at_defs(item, moved)),
+        _ => {}
+    }
+}
+
+/// The unescaped last segment of a JSON pointer reference.
+fn def_name(target: &str) -> String {
+    target
+        .rsplit('/')
+        .next()
+        .unwrap_or(target)
+        .replace("~1", "/")
+        .replace("~0", "~")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// `Person.employer` and `Company.owner` reference each other.
+    fn cyclic() -> Value {
+        json!({"components": {"schemas": {
+            "Person": {
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "address": {"$ref": "#/components/schemas/Address"},
+                    "employer": {"$ref": "#/components/schemas/Company"}
+                }
+            },
+            "Company": {
+                "type": "object",
+                "properties": {
+                    "owner": {"$ref": "#/components/schemas/Person"},
+                    "external": {"$ref": "other.json#/Thing"}
+                }
+            },
+            "Address": {"type": "object", "properties": {"city": {"type": "string"}}}
+        }}})
+    }
+
+    #[test]
+    fn expands_references_transitively() {
+        let resolved = resolve_schema(&cyclic(), "Address", false).unwrap();
+        assert_eq!(
+            resolved.value,
+            json!({"type": "object", "properties": {"city": {"type": "string"}}})
+        );
+        assert!(resolved.cycles.is_empty());
+    }
+
+    #[test]
+    fn cyclic_pair_stays_a_ref_and_is_reported() {
+        let resolved = resolve_schema(&cyclic(), "Person", false).unwrap();
+        let employer = &resolved.value["properties"]["employer"];
+        assert_eq!(
+            employer["properties"]["owner"],
+            json!({"$ref": "#/components/schemas/Person"})
+        );
+        assert_eq!(
+            employer["properties"]["external"],
+            json!({"$ref": "other.json#/Thing"})
+        );
+        assert_eq!(
+            resolved.value["properties"]["address"]["properties"]["city"],
+            json!({"type": "string"})
+        );
+        assert_eq!(
+            resolved.cycles,
+            vec![vec![
+                "#/components/schemas/Person".to_string(),
+                "#/components/schemas/Company".to_string(),
+                "#/components/schemas/Person".to_string(),
+            ]]
+        );
+        assert_eq!(
+            format_cycle(&resolved.cycles[0]),
+            "Person -> Company -> Person"
+        );
+    }
+
+    #[test]
+    fn without_origin_a_cycle_is_expanded_once() {
+        let doc = cyclic();
+        let person = doc.pointer("/components/schemas/Person").unwrap();
+        let resolved = resolve_refs(&doc, person, None);
+        assert_eq!(
+            resolved.value["properties"]["employer"]["properties"]["owner"]["properties"]["employer"],
+            json!({"$ref": "#/components/schemas/Company"})
+        );
+        assert_eq!(
+            format_cycle(&resolved.cycles[0]),
+            "Company -> Person -> Company"
+        );
+    }
+
+    #[test]
+    fn json_schema_draft_points_cycles_at_the_root_and_defs() {
+        let resolved = resolve_schema(&cyclic(), "Person", true).unwrap();
+        assert_eq!(resolved.value["$schema"], json!(JSON_SCHEMA_DRAFT));
+        assert_eq!(
+            resolved.value["properties"]["employer"]["properties"]["owner"],
+            json!({"$ref": "#"})
+        );
+        assert!(resolved.value.get("$defs").is_none());
+
+        let resolved = resolve_schema(&cyclic(), "Company", true).unwrap();
+        assert_eq!(
+            resolved.value["properties"]["owner"]["properties"]["employer"],
+            json!({"$ref": "#"})
+        );
+
+        let mut doc = cyclic();
+        doc["components"]["schemas"]["Team"] = json!({
+            "type": "array",
+            "items": {"$ref": "#/components/schemas/Person"}
+        });
+        let resolved = resolve_schema(&doc, "Team", true).unwrap();
+        // Person -> Company -> Person never passes back through Team, so
+        // Person becomes a definition of its own.
+        assert_eq!(
+            resolved.value["items"]["properties"]["employer"]["properties"]["owner"],
+            json!({"$ref": "#/$defs/Person"})
+        );
+        let person = &resolved.value["$defs"]["Person"];
+        assert_eq!(
+            person["properties"]["employer"]["properties"]["owner"],
+            json!({"$ref": "#/$defs/Person"})
+        );
+        assert_eq!(resolved.cycles.len(), 1);
+    }
+}
@@ -0,0 +1,266 @@
+//! Case-insensitive search over the names and prose of a document.
+
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::cli::SearchScope;
+use crate::errors::AppError;
+use crate::outline::is_http_method;
+
+/// Text fields of an operation that are searched, in output order.
+const OPERATION_FIELDS: [&str; 3] = ["operationId", "summary", "description"];
+
+/// A substring (the default) or a regular expression, both ignoring case.
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, regex: bool) -> Result<Self, AppError> {
+        if !regex {
+            return Ok(Matcher::Substring(pattern.to_lowercase()));
+        }
+        RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map(Matcher::Regex)
+            .map_err(|err| AppError::usage(format!("invalid --regex pattern: {err}")))
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Substring(needle) => text.to_lowercase().contains(needle),
+            Matcher::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// One match: where it is, what kind of field it is, and the matched text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hit {
+    pub location: String,
+    pub kind: &'static str,
+    pub value: String,
+}
+
+impl std::fmt::Display for Hit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}",
+            self.location,
+            Value::String(self.value.clone())
+        )
+    }
+}
+
+/// Every match in `scope`, in document order: paths (sorted) with their
+/// operations, then top-level tags, then component schemas (sorted).
+/// Besides names and prose, `$ref` targets are searched so a schema name
+/// also finds the operations and schemas that use it.
+pub fn search(doc: &Value, matcher: &Matcher, scope: SearchScope) -> Vec<Hit> {
+    let mut hits = Hits {
+        matcher,
+        found: Vec::new(),
+    };
+    if scope != SearchScope::Schemas {
+        search_paths(doc, &mut hits);
+    }
+    if scope != SearchScope::Paths {
+        search_schemas(doc, &mut hits);
+    }
+    hits.found
+}
+
+struct Hits<'a> {
+    matcher: &'a Matcher,
+    found: Vec<Hit>,
+}
+
+impl Hits<'_> {
+    fn check(&mut self, location: String, kind: &'static str, value: &str) {
+        if self.matcher.is_match(value) {
+            self.found.push(Hit {
+                location,
+                kind,
+                value: value.to_string(),
+            });
+        }
+    }
+
+    /// Searches `$ref` targets anywhere under `value`.
+    fn refs(&mut self, location: &str, value: &Value) {
+        match value {
+            Value::Object(object) => {
+                for (key, child) in object {
+                    let location = format!("{location}.{key}");
+                    match child.as_str() {
+                        Some(target) if key == "$ref" => self.check(location, "ref", target),
+                        _ => self.refs(&location, child),
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.refs(&format!("{location}[{index}]"), item);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn search_paths(doc: &Value, hits: &mut Hits) {
+    let paths = doc.get("paths").and_then(Value::as_object);
+    for (path, item) in paths.into_iter().flatten() {
+        hits.check(format!("paths.{path}"), "path", path);
+        let operations = item.as_object().into_iter().flatten();
+        for (method, op) in operations.filter(|(method, _)| is_http_method(method)) {
+            search_operation(&format!("paths.{path}.{method}"), op, hits);
+        }
+    }
+    let tags = doc.get("tags").and_then(Value::as_array);
+    for (index, tag) in tags.into_iter().flatten().enumerate() {
+        if let Some(name) = tag.get("name").and_then(Value::as_str) {
+            hits.check(format!("tags[{index}].name"), "tag", name);
+        }
+    }
+}
+
+fn search_operation(location: &str, op: &Value, hits: &mut Hits) {
+    for field in OPERATION_FIELDS {
+        if let Some(text) = op.get(field).and_then(Value::as_str) {
+            hits.check(format!("{location}.{field}"), field, text);
+        }
+    }
+    let tags = op.get("tags").and_then(Value::as_array);
+    for (index, tag) in tags.into_iter().flatten().enumerate() {
+        if let Some(tag) = tag.as_str() {
+            hits.check(format!("{location}.tags[{index}]"), "tag", tag);
+        }
+    }
+    for section in ["parameters", "requestBody", "responses"] {
+        if let Some(value) = op.get(section) {
+            hits.refs(&format!("{location}.{section}"), value);
+        }
+    }
+}
+
+fn search_schemas(doc: &Value, hits: &mut Hits) {
+    let schemas = doc
+        .pointer("/components/schemas")
+        .and_then(Value::as_object);
+    for (name, schema) in schemas.into_iter().flatten() {
+        let location = format!("components.schemas.{name}");
+        hits.check(location.clone(), "schema", name);
+        search_properties(&location, schema, hits);
+        hits.refs(&location, schema);
+    }
+}
+
+/// Property names at any depth: nested objects, array items, and
+/// compositions.
+fn search_properties(location: &str, schema: &Value, hits: &mut Hits) {
+    let Some(object) = schema.as_object() else {
+        return;
+    };
+    for (key, child) in object {
+        let child_location = format!("{location}.{key}");
+        match (key.as_str(), child) {
+            ("properties", Value::Object(properties)) => {
+                for (name, property) in properties {
+                    let property_location = format!("{child_location}.{name}");
+                    hits.check(property_location.clone(), "property", name);
+                    search_properties(&property_location, property, hits);
+                }
+            }
+            (_, Value::Array(items)) => {
+                for (index, item) in items.iter().enumerate() {
+                    search_properties(&format!("{child_location}[{index}]"), item, hits);
+                }
+            }
+            (_, Value::Object(_)) => search_properties(&child_location, child, hits),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Value {
+        serde_json::from_str(include_str!("../tests/fixtures/search/openapi.json")).unwrap()
+    }
+
+    fn lines(pattern: &str, regex: bool, scope: SearchScope) -> Vec<String> {
+        let matcher = Matcher::new(pattern, regex).unwrap();
+        search(&fixture(), &matcher, scope)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn finds_every_location_in_document_order() {
+        let expected: Vec<String> = include_str!("../tests/fixtures/search/invoice.txt")
+            .lines()
+            .map(str::to_string)
+            .collect();
+        assert_eq!(lines("INVOICE", false, SearchScope::All), expected);
+    }
+
+    #[test]
+    fn scope_limits_the_search() {
+        let paths = lines("invoice", false, SearchScope::Paths);
+        assert!(paths.iter().all(|line| !line.starts_with("components.")));
+        assert!(!paths.is_empty());
+        let schemas = lines("invoice", false, SearchScope::Schemas);
+        assert!(schemas.iter().all(|line| line.starts_with("components.")));
+        assert!(!schemas.is_empty());
+    }
+
+    #[test]
+    fn finds_operations_returning_a_schema() {
+        assert_eq!(
+            lines("lineitem", false, SearchScope::Paths),
+            vec![
+                "paths./invoices/{id}/lines.get.responses.200.content.application/json.schema.items.$ref: \"#/components/schemas/InvoiceLineItem\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn regex_matches_ignore_case() {
+        assert_eq!(
+            lines("^list", true, SearchScope::All),
+            vec![
+                "paths./invoices.get.operationId: \"listInvoices\"",
+                "paths./invoices.get.summary: \"List invoices\"",
+                "paths./invoices/{id}/lines.get.summary: \"List the lines of an invoice\"",
+            ]
+        );
+        let err = Matcher::new("(", true).unwrap_err();
+        assert!(
+            err.to_string().starts_with("invalid --regex pattern:"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn hits_serialize_with_kind() {
+        let matcher = Matcher::new("amount", false).unwrap();
+        let hits = search(&fixture(), &matcher, SearchScope::Schemas);
+        assert_eq!(
+            serde_json::to_value(&hits[0]).unwrap(),
+            serde_json::json!({
+                "location": "components.schemas.InvoiceLineItem.properties.amount",
+                "kind": "property",
+                "value": "amount"
+            })
+        );
+    }
+}
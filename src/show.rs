@@ -0,0 +1,293 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::cli::ResolveDepth;
+use crate::errors::AppError;
+use crate::outline::is_http_method;
+use crate::resolve::resolve_refs;
+
+const MAX_SUGGESTIONS: usize = 3;
+
+/// What `show` prints: one operation or one component schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Selector {
+    Operation { method: String, path: String },
+    Schema(String),
+}
+
+impl Selector {
+    /// Reads `GET /users/{id}` or `schema User`. Methods are case-insensitive
+    /// and stored lower-case; paths and schema names match literally.
+    pub fn parse(kind: &str, target: &str) -> Result<Self, AppError> {
+        if kind.eq_ignore_ascii_case("schema") {
+            return Ok(Selector::Schema(target.to_string()));
+        }
+        let method = kind.to_ascii_lowercase();
+        if !is_http_method(&method) {
+            return Err(AppError::usage(format!(
+                "expected an HTTP method or 'schema', got '{kind}'."
+            )));
+        }
+        Ok(Selector::Operation {
+            method,
+            path: target.to_string(),
+        })
+    }
+}
+
+/// Finds the selected operation or schema in `doc`, inlining local `$ref`s
+/// when `resolve` is set. A miss is a usage error naming close matches.
+pub fn show(
+    doc: &Value,
+    selector: &Selector,
+    resolve: Option<ResolveDepth>,
+) -> Result<Value, AppError> {
+    let found = match selector {
+        Selector::Operation { method, path } => find_operation(doc, method, path)?,
+        Selector::Schema(name) => find_schema(doc, name)?,
+    };
+    Ok(match resolve {
+        None => found.clone(),
+        Some(ResolveDepth::Shallow) => inline_refs(doc, found),
+        Some(ResolveDepth::Deep) => resolve_refs(doc, found, None).value,
+    })
+}
+
+pub(crate) fn find_operation<'a>(
+    doc: &'a Value,
+    method: &str,
+    path: &str,
+) -> Result<&'a Value, AppError> {
+    let paths = doc.get("paths").and_then(Value::as_object);
+    let label = format!("{} {path}", method.to_ascii_uppercase());
+    let Some(item) = paths
+        .and_then(|paths| paths.get(path))
+        .and_then(Value::as_object)
+    else {
+        let hint = suggestions(path, paths.into_iter().flat_map(Map::keys));
+        return Err(AppError::usage(format!("no operation {label}{hint}")));
+    };
+    if let Some(op) = item.get(method) {
+        return Ok(op);
+    }
+    let methods: Vec<String> = item
+        .keys()
+        .filter(|key| is_http_method(key))
+        .map(|key| key.to_ascii_uppercase())
+        .collect();
+    let hint = if methods.is_empty() {
+        ".".to_string()
+    } else {
+        format!("; {path} has {}.", methods.join(", "))
+    };
+    Err(AppError::usage(format!("no operation {label}{hint}")))
+}
+
+pub(crate) fn find_schema<'a>(doc: &'a Value, name: &str) -> Result<&'a Value, AppError> {
+    let schemas = doc
+        .pointer("/components/schemas")
+        .and_then(Value::as_object);
+    schemas
+        .and_then(|schemas| schemas.get(name))
+        .ok_or_else(|| {
+            let hint = suggestions(name, schemas.into_iter().flat_map(Map::keys));
+            AppError::usage(format!("no schema '{name}'{hint}"))
+        })
+}
+
+/// Replaces local `$ref` objects with their targets, one level deep; the
+/// targets keep their own references. [`resolve_refs`] goes all the way.
+fn inline_refs(doc: &Value, value: &Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            if let Some(resolved) = object
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|target| target.strip_prefix('#'))
+                .and_then(|pointer| doc.pointer(pointer))
+            {
+                return resolved.clone();
+            }
+            Value::Object(
+                object
+                    .iter()
+                    .map(|(key, child)| (key.clone(), inline_refs(doc, child)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| inline_refs(doc, item)).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+/// "; did you mean a, b?" for the candidates closest to `wanted`, or ".".
+fn suggestions<'a>(wanted: &str, candidates: impl Iterator<Item = &'a String>) -> String {
+    let wanted_lower = wanted.to_lowercase();
+    let limit = (wanted.len() / 3).max(2);
+    let mut close: Vec<(usize, &str)> = candidates
+        .filter_map(|candidate| {
+            let lower = candidate.to_lowercase();
+            let distance = edit_distance(&wanted_lower, &lower);
+            let related = lower.contains(&wanted_lower) || wanted_lower.contains(&lower);
+            (distance <= limit || related).then_some((distance, candidate.as_str()))
+        })
+        .collect();
+    close.sort();
+    if close.is_empty() {
+        return ".".to_string();
+    }
+    let names: Vec<&str> = close
+        .iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| *name)
+        .collect();
+    format!("; did you mean {}?", names.join(", "))
+}
+
+/// Levenshtein distance over characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture() -> Value {
+        json!({
+            "paths": {
+                "/users/{id}": {
+                    "get": {
+                        "responses": {"200": {"$ref": "#/components/responses/UserResponse"}}
+                    },
+                    "delete": {"responses": {"204": {}}}
+                },
+                "/users": {"post": {"responses": {}}}
+            },
+            "components": {
+                "responses": {
+                    "UserResponse": {
+                        "content": {"application/json": {"schema": {"$ref": "#/components/schemas/User"}}}
+                    }
+                },
+                "schemas": {
+                    "User": {
+                        "properties": {
+                            "team": {"$ref": "#/components/schemas/Team"},
+                            "manager": {"$ref": "#/components/schemas/User"}
+                        }
+                    },
+                    "Team": {"type": "object"},
+                    "UserList": {"items": {"$ref": "#/components/schemas/User"}}
+                }
+            }
+        })
+    }
+
+    fn select(kind: &str, target: &str) -> Selector {
+        Selector::parse(kind, target).unwrap()
+    }
+
+    #[test]
+    fn parse_accepts_methods_in_any_case_and_schemas() {
+        assert_eq!(
+            select("GET", "/users/{id}"),
+            Selector::Operation {
+                method: "get".to_string(),
+                path: "/users/{id}".to_string()
+            }
+        );
+        assert_eq!(
+            select("Schema", "User"),
+            Selector::Schema("User".to_string())
+        );
+        let err = Selector::parse("FETCH", "/users").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected an HTTP method or 'schema', got 'FETCH'."
+        );
+    }
+
+    #[test]
+    fn show_finds_operations_and_schemas_literally() {
+        let doc = fixture();
+        let op = show(&doc, &select("delete", "/users/{id}"), None).unwrap();
+        assert_eq!(op, json!({"responses": {"204": {}}}));
+        let schema = show(&doc, &select("schema", "Team"), None).unwrap();
+        assert_eq!(schema, json!({"type": "object"}));
+    }
+
+    #[test]
+    fn resolve_inlines_one_level_or_everything() {
+        let doc = fixture();
+        let shallow = show(
+            &doc,
+            &select("get", "/users/{id}"),
+            Some(ResolveDepth::Shallow),
+        )
+        .unwrap();
+        assert_eq!(
+            shallow["responses"]["200"]["content"]["application/json"]["schema"],
+            json!({"$ref": "#/components/schemas/User"})
+        );
+
+        let deep = show(
+            &doc,
+            &select("schema", "UserList"),
+            Some(ResolveDepth::Deep),
+        )
+        .unwrap();
+        assert_eq!(
+            deep["items"]["properties"]["team"],
+            json!({"type": "object"})
+        );
+        assert_eq!(
+            deep["items"]["properties"]["manager"],
+            json!({"$ref": "#/components/schemas/User"})
+        );
+    }
+
+    #[test]
+    fn misses_suggest_close_matches() {
+        let doc = fixture();
+        let err = show(&doc, &select("put", "/users/{id}"), None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no operation PUT /users/{id}; /users/{id} has DELETE, GET."
+        );
+
+        let err = show(&doc, &select("get", "/user/{id}"), None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no operation GET /user/{id}; did you mean /users/{id}?"
+        );
+
+        let err = show(&doc, &select("schema", "user"), None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no schema 'user'; did you mean User, UserList?"
+        );
+
+        let err = show(&doc, &select("schema", "Invoice"), None).unwrap_err();
+        assert_eq!(err.to_string(), "no schema 'Invoice'.");
+    }
+}
@@ -0,0 +1,258 @@
+//! The `slim` profile: a document that is still valid OpenAPI, minus the
+//! prose, examples, and vendor extensions, with unused components pruned.
+
+use serde_json::{Map, Value};
+
+use crate::prune::prune_components;
+
+/// Documentation-only keys. Response objects keep an empty `description`
+/// because the specification requires one.
+const DOC_KEYS: [&str; 4] = ["description", "summary", "example", "examples"];
+
+/// Keys whose object values are keyed by user-chosen names (paths, property
+/// names, media types, ...), which are never stripped.
+const NAME_MAPS: [&str; 15] = [
+    "paths",
+    "webhooks",
+    "properties",
+    "patternProperties",
+    "dependentSchemas",
+    "$defs",
+    "definitions",
+    "callbacks",
+    "content",
+    "encoding",
+    "headers",
+    "links",
+    "mapping",
+    "scopes",
+    "variables",
+];
+
+/// Keys holding literal values rather than spec objects; a `default`
+/// object with a `description` field is data and stays intact.
+const LITERAL_KEYS: [&str; 3] = ["default", "enum", "const"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    /// A spec object, whose documentation keys are stripped.
+    Node,
+    /// A response object, which keeps an empty `description`.
+    Response,
+    /// A map from user-chosen names to spec objects.
+    Names(Entry),
+    /// The `components` object: a map from kind to names.
+    Components,
+}
+
+/// What a name map holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    Node,
+    Response,
+}
+
+/// Strips documentation from `doc` and prunes the components it no longer
+/// references.
+pub fn slim_openapi(doc: &Value) -> Value {
+    let mut slim = doc.clone();
+    strip_documentation(&mut slim);
+    prune_components(&mut slim);
+    slim
+}
+
+/// Removes descriptions, summaries, examples, and `x-` extensions from every
+/// spec object, leaving names, schemas, and literal values alone.
+pub fn strip_documentation(doc: &mut Value) {
+    strip(doc, Context::Node);
+}
+
+fn strip(value: &mut Value, context: Context) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                strip(item, context);
+            }
+        }
+        Value::Object(object) => match context {
+            Context::Node | Context::Response => strip_node(object, context),
+            Context::Names(entry) => {
+                let child = match entry {
+                    Entry::Node => Context::Node,
+                    Entry::Response => Context::Response,
+                };
+                for item in object.values_mut() {
+                    strip(item, child);
+                }
+            }
+            Context::Components => {
+                for (kind, group) in object.iter_mut() {
+                    let entry = if kind == "responses" {
+                        Entry::Response
+                    } else {
+                        Entry::Node
+                    };
+                    strip(group, Context::Names(entry));
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+fn strip_node(object: &mut Map<String, Value>, context: Context) {
+    object.retain(|key, _| {
+        !key.starts_with("x-")
+            && (!DOC_KEYS.contains(&key.as_str())
+                || (context == Context::Response && key == "description"))
+    });
+    if context == Context::Response
+        && let Some(description) = object.get_mut("description")
+    {
+        *description = Value::String(String::new());
+    }
+    for (key, child) in object.iter_mut() {
+        let key = key.as_str();
+        if LITERAL_KEYS.contains(&key) {
+            continue;
+        }
+        let context = match key {
+            "components" => Context::Components,
+            "responses" => Context::Names(Entry::Response),
+            _ if NAME_MAPS.contains(&key) => Context::Names(Entry::Node),
+            _ => Context::Node,
+        };
+        strip(child, context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn strips_documentation_but_keeps_names_and_literals() {
+        let mut doc = json!({
+            "openapi": "3.0.3",
+            "info": {"title": "Users", "version": "1", "description": "Long prose", "x-logo": {}},
+            "x-tagGroups": [],
+            "tags": [{"name": "users", "description": "User things"}],
+            "paths": {
+                "/users": {
+                    "summary": "Users",
+                    "get": {
+                        "operationId": "listUsers",
+                        "summary": "List users",
+                        "x-internal": true,
+                        "parameters": [
+                            {"name": "limit", "in": "query", "example": 10, "schema": {"type": "integer"}}
+                        ],
+                        "responses": {
+                            "200": {
+                                "description": "The users",
+                                "content": {
+                                    "application/json": {
+                                        "examples": {"one": {"value": []}},
+                                        "schema": {"$ref": "#/components/schemas/User"}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "User": {
+                        "type": "object",
+                        "description": "A user",
+                        "properties": {
+                            "description": {"type": "string", "description": "Bio"},
+                            "x-id": {"type": "string"},
+                            "prefs": {"type": "object", "default": {"description": "keep"}}
+                        }
+                    }
+                },
+                "responses": {
+                    "NotFound": {"description": "Missing"}
+                }
+            }
+        });
+        strip_documentation(&mut doc);
+        assert_eq!(
+            doc,
+            json!({
+                "openapi": "3.0.3",
+                "info": {"title": "Users", "version": "1"},
+                "tags": [{"name": "users"}],
+                "paths": {
+                    "/users": {
+                        "get": {
+                            "operationId": "listUsers",
+                            "parameters": [
+                                {"name": "limit", "in": "query", "schema": {"type": "integer"}}
+                            ],
+                            "responses": {
+                                "200": {
+                                    "description": "",
+                                    "content": {
+                                        "application/json": {
+                                            "schema": {"$ref": "#/components/schemas/User"}
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                "components": {
+                    "schemas": {
+                        "User": {
+                            "type": "object",
+                            "properties": {
+                                "description": {"type": "string"},
+                                "x-id": {"type": "string"},
+                                "prefs": {"type": "object", "default": {"description": "keep"}}
+                            }
+                        }
+                    },
+                    "responses": {
+                        "NotFound": {"description": ""}
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn slim_prunes_components_only_documentation_used() {
+        let doc = json!({
+            "openapi": "3.0.3",
+            "info": {"title": "Users", "version": "1"},
+            "paths": {
+                "/users": {
+                    "get": {
+                        "responses": {
+                            "200": {
+                                "description": "ok",
+                                "content": {
+                                    "application/json": {
+                                        "examples": {"one": {"$ref": "#/components/examples/Users"}}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "examples": {"Users": {"value": []}},
+                "schemas": {"Unused": {"type": "string"}}
+            }
+        });
+        let slim = slim_openapi(&doc);
+        assert_eq!(slim["components"], json!({}));
+        assert_eq!(slim["info"], doc["info"]);
+    }
+}
@@ -0,0 +1,438 @@
+//! A builder for taking snapshots from code, with the command line's
+//! defaults, for build scripts and xtasks that would otherwise shell out to
+//! the binary.
+//!
+//! ```
+//! use openapi_snapshot::{OutputProfile, ReduceKey, Snapshot};
+//!
+//! let dir = std::env::temp_dir().join(format!("openapi-snapshot-builder-{}", std::process::id()));
+//! std::fs::create_dir_all(&dir)?;
+//! let input = dir.join("openapi.json");
+//! std::fs::write(&input, r#"{"openapi":"3.0.3","paths":{"/users":{}},"components":{}}"#)?;
+//!
+//! let snapshot = Snapshot::builder()
+//!     .input(&input)
+//!     .reduce([ReduceKey::Paths])
+//!     .build()?;
+//! let out = dir.join("snapshot.json");
+//! snapshot.write_to(&out)?;
+//! assert_eq!(std::fs::read_to_string(&out)?, "{\n  \"paths\": {\n    \"/users\": {}\n  }\n}");
+//!
+//! let outline = Snapshot::builder()
+//!     .input(&input)
+//!     .profile(OutputProfile::Outline)
+//!     .build()?
+//!     .fetch()?;
+//! assert!(outline.primary.contains("/users"));
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::cli::{
+    ColorChoice, DEFAULT_PATH, DEFAULT_PORT, DEFAULT_TIMEOUT_MS, OutlineCompat, OutputProfile,
+};
+use crate::config::{Config, ReduceKey, localhost_url, minify_output, validate_config};
+use crate::duration::duration_ms;
+use crate::errors::AppError;
+#[cfg(feature = "http")]
+use crate::fetch::build_headers;
+use crate::outline::OutlineOptions;
+use crate::output::{OutputPayloads, build_outputs, write_outputs};
+use crate::transform::{Pipeline, Transform};
+use crate::validate::ValidateOptions;
+use crate::verbosity::Verbosity;
+use crate::version::SpecVersion;
+
+/// A validated snapshot request. Build one with [`Snapshot::builder`].
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    config: Config,
+    minify: Option<bool>,
+}
+
+impl Snapshot {
+    /// A builder with the command line's defaults: the document at
+    /// `http://localhost:3000/api-docs/openapi.json`, the full profile, no
+    /// reduction, and a 10 second timeout.
+    pub fn builder() -> SnapshotBuilder {
+        SnapshotBuilder::default()
+    }
+
+    /// The resolved settings, as the command line would have produced them.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Fetches (or reads) and transforms the document without writing it.
+    pub fn fetch(&self) -> Result<OutputPayloads, AppError> {
+        build_outputs(&self.config)
+    }
+
+    /// Fetches the document and writes it atomically to `path`, minified
+    /// when the builder asked for it or the name ends in `.min.json`.
+    /// Like the command line, refuses to replace a snapshot that lists paths
+    /// with an empty one unless [`SnapshotBuilder::allow_empty`] is set.
+    pub fn write_to(&self, path: &Path) -> Result<OutputPayloads, AppError> {
+        let config = Config {
+            out: Some(path.to_path_buf()),
+            stdout: false,
+            minify: minify_output(self.minify, Some(path)),
+            ..self.config.clone()
+        };
+        validate_config(&config)?;
+        let outputs = build_outputs(&config)?;
+        write_outputs(&config, &outputs)?;
+        Ok(outputs)
+    }
+}
+
+/// Settings for a [`Snapshot`]; [`SnapshotBuilder::build`] validates them.
+#[derive(Debug, Clone)]
+pub struct SnapshotBuilder {
+    url: Option<String>,
+    input: Option<PathBuf>,
+    headers: Vec<String>,
+    reduce: Vec<ReduceKey>,
+    profile: OutputProfile,
+    outline: OutlineOptions,
+    timeout: Duration,
+    minify: Option<bool>,
+    validate: bool,
+    allow_empty: bool,
+    require_version: Option<SpecVersion>,
+    temp_dir: Option<PathBuf>,
+    verbosity: Verbosity,
+    transforms: Pipeline,
+}
+
+impl Default for SnapshotBuilder {
+    fn default() -> Self {
+        Self {
+            url: None,
+            input: None,
+            headers: Vec::new(),
+            reduce: Vec::new(),
+            profile: OutputProfile::Full,
+            outline: OutlineOptions::default(),
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+            minify: None,
+            validate: false,
+            allow_empty: false,
+            require_version: None,
+            temp_dir: None,
+            verbosity: Verbosity::Quiet,
+            transforms: Pipeline::default(),
+        }
+    }
+}
+
+impl SnapshotBuilder {
+    /// The http(s) URL to fetch the document from.
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// Reads a local JSON or YAML file instead of fetching a URL.
+    pub fn input(mut self, path: impl Into<PathBuf>) -> Self {
+        self.input = Some(path.into());
+        self
+    }
+
+    /// Adds a request header; may be called repeatedly.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push(format!("{name}: {value}"));
+        self
+    }
+
+    /// Keeps only these top-level keys, in this order (`--reduce`).
+    pub fn reduce(mut self, keys: impl IntoIterator<Item = ReduceKey>) -> Self {
+        self.reduce = keys.into_iter().collect();
+        self
+    }
+
+    pub fn profile(mut self, profile: OutputProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn outline_compat(mut self, compat: OutlineCompat) -> Self {
+        self.outline.compat = compat;
+        self
+    }
+
+    /// All outline settings at once; replaces an earlier
+    /// [`SnapshotBuilder::outline_compat`].
+    pub fn outline(mut self, options: OutlineOptions) -> Self {
+        self.outline = options;
+        self
+    }
+
+    /// Request timeout; must be greater than zero.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Minified (`true`) or indented (`false`) JSON, overriding the choice
+    /// made from the output file name.
+    pub fn minify(mut self, minify: bool) -> Self {
+        self.minify = Some(minify);
+        self
+    }
+
+    /// Runs the `validate` checks on the document and fails on errors.
+    pub fn validate(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Lets [`Snapshot::write_to`] replace a snapshot that has paths with
+    /// one that has none.
+    pub fn allow_empty(mut self, allow_empty: bool) -> Self {
+        self.allow_empty = allow_empty;
+        self
+    }
+
+    /// Fails the snapshot when the document declares a different OpenAPI
+    /// version (`--require-version`).
+    pub fn require_version(mut self, version: SpecVersion) -> Self {
+        self.require_version = Some(version);
+        self
+    }
+
+    /// Stages written files in `dir` instead of next to them (`--temp-dir`).
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+
+    /// Adds a step that runs on the document before the profile's and
+    /// `reduce`'s own, after any added earlier. See [`crate::transform`].
+    pub fn transform(mut self, transform: Box<dyn Transform>) -> Self {
+        self.transforms.push_boxed(transform);
+        self
+    }
+
+    /// What to log to stderr; nothing but errors by default.
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Checks the settings the way the command line does, reporting
+    /// problems as [`AppError::Usage`].
+    pub fn build(self) -> Result<Snapshot, AppError> {
+        if self.timeout.is_zero() {
+            return Err(AppError::usage(
+                "the timeout must be greater than zero.".to_string(),
+            ));
+        }
+        if self.url.is_some() && self.input.is_some() {
+            return Err(AppError::usage(
+                "pass either a URL or an input file, not both.".to_string(),
+            ));
+        }
+        let url_from_default = self.url.is_none() && self.input.is_none();
+        let config = Config {
+            name: None,
+            url: self
+                .url
+                .unwrap_or_else(|| localhost_url(DEFAULT_PORT, DEFAULT_PATH)),
+            url_from_default,
+            doc_path: DEFAULT_PATH.to_string(),
+            input: self.input,
+            out: None,
+            outline_out: None,
+            temp_dir: self.temp_dir,
+            reduce: self.reduce,
+            profile: self.profile,
+            outline: self.outline,
+            minify: self.minify.unwrap_or(false),
+            timeout_ms: duration_ms(self.timeout),
+            headers: self.headers,
+            // Nothing is written until `write_to` names a file.
+            stdout: true,
+            verbosity: self.verbosity,
+            validate: self.validate,
+            color: ColorChoice::Never,
+            fallback_to_last: None,
+            allow_empty: self.allow_empty,
+            strict_bytes: false,
+            verify_refs: None,
+            validate_options: ValidateOptions::default(),
+            require_version: self.require_version,
+            check_paths: false,
+            duplicate_keys: None,
+            transforms: self.transforms,
+        };
+        validate_config(&config)?;
+        #[cfg(feature = "http")]
+        build_headers(&config.headers)?;
+        Ok(Snapshot {
+            config,
+            minify: self.minify,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "http")]
+    use httpmock::prelude::*;
+
+    #[cfg(feature = "http")]
+    const SPEC: &str = r#"{"openapi":"3.0.3","info":{"title":"t","version":"1"},"paths":{"/users":{"get":{"responses":{"200":{"description":"ok"}}}}},"components":{}}"#;
+
+    #[test]
+    fn defaults_match_the_command_line() {
+        let snapshot = Snapshot::builder().build().unwrap();
+        let config = snapshot.config();
+        assert_eq!(config.url, "http://localhost:3000/api-docs/openapi.json");
+        assert!(config.url_from_default);
+        assert_eq!(config.profile, OutputProfile::Full);
+        assert!(config.reduce.is_empty());
+        assert_eq!(config.timeout_ms, 10_000);
+        assert!(!config.minify);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn fetches_with_headers_and_reduce() {
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/openapi.json")
+                .header("authorization", "Bearer token");
+            then.status(200).body(SPEC);
+        });
+        let outputs = Snapshot::builder()
+            .url(server.url("/openapi.json"))
+            .header("Authorization", "Bearer token")
+            .reduce([ReduceKey::Paths])
+            .timeout(Duration::from_secs(2))
+            .build()
+            .unwrap()
+            .fetch()
+            .unwrap();
+        mock.assert();
+        assert_eq!(outputs.status, Some(200));
+        let doc: serde_json::Value = serde_json::from_str(&outputs.primary).unwrap();
+        assert_eq!(
+            doc.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["paths"]
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn writes_atomically_and_minifies_min_json() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(200).body(SPEC);
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = Snapshot::builder()
+            .url(server.url("/openapi.json"))
+            .build()
+            .unwrap();
+
+        let pretty = dir.path().join("nested/api.json");
+        snapshot.write_to(&pretty).unwrap();
+        assert!(fs_text(&pretty).contains("\n  \"paths\""));
+
+        let minified = dir.path().join("api.min.json");
+        snapshot.write_to(&minified).unwrap();
+        assert!(!fs_text(&minified).contains('\n'));
+    }
+
+    #[test]
+    fn invalid_settings_are_usage_errors() {
+        let mut cases = vec![
+            Snapshot::builder().url("localhost:3000"),
+            Snapshot::builder().timeout(Duration::ZERO),
+            Snapshot::builder()
+                .profile(OutputProfile::Outline)
+                .reduce([ReduceKey::Paths]),
+            Snapshot::builder()
+                .url("http://localhost:3000/openapi.json")
+                .input("openapi.json"),
+        ];
+        if cfg!(feature = "http") {
+            cases.push(Snapshot::builder().header("Bad Header", "x"));
+        }
+        for builder in cases {
+            let err = builder.build().unwrap_err();
+            assert!(matches!(err, AppError::Usage { .. }), "{err}");
+        }
+    }
+
+    #[cfg(feature = "http")]
+    struct BasePath;
+
+    #[cfg(feature = "http")]
+    impl Transform for BasePath {
+        fn name(&self) -> &str {
+            "base-path"
+        }
+
+        fn apply(
+            &self,
+            mut doc: serde_json::Value,
+            ctx: &crate::transform::TransformCtx<'_>,
+        ) -> Result<serde_json::Value, AppError> {
+            assert_eq!(ctx.status, Some(200));
+            let paths = doc["paths"].as_object_mut().unwrap();
+            *paths = std::mem::take(paths)
+                .into_iter()
+                .map(|(path, item)| (format!("/gateway{path}"), item))
+                .collect();
+            Ok(doc)
+        }
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn custom_transforms_run_before_the_profile_and_reduce() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(200).body(SPEC);
+        });
+        let builder = || {
+            Snapshot::builder()
+                .url(server.url("/openapi.json"))
+                .transform(Box::new(BasePath))
+        };
+
+        let reduced = builder()
+            .reduce([ReduceKey::Paths])
+            .build()
+            .unwrap()
+            .fetch()
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&reduced.primary).unwrap(),
+            serde_json::json!({"paths": {"/gateway/users": {"get": {"responses": {"200": {"description": "ok"}}}}}})
+        );
+
+        let slim = builder()
+            .profile(OutputProfile::Slim)
+            .build()
+            .unwrap()
+            .fetch()
+            .unwrap();
+        assert!(slim.primary.contains("/gateway/users"));
+        assert!(!slim.primary.contains("\"ok\""));
+    }
+
+    #[cfg(feature = "http")]
+    fn fs_text(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+}
@@ -0,0 +1,169 @@
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{self, HeaderValue};
+
+use crate::errors::AppError;
+use crate::fetch::build_headers;
+
+/// A minimal Server-Sent Events reader: it only reports that an event
+/// arrived, which is all watch mode needs to trigger a fetch.
+pub struct EventStream {
+    reader: BufReader<Response>,
+    parser: SseParser,
+    /// The line being read, kept when a read times out partway through it.
+    line: String,
+}
+
+/// What [`EventStream::next_event`] saw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamRead {
+    Event,
+    /// Nothing arrived within the timeout; the stream is still open.
+    Quiet,
+    Closed,
+}
+
+impl EventStream {
+    /// Opens the stream. `timeout_ms` bounds connecting, waiting for the
+    /// response, and each read, so a quiet stream hands control back to the
+    /// caller regularly instead of blocking indefinitely.
+    pub fn connect(url: &str, raw_headers: &[String], timeout_ms: u64) -> Result<Self, AppError> {
+        let mut headers = build_headers(raw_headers)?;
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("text/event-stream"),
+        );
+        let client = Client::builder()
+            .timeout(Duration::from_millis(timeout_ms))
+            .default_headers(headers)
+            .build()
+            .map_err(|err| AppError::network(format!("client error: {err}")).with_source(err))?;
+        let response = client.get(url).send().map_err(|err| {
+            AppError::unreachable(format!("event stream failed: {err}")).with_source(err)
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AppError::http(
+                status.as_u16(),
+                url.to_string(),
+                format!("event stream returned HTTP {status}"),
+            ));
+        }
+        Ok(Self {
+            reader: BufReader::new(response),
+            parser: SseParser::default(),
+            line: String::new(),
+        })
+    }
+
+    /// Blocks until the next event is dispatched, the server closes the
+    /// stream, or a read times out.
+    pub fn next_event(&mut self) -> Result<StreamRead, AppError> {
+        loop {
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return Ok(StreamRead::Closed),
+                Ok(_) => {}
+                Err(err) if is_timeout(&err) => return Ok(StreamRead::Quiet),
+                Err(err) => {
+                    return Err(
+                        AppError::network(format!("event stream read failed: {err}"))
+                            .with_source(err),
+                    );
+                }
+            }
+            let dispatched = self.parser.feed_line(&self.line);
+            self.line.clear();
+            if dispatched {
+                return Ok(StreamRead::Event);
+            }
+        }
+    }
+}
+
+/// Whether a read failed only because the client's timeout elapsed.
+fn is_timeout(err: &std::io::Error) -> bool {
+    err.get_ref()
+        .and_then(|inner| inner.downcast_ref::<reqwest::Error>())
+        .is_some_and(reqwest::Error::is_timeout)
+}
+
+/// Tracks whether the current SSE block carried data; a blank line
+/// dispatches it.
+#[derive(Debug, Default)]
+struct SseParser {
+    has_data: bool,
+}
+
+impl SseParser {
+    fn feed_line(&mut self, line: &str) -> bool {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            return std::mem::take(&mut self.has_data);
+        }
+        if line == "data" || line.starts_with("data:") {
+            self.has_data = true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parser_dispatches_on_blank_line_after_data() {
+        let mut parser = SseParser::default();
+        let dispatched: Vec<bool> = [
+            ": keep-alive\n",
+            "\n",
+            "event: routes\n",
+            "data: {\"reason\":\"reload\"}\r\n",
+            "data: more\n",
+            "\r\n",
+            "\n",
+            "data\n",
+            "\n",
+        ]
+        .into_iter()
+        .map(|line| parser.feed_line(line))
+        .collect();
+        assert_eq!(
+            dispatched,
+            vec![false, false, false, false, false, true, false, false, true]
+        );
+    }
+
+    #[test]
+    fn quiet_streams_time_out_without_losing_partial_lines() {
+        use std::io::Write;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        let (send_more, more) = std::sync::mpsc::channel::<&str>();
+        let server = std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = std::io::Read::read(&mut socket, &mut request).unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\r\ndata: x")
+                .unwrap();
+            socket.flush().unwrap();
+            for chunk in more {
+                socket.write_all(chunk.as_bytes()).unwrap();
+                socket.flush().unwrap();
+            }
+        });
+
+        let mut stream = EventStream::connect(&url, &[], 200).unwrap();
+        assert_eq!(stream.next_event().unwrap(), StreamRead::Quiet);
+        send_more.send("yz\n\n").unwrap();
+        assert_eq!(stream.next_event().unwrap(), StreamRead::Event);
+        drop(send_more);
+        server.join().unwrap();
+        assert_eq!(stream.next_event().unwrap(), StreamRead::Closed);
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::outline::operations;
+
+/// Headline numbers for a document, as printed by `stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SpecStats {
+    pub paths: usize,
+    pub operations: usize,
+    /// Operation count per lower-case HTTP method.
+    pub methods: BTreeMap<String, usize>,
+    pub schemas: usize,
+    /// Operations that declare no `2XX` response.
+    pub without_success: usize,
+    pub deprecated: usize,
+    /// Operation count per tag; untagged operations are not listed.
+    pub tags: BTreeMap<String, usize>,
+    /// Size of the document as it would be written.
+    pub bytes: usize,
+}
+
+/// Counts paths, operations, and schemas in `doc`, which serializes to
+/// `bytes` bytes.
+pub fn spec_stats(doc: &Value, bytes: usize) -> SpecStats {
+    let mut stats = SpecStats {
+        bytes,
+        schemas: doc
+            .pointer("/components/schemas")
+            .and_then(Value::as_object)
+            .map_or(0, |schemas| schemas.len()),
+        ..SpecStats::default()
+    };
+    let Some(paths) = doc.get("paths").and_then(Value::as_object) else {
+        return stats;
+    };
+    stats.paths = paths.len();
+    for (_, method, op) in operations(paths) {
+        stats.operations += 1;
+        *stats.methods.entry(method.to_string()).or_default() += 1;
+        if !has_success_response(op) {
+            stats.without_success += 1;
+        }
+        if op.get("deprecated").and_then(Value::as_bool) == Some(true) {
+            stats.deprecated += 1;
+        }
+        let tags = op.get("tags").and_then(Value::as_array);
+        for tag in tags.into_iter().flatten().filter_map(Value::as_str) {
+            *stats.tags.entry(tag.to_string()).or_default() += 1;
+        }
+    }
+    stats
+}
+
+/// Renders the stats as an aligned two-column table.
+pub fn format_stats(stats: &SpecStats) -> String {
+    let mut rows = vec![
+        ("paths".to_string(), stats.paths.to_string()),
+        ("operations".to_string(), stats.operations.to_string()),
+    ];
+    rows.extend(stats.methods.iter().map(|(method, count)| {
+        (
+            format!("  {}", method.to_ascii_uppercase()),
+            count.to_string(),
+        )
+    }));
+    rows.push(("schemas".to_string(), stats.schemas.to_string()));
+    rows.push((
+        "without 2xx response".to_string(),
+        stats.without_success.to_string(),
+    ));
+    rows.push(("deprecated".to_string(), stats.deprecated.to_string()));
+    rows.push(("tags".to_string(), stats.tags.len().to_string()));
+    rows.extend(
+        stats
+            .tags
+            .iter()
+            .map(|(tag, count)| (format!("  {tag}"), count.to_string())),
+    );
+    rows.push(("size".to_string(), format!("{} bytes", stats.bytes)));
+
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    rows.iter()
+        .map(|(label, value)| format!("{label:<width$}  {value}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn has_success_response(op: &Value) -> bool {
+    op.get("responses")
+        .and_then(Value::as_object)
+        .is_some_and(|responses| responses.keys().any(|code| code.starts_with('2')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Value {
+        serde_json::from_str(include_str!("../tests/fixtures/stats/billing.json")).unwrap()
+    }
+
+    #[test]
+    fn spec_stats_counts_the_fixture() {
+        let stats = spec_stats(&fixture(), 1234);
+        let methods = [("delete", 1), ("get", 3), ("post", 1), ("put", 1)];
+        let tags = [("admin", 1), ("billing", 4), ("users", 1)];
+        assert_eq!(
+            stats,
+            SpecStats {
+                paths: 3,
+                operations: 6,
+                methods: methods.map(|(k, v)| (k.to_string(), v)).into(),
+                schemas: 3,
+                without_success: 2,
+                deprecated: 1,
+                tags: tags.map(|(k, v)| (k.to_string(), v)).into(),
+                bytes: 1234,
+            }
+        );
+    }
+
+    #[test]
+    fn spec_stats_tolerates_missing_sections() {
+        let stats = spec_stats(&serde_json::json!({"paths": {}}), 12);
+        assert_eq!(stats.paths, 0);
+        assert_eq!(stats.schemas, 0);
+        assert!(stats.tags.is_empty());
+    }
+
+    #[test]
+    fn format_stats_aligns_labels() {
+        assert_eq!(
+            format_stats(&spec_stats(&fixture(), 1234)),
+            "paths                 3\n\
+             operations            6\n\
+             \x20 DELETE              1\n\
+             \x20 GET                 3\n\
+             \x20 POST                1\n\
+             \x20 PUT                 1\n\
+             schemas               3\n\
+             without 2xx response  2\n\
+             deprecated            1\n\
+             tags                  3\n\
+             \x20 admin               1\n\
+             \x20 billing             4\n\
+             \x20 users               1\n\
+             size                  1234 bytes"
+        );
+    }
+}
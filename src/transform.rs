@@ -0,0 +1,377 @@
+//! The steps between the fetched document and the written one.
+//!
+//! [`crate::build_outputs`] parses and validates the document, then runs,
+//! in this order:
+//!
+//! 1. the program's own transforms, in the order they were added
+//!    ([`crate::SnapshotBuilder::transform`]);
+//! 2. the profile's built-in steps from [`Pipeline::for_profile`]:
+//!    [`Strip`] then [`Prune`] for `slim`, [`Outline`] for `outline`;
+//! 3. [`Reduce`] when `--reduce` lists keys.
+//!
+//! An `--outline-out` file is the [`Outline`] of the document after step 1.
+//!
+//! ```
+//! use openapi_snapshot::transform::{Transform, TransformCtx};
+//! use openapi_snapshot::{AppError, Snapshot};
+//! use serde_json::Value;
+//!
+//! struct BasePath(&'static str);
+//!
+//! impl Transform for BasePath {
+//!     fn name(&self) -> &str {
+//!         "base-path"
+//!     }
+//!
+//!     fn apply(&self, mut doc: Value, _ctx: &TransformCtx<'_>) -> Result<Value, AppError> {
+//!         if let Some(paths) = doc.get_mut("paths").and_then(Value::as_object_mut) {
+//!             *paths = std::mem::take(paths)
+//!                 .into_iter()
+//!                 .map(|(path, item)| (format!("{}{path}", self.0), item))
+//!                 .collect();
+//!         }
+//!         Ok(doc)
+//!     }
+//! }
+//!
+//! let snapshot = Snapshot::builder()
+//!     .url("http://localhost:8080/openapi.json")
+//!     .transform(Box::new(BasePath("/gateway")))
+//!     .build()?;
+//! assert_eq!(snapshot.config().transforms.names(), ["base-path"]);
+//! # Ok::<(), AppError>(())
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::cli::OutputProfile;
+use crate::config::{Config, ReduceKey};
+use crate::errors::AppError;
+use crate::outline::{OutlineOptions, outline_openapi};
+use crate::prune::prune_components;
+use crate::slim::strip_documentation;
+use crate::version::SpecVersion;
+
+/// What a transform may want to know about the snapshot being taken.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct TransformCtx<'a> {
+    pub config: &'a Config,
+    /// HTTP status of the fetched document; `None` for `--input`.
+    pub status: Option<u16>,
+    /// The OpenAPI version the fetched document declares.
+    pub version: SpecVersion,
+}
+
+impl<'a> TransformCtx<'a> {
+    pub fn new(config: &'a Config, status: Option<u16>) -> Self {
+        Self {
+            config,
+            status,
+            version: SpecVersion::default(),
+        }
+    }
+
+    pub fn with_version(mut self, version: SpecVersion) -> Self {
+        self.version = version;
+        self
+    }
+}
+
+/// One step of a [`Pipeline`]: takes the document and returns the next
+/// version of it.
+pub trait Transform: Send + Sync {
+    /// A short name for logs and [`Pipeline::names`].
+    fn name(&self) -> &str;
+
+    fn apply(&self, doc: Value, ctx: &TransformCtx<'_>) -> Result<Value, AppError>;
+}
+
+/// Transforms applied in order, each to the previous one's output.
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    steps: Vec<Arc<dyn Transform>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in steps for `config`'s profile and `--reduce` keys.
+    pub fn for_profile(config: &Config) -> Self {
+        let mut pipeline = Pipeline::new();
+        match config.profile {
+            OutputProfile::Full => {}
+            OutputProfile::Slim => {
+                pipeline.push(Strip);
+                pipeline.push(Prune);
+            }
+            OutputProfile::Outline => pipeline.push(Outline(config.outline.clone())),
+        }
+        if !config.reduce.is_empty() {
+            pipeline.push(Reduce(config.reduce.clone()));
+        }
+        pipeline
+    }
+
+    /// Adds `transform` after the existing steps.
+    pub fn push(&mut self, transform: impl Transform + 'static) {
+        self.steps.push(Arc::new(transform));
+    }
+
+    /// [`Pipeline::push`] for an already boxed transform.
+    pub fn push_boxed(&mut self, transform: Box<dyn Transform>) {
+        self.steps.push(Arc::from(transform));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// The steps' names, in the order they run.
+    pub fn names(&self) -> Vec<&str> {
+        self.steps.iter().map(|step| step.name()).collect()
+    }
+
+    pub fn apply(&self, doc: Value, ctx: &TransformCtx<'_>) -> Result<Value, AppError> {
+        self.steps
+            .iter()
+            .try_fold(doc, |doc, step| step.apply(doc, ctx))
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// Keeps only these top-level keys, in this order (`--reduce`).
+#[derive(Debug, Clone)]
+pub struct Reduce(pub Vec<ReduceKey>);
+
+impl Transform for Reduce {
+    fn name(&self) -> &str {
+        "reduce"
+    }
+
+    fn apply(&self, doc: Value, _ctx: &TransformCtx<'_>) -> Result<Value, AppError> {
+        reduce_openapi(doc, &self.0)
+    }
+}
+
+/// [`Reduce`] without a [`TransformCtx`]. Moves the kept entries out of
+/// `doc` rather than copying them.
+pub fn reduce_openapi(doc: Value, keys: &[ReduceKey]) -> Result<Value, AppError> {
+    let Value::Object(mut object) = doc else {
+        return Err(AppError::reduce(
+            "OpenAPI document must be a JSON object".to_string(),
+        ));
+    };
+    let mut reduced = serde_json::Map::new();
+    for key in keys {
+        let name = key.as_str();
+        if reduced.contains_key(name) {
+            continue;
+        }
+        let entry = object
+            .remove(name)
+            .ok_or_else(|| AppError::reduce(format!("missing top-level key: {name}")))?;
+        reduced.insert(name.to_string(), entry);
+    }
+    Ok(Value::Object(reduced))
+}
+
+/// Removes descriptions, summaries, examples, and extensions; see
+/// [`crate::slim`].
+#[derive(Debug, Clone, Copy)]
+pub struct Strip;
+
+impl Transform for Strip {
+    fn name(&self) -> &str {
+        "strip"
+    }
+
+    fn apply(&self, mut doc: Value, _ctx: &TransformCtx<'_>) -> Result<Value, AppError> {
+        strip_documentation(&mut doc);
+        Ok(doc)
+    }
+}
+
+/// Removes components that nothing references.
+#[derive(Debug, Clone, Copy)]
+pub struct Prune;
+
+impl Transform for Prune {
+    fn name(&self) -> &str {
+        "prune"
+    }
+
+    fn apply(&self, mut doc: Value, _ctx: &TransformCtx<'_>) -> Result<Value, AppError> {
+        prune_components(&mut doc);
+        Ok(doc)
+    }
+}
+
+/// Replaces the document with its outline.
+#[derive(Debug, Clone)]
+pub struct Outline(pub OutlineOptions);
+
+impl Transform for Outline {
+    fn name(&self) -> &str {
+        "outline"
+    }
+
+    fn apply(&self, doc: Value, _ctx: &TransformCtx<'_>) -> Result<Value, AppError> {
+        outline_openapi(&doc, &self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::Snapshot;
+    use serde_json::json;
+
+    fn config() -> Config {
+        Snapshot::builder().build().unwrap().config().clone()
+    }
+
+    fn run(transform: impl Transform, doc: Value) -> Result<Value, AppError> {
+        let config = config();
+        transform.apply(doc, &TransformCtx::new(&config, None))
+    }
+
+    struct Rename(&'static str, &'static str);
+
+    impl Transform for Rename {
+        fn name(&self) -> &str {
+            "rename"
+        }
+
+        fn apply(&self, mut doc: Value, _ctx: &TransformCtx<'_>) -> Result<Value, AppError> {
+            if let Some(schemas) = doc
+                .pointer_mut("/components/schemas")
+                .and_then(Value::as_object_mut)
+                && let Some(schema) = schemas.remove(self.0)
+            {
+                schemas.insert(self.1.to_string(), schema);
+            }
+            Ok(doc)
+        }
+    }
+
+    #[test]
+    fn reduce_keeps_only_requested_keys() {
+        let input = json!({
+            "paths": {"x": 1},
+            "components": {"y": 2},
+            "extra": {"z": 3}
+        });
+        let output = run(Reduce(vec![ReduceKey::Components]), input).unwrap();
+        assert_eq!(output, json!({"components": {"y": 2}}));
+    }
+
+    #[test]
+    fn reduce_moves_each_key_once() {
+        let input = json!({"paths": {"x": 1}, "components": {"y": 2}});
+        let keys = [ReduceKey::Paths, ReduceKey::Paths, ReduceKey::Components];
+        let output = reduce_openapi(input, &keys).unwrap();
+        assert_eq!(output, json!({"paths": {"x": 1}, "components": {"y": 2}}));
+    }
+
+    #[test]
+    fn reduce_missing_key_is_error() {
+        let input = json!({"paths": {"x": 1}});
+        let err = run(Reduce(vec![ReduceKey::Components]), input).unwrap_err();
+        assert!(matches!(err, AppError::Reduce { .. }));
+    }
+
+    #[test]
+    fn reduce_requires_object() {
+        let input = json!(["not an object"]);
+        let err = run(Reduce(vec![ReduceKey::Components]), input).unwrap_err();
+        assert!(matches!(err, AppError::Reduce { .. }));
+    }
+
+    #[test]
+    fn strip_removes_documentation_but_keeps_components() {
+        let input = json!({
+            "paths": {"/a": {"get": {"summary": "A", "responses": {}}}},
+            "components": {"schemas": {"Unused": {"type": "string", "description": "x"}}}
+        });
+        let output = run(Strip, input).unwrap();
+        assert_eq!(output["paths"]["/a"]["get"], json!({"responses": {}}));
+        assert_eq!(
+            output["components"]["schemas"]["Unused"],
+            json!({"type": "string"})
+        );
+    }
+
+    #[test]
+    fn prune_removes_unreferenced_components_only() {
+        let input = json!({
+            "paths": {"/a": {"get": {"responses": {"200": {"$ref": "#/components/responses/Ok"}}}}},
+            "components": {
+                "responses": {"Ok": {"description": "ok"}},
+                "schemas": {"Unused": {"type": "string", "description": "x"}}
+            }
+        });
+        let output = run(Prune, input).unwrap();
+        assert_eq!(
+            output["components"],
+            json!({"responses": {"Ok": {"description": "ok"}}})
+        );
+    }
+
+    #[test]
+    fn outline_replaces_the_document() {
+        let input = json!({"paths": {}, "components": {"schemas": {"Id": {"type": "string"}}}});
+        let output = run(Outline(OutlineOptions::default()), input).unwrap();
+        assert_eq!(output, json!({"paths": {}, "schemas": {"Id": "string"}}));
+    }
+
+    #[test]
+    fn built_in_steps_follow_the_profile_then_reduce() {
+        let base = config();
+        let names = |profile, reduce: Vec<ReduceKey>| {
+            Pipeline::for_profile(&Config {
+                profile,
+                reduce,
+                ..base.clone()
+            })
+            .names()
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+        };
+        assert!(names(OutputProfile::Full, Vec::new()).is_empty());
+        assert_eq!(
+            names(OutputProfile::Full, vec![ReduceKey::Paths]),
+            ["reduce"]
+        );
+        assert_eq!(names(OutputProfile::Slim, Vec::new()), ["strip", "prune"]);
+        assert_eq!(names(OutputProfile::Outline, Vec::new()), ["outline"]);
+    }
+
+    #[test]
+    fn pipeline_runs_steps_in_the_order_they_were_added() {
+        let mut pipeline = Pipeline::new();
+        pipeline.push(Rename("Legacy", "User"));
+        pipeline.push_boxed(Box::new(Rename("User", "Account")));
+        assert_eq!(format!("{pipeline:?}"), r#"["rename", "rename"]"#);
+        let doc = json!({"components": {"schemas": {"Legacy": {"type": "object"}}}});
+        let config = config();
+        let output = pipeline
+            .apply(doc, &TransformCtx::new(&config, None))
+            .unwrap();
+        assert_eq!(
+            output,
+            json!({"components": {"schemas": {"Account": {"type": "object"}}}})
+        );
+    }
+}
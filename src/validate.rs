@@ -0,0 +1,775 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::diff::resolve_ref;
+use crate::errors::AppError;
+use crate::examples::check_examples;
+use crate::outline::is_http_method;
+use crate::refs::check_refs;
+use crate::version::SpecVersion;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    /// Reported but never fails validation.
+    Info,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+}
+
+/// A check whose severity `--severity <ID>=<LEVEL>` can change or turn off.
+pub struct Check {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub default: Severity,
+}
+
+pub const CHECKS: &[Check] = &[
+    Check {
+        id: "operation-id-missing",
+        description: "an operation has no operationId",
+        default: Severity::Warning,
+    },
+    Check {
+        id: "operation-id-duplicate",
+        description: "more than one operation uses the same operationId",
+        default: Severity::Error,
+    },
+    Check {
+        id: "operation-id-format",
+        description: "an operationId is not made of letters, digits, and underscores",
+        default: Severity::Info,
+    },
+    Check {
+        id: "path-collision",
+        description: "paths differ only in placeholder names, a trailing slash, or case",
+        default: Severity::Error,
+    },
+    Check {
+        id: "response-status-key",
+        description: "a response key is not a status code, an `NXX` range, or `default`",
+        default: Severity::Error,
+    },
+    Check {
+        id: "responses-empty",
+        description: "an operation's responses object is empty",
+        default: Severity::Error,
+    },
+    Check {
+        id: "no-content-body",
+        description: "a 204 response declares content",
+        default: Severity::Warning,
+    },
+    Check {
+        id: "redirect-location",
+        description: "a 301, 302, 303, 307, or 308 response defines no Location header",
+        default: Severity::Warning,
+    },
+    Check {
+        id: "example-mismatch",
+        description: "an example does not match its schema (with --validate-examples)",
+        default: Severity::Error,
+    },
+];
+
+/// Severity overrides for the [`CHECKS`], by id; `None` turns a check off.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidateOptions {
+    pub severities: BTreeMap<String, Option<Severity>>,
+    /// Check examples against their schemas; see [`crate::examples`].
+    pub examples: bool,
+}
+
+impl ValidateOptions {
+    /// The severity `id` reports at, or `None` when it is turned off.
+    pub fn severity(&self, id: &str) -> Option<Severity> {
+        match self.severities.get(id) {
+            Some(severity) => *severity,
+            None => CHECKS
+                .iter()
+                .find(|check| check.id == id)
+                .map(|check| check.default),
+        }
+    }
+}
+
+/// Parses a `--severity` value such as `operation-id-missing=off`.
+pub fn parse_severity_override(text: &str) -> Result<(String, Option<Severity>), String> {
+    let (id, level) = text
+        .split_once('=')
+        .ok_or_else(|| format!("expected CHECK=LEVEL, got '{text}'"))?;
+    if !CHECKS.iter().any(|check| check.id == id) {
+        let ids: Vec<&str> = CHECKS.iter().map(|check| check.id).collect();
+        return Err(format!(
+            "unknown check '{id}'; expected one of {}",
+            ids.join(", ")
+        ));
+    }
+    let severity = match level {
+        "error" => Some(Severity::Error),
+        "warning" => Some(Severity::Warning),
+        "info" => Some(Severity::Info),
+        "off" => None,
+        _ => {
+            return Err(format!(
+                "unknown level '{level}'; expected error, warning, info, or off"
+            ));
+        }
+    };
+    Ok((id.to_string(), severity))
+}
+
+/// One structural problem, located by a JSON pointer such as
+/// `#/paths/~1users/get`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.severity.as_str(),
+            self.path,
+            self.message
+        )
+    }
+}
+
+/// Checks the structure an OpenAPI document needs before it is worth
+/// committing: the top-level keys, path keys, operation responses, parameter
+/// names and locations, operationIds, and local `$ref` targets. Findings
+/// come back in document order, errors and warnings interleaved.
+pub fn validate_spec(doc: &Value) -> Vec<Finding> {
+    validate_spec_with(doc, &ValidateOptions::default())
+}
+
+/// [`validate_spec`] with the severities of the [`CHECKS`] overridden.
+pub fn validate_spec_with(doc: &Value, options: &ValidateOptions) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let Some(root) = doc.as_object() else {
+        findings.push(error("#", "document is not a JSON object"));
+        return findings;
+    };
+
+    if root.get("openapi").and_then(Value::as_str).is_none() {
+        findings.push(error("#", "missing required key 'openapi'"));
+    }
+    if !root.contains_key("info") {
+        findings.push(error("#", "missing required key 'info'"));
+    }
+    // OpenAPI 3.1 documents may describe only webhooks or components.
+    let paths_optional = SpecVersion::detect(doc) == SpecVersion::OpenApi31;
+    match root.get("paths").map(Value::as_object) {
+        Some(Some(paths)) => {
+            check_paths(paths, &mut findings);
+            check_operation_ids(paths, options, &mut findings);
+            check_responses(doc, paths, options, &mut findings);
+            if let Some(severity) = options.severity("path-collision") {
+                for collision in path_collisions(paths) {
+                    findings.push(finding(
+                        severity,
+                        &collision.location(),
+                        &collision.message(),
+                    ));
+                }
+            }
+        }
+        Some(None) => findings.push(error("#/paths", "'paths' must be an object")),
+        None if !paths_optional => findings.push(error("#", "missing required key 'paths'")),
+        None => {}
+    }
+
+    for site in check_refs(doc).broken {
+        findings.push(error(
+            &site.location,
+            &format!("$ref '{}' does not resolve", site.target),
+        ));
+    }
+    findings.extend(example_findings(doc, options));
+    findings
+}
+
+/// The `example-mismatch` findings, when `options` asks for examples to be
+/// checked.
+pub fn example_findings(doc: &Value, options: &ValidateOptions) -> Vec<Finding> {
+    let Some(severity) = options
+        .severity("example-mismatch")
+        .filter(|_| options.examples)
+    else {
+        return Vec::new();
+    };
+    check_examples(doc)
+        .into_iter()
+        .map(|mismatch| {
+            finding(
+                severity,
+                &mismatch.location,
+                &format!("example does not match its schema: {}", mismatch.error),
+            )
+        })
+        .collect()
+}
+
+/// Fails when there are errors, or more than `max_warnings` warnings.
+pub fn check_findings(findings: &[Finding], max_warnings: Option<usize>) -> Result<(), AppError> {
+    let count = |severity| {
+        findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    };
+    let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+    let too_many_warnings = max_warnings.is_some_and(|max| warnings > max);
+    if errors == 0 && !too_many_warnings {
+        return Ok(());
+    }
+    let mut message = format!(
+        "validation failed: {}, {}",
+        plural(errors, "error"),
+        plural(warnings, "warning")
+    );
+    if let Some(max) = max_warnings.filter(|_| too_many_warnings) {
+        message.push_str(&format!(" (at most {max} allowed)"));
+    }
+    message.push('.');
+    Err(AppError::validation(message))
+}
+
+fn check_paths(paths: &Map<String, Value>, findings: &mut Vec<Finding>) {
+    for (path, item) in paths {
+        let location = format!("#/paths/{}", escape(path));
+        if !path.starts_with('/') {
+            findings.push(error(&location, "path must start with '/'"));
+        }
+        let Some(item) = item.as_object() else {
+            continue;
+        };
+        check_parameters(item.get("parameters"), &location, findings);
+        for (method, op) in item.iter().filter(|(method, _)| is_http_method(method)) {
+            let location = format!("{location}/{method}");
+            if op.get("responses").is_none() {
+                findings.push(Finding {
+                    severity: Severity::Warning,
+                    path: location.clone(),
+                    message: "operation has no responses".to_string(),
+                });
+            }
+            check_parameters(op.get("parameters"), &location, findings);
+        }
+    }
+}
+
+/// Reports operations without an operationId, operationIds that are not
+/// plain identifiers, and each operationId shared by several operations,
+/// with every operation that uses it.
+fn check_operation_ids(
+    paths: &Map<String, Value>,
+    options: &ValidateOptions,
+    findings: &mut Vec<Finding>,
+) {
+    let mut users: BTreeMap<&str, Vec<(String, String)>> = BTreeMap::new();
+    for (path, item) in paths {
+        let Some(item) = item.as_object() else {
+            continue;
+        };
+        for (method, op) in item.iter().filter(|(method, _)| is_http_method(method)) {
+            let location = format!("#/paths/{}/{method}", escape(path));
+            let Some(id) = op.get("operationId").and_then(Value::as_str) else {
+                if let Some(severity) = options.severity("operation-id-missing") {
+                    findings.push(finding(severity, &location, "operation has no operationId"));
+                }
+                continue;
+            };
+            if let Some(severity) = options
+                .severity("operation-id-format")
+                .filter(|_| !is_identifier(id))
+            {
+                findings.push(finding(
+                    severity,
+                    &location,
+                    &format!(
+                        "operationId '{id}' is not a valid identifier; \
+                         use letters, digits, and underscores"
+                    ),
+                ));
+            }
+            let operation = format!("{} {path}", method.to_ascii_uppercase());
+            users.entry(id).or_default().push((location, operation));
+        }
+    }
+    let Some(severity) = options.severity("operation-id-duplicate") else {
+        return;
+    };
+    for (id, users) in users.iter().filter(|(_, users)| users.len() > 1) {
+        let operations: Vec<&str> = users
+            .iter()
+            .map(|(_, operation)| operation.as_str())
+            .collect();
+        findings.push(finding(
+            severity,
+            &users[0].0,
+            &format!(
+                "operationId '{id}' is used by {} operations: {}",
+                users.len(),
+                operations.join(", ")
+            ),
+        ));
+    }
+}
+
+/// Reports malformed status keys, empty responses objects, 204 responses
+/// with content, and redirects without a Location header, each naming the
+/// operation and status key.
+fn check_responses(
+    doc: &Value,
+    paths: &Map<String, Value>,
+    options: &ValidateOptions,
+    findings: &mut Vec<Finding>,
+) {
+    for (path, item) in paths {
+        let Some(item) = item.as_object() else {
+            continue;
+        };
+        for (method, op) in item.iter().filter(|(method, _)| is_http_method(method)) {
+            let Some(responses) = op
+                .get("responses")
+                .map(|responses| resolve_ref(doc, responses))
+                .and_then(Value::as_object)
+            else {
+                continue;
+            };
+            let location = format!("#/paths/{}/{method}/responses", escape(path));
+            let operation = format!("{} {path}", method.to_ascii_uppercase());
+            let mut report = |id: &str, location: &str, message: String| {
+                if let Some(severity) = options.severity(id) {
+                    findings.push(finding(severity, location, &message));
+                }
+            };
+            if responses.is_empty() {
+                report(
+                    "responses-empty",
+                    &location,
+                    format!("{operation} has an empty responses object"),
+                );
+            }
+            for (status, response) in responses {
+                let location = format!("{location}/{}", escape(status));
+                let response = resolve_ref(doc, response);
+                if !is_status_key(status) {
+                    report(
+                        "response-status-key",
+                        &location,
+                        format!(
+                            "{operation} response '{status}' is not a three-digit status code, \
+                             an NXX range, or 'default'"
+                        ),
+                    );
+                }
+                if status == "204" && response.get("content").is_some() {
+                    report(
+                        "no-content-body",
+                        &location,
+                        format!("{operation} response '204' declares content"),
+                    );
+                }
+                let has_location = response
+                    .get("headers")
+                    .and_then(Value::as_object)
+                    .is_some_and(|headers| {
+                        headers
+                            .keys()
+                            .any(|name| name.eq_ignore_ascii_case("location"))
+                    });
+                if REDIRECT_STATUSES.contains(&status.as_str()) && !has_location {
+                    report(
+                        "redirect-location",
+                        &location,
+                        format!("{operation} response '{status}' defines no Location header"),
+                    );
+                }
+            }
+        }
+    }
+}
+
+const REDIRECT_STATUSES: [&str; 5] = ["301", "302", "303", "307", "308"];
+
+/// `default`, a code from 100 to 599, or a range such as `2XX`.
+fn is_status_key(status: &str) -> bool {
+    let bytes = status.as_bytes();
+    status == "default"
+        || (bytes.len() == 3
+            && (b'1'..=b'5').contains(&bytes[0])
+            && (bytes[1..].iter().all(u8::is_ascii_digit) || &bytes[1..] == b"XX"))
+}
+
+/// Paths that a router may treat as one: they differ only in placeholder
+/// names, a trailing slash, or letter case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathCollision {
+    /// The colliding paths, in document order.
+    pub paths: Vec<String>,
+    /// How they differ: `placeholder names`, `trailing slash`, and `case`,
+    /// in that order.
+    pub differences: Vec<&'static str>,
+}
+
+impl PathCollision {
+    /// A JSON pointer to the first of the paths.
+    pub fn location(&self) -> String {
+        format!("#/paths/{}", escape(&self.paths[0]))
+    }
+
+    pub fn message(&self) -> String {
+        format!(
+            "paths collide, differing only in {}: {}",
+            match self.differences.split_last() {
+                Some((last, rest)) if rest.len() > 1 => format!("{}, and {last}", rest.join(", ")),
+                _ => self.differences.join(" and "),
+            },
+            self.paths.join(", ")
+        )
+    }
+}
+
+impl fmt::Display for PathCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.location(), self.message())
+    }
+}
+
+/// Groups the keys of `paths` that are equal once placeholder names are
+/// ignored, a trailing slash is dropped, and letters are lowercased.
+pub fn path_collisions(paths: &Map<String, Value>) -> Vec<PathCollision> {
+    let mut groups: Vec<(String, Vec<&str>)> = Vec::new();
+    for path in paths.keys() {
+        let key = blank_placeholders(trim_slash(path)).to_lowercase();
+        match groups.iter_mut().find(|(group, _)| *group == key) {
+            Some((_, members)) => members.push(path),
+            None => groups.push((key, vec![path])),
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(_, members)| {
+            let differ = |aspect: &dyn Fn(&str) -> String| {
+                members
+                    .iter()
+                    .any(|path| aspect(path) != aspect(members[0]))
+            };
+            let mut differences = Vec::new();
+            if differ(&|path| placeholder_names(path).join(",")) {
+                differences.push("placeholder names");
+            }
+            if differ(&|path| path.ends_with('/').to_string()) {
+                differences.push("trailing slash");
+            }
+            if differ(&|path| blank_placeholders(trim_slash(path))) {
+                differences.push("case");
+            }
+            PathCollision {
+                paths: members.into_iter().map(str::to_string).collect(),
+                differences,
+            }
+        })
+        .collect()
+}
+
+fn trim_slash(path: &str) -> &str {
+    path.strip_suffix('/')
+        .filter(|trimmed| !trimmed.is_empty())
+        .unwrap_or(path)
+}
+
+/// `path` with every `{name}` replaced by `{}`.
+fn blank_placeholders(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    let mut in_placeholder = false;
+    for c in path.chars() {
+        match c {
+            '{' => {
+                in_placeholder = true;
+                out.push(c);
+            }
+            '}' => {
+                in_placeholder = false;
+                out.push(c);
+            }
+            _ if in_placeholder => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn placeholder_names(path: &str) -> Vec<&str> {
+    path.split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+/// Letters, digits, and underscores, not starting with a digit.
+fn is_identifier(id: &str) -> bool {
+    id.chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn check_parameters(parameters: Option<&Value>, location: &str, findings: &mut Vec<Finding>) {
+    let Some(parameters) = parameters.and_then(Value::as_array) else {
+        return;
+    };
+    for (index, parameter) in parameters.iter().enumerate() {
+        if parameter.get("$ref").is_some() {
+            continue;
+        }
+        let location = format!("{location}/parameters/{index}");
+        for key in ["name", "in"] {
+            if parameter.get(key).is_none() {
+                findings.push(error(&location, &format!("parameter is missing '{key}'")));
+            }
+        }
+    }
+}
+
+/// Escapes a key for use in a JSON pointer.
+pub(crate) fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn error(path: &str, message: &str) -> Finding {
+    finding(Severity::Error, path, message)
+}
+
+fn finding(severity: Severity, path: &str, message: &str) -> Finding {
+    Finding {
+        severity,
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+pub(crate) fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn findings(fixture: &str) -> Vec<String> {
+        let doc: Value = serde_json::from_str(fixture).unwrap();
+        validate_spec(&doc)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn valid_document_has_no_findings() {
+        let lines = findings(include_str!("../tests/fixtures/validate/valid.json"));
+        assert!(lines.is_empty(), "{lines:?}");
+    }
+
+    #[test]
+    fn missing_top_level_keys_are_errors() {
+        assert_eq!(
+            findings(include_str!("../tests/fixtures/validate/missing_keys.json")),
+            vec![
+                "error # missing required key 'openapi'",
+                "error # missing required key 'info'",
+                "error # missing required key 'paths'",
+            ]
+        );
+        let lines = findings(include_str!(
+            "../tests/fixtures/validate/paths_optional_in_3_1.json"
+        ));
+        assert!(lines.is_empty(), "{lines:?}");
+    }
+
+    #[test]
+    fn path_keys_must_start_with_a_slash() {
+        assert_eq!(
+            findings(include_str!("../tests/fixtures/validate/bad_path_key.json")),
+            vec!["error #/paths/users path must start with '/'"]
+        );
+    }
+
+    #[test]
+    fn operations_without_responses_are_warnings() {
+        assert_eq!(
+            findings(include_str!(
+                "../tests/fixtures/validate/missing_responses.json"
+            )),
+            vec!["warning #/paths/~1users/get operation has no responses"]
+        );
+    }
+
+    #[test]
+    fn parameters_need_name_and_location() {
+        assert_eq!(
+            findings(include_str!(
+                "../tests/fixtures/validate/bad_parameters.json"
+            )),
+            vec![
+                "error #/paths/~1users~1{id}/parameters/0 parameter is missing 'name'",
+                "error #/paths/~1users~1{id}/get/parameters/1 parameter is missing 'name'",
+                "error #/paths/~1users~1{id}/get/parameters/1 parameter is missing 'in'",
+            ]
+        );
+    }
+
+    #[test]
+    fn local_refs_must_resolve_and_external_refs_are_skipped() {
+        assert_eq!(
+            findings(include_str!(
+                "../tests/fixtures/validate/unresolved_refs.json"
+            )),
+            vec![
+                "error #/paths/~1users/get/responses/200/content/application~1json/schema \
+                 $ref '#/components/schemas/User' does not resolve"
+            ]
+        );
+    }
+
+    #[test]
+    fn operation_ids_must_exist_be_unique_and_be_identifiers() {
+        let fixture = include_str!("../tests/fixtures/validate/operation_ids.json");
+        assert_eq!(
+            findings(fixture),
+            vec![
+                "warning #/paths/~1users/post operation has no operationId",
+                "info #/paths/~1users~1{id}/delete operationId 'delete-user' is not a valid \
+                 identifier; use letters, digits, and underscores",
+                "error #/paths/~1teams/get operationId 'listUsers' is used by 2 operations: \
+                 GET /teams, GET /users",
+                "error #/paths/~1users~1{id}/get operationId 'user' is used by 2 operations: \
+                 GET /users/{id}, PUT /users/{id}",
+            ]
+        );
+
+        let doc: Value = serde_json::from_str(fixture).unwrap();
+        let options = ValidateOptions {
+            severities: BTreeMap::from([
+                (
+                    "operation-id-duplicate".to_string(),
+                    Some(Severity::Warning),
+                ),
+                ("operation-id-format".to_string(), None),
+            ]),
+            examples: false,
+        };
+        let severities: Vec<Severity> = validate_spec_with(&doc, &options)
+            .iter()
+            .map(|finding| finding.severity)
+            .collect();
+        assert_eq!(severities, vec![Severity::Warning; 3]);
+    }
+
+    #[test]
+    fn response_checks_name_the_operation_and_status() {
+        assert_eq!(
+            findings(include_str!("../tests/fixtures/validate/responses.json")),
+            vec![
+                "warning #/paths/~1login/post/responses/302 POST /login response '302' \
+                 defines no Location header",
+                "warning #/paths/~1login/post/responses/303 POST /login response '303' \
+                 defines no Location header",
+                "error #/paths/~1users/get/responses/2OO GET /users response '2OO' is not a \
+                 three-digit status code, an NXX range, or 'default'",
+                "error #/paths/~1users/get/responses/600 GET /users response '600' is not a \
+                 three-digit status code, an NXX range, or 'default'",
+                "error #/paths/~1users/post/responses POST /users has an empty responses object",
+                "warning #/paths/~1users~1{id}/delete/responses/204 DELETE /users/{id} \
+                 response '204' declares content",
+            ]
+        );
+        let clean = findings(include_str!(
+            "../tests/fixtures/validate/responses_clean.json"
+        ));
+        assert!(clean.is_empty(), "{clean:?}");
+    }
+
+    #[test]
+    fn path_collisions_are_grouped_with_how_they_differ() {
+        let fixture = include_str!("../tests/fixtures/validate/path_collisions.json");
+        assert_eq!(
+            findings(fixture),
+            vec![
+                "error #/paths/~1Reports paths collide, differing only in case: \
+                 /Reports, /reports",
+                "error #/paths/~1health paths collide, differing only in trailing slash: \
+                 /health, /health/",
+                "error #/paths/~1orgs~1{orgId}~1Teams~1 paths collide, differing only in \
+                 placeholder names, trailing slash, and case: \
+                 /orgs/{orgId}/Teams/, /orgs/{org}/teams",
+                "error #/paths/~1users~1{id} paths collide, differing only in placeholder \
+                 names: /users/{id}, /users/{userId}",
+            ]
+        );
+    }
+
+    #[test]
+    fn severity_overrides_name_a_known_check_and_level() {
+        assert_eq!(
+            parse_severity_override("operation-id-missing=off"),
+            Ok(("operation-id-missing".to_string(), None))
+        );
+        assert_eq!(
+            parse_severity_override("operation-id-format=error"),
+            Ok(("operation-id-format".to_string(), Some(Severity::Error)))
+        );
+        assert!(parse_severity_override("operation-id-missing").is_err());
+        assert!(parse_severity_override("nope=error").is_err());
+        assert!(parse_severity_override("operation-id-missing=loud").is_err());
+    }
+
+    #[test]
+    fn check_findings_fails_on_errors_or_too_many_warnings() {
+        let warning = Finding {
+            severity: Severity::Warning,
+            path: "#".to_string(),
+            message: "w".to_string(),
+        };
+        let findings = vec![warning.clone(), warning];
+        assert!(check_findings(&findings, None).is_ok());
+        assert!(check_findings(&findings, Some(2)).is_ok());
+        let err = check_findings(&findings, Some(1)).unwrap_err();
+        assert_eq!(err.exit_code(), 6);
+        assert_eq!(
+            err.to_string(),
+            "validation failed: 0 errors, 2 warnings (at most 1 allowed)."
+        );
+
+        let errors = vec![error("#", "e")];
+        assert_eq!(
+            check_findings(&errors, None).unwrap_err().to_string(),
+            "validation failed: 1 error, 0 warnings."
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// How much is written to stderr. Errors are always shown; `-q` drops
+/// everything else, `-v` adds request details, and `-vv` adds request and
+/// response headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verbosity {
+    Quiet,
+    #[default]
+    Normal,
+    Verbose,
+    Debug,
+}
+
+impl Verbosity {
+    pub fn from_flags(verbose: u8, quiet: bool) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+
+    /// Progress and warnings shown by default.
+    pub fn info(self, message: impl Display) {
+        self.emit(Verbosity::Normal, message);
+    }
+
+    /// Details shown with `-v`.
+    pub fn verbose(self, message: impl Display) {
+        self.emit(Verbosity::Verbose, message);
+    }
+
+    /// Details shown with `-vv`.
+    pub fn debug(self, message: impl Display) {
+        self.emit(Verbosity::Debug, message);
+    }
+
+    pub fn shows(self, level: Verbosity) -> bool {
+        self >= level
+    }
+
+    fn emit(self, level: Verbosity, message: impl Display) {
+        if self.shows(level) {
+            eprintln!("{message}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_map_to_levels() {
+        assert_eq!(Verbosity::from_flags(0, false), Verbosity::Normal);
+        assert_eq!(Verbosity::from_flags(1, false), Verbosity::Verbose);
+        assert_eq!(Verbosity::from_flags(3, false), Verbosity::Debug);
+        assert_eq!(Verbosity::from_flags(0, true), Verbosity::Quiet);
+        assert!(Verbosity::Debug.shows(Verbosity::Verbose));
+        assert!(!Verbosity::Quiet.shows(Verbosity::Normal));
+    }
+}
@@ -0,0 +1,226 @@
+//! The OpenAPI version a document declares, and the features whose handling
+//! depends on it.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The specification a document follows, from its top-level `openapi` or
+/// `swagger` field.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SpecVersion {
+    #[value(name = "3.0")]
+    #[serde(rename = "3.0")]
+    OpenApi30,
+    #[value(name = "3.1")]
+    #[serde(rename = "3.1")]
+    OpenApi31,
+    #[value(skip)]
+    #[serde(rename = "2.0")]
+    Swagger20,
+    /// No version field, or one this tool does not know; handled as 3.0.
+    #[default]
+    #[value(skip)]
+    #[serde(rename = "unknown")]
+    Unknown,
+}
+
+impl SpecVersion {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SpecVersion::OpenApi30 => "3.0",
+            SpecVersion::OpenApi31 => "3.1",
+            SpecVersion::Swagger20 => "2.0",
+            SpecVersion::Unknown => "unknown",
+        }
+    }
+
+    /// Classifies `doc` by its `openapi` (3.0.x, 3.1.x) or `swagger` (2.0)
+    /// field.
+    pub fn detect(doc: &Value) -> SpecVersion {
+        SpecVersion::detect_in(doc)
+    }
+
+    pub(crate) fn detect_in(doc: &impl VersionFeatures) -> SpecVersion {
+        if let Some(version) = doc.top_level_str("openapi") {
+            let minor =
+                |prefix: &str| version == prefix || version.starts_with(&format!("{prefix}."));
+            return if minor("3.0") {
+                SpecVersion::OpenApi30
+            } else if minor("3.1") {
+                SpecVersion::OpenApi31
+            } else {
+                SpecVersion::Unknown
+            };
+        }
+        match doc.top_level_str("swagger") {
+            Some("2.0") => SpecVersion::Swagger20,
+            _ => SpecVersion::Unknown,
+        }
+    }
+}
+
+/// What [`SpecVersion::detect`] and [`version_notes`] read from a document,
+/// so a document parsed without a [`Value`] gets the same answers.
+pub(crate) trait VersionFeatures {
+    /// A top-level field, when it is a string.
+    fn top_level_str(&self, key: &str) -> Option<&str>;
+    fn has_top_level(&self, key: &str) -> bool;
+    /// Whether `components` has a `$defs` key at any depth.
+    fn components_have_defs(&self) -> bool;
+    /// Whether any object has an array `type`.
+    fn has_type_array(&self) -> bool;
+}
+
+impl VersionFeatures for Value {
+    fn top_level_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(Value::as_str)
+    }
+
+    fn has_top_level(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn components_have_defs(&self) -> bool {
+        self.get("components")
+            .is_some_and(|components| has_key(components, "$defs"))
+    }
+
+    fn has_type_array(&self) -> bool {
+        has_type_array(self)
+    }
+}
+
+/// The raw `openapi` or `swagger` value of `doc`, for messages.
+pub fn declared_version(doc: &Value) -> Option<&str> {
+    declared_version_in(doc)
+}
+
+pub(crate) fn declared_version_in(doc: &impl VersionFeatures) -> Option<&str> {
+    if doc.has_top_level("openapi") {
+        doc.top_level_str("openapi")
+    } else {
+        doc.top_level_str("swagger")
+    }
+}
+
+/// One line for each feature of `doc` that this tool handles differently
+/// than a reader of `version` might expect, or does not handle at all.
+pub fn version_notes(doc: &Value, version: SpecVersion) -> Vec<String> {
+    version_notes_in(doc, version)
+}
+
+pub(crate) fn version_notes_in(doc: &impl VersionFeatures, version: SpecVersion) -> Vec<String> {
+    let mut notes = Vec::new();
+    match version {
+        SpecVersion::Swagger20 => notes.push(
+            "Swagger 2.0 document: outlines, diffs, and checks expect OpenAPI 3 and \
+             skip `definitions` and body parameters"
+                .to_string(),
+        ),
+        SpecVersion::Unknown => notes.push(match declared_version_in(doc) {
+            Some(declared) => {
+                format!("unrecognized OpenAPI version '{declared}'; handling it as 3.0")
+            }
+            None => "document declares no OpenAPI version; handling it as 3.0".to_string(),
+        }),
+        SpecVersion::OpenApi31 => {
+            if doc.has_top_level("webhooks") {
+                notes.push("OpenAPI 3.1 webhooks are not included in outlines".to_string());
+            }
+            if doc.components_have_defs() {
+                notes.push(
+                    "OpenAPI 3.1 schemas use $defs; outlines keep refs into them as-is".to_string(),
+                );
+            }
+        }
+        SpecVersion::OpenApi30 => {
+            if doc.has_type_array() {
+                notes.push(
+                    "document declares OpenAPI 3.0 but uses 3.1 type arrays; \
+                     they are handled as 3.1"
+                        .to_string(),
+                );
+            }
+        }
+    }
+    notes
+}
+
+fn has_key(value: &Value, key: &str) -> bool {
+    match value {
+        Value::Object(object) => {
+            object.contains_key(key) || object.values().any(|child| has_key(child, key))
+        }
+        Value::Array(items) => items.iter().any(|child| has_key(child, key)),
+        _ => false,
+    }
+}
+
+fn has_type_array(value: &Value) -> bool {
+    match value {
+        Value::Object(object) => {
+            object.get("type").is_some_and(Value::is_array) || object.values().any(has_type_array)
+        }
+        Value::Array(items) => items.iter().any(has_type_array),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn detect_classifies_the_version_fields() {
+        let cases = [
+            (json!({"openapi": "3.0.3"}), SpecVersion::OpenApi30),
+            (json!({"openapi": "3.0"}), SpecVersion::OpenApi30),
+            (json!({"openapi": "3.1.0"}), SpecVersion::OpenApi31),
+            (json!({"openapi": "3.10.0"}), SpecVersion::Unknown),
+            (json!({"openapi": "4.0.0"}), SpecVersion::Unknown),
+            (json!({"openapi": 3.1}), SpecVersion::Unknown),
+            (json!({"swagger": "2.0"}), SpecVersion::Swagger20),
+            (json!({"paths": {}}), SpecVersion::Unknown),
+        ];
+        for (doc, expected) in cases {
+            assert_eq!(SpecVersion::detect(&doc), expected, "{doc}");
+        }
+    }
+
+    #[test]
+    fn notes_name_the_features_that_differ_by_version() {
+        let doc = json!({
+            "openapi": "3.1.0",
+            "webhooks": {"created": {}},
+            "components": {"schemas": {"Tree": {"$defs": {"Node": {}}}}}
+        });
+        assert_eq!(
+            version_notes(&doc, SpecVersion::detect(&doc)),
+            vec![
+                "OpenAPI 3.1 webhooks are not included in outlines",
+                "OpenAPI 3.1 schemas use $defs; outlines keep refs into them as-is",
+            ]
+        );
+
+        let doc = json!({
+            "openapi": "3.0.3",
+            "components": {"schemas": {"Name": {"type": ["string", "null"]}}}
+        });
+        assert_eq!(
+            version_notes(&doc, SpecVersion::detect(&doc)),
+            vec!["document declares OpenAPI 3.0 but uses 3.1 type arrays; they are handled as 3.1"]
+        );
+
+        let doc =
+            json!({"openapi": "3.0.3", "components": {"schemas": {"Name": {"type": "string"}}}});
+        assert!(version_notes(&doc, SpecVersion::detect(&doc)).is_empty());
+
+        let doc = json!({"openapi": "4.0.0"});
+        assert_eq!(
+            version_notes(&doc, SpecVersion::detect(&doc)),
+            vec!["unrecognized OpenAPI version '4.0.0'; handling it as 3.0"]
+        );
+    }
+}
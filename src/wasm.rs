@@ -0,0 +1,76 @@
+//! JavaScript bindings for the `wasm` feature: JSON text in, JSON text out,
+//! with no filesystem, network, or clock access.
+//!
+//! ```text
+//! cargo build --lib --target wasm32-unknown-unknown --no-default-features --features wasm
+//! ```
+//!
+//! Errors reach JavaScript as the same message strings the CLI prints.
+
+use wasm_bindgen::prelude::*;
+
+use crate::config::parse_reduce_list;
+use crate::errors::AppError;
+use crate::fetch::parse_json;
+use crate::outline::{OutlineOptions, outline_openapi};
+use crate::output::serialize_json;
+use crate::transform::reduce_openapi;
+
+/// The default outline of an OpenAPI JSON document, pretty-printed.
+#[wasm_bindgen]
+pub fn outline_json(input: &str) -> Result<String, JsValue> {
+    outline(input).map_err(to_js)
+}
+
+/// Keeps the top-level keys in `keys`, a comma-separated list as for
+/// `--reduce` (`"paths,components"`), pretty-printed.
+#[wasm_bindgen]
+pub fn reduce_json(input: &str, keys: &str) -> Result<String, JsValue> {
+    reduce(input, keys).map_err(to_js)
+}
+
+fn outline(input: &str) -> Result<String, AppError> {
+    let doc = parse_json(input.as_bytes())?;
+    serialize_json(&outline_openapi(&doc, &OutlineOptions::default())?, false)
+}
+
+fn reduce(input: &str, keys: &str) -> Result<String, AppError> {
+    let keys = parse_reduce_list(keys)?;
+    let doc = parse_json(input.as_bytes())?;
+    serialize_json(&reduce_openapi(doc, &keys)?, false)
+}
+
+fn to_js(err: AppError) -> JsValue {
+    JsValue::from_str(&err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outline_matches_the_library() {
+        let input = r#"{"paths":{},"components":{"schemas":{"Id":{"type":"string"}}}}"#;
+        assert_eq!(
+            outline(input).unwrap(),
+            "{\n  \"paths\": {},\n  \"schemas\": {\n    \"Id\": \"string\"\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn reduce_takes_a_reduce_list() {
+        let input = r#"{"openapi":"3.0.3","paths":{"/a":{}},"components":{}}"#;
+        assert_eq!(
+            reduce(input, "paths").unwrap(),
+            "{\n  \"paths\": {\n    \"/a\": {}\n  }\n}"
+        );
+        assert!(matches!(
+            reduce(input, "Paths").unwrap_err(),
+            AppError::Reduce { .. }
+        ));
+        assert!(matches!(
+            reduce("[", "paths").unwrap_err(),
+            AppError::Json { .. }
+        ));
+    }
+}
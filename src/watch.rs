@@ -1,105 +1,1845 @@
-use std::io::{self, IsTerminal, Write};
-use std::sync::Arc;
+use std::collections::BTreeSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Local, SecondsFormat, TimeZone};
+#[cfg(not(target_arch = "wasm32"))]
 use ctrlc;
+use serde_json::Value;
 
-use crate::config::Config;
+use crate::baseline::{Baseline, Drift};
+use crate::cli::LogTimestamps;
+use crate::color::Palette;
+use crate::config::{Config, WatchOptions, normalize_user_url};
+use crate::diff::{ChangeKind, SpecChange, diff_specs, format_changes};
+use crate::duration::duration_ms;
 use crate::errors::AppError;
-use crate::output::{build_outputs, write_outputs};
+use crate::events::{EventOutcome, EventSink, PollEvent};
+#[cfg(not(feature = "http"))]
+use crate::fetch::NO_HTTP_SUPPORT;
+use crate::fetch::http_status;
+use crate::file_watch::{FileWatcher, MtimePoller};
+use crate::hook::ChangeHook;
+use crate::logfile::LogFile;
+use crate::metrics::{TargetMetrics, write_metrics};
+use crate::notify::notify_change;
+use crate::output::{
+    ComparedPayloads, OutputPayloads, check_empty_paths, collect_outputs, write_atomic,
+    write_outline, write_output,
+};
+#[cfg(feature = "http")]
+use crate::sse::{EventStream, StreamRead};
+use crate::verbosity::Verbosity;
 
 const MIN_INTERVAL_MS: u64 = 250;
-const BACKOFF_MAX_MS: u64 = 10_000;
+/// Invalid answers accepted at the URL prompt before keeping the current URL.
+const MAX_PROMPT_ATTEMPTS: usize = 3;
+const DIFF_LINE_LIMIT: usize = 20;
+/// Where SIGUSR2 writes the `--baseline` report without `--baseline-report`.
+const DEFAULT_BASELINE_REPORT: &str = "baseline-report.txt";
+/// `--input` targets are re-read on filesystem events; this is only a safety
+/// net for missed notifications.
+const FILE_RECHECK_MS: u64 = 60 * 60 * 1_000;
 
-pub fn run_watch(config: &mut Config, interval_ms: u64) -> Result<(), AppError> {
-    let shutdown = Arc::new(AtomicBool::new(false));
-    install_ctrlc_handler(shutdown.clone());
+/// Time source for the watch loop's scheduling. [`SystemClock`] is the real
+/// one; a host or a test can pass its own so that a run does not wait in
+/// real time. Log timestamps always come from the wall clock.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// What the watch loop takes from its host: how it is stopped, how it
+/// waits, and where its log lines go. The default is the command line's:
+/// Ctrl-C/SIGTERM and SIGUSR1 handlers, [`SystemClock`], and stderr.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use std::sync::atomic::AtomicBool;
+/// use openapi_snapshot::watch::WatchEnv;
+///
+/// let stop = Arc::new(AtomicBool::new(false));
+/// let env = WatchEnv::new().stop(stop.clone()).output(std::io::sink());
+/// // Hand `env` to `run_watch`; `stop.store(true, ..)` ends the loop.
+/// ```
+pub struct WatchEnv {
+    stop: Option<Arc<AtomicBool>>,
+    clock: Arc<dyn Clock>,
+    output: Option<Arc<Mutex<dyn Write + Send>>>,
+}
+
+impl WatchEnv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ends the loop after the current poll once `stop` is set. No signal
+    /// handlers are installed, so the host keeps its own Ctrl-C handling.
+    pub fn stop(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Writes log lines to `output` instead of stderr. The in-place status
+    /// and health lines are only drawn on a terminal stderr, so they are
+    /// left out.
+    pub fn output(mut self, output: impl Write + Send + 'static) -> Self {
+        self.output = Some(Arc::new(Mutex::new(output)));
+        self
+    }
+}
+
+impl Default for WatchEnv {
+    fn default() -> Self {
+        Self {
+            stop: None,
+            clock: Arc::new(SystemClock),
+            output: None,
+        }
+    }
+}
+
+pub fn run_watch(
+    config: &mut Config,
+    options: &WatchOptions,
+    env: WatchEnv,
+) -> Result<(), AppError> {
+    run_watch_targets(std::slice::from_mut(config), options, env)
+}
+
+/// Polls every target once per interval, sequentially. Each target keeps its
+/// own change detection, backoff, and error state so one failing target does
+/// not affect the others.
+pub fn run_watch_targets(
+    configs: &mut [Config],
+    options: &WatchOptions,
+    env: WatchEnv,
+) -> Result<(), AppError> {
+    #[cfg(not(feature = "http"))]
+    if let Some(url) = configs
+        .iter()
+        .find(|config| config.input.is_none())
+        .map(|config| config.url.as_str())
+        .or(options.sse_url.as_deref())
+    {
+        return Err(AppError::usage(format!("{url}: {NO_HTTP_SUPPORT}")));
+    }
+    let verbosity = configs
+        .first()
+        .map_or(Verbosity::Normal, |config| config.verbosity);
+    let palette = configs
+        .first()
+        .map_or_else(Palette::default, |config| Palette::stderr(config.color));
+    let mut log = WatchLog::new(options.timestamps)
+        .with_verbosity(verbosity)
+        .with_palette(palette);
+    let terminal = match env.output.clone() {
+        Some(output) => {
+            log = log.with_output(output);
+            false
+        }
+        None => io::stderr().is_terminal(),
+    };
+    if let Some(path) = &options.log_file {
+        log = log.with_file(LogFile::open(path)?);
+    }
+    let baseline = match (&options.baseline, configs.first()) {
+        (Some(source), Some(config)) => {
+            let baseline = Baseline::load(config, source)?;
+            log.info(&format!("comparing each poll against baseline {source}"));
+            Some(Arc::new(baseline))
+        }
+        _ => None,
+    };
+    let clock = env.clock.clone();
+    let signals = Signals::install(&env, &log);
+    let mut workers = Workers::default();
+    #[cfg(feature = "http")]
+    if let (Some(url), Some(config)) = (&options.sse_url, configs.first()) {
+        spawn_event_listener(
+            &mut workers,
+            url.clone(),
+            config,
+            options,
+            &signals,
+            log.clone(),
+        );
+    }
+
+    let base_interval = options.interval_ms.max(MIN_INTERVAL_MS);
+    for path in configs.iter().filter_map(|config| config.input.clone()) {
+        spawn_file_watcher(&mut workers, path, base_interval, &signals, log.clone());
+    }
+    let mut targets: Vec<WatchTarget> = configs
+        .iter()
+        .map(|config| {
+            WatchTarget::new(config, base_interval, options, clock.now())
+                .with_baseline(baseline.clone())
+        })
+        .collect();
+    let mut status = StatusLine::new(terminal);
+    let mut stats = WatchStats::new(clock.now());
+    let mut rounds: u64 = 0;
+    let mut jitter = Jitter::new(options.jitter_ms, Jitter::entropy_seed());
+    let mut events = options.events.as_ref().map(EventSink::open).transpose()?;
+    let deadline = options
+        .change_timeout_ms
+        .map(|ms| clock.now() + Duration::from_millis(ms));
+
+    if options.initial_delay_ms > 0 {
+        log.info(&format!(
+            "waiting {} before the first poll",
+            format_delay(options.initial_delay_ms)
+        ));
+    }
+    let initial_wait_ms =
+        options.initial_delay_ms + jitter.initial_offset(base_interval).unwrap_or_default();
+    if initial_wait_ms > 0 && signals.wait(initial_wait_ms) {
+        log.info(&stats.summary(clock.now()));
+        return Ok(());
+    }
+
+    let mut paused = false;
+    loop {
+        if signals.shutdown_requested() {
+            break;
+        }
+        let poll_now = signals.take_poll_now();
+        if poll_now && !paused {
+            // File events are debounced by the interval so a build that
+            // writes in several steps is read once it settles.
+            let now = clock.now();
+            for (config, target) in configs.iter().zip(targets.iter_mut()) {
+                target.next_due = match config.input {
+                    Some(_) => now + Duration::from_millis(base_interval),
+                    None => now,
+                };
+            }
+        }
+        if let Some(baseline) = &baseline
+            && signals.take_report_request()
+        {
+            let path = options
+                .baseline_report
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_BASELINE_REPORT));
+            status.clear(&log);
+            match write_baseline_report(&path, baseline, &targets) {
+                Ok(()) => log.info(&format!("wrote baseline report to {}", path.display())),
+                Err(err) => log.info(&format!("failed to write baseline report: {err}")),
+            }
+        }
+        if signals.take_pause_toggle() {
+            paused = !paused;
+            status.clear(&log);
+            if paused {
+                log.info("paused (SIGUSR1 to resume)");
+            } else {
+                log.info("resumed");
+                let now = clock.now();
+                for target in &mut targets {
+                    target.next_due = now;
+                }
+            }
+        }
+        if paused {
+            if signals.wait_paused() {
+                break;
+            }
+            continue;
+        }
+
+        let now = clock.now();
+        let mut polled = false;
+        let mut exhausted = false;
+        for (config, target) in configs.iter_mut().zip(targets.iter_mut()) {
+            if target.next_due > now {
+                continue;
+            }
+            polled = true;
+            let event = target.poll(config, options, &*clock, &log, &mut status, &mut stats)?;
+            if let Some(sink) = events.as_mut()
+                && let Err(err) = sink.emit(&event)
+            {
+                log.info(&err.to_string());
+            }
+            let delay_ms = match config.input {
+                Some(_) => FILE_RECHECK_MS,
+                None => jitter.apply(target.delay_ms(options, &log)),
+            };
+            target.next_due = clock.now() + Duration::from_millis(delay_ms);
+            exhausted |=
+                options.max_failures > 0 && target.consecutive_failures >= options.max_failures;
+        }
+        let mut drift_changed = false;
+        for target in &mut targets {
+            drift_changed |= std::mem::take(&mut target.drift_changed);
+        }
+        if drift_changed
+            && let (Some(baseline), Some(path)) = (&baseline, &options.baseline_report)
+            && let Err(err) = write_baseline_report(path, baseline, &targets)
+        {
+            log.info(&format!("failed to write baseline report: {err}"));
+        }
+        if polled && let Some(path) = &options.metrics_file {
+            let metrics: Vec<TargetMetrics> = targets
+                .iter()
+                .map(|target| target.metrics.clone())
+                .collect();
+            if let Err(err) = write_metrics(path, &options.metrics_prefix, &metrics) {
+                log.info(&format!("failed to write metrics: {err}"));
+            }
+        }
+        if options.exit_on_change
+            && let Some(target) = targets.iter().find(|target| target.spec_change.is_some())
+        {
+            let keys = target
+                .spec_change
+                .as_ref()
+                .map(|summary| summary.updated_keys.join(", "))
+                .unwrap_or_default();
+            let message = target.line(&format!("spec changed ({keys}); exiting"));
+            status.clear(&log);
+            for target in &mut targets {
+                target.finish(&log);
+            }
+            log.info(&message);
+            return Ok(());
+        }
+        if let (Some(deadline), Some(ms)) = (deadline, options.change_timeout_ms)
+            && clock.now() >= deadline
+        {
+            status.clear(&log);
+            for target in &mut targets {
+                target.finish(&log);
+            }
+            return Err(AppError::timeout(format!(
+                "no spec change within {}",
+                format_elapsed(Duration::from_millis(ms))
+            )));
+        }
+        if polled {
+            rounds += 1;
+            let subject = match &*configs {
+                [config] => config.source(),
+                _ => format!("{} targets", configs.len()),
+            };
+            let healthy = targets.iter().all(|target| target.last_error.is_none());
+            status.health(&log, &stats.health_line(&subject, healthy));
+        }
+
+        if exhausted
+            || options
+                .max_iterations
+                .is_some_and(|max_iterations| rounds >= max_iterations)
+        {
+            status.clear(&log);
+            for target in &mut targets {
+                target.finish(&log);
+            }
+            return match targets
+                .iter_mut()
+                .find_map(|target| target.last_error.take())
+            {
+                Some(err) => Err(err),
+                None => Ok(()),
+            };
+        }
+
+        let next_due = targets
+            .iter()
+            .map(|target| target.next_due)
+            .chain(deadline)
+            .min()
+            .unwrap_or_else(|| clock.now());
+        let sleep_ms = next_due.saturating_duration_since(clock.now()).as_millis() as u64;
+        if signals.wait(sleep_ms) {
+            break;
+        }
+    }
+
+    status.clear(&log);
+    for target in &mut targets {
+        target.finish(&log);
+    }
+    log.info(&stats.summary(clock.now()));
+    Ok(())
+}
+
+/// Per-target watch state.
+struct WatchTarget {
+    label: Option<String>,
+    tracker: ChangeTracker,
+    backoff: Backoff,
+    errors: ErrorDedup,
+    hook: Option<ChangeHook>,
+    prompt: PromptState,
+    /// When this target was first polled; prompting for a new URL waits out
+    /// `--prompt-grace` from here.
+    first_poll: Option<Instant>,
+    consecutive_failures: u64,
+    last_error: Option<AppError>,
+    last_version: Option<String>,
+    /// The version notes last logged, so each is logged once while the
+    /// document keeps them.
+    last_notes: Vec<String>,
+    baseline: Option<Arc<Baseline>>,
+    /// The changes from `baseline` at the last successful poll, and whether
+    /// they differ from the poll before it.
+    drift: Option<Drift>,
+    drift_changed: bool,
+    /// Cache-Control max-age from the last successful poll.
+    max_age: Option<Duration>,
+    cache_noted: bool,
+    /// Consecutive successful polls without a change, for `--adaptive`.
+    unchanged_polls: u64,
+    adaptive_ms: u64,
+    /// Set by the first poll that changes an existing snapshot, for
+    /// `--exit-on-change`.
+    spec_change: Option<ChangeSummary>,
+    metrics: TargetMetrics,
+    next_due: Instant,
+}
+
+impl WatchTarget {
+    fn new(config: &Config, base_interval: u64, options: &WatchOptions, now: Instant) -> Self {
+        Self {
+            label: config.name.clone(),
+            tracker: if options.exit_on_change {
+                ChangeTracker::from_existing(config)
+            } else {
+                ChangeTracker::default()
+            }
+            .with_stable_polls(options.stable_polls),
+            backoff: Backoff::new(base_interval, options.max_backoff_ms),
+            errors: ErrorDedup::default(),
+            hook: options.on_change.clone().map(ChangeHook::new),
+            prompt: if config.url_from_default {
+                PromptState::Default
+            } else {
+                PromptState::Done
+            },
+            first_poll: None,
+            consecutive_failures: 0,
+            last_error: None,
+            last_version: None,
+            last_notes: Vec::new(),
+            baseline: None,
+            drift: None,
+            drift_changed: false,
+            max_age: None,
+            cache_noted: false,
+            unchanged_polls: 0,
+            adaptive_ms: base_interval,
+            spec_change: None,
+            metrics: TargetMetrics::default(),
+            next_due: now,
+        }
+    }
+
+    fn with_baseline(mut self, baseline: Option<Arc<Baseline>>) -> Self {
+        self.baseline = baseline;
+        self
+    }
+
+    /// Compares the polled document with the baseline and logs the summary.
+    fn track_drift(&mut self, config: &Config, outputs: &OutputPayloads, log: &WatchLog) {
+        let Some(baseline) = &self.baseline else {
+            return;
+        };
+        let compared = outputs.comparison(config.strict_bytes);
+        let Some(doc) = &compared.doc else {
+            return;
+        };
+        let drift = baseline.drift_compared(doc);
+        log.info(&self.line(&drift.summary()));
+        if self.drift.as_ref() != Some(&drift) {
+            self.drift = Some(drift);
+            self.drift_changed = true;
+        }
+    }
+
+    fn line(&self, message: &str) -> String {
+        match &self.label {
+            Some(label) => format!("[{label}] {message}"),
+            None => message.to_string(),
+        }
+    }
+
+    /// Delay before this target's next poll: the backoff delay, stretched to
+    /// the server's Cache-Control max-age unless `--ignore-cache-control`.
+    fn delay_ms(&mut self, options: &WatchOptions, log: &WatchLog) -> u64 {
+        let mut delay_ms = self.backoff.delay_ms();
+        if options.adaptive {
+            let adaptive_ms = adaptive_interval_ms(
+                self.backoff.base_ms,
+                options.max_interval_ms,
+                self.unchanged_polls,
+            );
+            if adaptive_ms > self.adaptive_ms {
+                log.info(&self.line(&format!(
+                    "no changes in {}; polling every {}",
+                    plural(self.unchanged_polls as usize, "poll"),
+                    format_delay(adaptive_ms)
+                )));
+            } else if adaptive_ms < self.adaptive_ms {
+                log.info(&self.line(&format!(
+                    "change detected; polling every {} again",
+                    format_delay(adaptive_ms)
+                )));
+            }
+            self.adaptive_ms = adaptive_ms;
+            delay_ms = delay_ms.max(adaptive_ms);
+        }
+        if options.ignore_cache_control {
+            return delay_ms;
+        }
+        let stretched = cache_delay_ms(delay_ms, self.max_age);
+        if stretched > delay_ms && !self.cache_noted {
+            self.cache_noted = true;
+            log.info(&self.line(&format!(
+                "honoring server Cache-Control max-age: polling every {} (use --ignore-cache-control to override)",
+                format_delay(stretched)
+            )));
+        }
+        stretched
+    }
+
+    /// Runs one poll, recording its error in `last_error`, and describes it
+    /// as an event. The returned error is reserved for prompt I/O failures.
+    fn poll(
+        &mut self,
+        config: &mut Config,
+        options: &WatchOptions,
+        clock: &dyn Clock,
+        log: &WatchLog,
+        status: &mut StatusLine,
+        stats: &mut WatchStats,
+    ) -> Result<PollEvent, AppError> {
+        let mut event = PollEvent::new(
+            String::new(),
+            &config.source(),
+            self.label.as_deref(),
+            EventOutcome::Ok,
+        );
+        let first_poll = *self.first_poll.get_or_insert_with(|| clock.now());
+        let result = loop {
+            match collect_outputs(config)
+                .map(|outputs| outputs.with_comparison(config.strict_bytes))
+            {
+                Ok(outputs) => {
+                    self.backoff.reset();
+                    status.clear(log);
+                    // A refused empty document is reported through the error
+                    // dedup, so it is logged once for as long as the server
+                    // keeps serving it rather than on every poll.
+                    let empty_check = check_empty_paths(config, &outputs);
+                    if empty_check.is_ok() {
+                        self.errors.clear(log);
+                    }
+                    if let Some(message) =
+                        version_change(&mut self.last_version, outputs.version.as_deref())
+                    {
+                        log.info(&self.line(&message));
+                    }
+                    if outputs.version_notes != self.last_notes {
+                        for note in &outputs.version_notes {
+                            log.info(&self.line(&format!("warning: {note}")));
+                        }
+                        self.last_notes.clone_from(&outputs.version_notes);
+                    }
+                    self.track_drift(config, &outputs, log);
+                    self.max_age = outputs.max_age;
+                    event.status = outputs.status;
+                    event.bytes = Some(outputs.bytes);
+                    self.metrics.last_success = Local::now().timestamp();
+                    self.prompt.succeeded();
+                    self.metrics.last_payload_bytes = outputs.bytes;
+                    break match empty_check {
+                        Ok(warning) => {
+                            if let Some(warning) = warning {
+                                log.info(&self.line(&warning));
+                            }
+                            self.apply(config, options, log, &outputs, stats, &mut event)
+                        }
+                        Err(err) => {
+                            let message = self.line(&err.to_string());
+                            self.errors.report(&message, log);
+                            Err(err)
+                        }
+                    };
+                }
+                Err(err) => {
+                    self.max_age = None;
+                    let in_grace = err.is_unreachable()
+                        && clock.now().saturating_duration_since(first_poll)
+                            < Duration::from_millis(options.prompt_grace_ms);
+                    if self
+                        .prompt
+                        .should_prompt(&err, in_grace, options.reprompt_after)
+                    {
+                        status.clear(log);
+                        match prompt_for_url(&config.url, &config.doc_path)? {
+                            Some(new_url) => {
+                                let from = if config.url_from_default {
+                                    "from default "
+                                } else {
+                                    ""
+                                };
+                                log.info(&self.line(&format!(
+                                    "Switching watch URL {from}to '{new_url}' after prompt."
+                                )));
+                                config.url = new_url;
+                                config.url_from_default = false;
+                                self.prompt = PromptState::Entered { failures: 0 };
+                                continue;
+                            }
+                            None => self.prompt = PromptState::Done,
+                        }
+                    }
+                    // Ordering: the error text goes through dedup first (so a
+                    // new error is always visible), then the backoff status
+                    // line is redrawn beneath it.
+                    let message = self.line(&err.to_string());
+                    if !self.errors.is_repeat(&message) {
+                        status.clear(log);
+                    }
+                    self.errors.report(&message, log);
+                    if err.is_unreachable() {
+                        let delay_ms = self.backoff.failure();
+                        status.update(
+                            log,
+                            self.line(&format!(
+                                "server unreachable, retrying in {}",
+                                format_delay(delay_ms)
+                            )),
+                        );
+                    } else {
+                        self.backoff.reset();
+                        status.clear(log);
+                    }
+                    break Err(err);
+                }
+            }
+        };
+
+        stats.polls += 1;
+        stats.last_poll = Some(Local::now());
+        match result {
+            Ok(()) => {
+                self.consecutive_failures = 0;
+                self.last_error = None;
+            }
+            Err(err) => {
+                stats.failures += 1;
+                self.consecutive_failures += 1;
+                event.outcome = EventOutcome::Error;
+                event.status = http_status(&err);
+                event.error = Some(err.to_string());
+                self.last_error = Some(err);
+            }
+        }
+        if let Some(message) = self.hook.as_mut().and_then(ChangeHook::reap) {
+            log.info(&self.line(&message));
+        }
+        self.metrics.url = config.source();
+        self.metrics.polls_total += 1;
+        self.metrics.consecutive_failures = self.consecutive_failures;
+        event.url = config.source();
+        event.timestamp = Local::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        Ok(event)
+    }
+
+    fn apply(
+        &mut self,
+        config: &Config,
+        options: &WatchOptions,
+        log: &WatchLog,
+        outputs: &OutputPayloads,
+        stats: &mut WatchStats,
+        event: &mut PollEvent,
+    ) -> Result<(), AppError> {
+        match self.tracker.apply(config, outputs) {
+            Ok(PollOutcome::Changed(summary)) => {
+                self.unchanged_polls = 0;
+                if summary.initial_paths.is_none() {
+                    event.outcome = EventOutcome::Changed;
+                    event.paths_added = Some(summary.added);
+                    event.paths_removed = Some(summary.removed);
+                }
+                if options.stable_polls > 1 && summary.initial_paths.is_none() {
+                    log.info(&self.line(&format!(
+                        "change stable for {}; writing snapshot",
+                        plural(options.stable_polls as usize, "poll")
+                    )));
+                }
+                log.info(&self.line(&summary.log_line()));
+                let kinds = summary
+                    .changes
+                    .iter()
+                    .take(DIFF_LINE_LIMIT)
+                    .map(|change| Some(change.kind))
+                    .chain(std::iter::repeat(None));
+                for (line, kind) in format_changes(&summary.changes, DIFF_LINE_LIMIT)
+                    .iter()
+                    .zip(kinds)
+                {
+                    let line = self.line(&format!("  {line}"));
+                    match kind {
+                        Some(kind) => log.change(&line, kind),
+                        None => log.info(&line),
+                    }
+                }
+                stats.changes += 1;
+                stats.last_change = Some(Local::now());
+                self.metrics.last_change = Local::now().timestamp();
+                if options.notify
+                    && let Err(fallback) = notify_change(&self.line(&summary.notification_body()))
+                {
+                    log.info(&fallback);
+                }
+                if let Some(message) = self.hook.as_mut().and_then(|hook| hook.trigger(config)) {
+                    log.info(&self.line(&message));
+                }
+                if summary.initial_paths.is_none() && !summary.updated_keys.is_empty() {
+                    self.spec_change = Some(summary);
+                }
+                Ok(())
+            }
+            Ok(PollOutcome::Held { seen, required }) => {
+                log.info(&self.line(&format!(
+                    "change detected, holding for confirmation ({seen}/{required} polls)"
+                )));
+                Ok(())
+            }
+            Ok(PollOutcome::Unchanged) => {
+                self.unchanged_polls += 1;
+                event.outcome = EventOutcome::Unchanged;
+                if options.log_every_poll {
+                    log.info(&self.line("snapshot unchanged"));
+                }
+                Ok(())
+            }
+            Err(err) => {
+                let message = self.line(&err.to_string());
+                self.errors.report(&message, log);
+                Err(err)
+            }
+        }
+    }
+
+    /// Flushes pending dedup counts and waits for an in-flight hook.
+    fn finish(&mut self, log: &WatchLog) {
+        self.errors.clear(log);
+        if let Some(message) = self.hook.as_mut().and_then(ChangeHook::wait) {
+            log.info(&self.line(&message));
+        }
+    }
+}
+
+/// Prefixes every watch-mode line with a timestamp (unless disabled) so the
+/// output can be correlated with other logs. Also owns the in-place status
+/// line on a terminal, which is erased before any regular line is printed,
+/// and the optional `--log-file` copy of every line. With `--quiet` only
+/// errors reach the output; the log file still gets everything.
+#[derive(Clone)]
+struct WatchLog {
+    timestamps: Option<LogTimestamps>,
+    status_drawn: Arc<AtomicBool>,
+    output: Arc<Mutex<dyn Write + Send>>,
+    file: Option<Arc<Mutex<LogFile>>>,
+    verbosity: Verbosity,
+    palette: Palette,
+}
+
+impl WatchLog {
+    fn new(timestamps: Option<LogTimestamps>) -> Self {
+        Self {
+            timestamps,
+            status_drawn: Arc::new(AtomicBool::new(false)),
+            output: Arc::new(Mutex::new(io::stderr())),
+            file: None,
+            verbosity: Verbosity::Normal,
+            palette: Palette::default(),
+        }
+    }
+
+    fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    fn with_output(mut self, output: Arc<Mutex<dyn Write + Send>>) -> Self {
+        self.output = output;
+        self
+    }
+
+    fn quiet(&self) -> bool {
+        !self.verbosity.shows(Verbosity::Normal)
+    }
+
+    fn with_file(mut self, file: LogFile) -> Self {
+        self.file = Some(Arc::new(Mutex::new(file)));
+        self
+    }
+
+    fn info(&self, message: &str) {
+        let now = Local::now();
+        if !self.quiet() {
+            self.print(&self.format(&now, message));
+        }
+        self.record(&now, message);
+    }
+
+    /// Like [`WatchLog::info`], but shown even with `--quiet`.
+    fn error(&self, message: &str) {
+        let now = Local::now();
+        self.print(&self.format(&now, message));
+        self.record(&now, message);
+    }
+
+    /// Like [`WatchLog::info`], but colors the terminal copy by `kind`; the
+    /// log file stays plain.
+    fn change(&self, message: &str, kind: ChangeKind) {
+        let now = Local::now();
+        if !self.quiet() {
+            self.print(&self.format(&now, &self.palette.paint(kind, message)));
+        }
+        self.record(&now, message);
+    }
+
+    /// Writes `line` to the output, erasing the status line first.
+    fn print(&self, line: &str) {
+        self.write(|out| {
+            if self.status_drawn.swap(false, Ordering::SeqCst) {
+                write!(out, "\r\x1b[2K")?;
+            }
+            writeln!(out, "{line}")
+        });
+    }
+
+    fn write(&self, write: impl FnOnce(&mut dyn Write) -> io::Result<()>) {
+        if let Ok(mut out) = self.output.lock() {
+            let _ = write(&mut *out).and_then(|()| out.flush());
+        }
+    }
+
+    fn draw_status(&self, line: &str) {
+        self.write(|out| write!(out, "\r\x1b[2K{line}"));
+        self.status_drawn.store(true, Ordering::SeqCst);
+    }
+
+    fn clear_status(&self) {
+        if self.status_drawn.swap(false, Ordering::SeqCst) {
+            self.write(|out| write!(out, "\r\x1b[2K"));
+        }
+    }
+
+    fn status_drawn(&self) -> bool {
+        self.status_drawn.load(Ordering::SeqCst)
+    }
+
+    fn format<Tz: TimeZone>(&self, now: &DateTime<Tz>, message: &str) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        match self.timestamps {
+            None => message.to_string(),
+            Some(LogTimestamps::Time) => format!("{} {message}", now.format("%H:%M:%S")),
+            Some(LogTimestamps::Rfc3339) => format!(
+                "{} {message}",
+                now.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ),
+        }
+    }
+
+    /// Copies a line to `--log-file`, always with a full timestamp since the
+    /// file outlives a single day.
+    fn record<Tz: TimeZone>(&self, now: &DateTime<Tz>, message: &str)
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let line = format!(
+            "{} {message}",
+            now.to_rfc3339_opts(SecondsFormat::Millis, true)
+        );
+        let warning = match file.lock() {
+            Ok(mut file) => file.append(&line),
+            Err(_) => None,
+        };
+        if let Some(warning) = warning {
+            self.print(&warning);
+        }
+    }
+
+    fn stamped(&self, message: &str) -> String {
+        self.format(&Local::now(), message)
+    }
+}
+
+/// Counters reported when watch mode is interrupted, plus the times shown in
+/// the terminal health line.
+struct WatchStats {
+    started: Instant,
+    polls: u64,
+    changes: u64,
+    failures: u64,
+    last_poll: Option<DateTime<Local>>,
+    last_change: Option<DateTime<Local>>,
+}
+
+impl WatchStats {
+    fn new(started: Instant) -> Self {
+        Self {
+            started,
+            polls: 0,
+            changes: 0,
+            failures: 0,
+            last_poll: None,
+            last_change: None,
+        }
+    }
+
+    fn health_line(&self, subject: &str, healthy: bool) -> String {
+        format_health(
+            subject,
+            self.last_poll.as_ref().map(|time| (time, healthy)),
+            self.last_change.as_ref(),
+            self.failures,
+        )
+    }
+
+    fn summary(&self, now: Instant) -> String {
+        format_summary(
+            now.saturating_duration_since(self.started),
+            self.polls,
+            self.changes,
+            self.failures,
+        )
+    }
+}
+
+fn format_summary(elapsed: Duration, polls: u64, changes: u64, failures: u64) -> String {
+    format!(
+        "watch stopped after {}: {}, {}, {}",
+        format_elapsed(elapsed),
+        plural(polls as usize, "poll"),
+        plural(changes as usize, "change"),
+        plural(failures as usize, "failure")
+    )
+}
+
+fn format_health<Tz: TimeZone>(
+    subject: &str,
+    last_poll: Option<(&DateTime<Tz>, bool)>,
+    last_change: Option<&DateTime<Tz>>,
+    failures: u64,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let last_poll = match last_poll {
+        Some((time, true)) => format!("{} OK", time.format("%H:%M:%S")),
+        Some((time, false)) => format!("{} failed", time.format("%H:%M:%S")),
+        None => String::from("pending"),
+    };
+    let last_change = last_change
+        .map(|time| time.format("%H:%M:%S").to_string())
+        .unwrap_or_else(|| String::from("none"));
+    format!(
+        "watching {subject} | last poll {last_poll} | last change {last_change} | {}",
+        plural(failures as usize, "failure")
+    )
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let total = elapsed.as_secs();
+    let (hours, minutes, seconds) = (total / 3_600, (total / 60) % 60, total % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes}m {seconds}s")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum PollOutcome {
+    Unchanged,
+    /// New content seen on `seen` consecutive polls, not yet written.
+    Held {
+        seen: u64,
+        required: u64,
+    },
+    Changed(ChangeSummary),
+}
+
+/// Remembers what the last successful poll wrote so unchanged snapshots are
+/// neither rewritten nor logged.
+#[derive(Default)]
+pub(crate) struct ChangeTracker {
+    primary_hash: Option<u64>,
+    outline_hash: Option<u64>,
+    last_primary: Option<Value>,
+    debounce: Debounce,
+}
+
+impl ChangeTracker {
+    pub(crate) fn with_stable_polls(mut self, required: u64) -> Self {
+        self.debounce = Debounce::new(required);
+        self
+    }
+
+    /// Starts from the snapshot already on disk, if any, so the first poll
+    /// only counts as a change when it differs from that file.
+    pub(crate) fn from_existing(config: &Config) -> Self {
+        let read = |path: Option<&std::path::PathBuf>| {
+            path.and_then(|path| std::fs::read_to_string(path).ok())
+        };
+        let Some(primary) = read(config.out.as_ref()).filter(|_| !config.stdout) else {
+            return Self::default();
+        };
+        let outline = read(config.outline_out.as_ref());
+        let compared = ComparedPayloads::new(&primary, outline.as_deref(), config.strict_bytes);
+        Self {
+            primary_hash: Some(compared.primary_hash),
+            outline_hash: compared.outline_hash,
+            last_primary: compared.doc,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn apply(
+        &mut self,
+        config: &Config,
+        outputs: &OutputPayloads,
+    ) -> Result<PollOutcome, AppError> {
+        let compared = outputs.comparison(config.strict_bytes);
+        let primary_hash = compared.primary_hash;
+        let outline_hash = compared.outline_hash;
+        if self.primary_hash == Some(primary_hash) && self.outline_hash == outline_hash {
+            self.debounce.reset();
+            return Ok(PollOutcome::Unchanged);
+        }
+        // The first snapshot is written right away; only changes are held.
+        if self.primary_hash.is_some()
+            && let Some(seen) = self
+                .debounce
+                .observe(content_key(primary_hash, outline_hash))
+        {
+            return Ok(PollOutcome::Held {
+                seen,
+                required: self.debounce.required,
+            });
+        }
+
+        // Each file is only rewritten when its own content changed, so tools
+        // keyed on the outline's mtime skip description-only edits.
+        let primary_changed = self.primary_hash != Some(primary_hash);
+        let outline_changed = self.outline_hash != outline_hash;
+        if primary_changed {
+            write_output(config, &outputs.primary)?;
+        }
+        if outline_changed {
+            write_outline(config, outputs)?;
+        }
+
+        let current = compared.into_owned().doc;
+        let mut summary = summarize_change(self.last_primary.as_ref(), current.as_ref());
+        if self.primary_hash.is_some() && outline_hash.is_some() {
+            summary.only = match (primary_changed, outline_changed) {
+                (true, false) => Some(ChangedFile::Snapshot),
+                (false, true) => Some(ChangedFile::Outline),
+                _ => None,
+            };
+        }
+        self.primary_hash = Some(primary_hash);
+        self.outline_hash = outline_hash;
+        self.last_primary = current;
+        Ok(PollOutcome::Changed(summary))
+    }
+}
+
+/// Holds a new payload back until it has been seen on `required` consecutive
+/// polls; seeing different content restarts the count.
+#[derive(Debug, Default)]
+struct Debounce {
+    required: u64,
+    pending: Option<(u64, u64)>,
+}
+
+impl Debounce {
+    fn new(required: u64) -> Self {
+        Self {
+            required,
+            pending: None,
+        }
+    }
+
+    /// Returns how many consecutive polls have seen `key` while it is still
+    /// being held, or `None` once it is confirmed.
+    fn observe(&mut self, key: u64) -> Option<u64> {
+        let seen = match self.pending {
+            Some((pending, count)) if pending == key => count + 1,
+            _ => 1,
+        };
+        if seen >= self.required {
+            self.pending = None;
+            None
+        } else {
+            self.pending = Some((key, seen));
+            Some(seen)
+        }
+    }
+
+    fn reset(&mut self) {
+        self.pending = None;
+    }
+}
+
+fn content_key(primary_hash: u64, outline_hash: Option<u64>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (primary_hash, outline_hash).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Remembers the latest `info.version` and describes a change from the
+/// previous one. Documents without a version are ignored.
+fn version_change(last: &mut Option<String>, version: Option<&str>) -> Option<String> {
+    let current = version?;
+    let previous = last.replace(current.to_string())?;
+    (previous != current).then(|| format!("spec version changed: {previous} → {current}"))
+}
+
+/// What changed between two polls, at the granularity of path names and
+/// top-level keys.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ChangeSummary {
+    initial_paths: Option<usize>,
+    added: usize,
+    removed: usize,
+    updated_keys: Vec<String>,
+    /// Operation- and schema-level changes, for the diff lines.
+    changes: Vec<SpecChange>,
+    /// Set when only one of the snapshot and outline files was rewritten.
+    only: Option<ChangedFile>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChangedFile {
+    Snapshot,
+    Outline,
+}
+
+impl ChangeSummary {
+    pub(crate) fn log_line(&self) -> String {
+        if let Some(count) = self.initial_paths {
+            return format!("snapshot written ({})", plural(count, "path"));
+        }
+        match self.only {
+            Some(ChangedFile::Outline) => "outline changed (snapshot unchanged)".to_string(),
+            Some(ChangedFile::Snapshot) => format!("{}; outline unchanged", self.snapshot_line()),
+            None => self.snapshot_line(),
+        }
+    }
+
+    fn snapshot_line(&self) -> String {
+        let mut parts = Vec::new();
+        if self.added > 0 {
+            parts.push(format!("+{}", plural(self.added, "path")));
+        }
+        if self.removed > 0 {
+            parts.push(format!("-{}", plural(self.removed, "path")));
+        }
+        if parts.is_empty() && !self.updated_keys.is_empty() {
+            parts.push(format!("{} updated", self.updated_keys.join(", ")));
+        }
+        if parts.is_empty() {
+            "snapshot changed".to_string()
+        } else {
+            format!("snapshot changed ({})", parts.join(", "))
+        }
+    }
+
+    fn notification_body(&self) -> String {
+        if let Some(count) = self.initial_paths {
+            return format!("snapshot written with {}", plural(count, "path"));
+        }
+        let mut parts = Vec::new();
+        if self.added > 0 {
+            parts.push(format!("{} added", plural(self.added, "path")));
+        }
+        if self.removed > 0 {
+            parts.push(format!("{} removed", plural(self.removed, "path")));
+        }
+        if parts.is_empty() && !self.updated_keys.is_empty() {
+            parts.push(format!("{} updated", self.updated_keys.join(", ")));
+        }
+        if parts.is_empty() {
+            "backend spec changed".to_string()
+        } else {
+            format!("backend spec changed — {}", parts.join(", "))
+        }
+    }
+}
+
+fn summarize_change(previous: Option<&Value>, current: Option<&Value>) -> ChangeSummary {
+    let Some(current) = current else {
+        return ChangeSummary::default();
+    };
+    let Some(previous) = previous else {
+        return ChangeSummary {
+            initial_paths: Some(path_names(current).len()),
+            ..ChangeSummary::default()
+        };
+    };
+
+    let before = path_names(previous);
+    let after = path_names(current);
+    ChangeSummary {
+        initial_paths: None,
+        added: after.difference(&before).count(),
+        removed: before.difference(&after).count(),
+        updated_keys: changed_top_level_keys(previous, current),
+        changes: diff_specs(previous, current),
+        only: None,
+    }
+}
+
+fn path_names(value: &Value) -> BTreeSet<&str> {
+    value
+        .get("paths")
+        .and_then(|paths| paths.as_object())
+        .map(|paths| paths.keys().map(String::as_str).collect())
+        .unwrap_or_default()
+}
+
+fn changed_top_level_keys(previous: &Value, current: &Value) -> Vec<String> {
+    let (Some(before), Some(after)) = (previous.as_object(), current.as_object()) else {
+        return Vec::new();
+    };
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    keys.into_iter()
+        .filter(|key| before.get(*key) != after.get(*key))
+        .cloned()
+        .collect()
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+/// A flag with a condvar, so a thread waiting for it wakes as soon as it is
+/// raised rather than at its next check.
+#[derive(Default)]
+struct Bell {
+    rung: Mutex<bool>,
+    raised: Condvar,
+}
+
+impl Bell {
+    fn ring(&self) {
+        *self.rung.lock().unwrap_or_else(PoisonError::into_inner) = true;
+        self.raised.notify_all();
+    }
+
+    fn is_rung(&self) -> bool {
+        *self.rung.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lowers the flag, returning whether it was raised.
+    fn take(&self) -> bool {
+        std::mem::take(&mut *self.rung.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Blocks for up to `timeout` of real time until the flag is raised.
+    /// Returns whether it was.
+    fn wait(&self, timeout: Duration) -> bool {
+        let rung = self.rung.lock().unwrap_or_else(PoisonError::into_inner);
+        let (rung, _) = self
+            .raised
+            .wait_timeout_while(rung, timeout, |rung| !*rung)
+            .unwrap_or_else(PoisonError::into_inner);
+        *rung
+    }
+}
+
+/// How often a paused loop looks at the signal flags, which a signal
+/// handler sets without waking anyone.
+const PAUSED_CHECK: Duration = Duration::from_millis(50);
+
+/// How often a background thread blocked on something other than its stop
+/// bell, such as filesystem notifications, checks the bell.
+const STOP_CHECK: Duration = Duration::from_millis(100);
+
+/// Process signals watch mode reacts to. The first SIGINT/SIGTERM lets the
+/// current poll finish and the loop exit cleanly; a second one exits
+/// immediately. On Unix, each SIGUSR1 toggles pausing. `poll_now` is rung
+/// by the `--watch-events-url` listener and the `--input` watchers. A
+/// host's [`WatchEnv::stop`] flag takes the place of the signal handlers.
+struct Signals {
+    shutdown: Arc<AtomicBool>,
+    pause_toggle: Arc<AtomicBool>,
+    poll_now: Arc<Bell>,
+    /// Raised by SIGUSR2 to write the `--baseline` report.
+    report: Arc<AtomicBool>,
+    clock: Arc<dyn Clock>,
+    handlers: FlagHandlers,
+}
+
+impl Signals {
+    fn install(env: &WatchEnv, log: &WatchLog) -> Self {
+        let mut signals = Self {
+            shutdown: env.stop.clone().unwrap_or_default(),
+            pause_toggle: Arc::new(AtomicBool::new(false)),
+            poll_now: Arc::default(),
+            report: Arc::new(AtomicBool::new(false)),
+            clock: env.clock.clone(),
+            handlers: FlagHandlers::default(),
+        };
+        if env.stop.is_none() {
+            install_shutdown_handler(&signals.shutdown, log);
+            install_pause_handler(&mut signals.handlers, &signals.pause_toggle, log);
+            install_report_handler(&mut signals.handlers, &signals.report, log);
+        }
+        signals
+    }
+
+    fn shutdown_requested(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+
+    fn take_pause_toggle(&self) -> bool {
+        self.pause_toggle.swap(false, Ordering::SeqCst)
+    }
+
+    fn take_poll_now(&self) -> bool {
+        self.poll_now.take()
+    }
+
+    fn take_report_request(&self) -> bool {
+        self.report.swap(false, Ordering::SeqCst)
+    }
+
+    /// Whether something other than shutdown needs the loop's attention.
+    fn pending(&self) -> bool {
+        self.pause_toggle.load(Ordering::SeqCst)
+            || self.poll_now.is_rung()
+            || self.report.load(Ordering::SeqCst)
+    }
+
+    /// Sleeps up to `sleep_ms`, waking early for shutdown, a pause toggle, or
+    /// a poll request. Returns true when shutdown was requested.
+    fn wait(&self, sleep_ms: u64) -> bool {
+        let sleep_duration = Duration::from_millis(sleep_ms);
+        let slice = Duration::from_millis(50);
+        let mut waited = Duration::from_millis(0);
+        while waited < sleep_duration {
+            if self.shutdown_requested() {
+                return true;
+            }
+            if self.pending() {
+                return false;
+            }
+            let remaining = sleep_duration.saturating_sub(waited);
+            let step = remaining.min(slice);
+            self.clock.sleep(step);
+            waited += step;
+        }
+        self.shutdown_requested()
+    }
+
+    /// Blocks while paused until shutdown, a pause toggle, or a poll
+    /// request. Nothing is scheduled while paused, so this waits in real
+    /// time on the `poll_now` bell instead of on the clock, which a host
+    /// may not let sleep at all. Returns true when shutdown was requested.
+    fn wait_paused(&self) -> bool {
+        loop {
+            if self.shutdown_requested() {
+                return true;
+            }
+            if self.pending() || self.poll_now.wait(PAUSED_CHECK) {
+                return false;
+            }
+        }
+    }
+}
+
+/// The background threads feeding the watch loop. However the loop
+/// returns, dropping this rings their stop bell and waits for them.
+#[derive(Default)]
+struct Workers {
+    stop: Arc<Bell>,
+    handles: Vec<thread::JoinHandle<()>>,
+}
+
+impl Workers {
+    fn spawn(&mut self, name: &str, work: impl FnOnce(&Bell) + Send + 'static) {
+        let stop = self.stop.clone();
+        if let Ok(handle) = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || work(&stop))
+        {
+            self.handles.push(handle);
+        }
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        self.stop.ring();
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Holds the `--watch-events-url` SSE connection on a background thread and
+/// requests a poll for every event, reconnecting with backoff. A quiet
+/// stream returns from its read every `--timeout` to check for stopping.
+#[cfg(feature = "http")]
+fn spawn_event_listener(
+    workers: &mut Workers,
+    url: String,
+    config: &Config,
+    options: &WatchOptions,
+    signals: &Signals,
+    log: WatchLog,
+) {
+    let headers = config.headers.clone();
+    let timeout_ms = config.timeout_ms;
+    let poll_now = signals.poll_now.clone();
+    let mut backoff = Backoff::new(1_000, options.max_backoff_ms);
+    workers.spawn("event-stream", move |stop| {
+        while !stop.is_rung() {
+            let reason = match EventStream::connect(&url, &headers, timeout_ms) {
+                Ok(mut stream) => {
+                    log.info(&format!("listening for spec events on {url}"));
+                    backoff.reset();
+                    loop {
+                        match stream.next_event() {
+                            Ok(StreamRead::Event) => poll_now.ring(),
+                            Ok(StreamRead::Quiet) => {}
+                            Ok(StreamRead::Closed) => break "stream closed".to_string(),
+                            Err(err) => break err.to_string(),
+                        }
+                        if stop.is_rung() {
+                            return;
+                        }
+                    }
+                }
+                Err(err) => err.to_string(),
+            };
+            if stop.is_rung() {
+                return;
+            }
+            let delay_ms = backoff.failure();
+            log.info(&format!(
+                "spec event stream unavailable ({reason}); reconnecting in {}",
+                format_delay(delay_ms)
+            ));
+            if stop.wait(Duration::from_millis(delay_ms)) {
+                return;
+            }
+        }
+    });
+}
+
+/// Requests a poll whenever the `--input` file changes. Uses filesystem
+/// notifications, registering the watch again if it breaks, and falls back
+/// to checking the modification time every `interval_ms` when notifications
+/// are unavailable.
+fn spawn_file_watcher(
+    workers: &mut Workers,
+    path: PathBuf,
+    interval_ms: u64,
+    signals: &Signals,
+    log: WatchLog,
+) {
+    let poll_now = signals.poll_now.clone();
+    let interval = Duration::from_millis(interval_ms);
+    workers.spawn("file-watch", move |stop| {
+        let mut registered = false;
+        while !stop.is_rung() {
+            let mut watcher = match FileWatcher::new(&path) {
+                Ok(watcher) => watcher,
+                Err(_) if registered => {
+                    // The directory is gone; wait for it to come back.
+                    stop.wait(interval);
+                    continue;
+                }
+                Err(err) => {
+                    log.info(&format!(
+                        "file notifications unavailable ({err}); checking {} every {}",
+                        path.display(),
+                        format_delay(interval_ms)
+                    ));
+                    let mut poller = MtimePoller::new(&path);
+                    while !stop.wait(interval) {
+                        if poller.changed() {
+                            poll_now.ring();
+                        }
+                    }
+                    return;
+                }
+            };
+            if registered {
+                log.info(&format!("watching {} again", path.display()));
+                poll_now.ring();
+            }
+            registered = true;
+            // Notifications cannot ring the stop bell, so wait for them in
+            // short slices.
+            while !stop.is_rung() {
+                match watcher.wait_for_change(interval.min(STOP_CHECK)) {
+                    Ok(true) => poll_now.ring(),
+                    Ok(false) => {}
+                    Err(err) => {
+                        log.info(&format!("{err}; re-registering file watch"));
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn install_shutdown_handler(flag: &Arc<AtomicBool>, log: &WatchLog) {
+    let shutdown = flag.clone();
+    let log = log.clone();
+    let handler_log = log.clone();
+    // ctrlc allows one handler per process, so a second loop in the same
+    // program cannot be stopped with Ctrl-C; say so instead of hiding it.
+    if let Err(err) = ctrlc::set_handler(move || {
+        if shutdown.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        handler_log
+            .info("Stopping after the current poll; press Ctrl-C again to exit immediately.");
+    }) {
+        log.info(&format!("warning: Ctrl-C will not stop watch: {err}"));
+    }
+}
+
+/// wasm32 has no signals; `--max-iterations` is the only way to stop.
+#[cfg(target_arch = "wasm32")]
+fn install_shutdown_handler(_flag: &Arc<AtomicBool>, _log: &WatchLog) {}
+
+/// Signal handlers that raise a flag, unregistered when the loop that
+/// installed them returns so they do not outlive it.
+#[derive(Default)]
+struct FlagHandlers {
+    #[cfg(unix)]
+    ids: Vec<signal_hook::SigId>,
+}
+
+#[cfg(unix)]
+impl FlagHandlers {
+    fn register(&mut self, signal: i32, name: &str, flag: &Arc<AtomicBool>, log: &WatchLog) {
+        match signal_hook::flag::register(signal, flag.clone()) {
+            Ok(id) => self.ids.push(id),
+            Err(err) => log.info(&format!("warning: {name} handler not installed: {err}")),
+        }
+    }
+}
 
-    let base_interval = interval_ms.max(MIN_INTERVAL_MS);
-    let mut prompted = false;
-    let mut backoff_ms = base_interval;
-    let mut consecutive_errors: u32 = 0;
+impl Drop for FlagHandlers {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        for id in self.ids.drain(..) {
+            signal_hook::low_level::unregister(id);
+        }
+    }
+}
 
-    loop {
-        if shutdown.load(Ordering::SeqCst) {
-            break;
+#[cfg(unix)]
+fn install_pause_handler(handlers: &mut FlagHandlers, flag: &Arc<AtomicBool>, log: &WatchLog) {
+    handlers.register(signal_hook::consts::SIGUSR1, "SIGUSR1", flag, log);
+}
+
+/// SIGUSR1 does not exist on Windows, so watch mode cannot be paused there.
+#[cfg(not(unix))]
+fn install_pause_handler(_handlers: &mut FlagHandlers, _flag: &Arc<AtomicBool>, _log: &WatchLog) {}
+
+#[cfg(unix)]
+fn install_report_handler(handlers: &mut FlagHandlers, flag: &Arc<AtomicBool>, log: &WatchLog) {
+    handlers.register(signal_hook::consts::SIGUSR2, "SIGUSR2", flag, log);
+}
+
+/// Without SIGUSR2, only `--baseline-report` writes the report.
+#[cfg(not(unix))]
+fn install_report_handler(_handlers: &mut FlagHandlers, _flag: &Arc<AtomicBool>, _log: &WatchLog) {}
+
+/// Writes every target's changes from `baseline`, in target order, each
+/// labelled when it has a name.
+fn write_baseline_report(
+    path: &std::path::Path,
+    baseline: &Baseline,
+    targets: &[WatchTarget],
+) -> Result<(), AppError> {
+    let sections: Vec<String> = targets
+        .iter()
+        .map(|target| {
+            let report = match &target.drift {
+                Some(drift) => drift.report(&baseline.source),
+                None => format!("no successful poll to compare with {}\n", baseline.source),
+            };
+            target.line(&report)
+        })
+        .collect();
+    write_atomic(path, &sections.join("\n"))
+}
+
+/// Poll delay that doubles on each consecutive unreachable-server failure,
+/// capped at the configured maximum, and snaps back to the interval on reset.
+struct Backoff {
+    base_ms: u64,
+    max_ms: u64,
+    current_ms: Option<u64>,
+}
+
+impl Backoff {
+    fn new(base_ms: u64, max_ms: u64) -> Self {
+        Self {
+            base_ms,
+            max_ms: max_ms.max(base_ms),
+            current_ms: None,
         }
+    }
 
-        match build_outputs(config) {
-            Ok(outputs) => {
-                consecutive_errors = 0;
-                backoff_ms = base_interval;
-                if let Err(err) = write_outputs(config, &outputs) {
-                    eprintln!("{err}");
-                }
-            }
-            Err(err) => {
-                if !prompted && config.url_from_default && err.is_url_related() {
-                    if let Some(new_url) = prompt_for_url(&config.url)? {
-                        eprintln!("Switching watch URL from default to '{new_url}' after prompt.");
-                        config.url = new_url;
-                        config.url_from_default = false;
-                        prompted = true;
-                        continue;
-                    }
-                    prompted = true;
-                }
-                consecutive_errors = consecutive_errors.saturating_add(1);
-                backoff_ms = next_backoff(backoff_ms);
-                eprintln!("{err}");
-            }
+    fn failure(&mut self) -> u64 {
+        let next = match self.current_ms {
+            None => self.base_ms,
+            Some(current) => current.saturating_mul(2).min(self.max_ms),
+        };
+        self.current_ms = Some(next);
+        next
+    }
+
+    fn reset(&mut self) {
+        self.current_ms = None;
+    }
+
+    fn delay_ms(&self) -> u64 {
+        self.current_ms.unwrap_or(self.base_ms)
+    }
+}
+
+/// Collapses consecutive identical error messages: the first is printed, the
+/// repeats are counted, and the count is reported once the error clears or a
+/// different one appears.
+#[derive(Default)]
+struct ErrorDedup {
+    last: Option<String>,
+    repeats: u64,
+}
+
+impl ErrorDedup {
+    fn is_repeat(&self, message: &str) -> bool {
+        self.last.as_deref() == Some(message)
+    }
+
+    fn report(&mut self, message: &str, log: &WatchLog) {
+        if self.is_repeat(message) {
+            self.repeats += 1;
+            return;
         }
+        self.clear(log);
+        log.error(message);
+        self.last = Some(message.to_string());
+    }
 
-        let sleep_ms = if consecutive_errors == 0 {
-            base_interval
-        } else {
-            backoff_ms
+    fn clear(&mut self, log: &WatchLog) {
+        if self.last.take().is_some() && self.repeats > 0 {
+            log.error(&format!(
+                "previous error repeated {}",
+                plural(self.repeats as usize, "time")
+            ));
         }
-        .max(MIN_INTERVAL_MS);
+        self.repeats = 0;
+    }
+}
 
-        if wait_with_shutdown(&shutdown, sleep_ms) {
-            break;
+/// Randomizes poll sleeps by up to ±`range_ms` so many watch processes
+/// started together drift apart instead of polling in lockstep.
+struct Jitter {
+    range_ms: u64,
+    state: u64,
+}
+
+impl Jitter {
+    fn new(range_ms: u64, seed: u64) -> Self {
+        Self {
+            range_ms,
+            state: seed,
         }
     }
 
-    Ok(())
+    fn entropy_seed() -> u64 {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        nanos ^ u64::from(std::process::id()).rotate_left(32)
+    }
+
+    /// SplitMix64; plenty for spreading out poll times.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn apply(&mut self, sleep_ms: u64) -> u64 {
+        if self.range_ms == 0 {
+            return sleep_ms;
+        }
+        let span = self.range_ms.saturating_mul(2).saturating_add(1);
+        let offset = self.next_u64() % span;
+        sleep_ms
+            .saturating_add(offset)
+            .saturating_sub(self.range_ms)
+            .max(MIN_INTERVAL_MS)
+    }
+
+    fn initial_offset(&mut self, interval_ms: u64) -> Option<u64> {
+        if self.range_ms == 0 || interval_ms == 0 {
+            return None;
+        }
+        Some(self.next_u64() % interval_ms)
+    }
 }
 
-fn install_ctrlc_handler(flag: Arc<AtomicBool>) {
-    let _ = ctrlc::set_handler(move || {
-        flag.store(true, Ordering::SeqCst);
-    });
+/// A single stderr line that is rewritten in place on a terminal; elsewhere a
+/// message is only printed when it differs from the previous one. When no
+/// message is set, a terminal shows the health line instead.
+struct StatusLine {
+    terminal: bool,
+    last: Option<String>,
 }
 
-fn wait_with_shutdown(shutdown: &Arc<AtomicBool>, sleep_ms: u64) -> bool {
-    let sleep_duration = Duration::from_millis(sleep_ms);
-    let slice = Duration::from_millis(50);
-    let mut waited = Duration::from_millis(0);
-    while waited < sleep_duration {
-        if shutdown.load(Ordering::SeqCst) {
-            return true;
+impl StatusLine {
+    fn new(terminal: bool) -> Self {
+        Self {
+            terminal,
+            last: None,
+        }
+    }
+
+    fn update(&mut self, log: &WatchLog, message: String) {
+        let unchanged = self.last.as_deref() == Some(message.as_str());
+        if log.quiet() {
+            if !unchanged {
+                log.record(&Local::now(), &message);
+                self.last = Some(message);
+            }
+            return;
+        }
+        if self.terminal {
+            if unchanged && log.status_drawn() {
+                return;
+            }
+            log.draw_status(&log.stamped(&message));
+        } else {
+            if unchanged {
+                return;
+            }
+            log.print(&log.stamped(&message));
+        }
+        log.record(&Local::now(), &message);
+        self.last = Some(message);
+    }
+
+    /// Redraws the health line; suppressed entirely when stderr is not a
+    /// terminal or while another status message is showing.
+    fn health(&mut self, log: &WatchLog, line: &str) {
+        if self.terminal && self.last.is_none() {
+            log.draw_status(line);
+        }
+    }
+
+    fn clear(&mut self, log: &WatchLog) {
+        self.last = None;
+        if self.terminal {
+            log.clear_status();
         }
-        let remaining = sleep_duration.saturating_sub(waited);
-        let step = remaining.min(slice);
-        thread::sleep(step);
-        waited += step;
     }
-    shutdown.load(Ordering::SeqCst)
 }
 
-fn next_backoff(current: u64) -> u64 {
-    let doubled = current.saturating_mul(2);
-    doubled.min(BACKOFF_MAX_MS)
+/// Number of consecutive unchanged polls after which `--adaptive` doubles
+/// the interval.
+const ADAPTIVE_STEP_POLLS: u64 = 10;
+
+/// `--adaptive` schedule: start at the configured interval, double it after
+/// every [`ADAPTIVE_STEP_POLLS`] unchanged polls up to `max_ms`, and return
+/// to the interval as soon as a change is seen (`unchanged_polls` resets).
+fn adaptive_interval_ms(base_ms: u64, max_ms: u64, unchanged_polls: u64) -> u64 {
+    let doublings = (unchanged_polls / ADAPTIVE_STEP_POLLS).min(63) as u32;
+    base_ms
+        .saturating_mul(1_u64 << doublings)
+        .min(max_ms)
+        .max(base_ms)
+}
+
+fn cache_delay_ms(delay_ms: u64, max_age: Option<Duration>) -> u64 {
+    max_age.map_or(delay_ms, |max_age| delay_ms.max(duration_ms(max_age)))
+}
+
+fn format_delay(delay_ms: u64) -> String {
+    if delay_ms.is_multiple_of(1_000) {
+        format!("{}s", delay_ms / 1_000)
+    } else {
+        format!("{:.1}s", delay_ms as f64 / 1_000.0)
+    }
 }
 
 pub fn maybe_prompt_for_url(config: &mut Config, err: &AppError) -> Result<bool, AppError> {
     if !config.url_from_default || !err.is_url_related() {
         return Ok(false);
     }
-    if let Some(new_url) = prompt_for_url(&config.url)? {
-        eprintln!("Switching URL from default to '{new_url}' after prompt.");
+    if let Some(new_url) = prompt_for_url(&config.url, &config.doc_path)? {
+        config.verbosity.info(format!(
+            "Switching URL from default to '{new_url}' after prompt."
+        ));
         config.url = new_url;
         config.url_from_default = false;
         return Ok(true);
@@ -107,80 +1847,941 @@ pub fn maybe_prompt_for_url(config: &mut Config, err: &AppError) -> Result<bool,
     Ok(false)
 }
 
-fn prompt_for_url(default_url: &str) -> Result<Option<String>, AppError> {
+fn prompt_for_url(default_url: &str, doc_path: &str) -> Result<Option<String>, AppError> {
     if !io::stdin().is_terminal() {
         return Ok(None);
     }
+    read_url(
+        default_url,
+        doc_path,
+        &mut io::stdin().lock(),
+        &mut io::stderr(),
+    )
+}
 
-    let mut input = String::new();
-    loop {
-        eprint!("OpenAPI URL (default: {default_url}) - enter port or URL: ");
-        io::stdout()
-            .flush()
-            .map_err(|err| AppError::Io(format!("failed to flush prompt: {err}")))?;
-        input.clear();
-        io::stdin()
-            .read_line(&mut input)
-            .map_err(|err| AppError::Io(format!("failed to read input: {err}")))?;
-        let trimmed = input.trim();
-        if trimmed.is_empty() {
+/// Asks for a port or URL on `output` and reads the answer from `input`.
+/// An empty answer, end of input, or too many invalid answers keep the
+/// current URL (`None`). Port and `host:port` answers get `doc_path`.
+fn read_url(
+    default_url: &str,
+    doc_path: &str,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<Option<String>, AppError> {
+    let write_err = |err: io::Error| AppError::io(format!("failed to write prompt: {err}"));
+    let mut line = String::new();
+    for _ in 0..MAX_PROMPT_ATTEMPTS {
+        write!(
+            output,
+            "OpenAPI URL (default: {default_url}) - enter port or URL: "
+        )
+        .and_then(|()| output.flush())
+        .map_err(write_err)?;
+        line.clear();
+        let read = input
+            .read_line(&mut line)
+            .map_err(|err| AppError::io(format!("failed to read input: {err}")).with_source(err))?;
+        let trimmed = line.trim();
+        if read == 0 || trimmed.is_empty() {
             return Ok(None);
         }
-        if let Some(url) = normalize_user_url(trimmed) {
+        if let Some(url) = normalize_user_url(trimmed, doc_path) {
             return Ok(Some(url));
         }
-        eprintln!("Invalid input. Enter a port (e.g., 3000) or full URL.");
+        writeln!(
+            output,
+            "Invalid input. Enter a port (e.g., 3000 for http://localhost:3000{doc_path}) or full URL."
+        )
+        .map_err(write_err)?;
     }
+    writeln!(output, "Too many invalid entries; keeping {default_url}.").map_err(write_err)?;
+    Ok(None)
 }
 
-fn normalize_user_url(input: &str) -> Option<String> {
-    let trimmed = input.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
-    if trimmed.chars().all(|c| c.is_ascii_digit()) {
-        return Some(format!("http://localhost:{trimmed}/api-docs/openapi.json"));
-    }
-    if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
-        return Some(trimmed.to_string());
+/// When a failing URL may be replaced interactively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PromptState {
+    /// Still on the default URL: prompt once it fails (after the grace
+    /// period).
+    Default,
+    /// On a URL entered at the prompt: prompt again once it has failed
+    /// `--reprompt-after` polls in a row.
+    Entered { failures: u64 },
+    /// The prompt was declined or is unavailable.
+    Done,
+}
+
+impl PromptState {
+    /// Records a failed poll and decides whether to prompt now.
+    fn should_prompt(&mut self, err: &AppError, in_grace: bool, reprompt_after: u64) -> bool {
+        if !err.is_url_related() {
+            return false;
+        }
+        match self {
+            PromptState::Default => !in_grace,
+            PromptState::Entered { failures } => {
+                *failures += 1;
+                reprompt_after > 0 && *failures >= reprompt_after
+            }
+            PromptState::Done => false,
+        }
     }
-    if trimmed.contains(':') {
-        return Some(format!("http://{trimmed}/api-docs/openapi.json"));
+
+    fn succeeded(&mut self) {
+        if let PromptState::Entered { failures } = self {
+            *failures = 0;
+        }
     }
-    None
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::validate::ValidateOptions;
+
+    #[test]
+    fn backoff_doubles_until_cap_and_resets() {
+        let mut backoff = Backoff::new(2_000, 10_000);
+        assert_eq!(backoff.delay_ms(), 2_000);
+        let delays: Vec<u64> = (0..5).map(|_| backoff.failure()).collect();
+        assert_eq!(delays, vec![2_000, 4_000, 8_000, 10_000, 10_000]);
+        assert_eq!(backoff.delay_ms(), 10_000);
+        backoff.reset();
+        assert_eq!(backoff.delay_ms(), 2_000);
+    }
+
+    #[test]
+    fn backoff_cap_never_drops_below_interval() {
+        let mut backoff = Backoff::new(5_000, 1_000);
+        assert_eq!(backoff.failure(), 5_000);
+        assert_eq!(backoff.failure(), 5_000);
+    }
+
+    #[test]
+    fn format_delay_uses_seconds() {
+        assert_eq!(format_delay(4_000), "4s");
+        assert_eq!(format_delay(250), "0.2s");
+    }
+
+    fn file_config(out: std::path::PathBuf) -> Config {
+        Config {
+            name: None,
+            url: "http://localhost:3000/api-docs/openapi.json".to_string(),
+            url_from_default: false,
+            doc_path: crate::cli::DEFAULT_PATH.to_string(),
+            input: None,
+            out: Some(out),
+            outline_out: None,
+            temp_dir: None,
+            reduce: Vec::new(),
+            profile: crate::cli::OutputProfile::Full,
+            outline: crate::outline::OutlineOptions::default(),
+            minify: true,
+            timeout_ms: 1_000,
+            headers: Vec::new(),
+            stdout: false,
+            verbosity: crate::verbosity::Verbosity::Normal,
+            validate: false,
+            color: crate::cli::ColorChoice::Auto,
+            fallback_to_last: None,
+            allow_empty: false,
+            strict_bytes: false,
+            verify_refs: None,
+            validate_options: ValidateOptions::default(),
+            require_version: None,
+            check_paths: false,
+            duplicate_keys: None,
+            transforms: crate::transform::Pipeline::default(),
+        }
+    }
+
+    fn payloads(primary: &str) -> OutputPayloads {
+        OutputPayloads {
+            primary: primary.to_string(),
+            outline: None,
+            version: None,
+            status: Some(200),
+            bytes: primary.len(),
+            max_age: None,
+            spec_version: crate::version::SpecVersion::OpenApi30,
+            version_notes: Vec::new(),
+            compared: None,
+        }
+    }
+
+    #[test]
+    fn tracker_skips_write_when_payload_is_unchanged() {
+        let temp = tempfile::tempdir().unwrap();
+        let out = temp.path().join("openapi.json");
+        let config = file_config(out.clone());
+        let mut tracker = ChangeTracker::default();
+
+        let first = tracker
+            .apply(&config, &payloads(r#"{"paths":{"/a":{}}}"#))
+            .unwrap();
+        let PollOutcome::Changed(first) = first else {
+            panic!("expected a change, got {first:?}");
+        };
+        assert_eq!(first.log_line(), "snapshot written (1 path)");
+
+        std::fs::write(&out, "sentinel").unwrap();
+        let second = tracker
+            .apply(&config, &payloads(r#"{"paths":{"/a":{}}}"#))
+            .unwrap();
+        assert_eq!(second, PollOutcome::Unchanged);
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "sentinel");
+
+        let third = tracker
+            .apply(&config, &payloads(r#"{"paths":{"/a":{},"/b":{}}}"#))
+            .unwrap();
+        let PollOutcome::Changed(third) = third else {
+            panic!("expected a change, got {third:?}");
+        };
+        assert_eq!(third.log_line(), "snapshot changed (+1 path)");
+        assert_eq!(
+            third.notification_body(),
+            "backend spec changed — 1 path added"
+        );
+        assert!(std::fs::read_to_string(&out).unwrap().contains("/b"));
+    }
+
+    #[test]
+    fn tracker_ignores_reformatting_unless_strict_bytes() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut config = file_config(temp.path().join("openapi.json"));
+        let mut tracker = ChangeTracker::default();
+        tracker
+            .apply(&config, &payloads(r#"{"paths":{"/a":{"x-weight":1}}}"#))
+            .unwrap();
+        let reformatted = payloads("{\n  \"paths\": {\"/a\": {\"x-weight\": 1.0}}\n}");
+        assert_eq!(
+            tracker.apply(&config, &reformatted).unwrap(),
+            PollOutcome::Unchanged
+        );
+
+        config.strict_bytes = true;
+        let mut tracker = ChangeTracker::default();
+        tracker
+            .apply(&config, &payloads(r#"{"paths":{"/a":{"x-weight":1}}}"#))
+            .unwrap();
+        assert!(matches!(
+            tracker.apply(&config, &reformatted).unwrap(),
+            PollOutcome::Changed(_)
+        ));
+    }
+
+    #[test]
+    fn tracker_reads_the_payloads_compared_when_collected() {
+        let temp = tempfile::tempdir().unwrap();
+        let config = file_config(temp.path().join("openapi.json"));
+        let mut tracker = ChangeTracker::default();
+        tracker
+            .apply(&config, &payloads(r#"{"paths":{"/a":{}}}"#))
+            .unwrap();
+        // The compared document stands in for the payload, which is not
+        // parsed again.
+        let mut outputs = payloads(r#"{"paths":{"/a":{}}}"#);
+        outputs.compared = Some(ComparedPayloads::new(
+            r#"{"paths":{"/a":{},"/b":{}}}"#,
+            None,
+            false,
+        ));
+        let PollOutcome::Changed(summary) = tracker.apply(&config, &outputs).unwrap() else {
+            panic!("expected a change");
+        };
+        assert_eq!(summary.log_line(), "snapshot changed (+1 path)");
+    }
+
+    #[test]
+    fn tracker_writes_when_only_outline_changes() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut config = file_config(temp.path().join("openapi.json"));
+        config.outline_out = Some(temp.path().join("openapi.outline.json"));
+        let mut tracker = ChangeTracker::default();
+        let mut outputs = payloads(r#"{"paths":{}}"#);
+        outputs.outline = Some("{}".to_string());
+        tracker.apply(&config, &outputs).unwrap();
+
+        outputs.outline = Some(r#"{"schemas":{}}"#.to_string());
+        let outcome = tracker.apply(&config, &outputs).unwrap();
+        assert!(matches!(outcome, PollOutcome::Changed(_)));
+    }
+
+    #[test]
+    fn summarize_change_reports_added_removed_and_updated_keys() {
+        let before = serde_json::json!({"paths": {"/a": {}, "/b": {}}, "components": {}});
+        let removed = serde_json::json!({"paths": {"/a": {}}, "components": {}});
+        assert_eq!(
+            summarize_change(Some(&before), Some(&removed)).log_line(),
+            "snapshot changed (-1 path)"
+        );
+
+        let updated = serde_json::json!({"paths": {"/a": {}, "/b": {}}, "components": {"x": 1}});
+        assert_eq!(
+            summarize_change(Some(&before), Some(&updated)).log_line(),
+            "snapshot changed (components updated)"
+        );
+    }
+
+    #[test]
+    fn summary_reports_counts_and_duration() {
+        let summary = format_summary(Duration::from_secs(75), 36, 1, 2);
+        assert_eq!(
+            summary,
+            "watch stopped after 1m 15s: 36 polls, 1 change, 2 failures"
+        );
+    }
+
+    #[test]
+    fn format_elapsed_includes_hours_when_needed() {
+        assert_eq!(format_elapsed(Duration::from_secs(9)), "9s");
+        assert_eq!(format_elapsed(Duration::from_secs(3_725)), "1h 2m 5s");
+    }
+
+    /// A watch output the test can read back.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Captured {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+
+        fn log(&self) -> WatchLog {
+            WatchLog::new(None).with_output(Arc::new(Mutex::new(self.clone())))
+        }
+    }
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A clock that only moves when the loop sleeps.
+    #[cfg(feature = "http")]
+    struct ManualClock {
+        start: Instant,
+        elapsed: Arc<Mutex<Duration>>,
+    }
+
+    #[cfg(feature = "http")]
+    impl ManualClock {
+        fn new() -> (Self, Arc<Mutex<Duration>>) {
+            let elapsed = Arc::new(Mutex::new(Duration::ZERO));
+            let clock = Self {
+                start: Instant::now(),
+                elapsed: elapsed.clone(),
+            };
+            (clock, elapsed)
+        }
+    }
+
+    #[cfg(feature = "http")]
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            self.start + *self.elapsed.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            *self.elapsed.lock().unwrap() += duration;
+        }
+    }
+
+    #[cfg(feature = "http")]
+    fn watch_options(args: &[&str]) -> WatchOptions {
+        let cli = <crate::cli::Cli as clap::Parser>::try_parse_from(
+            ["openapi-snapshot", "watch", "--no-timestamps"]
+                .iter()
+                .chain(args),
+        )
+        .unwrap();
+        let (_, mode) = Config::from_cli(cli).unwrap();
+        let crate::config::Mode::Watch(options) = mode else {
+            panic!("expected watch mode");
+        };
+        *options
+    }
+
+    #[test]
+    fn error_dedup_collapses_repeats_until_cleared() {
+        let out = Captured::default();
+        let log = out.log();
+        let mut errors = ErrorDedup::default();
+        errors.report("HTTP 502: bad gateway", &log);
+        errors.report("HTTP 502: bad gateway", &log);
+        errors.report("HTTP 502: bad gateway", &log);
+        errors.clear(&log);
+        errors.report("HTTP 502: bad gateway", &log);
+        assert_eq!(
+            out.text(),
+            "HTTP 502: bad gateway\nprevious error repeated 2 times\nHTTP 502: bad gateway\n"
+        );
+    }
+
+    #[test]
+    fn error_dedup_reports_count_when_error_changes() {
+        let out = Captured::default();
+        let log = out.log();
+        let mut errors = ErrorDedup::default();
+        errors.report("HTTP 502: bad gateway", &log);
+        errors.report("HTTP 502: bad gateway", &log);
+        errors.report("HTTP 401: unauthorized", &log);
+        errors.clear(&log);
+        assert_eq!(
+            out.text(),
+            "HTTP 502: bad gateway\nprevious error repeated 1 time\nHTTP 401: unauthorized\n"
+        );
+    }
+
+    #[test]
+    fn errors_are_shown_when_quiet() {
+        let out = Captured::default();
+        let log = out.log().with_verbosity(Verbosity::Quiet);
+        log.info("snapshot written (1 path)");
+        ErrorDedup::default().report("HTTP 502: bad gateway", &log);
+        assert_eq!(out.text(), "HTTP 502: bad gateway\n");
+    }
+
+    #[test]
+    fn watch_log_formats_timestamps() {
+        let now = chrono::FixedOffset::east_opt(2 * 3_600)
+            .unwrap()
+            .with_ymd_and_hms(2024, 5, 1, 12, 31, 4)
+            .unwrap();
+
+        assert_eq!(
+            WatchLog::new(Some(LogTimestamps::Time)).format(&now, "snapshot changed"),
+            "12:31:04 snapshot changed"
+        );
+        assert_eq!(
+            WatchLog::new(Some(LogTimestamps::Rfc3339)).format(&now, "snapshot changed"),
+            "2024-05-01T12:31:04+02:00 snapshot changed"
+        );
+        assert_eq!(
+            WatchLog::new(None).format(&now, "snapshot changed"),
+            "snapshot changed"
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn a_set_stop_flag_ends_the_loop_before_polling() {
+        let out = Captured::default();
+        let (clock, _) = ManualClock::new();
+        let env = WatchEnv::new()
+            .stop(Arc::new(AtomicBool::new(true)))
+            .clock(clock)
+            .output(out.clone());
+        let mut config = file_config(std::path::PathBuf::from("unused.json"));
+        run_watch(&mut config, &watch_options(&[]), env).unwrap();
+        assert_eq!(
+            out.text(),
+            "watch stopped after 0s: 0 polls, 0 changes, 0 failures\n"
+        );
+    }
+
+    #[cfg(feature = "http")]
+    fn served_config(server: &httpmock::MockServer, out: std::path::PathBuf) -> Config {
+        let mut config = crate::snapshot::Snapshot::builder()
+            .url(server.url("/openapi.json"))
+            .reduce([crate::config::ReduceKey::Paths])
+            .verbosity(Verbosity::Normal)
+            .build()
+            .unwrap()
+            .config()
+            .clone();
+        config.out = Some(out);
+        config.stdout = false;
+        config
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn loop_runs_max_iterations_on_the_injected_clock() {
+        let server = httpmock::MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path("/openapi.json");
+            then.status(200)
+                .body(r#"{"openapi":"3.0.3","paths":{"/users":{}}}"#);
+        });
+        let temp = tempfile::tempdir().unwrap();
+        let out_path = temp.path().join("openapi.json");
+        let mut config = served_config(&server, out_path.clone());
+        let options = watch_options(&["--interval", "1m", "--max-iterations", "3"]);
+        let out = Captured::default();
+        let (clock, elapsed) = ManualClock::new();
+
+        let started = Instant::now();
+        run_watch(
+            &mut config,
+            &options,
+            WatchEnv::new()
+                .stop(Arc::default())
+                .clock(clock)
+                .output(out.clone()),
+        )
+        .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(*elapsed.lock().unwrap(), Duration::from_secs(120));
+        mock.assert_hits(3);
+        assert_eq!(out.text(), "snapshot written (1 path)\n");
+        assert_eq!(
+            std::fs::read_to_string(&out_path).unwrap(),
+            "{\n  \"paths\": {\n    \"/users\": {}\n  }\n}"
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn loop_collapses_repeated_failures_and_returns_the_last() {
+        let server = httpmock::MockServer::start();
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path("/openapi.json");
+            then.status(404).body("missing");
+        });
+        let temp = tempfile::tempdir().unwrap();
+        let out_path = temp.path().join("openapi.json");
+        let mut config = served_config(&server, out_path.clone());
+        let options = watch_options(&["--max-iterations", "3"]);
+        let out = Captured::default();
+        let (clock, _) = ManualClock::new();
+
+        let err = run_watch(
+            &mut config,
+            &options,
+            WatchEnv::new()
+                .stop(Arc::default())
+                .clock(clock)
+                .output(out.clone()),
+        )
+        .unwrap_err();
+
+        assert_eq!(err.kind(), crate::errors::ErrorKind::Http(404));
+        let text = out.text();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2, "{text}");
+        assert!(lines[0].contains("HTTP 404"), "{text}");
+        assert_eq!(lines[1], "previous error repeated 2 times");
+        assert!(!out_path.exists());
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds_and_floor() {
+        let mut jitter = Jitter::new(500, 42);
+        let mut seen_below = false;
+        let mut seen_above = false;
+        for _ in 0..1_000 {
+            let sleep = jitter.apply(2_000);
+            assert!(
+                (1_500..=2_500).contains(&sleep),
+                "sleep {sleep} out of range"
+            );
+            seen_below |= sleep < 2_000;
+            seen_above |= sleep > 2_000;
+        }
+        assert!(seen_below && seen_above);
+
+        let mut wide = Jitter::new(1_000, 7);
+        for _ in 0..1_000 {
+            assert!(wide.apply(300) >= MIN_INTERVAL_MS);
+        }
+    }
+
+    #[test]
+    fn jitter_disabled_is_a_no_op() {
+        let mut jitter = Jitter::new(0, 42);
+        assert_eq!(jitter.apply(2_000), 2_000);
+        assert_eq!(jitter.initial_offset(2_000), None);
+    }
+
+    #[test]
+    fn jitter_initial_offset_is_a_fraction_of_interval() {
+        let mut jitter = Jitter::new(100, 9);
+        for _ in 0..100 {
+            assert!(jitter.initial_offset(2_000).unwrap() < 2_000);
+        }
+    }
+
+    #[test]
+    fn health_line_reports_last_poll_change_and_failures() {
+        let offset = chrono::FixedOffset::east_opt(0).unwrap();
+        let poll = offset.with_ymd_and_hms(2024, 5, 1, 12, 40, 1).unwrap();
+        let change = offset.with_ymd_and_hms(2024, 5, 1, 12, 13, 45).unwrap();
+        assert_eq!(
+            format_health(
+                "http://localhost:3000/openapi.json",
+                Some((&poll, true)),
+                Some(&change),
+                3
+            ),
+            "watching http://localhost:3000/openapi.json | last poll 12:40:01 OK | last change 12:13:45 | 3 failures"
+        );
+        assert_eq!(
+            format_health("2 targets", Some((&poll, false)), None, 1),
+            "watching 2 targets | last poll 12:40:01 failed | last change none | 1 failure"
+        );
+    }
+
+    #[test]
+    fn health_line_is_suppressed_off_terminal() {
+        let log = WatchLog::new(None);
+        let mut status = StatusLine::new(false);
+        status.health(&log, "watching 2 targets");
+        assert!(!log.status_drawn());
+    }
+
+    #[test]
+    fn tracker_from_existing_compares_against_file_on_disk() {
+        let temp = tempfile::tempdir().unwrap();
+        let out = temp.path().join("openapi.json");
+        std::fs::write(&out, r#"{"paths":{"/a":{}}}"#).unwrap();
+        let config = file_config(out.clone());
+        let mut tracker = ChangeTracker::from_existing(&config);
+
+        let same = tracker
+            .apply(&config, &payloads(r#"{"paths":{"/a":{}}}"#))
+            .unwrap();
+        assert_eq!(same, PollOutcome::Unchanged);
+
+        let changed = tracker
+            .apply(&config, &payloads(r#"{"paths":{"/a":{},"/b":{}}}"#))
+            .unwrap();
+        let PollOutcome::Changed(changed) = changed else {
+            panic!("expected a change, got {changed:?}");
+        };
+        assert_eq!(changed.initial_paths, None);
+        assert_eq!(changed.updated_keys, vec!["paths".to_string()]);
+    }
+
+    #[test]
+    fn version_change_reports_only_differences() {
+        let mut last = None;
+        assert_eq!(version_change(&mut last, Some("1.4.2")), None);
+        assert_eq!(version_change(&mut last, Some("1.4.2")), None);
+        assert_eq!(version_change(&mut last, None), None);
+        assert_eq!(
+            version_change(&mut last, Some("1.5.0")).as_deref(),
+            Some("spec version changed: 1.4.2 → 1.5.0")
+        );
+    }
+
+    #[test]
+    fn debounce_confirms_after_consecutive_identical_polls() {
+        let mut debounce = Debounce::new(3);
+        let script = [
+            (7, Some(1)),
+            (7, Some(2)),
+            (9, Some(1)),
+            (7, Some(1)),
+            (7, Some(2)),
+            (7, None),
+            (9, Some(1)),
+        ];
+        for (step, (key, expected)) in script.into_iter().enumerate() {
+            assert_eq!(debounce.observe(key), expected, "step {step}");
+        }
+        debounce.reset();
+        assert_eq!(debounce.observe(9), Some(1));
+    }
+
+    #[test]
+    fn debounce_of_one_confirms_immediately() {
+        let mut debounce = Debounce::new(1);
+        assert_eq!(debounce.observe(7), None);
+        assert_eq!(debounce.observe(9), None);
+    }
+
+    #[test]
+    fn tracker_holds_changes_until_stable() {
+        let temp = tempfile::tempdir().unwrap();
+        let out = temp.path().join("openapi.json");
+        let config = file_config(out.clone());
+        let mut tracker = ChangeTracker::default().with_stable_polls(2);
+        let old = payloads(r#"{"paths":{"/a":{}}}"#);
+        let new = payloads(r#"{"paths":{"/a":{},"/b":{}}}"#);
+
+        assert!(matches!(
+            tracker.apply(&config, &old).unwrap(),
+            PollOutcome::Changed(_)
+        ));
+        assert_eq!(
+            tracker.apply(&config, &new).unwrap(),
+            PollOutcome::Held {
+                seen: 1,
+                required: 2
+            }
+        );
+        assert_eq!(
+            tracker.apply(&config, &old).unwrap(),
+            PollOutcome::Unchanged
+        );
+        assert!(matches!(
+            tracker.apply(&config, &new).unwrap(),
+            PollOutcome::Held { seen: 1, .. }
+        ));
+        assert!(!std::fs::read_to_string(&out).unwrap().contains("/b"));
+        assert!(matches!(
+            tracker.apply(&config, &new).unwrap(),
+            PollOutcome::Changed(_)
+        ));
+        assert!(std::fs::read_to_string(&out).unwrap().contains("/b"));
+    }
+
+    #[test]
+    fn cache_delay_stretches_only_to_longer_max_age() {
+        assert_eq!(cache_delay_ms(2_000, None), 2_000);
+        assert_eq!(cache_delay_ms(2_000, Some(Duration::from_secs(1))), 2_000);
+        assert_eq!(cache_delay_ms(2_000, Some(Duration::from_secs(2))), 2_000);
+        assert_eq!(cache_delay_ms(2_000, Some(Duration::from_secs(60))), 60_000);
+    }
+
+    #[test]
+    fn summarize_change_includes_operation_diff() {
+        let before = serde_json::json!({"paths": {"/users": {"get": {"responses": {}}}}});
+        let after = serde_json::json!({
+            "paths": {"/users": {"get": {"responses": {}}, "post": {"responses": {}}}}
+        });
+        let summary = summarize_change(Some(&before), Some(&after));
+        assert_eq!(
+            format_changes(&summary.changes, DIFF_LINE_LIMIT),
+            vec!["added: POST /users"]
+        );
+    }
+
+    #[test]
+    fn adaptive_interval_doubles_every_ten_unchanged_polls_up_to_cap() {
+        let schedule: Vec<u64> = [0, 9, 10, 19, 20, 30, 40, 1_000]
+            .into_iter()
+            .map(|polls| adaptive_interval_ms(2_000, 30_000, polls))
+            .collect();
+        assert_eq!(
+            schedule,
+            vec![2_000, 2_000, 4_000, 4_000, 8_000, 16_000, 30_000, 30_000]
+        );
+    }
+
+    #[test]
+    fn adaptive_interval_never_drops_below_base() {
+        assert_eq!(adaptive_interval_ms(2_000, 1_000, 50), 2_000);
+        assert_eq!(adaptive_interval_ms(2_000, 30_000, u64::MAX), 30_000);
+    }
+
+    #[test]
+    fn tracker_rewrites_only_the_file_that_changed() {
+        let temp = tempfile::tempdir().unwrap();
+        let out = temp.path().join("openapi.json");
+        let outline = temp.path().join("openapi.outline.json");
+        let mut config = file_config(out.clone());
+        config.outline_out = Some(outline.clone());
+        let modified =
+            |path: &std::path::Path| std::fs::metadata(path).unwrap().modified().unwrap();
+        let mut tracker = ChangeTracker::default();
+        let mut outputs = payloads(r#"{"info":{"description":"a"},"paths":{}}"#);
+        outputs.outline = Some("{}".to_string());
+        tracker.apply(&config, &outputs).unwrap();
+        let (out_before, outline_before) = (modified(&out), modified(&outline));
+
+        std::thread::sleep(Duration::from_millis(20));
+        outputs.primary = r#"{"info":{"description":"b"},"paths":{}}"#.to_string();
+        let PollOutcome::Changed(summary) = tracker.apply(&config, &outputs).unwrap() else {
+            panic!("expected a change");
+        };
+        assert_eq!(
+            summary.log_line(),
+            "snapshot changed (info updated); outline unchanged"
+        );
+        assert!(modified(&out) > out_before);
+        assert_eq!(modified(&outline), outline_before);
+
+        let out_before = modified(&out);
+        std::thread::sleep(Duration::from_millis(20));
+        outputs.outline = Some(r#"{"schemas":{}}"#.to_string());
+        let PollOutcome::Changed(summary) = tracker.apply(&config, &outputs).unwrap() else {
+            panic!("expected a change");
+        };
+        assert_eq!(summary.log_line(), "outline changed (snapshot unchanged)");
+        assert_eq!(modified(&out), out_before);
+        assert!(modified(&outline) > outline_before);
+    }
+
+    fn answer(lines: &str) -> (Option<String>, String) {
+        let mut output = Vec::new();
+        let url = read_url(
+            "http://localhost:3000/api-docs/openapi.json",
+            "/api-docs/openapi.json",
+            &mut io::Cursor::new(lines.as_bytes()),
+            &mut output,
+        )
+        .unwrap();
+        (url, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn read_url_writes_prompt_to_output_and_accepts_port() {
+        let (url, output) = answer("8080\n");
+        assert_eq!(
+            url.as_deref(),
+            Some("http://localhost:8080/api-docs/openapi.json")
+        );
+        assert_eq!(
+            output,
+            "OpenAPI URL (default: http://localhost:3000/api-docs/openapi.json) - enter port or URL: "
+        );
+    }
+
+    #[test]
+    fn read_url_retries_invalid_input() {
+        let (url, output) = answer("nope\nhttp://api:8080/openapi.json\n");
+        assert_eq!(url.as_deref(), Some("http://api:8080/openapi.json"));
+        assert_eq!(output.matches("Invalid input.").count(), 1);
+    }
+
+    #[test]
+    fn read_url_gives_up_after_max_invalid_attempts() {
+        let (url, output) = answer("a\nb\nc\n3000\n");
+        assert_eq!(url, None);
+        assert_eq!(
+            output.matches("enter port or URL").count(),
+            MAX_PROMPT_ATTEMPTS
+        );
+        assert!(output.ends_with(
+            "Too many invalid entries; keeping http://localhost:3000/api-docs/openapi.json.\n"
+        ));
+    }
+
+    #[test]
+    fn read_url_applies_the_configured_path_to_ports() {
+        let mut output = Vec::new();
+        let url = read_url(
+            "http://localhost:3000/openapi.json",
+            "/openapi.json",
+            &mut io::Cursor::new("nope\n4000\n".as_bytes()),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(url.as_deref(), Some("http://localhost:4000/openapi.json"));
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .contains("3000 for http://localhost:3000/openapi.json")
+        );
+    }
 
     #[test]
-    fn normalize_user_url_accepts_port() {
-        let url = normalize_user_url("3001").unwrap();
-        assert_eq!(url, "http://localhost:3001/api-docs/openapi.json");
+    fn read_url_keeps_current_url_on_empty_answer_or_eof() {
+        assert_eq!(answer("\n").0, None);
+        assert_eq!(answer("").0, None);
     }
 
     #[test]
-    fn normalize_user_url_accepts_full_url() {
-        let url = normalize_user_url("https://example.com/openapi.json").unwrap();
-        assert_eq!(url, "https://example.com/openapi.json");
+    fn prompt_state_reprompts_after_repeated_failures_of_entered_url() {
+        let unreachable = AppError::unreachable("connection refused".to_string());
+        let mut state = PromptState::Default;
+        assert!(!state.should_prompt(&unreachable, true, 3));
+        assert!(state.should_prompt(&unreachable, false, 3));
+
+        let mut state = PromptState::Entered { failures: 0 };
+        assert!(!state.should_prompt(&unreachable, false, 3));
+        assert!(!state.should_prompt(&unreachable, false, 3));
+        state.succeeded();
+        assert!(!state.should_prompt(&unreachable, false, 3));
+        assert!(!state.should_prompt(&unreachable, false, 3));
+        assert!(state.should_prompt(&unreachable, false, 3));
+
+        let mut state = PromptState::Entered { failures: 0 };
+        assert!(!state.should_prompt(&AppError::io("disk full".to_string()), false, 1));
+        assert!(!PromptState::Entered { failures: 5 }.should_prompt(&unreachable, false, 0));
+        assert!(!PromptState::Done.should_prompt(&unreachable, false, 1));
+    }
+
+    /// A clock that never sleeps and counts how often it was asked to.
+    #[derive(Clone, Default)]
+    struct CountingClock(Arc<std::sync::atomic::AtomicUsize>);
+
+    impl Clock for CountingClock {
+        fn now(&self) -> Instant {
+            Instant::now()
+        }
+
+        fn sleep(&self, _duration: Duration) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
     }
 
     #[test]
-    fn normalize_user_url_accepts_host_port() {
-        let url = normalize_user_url("localhost:4000").unwrap();
-        assert_eq!(url, "http://localhost:4000/api-docs/openapi.json");
+    fn paused_waits_block_instead_of_spinning_on_the_clock() {
+        let clock = CountingClock::default();
+        let stop = Arc::new(AtomicBool::new(false));
+        let env = WatchEnv::new().stop(stop.clone()).clock(clock.clone());
+        let signals = Signals::install(&env, &Captured::default().log());
+        let poll_now = signals.poll_now.clone();
+        let started = Instant::now();
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(200));
+                poll_now.ring();
+            });
+            assert!(!signals.wait_paused());
+        });
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert_eq!(clock.0.load(Ordering::SeqCst), 0);
+
+        stop.store(true, Ordering::SeqCst);
+        assert!(signals.wait_paused());
     }
 
+    #[cfg(unix)]
     #[test]
-    fn normalize_user_url_rejects_invalid() {
-        assert!(normalize_user_url("not a url").is_none());
+    fn flag_handlers_are_unregistered_when_dropped() {
+        let log = Captured::default().log();
+        let first = Arc::new(AtomicBool::new(false));
+        let mut handlers = FlagHandlers::default();
+        handlers.register(signal_hook::consts::SIGUSR2, "SIGUSR2", &first, &log);
+        signal_hook::low_level::raise(signal_hook::consts::SIGUSR2).unwrap();
+        assert!(first.swap(false, Ordering::SeqCst));
+        drop(handlers);
+
+        let second = Arc::new(AtomicBool::new(false));
+        let mut handlers = FlagHandlers::default();
+        handlers.register(signal_hook::consts::SIGUSR2, "SIGUSR2", &second, &log);
+        signal_hook::low_level::raise(signal_hook::consts::SIGUSR2).unwrap();
+        assert!(second.load(Ordering::SeqCst));
+        assert!(!first.load(Ordering::SeqCst));
     }
 
     #[test]
-    fn backoff_clamps() {
-        assert_eq!(next_backoff(250), 500);
-        assert_eq!(next_backoff(5_000), 10_000);
-        assert_eq!(next_backoff(20_000), 10_000);
+    fn background_threads_stop_and_are_joined_when_the_loop_returns() {
+        let temp = tempfile::tempdir().unwrap();
+        let watched = temp.path().join("openapi.json");
+        std::fs::write(&watched, "{}").unwrap();
+        let env = WatchEnv::new().stop(Arc::default());
+        let log = Captured::default().log();
+        let signals = Signals::install(&env, &log);
+        let mut workers = Workers::default();
+        // With an hour between checks, only the stop bell ends them quickly:
+        // one thread waits on notifications, the other polls the mtime of
+        // a file whose directory does not exist.
+        let hour = 60 * 60 * 1_000;
+        spawn_file_watcher(&mut workers, watched, hour, &signals, log.clone());
+        let missing = temp.path().join("missing/openapi.json");
+        spawn_file_watcher(&mut workers, missing, hour, &signals, log);
+        let finished = Arc::new(AtomicBool::new(false));
+        let flag = finished.clone();
+        workers.spawn("test", move |stop| {
+            while !stop.wait(Duration::from_secs(60 * 60)) {}
+            flag.store(true, Ordering::SeqCst);
+        });
+        assert_eq!(workers.handles.len(), 3);
+
+        let started = Instant::now();
+        drop(workers);
+        assert!(finished.load(Ordering::SeqCst));
+        assert!(started.elapsed() < Duration::from_secs(5));
     }
 }
@@ -1,10 +1,13 @@
 use assert_cmd::cargo::cargo_bin_cmd;
+#[cfg(feature = "http")]
 use httpmock::prelude::*;
+use predicates::prelude::PredicateBooleanExt;
 use predicates::str::contains;
 use serde_json::Value;
 use std::fs;
 use tempfile::tempdir;
 
+#[cfg(feature = "http")]
 fn mock_server_with_body(body: &str) -> MockServer {
     let server = MockServer::start();
     server.mock(|when, then| {
@@ -16,12 +19,13 @@ fn mock_server_with_body(body: &str) -> MockServer {
     server
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn writes_pretty_output_by_default() {
     let server =
         mock_server_with_body(r#"{"openapi":"3.0.3","paths":{"/health":{}},"components":{}}"#);
     let temp = tempdir().unwrap();
-    let out_path = temp.path().join("openapi.min.json");
+    let out_path = temp.path().join("openapi.json");
     let mut cmd = cargo_bin_cmd!("openapi-snapshot");
     cmd.arg("--url")
         .arg(server.url("/openapi.json"))
@@ -35,6 +39,7 @@ fn writes_pretty_output_by_default() {
     assert!(parsed.get("paths").is_some());
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn reduces_output_to_paths_and_components() {
     let server = mock_server_with_body(
@@ -58,6 +63,7 @@ fn reduces_output_to_paths_and_components() {
     assert!(parsed.get("info").is_none());
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn outline_profile_outputs_paths_and_schemas_only() {
     let server = mock_server_with_body(
@@ -82,6 +88,7 @@ fn outline_profile_outputs_paths_and_schemas_only() {
     assert!(parsed.get("info").is_none());
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn writes_outline_out_when_requested() {
     let server = mock_server_with_body(
@@ -108,6 +115,7 @@ fn writes_outline_out_when_requested() {
     assert!(outline_json.get("schemas").is_some());
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn non_200_returns_exit_code_1() {
     let server = MockServer::start();
@@ -125,6 +133,7 @@ fn non_200_returns_exit_code_1() {
     cmd.assert().failure().code(1);
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn invalid_json_returns_exit_code_2() {
     let server = mock_server_with_body("not-json");
@@ -138,6 +147,7 @@ fn invalid_json_returns_exit_code_2() {
     cmd.assert().failure().code(2);
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn reduce_missing_key_returns_exit_code_3() {
     let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{}}"#);
@@ -153,6 +163,7 @@ fn reduce_missing_key_returns_exit_code_3() {
     cmd.assert().failure().code(3);
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn reduce_empty_list_returns_exit_code_3() {
     let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{}}"#);
@@ -171,6 +182,7 @@ fn reduce_empty_list_returns_exit_code_3() {
         .stderr(contains("reduce list cannot be empty"));
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn outline_profile_rejects_reduce_flag() {
     let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{"/health":{}}}"#);
@@ -191,6 +203,7 @@ fn outline_profile_rejects_reduce_flag() {
         .stderr(contains("not supported with --profile outline"));
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn outline_profile_rejects_outline_out() {
     let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{"/health":{}}}"#);
@@ -212,6 +225,7 @@ fn outline_profile_rejects_outline_out() {
         .stderr(contains("--outline-out is not supported"));
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn outline_profile_rejects_query_param_missing_name() {
     let server = mock_server_with_body(
@@ -232,6 +246,7 @@ fn outline_profile_rejects_query_param_missing_name() {
         .stderr(contains("query parameter missing name"));
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn outline_profile_rejects_response_missing_schema() {
     let server = mock_server_with_body(
@@ -252,6 +267,7 @@ fn outline_profile_rejects_response_missing_schema() {
         .stderr(contains("content missing schema"));
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn reduce_rejects_unsupported_key() {
     let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{},"components":{}}"#);
@@ -270,6 +286,7 @@ fn reduce_rejects_unsupported_key() {
         .stderr(contains("unsupported reduce value"));
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn outline_profile_rejects_malformed_paths() {
     let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{"/health":[]}}"#);
@@ -288,6 +305,7 @@ fn outline_profile_rejects_malformed_paths() {
         .stderr(contains("path item must be an object"));
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn stdout_writes_output_without_file() {
     let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{}}"#);
@@ -301,6 +319,7 @@ fn stdout_writes_output_without_file() {
     assert!(!out_path.exists());
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn minify_true_writes_single_line() {
     let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{}}"#);
@@ -319,6 +338,32 @@ fn minify_true_writes_single_line() {
     assert!(!contents.contains('\n'));
 }
 
+#[cfg(feature = "http")]
+#[test]
+fn min_json_output_name_implies_minify_unless_pretty() {
+    let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{}}"#);
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.min.json");
+    snapshot_cmd(&server, &["--out", out_path.to_str().unwrap()])
+        .assert()
+        .success();
+    assert!(!fs::read_to_string(&out_path).unwrap().contains('\n'));
+
+    snapshot_cmd(&server, &["--out", out_path.to_str().unwrap(), "--pretty"])
+        .assert()
+        .success();
+    assert!(fs::read_to_string(&out_path).unwrap().contains("\n  "));
+
+    snapshot_cmd(
+        &server,
+        &["--out", out_path.to_str().unwrap(), "--pretty", "--minify"],
+    )
+    .assert()
+    .code(1)
+    .stderr(contains("--minify true contradicts --pretty true"));
+}
+
+#[cfg(feature = "http")]
 #[test]
 fn directory_as_output_returns_exit_code_4() {
     let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{}}"#);
@@ -331,6 +376,7 @@ fn directory_as_output_returns_exit_code_4() {
     cmd.assert().failure().code(4);
 }
 
+#[cfg(feature = "http")]
 #[test]
 fn creates_output_directory_if_missing() {
     let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{}}"#);
@@ -354,3 +400,3546 @@ fn help_includes_example() {
         .stdout(contains("Examples:"))
         .stdout(contains("openapi-snapshot watch"));
 }
+
+#[cfg(feature = "http")]
+#[test]
+fn outline_compat_v1_restores_ref_objects() {
+    let server = mock_server_with_body(
+        r##"{"openapi":"3.0.3","paths":{},"components":{"schemas":{"Alias":{"$ref":"#/components/schemas/User"},"User":{"type":"object"}}}}"##,
+    );
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--profile")
+        .arg("outline")
+        .arg("--outline-compat")
+        .arg("v1")
+        .arg("--stdout");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let parsed: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(
+        parsed["schemas"]["Alias"]["$ref"],
+        Value::String("#/components/schemas/User".to_string())
+    );
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn outline_flags_shape_the_outline() {
+    let server = mock_server_with_body(
+        r##"{"openapi":"3.0.3","paths":{"/users/{id}":{"get":{"summary":"Fetch a user","parameters":[{"in":"path","name":"id","schema":{"type":"string"}}],"responses":{"200":{"content":{"application/json":{"schema":{"$ref":"#/components/schemas/User"}}}},"204":{"description":"none"}}}}},"components":{"schemas":{"User":{"type":"object","properties":{"address":{"type":"object","properties":{"city":{"type":"string"}}}}}}}}"##,
+    );
+    snapshot_cmd(&server, &["--profile", "outline", "--stdout"])
+        .assert()
+        .code(3)
+        .stderr(contains("non-query parameter"));
+
+    let output = snapshot_cmd(
+        &server,
+        &[
+            "--profile",
+            "outline",
+            "--stdout",
+            "--outline-lenient",
+            "--outline-short-refs",
+            "--outline-descriptions",
+            "--outline-depth",
+            "1",
+        ],
+    )
+    .assert()
+    .success()
+    .get_output()
+    .stdout
+    .clone();
+    let parsed: Value = serde_json::from_slice(&output).unwrap();
+    let op = &parsed["paths"]["/users/{id}"]["get"];
+    assert_eq!(op["summary"], "Fetch a user");
+    assert_eq!(op["query"], serde_json::json!([]));
+    assert_eq!(op["responses"]["200"], "User");
+    assert_eq!(op["responses"]["204"], Value::Null);
+    assert_eq!(parsed["schemas"]["User"]["properties"]["address"], "object");
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_once_writes_snapshot_and_exits() {
+    let server = mock_server_with_body(
+        r#"{"openapi":"3.0.3","info":{"title":"x"},"paths":{"/health":{}},"components":{"schemas":{}}}"#,
+    );
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.json");
+    let outline_path = temp.path().join("openapi.outline.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(&out_path)
+        .arg("--outline-out")
+        .arg(&outline_path)
+        .arg("watch")
+        .arg("--once");
+    cmd.assert()
+        .success()
+        .stderr(contains("snapshot written (1 path)"));
+
+    let parsed: Value = serde_json::from_str(&fs::read_to_string(&out_path).unwrap()).unwrap();
+    assert!(parsed.get("paths").is_some());
+    assert!(parsed.get("info").is_none());
+    assert!(outline_path.exists());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_max_iterations_returns_last_error() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/openapi.json");
+        then.status(404).body("missing");
+    });
+    let temp = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(temp.path().join("openapi.json"))
+        .arg("watch")
+        .arg("--max-iterations")
+        .arg("2")
+        .arg("--interval-ms")
+        .arg("10");
+    cmd.assert().failure().code(9).stderr(contains("HTTP 404"));
+    mock.assert_hits(2);
+}
+
+#[cfg(feature = "http")]
+#[cfg(unix)]
+#[test]
+fn watch_exits_cleanly_with_summary_on_sigint() {
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use std::time::Duration;
+
+    let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{},"components":{}}"#);
+    let temp = tempdir().unwrap();
+    // Watch writes the default outline relative to the working directory.
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("openapi-snapshot"))
+        .current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(temp.path().join("openapi.json"))
+        .arg("watch")
+        .arg("--interval-ms")
+        .arg("250")
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(800));
+    let status = Command::new("kill")
+        .arg("-INT")
+        .arg(child.id().to_string())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stderr.contains("watch stopped after"), "stderr: {stderr}");
+    assert!(stderr.contains("1 change"), "stderr: {stderr}");
+}
+
+#[cfg(feature = "http")]
+#[cfg(unix)]
+#[test]
+fn watch_on_change_runs_command_with_snapshot_path() {
+    let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{},"components":{}}"#);
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.json");
+    let marker = temp.path().join("marker.txt");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(&out_path)
+        .arg("watch")
+        .arg("--once")
+        .arg("--on-change")
+        .arg(format!(
+            "printf '%s' \"$SNAPSHOT_PATH\" > '{}'; exit 3",
+            marker.display()
+        ));
+    cmd.assert()
+        .success()
+        .stderr(contains("on-change command exited with"));
+
+    let recorded = fs::read_to_string(&marker).unwrap();
+    assert_eq!(recorded, out_path.display().to_string());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_max_failures_exits_with_last_error() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/openapi.json");
+        then.status(500).body("backend never came up");
+    });
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(&out_path)
+        .arg("watch")
+        .arg("--max-failures")
+        .arg("2")
+        .arg("--interval-ms")
+        .arg("10");
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(contains("backend never came up"));
+    assert!(!out_path.exists());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_polls_multiple_targets_independently() {
+    let api = mock_server_with_body(
+        r#"{"openapi":"3.0.3","paths":{"/users":{}},"components":{"schemas":{}}}"#,
+    );
+    let admin = MockServer::start();
+    admin.mock(|when, then| {
+        when.method(GET).path("/openapi.json");
+        then.status(404).body("admin missing");
+    });
+    let temp = tempdir().unwrap();
+    let api_out = temp.path().join("api.json");
+    let admin_out = temp.path().join("admin.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(api.url("/openapi.json"))
+        .arg("--out")
+        .arg(&api_out)
+        .arg("--url")
+        .arg(admin.url("/openapi.json"))
+        .arg("--out")
+        .arg(&admin_out)
+        .arg("watch")
+        .arg("--once");
+    cmd.assert()
+        .failure()
+        .code(9)
+        .stderr(contains("[api] snapshot written (1 path)"))
+        .stderr(contains("[admin] HTTP 404"));
+
+    assert!(api_out.exists());
+    assert!(temp.path().join("api.outline.json").exists());
+    assert!(!admin_out.exists());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn snapshot_writes_each_url_out_pair() {
+    let api = mock_server_with_body(SPEC_V1);
+    let admin = mock_server_with_body(SPEC_V2);
+    let temp = tempdir().unwrap();
+    let api_out = temp.path().join("api.json");
+    let admin_out = temp.path().join("admin.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(api.url("/openapi.json"))
+        .arg("--out")
+        .arg(&api_out)
+        .arg("--url")
+        .arg(admin.url("/openapi.json"))
+        .arg("--out")
+        .arg(&admin_out)
+        .arg("--reduce")
+        .arg("paths");
+    cmd.assert().success();
+    let api_json: Value = serde_json::from_str(&fs::read_to_string(&api_out).unwrap()).unwrap();
+    let admin_json: Value = serde_json::from_str(&fs::read_to_string(&admin_out).unwrap()).unwrap();
+    assert!(api_json["paths"].get("/users").is_some());
+    assert!(admin_json["paths"].get("/teams").is_some());
+    assert!(api_json["paths"].get("/teams").is_none());
+    assert!(api_json.get("components").is_none());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn snapshot_pairs_report_each_failure_with_the_worst_exit_code() {
+    let api = mock_server_with_body(SPEC_V1);
+    let broken = mock_server_with_body("not json");
+    let temp = tempdir().unwrap();
+    let api_out = temp.path().join("api.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(api.url("/missing.json"))
+        .arg("--out")
+        .arg(temp.path().join("missing.json"))
+        .arg("--url")
+        .arg(broken.url("/openapi.json"))
+        .arg("--out")
+        .arg(temp.path().join("broken.json"))
+        .arg("--url")
+        .arg(api.url("/openapi.json"))
+        .arg("--out")
+        .arg(&api_out);
+    cmd.assert().failure().code(9).stderr(
+        contains("target 'missing' failed:")
+            .and(contains("target 'broken' failed:"))
+            .and(contains("2 of 3 targets failed: missing, broken")),
+    );
+    assert!(api_out.exists());
+}
+
+#[test]
+fn snapshot_rejects_one_out_for_several_urls() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg("http://localhost:1/a.json")
+        .arg("--url")
+        .arg("http://localhost:1/b.json")
+        .arg("--out")
+        .arg("a.json");
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(contains("each --url needs a matching --out"));
+}
+
+const SPEC_V1: &str = r#"{"openapi":"3.0.3","paths":{"/users":{}},"components":{"schemas":{}}}"#;
+const SPEC_V2: &str =
+    r#"{"openapi":"3.0.3","paths":{"/users":{},"/teams":{}},"components":{"schemas":{}}}"#;
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_exit_on_change_waits_for_spec_to_change() {
+    let server = MockServer::start();
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.json");
+
+    std::thread::scope(|scope| {
+        let server = &server;
+        let mut first = server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(SPEC_V1);
+        });
+        scope.spawn(move || {
+            while first.hits() < 2 {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            first.delete();
+            server.mock(|when, then| {
+                when.method(GET).path("/openapi.json");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(SPEC_V2);
+            });
+        });
+
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path())
+            .arg("--url")
+            .arg(server.url("/openapi.json"))
+            .arg("--out")
+            .arg(&out_path)
+            .arg("--reduce")
+            .arg("paths")
+            .arg("watch")
+            .arg("--interval-ms")
+            .arg("250")
+            .arg("--exit-on-change")
+            .arg("--change-timeout")
+            .arg("30s");
+        cmd.assert()
+            .success()
+            .stderr(contains("snapshot changed (+1 path)"))
+            .stderr(contains("spec changed (paths); exiting"));
+    });
+
+    let contents = fs::read_to_string(&out_path).unwrap();
+    assert!(contents.contains("/teams"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_exit_on_change_times_out_with_distinct_code() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(&out_path)
+        .arg("watch")
+        .arg("--interval-ms")
+        .arg("250")
+        .arg("--exit-on-change")
+        .arg("--change-timeout")
+        .arg("1s");
+    cmd.assert()
+        .failure()
+        .code(5)
+        .stderr(contains("no spec change within 1s"));
+}
+
+#[test]
+fn watch_change_timeout_requires_exit_on_change() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("watch").arg("--change-timeout").arg("5s");
+    cmd.assert().failure().stderr(contains("--exit-on-change"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_accepts_interval_duration() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(&out_path)
+        .arg("--timeout")
+        .arg("5s")
+        .arg("watch")
+        .arg("--interval")
+        .arg("250ms")
+        .arg("--once");
+    cmd.assert().success();
+    assert!(out_path.exists());
+}
+
+#[test]
+fn duration_flags_reject_bad_values_and_conflicts() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("watch").arg("--interval").arg("5").arg("--once");
+    cmd.assert().failure().code(1).stderr(contains(
+        "invalid duration for --interval: '5' (missing unit",
+    ));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--timeout").arg("0s").arg("--stdout");
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(contains("--timeout must be greater than zero."));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--timeout")
+        .arg("5s")
+        .arg("--timeout-ms")
+        .arg("5000");
+    cmd.assert()
+        .failure()
+        .stderr(contains("cannot be used with"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_logs_info_version_change_even_when_output_is_identical() {
+    let server = MockServer::start();
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.json");
+    let spec = |version: &str| {
+        format!(
+            r#"{{"openapi":"3.0.3","info":{{"title":"x","version":"{version}"}},"paths":{{"/users":{{}}}},"components":{{"schemas":{{}}}}}}"#
+        )
+    };
+
+    std::thread::scope(|scope| {
+        let server = &server;
+        let old_body = spec("1.4.2");
+        let new_body = spec("1.5.0");
+        let mut first = server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(&old_body);
+        });
+        scope.spawn(move || {
+            while first.hits() < 1 {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            first.delete();
+            server.mock(|when, then| {
+                when.method(GET).path("/openapi.json");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(&new_body);
+            });
+        });
+
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path())
+            .arg("--url")
+            .arg(server.url("/openapi.json"))
+            .arg("--out")
+            .arg(&out_path)
+            .arg("--reduce")
+            .arg("paths")
+            .arg("watch")
+            .arg("--interval-ms")
+            .arg("500")
+            .arg("--max-iterations")
+            .arg("2");
+        cmd.assert()
+            .success()
+            .stderr(contains("spec version changed: 1.4.2 → 1.5.0"))
+            .stderr(contains("snapshot changed").not());
+    });
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_events_writes_one_json_line_per_poll() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.json");
+    let events_path = temp.path().join("events.ndjson");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(&out_path)
+        .arg("watch")
+        .arg("--interval-ms")
+        .arg("250")
+        .arg("--max-iterations")
+        .arg("2")
+        .arg("--events")
+        .arg(&events_path);
+    cmd.assert().success();
+
+    let contents = fs::read_to_string(&events_path).unwrap();
+    let events: Vec<Value> = contents
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["outcome"], "ok");
+    assert_eq!(events[0]["status"], 200);
+    assert_eq!(events[0]["bytes"], SPEC_V1.len());
+    assert_eq!(events[0]["url"], server.url("/openapi.json"));
+    assert!(events[0]["timestamp"].is_string());
+    assert_eq!(events[1]["outcome"], "unchanged");
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_events_to_stdout_reports_errors() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/openapi.json");
+        then.status(404).body("missing");
+    });
+    let temp = tempdir().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(temp.path().join("openapi.json"))
+        .arg("watch")
+        .arg("--once")
+        .arg("--events")
+        .arg("-");
+    let output = cmd.output().unwrap();
+    assert_eq!(output.status.code(), Some(9));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let event: Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(event["outcome"], "error");
+    assert_eq!(event["status"], 404);
+    assert!(event["error"].as_str().unwrap().contains("missing"));
+}
+
+#[test]
+fn watch_events_stdout_conflicts_with_stdout_payload() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--stdout").arg("watch").arg("--events").arg("-");
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(contains("--events - cannot be combined with --stdout"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_honors_cache_control_max_age() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/openapi.json");
+        then.status(200)
+            .header("content-type", "application/json")
+            .header("cache-control", "max-age=1")
+            .body(SPEC_V1);
+    });
+    let temp = tempdir().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(temp.path().join("openapi.json"))
+        .arg("watch")
+        .arg("--interval-ms")
+        .arg("250")
+        .arg("--max-iterations")
+        .arg("2");
+    let started = std::time::Instant::now();
+    cmd.assert().success().stderr(contains(
+        "honoring server Cache-Control max-age: polling every 1s",
+    ));
+    assert!(started.elapsed() >= std::time::Duration::from_millis(900));
+    mock.assert_hits(2);
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(temp.path().join("openapi.json"))
+        .arg("watch")
+        .arg("--interval-ms")
+        .arg("250")
+        .arg("--max-iterations")
+        .arg("2")
+        .arg("--ignore-cache-control");
+    cmd.assert()
+        .success()
+        .stderr(contains("Cache-Control").not());
+}
+
+#[cfg(feature = "http")]
+#[cfg(unix)]
+#[test]
+fn watch_pauses_and_resumes_on_sigusr1() {
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use std::time::Duration;
+
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/openapi.json");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(SPEC_V1);
+    });
+    let temp = tempdir().unwrap();
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("openapi-snapshot"))
+        .current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(temp.path().join("openapi.json"))
+        .arg("watch")
+        .arg("--interval-ms")
+        .arg("250")
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let signal = |name: &str| {
+        let status = Command::new("kill")
+            .arg(format!("-{name}"))
+            .arg(child.id().to_string())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+
+    thread::sleep(Duration::from_millis(600));
+    signal("USR1");
+    thread::sleep(Duration::from_millis(300));
+    let paused_hits = mock.hits();
+    thread::sleep(Duration::from_millis(800));
+    assert_eq!(mock.hits(), paused_hits);
+
+    signal("USR1");
+    thread::sleep(Duration::from_millis(300));
+    assert!(mock.hits() > paused_hits);
+    signal("INT");
+
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stderr.matches("paused (SIGUSR1 to resume)").count(),
+        1,
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("resumed"), "stderr: {stderr}");
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_logs_endpoint_diff_on_change() {
+    let server = MockServer::start();
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.json");
+    let before = r#"{"openapi":"3.0.3","paths":{"/users":{"get":{"responses":{"200":{"description":"ok","content":{"application/json":{"schema":{"type":"string"}}}}}}},"/legacy/export":{"get":{"responses":{}}}},"components":{"schemas":{}}}"#;
+    let after = r#"{"openapi":"3.0.3","paths":{"/users":{"get":{"responses":{"200":{"description":"ok","content":{"application/json":{"schema":{"type":"integer"}}}}}}},"/invoices/{id}/void":{"post":{"responses":{}}}},"components":{"schemas":{}}}"#;
+
+    std::thread::scope(|scope| {
+        let server = &server;
+        let mut first = server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(before);
+        });
+        scope.spawn(move || {
+            while first.hits() < 1 {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            first.delete();
+            server.mock(|when, then| {
+                when.method(GET).path("/openapi.json");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(after);
+            });
+        });
+
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path())
+            .arg("--url")
+            .arg(server.url("/openapi.json"))
+            .arg("--out")
+            .arg(&out_path)
+            .arg("watch")
+            .arg("--no-timestamps")
+            .arg("--interval-ms")
+            .arg("500")
+            .arg("--max-iterations")
+            .arg("2");
+        cmd.assert()
+            .success()
+            .stderr(contains(
+                "  added: POST /invoices/{id}/void\n  removed: GET /legacy/export\n  modified: GET /users (response schema)\n",
+            ));
+    });
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_writes_default_outline_unless_disabled() {
+    let server = mock_server_with_body(SPEC_V1);
+
+    let temp = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("watch")
+        .arg("--once");
+    cmd.assert().success();
+    assert!(temp.path().join("openapi/backend_openapi.json").exists());
+    assert!(
+        temp.path()
+            .join("openapi/backend_openapi.outline.json")
+            .exists()
+    );
+
+    let temp = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("watch")
+        .arg("--once")
+        .arg("--no-outline");
+    cmd.assert().success();
+    assert!(temp.path().join("openapi/backend_openapi.json").exists());
+    assert!(
+        !temp
+            .path()
+            .join("openapi")
+            .join("backend_openapi.outline.json")
+            .exists()
+    );
+}
+
+#[test]
+fn watch_no_outline_conflicts_with_outline_out() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--outline-out")
+        .arg("outline.json")
+        .arg("watch")
+        .arg("--once")
+        .arg("--no-outline");
+    cmd.assert().failure().code(1).stderr(contains(
+        "--no-outline cannot be combined with --outline-out.",
+    ));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_initial_delay_postpones_first_poll() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/openapi.json");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(SPEC_V1);
+    });
+    let temp = tempdir().unwrap();
+
+    let started = std::time::Instant::now();
+    std::thread::scope(|scope| {
+        let mock = &mock;
+        scope.spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            assert_eq!(mock.hits(), 0);
+        });
+
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path())
+            .arg("--url")
+            .arg(server.url("/openapi.json"))
+            .arg("--out")
+            .arg(temp.path().join("openapi.json"))
+            .arg("watch")
+            .arg("--once")
+            .arg("--initial-delay")
+            .arg("1s");
+        cmd.assert()
+            .success()
+            .stderr(contains("waiting 1s before the first poll"));
+    });
+    assert!(started.elapsed() >= std::time::Duration::from_secs(1));
+    mock.assert_hits(1);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_events_url_triggers_polls_between_intervals() {
+    let server = MockServer::start();
+    let spec = server.mock(|when, then| {
+        when.method(GET).path("/openapi.json");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(SPEC_V1);
+    });
+    let events = server.mock(|when, then| {
+        when.method(GET)
+            .path("/events")
+            .header("accept", "text/event-stream");
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body("event: spec\ndata: changed\n\n");
+    });
+    let temp = tempdir().unwrap();
+
+    let started = std::time::Instant::now();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(temp.path().join("openapi.json"))
+        .arg("watch")
+        .arg("--interval")
+        .arg("1m")
+        .arg("--max-iterations")
+        .arg("2")
+        .arg("--watch-events-url")
+        .arg(server.url("/events"));
+    cmd.timeout(std::time::Duration::from_secs(30));
+    cmd.assert()
+        .success()
+        .stderr(contains("listening for spec events on"));
+    assert!(started.elapsed() < std::time::Duration::from_secs(30));
+    spec.assert_hits(2);
+    assert!(events.hits() >= 1);
+}
+
+#[test]
+fn snapshot_reads_local_input_file() {
+    let temp = tempdir().unwrap();
+    let input = temp.path().join("generated.json");
+    std::fs::write(&input, SPEC_V1).unwrap();
+    let out = temp.path().join("openapi.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--input")
+        .arg(&input)
+        .arg("--out")
+        .arg(&out)
+        .arg("--reduce")
+        .arg("paths");
+    cmd.assert().success();
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+    assert!(written.get("paths").is_some());
+    assert!(written.get("components").is_none());
+}
+
+#[test]
+fn input_conflicts_with_url() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--input")
+        .arg("generated.json")
+        .arg("--url")
+        .arg("http://localhost:3000/openapi.json");
+    cmd.assert().failure().stderr(contains("--url"));
+}
+
+#[test]
+fn watch_input_rewrites_output_when_file_changes() {
+    let temp = tempdir().unwrap();
+    let input = temp.path().join("build").join("openapi.json");
+    std::fs::create_dir_all(input.parent().unwrap()).unwrap();
+    std::fs::write(&input, SPEC_V1).unwrap();
+    let out = temp.path().join("openapi").join("openapi.json");
+
+    std::thread::scope(|scope| {
+        let (input, out) = (&input, &out);
+        scope.spawn(move || {
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+            while !out.exists() && std::time::Instant::now() < deadline {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            // Replace the file the way build tools do: write aside, then rename.
+            let staged = input.with_extension("tmp");
+            std::fs::write(&staged, SPEC_V2).unwrap();
+            std::fs::rename(&staged, input).unwrap();
+        });
+
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path())
+            .arg("--input")
+            .arg(input)
+            .arg("--out")
+            .arg(out)
+            .arg("watch")
+            .arg("--interval")
+            .arg("250ms")
+            .arg("--max-iterations")
+            .arg("2");
+        cmd.timeout(std::time::Duration::from_secs(30));
+        cmd.assert().success().stderr(contains("snapshot changed"));
+    });
+    let written = std::fs::read_to_string(&out).unwrap();
+    assert!(written.contains("/teams"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_metrics_file_tracks_polls() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET).path("/openapi.json");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(SPEC_V1);
+    });
+    let temp = tempdir().unwrap();
+    let metrics_path = temp.path().join("metrics").join("openapi.prom");
+    let metric = |text: &str, name: &str| -> u64 {
+        text.lines()
+            .find(|line| line.starts_with(&format!("snap_{name}{{")))
+            .and_then(|line| line.rsplit(' ').next())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_else(|| panic!("missing {name} in:\n{text}"))
+    };
+
+    let first = std::thread::scope(|scope| {
+        let metrics_path = &metrics_path;
+        let reader = scope.spawn(move || {
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+            while !metrics_path.exists() && std::time::Instant::now() < deadline {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            std::fs::read_to_string(metrics_path).unwrap()
+        });
+
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path())
+            .arg("--url")
+            .arg(server.url("/openapi.json"))
+            .arg("--out")
+            .arg(temp.path().join("openapi.json"))
+            .arg("watch")
+            .arg("--interval")
+            .arg("1s")
+            .arg("--max-iterations")
+            .arg("2")
+            .arg("--metrics-file")
+            .arg(metrics_path)
+            .arg("--metrics-prefix")
+            .arg("snap");
+        cmd.assert().success();
+        reader.join().unwrap()
+    });
+    let last = std::fs::read_to_string(&metrics_path).unwrap();
+
+    assert!(first.contains("# TYPE snap_polls_total counter"));
+    assert!(first.contains(&format!(
+        "snap_polls_total{{url=\"{}\"}} 1",
+        server.url("/openapi.json")
+    )));
+    assert_eq!(metric(&last, "polls_total"), 2);
+    assert_eq!(metric(&last, "consecutive_failures"), 0);
+    assert_eq!(metric(&last, "last_payload_bytes"), SPEC_V1.len() as u64);
+    assert!(metric(&last, "last_success_timestamp_seconds") > 0);
+    assert!(metric(&last, "last_change_timestamp_seconds") > 0);
+    mock.assert_hits(2);
+}
+
+#[test]
+fn metrics_prefix_rejects_invalid_names() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("watch")
+        .arg("--metrics-file")
+        .arg("metrics.prom")
+        .arg("--metrics-prefix")
+        .arg("bad-prefix");
+    cmd.assert()
+        .failure()
+        .stderr(contains("invalid --metrics-prefix 'bad-prefix'"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_log_file_records_the_session_with_timestamps() {
+    let server = MockServer::start();
+    let temp = tempdir().unwrap();
+    let log_path = temp.path().join("logs").join("watch.log");
+
+    std::thread::scope(|scope| {
+        let server = &server;
+        let mut first = server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(SPEC_V1);
+        });
+        scope.spawn(move || {
+            while first.hits() < 1 {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            first.delete();
+            server.mock(|when, then| {
+                when.method(GET).path("/openapi.json");
+                then.status(403).body("forbidden");
+            });
+        });
+
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path())
+            .arg("--url")
+            .arg(server.url("/openapi.json"))
+            .arg("--out")
+            .arg(temp.path().join("openapi.json"))
+            .arg("watch")
+            .arg("--interval")
+            .arg("500ms")
+            .arg("--max-iterations")
+            .arg("2")
+            .arg("--no-timestamps")
+            .arg("--log-file")
+            .arg(&log_path);
+        cmd.assert()
+            .failure()
+            .stderr(contains("snapshot written (1 path)"));
+    });
+
+    let contents = std::fs::read_to_string(&log_path).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert!(lines.len() >= 2, "{contents}");
+    assert!(
+        lines[0].ends_with(" snapshot written (1 path)"),
+        "{contents}"
+    );
+    assert!(
+        lines[1].ends_with(" HTTP 403 Forbidden: forbidden"),
+        "{contents}"
+    );
+    for line in &lines {
+        let (timestamp, _) = line.split_once(' ').unwrap();
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(timestamp).is_ok(),
+            "{line}"
+        );
+    }
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_leaves_outline_untouched_when_only_descriptions_change() {
+    const DESCRIBED_V1: &str = r#"{"openapi":"3.0.3","paths":{"/users":{"get":{"description":"List users","responses":{"200":{"description":"ok","content":{"application/json":{"schema":{"type":"object"}}}}}}}},"components":{"schemas":{}}}"#;
+    const DESCRIBED_V2: &str = r#"{"openapi":"3.0.3","paths":{"/users":{"get":{"description":"List every user","responses":{"200":{"description":"ok","content":{"application/json":{"schema":{"type":"object"}}}}}}}},"components":{"schemas":{}}}"#;
+    let server = MockServer::start();
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    let outline = temp.path().join("openapi.outline.json");
+    let modified = |path: &std::path::Path| std::fs::metadata(path).unwrap().modified().unwrap();
+
+    let mtimes = std::thread::scope(|scope| {
+        let (server, out, outline) = (&server, &out, &outline);
+        let mut first = server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(DESCRIBED_V1);
+        });
+        let swapper = scope.spawn(move || {
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+            while (first.hits() < 1 || !outline.exists()) && std::time::Instant::now() < deadline {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            let mtimes = (modified(out), modified(outline));
+            first.delete();
+            server.mock(|when, then| {
+                when.method(GET).path("/openapi.json");
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(DESCRIBED_V2);
+            });
+            mtimes
+        });
+
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.arg("--url")
+            .arg(server.url("/openapi.json"))
+            .arg("--out")
+            .arg(out)
+            .arg("--outline-out")
+            .arg(outline)
+            .arg("--reduce")
+            .arg("paths")
+            .arg("watch")
+            .arg("--interval")
+            .arg("500ms")
+            .arg("--max-iterations")
+            .arg("2");
+        cmd.assert().success().stderr(contains(
+            "snapshot changed (paths updated); outline unchanged",
+        ));
+        swapper.join().unwrap()
+    });
+
+    assert!(modified(&out) > mtimes.0);
+    assert_eq!(modified(&outline), mtimes.1);
+    assert!(
+        std::fs::read_to_string(&out)
+            .unwrap()
+            .contains("List every user")
+    );
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn config_file_supplies_defaults_and_flags_override_it() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("openapi-snapshot.toml"),
+        format!(
+            "url = \"{}\"\nout = \"from-config.json\"\nreduce = \"paths\"\ncolour = \"auto\"\n",
+            server.url("/openapi.json")
+        ),
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path());
+    cmd.assert()
+        .success()
+        .stderr(contains("unknown key 'colour' ignored"));
+    let written = fs::read_to_string(temp.path().join("from-config.json")).unwrap();
+    assert!(!written.contains("components"));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--out")
+        .arg("from-flag.json");
+    cmd.assert().success();
+    assert!(temp.path().join("from-flag.json").exists());
+}
+
+#[test]
+fn config_file_type_errors_are_usage_errors() {
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("settings.toml"),
+        "[watch]\ninterval-ms = \"fast\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--config")
+        .arg("settings.toml");
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(contains("invalid value for 'watch.interval-ms' in"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn environment_variables_sit_between_flags_and_config_file() {
+    let server = httpmock::MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(httpmock::Method::GET)
+            .path("/openapi.json")
+            .header("x-team", "api")
+            .header("authorization", "Bearer t");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(SPEC_V1);
+    });
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("openapi-snapshot.toml"),
+        "url = \"http://127.0.0.1:9/unused.json\"\nout = \"from-config.json\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .env("OPENAPI_SNAPSHOT_URL", server.url("/openapi.json"))
+        .env("OPENAPI_SNAPSHOT_OUT", "from-env.json")
+        .env("OPENAPI_SNAPSHOT_REDUCE", "paths")
+        .env("OPENAPI_SNAPSHOT_HEADER", "X-Team: api")
+        .env("OPENAPI_SNAPSHOT_HEADER_1", "Authorization: Bearer t");
+    cmd.assert().success();
+    let written = fs::read_to_string(temp.path().join("from-env.json")).unwrap();
+    assert!(!written.contains("components"));
+    assert!(!temp.path().join("from-config.json").exists());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .env("OPENAPI_SNAPSHOT_URL", server.url("/openapi.json"))
+        .env("OPENAPI_SNAPSHOT_OUT", "from-env.json")
+        .env("OPENAPI_SNAPSHOT_HEADER", "X-Team: api")
+        .env("OPENAPI_SNAPSHOT_HEADER_1", "Authorization: Bearer t")
+        .arg("--out")
+        .arg("from-flag.json");
+    cmd.assert().success();
+    assert!(temp.path().join("from-flag.json").exists());
+    mock.assert_hits(2);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn help_lists_environment_variables() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--help");
+    cmd.assert().success().stdout(
+        contains("[env: OPENAPI_SNAPSHOT_URL=]").and(contains("OPENAPI_SNAPSHOT_HEADER_1")),
+    );
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn target_all_snapshots_each_section_and_reports_failures() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("openapi-snapshot.toml"),
+        format!(
+            "[defaults]\nreduce = \"paths\"\n\n\
+             [target.api]\nurl = \"{}\"\nout = \"api.json\"\n\n\
+             [target.broken]\nurl = \"{}\"\nout = \"broken.json\"\n\n\
+             [target.full]\nurl = \"{}\"\nout = \"full.json\"\nreduce = \"paths,components\"\n",
+            server.url("/openapi.json"),
+            server.url("/missing.json"),
+            server.url("/openapi.json"),
+        ),
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path()).arg("--target").arg("all");
+    cmd.assert()
+        .failure()
+        .code(9)
+        .stderr(contains("target 'broken' failed:").and(contains("1 of 3 targets failed: broken")));
+    let api = fs::read_to_string(temp.path().join("api.json")).unwrap();
+    assert!(!api.contains("components"));
+    let full = fs::read_to_string(temp.path().join("full.json")).unwrap();
+    assert!(full.contains("components"));
+    assert!(!temp.path().join("broken.json").exists());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path()).arg("--target").arg("billing");
+    cmd.assert().failure().code(1).stderr(contains(
+        "unknown target 'billing'; available targets: api, broken, full",
+    ));
+}
+
+#[test]
+fn completions_print_a_script_without_fetching() {
+    let temp = tempdir().unwrap();
+    fs::write(temp.path().join("openapi-snapshot.toml"), "url = ").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path()).arg("completions").arg("bash");
+    cmd.assert()
+        .success()
+        .stdout(contains("watch").and(contains("--outline-out")));
+    assert!(!temp.path().join("openapi").exists());
+}
+
+#[test]
+fn init_scaffolds_config_output_dir_and_gitignore() {
+    let temp = tempdir().unwrap();
+    fs::write(temp.path().join(".gitignore"), "target/\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("init")
+        .arg("--url")
+        .arg("http://localhost:8080/openapi.json");
+    cmd.assert().success().stdout(
+        contains("created openapi-snapshot.toml")
+            .and(contains("created openapi/"))
+            .and(contains("added .*.tmp to .gitignore")),
+    );
+    let config = fs::read_to_string(temp.path().join("openapi-snapshot.toml")).unwrap();
+    assert!(config.contains("url = \"http://localhost:8080/openapi.json\""));
+    assert!(temp.path().join("openapi").is_dir());
+    assert_eq!(
+        fs::read_to_string(temp.path().join(".gitignore")).unwrap(),
+        "target/\n# openapi-snapshot temp files\n.*.tmp\n"
+    );
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path()).arg("init");
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr(contains("already exists; pass --force"));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path()).arg("init").arg("--force");
+    cmd.assert()
+        .success()
+        .stdout(contains("overwrote openapi-snapshot.toml"));
+    let gitignore = fs::read_to_string(temp.path().join(".gitignore")).unwrap();
+    assert_eq!(gitignore.matches(".*.tmp").count(), 1);
+}
+
+#[test]
+fn print_config_dumps_the_effective_config_and_exits() {
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("openapi-snapshot.toml"),
+        "reduce = \"paths\"\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .env("OPENAPI_SNAPSHOT_ALLOW_EMPTY", "true")
+        .arg("--header")
+        .arg("Authorization: Bearer secret")
+        .arg("--print-config");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let printed: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(printed["mode"], "snapshot");
+    let target = &printed["targets"][0];
+    assert_eq!(target["reduce"], serde_json::json!(["paths"]));
+    assert_eq!(target["allow_empty"], true);
+    assert_eq!(target["headers"], serde_json::json!(["Authorization: ***"]));
+    assert!(!temp.path().join("openapi").exists());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn dry_run_fetches_but_writes_nothing() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi").join("api.json");
+    let hook_marker = temp.path().join("hook-ran");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(&out)
+        .arg("--header")
+        .arg("Authorization: Bearer secret-token")
+        .arg("--dry-run");
+    cmd.assert().success().stdout(
+        contains(format!("snapshot: {}", out.display()))
+            .and(contains("would be created"))
+            .and(contains("Authorization: ***"))
+            .and(contains("secret-token").not()),
+    );
+    assert!(!out.exists());
+    assert!(!temp.path().join("openapi").exists());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(&out)
+        .arg("--dry-run")
+        .arg("watch")
+        .arg("--on-change")
+        .arg(format!("touch {}", hook_marker.display()));
+    cmd.assert()
+        .success()
+        .stdout(contains("dry run: nothing was written"));
+    assert!(!out.exists());
+    assert!(!hook_marker.exists());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(server.url("/missing.json"))
+        .arg("--out")
+        .arg(&out)
+        .arg("--dry-run");
+    cmd.assert().failure().code(9);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn port_shorthand_skips_the_prompt_in_watch_mode() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/api-docs/openapi.json");
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(SPEC_V1);
+    });
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--port")
+        .arg(server.port().to_string())
+        .arg("--out")
+        .arg(&out)
+        .arg("watch")
+        .arg("--no-outline")
+        .arg("--once")
+        .write_stdin("");
+    cmd.assert().success();
+    assert!(out.exists());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--port")
+        .arg("3000")
+        .arg("--url")
+        .arg("http://localhost:3000/api-docs/openapi.json");
+    cmd.assert()
+        .failure()
+        .stderr(contains("--port").and(contains("--url")));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--port").arg("70000");
+    cmd.assert().failure().stderr(contains("70000"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn path_overrides_the_document_path_for_port_urls() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--port")
+        .arg(server.port().to_string())
+        .arg("--path")
+        .arg("openapi.json")
+        .arg("--out")
+        .arg(&out);
+    cmd.assert().success();
+    assert!(out.exists());
+}
+
+#[test]
+fn malformed_url_is_a_usage_error_before_watch_starts() {
+    let temp = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg("localhost:3000")
+        .arg("watch")
+        .timeout(std::time::Duration::from_secs(10));
+    cmd.assert().failure().code(1).stderr(contains(
+        "invalid --url 'localhost:3000': did you mean http://localhost:3000/api-docs/openapi.json?",
+    ));
+    assert!(!temp.path().join("openapi").exists());
+}
+
+#[cfg(feature = "http")]
+fn snapshot_cmd(server: &MockServer, args: &[&str]) -> assert_cmd::Command {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url").arg(server.url("/openapi.json")).args(args);
+    cmd
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn contradictory_flag_combinations_are_rejected() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let outline = temp.path().join("outline.json");
+    let outline = outline.to_str().unwrap();
+    let out = temp.path().join("openapi.json");
+    let out = out.to_str().unwrap();
+
+    let cases: [(&[&str], &str); 2] = [
+        (
+            &["--stdout", "--outline-out", outline],
+            "--outline-out cannot be combined with --stdout",
+        ),
+        (
+            &[
+                "--out",
+                out,
+                "--profile",
+                "outline",
+                "--outline-out",
+                outline,
+            ],
+            "--outline-out is not supported with --profile outline",
+        ),
+    ];
+    for (args, message) in cases {
+        snapshot_cmd(&server, args)
+            .assert()
+            .failure()
+            .code(1)
+            .stderr(contains(message));
+    }
+    assert!(!temp.path().join("outline.json").exists());
+    assert!(!temp.path().join("openapi.json").exists());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn deliberately_allowed_flag_combinations_succeed() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    let out = out.to_str().unwrap();
+    let outline = temp.path().join("outline.json");
+    let outline = outline.to_str().unwrap();
+
+    snapshot_cmd(&server, &["--stdout", "--out", out])
+        .assert()
+        .success()
+        .stdout(contains("/users"))
+        .stderr(contains("--out is ignored because --stdout is set."));
+    assert!(!temp.path().join("openapi.json").exists());
+
+    snapshot_cmd(
+        &server,
+        &["--stdout", "--minify", "true", "--profile", "outline"],
+    )
+    .assert()
+    .success()
+    .stdout(contains("/users"));
+
+    snapshot_cmd(
+        &server,
+        &["--out", out, "--reduce", "paths", "--outline-out", outline],
+    )
+    .assert()
+    .success();
+    assert!(temp.path().join("openapi.json").exists());
+    assert!(temp.path().join("outline.json").exists());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn verbose_logs_request_details_and_quiet_silences_warnings() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    let out = out.to_str().unwrap();
+
+    snapshot_cmd(&server, &["--out", out])
+        .assert()
+        .success()
+        .stderr(contains("GET ").not().and(contains("wrote").not()));
+
+    snapshot_cmd(&server, &["--out", out, "-v"])
+        .assert()
+        .success()
+        .stderr(
+            contains(format!("GET {} (attempt 1)", server.url("/openapi.json")))
+                .and(contains("HTTP 200 OK in"))
+                .and(contains(format!("wrote {out} (")))
+                .and(contains("> ").not()),
+        );
+
+    snapshot_cmd(
+        &server,
+        &[
+            "--out",
+            out,
+            "-vv",
+            "--header",
+            "Authorization: Bearer secret-token",
+        ],
+    )
+    .assert()
+    .success()
+    .stderr(
+        contains("> authorization: ***")
+            .and(contains("< content-type: application/json"))
+            .and(contains("secret-token").not()),
+    );
+
+    snapshot_cmd(&server, &["--stdout", "--out", out, "--quiet"])
+        .assert()
+        .success()
+        .stderr(predicates::str::is_empty());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn quiet_watch_prints_only_errors() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(&out)
+        .arg("-q")
+        .arg("watch")
+        .arg("--once");
+    cmd.assert().success().stderr(predicates::str::is_empty());
+    assert!(out.exists());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--url")
+        .arg(server.url("/missing.json"))
+        .arg("--out")
+        .arg(&out)
+        .arg("-q")
+        .arg("watch")
+        .arg("--once");
+    cmd.assert().stderr(contains("HTTP 404"));
+}
+
+#[test]
+fn diff_matches_golden_output_for_fixture_pairs() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/diff");
+    for (case, code) in [("changed", 1), ("reordered", 0)] {
+        let dir = fixtures.join(case);
+        let expected = fs::read_to_string(dir.join("expected.txt")).unwrap();
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.arg("diff")
+            .arg(dir.join("old.json"))
+            .arg(dir.join("new.json"));
+        cmd.assert().code(code).stdout(expected);
+
+        let expected = fs::read_to_string(dir.join("expected.operations.txt")).unwrap();
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.arg("diff")
+            .arg(dir.join("old.json"))
+            .arg(dir.join("new.json"))
+            .args(["--format", "operations"]);
+        cmd.assert().code(code).stdout(expected);
+    }
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn diff_fetches_urls_with_headers_and_reports_read_errors() {
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.method(GET)
+            .path("/openapi.json")
+            .header("authorization", "Bearer t");
+        then.status(200).body(SPEC_V2);
+    });
+    let temp = tempdir().unwrap();
+    let old = temp.path().join("old.json");
+    fs::write(&old, SPEC_V1).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--header")
+        .arg("Authorization: Bearer t")
+        .arg("diff")
+        .arg(&old)
+        .arg(server.url("/openapi.json"));
+    cmd.assert()
+        .code(1)
+        .stdout("added: path /teams\n1 change: 1 added, 0 removed, 0 modified\n");
+    mock.assert();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("diff")
+        .arg(temp.path().join("missing.json"))
+        .arg(&old);
+    cmd.assert().code(4).stderr(contains("failed to read"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn diff_and_check_keep_exit_1_for_differences() {
+    let temp = tempdir().unwrap();
+    let spec = temp.path().join("spec.json");
+    fs::write(&spec, SPEC_V1).unwrap();
+    let broken = temp.path().join("broken.json");
+    fs::write(&broken, "{\"paths\":").unwrap();
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/down.json");
+        then.status(503).body("unavailable");
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/gone.json");
+        then.status(404);
+    });
+    let diff = |new: &str| {
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.arg("diff").arg(&spec).arg(new);
+        cmd.assert()
+    };
+
+    diff(spec.to_str().unwrap()).code(0);
+    diff(broken.to_str().unwrap()).code(2);
+    diff(temp.path().join("missing.json").to_str().unwrap()).code(4);
+    diff(&server.url("/gone.json")).code(9);
+    diff(&server.url("/down.json"))
+        .code(11)
+        .stderr(contains("503"));
+
+    let out = temp.path().join("openapi.json");
+    let check = |url: &str| {
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.arg("--url")
+            .arg(url)
+            .arg("--out")
+            .arg(&out)
+            .arg("check");
+        cmd.assert()
+    };
+    check(&server.url("/down.json")).code(11);
+    check(&server.url("/gone.json")).code(9);
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.args(["diff", "--help"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("  1  the documents differ"))
+        .stdout(contains("  11  usage, network, or server error"));
+}
+
+#[test]
+fn diff_and_check_usage_errors_exit_11() {
+    let temp = tempdir().unwrap();
+    let run = |args: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path()).args(args);
+        cmd.assert()
+    };
+
+    run(&["diff", "--bogus", "a.json", "b.json"])
+        .code(11)
+        .stderr(contains("--bogus"));
+    run(&["diff", "a.json"]).code(11).stderr(contains("<NEW>"));
+    run(&["check", "--bogus"]).code(11);
+    // Other subcommands keep clap's exit code.
+    run(&["validate", "--bogus", "a.json"]).code(2);
+
+    fs::write(temp.path().join("openapi-snapshot.toml"), "url = ").unwrap();
+    run(&["check"])
+        .code(11)
+        .stderr(contains("invalid config file"));
+}
+
+#[test]
+fn validate_reports_findings_and_gates_on_errors_and_warnings() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/validate");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate").arg(fixtures.join("valid.json"));
+    cmd.assert().success().stdout("no problems found\n");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate").arg(fixtures.join("bad_path_key.json"));
+    cmd.assert()
+        .code(6)
+        .stdout("error #/paths/users path must start with '/'\n")
+        .stderr(contains("validation failed: 1 error, 0 warnings."));
+
+    let missing_responses = fixtures.join("missing_responses.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate").arg(&missing_responses);
+    cmd.assert().success().stdout(contains(
+        "warning #/paths/~1users/get operation has no responses",
+    ));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate")
+        .arg(&missing_responses)
+        .arg("--max-warnings")
+        .arg("0");
+    cmd.assert().code(6).stderr(contains("(at most 0 allowed)"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn validate_flag_fails_snapshots_with_structural_errors() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+
+    snapshot_cmd(&server, &["--out", out.to_str().unwrap(), "--validate"])
+        .assert()
+        .code(6)
+        .stderr(contains("error # missing required key 'info'"));
+    assert!(!out.exists());
+
+    let mut cmd = snapshot_cmd(&server, &[]);
+    cmd.arg("validate");
+    cmd.assert()
+        .code(6)
+        .stdout(contains("missing required key 'info'"));
+}
+
+#[test]
+fn outline_subcommand_matches_golden_output_from_file_and_stdin() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/outline");
+    let input = fixtures.join("users.json");
+    let expected = fs::read_to_string(fixtures.join("users.outline.json")).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("outline").arg(&input);
+    cmd.assert().success().stdout(expected.clone());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("outline")
+        .arg("-")
+        .write_stdin(fs::read(&input).unwrap());
+    cmd.assert().success().stdout(expected.clone());
+
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("users.outline.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--out").arg(&out).arg("outline").arg(&input);
+    cmd.assert().success().stdout("");
+    assert_eq!(fs::read_to_string(&out).unwrap(), expected.trim_end());
+}
+
+#[test]
+fn outline_subcommand_maps_input_errors_to_exit_codes() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("outline").arg("-").write_stdin("not json");
+    cmd.assert().code(2).stderr(contains("invalid JSON"));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("outline").arg("-").write_stdin(r#"{"paths": 3}"#);
+    cmd.assert().code(3);
+
+    let temp = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("outline").arg(temp.path().join("missing.json"));
+    cmd.assert().code(4).stderr(contains("failed to read"));
+}
+
+#[test]
+fn reduce_subcommand_filters_local_files_and_stdin() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let input = fixtures.join("outline/users.json");
+    let expected = fs::read_to_string(fixtures.join("reduce/users.paths.json")).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--reduce").arg("paths").arg("reduce").arg(&input);
+    cmd.assert().success().stdout(expected.clone());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--reduce")
+        .arg("paths")
+        .arg("reduce")
+        .arg("-")
+        .write_stdin(fs::read(&input).unwrap());
+    cmd.assert().success().stdout(expected);
+
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("reduced.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--out")
+        .arg(&out)
+        .arg("--minify")
+        .arg("true")
+        .arg("reduce")
+        .arg(&input);
+    cmd.assert().success().stdout("");
+    let reduced: Value = serde_json::from_str(&fs::read_to_string(&out).unwrap()).unwrap();
+    let keys: Vec<&String> = reduced.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["components", "paths"]);
+}
+
+#[test]
+fn reduce_subcommand_maps_errors_to_exit_codes() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("reduce").arg("-").write_stdin(r#"{"paths": {}}"#);
+    cmd.assert().code(3).stderr(contains("components"));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--reduce")
+        .arg("info")
+        .arg("reduce")
+        .arg("-")
+        .write_stdin("{}");
+    cmd.assert().code(3);
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("reduce").arg("-").write_stdin("{");
+    cmd.assert().code(2);
+
+    let temp = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("reduce").arg(temp.path().join("missing.json"));
+    cmd.assert().code(4);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn check_reports_drift_with_a_distinct_exit_code_and_update_refreshes() {
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    let out_arg = out.to_str().unwrap();
+    let v1 = mock_server_with_body(SPEC_V1);
+    let v2 = mock_server_with_body(SPEC_V2);
+
+    snapshot_cmd(&v1, &["--out", out_arg, "check"])
+        .assert()
+        .code(1)
+        .stdout(contains(format!("{out_arg}: missing")));
+
+    snapshot_cmd(&v1, &["--out", out_arg]).assert().success();
+    snapshot_cmd(&v1, &["--out", out_arg, "check"])
+        .assert()
+        .success()
+        .stdout(format!("{out_arg} is up to date\n"));
+
+    snapshot_cmd(&v2, &["--out", out_arg, "check"])
+        .assert()
+        .code(1)
+        .stdout(contains("  added: path /teams"))
+        .stderr(contains("is out of date"));
+    assert!(!fs::read_to_string(&out).unwrap().contains("/teams"));
+
+    snapshot_cmd(&v2, &["--out", out_arg, "check", "--update"])
+        .assert()
+        .success()
+        .stdout(contains(format!("updated {out_arg}")));
+    assert!(fs::read_to_string(&out).unwrap().contains("/teams"));
+
+    snapshot_cmd(&v2, &["--stdout", "check"]).assert().code(11);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn check_with_an_unreachable_target_exits_with_the_compare_failure() {
+    let temp = tempdir().unwrap();
+    let stale = temp.path().join("api.json");
+    fs::write(&stale, SPEC_V1).unwrap();
+    let api = mock_server_with_body(SPEC_V2);
+
+    // A stale snapshot alone exits 1; the unreachable target's failure is
+    // the worse of the two, as `check` maps it.
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(api.url("/openapi.json"))
+        .arg("--out")
+        .arg(&stale)
+        .arg("--url")
+        .arg("http://127.0.0.1:1/openapi.json")
+        .arg("--out")
+        .arg(temp.path().join("down.json"))
+        .arg("check");
+    cmd.assert()
+        .code(11)
+        .stderr(contains("is out of date").and(contains("2 of 2 targets failed")));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn stats_prints_a_table_or_json_after_reduce() {
+    let input =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/stats/billing.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("stats").arg(&input);
+    cmd.assert()
+        .success()
+        .stdout(contains("operations            6\n").and(contains("  billing             4\n")));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--reduce")
+        .arg("paths")
+        .arg("stats")
+        .arg("--json")
+        .arg(&input);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stats: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(stats["paths"], 3);
+    assert_eq!(stats["schemas"], 0);
+    assert_eq!(stats["without_success"], 2);
+    assert_eq!(stats["methods"]["get"], 3);
+
+    let server = mock_server_with_body(SPEC_V2);
+    let mut cmd = snapshot_cmd(&server, &["stats", "--json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stats: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(stats["paths"], 2);
+    assert_eq!(stats["operations"], 0);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn paths_lists_operations_from_files_stdin_and_urls() {
+    let input =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/stats/billing.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("paths").arg(&input);
+    cmd.assert().success().stdout(
+        "GET /invoices\nPOST /invoices\nDELETE /invoices/{id}\nGET /invoices/{id}\n\
+         GET /users\nPUT /users\n",
+    );
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("paths")
+        .arg("--json")
+        .arg("-")
+        .write_stdin(fs::read(&input).unwrap());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let endpoints: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(endpoints.as_array().unwrap().len(), 6);
+    assert_eq!(endpoints[5]["summary"], "No responses");
+
+    let server = mock_server_with_body(
+        r#"{"paths":{"/users":{"get":{"operationId":"listUsers","summary":"List"}}}}"#,
+    );
+    snapshot_cmd(&server, &["paths", "--long"])
+        .assert()
+        .success()
+        .stdout("GET /users  listUsers  List\n");
+}
+
+#[test]
+fn paths_with_no_operations_exits_zero_with_a_note() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("paths").arg("-").write_stdin(r#"{"paths":{}}"#);
+    cmd.assert()
+        .success()
+        .stdout("")
+        .stderr(contains("no operations found"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn show_prints_one_operation_or_schema_and_suggests_on_miss() {
+    let input =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/validate/valid.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("show").arg("schema").arg("User").arg(&input);
+    cmd.assert()
+        .success()
+        .stdout("{\n  \"type\": \"object\"\n}\n");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("show")
+        .arg("GET")
+        .arg("/users/{id}")
+        .arg("--resolve")
+        .arg("-")
+        .write_stdin(fs::read(&input).unwrap());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let op: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(
+        op["responses"]["200"]["content"]["application/json"]["schema"],
+        serde_json::json!({"type": "object"})
+    );
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("show").arg("post").arg("/users/{id}").arg(&input);
+    cmd.assert()
+        .code(1)
+        .stdout("")
+        .stderr("no operation POST /users/{id}; /users/{id} has GET.\n");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("show").arg("schema").arg("Usr").arg(&input);
+    cmd.assert().code(1).stderr(contains("did you mean User?"));
+
+    let server = mock_server_with_body(SPEC_V2);
+    snapshot_cmd(&server, &["--minify", "true", "show", "get", "/teams"])
+        .assert()
+        .code(1)
+        .stderr(contains("no operation GET /teams."));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn merge_combines_files_and_urls_into_one_document() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/merge");
+    let server = mock_server_with_body(&fs::read_to_string(fixtures.join("billing.json")).unwrap());
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("combined.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("merge")
+        .arg(fixtures.join("users.json"))
+        .arg(server.url("/openapi.json"));
+    cmd.assert()
+        .code(1)
+        .stderr(contains("pass --rename-collisions"));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--out")
+        .arg(&out)
+        .arg("merge")
+        .arg(fixtures.join("users.json"))
+        .arg(server.url("/openapi.json"))
+        .arg("--rename-collisions");
+    cmd.assert().success().stdout("");
+    let merged: Value = serde_json::from_str(&fs::read_to_string(&out).unwrap()).unwrap();
+    assert!(merged["components"]["schemas"].get("User127001").is_some());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate").arg(&out);
+    cmd.assert().success();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("merge")
+        .arg(fixtures.join("users.json"))
+        .arg(fixtures.join("users_v2.json"))
+        .arg("--prefer-last");
+    cmd.assert().success().stdout(contains("List users (v2)"));
+}
+
+#[test]
+fn convert_round_trips_json_through_yaml() {
+    let input =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/stats/billing.json");
+    let original: Value = serde_json::from_str(&fs::read_to_string(&input).unwrap()).unwrap();
+    let temp = tempdir().unwrap();
+    let yaml = temp.path().join("billing.yaml");
+    let json = temp.path().join("billing.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--out").arg(&yaml).arg("convert").arg(&input);
+    cmd.assert().success();
+    assert!(
+        fs::read_to_string(&yaml)
+            .unwrap()
+            .contains("openapi: 3.0.3")
+    );
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--out").arg(&json).arg("convert").arg(&yaml);
+    cmd.assert().success();
+    let round_trip: Value = serde_json::from_str(&fs::read_to_string(&json).unwrap()).unwrap();
+    assert_eq!(round_trip, original);
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("convert")
+        .arg("-")
+        .arg("--from")
+        .arg("yaml")
+        .arg("--to")
+        .arg("json")
+        .write_stdin(fs::read(&yaml).unwrap());
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert_eq!(serde_json::from_slice::<Value>(&output).unwrap(), original);
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--input").arg(&yaml).arg("paths");
+    cmd.assert().success().stdout(contains("PUT /users"));
+}
+
+#[test]
+fn convert_refuses_to_guess_the_output_format() {
+    let input =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/stats/billing.json");
+    let temp = tempdir().unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("convert").arg(&input);
+    cmd.assert()
+        .code(1)
+        .stderr(contains("pass --to json or --to yaml"));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--out")
+        .arg(temp.path().join("billing.txt"))
+        .arg("convert")
+        .arg(&input);
+    cmd.assert()
+        .code(1)
+        .stderr(contains("cannot tell the output format from"));
+}
+
+#[test]
+fn diff_colors_changes_only_when_asked_or_on_a_terminal() {
+    let temp = tempdir().unwrap();
+    let old = temp.path().join("old.json");
+    let new = temp.path().join("new.json");
+    fs::write(&old, SPEC_V2).unwrap();
+    fs::write(&new, SPEC_V1).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.env_remove("NO_COLOR").arg("diff").arg(&old).arg(&new);
+    cmd.assert().code(1).stdout(
+        contains("\x1b[")
+            .not()
+            .and(contains("removed: path /teams")),
+    );
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.env("NO_COLOR", "1")
+        .arg("--color")
+        .arg("always")
+        .arg("diff")
+        .arg(&old)
+        .arg(&new);
+    cmd.assert()
+        .code(1)
+        .stdout(contains("\x1b[31mremoved: path /teams\x1b[0m\n"));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.env("OPENAPI_SNAPSHOT_COLOR", "never")
+        .arg("diff")
+        .arg(&new)
+        .arg(&old);
+    cmd.assert()
+        .code(1)
+        .stdout("added: path /teams\n1 change: 1 added, 0 removed, 0 modified\n");
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn http_failures_exit_with_status_specific_codes() {
+    for (status, code) in [(401, 8), (403, 8), (404, 9), (400, 1), (500, 1)] {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/openapi.json");
+            then.status(status).body("nope");
+        });
+        let temp = tempdir().unwrap();
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.arg("--url")
+            .arg(server.url("/openapi.json"))
+            .arg("--out")
+            .arg(temp.path().join("openapi.json"));
+        cmd.assert()
+            .failure()
+            .code(code)
+            .stderr(contains(format!("HTTP {status}")));
+    }
+}
+
+#[test]
+fn help_documents_exit_codes() {
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--help");
+    cmd.assert().success().stdout(
+        contains("Exit codes:")
+            .and(contains("8  authentication failed (HTTP 401/403)"))
+            .and(contains("9  not found (HTTP 404)")),
+    );
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn curl_prints_a_command_for_an_operation() {
+    let fixture =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/curl/openapi.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("curl").arg("GET").arg("/invoices").arg(&fixture);
+    cmd.assert().success().stdout(
+        "curl 'https://eu.api.example.com/v1/invoices?status=open&limit=20' -H 'X-Tenant: <X-Tenant>'\n",
+    );
+
+    let server = mock_server_with_body(
+        r#"{"openapi":"3.0.3","servers":[{"url":"/api"}],"paths":{"/users":{"get":{}}}}"#,
+    );
+    snapshot_cmd(&server, &["curl", "GET", "/users"])
+        .assert()
+        .success()
+        .stdout(format!("curl '{}'\n", server.url("/api/users")));
+    snapshot_cmd(
+        &server,
+        &[
+            "curl",
+            "GET",
+            "/users",
+            "--base-url",
+            "https://api.example.com",
+        ],
+    )
+    .assert()
+    .success()
+    .stdout("curl 'https://api.example.com/users'\n");
+    snapshot_cmd(&server, &["curl", "GET", "/teams"])
+        .assert()
+        .code(1)
+        .stderr(contains("no operation GET /teams"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn example_prints_a_sample_for_a_schema() {
+    let server = mock_server_with_body(
+        r#"{"openapi":"3.0.3","paths":{},"components":{"schemas":{"UserCreateRequest":{"type":"object","properties":{"email":{"type":"string","format":"email"},"role":{"type":"string","enum":["admin","member"]},"age":{"type":"integer","minimum":18}}}}}}"#,
+    );
+    snapshot_cmd(
+        &server,
+        &["--minify", "true", "example", "UserCreateRequest"],
+    )
+    .assert()
+    .success()
+    .stdout("{\"age\":18,\"email\":\"user@example.com\",\"role\":\"admin\"}\n");
+    snapshot_cmd(
+        &server,
+        &["--minify", "true", "example", "UserCreateRequest", "--count", "2"],
+    )
+    .assert()
+    .success()
+    .stdout(
+        "[{\"age\":18,\"email\":\"user@example.com\",\"role\":\"admin\"},{\"age\":19,\"email\":\"user2@example.com\",\"role\":\"member\"}]\n",
+    );
+    snapshot_cmd(&server, &["example", "UserCreate"])
+        .assert()
+        .code(1)
+        .stderr(contains(
+            "no schema 'UserCreate'; did you mean UserCreateRequest?",
+        ));
+    snapshot_cmd(&server, &["example", "UserCreateRequest", "--count", "0"])
+        .assert()
+        .code(2);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn schema_prints_a_resolved_schema_and_warns_about_cycles() {
+    let server = mock_server_with_body(
+        r##"{"openapi":"3.0.3","paths":{},"components":{"schemas":{
+            "Person":{"type":"object","properties":{"employer":{"$ref":"#/components/schemas/Company"}}},
+            "Company":{"type":"object","properties":{"owner":{"$ref":"#/components/schemas/Person"},"name":{"type":"string"}}}
+        }}}"##,
+    );
+    snapshot_cmd(&server, &["--minify", "true", "schema", "Company"])
+        .assert()
+        .success()
+        .stdout(
+            "{\"properties\":{\"name\":{\"type\":\"string\"},\"owner\":{\"properties\":{\"employer\":{\"$ref\":\"#/components/schemas/Company\"}},\"type\":\"object\"}},\"type\":\"object\"}\n",
+        )
+        .stderr("warning: cyclic reference left as $ref: Company -> Person -> Company\n");
+    snapshot_cmd(
+        &server,
+        &[
+            "--minify",
+            "true",
+            "schema",
+            "Person",
+            "--json-schema-draft",
+        ],
+    )
+    .assert()
+    .success()
+    .stdout(contains(
+        "\"$schema\":\"https://json-schema.org/draft/2020-12/schema\"",
+    ))
+    .stdout(contains("\"owner\":{\"$ref\":\"#\"}"));
+}
+
+#[test]
+fn prune_removes_unused_components_from_a_local_file() {
+    let fixture =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/prune/openapi.json");
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("pruned.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--dry-run").arg("prune").arg(&fixture);
+    cmd.assert().success().stdout(
+        "removed 5 unused components:\n  examples: Old\n  parameters: Legacy\n  schemas: Orphan, OrphanChild\n  securitySchemes: basic\n",
+    );
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--out").arg(&out).arg("prune").arg(&fixture);
+    cmd.assert()
+        .success()
+        .stderr(contains("removed 5 unused components:"));
+    let pruned: Value = serde_json::from_str(&fs::read_to_string(&out).unwrap()).unwrap();
+    assert!(pruned["components"]["schemas"].get("Orphan").is_none());
+    assert!(pruned["components"]["schemas"].get("Team").is_some());
+    assert!(pruned["components"].get("examples").is_none());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--dry-run").arg("prune").arg(&out);
+    cmd.assert().success().stdout("no unused components\n");
+}
+
+#[test]
+fn normalize_rewrites_a_file_and_check_enforces_it() {
+    let temp = tempdir().unwrap();
+    let path = temp.path().join("openapi.json");
+    fs::write(
+        &path,
+        r#"{"paths":{"/users":{"post":{},"get":{}}},"openapi":"3.0.3"}"#,
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("normalize").arg(&path).arg("--check");
+    cmd.assert().code(1).stderr(contains(
+        "is not normalized; run `openapi-snapshot normalize",
+    ));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("normalize").arg(&path);
+    cmd.assert().success().stdout(contains("normalized "));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "{\n  \"openapi\": \"3.0.3\",\n  \"paths\": {\n    \"/users\": {\n      \"get\": {},\n      \"post\": {}\n    }\n  }\n}\n"
+    );
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("normalize").arg(&path).arg("--check");
+    cmd.assert()
+        .success()
+        .stdout(contains("is already normalized"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn search_prints_hits_with_locations() {
+    let fixture =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/search/openapi.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("search").arg("Invoice").arg(&fixture);
+    cmd.assert()
+        .success()
+        .stdout(include_str!("fixtures/search/invoice.txt"));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.args([
+        "search",
+        "^customers?$",
+        "--regex",
+        "--in",
+        "paths",
+        "--json",
+    ])
+    .arg(&fixture);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let hits: Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(
+        hits,
+        serde_json::json!([
+            {"location": "paths./customers.get.tags[0]", "kind": "tag", "value": "customers"},
+            {"location": "tags[1].name", "kind": "tag", "value": "customers"}
+        ])
+    );
+
+    let server = mock_server_with_body(SPEC_V1);
+    snapshot_cmd(&server, &["search", "nothing-like-this"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(contains("no matches"));
+    snapshot_cmd(&server, &["search", "(", "--regex"])
+        .assert()
+        .code(1)
+        .stderr(contains("invalid --regex pattern"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn common_flags_work_before_and_after_the_subcommand() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let url = server.url("/openapi.json");
+    let out = temp.path().join("api.json");
+    let out = out.to_str().unwrap();
+    let placements: [(&[&str], &[&str]); 3] = [
+        (&["--url", &url, "--out", out, "--dry-run"], &[]),
+        (&[], &["--url", &url, "--out", out, "--dry-run"]),
+        (&["--url", &url], &["--out", out, "--dry-run"]),
+    ];
+    for (before, after) in placements {
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path())
+            .args(before)
+            .arg("watch")
+            .args(after)
+            .arg("--no-outline");
+        cmd.assert().success().stdout(
+            contains(format!("snapshot: {out}")).and(contains("dry run: nothing was written")),
+        );
+    }
+    assert!(!temp.path().join("api.json").exists());
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn common_flags_after_the_subcommand_win() {
+    let server = mock_server_with_body(SPEC_V1);
+    let temp = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .args([
+            "--url",
+            "http://127.0.0.1:9/unused.json",
+            "--out",
+            "before.json",
+        ])
+        .arg("watch")
+        .args(["--url", &server.url("/openapi.json"), "--out", "after.json"])
+        .args(["--dry-run", "--no-outline"]);
+    cmd.assert().success().stdout(
+        contains("snapshot: after.json")
+            .and(contains("before.json").not())
+            .and(contains("unused.json").not()),
+    );
+
+    let fixture =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/search/openapi.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("paths").arg("--input").arg(&fixture);
+    cmd.assert().success().stdout(contains("GET /customers"));
+}
+
+#[test]
+fn man_prints_a_manual_page_without_any_configuration() {
+    let temp = tempdir().unwrap();
+    fs::write(temp.path().join("openapi-snapshot.toml"), "not = [valid").unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path()).arg("man");
+    cmd.assert().success().stdout(
+        contains(".TH openapi-snapshot 1")
+            .and(contains(".SH \"EXIT CODES\""))
+            .and(contains("breaking API changes found")),
+    );
+
+    let page = temp.path().join("man").join("openapi-snapshot.1");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("man")
+        .arg("--out")
+        .arg(&page);
+    cmd.assert().success().stdout("");
+    assert!(fs::read_to_string(&page).unwrap().contains(".SH EXAMPLES"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn fallback_to_last_keeps_the_existing_snapshot_when_the_server_is_down() {
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    fs::write(&out, "{\"committed\": true}\n").unwrap();
+    let down = "http://127.0.0.1:9/openapi.json";
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.args([
+        "--url",
+        down,
+        "--out",
+        out.to_str().unwrap(),
+        "--fallback-to-last",
+    ]);
+    cmd.assert()
+        .success()
+        .stderr(contains("warning: ").and(contains("keeping the existing snapshot")));
+    assert_eq!(fs::read_to_string(&out).unwrap(), "{\"committed\": true}\n");
+
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/openapi.json");
+        then.status(503).body("deploying");
+    });
+    snapshot_cmd(
+        &server,
+        &[
+            "--out",
+            out.to_str().unwrap(),
+            "--fallback-to-last",
+            "--fallback-exit-code",
+            "75",
+        ],
+    )
+    .assert()
+    .code(75)
+    .stderr(contains("HTTP 503"));
+    assert_eq!(fs::read_to_string(&out).unwrap(), "{\"committed\": true}\n");
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn fallback_to_last_still_fails_without_a_snapshot_or_on_a_bad_document() {
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.args([
+        "--url",
+        "http://127.0.0.1:9/openapi.json",
+        "--out",
+        out.to_str().unwrap(),
+        "--fallback-to-last",
+    ]);
+    cmd.assert()
+        .code(1)
+        .stderr(contains("keeping the existing snapshot").not());
+    assert!(!out.exists());
+
+    fs::write(&out, "{}\n").unwrap();
+    let server = mock_server_with_body("not json");
+    snapshot_cmd(
+        &server,
+        &["--out", out.to_str().unwrap(), "--fallback-to-last"],
+    )
+    .assert()
+    .code(2);
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.args(["--fallback-exit-code", "3"]);
+    cmd.assert().code(2).stderr(contains("--fallback-to-last"));
+}
+
+#[test]
+fn login_needs_a_url_and_a_token() {
+    let temp = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("login")
+        .write_stdin("token\n");
+    cmd.assert()
+        .code(1)
+        .stderr(contains("pass the API with --url <URL>."));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .args(["login", "--url", "https://api.example.com"])
+        .write_stdin("\n");
+    cmd.assert().code(1).stderr(contains("the token is empty."));
+}
+
+#[test]
+fn clean_removes_only_stale_temp_files_and_expired_history() {
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("api.json");
+    let history = temp.path().join("history");
+    fs::create_dir(&history).unwrap();
+    let seeded = [
+        "api.json",
+        ".api.json.1000.tmp",
+        ".api.outline.json.2000.tmp",
+        ".api.json.tmp",
+        ".other.json.1000.tmp",
+        "notes.txt",
+        "history/api.1000.json",
+        "history/api.2000.json",
+        "history/api.3000.json",
+        "history/api.json",
+        "history/other.1000.json",
+    ];
+    for name in seeded {
+        fs::write(temp.path().join(name), "{}").unwrap();
+    }
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("clean")
+        .arg("--out")
+        .arg(&out)
+        .arg("--outline-out")
+        .arg(temp.path().join("api.outline.json"))
+        .arg("--history-dir")
+        .arg(&history)
+        .arg("--keep-last")
+        .arg("2")
+        .arg("--dry-run");
+    cmd.assert().success().stdout(
+        contains("would remove")
+            .and(contains(".api.json.1000.tmp"))
+            .and(contains(".api.outline.json.2000.tmp"))
+            .and(contains("api.1000.json"))
+            .and(contains("api.2000.json").not()),
+    );
+    for name in seeded {
+        assert!(temp.path().join(name).exists(), "{name}");
+    }
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("clean")
+        .arg("--out")
+        .arg(&out)
+        .arg("--outline-out")
+        .arg(temp.path().join("api.outline.json"))
+        .arg("--history-dir")
+        .arg(&history)
+        .arg("--older-than")
+        .arg("1d");
+    cmd.assert().success().stdout(contains("removed "));
+    let removed = [
+        ".api.json.1000.tmp",
+        ".api.outline.json.2000.tmp",
+        "history/api.1000.json",
+        "history/api.2000.json",
+    ];
+    for name in seeded {
+        assert_eq!(
+            temp.path().join(name).exists(),
+            !removed.contains(&name),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn clean_history_needs_a_retention_policy() {
+    let temp = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("clean")
+        .arg("--out")
+        .arg(temp.path().join("api.json"))
+        .arg("--history-dir")
+        .arg(temp.path());
+    cmd.assert()
+        .code(1)
+        .stderr(contains("--history-dir needs --keep-last or --older-than."));
+}
+
+#[test]
+fn slim_profile_keeps_a_valid_spec_without_documentation() {
+    let fixture = "tests/fixtures/slim/openapi.json";
+    let temp = tempdir().unwrap();
+    let full_path = temp.path().join("full.json");
+    let slim_path = temp.path().join("slim.json");
+    let outline_path = temp.path().join("slim.outline.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--input").arg(fixture).arg("--out").arg(&full_path);
+    cmd.assert().success();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--input")
+        .arg(fixture)
+        .arg("--profile")
+        .arg("slim")
+        .arg("--out")
+        .arg(&slim_path)
+        .arg("--outline-out")
+        .arg(&outline_path);
+    cmd.assert().success();
+
+    let full = fs::read_to_string(&full_path).unwrap();
+    let slim = fs::read_to_string(&slim_path).unwrap();
+    assert!(
+        slim.len() * 10 < full.len() * 6,
+        "slim {} bytes, full {} bytes",
+        slim.len(),
+        full.len()
+    );
+    let doc: Value = serde_json::from_str(&slim).unwrap();
+    assert_eq!(doc["openapi"], "3.0.3");
+    assert_eq!(
+        doc["info"],
+        serde_json::json!({"title": "Accounts API", "version": "2.1.0"})
+    );
+    for gone in [
+        "\"x-",
+        "\"summary\"",
+        "\"example\"",
+        "\"examples\"",
+        "LegacyAccount",
+    ] {
+        assert!(!slim.contains(gone), "{gone}");
+    }
+    let schemas = &doc["components"]["schemas"];
+    assert!(schemas["Account"]["properties"]["description"].is_object());
+    assert!(schemas["Error"].is_object());
+    assert_eq!(
+        doc["components"]["responses"]["NotFound"]["description"],
+        ""
+    );
+    assert_eq!(
+        doc["paths"]["/accounts"]["get"]["responses"]["200"]["description"],
+        ""
+    );
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate").arg(&slim_path);
+    cmd.assert().success();
+    let outline: Value = serde_json::from_str(&fs::read_to_string(&outline_path).unwrap()).unwrap();
+    assert!(outline["paths"].is_object());
+}
+
+#[test]
+fn slim_profile_rejects_reduce() {
+    let temp = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--input")
+        .arg("tests/fixtures/slim/openapi.json")
+        .arg("--profile")
+        .arg("slim")
+        .arg("--reduce")
+        .arg("paths")
+        .arg("--out")
+        .arg(temp.path().join("slim.json"));
+    cmd.assert()
+        .code(1)
+        .stderr(contains("--reduce is not supported with --profile slim"));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn empty_paths_never_replace_a_snapshot_with_paths_unless_allowed() {
+    let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{}}"#);
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.json");
+
+    // First run: nothing to protect, so the empty document is written.
+    let mut cmd = snapshot_cmd(&server, &["--out", out_path.to_str().unwrap()]);
+    cmd.assert()
+        .success()
+        .stderr(contains("with an empty `paths` object"));
+    assert!(out_path.exists());
+
+    let existing = r#"{"openapi":"3.0.3","paths":{"/a":{},"/b":{}}}"#;
+    fs::write(&out_path, existing).unwrap();
+    let mut cmd = snapshot_cmd(&server, &["--out", out_path.to_str().unwrap()]);
+    cmd.assert().code(6).stderr(
+        contains("refusing to replace")
+            .and(contains("(2 paths)"))
+            .and(contains("--allow-empty")),
+    );
+    assert_eq!(fs::read_to_string(&out_path).unwrap(), existing);
+
+    let mut cmd = snapshot_cmd(
+        &server,
+        &["--out", out_path.to_str().unwrap(), "--allow-empty"],
+    );
+    cmd.assert().success();
+    let written: Value = serde_json::from_str(&fs::read_to_string(&out_path).unwrap()).unwrap();
+    assert_eq!(written["paths"], serde_json::json!({}));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn watch_logs_an_empty_paths_refusal_once() {
+    let server = mock_server_with_body(r#"{"openapi":"3.0.3","paths":{},"components":{}}"#);
+    let temp = tempdir().unwrap();
+    let out_path = temp.path().join("openapi.json");
+    let existing = r#"{"openapi":"3.0.3","paths":{"/a":{}}}"#;
+    fs::write(&out_path, existing).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--url")
+        .arg(server.url("/openapi.json"))
+        .arg("--out")
+        .arg(&out_path)
+        .arg("watch")
+        .arg("--no-timestamps")
+        .arg("--no-outline")
+        .arg("--max-iterations")
+        .arg("3")
+        .arg("--interval-ms")
+        .arg("10");
+    let output = cmd.assert().code(6).get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert_eq!(
+        stderr.matches("refusing to replace").count(),
+        // Once when first seen, once more as the final error.
+        2,
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("previous error repeated 2 times"),
+        "{stderr}"
+    );
+    assert_eq!(fs::read_to_string(&out_path).unwrap(), existing);
+}
+
+#[cfg(not(feature = "http"))]
+#[test]
+fn urls_need_http_support() {
+    let temp = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("--out")
+        .arg(temp.path().join("openapi.json"))
+        .arg("--outline-out")
+        .arg(temp.path().join("openapi.outline.json"));
+    let output = cmd.assert().code(1).get_output().stderr.clone();
+    let stderr = String::from_utf8(output).unwrap();
+    assert!(stderr.contains("built without HTTP support"), "{stderr}");
+}
+
+#[test]
+fn diff_breaking_lists_findings_and_exits_10() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/breaking");
+    let base = fixtures.join("base.json");
+    let dir = fixtures.join("removed-response");
+    let expected = fs::read_to_string(dir.join("expected.txt")).unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("diff")
+        .arg(&base)
+        .arg(dir.join("new.json"))
+        .arg("--breaking");
+    cmd.assert()
+        .code(10)
+        .stdout(format!("{expected}1 breaking change\n"))
+        .stderr(contains("has 1 breaking change compared with"));
+
+    let dir = fixtures.join("additions");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("diff")
+        .arg(&base)
+        .arg(dir.join("new.json"))
+        .arg("--breaking");
+    cmd.assert().success().stdout("no breaking changes\n");
+}
+
+#[test]
+fn check_breaking_fails_with_10_only_when_clients_would_break() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/breaking");
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    let check = |case: &str| {
+        let dir = fixtures.join(case);
+        fs::copy(fixtures.join("base.json"), &out).unwrap();
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path())
+            .arg("--input")
+            .arg(dir.join("new.json"))
+            .arg("--out")
+            .arg(&out)
+            .args(["check", "--breaking"]);
+        cmd.assert()
+    };
+
+    check("removed-operation")
+        .code(10)
+        .stdout(contains(
+            "removed-operation #/paths/~1users~1{id}/delete DELETE /users/{id} was removed",
+        ))
+        .stderr(contains(
+            "is out of date and the new document has 1 breaking change",
+        ));
+    check("additions")
+        .code(1)
+        .stdout(contains("no breaking changes"))
+        .stderr(contains("is out of date"));
+}
+
+#[test]
+fn diff_ignores_number_formatting_unless_strict_bytes() {
+    let temp = tempdir().unwrap();
+    let old = temp.path().join("old.json");
+    let new = temp.path().join("new.json");
+    fs::write(&old, r#"{"paths":{"/users":{"get":{"x-weight":1.0}}}}"#).unwrap();
+    fs::write(
+        &new,
+        "{\n  \"paths\": {\n    \"/users\": {\"get\": {\"x-weight\": 1}}\n  }\n}\n",
+    )
+    .unwrap();
+    let diff = |strict: bool| {
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        if strict {
+            cmd.arg("--strict-bytes");
+        }
+        cmd.arg("diff").arg(&old).arg(&new);
+        cmd.assert()
+    };
+    diff(false).success().stdout("no differences\n");
+    diff(true)
+        .code(1)
+        .stdout(contains("modified: GET /users (metadata)"));
+
+    let reordered =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/diff/reordered");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.args(["diff", "--strict-bytes"])
+        .arg(reordered.join("old.json"))
+        .arg(reordered.join("new.json"));
+    cmd.assert()
+        .code(1)
+        .stdout("documents differ only in formatting\n");
+}
+
+#[test]
+fn diff_json_patch_applies_old_to_new() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/diff/changed");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("diff")
+        .arg(dir.join("old.json"))
+        .arg(dir.join("new.json"))
+        .args(["--format", "json-patch"]);
+    let output = cmd.assert().code(1).get_output().stdout.clone();
+    let patch: json_patch::Patch = serde_json::from_slice(&output).unwrap();
+    let read = |file: &str| -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(dir.join(file)).unwrap()).unwrap()
+    };
+    let mut doc = read("old.json");
+    json_patch::patch(&mut doc, &patch).unwrap();
+    assert_eq!(doc, read("new.json"));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("diff")
+        .arg(dir.join("new.json"))
+        .arg(dir.join("new.json"))
+        .args(["--format", "json-patch"]);
+    cmd.assert().success().stdout("[]\n");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("diff")
+        .arg(dir.join("old.json"))
+        .arg(dir.join("new.json"))
+        .args(["--format", "json-patch", "--breaking"]);
+    cmd.assert()
+        .code(11)
+        .stderr(contains("cannot be combined with --format json-patch"));
+}
+
+#[test]
+fn diff_markdown_matches_golden_changelog() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/diff/changed");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("diff")
+        .arg(dir.join("old.json"))
+        .arg(dir.join("new.json"))
+        .args(["--format", "markdown", "--max-items", "1"]);
+    cmd.assert()
+        .code(1)
+        .stdout(fs::read_to_string(dir.join("expected.max1.md")).unwrap());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("diff")
+        .arg(dir.join("new.json"))
+        .arg(dir.join("new.json"))
+        .args(["--format", "markdown"]);
+    cmd.assert().success().stdout("No changes.\n");
+}
+
+/// Each `--fail-on` policy with the exit code it should give.
+type PolicyCodes = [(&'static str, i32); 3];
+
+/// The `tests/fixtures/breaking` case for each `--fail-on` outcome, compared
+/// with `base.json`, with the exit code each policy gives it.
+const FAIL_ON_CASES: [(&str, &str, PolicyCodes); 3] = [
+    (
+        "clean",
+        "additions",
+        [("none", 0), ("breaking", 0), ("any", 0)],
+    ),
+    (
+        "changed",
+        "additions",
+        [("none", 0), ("breaking", 0), ("any", 1)],
+    ),
+    (
+        "breaking",
+        "required-parameter",
+        [("none", 0), ("breaking", 10), ("any", 10)],
+    ),
+];
+
+#[test]
+fn diff_fail_on_maps_each_outcome_to_its_exit_code() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/breaking");
+    for (outcome, case, policies) in FAIL_ON_CASES {
+        let dir = fixtures.join(case);
+        let old = if outcome == "clean" {
+            dir.join("new.json")
+        } else {
+            fixtures.join("base.json")
+        };
+        for (policy, code) in policies {
+            let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+            cmd.arg("diff")
+                .arg(&old)
+                .arg(dir.join("new.json"))
+                .args(["--fail-on", policy]);
+            cmd.assert().code(code).stdout(contains(format!(
+                "--fail-on {policy}: {outcome}, exit {code}"
+            )));
+        }
+    }
+}
+
+#[test]
+fn check_fail_on_maps_each_outcome_to_its_exit_code() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/breaking");
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    for (outcome, case, policies) in FAIL_ON_CASES {
+        let dir = fixtures.join(case);
+        let old = if outcome == "clean" {
+            dir.join("new.json")
+        } else {
+            fixtures.join("base.json")
+        };
+        for (policy, code) in policies {
+            fs::copy(&old, &out).unwrap();
+            let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+            cmd.current_dir(temp.path())
+                .arg("--input")
+                .arg(dir.join("new.json"))
+                .arg("--out")
+                .arg(&out)
+                .args(["check", "--fail-on", policy]);
+            cmd.assert().code(code).stdout(contains(format!(
+                "--fail-on {policy}: {outcome}, exit {code}"
+            )));
+        }
+    }
+}
+
+#[test]
+fn diff_json_report_states_the_policy_and_outcome() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/breaking");
+    let dir = fixtures.join("required-parameter");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("diff")
+        .arg(fixtures.join("base.json"))
+        .arg(dir.join("new.json"))
+        .args(["--format", "json", "--fail-on", "breaking"]);
+    let output = cmd.assert().code(10).get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["fail_on"], "breaking");
+    assert_eq!(report["outcome"], "breaking");
+    assert_eq!(report["exit_code"], 10);
+    assert_eq!(report["breaking"][0]["rule"], "required-parameter");
+    assert!(!report["changes"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn verify_refs_warns_and_strict_refs_fails_on_broken_refs() {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/validate/unresolved_refs.json");
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    let snapshot = |flag: &str| {
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path())
+            .arg("--input")
+            .arg(&fixture)
+            .arg("--out")
+            .arg(&out)
+            .arg(flag);
+        cmd.assert()
+    };
+
+    snapshot("--verify-refs")
+        .success()
+        .stderr(contains(
+            "warning: #/paths/~1users/get/responses/200/content/application~1json/schema \
+             $ref '#/components/schemas/User' does not resolve",
+        ))
+        .stderr(contains(
+            "not checked: #/paths/~1users/get/responses/404 \
+             $ref 'https://example.com/errors.json#/NotFound'",
+        ));
+    assert!(out.exists());
+
+    fs::remove_file(&out).unwrap();
+    snapshot("--strict-refs")
+        .code(3)
+        .stderr(contains("1 $ref does not resolve:"))
+        .stderr(contains("$ref '#/components/schemas/User'"));
+    assert!(!out.exists());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate").arg(&fixture);
+    cmd.assert().code(6).stdout(contains(
+        "not checked: #/paths/~1users/get/responses/404 \
+         $ref 'https://example.com/errors.json#/NotFound'",
+    ));
+}
+
+#[test]
+fn validate_reports_operation_id_collisions_at_configurable_severity() {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/validate/operation_ids.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate").arg(&fixture);
+    cmd.assert()
+        .code(6)
+        .stdout(contains(
+            "error #/paths/~1teams/get operationId 'listUsers' is used by 2 operations: \
+             GET /teams, GET /users",
+        ))
+        .stdout(contains(
+            "warning #/paths/~1users/post operation has no operationId",
+        ))
+        .stderr(contains("validation failed: 2 errors, 1 warning."));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate")
+        .arg(&fixture)
+        .args(["--severity", "operation-id-duplicate=warning"])
+        .args(["--severity", "operation-id-missing=off"]);
+    cmd.assert()
+        .success()
+        .stdout(contains(
+            "warning #/paths/~1users~1{id}/get operationId 'user'",
+        ))
+        .stdout(contains("no operationId").not());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate")
+        .arg(&fixture)
+        .args(["--severity", "operation-id-typo=off"]);
+    cmd.assert()
+        .code(2)
+        .stderr(contains("unknown check 'operation-id-typo'"));
+}
+
+#[test]
+fn lint_runs_selected_rules_and_fails_on_errors() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/lint");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("lint").arg(fixtures.join("clean.json"));
+    cmd.assert().success().stdout("no lint findings\n");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("lint").arg(fixtures.join("operation-tags.json"));
+    cmd.assert().success().stdout(contains(
+        "warning #/paths/~1users/get operation has no tags (operation-tags)",
+    ));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("lint").arg(fixtures.join("no-trailing-slash.json"));
+    cmd.assert()
+        .code(6)
+        .stdout(contains(
+            "error #/paths/~1users~1 path ends with a trailing slash",
+        ))
+        .stderr(contains("lint failed: 1 error, 0 warnings."));
+
+    let temp = tempdir().unwrap();
+    fs::write(
+        temp.path().join("openapi-snapshot.toml"),
+        "[lint]\ndisable-rule = [\"no-trailing-slash\"]\n",
+    )
+    .unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("lint")
+        .arg(fixtures.join("no-trailing-slash.json"));
+    cmd.assert().success().stdout("no lint findings\n");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("lint")
+        .arg(fixtures.join("clean.json"))
+        .args(["--rule", "tags"]);
+    cmd.assert()
+        .code(2)
+        .stderr(contains("unknown lint rule 'tags'"));
+}
+
+#[test]
+fn require_version_fails_fast_on_a_mismatched_document() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/validate");
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    let snapshot = |fixture: &str, version: &str| {
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.current_dir(temp.path())
+            .arg("--input")
+            .arg(fixtures.join(fixture))
+            .arg("--out")
+            .arg(&out)
+            .args(["--require-version", version]);
+        cmd.assert()
+    };
+
+    snapshot("paths_optional_in_3_1.json", "3.0")
+        .code(6)
+        .stderr(contains("expected an OpenAPI 3.0 document"))
+        .stderr(contains("declares version '3.1.0'."));
+    assert!(!out.exists());
+
+    snapshot("paths_optional_in_3_1.json", "3.1")
+        .success()
+        .stderr(contains(
+            "warning: OpenAPI 3.1 webhooks are not included in outlines",
+        ));
+    assert!(out.exists());
+
+    snapshot("valid.json", "3.0").success();
+    snapshot("valid.json", "3.1")
+        .code(6)
+        .stderr(contains("declares version '3.0.3'."));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.args(["--require-version", "2.0", "--stdout"]);
+    cmd.assert()
+        .code(2)
+        .stderr(contains("possible values: 3.0, 3.1"));
+}
+
+#[test]
+fn colliding_paths_fail_validate_and_warn_with_check_paths() {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/validate/path_collisions.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate").arg(&fixture);
+    cmd.assert()
+        .code(6)
+        .stdout(contains(
+            "error #/paths/~1users~1{id} paths collide, differing only in placeholder names: \
+             /users/{id}, /users/{userId}",
+        ))
+        .stdout(contains(
+            "error #/paths/~1health paths collide, differing only in trailing slash: \
+             /health, /health/",
+        ));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("lint")
+        .arg(&fixture)
+        .args(["--rule", "path-collision"]);
+    cmd.assert().code(6).stdout(contains(
+        "error #/paths/~1Reports paths collide, differing only in case: /Reports, /reports \
+         (path-collision)",
+    ));
+
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--input")
+        .arg(&fixture)
+        .arg("--out")
+        .arg(&out)
+        .arg("--check-paths");
+    cmd.assert().success().stderr(contains(
+        "warning: #/paths/~1health paths collide, differing only in trailing slash: \
+         /health, /health/",
+    ));
+    assert!(out.exists());
+}
+
+#[test]
+fn temp_dir_stages_writes_outside_the_output_directory() {
+    let temp = tempdir().unwrap();
+    let stage = temp.path().join("stage");
+    fs::create_dir_all(&stage).unwrap();
+    let fixture =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/outline/users.json");
+    let out = temp.path().join("out/openapi.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--input")
+        .arg(&fixture)
+        .arg("--out")
+        .arg(&out)
+        .arg("--outline-out")
+        .arg(temp.path().join("out/openapi.outline.json"))
+        .arg("--temp-dir")
+        .arg(&stage);
+    cmd.assert().success();
+    assert!(out.exists());
+    assert_eq!(fs::read_dir(&stage).unwrap().count(), 0);
+    assert_eq!(fs::read_dir(temp.path().join("out")).unwrap().count(), 2);
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--input")
+        .arg(&fixture)
+        .arg("--out")
+        .arg(&out)
+        .arg("--temp-dir")
+        .arg(temp.path().join("missing"));
+    cmd.assert()
+        .code(4)
+        .stderr(contains("failed to create temp file"))
+        .stderr(contains(format!("for {}:", out.display())));
+}
+
+#[test]
+fn duplicate_keys_warn_or_fail_and_the_last_one_is_written() {
+    let temp = tempdir().unwrap();
+    let input = temp.path().join("dup.json");
+    // `json!` cannot express a duplicate key.
+    fs::write(
+        &input,
+        r#"{"openapi": "3.0.3", "info": {"title": "a", "version": "1"}, "paths": {
+            "/users": {"get": {"responses": {}}},
+            "/users": {"post": {"responses": {}}}
+        }}"#,
+    )
+    .unwrap();
+    let out = temp.path().join("openapi.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--input")
+        .arg(&input)
+        .arg("--out")
+        .arg(&out);
+    cmd.assert()
+        .success()
+        .stderr(contains("duplicate key").not());
+    let unchecked = fs::read_to_string(&out).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--input")
+        .arg(&input)
+        .arg("--out")
+        .arg(&out)
+        .arg("--detect-duplicate-keys");
+    cmd.assert().success().stderr(contains(
+        "warning: duplicate key #/paths/~1users appears 2 times; the last one is kept",
+    ));
+    let checked = fs::read_to_string(&out).unwrap();
+    assert_eq!(checked, unchecked);
+    let written: Value = serde_json::from_str(&checked).unwrap();
+    assert!(written["paths"]["/users"].get("post").is_some());
+    assert!(written["paths"]["/users"].get("get").is_none());
+
+    fs::remove_file(&out).unwrap();
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--input")
+        .arg(&input)
+        .arg("--out")
+        .arg(&out)
+        .arg("--strict-duplicate-keys");
+    cmd.assert().code(6).stderr(contains(
+        "1 duplicate key found:\n  #/paths/~1users appears 2 times; the last one is kept",
+    ));
+    assert!(!out.exists());
+}
+
+#[test]
+fn validate_examples_reports_examples_that_do_not_match_their_schemas() {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/validate/examples.json");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate").arg(&fixture);
+    cmd.assert().success();
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate").arg(&fixture).arg("--validate-examples");
+    cmd.assert()
+        .code(6)
+        .stdout(contains(
+            "error #/components/schemas/User/example example does not match its schema: \
+             \"forty-two\" is not of type \"integer\" (at /age)",
+        ))
+        .stdout(contains(
+            "/examples/stale/value example does not match its schema",
+        ))
+        .stdout(contains("examples/current").not());
+
+    let temp = tempdir().unwrap();
+    let out = temp.path().join("openapi.json");
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.current_dir(temp.path())
+        .arg("--input")
+        .arg(&fixture)
+        .arg("--out")
+        .arg(&out)
+        .arg("--validate-examples");
+    cmd.assert()
+        .code(6)
+        .stderr(contains("validation failed: 2 errors, 0 warnings."));
+    assert!(!out.exists());
+}
+
+#[test]
+fn validate_checks_responses_at_configurable_severity() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/validate");
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate").arg(fixtures.join("responses.json"));
+    cmd.assert()
+        .code(6)
+        .stdout(contains(
+            "error #/paths/~1users/get/responses/2OO GET /users response '2OO' is not a \
+             three-digit status code, an NXX range, or 'default'",
+        ))
+        .stdout(contains(
+            "error #/paths/~1users/post/responses POST /users has an empty responses object",
+        ));
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate")
+        .arg(fixtures.join("responses.json"))
+        .args(["--severity", "response-status-key=warning"])
+        .args(["--severity", "responses-empty=off"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("warning #/paths/~1users/get/responses/2OO"))
+        .stdout(contains("empty responses object").not());
+
+    let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+    cmd.arg("validate")
+        .arg(fixtures.join("responses_clean.json"));
+    cmd.assert().success().stdout("no problems found\n");
+}
+
+#[test]
+fn watch_baseline_logs_drift_and_writes_the_report() {
+    let fixtures =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/diff/changed");
+    let temp = tempdir().unwrap();
+    let report = temp.path().join("drift.txt");
+    let watch = |baseline: &std::path::Path| {
+        let mut cmd = cargo_bin_cmd!("openapi-snapshot");
+        cmd.arg("--input")
+            .arg(fixtures.join("new.json"))
+            .arg("--out")
+            .arg(temp.path().join("openapi.json"))
+            .args(["watch", "--once", "--no-outline", "--no-timestamps"])
+            .arg("--baseline")
+            .arg(baseline)
+            .arg("--baseline-report")
+            .arg(&report);
+        cmd.assert()
+    };
+
+    watch(&fixtures.join("old.json")).success().stderr(contains(
+        "vs baseline: 1 path added, 1 removed, 6 operations changed\n",
+    ));
+    let written = fs::read_to_string(&report).unwrap();
+    assert!(written.contains("added: path /teams\n"));
+    assert!(written.ends_with("11 changes: 4 added, 4 removed, 3 modified\n"));
+
+    fs::remove_file(&report).unwrap();
+    watch(&temp.path().join("missing.json"))
+        .code(4)
+        .stderr(contains("failed to load --baseline"))
+        .stderr(contains("snapshot written").not());
+    assert!(!report.exists());
+}
@@ -0,0 +1,106 @@
+//! Peak heap use of a snapshot of a large generated spec. The test counts
+//! allocations with its own global allocator, so it lives in a binary of
+//! its own where no other test allocates at the same time.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use clap::Parser;
+use openapi_snapshot::{Cli, Config, build_outputs};
+
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// A spec of roughly `paths` times 1.5 KB: operations with parameters,
+/// responses, and a schema each, like a large generated gateway spec.
+fn generated_spec(paths: usize) -> String {
+    let mut spec =
+        String::from(r#"{"openapi":"3.0.3","info":{"title":"gateway","version":"1"},"paths":{"#);
+    for index in 0..paths {
+        if index > 0 {
+            spec.push(',');
+        }
+        let _ = write!(
+            spec,
+            r##""/service/{index}/items/{{id}}":{{"get":{{"operationId":"getItem{index}","summary":"Fetch item {index}","parameters":[{{"name":"id","in":"path","required":true,"schema":{{"type":"string"}}}},{{"name":"expand","in":"query","schema":{{"type":"boolean"}}}}],"responses":{{"200":{{"description":"ok","content":{{"application/json":{{"schema":{{"$ref":"#/components/schemas/Item{index}"}}}}}}}},"404":{{"description":"missing"}}}}}}}}"##
+        );
+    }
+    spec.push_str(r#"},"components":{"schemas":{"#);
+    for index in 0..paths {
+        if index > 0 {
+            spec.push(',');
+        }
+        let _ = write!(
+            spec,
+            r#""Item{index}":{{"type":"object","required":["id"],"properties":{{"id":{{"type":"string"}},"name":{{"type":"string","description":"The display name of item {index}"}},"tags":{{"type":"array","items":{{"type":"string"}}}},"price":{{"type":"number","minimum":0}}}}}}"#
+        );
+    }
+    spec.push_str("}}}");
+    spec
+}
+
+/// Peak heap use while building the snapshot of `input`, above what was
+/// allocated before.
+fn peak_while_building(input: &Path, extra: &[&str]) -> usize {
+    let args = [
+        "openapi-snapshot",
+        "--input",
+        input.to_str().unwrap(),
+        "--out",
+        "unused.json",
+    ];
+    let cli = Cli::try_parse_from(args.iter().chain(extra)).unwrap();
+    let (config, _) = Config::from_cli(cli).unwrap();
+    let before = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(before, Ordering::SeqCst);
+    let outputs = build_outputs(&config).unwrap();
+    let peak = PEAK.load(Ordering::SeqCst) - before;
+    assert!(!outputs.primary.is_empty());
+    peak
+}
+
+#[test]
+fn untransformed_snapshots_of_large_specs_use_less_than_half_the_memory() {
+    let temp = tempfile::tempdir().unwrap();
+    let input = temp.path().join("gateway.json");
+    let spec = generated_spec(8_000);
+    let size = spec.len();
+    fs::write(&input, spec).unwrap();
+
+    let passthrough = peak_while_building(&input, &[]);
+    let transformed = peak_while_building(&input, &["--reduce", "paths,components"]);
+    eprintln!(
+        "{size} byte spec: peak {passthrough} bytes as served, {transformed} bytes transformed"
+    );
+    assert!(
+        passthrough < transformed / 2,
+        "{passthrough} vs {transformed}"
+    );
+    assert!(passthrough < size * 12, "{passthrough} for {size}");
+}
@@ -0,0 +1,36 @@
+//! Run with `wasm-bindgen-test-runner` as the wasm32 runner:
+//!
+//! ```text
+//! CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+//!     cargo test --target wasm32-unknown-unknown --no-default-features --features wasm --test wasm
+//! ```
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use openapi_snapshot::wasm::{outline_json, reduce_json};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const USERS: &str = include_str!("fixtures/outline/users.json");
+
+#[wasm_bindgen_test]
+fn outline_json_matches_golden_output() {
+    let expected = include_str!("fixtures/outline/users.outline.json");
+    assert_eq!(outline_json(USERS).unwrap(), expected.trim_end());
+}
+
+#[wasm_bindgen_test]
+fn reduce_json_matches_golden_output() {
+    let expected = include_str!("fixtures/reduce/users.paths.json");
+    assert_eq!(reduce_json(USERS, "paths").unwrap(), expected.trim_end());
+}
+
+#[wasm_bindgen_test]
+fn errors_are_message_strings() {
+    let err = reduce_json(USERS, "servers").unwrap_err();
+    assert_eq!(
+        err.as_string().unwrap(),
+        "unsupported reduce value: servers"
+    );
+    let err = outline_json("not json").unwrap_err();
+    assert!(err.as_string().unwrap().starts_with("invalid JSON:"));
+}